@@ -0,0 +1,128 @@
+// External.
+use aes::cipher::block_padding::UnpadError;
+
+// Error type used by the config and network services.
+#[derive(Debug)]
+pub enum SilentError {
+    IoError(std::io::Error),
+    SerializationError(String),
+    CryptoError(String),
+    ConfigError(String),
+    UnsupportedConfigVersion(String), // the config was created by a newer version of the app
+    InternalError(String),
+}
+
+impl std::fmt::Display for SilentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // messages already describe the error (including its location)
+        match self {
+            SilentError::IoError(e) => write!(f, "IO error: {}", e),
+            SilentError::SerializationError(msg)
+            | SilentError::CryptoError(msg)
+            | SilentError::ConfigError(msg)
+            | SilentError::UnsupportedConfigVersion(msg)
+            | SilentError::InternalError(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SilentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SilentError::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SilentError {
+    fn from(error: std::io::Error) -> Self {
+        SilentError::IoError(error)
+    }
+}
+
+impl From<bincode::Error> for SilentError {
+    fn from(error: bincode::Error) -> Self {
+        SilentError::SerializationError(error.to_string())
+    }
+}
+
+impl From<UnpadError> for SilentError {
+    fn from(error: UnpadError) -> Self {
+        SilentError::CryptoError(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn io_error_display() {
+        let error = SilentError::IoError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ));
+
+        assert_eq!(error.to_string(), "IO error: no such file");
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn message_variants_display_message() {
+        let errors = vec![
+            SilentError::SerializationError(String::from("serialization message")),
+            SilentError::CryptoError(String::from("crypto message")),
+            SilentError::ConfigError(String::from("config message")),
+            SilentError::UnsupportedConfigVersion(String::from("version message")),
+            SilentError::InternalError(String::from("internal message")),
+        ];
+        let expected = [
+            "serialization message",
+            "crypto message",
+            "config message",
+            "version message",
+            "internal message",
+        ];
+
+        for (error, expected) in errors.iter().zip(expected.iter()) {
+            assert_eq!(error.to_string(), *expected);
+            assert!(error.source().is_none());
+        }
+    }
+
+    #[test]
+    fn io_error_is_converted() {
+        let error: SilentError =
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof").into();
+
+        match error {
+            SilentError::IoError(e) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            _ => panic!("unexpected variant: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn bincode_error_is_converted() {
+        let bincode_error = bincode::deserialize::<u64>(&[0u8; 2]).unwrap_err();
+        let message = bincode_error.to_string();
+
+        let error: SilentError = bincode_error.into();
+
+        match &error {
+            SilentError::SerializationError(msg) => assert_eq!(*msg, message),
+            _ => panic!("unexpected variant: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn unpad_error_is_converted() {
+        let error: SilentError = UnpadError.into();
+
+        match &error {
+            SilentError::CryptoError(msg) => assert_eq!(*msg, UnpadError.to_string()),
+            _ => panic!("unexpected variant: {:?}", error),
+        }
+    }
+}
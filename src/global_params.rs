@@ -1,28 +1,80 @@
 pub const CLIENT_CONFIG_FILE_NAME: &str = "silent.config";
+pub const CLIENT_CONFIG_BACKUP_FILE_NAME: &str = "silent_config.bak";
+pub const CONNECTION_LOG_FILE_NAME: &str = "connection_log.json";
+pub const VOICE_RECORDINGS_DIRECTORY_NAME: &str = "recordings"; // in the config directory
+pub const CONNECTION_LOG_MAX_ENTRIES: usize = 30;
 pub const CONFIG_FILE_MAGIC_NUMBER: u16 = 51338;
-pub const CONFIG_FILE_VERSION: u64 = 2;
-pub const NETWORK_PROTOCOL_VERSION: u64 = 2; // server with the same version is considered compatible
+pub const CONFIG_FILE_VERSION: u64 = 35;
+pub const CONFIG_SAVE_DELAY_MS: u64 = 1000; // changed settings are written after this delay
+pub const NETWORK_PROTOCOL_VERSION: u64 = 11; // server with the same version is considered compatible
+
+pub const DEFAULT_WINDOW_WIDTH: u16 = 650;
+pub const DEFAULT_WINDOW_HEIGHT: u16 = 500;
 
 pub const TEXT_SIZE: f64 = 18.0;
 pub const MESSAGE_AUTHOR_TEXT_SIZE: f64 = 16.0;
+pub const USER_DRAG_THRESHOLD: f64 = 8.0; // in pixels
 pub const MESSAGE_TEXT_SIZE: f64 = 15.0;
 
 // these should be in sync with the server global parameters
 pub const MAX_MESSAGE_SIZE: usize = 500;
+pub const MIN_USERNAME_SIZE: usize = 2;
 pub const MAX_USERNAME_SIZE: usize = 25;
 pub const MAX_PASSWORD_SIZE: usize = 20;
-pub const SPAM_PROTECTION_SEC: usize = 3; // (should be 'server value' + 1), can send only 1 message per SPAM_PROTECTION_SEC
+// Spam protection (0 disables it), can send only 1 message per N seconds.
+pub const DEFAULT_MESSAGE_COOLDOWN_SEC: u8 = 1;
+pub const MAX_MESSAGE_COOLDOWN_SEC: u8 = 10;
+pub const DEFAULT_ROOM_CHANGE_SAME_TIER_COOLDOWN_SEC: u8 = 2; // between two rooms other than the lobby
+pub const DEFAULT_ROOM_CHANGE_LOBBY_COOLDOWN_SEC: u8 = 1; // to or from the lobby
+pub const MAX_ROOM_CHANGE_COOLDOWN_SEC: u8 = 30;
+
+pub const DEFAULT_IDLE_TIMEOUT_MIN: u8 = 10; // switch to "away" after N minutes without input (0 disables it)
+pub const DEFAULT_MICROPHONE_CHANNELS: u8 = 1; // 2 for devices that only work with stereo capture
+pub const DEFAULT_UDP_RECV_BUFFER_KB: u32 = 256; // SO_RCVBUF, bigger buffer - less packet loss with many speakers
+pub const DEFAULT_UDP_SEND_BUFFER_KB: u32 = 128; // SO_SNDBUF
+pub const MIN_UDP_SOCKET_BUFFER_KB: u32 = 8;
+pub const DEFAULT_UDP_HEALTH_CHECK_TIMEOUT_SEC: u8 = 60; // warn if nothing was received over UDP for N seconds
+pub const MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC: u8 = 10; // (the server sends ping checks, don't go too low)
+pub const MAX_UDP_HEALTH_CHECK_TIMEOUT_SEC: u8 = 240;
+pub const MAX_UDP_SOCKET_BUFFER_KB: u32 = 8192;
+pub const SEARCH_HIGHLIGHT_DURATION_SEC: u64 = 3; // message found by the search is highlighted for N seconds
+pub const MENTION_NOTICE_DURATION_SEC: u64 = 3; // "Notified @username" is shown for N seconds after sending
+pub const NEW_USER_BADGE_DURATION_SEC: u64 = 30; // "NEW" badge is shown for recently joined users
+pub const ACCESSIBILITY_MIN_BUTTON_HEIGHT: f64 = 32.0; // used instead of the theme value in the accessibility mode
+pub const DEFAULT_AUTO_AWAY_MESSAGE: &str = "Away"; // sent to other users when the status changes to "away"
+pub const MAX_IDLE_TIMEOUT_MIN: u8 = 120;
+pub const IDLE_CHECK_INTERVAL_SEC: u64 = 10;
 pub const PASSWORD_RETRY_DELAY_SEC: usize = 6; // (should be 'server value' + 1)
 pub const DEFAULT_ROOM_NAME: &str = "Lobby";
 pub const IV_LENGTH: usize = 16;
 pub const CMAC_TAG_LENGTH: usize = 16;
 
-pub const MAX_MESSAGES_ON_SCREEN: usize = 50;
+pub const DEFAULT_CHAT_MAX_MESSAGES: usize = 500; // older messages are removed from the chat
+pub const MIN_CHAT_MAX_MESSAGES: usize = 100;
+pub const MAX_CHAT_MAX_MESSAGES: usize = 5000;
+pub const CHAT_MAX_MESSAGES_STEP: usize = 100;
+pub const MAX_PINNED_MESSAGES: usize = 3;
+pub const PIN_ICON: &str = "📌";
+pub const ROOM_INVITE_EXPIRY_SEC: u64 = 30; // "Accept" and "Decline" buttons are shown for N seconds
+pub const MESSAGE_GROUP_TIME_WINDOW_SEC: i64 = 60; // consecutive messages from the same author are grouped
+pub const CLEAR_CHAT_UNDO_TIMEOUT_SEC: u64 = 5; // "Undo" button is shown for N seconds after clearing the chat
 pub const DEFAULT_SERVER_PORT: u16 = 51337;
+pub const DNS_CACHE_TTL_SEC: u64 = 30; // prefetched server address is resolved again after N seconds
 
 pub const INTERVAL_TCP_IDLE_MS: u64 = 250;
 pub const INTERVAL_TCP_MESSAGE_MS: u64 = 10;
+pub const AUTO_CONNECT_DELAY_MS: u64 = 3000; // the user can cancel the auto-connect during this time
+pub const HANDSHAKE_TIMEOUT_SEC: u64 = 10; // give up if the server does not answer during the key exchange
+pub const TCP_KEEPALIVE_IDLE_SEC: u64 = 30; // OS starts sending TCP keep-alive probes after N seconds of silence
+pub const TCP_KEEPALIVE_INTERVAL_SEC: u64 = 5; // (not used on macOS)
+pub const TCP_KEEPALIVE_RETRIES: u32 = 4; // unanswered probes before the connection is dropped (Linux only, Windows uses 10)
 pub const INTERVAL_UDP_MESSAGE_MS: u64 = 2;
+pub const DEFAULT_MAX_VOICE_PACKETS_PER_SEC: u32 = 500; // incoming UDP packets above this rate are dropped (0 disables the limit)
+
+pub const SEND_QUEUE_CAPACITY: usize = 32; // max chat messages waiting to be sent
+pub const SEND_QUEUE_WAIT_TIMEOUT_MS: u64 = 250;
+pub const UDP_SEND_QUEUE_MAX_NORMAL_DEPTH: usize = 25; // max voice packets waiting to be sent (~0.5 sec of voice)
+pub const HIGH_UDP_JITTER_MS: f64 = 20.0; // show a warning when the jitter is higher
 
 pub const USER_CONNECT_FIRST_UDP_PING_RETRY_MAX_COUNT: u8 = 4; // when somebody connected and we already received his ping on UDP,
                                                                // but no info about user was received on TCP (so retry later)
@@ -30,10 +82,32 @@ pub const USER_CONNECT_FIRST_UDP_PING_RETRY_INTERVAL_MS: usize = 250; // try aga
 
 pub const MAX_WAIT_TIME_IN_VOICE_PLAYER_SEC: u64 = 3;
 
+pub const LATEST_RELEASE_API_URL: &str =
+    "https://api.github.com/repos/Flone-dnb/silent-rs/releases/latest";
+pub const RELEASES_PAGE_URL: &str = "https://github.com/Flone-dnb/silent-rs/releases/latest";
+pub const UPDATE_CHECK_TIMEOUT_SEC: u64 = 10;
+pub const IMAGE_FETCH_TIMEOUT_SEC: u64 = 10;
+pub const MAX_INLINE_IMAGE_SIZE: u64 = 1024 * 1024; // in bytes, bigger images are not downloaded
+pub const MAX_INLINE_IMAGE_DIMENSION: u32 = 200; // thumbnails are fit into N x N pixels
+
 pub const NEW_MESSAGE_SOUND_PATH: &str = "res/sounds/newmessage.wav";
 pub const CONNECTED_SOUND_PATH: &str = "res/sounds/connect.wav";
 pub const DISCONNECT_SOUND_PATH: &str = "res/sounds/disconnect.wav";
 pub const PUSH_TO_TALK_PRESS_SOUND: &str = "res/sounds/press.wav";
 pub const PUSH_TO_TALK_UNPRESS_SOUND: &str = "res/sounds/unpress.wav";
+pub const DEFAULT_PTT_RELEASE_DELAY_MS: u16 = 200; // keep recording for N ms after the push-to-talk button is released
+pub const MAX_PTT_RELEASE_DELAY_MS: u16 = 2000;
+pub const DEFAULT_AEC_DELAY_MS: u32 = 50; // estimated delay between the speakers and the microphone
+pub const MAX_AEC_DELAY_MS: u32 = 500;
+pub const DEFAULT_AUDIO_PLAY_BUFFER_CHUNKS: u8 = 3; // chunks to receive before starting to play the voice
+pub const MIN_AUDIO_PLAY_BUFFER_CHUNKS: u8 = 1;
+pub const MAX_AUDIO_PLAY_BUFFER_CHUNKS: u8 = 20;
+pub const DEFAULT_AUDIO_RECORD_BUFFER_CHUNKS: u8 = 6; // chunks to send before checking if the push-to-talk button was released
+pub const MAX_AUDIO_RECORD_BUFFER_CHUNKS: u8 = 20;
+pub const DEFAULT_RECONNECT_MAX_ATTEMPTS: u8 = 5;
+pub const DEFAULT_RECONNECT_BASE_DELAY_MS: u64 = 1000; // doubled after each failed attempt
+pub const MAX_RECONNECT_DELAY_MS: u64 = 60000;
+pub const DEFAULT_LOG_LEVEL: &str = "warn"; // 'RUST_LOG' environment variable overrides the config value
+pub const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
 
 pub const LOCALIZATION_FILE_PATH: &str = "res/localization.csv";
@@ -2,27 +2,66 @@
 use chrono::Local;
 use druid::widget::prelude::*;
 use druid::widget::{
-    Button, CrossAxisAlignment, Flex, Label, LineBreaking, MainAxisAlignment, SizedBox, TextBox,
+    Button, Checkbox, CrossAxisAlignment, Either, EnvScope, Flex, Label, LineBreaking,
+    MainAxisAlignment, SizedBox, TextBox, ViewSwitcher,
 };
-use druid::{Lens, LensExt, TextAlignment, WidgetExt};
+use druid::{Color, Lens, LensExt, Selector, Target, TextAlignment, WidgetExt};
 use system_wide_key_state::*;
+use tracing::warn;
 
 use std::collections::HashMap;
 // Std.
+use std::net::{SocketAddr, ToSocketAddrs};
 use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 // Custom.
+use crate::error::SilentError;
 use crate::global_params::*;
+use crate::layouts::main_layout::MainLayout;
+use crate::layouts::settings_layout::SettingsLayout;
+use crate::misc::auto_connect_controller::*;
+use crate::misc::focus_lost_controller::*;
 use crate::misc::formatter_max_characters::*;
+use crate::misc::formatter_min_characters::*;
+use crate::misc::formatter_server_address::*;
+use crate::misc::input_sanitizer::*;
 use crate::misc::locale_keys::*;
+use crate::misc::masked_textbox_controller::*;
 use crate::services::audio_service::audio_service::UserVoiceData;
 use crate::services::config_service::*;
+use crate::services::event_sink::EventSink;
 use crate::services::net_service::*;
+use crate::services::tcp_packets::VoiceCodec;
 use crate::services::user_tcp_service::*;
+use crate::widgets::animated_progress_bar::AnimatedProgressBar;
 use crate::ApplicationState;
 use crate::Layout;
 
 const WIDTH_SPACING: f64 = 2.0;
+const CONNECT_PROGRESS_BAR_WIDTH: f64 = 200.0;
+const CONNECT_PROGRESS_BOUNCES_PER_SEC: f64 = 0.7;
+
+pub const CONNECT_LAYOUT_PREFETCH_SERVER_ADDRESS: Selector =
+    Selector::new("connect_layout_prefetch_server_address");
+pub const CONNECT_LAYOUT_DNS_RESOLVED: Selector<ResolvedServerAddress> =
+    Selector::new("connect_layout_dns_resolved");
+
+#[derive(Clone)]
+pub struct ResolvedServerAddress {
+    pub server: String,
+    pub port: String,
+    pub addr: Option<SocketAddr>, // None if failed to resolve
+}
+
+#[derive(Clone)]
+struct CachedServerAddress {
+    server: String,
+    port: String,
+    addr: SocketAddr,
+    resolved_at: Instant,
+}
 
 #[derive(Clone, Data, Lens)]
 pub struct ConnectLayout {
@@ -30,8 +69,15 @@ pub struct ConnectLayout {
     pub server: String,
     pub port: String,
     pub password: String,
+    pub password_display: String, // bullets shown while the password is hidden
+    pub is_password_shown: bool,
+    pub password_remember: bool,
     pub connect_result: String,
     pub show_input_notice: bool,
+    pub is_resolving: bool,
+    pub is_auto_connecting: bool, // waiting before the auto-connect (can be cancelled)
+    #[data(ignore)]
+    resolved_addr: Option<CachedServerAddress>,
 }
 
 impl ConnectLayout {
@@ -41,8 +87,14 @@ impl ConnectLayout {
             server: String::new(),
             port: String::from("51337"),
             password: String::new(),
+            password_display: String::new(),
+            is_password_shown: false,
+            password_remember: true,
             connect_result: String::new(),
             show_input_notice: false,
+            is_resolving: false,
+            is_auto_connecting: false,
+            resolved_addr: None,
         }
     }
     pub fn build_ui() -> impl Widget<ApplicationState> {
@@ -119,49 +171,142 @@ impl ConnectLayout {
                     .with_flex_child(
                         Flex::column()
                             .with_flex_child(
-                                TextBox::new()
-                                    .with_text_size(TEXT_SIZE)
-                                    .with_formatter(MaxCharactersFormatter::new(MAX_USERNAME_SIZE))
-                                    .update_data_while_editing(true)
-                                    .lens(
-                                        ApplicationState::connect_layout
-                                            .then(ConnectLayout::username),
+                                Flex::row()
+                                    .with_flex_child(
+                                        EnvScope::new(
+                                            |env, data: &ApplicationState| {
+                                                if data.connect_layout.is_username_too_short() {
+                                                    env.set(
+                                                        druid::theme::BORDER_DARK,
+                                                        Color::YELLOW,
+                                                    );
+                                                    env.set(
+                                                        druid::theme::PRIMARY_LIGHT,
+                                                        Color::YELLOW,
+                                                    );
+                                                }
+                                            },
+                                            TextBox::new()
+                                                .with_text_size(TEXT_SIZE)
+                                                .with_formatter(MaxCharactersFormatter::new(
+                                                    MAX_USERNAME_SIZE,
+                                                    MaxCharactersFormatterMode::Graphemes,
+                                                ))
+                                                .update_data_while_editing(true)
+                                                .lens(
+                                                    ApplicationState::connect_layout
+                                                        .then(ConnectLayout::username),
+                                                ),
+                                        )
+                                        .expand(),
+                                        1.0,
                                     )
+                                    .with_child(Either::new(
+                                        |data: &ApplicationState, _env| {
+                                            data.connect_layout.is_username_too_short()
+                                        },
+                                        Label::new(|data: &ApplicationState, _env: &Env| {
+                                            data.localization
+                                                .get(LOCALE_CONNECT_LAYOUT_USERNAME_TOO_SHORT_TEXT)
+                                                .unwrap()
+                                                .clone()
+                                        })
+                                        .with_text_color(Color::YELLOW)
+                                        .with_text_size(TEXT_SIZE),
+                                        SizedBox::empty(),
+                                    ))
                                     .expand(),
                                 1.0,
                             )
                             .with_default_spacer()
                             .with_flex_child(
-                                TextBox::new()
-                                    .with_text_size(TEXT_SIZE)
-                                    .lens(
-                                        ApplicationState::connect_layout
-                                            .then(ConnectLayout::server),
-                                    )
-                                    .expand(),
+                                EnvScope::new(
+                                    |env, data: &ApplicationState| {
+                                        if !data.connect_layout.is_server_address_valid() {
+                                            env.set(druid::theme::BORDER_DARK, Color::RED);
+                                            env.set(druid::theme::PRIMARY_LIGHT, Color::RED);
+                                        }
+                                    },
+                                    TextBox::new()
+                                        .with_text_size(TEXT_SIZE)
+                                        .with_formatter(ServerAddressFormatter::new())
+                                        .update_data_while_editing(true)
+                                        .lens(
+                                            ApplicationState::connect_layout
+                                                .then(ConnectLayout::server),
+                                        ),
+                                )
+                                .controller(FocusLostController::new(
+                                    CONNECT_LAYOUT_PREFETCH_SERVER_ADDRESS,
+                                ))
+                                .expand(),
                                 1.0,
                             )
                             .with_default_spacer()
                             .with_flex_child(
                                 TextBox::new()
                                     .with_text_size(TEXT_SIZE)
-                                    .with_formatter(MaxCharactersFormatter::new(5))
+                                    .with_formatter(MaxCharactersFormatter::new(
+                                        5,
+                                        MaxCharactersFormatterMode::Graphemes,
+                                    ))
                                     .update_data_while_editing(true)
                                     .lens(
                                         ApplicationState::connect_layout.then(ConnectLayout::port),
                                     )
+                                    .controller(FocusLostController::new(
+                                        CONNECT_LAYOUT_PREFETCH_SERVER_ADDRESS,
+                                    ))
                                     .expand(),
                                 1.0,
                             )
                             .with_default_spacer()
                             .with_flex_child(
-                                TextBox::new()
-                                    .with_text_size(TEXT_SIZE)
-                                    .with_formatter(MaxCharactersFormatter::new(MAX_PASSWORD_SIZE))
-                                    .update_data_while_editing(true)
-                                    .lens(
-                                        ApplicationState::connect_layout
-                                            .then(ConnectLayout::password),
+                                Flex::row()
+                                    .with_flex_child(
+                                        ViewSwitcher::new(
+                                            |data: &ApplicationState, _env| {
+                                                data.connect_layout.is_password_shown
+                                            },
+                                            |is_password_shown, _data, _env| {
+                                                ConnectLayout::build_password_text_box(
+                                                    *is_password_shown,
+                                                )
+                                            },
+                                        )
+                                        .expand(),
+                                        1.0,
+                                    )
+                                    .with_default_spacer()
+                                    .with_child(
+                                        Button::from_label(
+                                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                                let key = if data.connect_layout.is_password_shown {
+                                                    LOCALE_CONNECT_LAYOUT_HIDE_PASSWORD_TEXT
+                                                } else {
+                                                    LOCALE_CONNECT_LAYOUT_SHOW_PASSWORD_TEXT
+                                                };
+                                                data.localization.get(key).unwrap().clone()
+                                            })
+                                            .with_text_size(TEXT_SIZE),
+                                        )
+                                        .on_click(ConnectLayout::on_show_password_clicked),
+                                    )
+                                    .with_default_spacer()
+                                    .with_child(
+                                        Checkbox::new("").lens(
+                                            ApplicationState::connect_layout
+                                                .then(ConnectLayout::password_remember),
+                                        ),
+                                    )
+                                    .with_child(
+                                        Label::new(|data: &ApplicationState, _env: &Env| {
+                                            data.localization
+                                                .get(LOCALE_CONNECT_LAYOUT_REMEMBER_PASSWORD_TEXT)
+                                                .unwrap()
+                                                .clone()
+                                        })
+                                        .with_text_size(TEXT_SIZE),
                                     )
                                     .expand(),
                                 1.0,
@@ -177,6 +322,18 @@ impl ConnectLayout {
                 Label::new(|data: &ApplicationState, _env: &_| {
                     if data.connect_layout.show_input_notice {
                         "Please fill all non-optional fields.".to_string()
+                    } else if !data.connect_layout.is_server_address_valid() {
+                        data.localization
+                            .get(LOCALE_CONNECT_LAYOUT_INVALID_SERVER_ADDRESS_TEXT)
+                            .unwrap()
+                            .clone()
+                    } else if data.connect_layout.is_resolving
+                        && data.connect_layout.connect_result.is_empty()
+                    {
+                        data.localization
+                            .get(LOCALE_CONNECT_LAYOUT_RESOLVING_TEXT)
+                            .unwrap()
+                            .clone()
                     } else {
                         data.connect_layout.connect_result.clone()
                     }
@@ -186,21 +343,46 @@ impl ConnectLayout {
                 .with_line_break_mode(LineBreaking::WordWrap),
                 5.0,
             )
+            .with_child(Either::new(
+                |data: &ApplicationState, _env| {
+                    data.connect_layout.is_auto_connecting
+                        || (data.connect_layout.is_resolving
+                            && data.connect_layout.connect_result.is_empty())
+                },
+                // duration is unknown
+                AnimatedProgressBar::new(|_data: &ApplicationState| None)
+                    .with_bounces_per_sec(CONNECT_PROGRESS_BOUNCES_PER_SEC)
+                    .fix_width(CONNECT_PROGRESS_BAR_WIDTH),
+                SizedBox::empty(),
+            ))
             .with_flex_child(SizedBox::empty().expand(), 5.0)
             .with_flex_child(
                 Flex::row()
                     .with_flex_child(SizedBox::empty().expand(), 35.0)
                     .with_flex_child(
-                        Button::from_label(
-                            Label::new(|data: &ApplicationState, _env: &Env| {
-                                data.localization
-                                    .get(LOCALE_CONNECT_LAYOUT_CONNECT_TEXT)
-                                    .unwrap()
-                                    .clone()
-                            })
-                            .with_text_size(TEXT_SIZE),
+                        Either::new(
+                            |data: &ApplicationState, _env| data.connect_layout.is_auto_connecting,
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.localization
+                                        .get(LOCALE_CONNECT_LAYOUT_CANCEL_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(ConnectLayout::on_cancel_auto_connect_clicked),
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.localization
+                                        .get(LOCALE_CONNECT_LAYOUT_CONNECT_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(ConnectLayout::on_connect_clicked),
                         )
-                        .on_click(ConnectLayout::on_connect_clicked)
                         .expand(),
                         30.0,
                     )
@@ -229,28 +411,142 @@ impl ConnectLayout {
                 10.0,
             )
             .with_flex_child(SizedBox::empty().expand(), 10.0)
+            .controller(AutoConnectController::new())
+    }
+    fn build_password_text_box(is_password_shown: bool) -> Box<dyn Widget<ApplicationState>> {
+        if is_password_shown {
+            return Box::new(
+                TextBox::new()
+                    .with_text_size(TEXT_SIZE)
+                    .with_formatter(MaxCharactersFormatter::new(
+                        MAX_PASSWORD_SIZE,
+                        MaxCharactersFormatterMode::Graphemes,
+                    ))
+                    .update_data_while_editing(true)
+                    .lens(ApplicationState::connect_layout.then(ConnectLayout::password)),
+            );
+        }
+
+        // the controller edits the real password, the text box only shows bullets
+        Box::new(
+            TextBox::new()
+                .with_text_size(TEXT_SIZE)
+                .lens(ApplicationState::connect_layout.then(ConnectLayout::password_display))
+                .controller(MaskedTextBoxController::new(
+                    ApplicationState::connect_layout.then(ConnectLayout::password),
+                    ApplicationState::connect_layout.then(ConnectLayout::password_display),
+                    MAX_PASSWORD_SIZE,
+                )),
+        )
+    }
+    fn on_show_password_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.connect_layout.toggle_password_shown();
+    }
+    fn toggle_password_shown(&mut self) {
+        self.is_password_shown = !self.is_password_shown;
+
+        // the password might have been changed while it was shown
+        self.password_display = mask_text(&self.password);
     }
     pub fn read_user_config(&mut self, config: &UserConfig) -> Result<(), String> {
         self.username = config.username.clone();
         self.server = config.server.clone();
         self.port = config.server_port.to_string();
         self.password = config.server_password.clone();
+        self.password_display = mask_text(&self.password);
+        self.password_remember = config.password_remember;
 
         Ok(())
     }
-    pub fn save_user_config(&self, data: &ApplicationState) -> Result<(), String> {
+    pub fn save_user_config(&self, data: &ApplicationState) -> Result<(), SilentError> {
         let mut config_guard = data.user_config.lock().unwrap();
 
         config_guard.username = self.username.clone();
         config_guard.server = self.server.clone();
         config_guard.server_port = self.port.parse::<u16>().unwrap();
         config_guard.server_password = self.password.clone();
+        config_guard.password_remember = self.password_remember;
 
         config_guard.save()
     }
-    pub fn is_data_filled(&mut self, push_to_talk_key: KeyCode) -> Result<ClientConfig, ()> {
+    /// Resolves the server address in background (if the cached one is missing or stale),
+    /// the result is sent as CONNECT_LAYOUT_DNS_RESOLVED.
+    pub fn prefetch_server_address(&mut self, event_sink: EventSink) {
+        if self.is_resolving
+            || self.server.is_empty()
+            || !self.is_server_address_valid()
+            || self.port.parse::<u16>().is_err()
+            || self.get_cached_server_address().is_some()
+        {
+            return;
+        }
+
+        self.is_resolving = true;
+
+        let server = self.server.clone();
+        let port = self.port.clone();
+        thread::spawn(move || {
+            let addr = match format!("{}:{}", server, port).to_socket_addrs() {
+                Ok(mut addrs) => addrs.next(),
+                Err(e) => {
+                    warn!(
+                        "failed to resolve '{}', error: {} at [{}, {}]",
+                        server,
+                        e,
+                        file!(),
+                        line!()
+                    );
+                    None
+                }
+            };
+
+            event_sink
+                .submit_command(
+                    CONNECT_LAYOUT_DNS_RESOLVED,
+                    ResolvedServerAddress { server, port, addr },
+                    Target::Auto,
+                )
+                .expect("failed to submit CONNECT_LAYOUT_DNS_RESOLVED command");
+        });
+    }
+    pub fn set_resolved_server_address(&mut self, resolved: &ResolvedServerAddress) {
+        self.is_resolving = false;
+
+        self.resolved_addr = resolved.addr.map(|addr| CachedServerAddress {
+            server: resolved.server.clone(),
+            port: resolved.port.clone(),
+            addr,
+            resolved_at: Instant::now(),
+        });
+    }
+    /// Returns None if the address was not resolved yet, is stale or was resolved for other fields.
+    pub fn get_cached_server_address(&self) -> Option<SocketAddr> {
+        let cached = self.resolved_addr.as_ref()?;
+
+        if cached.server != self.server
+            || cached.port != self.port
+            || cached.resolved_at.elapsed() > Duration::from_secs(DNS_CACHE_TTL_SEC)
+        {
+            return None;
+        }
+
+        Some(cached.addr)
+    }
+    // Empty username is not too short here (the "fill all fields" notice is shown instead).
+    pub fn is_username_too_short(&self) -> bool {
+        MinCharactersValidator::new(MIN_USERNAME_SIZE).is_too_short(&self.username)
+    }
+    pub fn is_server_address_valid(&self) -> bool {
+        self.server.is_empty() || ServerAddressFormatter::validate_full_input(&self.server).is_ok()
+    }
+    pub fn is_data_filled(
+        &mut self,
+        push_to_talk_key: KeyCode,
+        voice_codec: VoiceCodec,
+    ) -> Result<ClientConfig, ()> {
         if self.server.chars().count() > 1
-            && self.username.chars().count() > 1
+            && !self.username.is_empty()
+            && !self.is_username_too_short()
             && self.port.chars().count() > 1
         {
             self.show_input_notice = false;
@@ -260,12 +556,27 @@ impl ConnectLayout {
                 server_port: self.port.clone(),
                 server_password: self.password.clone(),
                 push_to_talk_key,
+                voice_codec,
+                server_addr: self.get_cached_server_address(),
             })
         } else {
             self.show_input_notice = true;
             Err(())
         }
     }
+    pub fn show_config_recovered_message(
+        &mut self,
+        backup_path: &str,
+        localization: &Arc<HashMap<String, String>>,
+    ) {
+        self.connect_result = format!(
+            "{} \"{}\".",
+            localization
+                .get(LOCALE_CONNECT_LAYOUT_CONFIG_RECOVERED_TEXT)
+                .unwrap(),
+            backup_path
+        );
+    }
     pub fn set_connect_result(
         &mut self,
         connect_result: ConnectResult,
@@ -335,6 +646,10 @@ impl ConnectLayout {
                 .get(LOCALE_CONNECT_LAYOUT_CONNECT_RESULT_ERR_SERVER_IS_FULL)
                 .unwrap()
                 .clone(),
+            ConnectResult::ErrTimeout => localization
+                .get(LOCALE_CONNECT_LAYOUT_CONNECT_RESULT_ERR_HANDSHAKE_TIMEOUT)
+                .unwrap()
+                .clone(),
             ConnectResult::InfoAboutOtherUser(_, _, _) => String::from(""), // will never be here
             ConnectResult::InfoAboutRoom(_) => String::from(""),            // will never be here
             ConnectResult::Ok => String::from(""),
@@ -371,18 +686,62 @@ impl ConnectLayout {
 
         Ok(())
     }
-    fn on_connect_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+    /// Returns 'true' if all fields needed for the auto-connect are filled.
+    pub fn can_auto_connect(&self) -> bool {
+        !self.username.is_empty() && !self.server.is_empty() && !self.port.is_empty()
+    }
+    /// Returns 'true' if the auto-connect should be started on launch.
+    pub fn should_auto_connect(&self, config: &UserConfig) -> bool {
+        config.connect_on_launch && self.can_auto_connect()
+    }
+    /// Shows "Connecting to [server]..." and connects after AUTO_CONNECT_DELAY_MS
+    /// (see AutoConnectController) unless cancelled.
+    pub fn start_auto_connect(&mut self, localization: &Arc<HashMap<String, String>>) {
+        self.is_auto_connecting = true;
+        self.connect_result = format!(
+            "{} {}...",
+            localization
+                .get(LOCALE_CONNECT_LAYOUT_AUTO_CONNECTING_TEXT)
+                .unwrap(),
+            self.server
+        );
+    }
+    fn on_cancel_auto_connect_clicked(
+        _ctx: &mut EventCtx,
+        data: &mut ApplicationState,
+        _env: &Env,
+    ) {
+        data.connect_layout.cancel_auto_connect();
+    }
+    /// AutoConnectController will not connect.
+    pub fn cancel_auto_connect(&mut self) {
+        self.is_auto_connecting = false;
+        self.connect_result = String::new();
+    }
+    pub fn on_connect_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
         data.window_handle = Arc::new(Some(ctx.window().clone()));
 
+        // the user might have clicked "connect" before the auto-connect started
+        data.connect_layout.is_auto_connecting = false;
+
+        data.connect_layout.username =
+            InputSanitizer::sanitize_username(&data.connect_layout.username);
+
         if let Err(msg) = ConnectLayout::check_fields_length(data) {
             data.connect_layout
                 .set_connect_result(ConnectResult::Err(msg), &data.localization);
             return;
         }
 
+        if !data.connect_layout.is_server_address_valid() {
+            // the notice is already shown
+            return;
+        }
+
+        let voice_codec = data.user_config.lock().unwrap().voice_codec;
         let config = data
             .connect_layout
-            .is_data_filled(data.settings_layout.push_to_talk_keycode);
+            .is_data_filled(data.settings_layout.push_to_talk_keycode, voice_codec);
         if config.is_err() {
             return;
         }
@@ -392,13 +751,29 @@ impl ConnectLayout {
         let mut net_service_guard = data.network_service.lock().unwrap();
 
         net_service_guard.init_audio_service(Arc::clone(&data.audio_service));
+        {
+            let config_guard = data.user_config.lock().unwrap();
+            let mut udp_service_guard = net_service_guard.user_udp_service.lock().unwrap();
+            udp_service_guard.set_max_voice_upload_kbps(config_guard.max_voice_upload_kbps);
+            udp_service_guard.set_max_voice_packets_per_sec(config_guard.max_voice_packets_per_sec);
+            udp_service_guard.set_socket_buffer_sizes(
+                config_guard.udp_recv_buffer_kb,
+                config_guard.udp_send_buffer_kb,
+            );
+            udp_service_guard.set_health_check(
+                config_guard.udp_health_check_enabled,
+                config_guard.udp_health_check_timeout_sec,
+            );
+            udp_service_guard
+                .set_preferred_network_interface(config_guard.preferred_network_interface.clone());
+        }
 
         net_service_guard.start(
             config,
             data.connect_layout.username.clone(),
             data.connect_layout.password.clone(),
             tx,
-            ctx.get_external_handle(),
+            ctx.get_external_handle().into(),
         );
 
         loop {
@@ -429,10 +804,18 @@ impl ConnectLayout {
                     }
 
                     data.main_layout.current_user_name = data.connect_layout.username.clone();
+                    data.main_layout.current_server = data.connect_layout.server.clone();
+                    data.main_layout.current_port =
+                        data.connect_layout.port.parse::<u16>().unwrap_or(0);
                     data.current_layout = Layout::Main;
                     data.is_connected = true;
                     data.main_layout.play_connect_sound();
 
+                    let auto_record = data.user_config.lock().unwrap().auto_record;
+                    if auto_record {
+                        MainLayout::start_voice_recording(data);
+                    }
+
                     // Save config.
                     if let Err(msg) = data.connect_layout.save_user_config(data) {
                         data.main_layout.add_system_message(format!(
@@ -498,6 +881,254 @@ impl ConnectLayout {
         }
     }
     fn on_settings_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
-        data.current_layout = Layout::Settings;
+        SettingsLayout::open(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::event_sink::SubmittedCommand;
+
+    const RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn filled_connect_layout() -> ConnectLayout {
+        let mut connect_layout = ConnectLayout::new();
+        connect_layout.server = String::from("127.0.0.1");
+        connect_layout.port = String::from("51337");
+        connect_layout
+    }
+
+    fn resolved_address(connect_layout: &ConnectLayout) -> ResolvedServerAddress {
+        ResolvedServerAddress {
+            server: connect_layout.server.clone(),
+            port: connect_layout.port.clone(),
+            addr: Some("127.0.0.1:51337".parse().unwrap()),
+        }
+    }
+
+    fn receive_resolved_address(
+        receiver: &mpsc::Receiver<SubmittedCommand>,
+    ) -> ResolvedServerAddress {
+        let command = receiver
+            .recv_timeout(RESOLVE_TIMEOUT)
+            .expect("the address was not resolved");
+
+        command
+            .get(CONNECT_LAYOUT_DNS_RESOLVED)
+            .expect("unexpected command")
+            .clone()
+    }
+
+    #[test]
+    fn address_is_prefetched() {
+        let mut connect_layout = filled_connect_layout();
+        let (sender, receiver) = mpsc::channel();
+
+        connect_layout.prefetch_server_address(EventSink::Channel(sender));
+        assert!(connect_layout.is_resolving);
+        assert_eq!(connect_layout.get_cached_server_address(), None);
+
+        let resolved = receive_resolved_address(&receiver);
+        assert_eq!(resolved.server, "127.0.0.1");
+        assert_eq!(resolved.port, "51337");
+        assert_eq!(resolved.addr, Some("127.0.0.1:51337".parse().unwrap()));
+
+        connect_layout.set_resolved_server_address(&resolved);
+        assert!(!connect_layout.is_resolving);
+        assert_eq!(connect_layout.get_cached_server_address(), resolved.addr);
+    }
+
+    #[test]
+    fn fresh_address_is_not_resolved_again() {
+        let mut connect_layout = filled_connect_layout();
+        connect_layout.set_resolved_server_address(&resolved_address(&connect_layout));
+        let (sender, receiver) = mpsc::channel();
+
+        connect_layout.prefetch_server_address(EventSink::Channel(sender));
+
+        assert!(!connect_layout.is_resolving);
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn stale_address_is_not_used() {
+        let mut connect_layout = filled_connect_layout();
+        connect_layout.set_resolved_server_address(&resolved_address(&connect_layout));
+
+        let cached = connect_layout.resolved_addr.as_mut().unwrap();
+        cached.resolved_at -= Duration::from_secs(DNS_CACHE_TTL_SEC - 1);
+        assert!(connect_layout.get_cached_server_address().is_some());
+
+        let cached = connect_layout.resolved_addr.as_mut().unwrap();
+        cached.resolved_at -= Duration::from_secs(2);
+        assert_eq!(connect_layout.get_cached_server_address(), None);
+
+        // Resolved again.
+        let (sender, receiver) = mpsc::channel();
+        connect_layout.prefetch_server_address(EventSink::Channel(sender));
+        assert!(connect_layout.is_resolving);
+
+        let resolved = receive_resolved_address(&receiver);
+        connect_layout.set_resolved_server_address(&resolved);
+        assert_eq!(connect_layout.get_cached_server_address(), resolved.addr);
+    }
+
+    #[test]
+    fn address_of_other_fields_is_not_used() {
+        let mut connect_layout = filled_connect_layout();
+        connect_layout.set_resolved_server_address(&resolved_address(&connect_layout));
+
+        connect_layout.port = String::from("51338");
+        assert_eq!(connect_layout.get_cached_server_address(), None);
+
+        connect_layout.port = String::from("51337");
+        connect_layout.server = String::from("127.0.0.2");
+        assert_eq!(connect_layout.get_cached_server_address(), None);
+    }
+
+    #[test]
+    fn failed_resolution_is_not_cached() {
+        let mut connect_layout = filled_connect_layout();
+        connect_layout.is_resolving = true;
+
+        connect_layout.set_resolved_server_address(&ResolvedServerAddress {
+            server: connect_layout.server.clone(),
+            port: connect_layout.port.clone(),
+            addr: None,
+        });
+
+        assert!(!connect_layout.is_resolving);
+        assert_eq!(connect_layout.get_cached_server_address(), None);
+    }
+
+    #[test]
+    fn invalid_fields_are_not_resolved() {
+        let mut connect_layout = filled_connect_layout();
+        connect_layout.port = String::from("not a port");
+        let (sender, _receiver) = mpsc::channel();
+
+        connect_layout.prefetch_server_address(EventSink::Channel(sender));
+        assert!(!connect_layout.is_resolving);
+
+        connect_layout.port = String::from("51337");
+        connect_layout.server = String::new();
+        let (sender, _receiver) = mpsc::channel();
+
+        connect_layout.prefetch_server_address(EventSink::Channel(sender));
+        assert!(!connect_layout.is_resolving);
+    }
+
+    #[test]
+    fn password_is_hidden_by_default() {
+        let mut connect_layout = ConnectLayout::new();
+        assert!(!connect_layout.is_password_shown);
+
+        let mut config = UserConfig::empty();
+        config.server_password = String::from("пароль");
+        connect_layout.read_user_config(&config).unwrap();
+
+        assert_eq!(connect_layout.password, "пароль");
+        assert_eq!(connect_layout.password_display, "••••••");
+    }
+
+    #[test]
+    fn show_password_toggle_keeps_real_password() {
+        let mut connect_layout = ConnectLayout::new();
+        connect_layout.password = String::from("secret");
+        connect_layout.password_display = mask_text(&connect_layout.password);
+
+        connect_layout.toggle_password_shown();
+        assert!(connect_layout.is_password_shown);
+        assert_eq!(connect_layout.password, "secret");
+
+        // edited while shown
+        connect_layout.password.push_str("123");
+
+        connect_layout.toggle_password_shown();
+        assert!(!connect_layout.is_password_shown);
+        assert_eq!(connect_layout.password, "secret123");
+        assert_eq!(connect_layout.password_display, "•••••••••");
+    }
+
+    fn auto_connect_config(connect_on_launch: bool) -> UserConfig {
+        let mut config = UserConfig::empty();
+        config.connect_on_launch = connect_on_launch;
+        config.username = String::from("user");
+        config.server = String::from("example.com");
+        config.server_port = 51337;
+        config
+    }
+
+    fn auto_connect_localization() -> Arc<HashMap<String, String>> {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_CONNECT_LAYOUT_AUTO_CONNECTING_TEXT),
+            String::from("Connecting to"),
+        );
+        Arc::new(localization)
+    }
+
+    #[test]
+    fn auto_connect_requires_connect_on_launch() {
+        let mut connect_layout = ConnectLayout::new();
+        connect_layout
+            .read_user_config(&auto_connect_config(true))
+            .unwrap();
+        assert!(connect_layout.should_auto_connect(&auto_connect_config(true)));
+
+        assert!(!connect_layout.should_auto_connect(&auto_connect_config(false)));
+    }
+
+    #[test]
+    fn auto_connect_requires_filled_fields() {
+        let config = auto_connect_config(true);
+        let clear_field_functions: [fn(&mut ConnectLayout); 3] = [
+            |layout| layout.username.clear(),
+            |layout| layout.server.clear(),
+            |layout| layout.port.clear(),
+        ];
+        for clear_field in clear_field_functions.iter() {
+            let mut connect_layout = ConnectLayout::new();
+            connect_layout.read_user_config(&config).unwrap();
+
+            clear_field(&mut connect_layout);
+
+            assert!(!connect_layout.can_auto_connect());
+            assert!(!connect_layout.should_auto_connect(&config));
+        }
+    }
+
+    #[test]
+    fn auto_connect_shows_server() {
+        let mut connect_layout = ConnectLayout::new();
+        connect_layout
+            .read_user_config(&auto_connect_config(true))
+            .unwrap();
+
+        connect_layout.start_auto_connect(&auto_connect_localization());
+
+        assert!(connect_layout.is_auto_connecting);
+        assert_eq!(
+            connect_layout.connect_result,
+            "Connecting to example.com..."
+        );
+    }
+
+    #[test]
+    fn cancelled_auto_connect_does_not_connect() {
+        let mut connect_layout = ConnectLayout::new();
+        connect_layout
+            .read_user_config(&auto_connect_config(true))
+            .unwrap();
+        connect_layout.start_auto_connect(&auto_connect_localization());
+
+        connect_layout.cancel_auto_connect();
+
+        // checked by AutoConnectController when its timer fires
+        assert!(!connect_layout.is_auto_connecting);
+        assert!(connect_layout.connect_result.is_empty());
+        // the fields are kept for a manual connect
+        assert!(connect_layout.can_auto_connect());
     }
 }
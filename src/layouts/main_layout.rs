@@ -1,26 +1,47 @@
 // External.
 use druid::widget::prelude::*;
 use druid::widget::{
-    Button, Container, CrossAxisAlignment, Flex, Label, Padding, SizedBox, TextBox,
+    Button, Container, CrossAxisAlignment, Either, Flex, Label, LineBreaking, Padding, SizedBox,
+    TextBox,
 };
-use druid::{Application, Data, Lens, LensExt, WidgetExt};
+use druid::{Application, Color, Data, Lens, LensExt, Selector, WidgetExt};
 use sfml::audio::{Sound, SoundBuffer, SoundStatus};
+use tracing::warn;
 
 // Std.
+use std::path::Path;
+use std::rc::Rc;
 use std::thread;
 use std::time::Duration;
 
 // Custom.
+use crate::global_params::*;
+use crate::layouts::connect_layout::ConnectLayout;
+use crate::layouts::settings_layout::SettingsLayout;
 use crate::misc::formatter_max_characters::*; // add formatter when #1975 is resolved
 use crate::misc::{
-    custom_data_button_controller::*, custom_text_box_controller::*, locale_keys::*,
+    accessibility_controller::*, clear_chat_undo_controller::*, custom_data_button_controller::*,
+    custom_text_box_controller::*, input_sanitizer::*, locale_keys::*,
+    mention_notice_controller::*, reconnect_controller::*, text_highlighter::*,
+    user_drag_controller::*, voice_session_controller::*,
 };
-use crate::services::net_service::ActionError;
+use crate::services::audio_service::voice_session_recording::VoiceSessionRecording;
+use crate::services::config_service::UserConfig;
+use crate::services::net_service::{ActionError, ReconnectPolicy};
 use crate::theme::BACKGROUND_SPECIAL_COLOR;
 use crate::widgets::chat_list::*;
+use crate::widgets::confirm_dialog::ConfirmDialog;
 use crate::widgets::connected_list::*;
+use crate::widgets::ptt_status::PttStatusWidget;
+use crate::widgets::room_info_popup::RoomInfoPopup;
+use crate::widgets::search_panel::*;
 use crate::ApplicationState;
-use crate::{global_params::*, Layout};
+use crate::Layout;
+
+pub const DISCONNECT_REQUESTED: Selector<()> = Selector::new("main_layout_disconnect_requested");
+
+const UNREAD_BADGE_COLOR: Color = Color::rgb8(200, 60, 60);
+const RECONNECT_TEXT_COLOR: Color = Color::rgb8(255, 200, 0);
 
 #[derive(Clone, Data, Lens)]
 pub struct MainLayout {
@@ -30,6 +51,27 @@ pub struct MainLayout {
     pub current_user_room: String,
     pub current_user_name: String,
     pub connected_count_text: usize,
+    pub room_user_counts: Rc<Vec<(String, usize)>>,
+    pub send_queue_depth: usize,
+    pub current_server: String,
+    pub current_port: u16,
+    pub clear_chat_confirmation: bool,
+    pub udp_jitter_ms: f64,
+    pub udp_decryption_failures: u64, // UDP packets that failed authentication
+    pub current_user_status: UserStatus,
+    pub is_ptt_recording: bool,
+    pub ptt_recording_sec: u64,
+    pub is_search_shown: bool,
+    pub search_query: String,
+    pub search_results: Rc<SearchResults>,
+    pub talking_user_count: u32, // not counting us
+    pub session_voice_seconds: u64,
+    pub mention_notice: String, // "Notified @username" after sending a message with mentions
+    pub is_recording_voice: bool,
+    pub reconnect_attempt: u8, // 0 if not reconnecting
+    pub reconnect_max_attempts: u8,
+    pub reconnect_countdown_sec: u64,
+    pub reconnect_room: String, // the room to return to after reconnecting
 }
 
 impl MainLayout {
@@ -41,93 +83,412 @@ impl MainLayout {
             current_user_room: String::new(),
             current_user_name: String::new(),
             connected_count_text: 0,
+            room_user_counts: Rc::new(Vec::new()),
+            send_queue_depth: 0,
+            current_server: String::new(),
+            current_port: 0,
+            clear_chat_confirmation: false,
+            udp_jitter_ms: 0.0,
+            udp_decryption_failures: 0,
+            current_user_status: UserStatus::Active,
+            is_ptt_recording: false,
+            ptt_recording_sec: 0,
+            is_search_shown: false,
+            search_query: String::new(),
+            search_results: Rc::new(SearchResults::default()),
+            talking_user_count: 0,
+            session_voice_seconds: 0,
+            mention_notice: String::new(),
+            is_recording_voice: false,
+            reconnect_attempt: 0,
+            reconnect_max_attempts: 0,
+            reconnect_countdown_sec: 0,
+            reconnect_room: String::new(),
         }
     }
     pub fn build_ui() -> impl Widget<ApplicationState> {
-        Padding::new(
+        SearchPanel::new(RoomInfoPopup::new(PttStatusWidget::new(Padding::new(
             10.0,
-            Flex::row()
+            Flex::column()
+                .must_fill_main_axis(true)
                 .cross_axis_alignment(CrossAxisAlignment::Start)
-                .with_flex_child(
-                    Flex::column()
-                        .must_fill_main_axis(true)
-                        .with_flex_child(
-                            Flex::row()
-                                .with_child(
-                                    Button::from_label(
-                                        Label::new(|data: &ApplicationState, _env: &Env| {
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.main_layout.get_info_bar_text(&data.localization)
+                    })
+                    .with_text_size(TEXT_SIZE)
+                    .controller(VoiceSessionController::new()),
+                )
+                .with_child(Either::new(
+                    |data: &ApplicationState, _env| data.main_layout.is_reconnecting(),
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.main_layout.get_reconnect_text(&data.localization)
+                    })
+                    .with_text_color(RECONNECT_TEXT_COLOR)
+                    .with_text_size(TEXT_SIZE),
+                    SizedBox::empty(),
+                ))
+                .with_default_spacer()
+                .with_flex_child(MainLayout::build_chat_and_users_ui(), 1.0)
+                .controller(ReconnectController::new()),
+        ))))
+    }
+    pub fn is_reconnecting(&self) -> bool {
+        self.reconnect_attempt > 0
+    }
+    /// Starts the countdown to the next reconnect attempt,
+    /// returns 'false' if all attempts were used (reconnecting is stopped then).
+    pub fn schedule_reconnect(&mut self, policy: ReconnectPolicy) -> bool {
+        if self.reconnect_attempt >= policy.max_attempts {
+            self.stop_reconnecting();
+            return false;
+        }
+
+        let delay_ms = policy.get_delay_ms(self.reconnect_attempt);
+        self.reconnect_attempt += 1;
+        self.reconnect_max_attempts = policy.max_attempts;
+        self.reconnect_countdown_sec = ((delay_ms + 999) / 1000).max(1);
+
+        true
+    }
+    pub fn stop_reconnecting(&mut self) {
+        self.reconnect_attempt = 0;
+        self.reconnect_countdown_sec = 0;
+        self.reconnect_room = String::new();
+    }
+    /// "Reconnecting in N seconds (attempt X/Y)".
+    pub fn get_reconnect_text(
+        &self,
+        localization: &std::sync::Arc<std::collections::HashMap<String, String>>,
+    ) -> String {
+        format!(
+            "{} {} {} ({} {}/{})",
+            localization
+                .get(LOCALE_MAIN_LAYOUT_RECONNECTING_IN_TEXT)
+                .unwrap(),
+            self.reconnect_countdown_sec,
+            localization
+                .get(LOCALE_SETTINGS_LAYOUT_SETTING_SECONDS_TEXT)
+                .unwrap(),
+            localization
+                .get(LOCALE_MAIN_LAYOUT_RECONNECT_ATTEMPT_TEXT)
+                .unwrap(),
+            self.reconnect_attempt,
+            self.reconnect_max_attempts
+        )
+    }
+    /// Called by the connection lost event, the room is remembered
+    /// only on the first attempt (later attempts start from an empty room list).
+    pub fn on_connection_lost(data: &mut ApplicationState) {
+        if !data.main_layout.is_reconnecting() {
+            data.main_layout.reconnect_room = data.main_layout.current_user_room.clone();
+        }
+        data.is_connected = false;
+
+        let policy = data.network_service.lock().unwrap().reconnect_policy;
+        if data.main_layout.schedule_reconnect(policy) {
+            return;
+        }
+
+        MainLayout::give_up_reconnecting(data);
+    }
+    /// Connects using the values of the connect layout, on success returns
+    /// to the room we were in, otherwise schedules the next attempt.
+    pub fn reconnect(ctx: &mut EventCtx, data: &mut ApplicationState, env: &Env) {
+        let room = data.main_layout.reconnect_room.clone();
+
+        ConnectLayout::on_connect_clicked(ctx, data, env);
+
+        if data.is_connected {
+            data.main_layout.stop_reconnecting();
+            data.main_layout.add_system_message(
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_RECONNECTED_TEXT)
+                    .unwrap()
+                    .clone(),
+            );
+            if !room.is_empty() {
+                MainLayout::enter_room(data, &room);
+            }
+            return;
+        }
+
+        let policy = data.network_service.lock().unwrap().reconnect_policy;
+        if !data.main_layout.schedule_reconnect(policy) {
+            MainLayout::give_up_reconnecting(data);
+        }
+    }
+    /// Returns to the connect layout, it shows the error of the last attempt.
+    fn give_up_reconnecting(data: &mut ApplicationState) {
+        data.main_layout.stop_reconnecting();
+        data.main_layout.current_user_name = String::new();
+        data.main_layout.clear_text_chat();
+        data.current_layout = Layout::Connect;
+    }
+    pub fn close_search(&mut self) {
+        self.is_search_shown = false;
+        self.search_query = String::new();
+        self.search_results = Rc::new(SearchResults::default());
+    }
+    /// Ignores results of the outdated queries.
+    pub fn set_search_results(&mut self, results: SearchResults) {
+        if self.is_search_shown && results.query == self.search_query.trim() {
+            self.search_results = Rc::new(results);
+        }
+    }
+    pub fn set_ptt_recording(&mut self, is_recording: bool) {
+        self.is_ptt_recording = is_recording;
+        self.ptt_recording_sec = 0;
+    }
+    fn build_clear_chat_ui() -> impl Widget<ApplicationState> {
+        Either::new(
+            |data: &ApplicationState, _env| data.main_layout.clear_chat_confirmation,
+            ConfirmDialog::new(
+                LOCALE_MAIN_LAYOUT_CLEAR_CHAT_CONFIRMATION_TEXT,
+                MainLayout::on_clear_chat_confirmed,
+                |_ctx, data: &mut ApplicationState, _env| {
+                    data.main_layout.cancel_clear_chat();
+                },
+            ),
+            Either::new(
+                |data: &ApplicationState, _env| data.main_layout.chat_list.can_undo_clear_all(),
+                Button::from_label(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_UNDO_CLEAR_CHAT_BUTTON_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .on_click(|_ctx, data: &mut ApplicationState, _env| {
+                    data.main_layout.chat_list.undo_clear_all();
+                }),
+                Button::from_label(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_CLEAR_CHAT_BUTTON_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .on_click(|_ctx, data: &mut ApplicationState, _env| {
+                    data.main_layout.request_clear_chat();
+                }),
+            ),
+        )
+        .controller(ClearChatUndoController::new())
+    }
+    fn on_clear_chat_confirmed(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.main_layout.confirm_clear_chat();
+        ctx.submit_command(CLEAR_CHAT_UNDO_TIMER_START);
+    }
+    /// Shows the confirmation instead of the "Clear Chat" button.
+    pub fn request_clear_chat(&mut self) {
+        self.clear_chat_confirmation = true;
+    }
+    pub fn cancel_clear_chat(&mut self) {
+        self.clear_chat_confirmation = false;
+    }
+    /// Clears the chat, the "Undo" button is shown until the undo timer
+    /// expires or a new message arrives.
+    pub fn confirm_clear_chat(&mut self) {
+        self.clear_chat_confirmation = false;
+        self.chat_list.clear_all();
+    }
+    fn build_chat_and_users_ui() -> impl Widget<ApplicationState> {
+        Flex::row()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_flex_child(
+                Flex::column()
+                    .must_fill_main_axis(true)
+                    .with_flex_child(
+                        Flex::row()
+                            .with_child(
+                                Button::from_label(
+                                    Label::new(|data: &ApplicationState, _env: &Env| {
+                                        data.localization
+                                            .get(LOCALE_MAIN_LAYOUT_SETTINGS_BUTTON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    })
+                                    .with_text_size(TEXT_SIZE),
+                                )
+                                .on_click(MainLayout::on_settings_clicked),
+                            )
+                            .with_default_spacer()
+                            .with_child(
+                                Button::from_label(
+                                    Label::new(|data: &ApplicationState, _env: &Env| {
+                                        data.localization
+                                            .get(LOCALE_MAIN_LAYOUT_DISCONNECT_BUTTON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    })
+                                    .with_text_size(TEXT_SIZE),
+                                )
+                                .on_click(MainLayout::on_disconnect_clicked)
+                                .disabled_if(
+                                    |data: &ApplicationState, _env: &Env| !data.is_connected,
+                                ),
+                            )
+                            .with_default_spacer()
+                            .with_child(MainLayout::build_clear_chat_ui())
+                            .with_default_spacer()
+                            .with_child(
+                                Button::from_label(
+                                    Label::new(|data: &ApplicationState, _env: &Env| {
+                                        format!(
+                                            "{}: {}",
                                             data.localization
-                                                .get(LOCALE_MAIN_LAYOUT_SETTINGS_BUTTON_TEXT)
+                                                .get(LOCALE_MAIN_LAYOUT_COMPACT_USER_LIST_BUTTON_TEXT)
+                                                .unwrap(),
+                                            data.localization
+                                                .get(if data.main_layout.connected_list.is_compact {
+                                                    LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT
+                                                } else {
+                                                    LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT
+                                                })
                                                 .unwrap()
-                                                .clone()
-                                        })
-                                        .with_text_size(TEXT_SIZE),
-                                    )
-                                    .on_click(MainLayout::on_settings_clicked),
+                                        )
+                                    })
+                                    .with_text_size(TEXT_SIZE),
                                 )
-                                .expand(),
-                            10.0,
-                        )
-                        .with_flex_child(
-                            Label::new(|data: &ApplicationState, _env: &Env| {
-                                data.localization
-                                    .get(LOCALE_MAIN_LAYOUT_TEXT_CHAT_TITLE_TEXT)
-                                    .unwrap()
-                                    .clone()
-                            })
-                            .with_text_size(TEXT_SIZE),
-                            10.0,
-                        )
-                        .with_default_spacer()
-                        .with_flex_child(
-                            Container::new(ChatList::build_ui())
-                                .background(BACKGROUND_SPECIAL_COLOR)
-                                .rounded(druid::theme::BUTTON_BORDER_RADIUS)
-                                .expand(),
-                            70.0,
-                        )
-                        .with_default_spacer()
-                        .with_flex_child(
-                            TextBox::multiline()
-                                .with_text_size(TEXT_SIZE)
-                                //.with_formatter(MaxCharactersFormatter::new(MAX_MESSAGE_SIZE))
-                                .controller(CustomTextBoxController::new())
-                                .lens(ApplicationState::main_layout.then(MainLayout::message))
-                                .expand(),
-                            10.0,
-                        ),
-                    60.0,
-                )
-                .with_default_spacer()
-                .with_flex_child(
-                    Flex::column()
-                        .must_fill_main_axis(true)
-                        .cross_axis_alignment(CrossAxisAlignment::Center)
-                        .with_flex_child(SizedBox::empty().expand(), 10.0)
-                        .with_flex_child(
-                            Label::new(|data: &ApplicationState, _env: &Env| {
-                                format!(
-                                    "{}: {}",
-                                    data.localization
-                                        .get(LOCALE_MAIN_LAYOUT_CONNECTED_TITLE_TEXT)
-                                        .unwrap(),
-                                    data.main_layout.connected_count_text
+                                .on_click(MainLayout::on_compact_user_list_clicked),
+                            )
+                            .with_default_spacer()
+                            .with_child(
+                                Button::from_label(
+                                    Label::new(|data: &ApplicationState, _env: &Env| {
+                                        data.localization
+                                            .get(if data.main_layout.is_recording_voice {
+                                                LOCALE_MAIN_LAYOUT_STOP_RECORDING_BUTTON_TEXT
+                                            } else {
+                                                LOCALE_MAIN_LAYOUT_RECORD_BUTTON_TEXT
+                                            })
+                                            .unwrap()
+                                            .clone()
+                                    })
+                                    .with_text_size(TEXT_SIZE),
                                 )
-                            })
-                            .with_text_size(TEXT_SIZE),
-                            10.0,
-                        )
-                        .with_default_spacer()
-                        .with_flex_child(
-                            Container::new(ConnectedList::build_ui())
-                                .background(BACKGROUND_SPECIAL_COLOR)
-                                .rounded(druid::theme::BUTTON_BORDER_RADIUS)
-                                .expand(),
-                            80.0,
-                        ),
-                    40.0,
-                ),
-        )
+                                .on_click(MainLayout::on_record_clicked)
+                                .disabled_if(
+                                    |data: &ApplicationState, _env: &Env| !data.is_connected,
+                                ),
+                            )
+                            .expand(),
+                        10.0,
+                    )
+                    .with_flex_child(
+                        Flex::row()
+                            .with_child(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.localization
+                                        .get(LOCALE_MAIN_LAYOUT_TEXT_CHAT_TITLE_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .with_spacer(5.0)
+                            .with_child(Either::new(
+                                |data: &ApplicationState, _env| {
+                                    data.main_layout.chat_list.unread_count > 0
+                                },
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.main_layout.chat_list.unread_count.to_string()
+                                })
+                                .with_text_size(TEXT_SIZE * 0.7)
+                                .with_text_color(Color::WHITE)
+                                .padding((4.0, 1.0))
+                                .background(UNREAD_BADGE_COLOR)
+                                .rounded(3.0),
+                                SizedBox::empty(),
+                            )),
+                        10.0,
+                    )
+                    .with_default_spacer()
+                    .with_flex_child(
+                        Container::new(ChatList::build_ui())
+                            .background(BACKGROUND_SPECIAL_COLOR)
+                            .rounded(druid::theme::BUTTON_BORDER_RADIUS)
+                            .expand(),
+                        70.0,
+                    )
+                    .with_default_spacer()
+                    .with_child(Either::new(
+                        |data: &ApplicationState, _env| !data.main_layout.mention_notice.is_empty(),
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            data.main_layout.mention_notice.clone()
+                        })
+                        .with_text_size(TEXT_SIZE)
+                        .with_text_color(Color::GRAY),
+                        SizedBox::empty(),
+                    )
+                    .controller(MentionNoticeController::new()))
+                    .with_child(Either::new(
+                        |data: &ApplicationState, _env| data.main_layout.send_queue_depth > 0,
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            format!(
+                                "{}: {}",
+                                data.localization
+                                    .get(LOCALE_MAIN_LAYOUT_SEND_QUEUE_DEPTH_TEXT)
+                                    .unwrap(),
+                                data.main_layout.send_queue_depth
+                            )
+                        })
+                        .with_text_size(TEXT_SIZE),
+                        SizedBox::empty(),
+                    ))
+                    .with_flex_child(
+                        TextBox::multiline()
+                            .with_text_size(TEXT_SIZE)
+                            //.with_formatter(MaxCharactersFormatter::new(MAX_MESSAGE_SIZE, MaxCharactersFormatterMode::Graphemes))
+                            .controller(CustomTextBoxController::new())
+                            .lens(ApplicationState::main_layout.then(MainLayout::message))
+                            .controller(AccessibilityController::new(String::from("Message input")))
+                            .expand(),
+                        10.0,
+                    ),
+                60.0,
+            )
+            .with_default_spacer()
+            .with_flex_child(
+                Flex::column()
+                    .must_fill_main_axis(true)
+                    .cross_axis_alignment(CrossAxisAlignment::Center)
+                    .with_flex_child(SizedBox::empty().expand(), 10.0)
+                    .with_flex_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            format!(
+                                "{}: {}",
+                                data.localization
+                                    .get(LOCALE_MAIN_LAYOUT_CONNECTED_TITLE_TEXT)
+                                    .unwrap(),
+                                data.main_layout.connected_count_text
+                            )
+                        })
+                        .with_text_size(TEXT_SIZE),
+                        10.0,
+                    )
+                    .with_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            data.main_layout.get_room_user_counts_text()
+                        })
+                        .with_text_size(TEXT_SIZE * 0.8)
+                        .with_line_break_mode(LineBreaking::WordWrap),
+                    )
+                    .with_default_spacer()
+                    .with_flex_child(
+                        Container::new(ConnectedList::build_ui())
+                            .background(BACKGROUND_SPECIAL_COLOR)
+                            .rounded(druid::theme::BUTTON_BORDER_RADIUS)
+                            .expand(),
+                        80.0,
+                    ),
+                40.0,
+            )
     }
     pub fn set_user_talking(&mut self, username: &str, talk_start: bool) {
         let mut found = false;
@@ -139,6 +500,15 @@ impl MainLayout {
 
                 for user in users_guard.iter_mut() {
                     if &user.user_data.username == username {
+                        if user.user_data.is_talking != talk_start
+                            && username != self.current_user_name
+                        {
+                            if talk_start {
+                                self.talking_user_count += 1;
+                            } else {
+                                self.talking_user_count = self.talking_user_count.saturating_sub(1);
+                            }
+                        }
                         user.user_data.is_talking = talk_start;
                         found = true;
                         self.connected_list.refresh_ui = !self.connected_list.refresh_ui;
@@ -152,14 +522,26 @@ impl MainLayout {
         }
 
         if found == false {
-            println!(
-                "SILENT_WARNING: can't find user {} to set_user_talking, at [{}:{}]",
+            warn!(
+                "can't find user {} to set_user_talking, at [{}:{}]",
                 username,
                 file!(),
                 line!()
             );
         }
     }
+    /// Called every second by 'VoiceSessionController' while its timer runs.
+    /// Adds one second no matter how many users are talking,
+    /// returns 'false' if nobody is talking (the timer should stop).
+    pub fn on_voice_session_tick(&mut self) -> bool {
+        if self.talking_user_count == 0 {
+            return false;
+        }
+
+        self.session_voice_seconds += 1;
+
+        true
+    }
     pub fn play_connect_sound(&self) {
         thread::spawn(move || {
             let buffer = SoundBuffer::from_file(CONNECTED_SOUND_PATH).unwrap();
@@ -179,8 +561,138 @@ impl MainLayout {
     pub fn get_message_input(&self) -> String {
         self.message.clone()
     }
+    pub fn set_room_description(
+        &mut self,
+        room_name: &str,
+        description: String,
+    ) -> Result<(), String> {
+        self.connected_list
+            .set_room_description(room_name, description)
+    }
     pub fn set_user_ping(&mut self, username: &str, ping_ms: u16) -> Result<(), ()> {
-        self.connected_list.set_user_ping(username, ping_ms)
+        let result = self.connected_list.set_user_ping(username, ping_ms);
+
+        if result.is_err() && username == self.current_user_name {
+            // our own ping is measured on each ping check, we are not in the list yet,
+            // the next measurement will be shown
+            return Ok(());
+        }
+
+        result
+    }
+    pub fn add_user_voice_session(
+        &mut self,
+        username: &str,
+        talking_ms: u64,
+        bytes_received: u64,
+    ) -> Result<(), ()> {
+        self.connected_list
+            .add_user_voice_session(username, talking_ms, bytes_received)
+    }
+    /// "[Server: name:port] [Room: name] [Users: N] [UDP Jitter: X ms] [Status: S]",
+    /// "[UDP Decryption Errors: N]" is added if some UDP packets failed to decrypt.
+    pub fn get_info_bar_text(
+        &self,
+        localization: &std::sync::Arc<std::collections::HashMap<String, String>>,
+    ) -> String {
+        let text = format!(
+            "[{}: {}:{}] [{}: {}] [{}: {}] [{}: {:.1} {}] [{}: {}] [{}: {}]",
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_SERVER_TEXT)
+                .unwrap(),
+            self.current_server,
+            self.current_port,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_ROOM_TEXT)
+                .unwrap(),
+            self.current_user_room,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_USERS_TEXT)
+                .unwrap(),
+            self.connected_count_text,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_UDP_JITTER_TEXT)
+                .unwrap(),
+            self.udp_jitter_ms,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_USER_INFO_PING_TIME_TEXT)
+                .unwrap(),
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_STATUS_TEXT)
+                .unwrap(),
+            localization
+                .get(match self.current_user_status {
+                    UserStatus::Active => LOCALE_MAIN_LAYOUT_USER_STATUS_ACTIVE_TEXT,
+                    UserStatus::Away => LOCALE_MAIN_LAYOUT_USER_STATUS_AWAY_TEXT,
+                })
+                .unwrap(),
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_VOICE_ACTIVE_TEXT)
+                .unwrap(),
+            MainLayout::format_voice_duration(self.session_voice_seconds)
+        );
+
+        if self.udp_decryption_failures == 0 {
+            return text;
+        }
+
+        format!(
+            "{} [{}: {}]",
+            text,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_INFO_BAR_UDP_DECRYPTION_ERRORS_TEXT)
+                .unwrap(),
+            self.udp_decryption_failures
+        )
+    }
+    /// Example: "1h 5m".
+    pub fn format_voice_duration(seconds: u64) -> String {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    }
+    pub fn set_current_user_status(&mut self, status: UserStatus) {
+        if self.current_user_status == status {
+            return;
+        }
+
+        self.current_user_status = status;
+
+        if !self.current_user_name.is_empty() {
+            let username = self.current_user_name.clone();
+            if self
+                .connected_list
+                .set_user_status(&username, status)
+                .is_err()
+            {
+                warn!(
+                    "can't find user {} to set_user_status, at [{}:{}]",
+                    username,
+                    file!(),
+                    line!()
+                );
+            }
+        }
+    }
+    /// Shows a warning when the jitter becomes higher than HIGH_UDP_JITTER_MS.
+    pub fn set_udp_jitter(
+        &mut self,
+        jitter_ms: f64,
+        localization: &std::sync::Arc<std::collections::HashMap<String, String>>,
+    ) {
+        let was_high = self.udp_jitter_ms > HIGH_UDP_JITTER_MS;
+        self.udp_jitter_ms = jitter_ms;
+
+        if !was_high && jitter_ms > HIGH_UDP_JITTER_MS {
+            self.add_system_message(format!(
+                "{} ({:.1} {}).",
+                localization
+                    .get(LOCALE_MAIN_LAYOUT_MESSAGE_HIGH_UDP_JITTER_TEXT)
+                    .unwrap(),
+                jitter_ms,
+                localization
+                    .get(LOCALE_MAIN_LAYOUT_USER_INFO_PING_TIME_TEXT)
+                    .unwrap()
+            ));
+        }
     }
     pub fn clear_message_input(&mut self) {
         self.message.clear();
@@ -217,12 +729,20 @@ impl MainLayout {
             }
         }
 
-        let res = self.connected_list.add_user(username, room, ping_ms);
+        let res = self
+            .connected_list
+            .add_user(username.clone(), room, ping_ms);
         if let Err(msg) = res {
             return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
         }
 
+        if dont_show_notice {
+            // the user was already there when we connected, not a new one
+            self.connected_list.clear_new_badge(&username);
+        }
+
         self.connected_count_text += 1;
+        self.update_room_user_counts();
 
         Ok(())
     }
@@ -231,6 +751,7 @@ impl MainLayout {
     }
     pub fn add_room(&mut self, room_name: String) {
         self.connected_list.add_room(room_name);
+        self.update_room_user_counts();
     }
     pub fn move_user(&mut self, username: &str, room_to: &str) -> Result<(), String> {
         if self.current_user_name.is_empty() {
@@ -249,6 +770,7 @@ impl MainLayout {
         ) {
             Err(format!("{} at [{}, {}]", msg, file!(), line!()))
         } else {
+            self.update_room_user_counts();
             Ok(())
         }
     }
@@ -257,6 +779,10 @@ impl MainLayout {
         username: &str,
         localization: &std::sync::Arc<std::collections::HashMap<String, String>>,
     ) -> Result<(), String> {
+        if username != self.current_user_name && self.connected_list.is_user_talking(username) {
+            self.talking_user_count = self.talking_user_count.saturating_sub(1);
+        }
+
         let mut removed_user_room = String::new();
         match self
             .connected_list
@@ -284,13 +810,30 @@ impl MainLayout {
                 }
 
                 self.connected_count_text -= 1;
+                self.update_room_user_counts();
 
                 return Ok(());
             }
         }
     }
-    pub fn add_message(&mut self, message: String, author: String, show_notification: bool) {
-        self.chat_list.add_message(&message, &author);
+    /// Returns ID of the chat message that contains the text.
+    /// If the chat is not visible (another layout is shown) the message is counted as unread.
+    pub fn add_message(
+        &mut self,
+        message: String,
+        author: String,
+        server_message_id: u64,
+        show_notification: bool,
+        is_chat_visible: bool,
+    ) -> u64 {
+        let usernames = self.connected_list.get_usernames();
+        let message_id =
+            self.chat_list
+                .add_message(&message, &author, server_message_id, &usernames);
+
+        if (author != self.current_user_name) && !is_chat_visible {
+            self.chat_list.increment_unread_count();
+        }
 
         if (author != self.current_user_name) && show_notification {
             use notify_rust::Notification;
@@ -311,6 +854,23 @@ impl MainLayout {
                 .show()
                 .unwrap();
         }
+
+        message_id
+    }
+    /// Formats the status message of a user, for example: "[username] is away: message".
+    pub fn format_user_status_message(
+        username: &str,
+        text: &str,
+        localization: &std::sync::Arc<std::collections::HashMap<String, String>>,
+    ) -> String {
+        format!(
+            "[{}] {}: {}",
+            username,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_MESSAGE_USER_IS_AWAY_TEXT)
+                .unwrap(),
+            text
+        )
     }
     pub fn add_system_message(&mut self, message: String) {
         self.chat_list.add_system_message(message);
@@ -320,11 +880,135 @@ impl MainLayout {
     }
     pub fn clear_all_users(&mut self) {
         self.connected_list.clear_all_users();
+        self.talking_user_count = 0;
+        self.session_voice_seconds = 0;
+        self.update_room_user_counts();
+    }
+    /// Returns a compact list of non-empty rooms, for example: "Lobby: 2, Gaming: 3".
+    pub fn get_room_user_counts_text(&self) -> String {
+        self.room_user_counts
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(room, count)| format!("{}: {}", room, count))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+    fn update_room_user_counts(&mut self) {
+        self.room_user_counts = Rc::new(self.connected_list.get_room_user_counts());
     }
     fn on_settings_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
-        data.current_layout = Layout::Settings;
+        SettingsLayout::open(data);
+    }
+    fn on_compact_user_list_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        MainLayout::toggle_compact_user_list(data);
+    }
+    fn toggle_compact_user_list(data: &mut ApplicationState) {
+        let connected_list = &mut data.main_layout.connected_list;
+        connected_list.is_compact = !connected_list.is_compact;
+        connected_list.refresh_ui = !connected_list.refresh_ui;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.compact_user_list = connected_list.is_compact;
+
+        config_guard.mark_dirty();
+    }
+    fn on_record_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        if data.main_layout.is_recording_voice {
+            MainLayout::stop_voice_recording(data);
+        } else {
+            MainLayout::start_voice_recording(data);
+        }
+    }
+    /// Records the voice of all users to a new file in the recordings directory
+    /// (see 'stop_voice_recording').
+    pub fn start_voice_recording(data: &mut ApplicationState) {
+        let recordings_dir = UserConfig::get_config_directory();
+        if let Err(err) = recordings_dir {
+            data.main_layout
+                .add_system_message(format!("{} at [{}, {}]", err, file!(), line!()));
+            return;
+        }
+        let recordings_dir =
+            Path::new(&recordings_dir.unwrap()).join(VOICE_RECORDINGS_DIRECTORY_NAME);
+
+        if let Err(e) = std::fs::create_dir_all(&recordings_dir) {
+            data.main_layout.add_system_message(format!(
+                "std::fs::create_dir_all() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+            return;
+        }
+
+        let path = recordings_dir.join(VoiceSessionRecording::get_file_name(
+            &format!(
+                "{}:{}",
+                data.main_layout.current_server, data.main_layout.current_port
+            ),
+            chrono::Local::now(),
+        ));
+
+        if let Err(msg) = data
+            .audio_service
+            .lock()
+            .unwrap()
+            .start_recording_session(&path)
+        {
+            data.main_layout.add_system_message(msg);
+            return;
+        }
+
+        data.main_layout.is_recording_voice = true;
+        data.main_layout.add_system_message(format!(
+            "{} \"{}\".",
+            data.localization
+                .get(LOCALE_MAIN_LAYOUT_RECORDING_STARTED_TEXT)
+                .unwrap(),
+            path.display()
+        ));
+    }
+    /// Saves the recording (if there is something to save), can be called after disconnect.
+    pub fn stop_voice_recording(data: &mut ApplicationState) {
+        if !data.main_layout.is_recording_voice {
+            return;
+        }
+        data.main_layout.is_recording_voice = false;
+
+        let result = data.audio_service.lock().unwrap().stop_recording_session();
+        let message = match result {
+            Ok(Some(path)) => format!(
+                "{} \"{}\".",
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_RECORDING_SAVED_TEXT)
+                    .unwrap(),
+                path.display()
+            ),
+            Ok(None) => return, // nobody talked
+            Err(msg) => msg,
+        };
+
+        if data.is_connected {
+            data.main_layout.add_system_message(message);
+        } else if data.connect_layout.connect_result.is_empty() {
+            data.connect_layout.connect_result = message;
+        } else {
+            // don't hide the disconnect reason
+            data.connect_layout.connect_result =
+                format!("{}\n{}", data.connect_layout.connect_result, message);
+        }
+    }
+    fn on_disconnect_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        if !data.is_connected {
+            return;
+        }
+
+        ctx.submit_command(DISCONNECT_REQUESTED);
     }
     pub fn send_message_event(data: &mut ApplicationState) {
+        data.main_layout.message = InputSanitizer::sanitize_message(&data.main_layout.message);
+
         if !data.main_layout.message.is_empty() {
             // remove last '\n's
             loop {
@@ -389,12 +1073,43 @@ impl MainLayout {
                                 .clone(),
                         );
                     }
+                    ActionError::SendQueueFull => {
+                        data.main_layout.add_system_message(
+                            data.localization
+                                .get(LOCALE_MAIN_LAYOUT_MESSAGE_SEND_QUEUE_FULL_TEXT)
+                                .unwrap()
+                                .clone(),
+                        );
+                    }
                 };
             } else {
+                data.main_layout.mention_notice = MainLayout::get_mention_notice(data);
                 data.main_layout.clear_message_input();
             }
         }
     }
+    /// Returns "Notified @username, ..." for the users (except us) mentioned
+    /// in the message input, empty if nobody is mentioned.
+    fn get_mention_notice(data: &ApplicationState) -> String {
+        let usernames = data.main_layout.connected_list.get_usernames();
+        let mentioned: Vec<String> =
+            TextHighlighter::get_mentioned_users(&data.main_layout.message, &usernames)
+                .into_iter()
+                .filter(|username| *username != data.main_layout.current_user_name)
+                .map(|username| format!("@{}", username))
+                .collect();
+        if mentioned.is_empty() {
+            return String::new();
+        }
+
+        format!(
+            "{} {}",
+            data.localization
+                .get(LOCALE_MAIN_LAYOUT_MENTION_NOTIFIED_TEXT)
+                .unwrap(),
+            mentioned.join(", ")
+        )
+    }
     pub fn chat_list_message_pressed_event(
         data: &mut ApplicationState,
         button_info: &CustomButtonData,
@@ -431,6 +1146,155 @@ impl MainLayout {
             }
         }
     }
+    pub fn user_dropped_on_room_event(data: &mut ApplicationState, drop_info: &UserDropInfo) {
+        data.main_layout.connected_list.end_user_drag();
+
+        if drop_info.from_room == drop_info.to_room {
+            return;
+        }
+
+        if drop_info.username == data.main_layout.current_user_name {
+            // same as clicking on the room
+            MainLayout::connect_list_item_pressed_event(
+                data,
+                &CustomButtonData::ConnectedListData {
+                    is_room: true,
+                    button_name: drop_info.to_room.clone(),
+                },
+            );
+            return;
+        }
+
+        if !data
+            .main_layout
+            .connected_list
+            .is_user_admin(&data.main_layout.current_user_name)
+        {
+            data.main_layout.add_system_message(
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_MESSAGE_MOVE_USER_NO_PERMISSION_TEXT)
+                    .unwrap()
+                    .clone(),
+            );
+            return;
+        }
+
+        // TODO: the network protocol has no message to move other users yet.
+        data.main_layout.add_system_message(
+            data.localization
+                .get(LOCALE_MAIN_LAYOUT_MESSAGE_MOVE_USER_NOT_SUPPORTED_TEXT)
+                .unwrap()
+                .clone(),
+        );
+    }
+    /// Asks the server to move us to the room (if we are not there already),
+    /// errors are shown in the chat.
+    pub fn enter_room(data: &mut ApplicationState, room_name: &str) {
+        if data.main_layout.current_user_room == room_name {
+            return;
+        }
+
+        let result = data
+            .network_service
+            .lock()
+            .unwrap()
+            .enter_room(room_name, &data.main_layout.current_user_room);
+        if let Err(err) = result {
+            match err {
+                ActionError::SystemError(msg) => {
+                    data.main_layout.add_system_message(format!(
+                        "{}: {}",
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_MESSAGE_SYSTEM_ERROR_TEXT)
+                            .unwrap(),
+                        msg
+                    ));
+                }
+                ActionError::ChangeRoomsTooQuick => {
+                    data.main_layout.add_system_message(
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_MESSAGE_CHANGE_ROOMS_TOO_QUICK_TEXT)
+                            .unwrap()
+                            .clone(),
+                    );
+                }
+                ActionError::SendMessagesTooQuick => {
+                    data.main_layout.add_system_message(
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_MESSAGE_SEND_MESSAGES_TOO_QUICK_TEXT)
+                            .unwrap()
+                            .clone(),
+                    );
+                }
+                ActionError::SendQueueFull => {
+                    data.main_layout.add_system_message(
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_MESSAGE_SEND_QUEUE_FULL_TEXT)
+                            .unwrap()
+                            .clone(),
+                    );
+                }
+            };
+        }
+    }
+    /// Asks the server to suggest the user to join the room.
+    /// Inviting yourself or an admin does nothing.
+    /// Enters the invite's room if the invite is still active.
+    pub fn accept_room_invite(data: &mut ApplicationState, message_id: u64) {
+        if let Some(room_name) = data.main_layout.chat_list.close_room_invite(message_id) {
+            MainLayout::enter_room(data, &room_name);
+        }
+    }
+    pub fn invite_user_to_room(
+        data: &mut ApplicationState,
+        target_username: String,
+        room_name: String,
+    ) {
+        if target_username == data.main_layout.current_user_name
+            || data
+                .main_layout
+                .connected_list
+                .is_user_admin(&target_username)
+        {
+            return;
+        }
+
+        let result = data
+            .network_service
+            .lock()
+            .unwrap()
+            .send_room_invite(target_username.clone(), room_name.clone());
+        match result {
+            Ok(()) => {
+                data.main_layout.add_info_message(format!(
+                    "{}: {} → {}",
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_ROOM_INVITE_SENT_TEXT)
+                        .unwrap(),
+                    target_username,
+                    room_name
+                ));
+            }
+            Err(ActionError::SystemError(msg)) => {
+                data.main_layout.add_system_message(format!(
+                    "{}: {}",
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_MESSAGE_SYSTEM_ERROR_TEXT)
+                        .unwrap(),
+                    msg
+                ));
+            }
+            Err(ActionError::SendQueueFull) => {
+                data.main_layout.add_system_message(
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_MESSAGE_SEND_QUEUE_FULL_TEXT)
+                        .unwrap()
+                        .clone(),
+                );
+            }
+            Err(_) => {} // no cooldown for invites
+        }
+    }
     pub fn connect_list_item_pressed_event(
         data: &mut ApplicationState,
         button_info: &CustomButtonData,
@@ -451,37 +1315,7 @@ impl MainLayout {
         }
 
         if _is_room_button {
-            if data.main_layout.current_user_room != _room_name {
-                if let Err(err) = data.network_service.lock().unwrap().enter_room(_room_name) {
-                    match err {
-                        ActionError::SystemError(msg) => {
-                            data.main_layout.add_system_message(format!(
-                                "{}: {}",
-                                data.localization
-                                    .get(LOCALE_MAIN_LAYOUT_MESSAGE_SYSTEM_ERROR_TEXT)
-                                    .unwrap(),
-                                msg
-                            ));
-                        }
-                        ActionError::ChangeRoomsTooQuick => {
-                            data.main_layout.add_system_message(
-                                data.localization
-                                    .get(LOCALE_MAIN_LAYOUT_MESSAGE_CHANGE_ROOMS_TOO_QUICK_TEXT)
-                                    .unwrap()
-                                    .clone(),
-                            );
-                        }
-                        ActionError::SendMessagesTooQuick => {
-                            data.main_layout.add_system_message(
-                                data.localization
-                                    .get(LOCALE_MAIN_LAYOUT_MESSAGE_SEND_MESSAGES_TOO_QUICK_TEXT)
-                                    .unwrap()
-                                    .clone(),
-                            );
-                        }
-                    };
-                }
-            }
+            MainLayout::enter_room(data, _room_name);
         } else {
             data.main_layout
                 .open_selected_user_info(String::from(_room_name));
@@ -546,3 +1380,753 @@ impl MainLayout {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::event_sink::{EventSink, SubmittedCommand};
+    use std::collections::HashMap;
+    use std::sync::{mpsc, Arc};
+
+    const POLICY: ReconnectPolicy = ReconnectPolicy {
+        max_attempts: 3,
+        base_delay_ms: 1000,
+    };
+
+    #[test]
+    fn reconnect_countdown_follows_backoff() {
+        let mut main_layout = MainLayout::new();
+        assert!(!main_layout.is_reconnecting());
+
+        let mut countdowns = Vec::new();
+        while main_layout.schedule_reconnect(POLICY) {
+            assert!(main_layout.is_reconnecting());
+            assert_eq!(main_layout.reconnect_max_attempts, POLICY.max_attempts);
+            countdowns.push(main_layout.reconnect_countdown_sec);
+        }
+
+        assert_eq!(countdowns, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn reconnect_countdown_is_capped() {
+        let policy = ReconnectPolicy {
+            max_attempts: 10,
+            base_delay_ms: 1000,
+        };
+        let mut main_layout = MainLayout::new();
+
+        let mut countdowns = Vec::new();
+        while main_layout.schedule_reconnect(policy) {
+            countdowns.push(main_layout.reconnect_countdown_sec);
+        }
+
+        assert_eq!(countdowns, vec![1, 2, 4, 8, 16, 32, 60, 60, 60, 60]);
+    }
+
+    #[test]
+    fn short_delay_is_rounded_up_to_1_sec() {
+        let policy = ReconnectPolicy {
+            max_attempts: 2,
+            base_delay_ms: 1,
+        };
+        let mut main_layout = MainLayout::new();
+
+        assert!(main_layout.schedule_reconnect(policy));
+        assert_eq!(main_layout.reconnect_countdown_sec, 1);
+    }
+
+    #[test]
+    fn reconnecting_stops_after_max_attempts() {
+        let mut main_layout = MainLayout::new();
+        main_layout.reconnect_room = String::from("Lobby");
+
+        for attempt in 1..=POLICY.max_attempts {
+            assert!(main_layout.schedule_reconnect(POLICY));
+            assert_eq!(main_layout.reconnect_attempt, attempt);
+        }
+
+        assert!(!main_layout.schedule_reconnect(POLICY));
+        assert!(!main_layout.is_reconnecting());
+        assert_eq!(main_layout.reconnect_countdown_sec, 0);
+        assert!(main_layout.reconnect_room.is_empty());
+    }
+
+    #[test]
+    fn no_attempts_when_max_attempts_is_zero() {
+        let policy = ReconnectPolicy {
+            max_attempts: 0,
+            base_delay_ms: 1000,
+        };
+        let mut main_layout = MainLayout::new();
+
+        assert!(!main_layout.schedule_reconnect(policy));
+        assert!(!main_layout.is_reconnecting());
+    }
+
+    fn info_bar_localization() -> Arc<HashMap<String, String>> {
+        let texts = [
+            (LOCALE_MAIN_LAYOUT_INFO_BAR_SERVER_TEXT, "Server"),
+            (LOCALE_MAIN_LAYOUT_INFO_BAR_ROOM_TEXT, "Room"),
+            (LOCALE_MAIN_LAYOUT_INFO_BAR_USERS_TEXT, "Users"),
+            (LOCALE_MAIN_LAYOUT_INFO_BAR_UDP_JITTER_TEXT, "UDP Jitter"),
+            (LOCALE_MAIN_LAYOUT_USER_INFO_PING_TIME_TEXT, "ms"),
+            (LOCALE_MAIN_LAYOUT_INFO_BAR_STATUS_TEXT, "Status"),
+            (LOCALE_MAIN_LAYOUT_USER_STATUS_ACTIVE_TEXT, "active"),
+            (LOCALE_MAIN_LAYOUT_USER_STATUS_AWAY_TEXT, "away"),
+            (
+                LOCALE_MAIN_LAYOUT_INFO_BAR_VOICE_ACTIVE_TEXT,
+                "Voice active",
+            ),
+            (
+                LOCALE_MAIN_LAYOUT_INFO_BAR_UDP_DECRYPTION_ERRORS_TEXT,
+                "UDP Decryption Errors",
+            ),
+        ];
+
+        Arc::new(
+            texts
+                .iter()
+                .map(|(key, text)| (String::from(*key), String::from(*text)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn info_bar_text_contains_server_room_and_users() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_server = String::from("example.com");
+        main_layout.current_port = 51337;
+        main_layout.current_user_room = String::from("Комната 1");
+        main_layout.connected_count_text = 5;
+        main_layout.udp_jitter_ms = 2.34;
+        main_layout.session_voice_seconds = 3900;
+
+        assert_eq!(
+            main_layout.get_info_bar_text(&info_bar_localization()),
+            "[Server: example.com:51337] [Room: Комната 1] [Users: 5] \
+            [UDP Jitter: 2.3 ms] [Status: active] [Voice active: 1h 5m]"
+        );
+    }
+
+    #[test]
+    fn info_bar_text_of_new_layout() {
+        assert_eq!(
+            MainLayout::new().get_info_bar_text(&info_bar_localization()),
+            "[Server: :0] [Room: ] [Users: 0] [UDP Jitter: 0.0 ms] \
+            [Status: active] [Voice active: 0h 0m]"
+        );
+    }
+
+    #[test]
+    fn info_bar_text_shows_away_status_and_decryption_errors() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_server = String::from("127.0.0.1");
+        main_layout.current_port = 1;
+        main_layout.current_user_room = String::from("Lobby");
+        main_layout.connected_count_text = 1;
+        main_layout.set_current_user_status(UserStatus::Away);
+        main_layout.udp_decryption_failures = 3;
+
+        assert_eq!(
+            main_layout.get_info_bar_text(&info_bar_localization()),
+            "[Server: 127.0.0.1:1] [Room: Lobby] [Users: 1] [UDP Jitter: 0.0 ms] \
+            [Status: away] [Voice active: 0h 0m] [UDP Decryption Errors: 3]"
+        );
+    }
+
+    #[test]
+    fn voice_duration_is_formatted_in_hours_and_minutes() {
+        assert_eq!(MainLayout::format_voice_duration(0), "0h 0m");
+        assert_eq!(MainLayout::format_voice_duration(59), "0h 0m");
+        assert_eq!(MainLayout::format_voice_duration(60), "0h 1m");
+        assert_eq!(MainLayout::format_voice_duration(3599), "0h 59m");
+        assert_eq!(MainLayout::format_voice_duration(3600), "1h 0m");
+        assert_eq!(
+            MainLayout::format_voice_duration(100 * 3600 + 61),
+            "100h 1m"
+        );
+    }
+
+    fn layout_with_messages(messages: &[&str]) -> MainLayout {
+        let mut main_layout = MainLayout::new();
+        for message in messages {
+            main_layout.add_system_message(String::from(*message));
+        }
+
+        main_layout
+    }
+
+    fn get_chat_messages(main_layout: &MainLayout) -> Vec<String> {
+        main_layout
+            .chat_list
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| message.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn cancelled_clear_keeps_messages() {
+        let mut main_layout = layout_with_messages(&["first", "second"]);
+
+        main_layout.request_clear_chat();
+        assert!(main_layout.clear_chat_confirmation);
+
+        main_layout.cancel_clear_chat();
+        assert!(!main_layout.clear_chat_confirmation);
+        assert!(!main_layout.chat_list.can_undo_clear_all());
+        assert_eq!(get_chat_messages(&main_layout), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn confirmed_clear_can_be_undone() {
+        let mut main_layout = layout_with_messages(&["first", "second"]);
+
+        main_layout.request_clear_chat();
+        main_layout.confirm_clear_chat();
+
+        assert!(!main_layout.clear_chat_confirmation);
+        assert!(get_chat_messages(&main_layout).is_empty());
+        assert!(main_layout.chat_list.can_undo_clear_all());
+
+        main_layout.chat_list.undo_clear_all();
+
+        assert!(!main_layout.chat_list.can_undo_clear_all());
+        assert_eq!(get_chat_messages(&main_layout), vec!["first", "second"]);
+
+        // nothing to undo anymore
+        main_layout.chat_list.undo_clear_all();
+        assert_eq!(get_chat_messages(&main_layout), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn new_message_drops_undo() {
+        let mut main_layout = layout_with_messages(&["first"]);
+
+        main_layout.confirm_clear_chat();
+        main_layout.add_system_message(String::from("new"));
+
+        assert!(!main_layout.chat_list.can_undo_clear_all());
+        main_layout.chat_list.undo_clear_all();
+        assert_eq!(get_chat_messages(&main_layout), vec!["new"]);
+    }
+
+    #[test]
+    fn undo_timeout_drops_undo() {
+        let mut main_layout = layout_with_messages(&["first"]);
+
+        main_layout.confirm_clear_chat();
+        // what 'ClearChatUndoController' does when the timer fires
+        main_layout.chat_list.drop_undo_buffer();
+
+        assert!(!main_layout.chat_list.can_undo_clear_all());
+        main_layout.chat_list.undo_clear_all();
+        assert!(get_chat_messages(&main_layout).is_empty());
+    }
+
+    #[test]
+    fn clear_after_undo_can_be_undone_again() {
+        let mut main_layout = layout_with_messages(&["first"]);
+
+        main_layout.confirm_clear_chat();
+        main_layout.chat_list.undo_clear_all();
+        main_layout.add_system_message(String::from("second"));
+        main_layout.confirm_clear_chat();
+
+        main_layout.chat_list.undo_clear_all();
+        assert_eq!(get_chat_messages(&main_layout), vec!["first", "second"]);
+        assert!(!main_layout.chat_list.can_undo_clear_all());
+    }
+
+    fn get_user_status(main_layout: &MainLayout, username: &str) -> UserStatus {
+        for room in main_layout.connected_list.rooms.lock().unwrap().iter() {
+            if let Some(user) = room
+                .users
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|user| user.user_data.username == username)
+            {
+                return user.user_data.status;
+            }
+        }
+
+        panic!("user '{}' not found", username);
+    }
+
+    #[test]
+    fn local_user_status_is_shown_in_connected_list() {
+        let mut main_layout = MainLayout::new();
+        main_layout.connected_list.add_room(String::from("Lobby"));
+        for username in ["me", "other"].iter() {
+            main_layout
+                .connected_list
+                .add_user(String::from(*username), String::from("Lobby"), 0)
+                .unwrap();
+        }
+        main_layout.current_user_name = String::from("me");
+
+        main_layout.set_current_user_status(UserStatus::Away);
+        assert_eq!(main_layout.current_user_status, UserStatus::Away);
+        assert_eq!(get_user_status(&main_layout, "me"), UserStatus::Away);
+        assert_eq!(get_user_status(&main_layout, "other"), UserStatus::Active);
+
+        main_layout.set_current_user_status(UserStatus::Active);
+        assert_eq!(main_layout.current_user_status, UserStatus::Active);
+        assert_eq!(get_user_status(&main_layout, "me"), UserStatus::Active);
+    }
+
+    fn user_count_localization() -> Arc<HashMap<String, String>> {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_MESSAGE_USER_DISCONNECTED_TEXT),
+            String::from("disconnected"),
+        );
+        Arc::new(localization)
+    }
+
+    // "Lobby": me, user1; "Gaming": user2, user3, user4; "Empty": nobody.
+    fn layout_with_users() -> MainLayout {
+        let localization = user_count_localization();
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+        main_layout.current_user_room = String::from("Lobby");
+
+        for room in ["Lobby", "Gaming", "Empty"].iter() {
+            main_layout.add_room(String::from(*room));
+        }
+        let users = [
+            ("me", "Lobby"),
+            ("user1", "Lobby"),
+            ("user2", "Gaming"),
+            ("user3", "Gaming"),
+            ("user4", "Gaming"),
+        ];
+        for (username, room) in users.iter() {
+            main_layout
+                .add_user(
+                    String::from(*username),
+                    String::from(*room),
+                    0,
+                    true,
+                    &localization,
+                )
+                .unwrap();
+        }
+
+        main_layout
+    }
+
+    #[test]
+    fn room_user_counts_include_empty_rooms() {
+        let main_layout = layout_with_users();
+
+        assert_eq!(
+            main_layout.connected_list.get_room_user_counts(),
+            vec![
+                (String::from("Lobby"), 2),
+                (String::from("Gaming"), 3),
+                (String::from("Empty"), 0),
+            ]
+        );
+        assert_eq!(main_layout.connected_count_text, 5);
+    }
+
+    #[test]
+    fn room_user_counts_text_skips_empty_rooms() {
+        let main_layout = layout_with_users();
+
+        assert_eq!(
+            main_layout.get_room_user_counts_text(),
+            "Lobby: 2, Gaming: 3"
+        );
+    }
+
+    #[test]
+    fn room_user_counts_follow_user_changes() {
+        let localization = user_count_localization();
+        let mut main_layout = layout_with_users();
+
+        // only other rooms are changed (no sounds are played)
+        main_layout.move_user("user4", "Empty").unwrap();
+        assert_eq!(
+            main_layout.get_room_user_counts_text(),
+            "Lobby: 2, Gaming: 2, Empty: 1"
+        );
+
+        main_layout.remove_user("user2", &localization).unwrap();
+        assert_eq!(
+            main_layout.get_room_user_counts_text(),
+            "Lobby: 2, Gaming: 1, Empty: 1"
+        );
+
+        main_layout.move_user("user3", "Empty").unwrap();
+        assert_eq!(
+            main_layout.get_room_user_counts_text(),
+            "Lobby: 2, Empty: 2"
+        );
+
+        main_layout.add_room(String::from("New"));
+        assert_eq!(main_layout.room_user_counts.len(), 4);
+        assert_eq!(
+            main_layout.get_room_user_counts_text(),
+            "Lobby: 2, Empty: 2"
+        );
+
+        main_layout.clear_all_users();
+        assert_eq!(main_layout.get_room_user_counts_text(), "");
+    }
+
+    #[test]
+    fn user_status_message_is_formatted() {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_MESSAGE_USER_IS_AWAY_TEXT),
+            String::from("is away"),
+        );
+
+        assert_eq!(
+            MainLayout::format_user_status_message("user1", "back in 5", &Arc::new(localization)),
+            "[user1] is away: back in 5"
+        );
+    }
+
+    #[test]
+    fn only_newly_joined_users_have_new_badge() {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_MESSAGE_USER_CONNECTED_TEXT),
+            String::from("connected"),
+        );
+        let localization = Arc::new(localization);
+
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+        // not in the lobby so no sound is played for the new user
+        main_layout.current_user_room = String::from("Gaming");
+        main_layout.add_room(String::from("Lobby"));
+        main_layout.add_room(String::from("Gaming"));
+
+        // received on connect
+        main_layout
+            .add_user(
+                String::from("user1"),
+                String::from("Lobby"),
+                0,
+                true,
+                &localization,
+            )
+            .unwrap();
+        // joined after we connected
+        main_layout
+            .add_user(
+                String::from("user2"),
+                String::from("Lobby"),
+                0,
+                false,
+                &localization,
+            )
+            .unwrap();
+
+        assert_eq!(
+            main_layout.connected_list.is_new_badge_shown("user1"),
+            Some(false)
+        );
+        assert_eq!(
+            main_layout.connected_list.is_new_badge_shown("user2"),
+            Some(true)
+        );
+    }
+
+    fn tick_voice_session(main_layout: &mut MainLayout, seconds: u64) {
+        for _ in 0..seconds {
+            assert!(main_layout.on_voice_session_tick());
+        }
+    }
+
+    #[test]
+    fn simultaneous_talkers_are_counted_once_per_second() {
+        let mut main_layout = layout_with_users();
+
+        main_layout.set_user_talking("user1", true);
+        tick_voice_session(&mut main_layout, 2);
+
+        // other users start talking too
+        main_layout.set_user_talking("user2", true);
+        main_layout.set_user_talking("user3", true);
+        assert_eq!(main_layout.talking_user_count, 3);
+        tick_voice_session(&mut main_layout, 3);
+
+        main_layout.set_user_talking("user1", false);
+        main_layout.set_user_talking("user2", false);
+        tick_voice_session(&mut main_layout, 1);
+
+        main_layout.set_user_talking("user3", false);
+        assert_eq!(main_layout.talking_user_count, 0);
+        assert!(!main_layout.on_voice_session_tick());
+
+        assert_eq!(main_layout.session_voice_seconds, 6);
+    }
+
+    #[test]
+    fn repeated_talk_events_are_not_double_counted() {
+        let mut main_layout = layout_with_users();
+
+        main_layout.set_user_talking("user1", true);
+        main_layout.set_user_talking("user1", true);
+        assert_eq!(main_layout.talking_user_count, 1);
+
+        main_layout.set_user_talking("user1", false);
+        main_layout.set_user_talking("user1", false);
+        assert_eq!(main_layout.talking_user_count, 0);
+
+        // unknown user
+        main_layout.set_user_talking("user5", true);
+        assert_eq!(main_layout.talking_user_count, 0);
+    }
+
+    #[test]
+    fn own_voice_is_not_counted() {
+        let mut main_layout = layout_with_users();
+
+        main_layout.set_user_talking("me", true);
+
+        assert_eq!(main_layout.talking_user_count, 0);
+        assert!(!main_layout.on_voice_session_tick());
+        assert_eq!(main_layout.session_voice_seconds, 0);
+    }
+
+    #[test]
+    fn talking_user_leaves() {
+        let localization = user_count_localization();
+        let mut main_layout = layout_with_users();
+        main_layout.set_user_talking("user2", true);
+        main_layout.set_user_talking("user3", true);
+
+        main_layout.remove_user("user2", &localization).unwrap();
+        assert_eq!(main_layout.talking_user_count, 1);
+
+        // a silent user leaves
+        main_layout.remove_user("user4", &localization).unwrap();
+        assert_eq!(main_layout.talking_user_count, 1);
+        tick_voice_session(&mut main_layout, 1);
+
+        main_layout.clear_all_users();
+        assert_eq!(main_layout.talking_user_count, 0);
+        assert_eq!(main_layout.session_voice_seconds, 0);
+    }
+
+    #[test]
+    fn voice_duration_is_formatted_in_hours_and_minutes() {
+        assert_eq!(MainLayout::format_voice_duration(0), "0h 0m");
+        assert_eq!(MainLayout::format_voice_duration(59), "0h 0m");
+        assert_eq!(MainLayout::format_voice_duration(60), "0h 1m");
+        assert_eq!(MainLayout::format_voice_duration(3599), "0h 59m");
+        assert_eq!(MainLayout::format_voice_duration(3600), "1h 0m");
+        assert_eq!(MainLayout::format_voice_duration(36000 + 61), "10h 1m");
+    }
+
+    #[test]
+    fn compact_user_list_toggle_is_saved_to_config() {
+        let mut data = crate::tests::test_application_state();
+        let refresh_ui = data.main_layout.connected_list.refresh_ui;
+
+        MainLayout::toggle_compact_user_list(&mut data);
+
+        assert!(data.main_layout.connected_list.is_compact);
+        // rebuilt right away
+        assert_ne!(data.main_layout.connected_list.refresh_ui, refresh_ui);
+        {
+            let config_guard = data.user_config.lock().unwrap();
+            assert!(config_guard.compact_user_list);
+            assert!(config_guard.is_dirty());
+        }
+
+        MainLayout::toggle_compact_user_list(&mut data);
+
+        assert!(!data.main_layout.connected_list.is_compact);
+        assert_eq!(data.main_layout.connected_list.refresh_ui, refresh_ui);
+        assert!(!data.user_config.lock().unwrap().compact_user_list);
+    }
+
+    fn receive_message(main_layout: &mut MainLayout, author: &str, is_chat_visible: bool) {
+        main_layout.add_message(
+            String::from("hello"),
+            String::from(author),
+            0,
+            false,
+            is_chat_visible,
+        );
+    }
+
+    #[test]
+    fn messages_received_while_chat_is_hidden_are_unread() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+
+        receive_message(&mut main_layout, "user1", false);
+        receive_message(&mut main_layout, "user2", false);
+
+        assert_eq!(main_layout.chat_list.unread_count, 2);
+    }
+
+    #[test]
+    fn own_and_visible_messages_are_not_unread() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+
+        receive_message(&mut main_layout, "me", false);
+        receive_message(&mut main_layout, "user1", true);
+
+        assert_eq!(main_layout.chat_list.unread_count, 0);
+    }
+
+    #[test]
+    fn unread_count_is_reset_when_chat_is_shown() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+        receive_message(&mut main_layout, "user1", false);
+
+        // the mouse is moved over the chat
+        main_layout.chat_list.clear_unread_count();
+        assert_eq!(main_layout.chat_list.unread_count, 0);
+
+        receive_message(&mut main_layout, "user1", true);
+        assert_eq!(main_layout.chat_list.unread_count, 0);
+
+        receive_message(&mut main_layout, "user1", false);
+        assert_eq!(main_layout.chat_list.unread_count, 1);
+    }
+
+    #[test]
+    fn unread_count_is_reset_on_disconnect() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+        receive_message(&mut main_layout, "user1", false);
+
+        main_layout.clear_text_chat();
+
+        assert_eq!(main_layout.chat_list.unread_count, 0);
+    }
+
+    /// Returns the state of a user in the "Gaming" room with a not connected 'NetService'
+    /// (so an entered room is seen as a system error in the chat).
+    fn state_with_room_invite() -> (ApplicationState, mpsc::Receiver<SubmittedCommand>) {
+        let mut data = crate::tests::test_application_state();
+        data.main_layout.current_user_room = String::from("Gaming");
+
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_MESSAGE_SYSTEM_ERROR_TEXT),
+            String::from("System error"),
+        );
+        data.localization = Arc::new(localization);
+
+        {
+            let mut user_config = data.user_config.lock().unwrap();
+            user_config.room_change_same_tier_cooldown_sec = 0;
+            user_config.room_change_lobby_cooldown_sec = 0;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let mut net_service = data.network_service.lock().unwrap();
+        net_service.event_sink = Some(EventSink::Channel(sender));
+        net_service.audio_service = Some(Arc::clone(&data.audio_service));
+        drop(net_service);
+
+        (data, receiver)
+    }
+
+    fn is_room_entered(data: &ApplicationState) -> bool {
+        get_chat_messages(&data.main_layout)
+            .iter()
+            .any(|message| message.starts_with("System error: "))
+    }
+
+    #[test]
+    fn accepted_room_invite_enters_room() {
+        let (mut data, _receiver) = state_with_room_invite();
+        let message_id = data
+            .main_layout
+            .chat_list
+            .add_room_invite(String::from("invite"), String::from("Music"));
+
+        MainLayout::accept_room_invite(&mut data, message_id);
+
+        assert!(is_room_entered(&data));
+        assert_eq!(
+            data.main_layout.chat_list.close_room_invite(message_id),
+            None
+        );
+    }
+
+    #[test]
+    fn declined_room_invite_does_not_enter_room() {
+        let (mut data, _receiver) = state_with_room_invite();
+        let message_id = data
+            .main_layout
+            .chat_list
+            .add_room_invite(String::from("invite"), String::from("Music"));
+        data.main_layout.chat_list.close_room_invite(message_id);
+
+        MainLayout::accept_room_invite(&mut data, message_id);
+
+        assert!(!is_room_entered(&data));
+    }
+
+    #[test]
+    fn room_invite_to_current_room_does_nothing() {
+        let (mut data, _receiver) = state_with_room_invite();
+        let message_id = data
+            .main_layout
+            .chat_list
+            .add_room_invite(String::from("invite"), String::from("Gaming"));
+
+        MainLayout::accept_room_invite(&mut data, message_id);
+
+        assert!(!is_room_entered(&data));
+    }
+
+    fn get_user_ping(main_layout: &MainLayout, username: &str) -> u16 {
+        for room in main_layout.connected_list.rooms.lock().unwrap().iter() {
+            if let Some(user) = room
+                .users
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|user| user.user_data.username == username)
+            {
+                return user.user_data.ping_ms;
+            }
+        }
+
+        panic!("user '{}' not found", username);
+    }
+
+    #[test]
+    fn own_ping_is_shown_in_connected_list() {
+        let mut main_layout = layout_with_users();
+
+        assert!(main_layout.set_user_ping("me", 12).is_ok());
+        assert!(main_layout.set_user_ping("user2", 40).is_ok());
+
+        assert_eq!(get_user_ping(&main_layout, "me"), 12);
+        assert_eq!(get_user_ping(&main_layout, "user2"), 40);
+        assert_eq!(get_user_ping(&main_layout, "user1"), 0);
+    }
+
+    #[test]
+    fn own_ping_before_user_list_is_ignored() {
+        let mut main_layout = MainLayout::new();
+        main_layout.current_user_name = String::from("me");
+
+        // the ping check came before the list of users
+        assert!(main_layout.set_user_ping("me", 12).is_ok());
+        // but other users should be in the list
+        assert!(main_layout.set_user_ping("user1", 40).is_err());
+    }
+}
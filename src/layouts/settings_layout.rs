@@ -1,26 +1,54 @@
 // External.
 use druid::widget::prelude::*;
 use druid::widget::{
-    Button, Container, CrossAxisAlignment, Flex, Label, LineBreaking, MainAxisAlignment, Padding,
-    Scroll, SizedBox, Slider, ViewSwitcher,
+    Button, Container, CrossAxisAlignment, Either, EnvScope, Flex, Label, LineBreaking,
+    MainAxisAlignment, Padding, ProgressBar, RadioGroup, Scroll, SizedBox, Slider, TextBox,
+    ViewSwitcher,
+};
+use druid::{
+    commands, Color, Data, FileDialogOptions, FileInfo, FileSpec, Lens, LensExt, Selector, Target,
+    WidgetExt,
 };
-use druid::{Color, Data, Lens, LensExt, Selector, Target, WidgetExt};
 use rdev::{listen, EventType};
 use system_wide_key_state::*;
+use tracing::{error, warn};
 
 // Std.
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
 use std::thread;
 
 // Custom.
-use crate::misc::{custom_slider_controller::*, locale_keys::*};
+use crate::layouts::connect_layout::CONNECT_LAYOUT_PREFETCH_SERVER_ADDRESS;
+use crate::misc::{
+    config_validator_ui::*, custom_slider_controller::*, data_changed_controller::*,
+    input_level_controller::*, locale_keys::*,
+};
+use crate::services::audio_service::agc::AGC_DEFAULT_TARGET_RMS;
+use crate::services::audio_service::audio_service::{AudioService, SAMPLES_IN_CHUNK, SAMPLE_RATE};
+use crate::services::audio_service::microphone_boost::*;
+use crate::services::audio_service::silence_detector::SILENCE_DEFAULT_THRESHOLD;
+use crate::services::audio_service::voice_activity_detector::*;
+use crate::services::config_service::{ConfigField, UserConfig};
+use crate::services::connection_log::*;
+use crate::services::network_interfaces::*;
+use crate::services::update_checker::*;
 use crate::services::user_tcp_service::ConnectResult;
 use crate::theme::*;
+use crate::widgets::confirm_dialog::ConfirmDialog;
 use crate::ApplicationState;
 use crate::CustomSliderID;
 use crate::{global_params::*, Layout};
 
 pub const PUSH_TO_TALK_KEY_CHANGE_EVENT: Selector<String> =
     Selector::new("settings_push_to_talk_key_change_event");
+pub const SETTINGS_UPDATE_CHECK_RESULT: Selector<Result<UpdateCheckResult, String>> =
+    Selector::new("settings_update_check_result");
+pub const SETTINGS_PTT_PRESS_SOUND_SELECTED: Selector<FileInfo> =
+    Selector::new("settings_ptt_press_sound_selected");
+pub const SETTINGS_PTT_RELEASE_SOUND_SELECTED: Selector<FileInfo> =
+    Selector::new("settings_ptt_release_sound_selected");
 
 #[derive(Data, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
 pub enum SupportedLocale {
@@ -31,17 +59,74 @@ pub enum SupportedLocale {
 #[derive(Clone, Data, PartialEq)]
 pub enum ActiveOption {
     General,
+    History,
     About,
 }
 
+// Settings that are changed with "-" and "+" buttons.
+#[derive(Clone, Copy)]
+enum StepperSetting {
+    MessageCooldown,
+    RoomChangeSameTierCooldown,
+    RoomChangeLobbyCooldown,
+    IdleTimeout,
+    UdpHealthCheckTimeout,
+}
+
 #[derive(Clone, Data, Lens)]
 pub struct SettingsLayout {
     pub active_option: ActiveOption,
     pub show_message_notification: bool,
     pub master_volume: f64,
     pub microphone_volume: f64,
+    pub microphone_boost_db: f64,
+    pub agc_enabled: bool,
+    pub agc_target_level: f64, // in percent of the maximum amplitude
+    pub ptt_release_delay_ms: f64,
+    pub aec_enabled: bool,
+    pub silence_suppression_enabled: bool,
+    pub silence_threshold: f64, // in percent of the maximum amplitude
+    pub aec_delay_ms: f64,
+    pub pause_on_minimize: bool,
+    pub start_fullscreen: bool,
+    pub message_cooldown_sec: u8,
+    pub room_change_same_tier_cooldown_sec: u8,
+    pub room_change_lobby_cooldown_sec: u8,
+    pub udp_health_check_enabled: bool,
+    pub udp_health_check_timeout_sec: u8,
+    pub idle_timeout_min: u8,
+    pub auto_away_message: String, // saved when leaving the settings
+    pub show_inline_images: bool,
+    pub chat_max_messages: usize,
+    pub spatial_audio_enabled: bool,
+    pub ptt_press_sound: String,
+    pub ptt_release_sound: String,
+    pub microphone_channels: u8,
+    pub push_to_talk_mode: PushToTalkMode,
+    pub vad_sensitivity: f64,
+    pub audio_play_buffer_chunks: f64,
+    pub audio_record_buffer_chunks: f64,
+    pub audio_input_device: String, // empty to use the system default
+    pub available_audio_input_devices: Rc<Vec<String>>, // updated when the settings are opened
+    pub input_level: f64,           // microphone peak level, 1.0 is 0 dBFS (above if clipping)
+    pub accessibility_mode: bool,
+    pub is_advanced_expanded: bool,
+    pub udp_recv_buffer_kb: u32,
+    pub preferred_network_interface: String, // empty to bind to all interfaces
+    pub available_network_interfaces: Rc<Vec<String>>, // updated when the settings are opened
+    pub connect_on_launch: bool,
+    pub auto_record: bool,
     pub push_to_talk_key_text: String,
     pub selected_locale: SupportedLocale,
+    pub selected_theme: String,            // empty for the default theme
+    pub available_themes: Rc<Vec<String>>, // found on startup
+    pub restore_defaults_confirmation: bool,
+    pub config_errors: Rc<Vec<String>>, // shown when closing the settings with invalid values
+    pub is_fixing_config: bool,         // highlight invalid values
+    pub update_check_text: String,
+    pub is_update_available: bool,
+    pub is_checking_for_updates: bool,
+    pub connection_history: Rc<Vec<ConnectionEvent>>, // read when the history section is opened
     #[data(ignore)]
     pub push_to_talk_keycode: KeyCode,
 }
@@ -52,11 +137,131 @@ impl SettingsLayout {
             active_option: ActiveOption::General,
             master_volume: 100.0,
             microphone_volume: 100.0,
+            microphone_boost_db: 0.0,
+            agc_enabled: false,
+            agc_target_level: (AGC_DEFAULT_TARGET_RMS * 100.0) as f64,
+            ptt_release_delay_ms: DEFAULT_PTT_RELEASE_DELAY_MS as f64,
+            aec_enabled: false,
+            silence_suppression_enabled: false,
+            silence_threshold: (SILENCE_DEFAULT_THRESHOLD * 100.0) as f64,
+            aec_delay_ms: DEFAULT_AEC_DELAY_MS as f64,
+            pause_on_minimize: false,
+            start_fullscreen: false,
+            message_cooldown_sec: DEFAULT_MESSAGE_COOLDOWN_SEC,
+            room_change_same_tier_cooldown_sec: DEFAULT_ROOM_CHANGE_SAME_TIER_COOLDOWN_SEC,
+            room_change_lobby_cooldown_sec: DEFAULT_ROOM_CHANGE_LOBBY_COOLDOWN_SEC,
+            udp_health_check_enabled: true,
+            udp_health_check_timeout_sec: DEFAULT_UDP_HEALTH_CHECK_TIMEOUT_SEC,
+            idle_timeout_min: DEFAULT_IDLE_TIMEOUT_MIN,
+            auto_away_message: String::from(DEFAULT_AUTO_AWAY_MESSAGE),
+            show_inline_images: true,
+            chat_max_messages: DEFAULT_CHAT_MAX_MESSAGES,
+            spatial_audio_enabled: false,
+            ptt_press_sound: String::from(PUSH_TO_TALK_PRESS_SOUND),
+            ptt_release_sound: String::from(PUSH_TO_TALK_UNPRESS_SOUND),
+            microphone_channels: DEFAULT_MICROPHONE_CHANNELS,
+            push_to_talk_mode: PushToTalkMode::HoldKey,
+            vad_sensitivity: VAD_DEFAULT_SENSITIVITY as f64,
+            audio_play_buffer_chunks: DEFAULT_AUDIO_PLAY_BUFFER_CHUNKS as f64,
+            audio_record_buffer_chunks: DEFAULT_AUDIO_RECORD_BUFFER_CHUNKS as f64,
+            audio_input_device: String::new(),
+            available_audio_input_devices: Rc::new(Vec::new()),
+            input_level: 0.0,
+            accessibility_mode: false,
+            is_advanced_expanded: false,
+            udp_recv_buffer_kb: DEFAULT_UDP_RECV_BUFFER_KB,
+            connect_on_launch: false,
+            auto_record: false,
             push_to_talk_key_text: "T".to_string(),
             push_to_talk_keycode: KeyCode::KT,
             show_message_notification: true,
             selected_locale: SupportedLocale::En,
+            selected_theme: String::new(),
+            available_themes: Rc::new(Vec::new()),
+            preferred_network_interface: String::new(),
+            available_network_interfaces: Rc::new(Vec::new()),
+            restore_defaults_confirmation: false,
+            config_errors: Rc::new(Vec::new()),
+            is_fixing_config: false,
+            update_check_text: String::new(),
+            is_update_available: false,
+            is_checking_for_updates: false,
+            connection_history: Rc::new(Vec::new()),
+        }
+    }
+    pub fn read_user_config(&mut self, config: &UserConfig) {
+        self.master_volume = config.master_volume as f64;
+        self.microphone_volume = config.microphone_volume as f64;
+        self.microphone_boost_db = config.microphone_boost_db as f64;
+        self.push_to_talk_key_text = get_key_name(config.push_to_talk_button);
+        self.push_to_talk_keycode = config.push_to_talk_button;
+        self.show_message_notification = config.show_message_notification;
+        self.agc_enabled = config.agc_enabled;
+        self.agc_target_level = (config.agc_target_rms * 100.0) as f64;
+        self.ptt_release_delay_ms = config.ptt_release_delay_ms as f64;
+        self.aec_enabled = config.aec_enabled;
+        self.silence_suppression_enabled = config.silence_suppression_enabled;
+        self.silence_threshold = (config.silence_threshold * 100.0) as f64;
+        self.spatial_audio_enabled = config.spatial_audio_enabled;
+        self.ptt_press_sound = config.ptt_press_sound.clone();
+        self.ptt_release_sound = config.ptt_release_sound.clone();
+        self.microphone_channels = config.microphone_channels;
+        self.push_to_talk_mode = config.push_to_talk_mode;
+        self.vad_sensitivity = config.vad_sensitivity as f64;
+        self.audio_play_buffer_chunks = config.audio_play_buffer_chunks as f64;
+        self.audio_record_buffer_chunks = config.audio_record_buffer_chunks as f64;
+        self.audio_input_device = config.audio_input_device.clone();
+        self.aec_delay_ms = config.aec_delay_ms as f64;
+        self.pause_on_minimize = config.pause_on_minimize;
+        self.start_fullscreen = config.start_fullscreen;
+        self.message_cooldown_sec = config.message_cooldown_sec;
+        self.room_change_same_tier_cooldown_sec = config.room_change_same_tier_cooldown_sec;
+        self.room_change_lobby_cooldown_sec = config.room_change_lobby_cooldown_sec;
+        self.udp_health_check_enabled = config.udp_health_check_enabled;
+        self.connect_on_launch = config.connect_on_launch;
+        self.auto_record = config.auto_record;
+        self.udp_health_check_timeout_sec = config.udp_health_check_timeout_sec;
+        self.idle_timeout_min = config.idle_timeout_min;
+        self.auto_away_message = config.auto_away_message.clone();
+        self.show_inline_images = config.show_inline_images;
+        self.chat_max_messages = config.chat_max_messages;
+        self.accessibility_mode = config.accessibility_mode;
+        self.is_advanced_expanded = config.advanced_settings_expanded;
+        self.udp_recv_buffer_kb = config.udp_recv_buffer_kb;
+        self.preferred_network_interface = config.preferred_network_interface.clone();
+        self.selected_theme = config.theme_name.clone();
+        if config.locale == "en" {
+            self.selected_locale = SupportedLocale::En;
+        } else if config.locale == "ru" {
+            self.selected_locale = SupportedLocale::Ru;
+        }
+    }
+    /// Opens the settings, the "back" button returns to the current layout.
+    pub fn open(data: &mut ApplicationState) {
+        data.previous_layout = data.current_layout;
+        data.current_layout = Layout::Settings;
+
+        // adapters (VPN, etc.) might appear or disappear while the app is running
+        match get_network_interfaces() {
+            Ok(interfaces) => {
+                data.settings_layout.available_network_interfaces =
+                    Rc::new(get_network_interface_names(&interfaces));
+            }
+            Err(msg) => {
+                warn!("{}", msg);
+                data.settings_layout.available_network_interfaces = Rc::new(Vec::new());
+            }
         }
+
+        // microphones might be plugged in or out
+        data.settings_layout.available_audio_input_devices =
+            Rc::new(AudioService::list_audio_input_devices());
+
+        // show the microphone level even if the push-to-talk button is not pressed
+        data.audio_service
+            .lock()
+            .unwrap()
+            .start_input_level_monitor();
     }
     pub fn build_ui() -> impl Widget<ApplicationState> {
         let mut active_option_content = Flex::column()
@@ -79,6 +284,24 @@ impl SettingsLayout {
                         )
                         .with_flex_child(SizedBox::empty().expand(), 5.0)
                         .with_flex_child(Container::new(SizedBox::empty().expand()).expand(), 10.0)
+                        .with_flex_child(SizedBox::empty().expand(), 5.0)
+                        .with_flex_child(Container::new(SizedBox::empty().expand()).expand(), 10.0)
+                        .expand(),
+                ),
+                &ActiveOption::History => Box::new(
+                    Flex::column()
+                        .must_fill_main_axis(true)
+                        .main_axis_alignment(MainAxisAlignment::Center)
+                        .with_flex_child(Container::new(SizedBox::empty().expand()).expand(), 10.0)
+                        .with_flex_child(SizedBox::empty().expand(), 5.0)
+                        .with_flex_child(
+                            Container::new(SizedBox::empty().expand())
+                                .background(BACKGROUND_SPECIAL_COLOR)
+                                .expand(),
+                            10.0,
+                        )
+                        .with_flex_child(SizedBox::empty().expand(), 5.0)
+                        .with_flex_child(Container::new(SizedBox::empty().expand()).expand(), 10.0)
                         .expand(),
                 ),
                 &ActiveOption::About => Box::new(
@@ -87,6 +310,8 @@ impl SettingsLayout {
                         .main_axis_alignment(MainAxisAlignment::Center)
                         .with_flex_child(Container::new(SizedBox::empty().expand()).expand(), 10.0)
                         .with_flex_child(SizedBox::empty().expand(), 5.0)
+                        .with_flex_child(Container::new(SizedBox::empty().expand()).expand(), 10.0)
+                        .with_flex_child(SizedBox::empty().expand(), 5.0)
                         .with_flex_child(
                             Container::new(SizedBox::empty().expand())
                                 .background(BACKGROUND_SPECIAL_COLOR)
@@ -98,8 +323,8 @@ impl SettingsLayout {
             },
         );
 
-        active_option_content.add_flex_child(res, 25.0);
-        active_option_content.add_flex_child(SizedBox::empty().expand(), 45.0);
+        active_option_content.add_flex_child(res, 40.0);
+        active_option_content.add_flex_child(SizedBox::empty().expand(), 30.0);
         // for back button
         active_option_content.add_flex_child(SizedBox::empty().expand(), 10.0);
         active_option_content.add_flex_child(SizedBox::empty().expand(), 10.0);
@@ -127,6 +352,21 @@ impl SettingsLayout {
                             10.0,
                         )
                         .with_flex_child(SizedBox::empty().expand(), 5.0)
+                        .with_flex_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_HISTORY_SECTION_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_history_button_clicked)
+                            .expand(),
+                            10.0,
+                        )
+                        .with_flex_child(SizedBox::empty().expand(), 5.0)
                         .with_flex_child(
                             Button::from_label(
                                 Label::new(|data: &ApplicationState, _env: &Env| {
@@ -141,7 +381,7 @@ impl SettingsLayout {
                             .expand(),
                             10.0,
                         )
-                        .with_flex_child(SizedBox::empty().expand(), 45.0)
+                        .with_flex_child(SizedBox::empty().expand(), 30.0)
                         .with_flex_child(
                             Button::from_label(
                                 Label::new(|data: &ApplicationState, _env: &Env| {
@@ -168,6 +408,7 @@ impl SettingsLayout {
                     .must_fill_main_axis(true)
                     .main_axis_alignment(MainAxisAlignment::Center)
                     .with_flex_child(SizedBox::empty().expand(), 5.0)
+                    .with_child(ConfigValidatorUi::build_summary_ui())
                     .with_flex_child(
                         Container::new(ViewSwitcher::new(
                             |data: &ApplicationState, _env| {
@@ -177,6 +418,9 @@ impl SettingsLayout {
                                 &ActiveOption::General => {
                                     Box::new(SettingsLayout::get_general_content())
                                 }
+                                &ActiveOption::History => {
+                                    Box::new(SettingsLayout::get_history_content())
+                                }
                                 &ActiveOption::About => {
                                     Box::new(SettingsLayout::get_about_content())
                                 }
@@ -195,6 +439,56 @@ impl SettingsLayout {
     fn on_general_button_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
         data.settings_layout.active_option = ActiveOption::General;
     }
+    fn on_history_button_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        // finish changing push-to-talk button if it was pressed
+        ctx.get_external_handle()
+            .submit_command(PUSH_TO_TALK_KEY_CHANGE_EVENT, String::new(), Target::Auto)
+            .expect("failed to submit PUSH_TO_TALK_KEY_CHANGE_EVENT command");
+
+        match ConnectionLog::open() {
+            Ok(log) => data.settings_layout.connection_history = Rc::new(log.events),
+            Err(msg) => {
+                let error_msg = format!("{} at [{}, {}]", msg, file!(), line!());
+                if !data.is_connected {
+                    data.connect_layout
+                        .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
+                } else {
+                    data.main_layout.add_system_message(error_msg);
+                }
+            }
+        }
+
+        data.settings_layout.active_option = ActiveOption::History;
+    }
+    fn on_clear_history_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        let log = ConnectionLog::open();
+        if let Err(err) = log {
+            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+            if !data.is_connected {
+                data.connect_layout
+                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
+            } else {
+                data.main_layout.add_system_message(error_msg);
+            }
+            return;
+        }
+        let mut log = log.unwrap();
+
+        log.clear();
+
+        if let Err(err) = log.save() {
+            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+            if !data.is_connected {
+                data.connect_layout
+                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
+            } else {
+                data.main_layout.add_system_message(error_msg);
+            }
+            return;
+        }
+
+        data.settings_layout.connection_history = Rc::new(log.events);
+    }
     fn on_about_button_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
         // finish changing push-to-talk button if it was pressed
         ctx.get_external_handle()
@@ -211,8 +505,27 @@ impl SettingsLayout {
             SupportedLocale::Ru => config_guard.locale = String::from("ru"),
         }
 
-        if let Err(err) = config_guard.save() {
-            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+        config_guard.mark_dirty();
+    }
+    /// Called by the delegate when the user selected a push-to-talk sound file.
+    pub fn on_ptt_sound_file_selected(
+        data: &mut ApplicationState,
+        file_info: &FileInfo,
+        is_press_sound: bool,
+    ) {
+        let default_path = if is_press_sound {
+            PUSH_TO_TALK_PRESS_SOUND
+        } else {
+            PUSH_TO_TALK_UNPRESS_SOUND
+        };
+
+        let selected_path = file_info.path().to_string_lossy().to_string();
+        if !AudioService::is_sound_file_valid(&selected_path) {
+            let error_msg = data
+                .localization
+                .get(LOCALE_SETTINGS_LAYOUT_INVALID_SOUND_FILE_TEXT)
+                .unwrap()
+                .clone();
             if !data.is_connected {
                 data.connect_layout
                     .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
@@ -220,6 +533,69 @@ impl SettingsLayout {
                 data.main_layout.add_system_message(error_msg);
             }
         }
+        let sound_path = AudioService::get_valid_sound_path(&selected_path, default_path);
+
+        // Apply.
+        {
+            let mut audio_service_guard = data.audio_service.lock().unwrap();
+            if is_press_sound {
+                data.settings_layout.ptt_press_sound = sound_path.clone();
+                audio_service_guard.ptt_press_sound = sound_path.clone();
+            } else {
+                data.settings_layout.ptt_release_sound = sound_path.clone();
+                audio_service_guard.ptt_release_sound = sound_path.clone();
+            }
+        }
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        if is_press_sound {
+            config_guard.ptt_press_sound = sound_path;
+        } else {
+            config_guard.ptt_release_sound = sound_path;
+        }
+
+        config_guard.mark_dirty();
+    }
+    fn on_microphone_channels_changed(data: &mut ApplicationState) {
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().microphone_channels =
+            data.settings_layout.microphone_channels;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.microphone_channels = data.settings_layout.microphone_channels;
+
+        config_guard.mark_dirty();
+    }
+    fn on_push_to_talk_mode_changed(data: &mut ApplicationState) {
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().push_to_talk_mode =
+            data.settings_layout.push_to_talk_mode;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.push_to_talk_mode = data.settings_layout.push_to_talk_mode;
+
+        config_guard.mark_dirty();
+    }
+    fn save_auto_away_message(data: &mut ApplicationState) {
+        let auto_away_message: String = data
+            .settings_layout
+            .auto_away_message
+            .trim()
+            .chars()
+            .take(MAX_MESSAGE_SIZE)
+            .collect();
+        data.settings_layout.auto_away_message = auto_away_message.clone();
+
+        let mut config_guard = data.user_config.lock().unwrap();
+        if config_guard.auto_away_message == auto_away_message {
+            return;
+        }
+        config_guard.auto_away_message = auto_away_message;
+
+        config_guard.mark_dirty();
     }
     fn on_back_button_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
         // finish changing push-to-talk button if it was pressed
@@ -227,10 +603,33 @@ impl SettingsLayout {
             .submit_command(PUSH_TO_TALK_KEY_CHANGE_EVENT, String::new(), Target::Auto)
             .expect("failed to submit PUSH_TO_TALK_KEY_CHANGE_EVENT command");
 
-        if data.is_connected {
-            data.current_layout = Layout::Main;
+        SettingsLayout::save_auto_away_message(data);
+
+        if !ConfigValidatorUi::check_before_close(data) {
+            return;
+        }
+
+        SettingsLayout::close(ctx, data);
+    }
+    /// Returns to the layout that was shown before the settings were opened.
+    pub fn close(ctx: &mut EventCtx, data: &mut ApplicationState) {
+        data.audio_service
+            .lock()
+            .unwrap()
+            .stop_input_level_monitor();
+        data.settings_layout.input_level = 0.0;
+
+        data.current_layout = SettingsLayout::get_layout_to_return_to(data);
+        if data.current_layout == Layout::Connect {
+            ctx.submit_command(CONNECT_LAYOUT_PREFETCH_SERVER_ADDRESS);
+        }
+    }
+    pub fn get_layout_to_return_to(data: &ApplicationState) -> Layout {
+        // we might have been disconnected while the settings were opened
+        if data.previous_layout == Layout::Main && data.is_connected {
+            Layout::Main
         } else {
-            data.current_layout = Layout::Connect;
+            Layout::Connect
         }
     }
     fn on_show_message_notification_clicked(
@@ -245,16 +644,299 @@ impl SettingsLayout {
         let mut config_guard = data.user_config.lock().unwrap();
         config_guard.show_message_notification = data.settings_layout.show_message_notification;
 
-        if let Err(err) = config_guard.save() {
-            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
-            if !data.is_connected {
-                data.connect_layout
-                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
-            } else {
-                data.main_layout.add_system_message(error_msg);
+        config_guard.mark_dirty();
+    }
+    fn on_agc_enabled_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.agc_enabled = !data.settings_layout.agc_enabled;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.agc_enabled = data.settings_layout.agc_enabled;
+
+        config_guard.mark_dirty();
+    }
+    fn on_aec_enabled_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.aec_enabled = !data.settings_layout.aec_enabled;
+
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().aec_enabled = data.settings_layout.aec_enabled;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.aec_enabled = data.settings_layout.aec_enabled;
+
+        config_guard.mark_dirty();
+    }
+    fn on_silence_suppression_enabled_clicked(
+        _ctx: &mut EventCtx,
+        data: &mut ApplicationState,
+        _env: &Env,
+    ) {
+        data.settings_layout.silence_suppression_enabled =
+            !data.settings_layout.silence_suppression_enabled;
+
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service
+            .lock()
+            .unwrap()
+            .silence_suppression_enabled = data.settings_layout.silence_suppression_enabled;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.silence_suppression_enabled = data.settings_layout.silence_suppression_enabled;
+
+        config_guard.mark_dirty();
+    }
+    fn on_udp_health_check_enabled_clicked(
+        _ctx: &mut EventCtx,
+        data: &mut ApplicationState,
+        _env: &Env,
+    ) {
+        data.settings_layout.udp_health_check_enabled =
+            !data.settings_layout.udp_health_check_enabled;
+
+        // Save to config (applied on the next connection).
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.udp_health_check_enabled = data.settings_layout.udp_health_check_enabled;
+
+        config_guard.mark_dirty();
+    }
+    fn on_pause_on_minimize_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.pause_on_minimize = !data.settings_layout.pause_on_minimize;
+
+        if !data.settings_layout.pause_on_minimize {
+            data.network_service
+                .lock()
+                .unwrap()
+                .resume_voice_transmission();
+        }
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.pause_on_minimize = data.settings_layout.pause_on_minimize;
+
+        config_guard.mark_dirty();
+    }
+    fn on_restore_defaults_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        // finish changing push-to-talk button if it was pressed
+        ctx.get_external_handle()
+            .submit_command(PUSH_TO_TALK_KEY_CHANGE_EVENT, String::new(), Target::Auto)
+            .expect("failed to submit PUSH_TO_TALK_KEY_CHANGE_EVENT command");
+
+        data.settings_layout.restore_defaults_confirmation = true;
+    }
+    fn on_restore_defaults_cancelled(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.restore_defaults_confirmation = false;
+    }
+    fn on_restore_defaults_confirmed(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.restore_defaults_confirmation = false;
+
+        // connection fields (from ConnectLayout) are kept
+        let (locale, pause_on_minimize) = {
+            let mut config_guard = data.user_config.lock().unwrap();
+            config_guard.restore_defaults();
+            config_guard.mark_dirty();
+
+            (config_guard.locale.clone(), config_guard.pause_on_minimize)
+        };
+
+        // apply the restored values like on start
+        // (theme, audio settings, chat and user list settings)
+        crate::apply_config(data);
+        crate::read_localization(&locale, data);
+
+        if !pause_on_minimize {
+            data.network_service
+                .lock()
+                .unwrap()
+                .resume_voice_transmission();
+        }
+    }
+    fn on_start_fullscreen_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.start_fullscreen = !data.settings_layout.start_fullscreen;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.start_fullscreen = data.settings_layout.start_fullscreen;
+
+        config_guard.mark_dirty();
+    }
+    fn get_stepper_value(data: &ApplicationState, setting: StepperSetting) -> u8 {
+        match setting {
+            StepperSetting::MessageCooldown => data.settings_layout.message_cooldown_sec,
+            StepperSetting::RoomChangeSameTierCooldown => {
+                data.settings_layout.room_change_same_tier_cooldown_sec
+            }
+            StepperSetting::RoomChangeLobbyCooldown => {
+                data.settings_layout.room_change_lobby_cooldown_sec
+            }
+            StepperSetting::IdleTimeout => data.settings_layout.idle_timeout_min,
+            StepperSetting::UdpHealthCheckTimeout => {
+                data.settings_layout.udp_health_check_timeout_sec
             }
         }
     }
+    /// Applies `delta` to the value (clamped to min..=max) and saves it to the config.
+    fn change_stepper_value(data: &mut ApplicationState, setting: StepperSetting, delta: i16) {
+        let (value, min, max) = match setting {
+            StepperSetting::MessageCooldown => (
+                &mut data.settings_layout.message_cooldown_sec,
+                0,
+                MAX_MESSAGE_COOLDOWN_SEC,
+            ),
+            StepperSetting::RoomChangeSameTierCooldown => (
+                &mut data.settings_layout.room_change_same_tier_cooldown_sec,
+                0,
+                MAX_ROOM_CHANGE_COOLDOWN_SEC,
+            ),
+            StepperSetting::RoomChangeLobbyCooldown => (
+                &mut data.settings_layout.room_change_lobby_cooldown_sec,
+                0,
+                MAX_ROOM_CHANGE_COOLDOWN_SEC,
+            ),
+            StepperSetting::IdleTimeout => (
+                &mut data.settings_layout.idle_timeout_min,
+                0,
+                MAX_IDLE_TIMEOUT_MIN,
+            ),
+            StepperSetting::UdpHealthCheckTimeout => (
+                &mut data.settings_layout.udp_health_check_timeout_sec,
+                MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC,
+                MAX_UDP_HEALTH_CHECK_TIMEOUT_SEC,
+            ),
+        };
+        *value = (*value as i16 + delta).max(min as i16).min(max as i16) as u8;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.message_cooldown_sec = data.settings_layout.message_cooldown_sec;
+        config_guard.room_change_same_tier_cooldown_sec =
+            data.settings_layout.room_change_same_tier_cooldown_sec;
+        config_guard.room_change_lobby_cooldown_sec =
+            data.settings_layout.room_change_lobby_cooldown_sec;
+        config_guard.idle_timeout_min = data.settings_layout.idle_timeout_min;
+        config_guard.udp_health_check_timeout_sec =
+            data.settings_layout.udp_health_check_timeout_sec;
+
+        config_guard.mark_dirty();
+    }
+    /// "Name:  [-] N units [+]", 0 is shown as "off".
+    fn build_stepper_row(
+        locale_key: &'static str,
+        setting: StepperSetting,
+    ) -> impl Widget<ApplicationState> {
+        let units_locale_key = match setting {
+            StepperSetting::MessageCooldown
+            | StepperSetting::RoomChangeSameTierCooldown
+            | StepperSetting::RoomChangeLobbyCooldown
+            | StepperSetting::UdpHealthCheckTimeout => LOCALE_SETTINGS_LAYOUT_SETTING_SECONDS_TEXT,
+            StepperSetting::IdleTimeout => LOCALE_SETTINGS_LAYOUT_SETTING_MINUTES_TEXT,
+        };
+        let config_field = match setting {
+            StepperSetting::MessageCooldown => ConfigField::MessageCooldown,
+            StepperSetting::RoomChangeSameTierCooldown => ConfigField::RoomChangeSameTierCooldown,
+            StepperSetting::RoomChangeLobbyCooldown => ConfigField::RoomChangeLobbyCooldown,
+            StepperSetting::IdleTimeout => ConfigField::IdleTimeout,
+            StepperSetting::UdpHealthCheckTimeout => ConfigField::UdpHealthCheckTimeout,
+        };
+
+        let stepper = Flex::row()
+            .with_child(
+                Label::new(move |data: &ApplicationState, _env: &Env| {
+                    format!("{}:  ", data.localization.get(locale_key).unwrap())
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Button::from_label(Label::new("-").with_text_size(TEXT_SIZE)).on_click(
+                    move |_ctx, data: &mut ApplicationState, _env| {
+                        SettingsLayout::change_stepper_value(data, setting, -1);
+                    },
+                ),
+            )
+            .with_default_spacer()
+            .with_child(
+                Label::new(move |data: &ApplicationState, _env: &Env| {
+                    let value = SettingsLayout::get_stepper_value(data, setting);
+                    if value == 0 {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                            .unwrap()
+                            .clone()
+                    } else {
+                        format!(
+                            "{} {}",
+                            value,
+                            data.localization.get(units_locale_key).unwrap()
+                        )
+                    }
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_default_spacer()
+            .with_child(
+                Button::from_label(Label::new("+").with_text_size(TEXT_SIZE)).on_click(
+                    move |_ctx, data: &mut ApplicationState, _env| {
+                        SettingsLayout::change_stepper_value(data, setting, 1);
+                    },
+                ),
+            );
+
+        ConfigValidatorUi::highlight_if_invalid(config_field, stepper)
+    }
+    fn on_show_inline_images_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.show_inline_images = !data.settings_layout.show_inline_images;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.show_inline_images = data.settings_layout.show_inline_images;
+
+        config_guard.mark_dirty();
+    }
+    fn on_spatial_audio_enabled_clicked(
+        _ctx: &mut EventCtx,
+        data: &mut ApplicationState,
+        _env: &Env,
+    ) {
+        data.settings_layout.spatial_audio_enabled = !data.settings_layout.spatial_audio_enabled;
+
+        // applied to the next voice message of each user
+        data.audio_service.lock().unwrap().spatial_audio_enabled =
+            data.settings_layout.spatial_audio_enabled;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.spatial_audio_enabled = data.settings_layout.spatial_audio_enabled;
+
+        config_guard.mark_dirty();
+    }
+    fn on_accessibility_mode_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.accessibility_mode = !data.settings_layout.accessibility_mode;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.accessibility_mode = data.settings_layout.accessibility_mode;
+
+        config_guard.mark_dirty();
+    }
+    fn on_connect_on_launch_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.connect_on_launch = !data.settings_layout.connect_on_launch;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.connect_on_launch = data.settings_layout.connect_on_launch;
+
+        config_guard.mark_dirty();
+    }
+    fn on_auto_record_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        data.settings_layout.auto_record = !data.settings_layout.auto_record;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.auto_record = data.settings_layout.auto_record;
+
+        config_guard.mark_dirty();
+    }
     fn on_push_to_talk_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
         data.settings_layout.push_to_talk_key_text = data
             .localization
@@ -286,7 +968,7 @@ impl SettingsLayout {
                         .expect("failed to submit PUSH_TO_TALK_KEY_CHANGE_EVENT command");
                 }
             }) {
-                println!("rdev listen error: {:?}", error);
+                error!("rdev listen error: {:?}", error);
             }
         });
     }
@@ -308,6 +990,27 @@ impl SettingsLayout {
                     .with_line_break_mode(LineBreaking::WordWrap)
                     .with_text_size(TEXT_SIZE),
                 )
+                .with_child(Either::new(
+                    |data: &ApplicationState, _env| {
+                        data.user_config.lock().unwrap().validate().is_err()
+                    },
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        match data.user_config.lock().unwrap().validate() {
+                            Ok(()) => String::new(),
+                            Err(errors) => format!(
+                                "{}:\n- {}",
+                                data.localization
+                                    .get(LOCALE_SETTINGS_LAYOUT_CONFIG_VALIDATION_ERRORS_TEXT)
+                                    .unwrap(),
+                                errors.join("\n- ")
+                            ),
+                        }
+                    })
+                    .with_text_color(Color::RED)
+                    .with_line_break_mode(LineBreaking::WordWrap)
+                    .with_text_size(TEXT_SIZE),
+                    SizedBox::empty(),
+                ))
                 .with_default_spacer()
                 .with_default_spacer()
                 .with_child(
@@ -323,17 +1026,23 @@ impl SettingsLayout {
                     Flex::row()
                         .must_fill_main_axis(true)
                         .with_flex_child(
-                            Slider::new()
-                                .with_step(1.0)
-                                .with_range(0.0, 100.0)
-                                .expand_width()
-                                .controller(CustomSliderController::new(
-                                    CustomSliderID::MasterVolumeSlider,
-                                ))
-                                .lens(
-                                    ApplicationState::settings_layout
-                                        .then(SettingsLayout::master_volume),
-                                ),
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::MasterVolume,
+                                Slider::new()
+                                    .with_step(1.0)
+                                    .with_range(0.0, 100.0)
+                                    .expand_width()
+                                    .controller(
+                                        CustomSliderController::new(
+                                            CustomSliderID::MasterVolumeSlider,
+                                        )
+                                        .with_aria_label(String::from("Master volume")),
+                                    )
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::master_volume),
+                                    ),
+                            ),
                             80.0,
                         )
                         .with_flex_child(
@@ -358,28 +1067,412 @@ impl SettingsLayout {
                     Flex::row()
                         .must_fill_main_axis(true)
                         .with_flex_child(
-                            Slider::new()
-                                .with_step(1.0)
-                                .with_range(0.0, 200.0)
-                                .expand_width()
-                                .controller(CustomSliderController::new(
-                                    CustomSliderID::MicrophoneVolumeSlider,
-                                ))
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::MicrophoneVolume,
+                                Slider::new()
+                                    .with_step(1.0)
+                                    .with_range(0.0, 200.0)
+                                    .expand_width()
+                                    .controller(
+                                        CustomSliderController::new(
+                                            CustomSliderID::MicrophoneVolumeSlider,
+                                        )
+                                        .with_aria_label(String::from("Microphone volume")),
+                                    )
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::microphone_volume),
+                                    ),
+                            ),
+                            80.0,
+                        )
+                        .with_flex_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{:.3} %", data.settings_layout.microphone_volume.to_string())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                            20.0,
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_MICROPHONE_BOOST_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_child(
+                    Flex::row()
+                        .must_fill_main_axis(true)
+                        .with_flex_child(
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::MicrophoneBoost,
+                                Slider::new()
+                                    .with_step(1.0)
+                                    .with_range(
+                                        MIN_MICROPHONE_BOOST_DB as f64,
+                                        MAX_MICROPHONE_BOOST_DB as f64,
+                                    )
+                                    .expand_width()
+                                    .controller(
+                                        CustomSliderController::new(
+                                            CustomSliderID::MicrophoneBoostSlider,
+                                        )
+                                        .with_aria_label(String::from("Microphone boost")),
+                                    )
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::microphone_boost_db),
+                                    ),
+                            ),
+                            80.0,
+                        )
+                        .with_flex_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                let boost_db = data.settings_layout.microphone_boost_db.round();
+                                if boost_db == 0.0 {
+                                    String::from("±0 dB")
+                                } else {
+                                    format!("{:+} dB", boost_db)
+                                }
+                            })
+                            .with_text_size(TEXT_SIZE),
+                            20.0,
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_input_level_row())
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_audio_input_device_row())                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_MICROPHONE_CHANNELS_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::MicrophoneChannels,
+                                RadioGroup::row(vec![("1", 1u8), ("2", 2u8)])
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::microphone_channels),
+                                    )
+                                    .controller(DataChangedController::new(
+                                        |data: &ApplicationState| data.settings_layout.microphone_channels,
+                                        SettingsLayout::on_microphone_channels_changed,
+                                    )),
+                            ),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_AGC_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.agc_enabled {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_agc_enabled_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_AGC_TARGET_LEVEL_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_child(
+                    Flex::row()
+                        .must_fill_main_axis(true)
+                        .with_flex_child(
+                            Slider::new()
+                                .with_step(1.0)
+                                .with_range(1.0, 50.0)
+                                .expand_width()
+                                .controller(CustomSliderController::new(
+                                    CustomSliderID::AgcTargetLevelSlider,
+                                ))
                                 .lens(
                                     ApplicationState::settings_layout
-                                        .then(SettingsLayout::microphone_volume),
+                                        .then(SettingsLayout::agc_target_level),
                                 ),
                             80.0,
                         )
                         .with_flex_child(
                             Label::new(|data: &ApplicationState, _env: &Env| {
-                                format!("{:.3} %", data.settings_layout.microphone_volume.to_string())
+                                format!("{:.3} %", data.settings_layout.agc_target_level.to_string())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                            20.0,
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_PTT_RELEASE_DELAY_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_child(
+                    Flex::row()
+                        .must_fill_main_axis(true)
+                        .with_flex_child(
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::PttReleaseDelay,
+                                Slider::new()
+                                    .with_step(50.0)
+                                    .with_range(0.0, MAX_PTT_RELEASE_DELAY_MS as f64)
+                                    .expand_width()
+                                    .controller(CustomSliderController::new(
+                                        CustomSliderID::PttReleaseDelaySlider,
+                                    ))
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::ptt_release_delay_ms),
+                                    ),
+                            ),
+                            80.0,
+                        )
+                        .with_flex_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{} {}",
+                                    data.settings_layout.ptt_release_delay_ms,
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_MILLISECONDS_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                            20.0,
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_audio_buffer_rows())
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_AEC_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.aec_enabled {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_aec_enabled_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_AEC_DELAY_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_child(
+                    Flex::row()
+                        .must_fill_main_axis(true)
+                        .with_flex_child(
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::AecDelay,
+                                Slider::new()
+                                    .with_step(10.0)
+                                    .with_range(0.0, MAX_AEC_DELAY_MS as f64)
+                                    .expand_width()
+                                    .controller(CustomSliderController::new(
+                                        CustomSliderID::AecDelaySlider,
+                                    ))
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::aec_delay_ms),
+                                    ),
+                            ),
+                            80.0,
+                        )
+                        .with_flex_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{} {}",
+                                    data.settings_layout.aec_delay_ms,
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_MILLISECONDS_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                            20.0,
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_SILENCE_SUPPRESSION_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.silence_suppression_enabled {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_silence_suppression_enabled_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_SILENCE_THRESHOLD_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_child(
+                    Flex::row()
+                        .must_fill_main_axis(true)
+                        .with_flex_child(
+                            Slider::new()
+                                .with_step(0.5)
+                                .with_range(0.5, 10.0)
+                                .expand_width()
+                                .controller(CustomSliderController::new(
+                                    CustomSliderID::SilenceThresholdSlider,
+                                ))
+                                .lens(
+                                    ApplicationState::settings_layout
+                                        .then(SettingsLayout::silence_threshold),
+                                ),
+                            80.0,
+                        )
+                        .with_flex_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{:.1} %", data.settings_layout.silence_threshold)
                             })
                             .with_text_size(TEXT_SIZE),
                             20.0,
                         ),
                 )
                 .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{}:  ", data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_SPATIAL_AUDIO_TEXT).unwrap())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.spatial_audio_enabled {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_spatial_audio_enabled_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_ptt_sound_row(true))
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_ptt_sound_row(false))
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_push_to_talk_mode_row())
+                .with_default_spacer()
                 .with_child(
                     Flex::row()
                         .with_child(
@@ -405,83 +1498,1130 @@ impl SettingsLayout {
                             .on_click(SettingsLayout::on_push_to_talk_clicked),
                         ),
                 )
-                .with_default_spacer()
-                .with_child(
-                    Flex::row()
-                        .with_child(Label::new(|data: &ApplicationState, _env: &Env| {
-                                format!(
-                                    "{}:  ",
-                                    data.localization
-                                        .get(
-                                            LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_NOTIFICATIONS_TEXT
-                                        )
-                                        .unwrap()
-                                )
-                            }).with_text_size(TEXT_SIZE))
-                        .with_child(
-                            Button::from_label(
-                                Label::new(|data: &ApplicationState, _env: &Env| {
-                                    if data.settings_layout.show_message_notification {
-                                        data.localization
-                                        .get(
-                                            LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_NOTIFICATIONS_BUTTON_ON_TEXT
-                                        )
-                                        .unwrap().clone()
-                                    } else {
-                                        data.localization
-                                        .get(
-                                            LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_NOTIFICATIONS_BUTTON_OFF_TEXT
-                                        )
-                                        .unwrap().clone()
-                                    }
-                                })
-                                .with_text_size(TEXT_SIZE),
-                            )
-                            .on_click(SettingsLayout::on_show_message_notification_clicked),
-                        ),
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{}:  ", data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_PAUSE_ON_MINIMIZE_TEXT).unwrap())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.pause_on_minimize {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_pause_on_minimize_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{}:  ", data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_START_FULLSCREEN_TEXT).unwrap())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.start_fullscreen {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_start_fullscreen_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(SettingsLayout::build_stepper_row(
+                    LOCALE_SETTINGS_LAYOUT_SETTING_IDLE_TIMEOUT_TEXT,
+                    StepperSetting::IdleTimeout,
+                ))
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUTO_AWAY_MESSAGE_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            ConfigValidatorUi::highlight_if_invalid(
+                                ConfigField::AutoAwayMessage,
+                                TextBox::new()
+                                    .with_text_size(TEXT_SIZE)
+                                    .fix_width(300.0)
+                                    .lens(
+                                        ApplicationState::settings_layout
+                                            .then(SettingsLayout::auto_away_message),
+                                    ),
+                            ),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{}:  ", data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_SHOW_INLINE_IMAGES_TEXT).unwrap())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.show_inline_images {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_show_inline_images_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(SettingsLayout::get_chat_max_messages_row())
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{}:  ", data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_ACCESSIBILITY_MODE_TEXT).unwrap())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.accessibility_mode {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_accessibility_mode_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!("{}:  ", data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_CONNECT_ON_LAUNCH_TEXT).unwrap())
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.connect_on_launch {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_connect_on_launch_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUTO_RECORD_TEXT)
+                                        .unwrap()
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.auto_record {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    } else {
+                                        data.localization
+                                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                            .unwrap()
+                                            .clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_auto_record_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::row()
+                        .with_child(Label::new(|data: &ApplicationState, _env: &Env| {
+                                format!(
+                                    "{}:  ",
+                                    data.localization
+                                        .get(
+                                            LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_NOTIFICATIONS_TEXT
+                                        )
+                                        .unwrap()
+                                )
+                            }).with_text_size(TEXT_SIZE))
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    if data.settings_layout.show_message_notification {
+                                        data.localization
+                                        .get(
+                                            LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_NOTIFICATIONS_BUTTON_ON_TEXT
+                                        )
+                                        .unwrap().clone()
+                                    } else {
+                                        data.localization
+                                        .get(
+                                            LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_NOTIFICATIONS_BUTTON_OFF_TEXT
+                                        )
+                                        .unwrap().clone()
+                                    }
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_show_message_notification_clicked),
+                        ),
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::column()
+                        .cross_axis_alignment(CrossAxisAlignment::Start)
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                let current_language = match data.settings_layout.selected_locale{
+                                    SupportedLocale::En => "English",
+                                    SupportedLocale::Ru => "Русский",
+                                };
+                                format!("{}: {}", 
+                                    data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_LOCALE_TEXT)
+                                    .unwrap(), current_language
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(
+                            Scroll::new(
+                                Flex::column()
+                                    .cross_axis_alignment(CrossAxisAlignment::Start)
+                                    .with_child(
+                                        Button::from_label(Label::new("English").with_text_size(TEXT_SIZE))
+                                            .expand_width()
+                                            .on_click(|ctx: &mut EventCtx, data: &mut ApplicationState, env: &Env|{
+                                                data.settings_layout.selected_locale = SupportedLocale::En;
+                                                SettingsLayout::on_locale_changed(ctx, data, env);
+                                            })
+                                    )
+                                    .with_child(
+                                        Button::from_label(Label::new("Русский").with_text_size(TEXT_SIZE))
+                                            .expand_width()
+                                            .on_click(|ctx: &mut EventCtx, data: &mut ApplicationState, env: &Env|{
+                                                data.settings_layout.selected_locale = SupportedLocale::Ru;
+                                                SettingsLayout::on_locale_changed(ctx, data, env);
+                                            })
+                                    )
+                                ).vertical()
+                        )
+                )
+                .with_default_spacer()
+                .with_child(
+                    Flex::column()
+                        .cross_axis_alignment(CrossAxisAlignment::Start)
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                let current_theme = if data.settings_layout.selected_theme.is_empty() {
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_THEME_DEFAULT_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                } else {
+                                    data.settings_layout.selected_theme.clone()
+                                };
+                                format!("{}: {}",
+                                    data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_THEME_TEXT)
+                                    .unwrap(), current_theme
+                                )
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_child(ViewSwitcher::new(
+                            |data: &ApplicationState, _env| {
+                                data.settings_layout.available_themes.clone()
+                            },
+                            |_selector, data, _env| Box::new(SettingsLayout::get_theme_list(data)),
+                        ))
+                )
+                .with_default_spacer()
+                .with_child(
+                    Button::from_label(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            SettingsLayout::get_advanced_toggle_text(
+                                &data.localization,
+                                data.settings_layout.is_advanced_expanded,
+                            )
+                        })
+                        .with_text_size(TEXT_SIZE),
+                    )
+                    .on_click(SettingsLayout::on_advanced_expanded_clicked),
+                )
+                .with_child(Either::new(
+                    |data: &ApplicationState, _env: &Env| data.settings_layout.is_advanced_expanded,
+                    SettingsLayout::get_advanced_content(),
+                    SizedBox::empty(),
+                ))
+                .with_default_spacer()
+                .with_child(Either::new(
+                    |data: &ApplicationState, _env: &Env| {
+                        data.settings_layout.restore_defaults_confirmation
+                    },
+                    ConfirmDialog::new(
+                        LOCALE_SETTINGS_LAYOUT_RESTORE_DEFAULTS_CONFIRMATION_TEXT,
+                        SettingsLayout::on_restore_defaults_confirmed,
+                        SettingsLayout::on_restore_defaults_cancelled,
+                    ),
+                    Button::from_label(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            data.localization
+                                .get(LOCALE_SETTINGS_LAYOUT_RESTORE_DEFAULTS_BUTTON_TEXT)
+                                .unwrap()
+                                .clone()
+                        })
+                        .with_text_size(TEXT_SIZE),
+                    )
+                    .on_click(SettingsLayout::on_restore_defaults_clicked),
+                )),
+        )
+    }
+    /// Power-user settings, hidden until the "Advanced" section is expanded.
+    fn get_advanced_content() -> impl Widget<ApplicationState> {
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_default_spacer()
+            .with_child(SettingsLayout::build_stepper_row(
+                LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_COOLDOWN_TEXT,
+                StepperSetting::MessageCooldown,
+            ))
+            .with_default_spacer()
+            .with_child(SettingsLayout::build_stepper_row(
+                LOCALE_SETTINGS_LAYOUT_SETTING_ROOM_CHANGE_COOLDOWN_TEXT,
+                StepperSetting::RoomChangeSameTierCooldown,
+            ))
+            .with_default_spacer()
+            .with_child(SettingsLayout::build_stepper_row(
+                LOCALE_SETTINGS_LAYOUT_SETTING_ROOM_CHANGE_LOBBY_COOLDOWN_TEXT,
+                StepperSetting::RoomChangeLobbyCooldown,
+            ))
+            .with_default_spacer()
+            .with_child(SettingsLayout::get_udp_recv_buffer_row())
+            .with_default_spacer()
+            .with_child(
+                Flex::row()
+                    .with_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            format!(
+                                "{}:  ",
+                                data.localization
+                                    .get(LOCALE_SETTINGS_LAYOUT_SETTING_UDP_HEALTH_CHECK_TEXT)
+                                    .unwrap()
+                            )
+                        })
+                        .with_text_size(TEXT_SIZE),
+                    )
+                    .with_child(
+                        Button::from_label(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                if data.settings_layout.udp_health_check_enabled {
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                } else {
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                }
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .on_click(SettingsLayout::on_udp_health_check_enabled_clicked),
+                    ),
+            )
+            .with_default_spacer()
+            .with_child(SettingsLayout::build_stepper_row(
+                LOCALE_SETTINGS_LAYOUT_SETTING_UDP_HEALTH_CHECK_TIMEOUT_TEXT,
+                StepperSetting::UdpHealthCheckTimeout,
+            ))
+            .with_default_spacer()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    let current_interface =
+                        if data.settings_layout.preferred_network_interface.is_empty() {
+                            data.localization
+                                .get(LOCALE_SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_AUTO_TEXT)
+                                .unwrap()
+                                .clone()
+                        } else {
+                            data.settings_layout.preferred_network_interface.clone()
+                        };
+                    format!(
+                        "{}: {}",
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_TEXT)
+                            .unwrap(),
+                        current_interface
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(ViewSwitcher::new(
+                |data: &ApplicationState, _env| {
+                    data.settings_layout.available_network_interfaces.clone()
+                },
+                |_selector, data, _env| Box::new(SettingsLayout::get_network_interface_list(data)),
+            ))
+    }
+    fn get_network_interface_list(data: &ApplicationState) -> impl Widget<ApplicationState> {
+        // the first one binds to all interfaces
+        let mut list = Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(SettingsLayout::get_network_interface_button(
+                data.localization
+                    .get(LOCALE_SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_AUTO_TEXT)
+                    .unwrap()
+                    .clone(),
+                String::new(),
+            ));
+
+        for interface_name in data.settings_layout.available_network_interfaces.iter() {
+            list.add_child(SettingsLayout::get_network_interface_button(
+                interface_name.clone(),
+                interface_name.clone(),
+            ));
+        }
+
+        Scroll::new(list).vertical()
+    }
+    fn get_network_interface_button(
+        text: String,
+        interface_name: String,
+    ) -> impl Widget<ApplicationState> {
+        Button::from_label(Label::new(text).with_text_size(TEXT_SIZE))
+            .expand_width()
+            .on_click(
+                move |_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env| {
+                    SettingsLayout::on_network_interface_changed(data, interface_name.clone());
+                },
+            )
+    }
+    fn on_network_interface_changed(data: &mut ApplicationState, interface_name: String) {
+        data.settings_layout.preferred_network_interface = interface_name;
+
+        // Save to config (used on the next connect).
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.preferred_network_interface =
+            data.settings_layout.preferred_network_interface.clone();
+
+        config_guard.mark_dirty();
+    }
+    /// "Name:  [-] N KB [+]", the buffer size is halved or doubled.
+    fn get_udp_recv_buffer_row() -> impl Widget<ApplicationState> {
+        Flex::row()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    format!(
+                        "{}:  ",
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_UDP_RECV_BUFFER_TEXT)
+                            .unwrap()
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Button::from_label(Label::new("-").with_text_size(TEXT_SIZE)).on_click(
+                    |_ctx, data: &mut ApplicationState, _env| {
+                        let new_size_kb = data.settings_layout.udp_recv_buffer_kb / 2;
+                        SettingsLayout::on_udp_recv_buffer_changed(data, new_size_kb);
+                    },
+                ),
+            )
+            .with_default_spacer()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    format!(
+                        "{} {}",
+                        data.settings_layout.udp_recv_buffer_kb,
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_USER_INFO_KILOBYTES_TEXT)
+                            .unwrap()
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_default_spacer()
+            .with_child(
+                Button::from_label(Label::new("+").with_text_size(TEXT_SIZE)).on_click(
+                    |_ctx, data: &mut ApplicationState, _env| {
+                        let new_size_kb = data.settings_layout.udp_recv_buffer_kb * 2;
+                        SettingsLayout::on_udp_recv_buffer_changed(data, new_size_kb);
+                    },
+                ),
+            )
+    }
+    fn get_push_to_talk_mode_row() -> impl Widget<ApplicationState> {
+        let mode_row = Flex::row()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    format!(
+                        "{}:  ",
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_TEXT)
+                            .unwrap()
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(ViewSwitcher::new(
+                // radio labels are not dynamic so rebuild them if the locale is changed
+                |data: &ApplicationState, _env| data.localization.clone(),
+                |_selector, data, _env| {
+                    let get_text = |key| data.localization.get(key).unwrap().clone();
+
+                    Box::new(
+                        RadioGroup::row(vec![
+                            (
+                                get_text(LOCALE_SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_HOLD_KEY_TEXT),
+                                PushToTalkMode::HoldKey,
+                            ),
+                            (
+                                get_text(
+                                    LOCALE_SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_VOICE_ACTIVATED_TEXT,
+                                ),
+                                PushToTalkMode::VoiceActivated,
+                            ),
+                        ])
+                        .lens(
+                            ApplicationState::settings_layout
+                                .then(SettingsLayout::push_to_talk_mode),
+                        )
+                        .controller(DataChangedController::new(
+                            |data: &ApplicationState| data.settings_layout.push_to_talk_mode,
+                            SettingsLayout::on_push_to_talk_mode_changed,
+                        )),
+                    )
+                },
+            ));
+
+        let sensitivity_row = Flex::column()
+            .with_default_spacer()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_VAD_SENSITIVITY_TEXT)
+                        .unwrap()
+                        .clone()
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Flex::row()
+                    .must_fill_main_axis(true)
+                    .with_flex_child(
+                        ConfigValidatorUi::highlight_if_invalid(
+                            ConfigField::VadSensitivity,
+                            Slider::new()
+                                .with_step(1.0)
+                                .with_range(0.0, VAD_MAX_SENSITIVITY as f64)
+                                .expand_width()
+                                .controller(
+                                    CustomSliderController::new(
+                                        CustomSliderID::VadSensitivitySlider,
+                                    )
+                                    .with_aria_label(String::from("Voice activation sensitivity")),
+                                )
+                                .lens(
+                                    ApplicationState::settings_layout
+                                        .then(SettingsLayout::vad_sensitivity),
+                                ),
+                        ),
+                        80.0,
+                    )
+                    .with_flex_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            format!("{} %", data.settings_layout.vad_sensitivity.round())
+                        })
+                        .with_text_size(TEXT_SIZE),
+                        20.0,
+                    ),
+            );
+
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(mode_row)
+            .with_child(Either::new(
+                |data: &ApplicationState, _env| {
+                    data.settings_layout.push_to_talk_mode == PushToTalkMode::VoiceActivated
+                },
+                sensitivity_row,
+                SizedBox::empty(),
+            ))
+    }
+    /// Sliders for the number of voice chunks buffered before playing and
+    /// sent before the push-to-talk button is checked (bigger buffers hide network jitter).
+    fn get_audio_buffer_rows() -> impl Widget<ApplicationState> {
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_PLAY_BUFFER_TEXT)
+                        .unwrap()
+                        .clone()
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Flex::row()
+                    .must_fill_main_axis(true)
+                    .with_flex_child(
+                        ConfigValidatorUi::highlight_if_invalid(
+                            ConfigField::AudioPlayBufferChunks,
+                            Slider::new()
+                                .with_step(1.0)
+                                .with_range(
+                                    MIN_AUDIO_PLAY_BUFFER_CHUNKS as f64,
+                                    MAX_AUDIO_PLAY_BUFFER_CHUNKS as f64,
+                                )
+                                .expand_width()
+                                .controller(
+                                    CustomSliderController::new(
+                                        CustomSliderID::AudioPlayBufferSlider,
+                                    )
+                                    .with_aria_label(String::from("Audio play buffer")),
+                                )
+                                .lens(
+                                    ApplicationState::settings_layout
+                                        .then(SettingsLayout::audio_play_buffer_chunks),
+                                ),
+                        ),
+                        80.0,
+                    )
+                    .with_flex_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            SettingsLayout::get_audio_buffer_text(
+                                data,
+                                data.settings_layout.audio_play_buffer_chunks,
+                            )
+                        })
+                        .with_text_size(TEXT_SIZE),
+                        20.0,
+                    ),
+            )
+            .with_default_spacer()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_RECORD_BUFFER_TEXT)
+                        .unwrap()
+                        .clone()
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Flex::row()
+                    .must_fill_main_axis(true)
+                    .with_flex_child(
+                        ConfigValidatorUi::highlight_if_invalid(
+                            ConfigField::AudioRecordBufferChunks,
+                            Slider::new()
+                                .with_step(1.0)
+                                .with_range(0.0, MAX_AUDIO_RECORD_BUFFER_CHUNKS as f64)
+                                .expand_width()
+                                .controller(
+                                    CustomSliderController::new(
+                                        CustomSliderID::AudioRecordBufferSlider,
+                                    )
+                                    .with_aria_label(String::from("Audio record buffer")),
+                                )
+                                .lens(
+                                    ApplicationState::settings_layout
+                                        .then(SettingsLayout::audio_record_buffer_chunks),
+                                ),
+                        ),
+                        80.0,
+                    )
+                    .with_flex_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            SettingsLayout::get_audio_buffer_text(
+                                data,
+                                data.settings_layout.audio_record_buffer_chunks,
+                            )
+                        })
+                        .with_text_size(TEXT_SIZE),
+                        20.0,
+                    ),
+            )
+    }
+    /// "N (M ms)" where M is the duration of N voice chunks.
+    fn get_audio_buffer_text(data: &ApplicationState, chunks: f64) -> String {
+        let chunks = chunks.round() as usize;
+        format!(
+            "{} ({} {})",
+            chunks,
+            chunks * SAMPLES_IN_CHUNK * 1000 / SAMPLE_RATE as usize,
+            data.localization
+                .get(LOCALE_SETTINGS_LAYOUT_SETTING_MILLISECONDS_TEXT)
+                .unwrap()
+        )
+    }
+    /// Microphone level meter, red if the microphone is clipping.
+    fn get_input_level_row() -> impl Widget<ApplicationState> {
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_SETTING_INPUT_LEVEL_TEXT)
+                        .unwrap()
+                        .clone()
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                EnvScope::new(
+                    |env, data: &ApplicationState| {
+                        if data.settings_layout.input_level > 1.0 {
+                            env.set(druid::theme::PRIMARY_LIGHT, Color::RED);
+                            env.set(druid::theme::PRIMARY_DARK, Color::RED);
+                        }
+                    },
+                    ProgressBar::new()
+                        .expand_width()
+                        .lens(ApplicationState::settings_layout.then(SettingsLayout::input_level)),
+                )
+                .controller(InputLevelController::new()),
+            )
+    }
+    fn get_audio_input_device_row() -> impl Widget<ApplicationState> {
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    let current_device = if data.settings_layout.audio_input_device.is_empty() {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_DEVICE_DEFAULT_TEXT)
+                            .unwrap()
+                            .clone()
+                    } else {
+                        data.settings_layout.audio_input_device.clone()
+                    };
+                    format!(
+                        "{}: {}",
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_INPUT_DEVICE_TEXT)
+                            .unwrap(),
+                        current_device
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(ViewSwitcher::new(
+                |data: &ApplicationState, _env| {
+                    data.settings_layout.available_audio_input_devices.clone()
+                },
+                |_selector, data, _env| Box::new(SettingsLayout::get_audio_input_device_list(data)),
+            ))
+    }
+    fn get_audio_input_device_list(data: &ApplicationState) -> impl Widget<ApplicationState> {
+        // the first one is the system default
+        let mut list = Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(SettingsLayout::get_audio_input_device_button(
+                data.localization
+                    .get(LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_DEVICE_DEFAULT_TEXT)
+                    .unwrap()
+                    .clone(),
+                String::new(),
+            ));
+
+        for device_name in data.settings_layout.available_audio_input_devices.iter() {
+            list.add_child(SettingsLayout::get_audio_input_device_button(
+                device_name.clone(),
+                device_name.clone(),
+            ));
+        }
+
+        Scroll::new(list).vertical()
+    }
+    fn get_audio_input_device_button(
+        text: String,
+        device_name: String,
+    ) -> impl Widget<ApplicationState> {
+        Button::from_label(Label::new(text).with_text_size(TEXT_SIZE))
+            .expand_width()
+            .on_click(
+                move |_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env| {
+                    SettingsLayout::on_audio_input_device_changed(data, device_name.clone());
+                },
+            )
+    }
+    fn on_audio_input_device_changed(data: &mut ApplicationState, device_name: String) {
+        data.settings_layout.audio_input_device = device_name;
+
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().audio_input_device =
+            data.settings_layout.audio_input_device.clone();
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.audio_input_device = data.settings_layout.audio_input_device.clone();
+
+        config_guard.mark_dirty();
+    }
+    fn get_chat_max_messages_row() -> impl Widget<ApplicationState> {
+        let row = Flex::row()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    format!(
+                        "{}:  ",
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_SETTING_CHAT_MAX_MESSAGES_TEXT)
+                            .unwrap()
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Button::from_label(Label::new("-").with_text_size(TEXT_SIZE)).on_click(
+                    |_ctx, data: &mut ApplicationState, _env| {
+                        let new_max_messages = data
+                            .settings_layout
+                            .chat_max_messages
+                            .saturating_sub(CHAT_MAX_MESSAGES_STEP);
+                        SettingsLayout::on_chat_max_messages_changed(data, new_max_messages);
+                    },
+                ),
+            )
+            .with_default_spacer()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    data.settings_layout.chat_max_messages.to_string()
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_default_spacer()
+            .with_child(
+                Button::from_label(Label::new("+").with_text_size(TEXT_SIZE)).on_click(
+                    |_ctx, data: &mut ApplicationState, _env| {
+                        let new_max_messages =
+                            data.settings_layout.chat_max_messages + CHAT_MAX_MESSAGES_STEP;
+                        SettingsLayout::on_chat_max_messages_changed(data, new_max_messages);
+                    },
+                ),
+            );
+
+        ConfigValidatorUi::highlight_if_invalid(ConfigField::ChatMaxMessages, row)
+    }
+    fn on_chat_max_messages_changed(data: &mut ApplicationState, new_max_messages: usize) {
+        data.settings_layout.chat_max_messages = new_max_messages
+            .max(MIN_CHAT_MAX_MESSAGES)
+            .min(MAX_CHAT_MAX_MESSAGES);
+
+        data.main_layout
+            .chat_list
+            .set_max_messages(data.settings_layout.chat_max_messages);
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.chat_max_messages = data.settings_layout.chat_max_messages;
+
+        config_guard.mark_dirty();
+    }
+    fn on_udp_recv_buffer_changed(data: &mut ApplicationState, new_size_kb: u32) {
+        data.settings_layout.udp_recv_buffer_kb = new_size_kb
+            .max(MIN_UDP_SOCKET_BUFFER_KB)
+            .min(MAX_UDP_SOCKET_BUFFER_KB);
+
+        // Save to config (used on the next connect).
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.udp_recv_buffer_kb = data.settings_layout.udp_recv_buffer_kb;
+
+        config_guard.mark_dirty();
+    }
+    /// Example: "Advanced ▶" (collapsed), "Advanced ▼" (expanded).
+    fn get_advanced_toggle_text(
+        localization: &HashMap<String, String>,
+        is_expanded: bool,
+    ) -> String {
+        format!(
+            "{} {}",
+            localization
+                .get(LOCALE_SETTINGS_LAYOUT_ADVANCED_SECTION_TEXT)
+                .unwrap(),
+            if is_expanded { "▼" } else { "▶" }
+        )
+    }
+    fn on_advanced_expanded_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        SettingsLayout::toggle_advanced_expanded(data);
+    }
+    fn toggle_advanced_expanded(data: &mut ApplicationState) {
+        data.settings_layout.is_advanced_expanded = !data.settings_layout.is_advanced_expanded;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.advanced_settings_expanded = data.settings_layout.is_advanced_expanded;
+
+        config_guard.mark_dirty();
+    }
+    fn get_ptt_sound_row(is_press_sound: bool) -> impl Widget<ApplicationState> {
+        let row = Flex::row()
+            .with_child(
+                Label::new(move |data: &ApplicationState, _env: &Env| {
+                    let (locale_key, sound_path) = if is_press_sound {
+                        (
+                            LOCALE_SETTINGS_LAYOUT_SETTING_PTT_PRESS_SOUND_TEXT,
+                            &data.settings_layout.ptt_press_sound,
+                        )
+                    } else {
+                        (
+                            LOCALE_SETTINGS_LAYOUT_SETTING_PTT_RELEASE_SOUND_TEXT,
+                            &data.settings_layout.ptt_release_sound,
+                        )
+                    };
+
+                    // only show the file name
+                    let file_name = Path::new(sound_path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+
+                    format!(
+                        "{}: {}  ",
+                        data.localization.get(locale_key).unwrap(),
+                        file_name
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Button::from_label(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_CHOOSE_FILE_BUTTON_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .on_click(move |ctx, _data: &mut ApplicationState, _env| {
+                    let accept_command = if is_press_sound {
+                        SETTINGS_PTT_PRESS_SOUND_SELECTED
+                    } else {
+                        SETTINGS_PTT_RELEASE_SOUND_SELECTED
+                    };
+                    let options = FileDialogOptions::new()
+                        .allowed_types(vec![FileSpec::new("Sound", &["wav", "ogg"])])
+                        .accept_command(accept_command);
+                    ctx.submit_command(commands::SHOW_OPEN_PANEL.with(options));
+                }),
+            )
+            .with_spacer(5.0)
+            .with_child(
+                Button::from_label(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        data.localization
+                            .get(LOCALE_SETTINGS_LAYOUT_PREVIEW_BUTTON_TEXT)
+                            .unwrap()
+                            .clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                    if is_press_sound {
+                        AudioService::play_sound_file(data.settings_layout.ptt_press_sound.clone());
+                    } else {
+                        AudioService::play_sound_file(
+                            data.settings_layout.ptt_release_sound.clone(),
+                        );
+                    }
+                }),
+            );
+
+        let config_field = if is_press_sound {
+            ConfigField::PttPressSound
+        } else {
+            ConfigField::PttReleaseSound
+        };
+
+        ConfigValidatorUi::highlight_if_invalid(config_field, row)
+    }
+    fn get_theme_list(data: &ApplicationState) -> impl Widget<ApplicationState> {
+        // the first one is the bundled default theme
+        let mut list = Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(SettingsLayout::get_theme_button(
+                data.localization
+                    .get(LOCALE_SETTINGS_LAYOUT_SETTING_THEME_DEFAULT_TEXT)
+                    .unwrap()
+                    .clone(),
+                String::new(),
+            ));
+
+        for theme_name in data.settings_layout.available_themes.iter() {
+            list.add_child(SettingsLayout::get_theme_button(
+                theme_name.clone(),
+                theme_name.clone(),
+            ));
+        }
+
+        Scroll::new(list).vertical()
+    }
+    fn get_theme_button(text: String, theme_name: String) -> impl Widget<ApplicationState> {
+        Button::from_label(Label::new(text).with_text_size(TEXT_SIZE))
+            .expand_width()
+            .on_click(
+                move |ctx: &mut EventCtx, _data: &mut ApplicationState, _env: &Env| {
+                    ctx.get_external_handle()
+                        .submit_command(THEME_MANAGER_APPLY_THEME, theme_name.clone(), Target::Auto)
+                        .expect("failed to submit THEME_MANAGER_APPLY_THEME command");
+                },
+            )
+    }
+    fn get_history_content() -> impl Widget<ApplicationState> {
+        Padding::new(
+            10.0,
+            Flex::column()
+                .must_fill_main_axis(true)
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_flex_child(
+                    ViewSwitcher::new(
+                        |data: &ApplicationState, _env| {
+                            data.settings_layout.connection_history.clone()
+                        },
+                        |_selector, data, _env| Box::new(SettingsLayout::get_history_list(data)),
+                    ),
+                    1.0,
                 )
                 .with_default_spacer()
                 .with_child(
-                    Flex::column()
-                        .cross_axis_alignment(CrossAxisAlignment::Start)
-                        .with_child(
-                            Label::new(|data: &ApplicationState, _env: &Env| {
-                                let current_language = match data.settings_layout.selected_locale{
-                                    SupportedLocale::En => "English",
-                                    SupportedLocale::Ru => "Русский",
-                                };
-                                format!("{}: {}", 
-                                    data.localization.get(LOCALE_SETTINGS_LAYOUT_SETTING_LOCALE_TEXT)
-                                    .unwrap(), current_language
-                                )
-                            })
-                            .with_text_size(TEXT_SIZE),
-                        )
-                        .with_child(
-                            Scroll::new(
-                                Flex::column()
-                                    .cross_axis_alignment(CrossAxisAlignment::Start)
-                                    .with_child(
-                                        Button::from_label(Label::new("English").with_text_size(TEXT_SIZE))
-                                            .expand_width()
-                                            .on_click(|ctx: &mut EventCtx, data: &mut ApplicationState, env: &Env|{
-                                                data.settings_layout.selected_locale = SupportedLocale::En;
-                                                SettingsLayout::on_locale_changed(ctx, data, env);
-                                            })
-                                    )
-                                    .with_child(
-                                        Button::from_label(Label::new("Русский").with_text_size(TEXT_SIZE))
-                                            .expand_width()
-                                            .on_click(|ctx: &mut EventCtx, data: &mut ApplicationState, env: &Env|{
-                                                data.settings_layout.selected_locale = SupportedLocale::Ru;
-                                                SettingsLayout::on_locale_changed(ctx, data, env);
-                                            })
-                                    )
-                                ).vertical()
-                        )
+                    Button::from_label(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            data.localization
+                                .get(LOCALE_SETTINGS_LAYOUT_HISTORY_CLEAR_BUTTON_TEXT)
+                                .unwrap()
+                                .clone()
+                        })
+                        .with_text_size(TEXT_SIZE),
+                    )
+                    .on_click(SettingsLayout::on_clear_history_clicked),
                 ),
         )
     }
+    fn get_history_list(data: &ApplicationState) -> impl Widget<ApplicationState> {
+        let mut content: Flex<ApplicationState> =
+            Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+
+        if data.settings_layout.connection_history.is_empty() {
+            content.add_child(
+                Label::new(
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_HISTORY_EMPTY_TEXT)
+                        .unwrap()
+                        .clone(),
+                )
+                .with_text_size(TEXT_SIZE),
+            );
+        }
+
+        // newest first
+        for event in data.settings_layout.connection_history.iter().rev() {
+            content.add_child(
+                Label::new(format!(
+                    "{}  {}:{}  ({} {} {})  {}",
+                    event.timestamp.format("%Y.%m.%d %H:%M"),
+                    event.server,
+                    event.port,
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_HISTORY_CONNECTED_FOR_TEXT)
+                        .unwrap(),
+                    event.connected_for_sec / 60,
+                    data.localization
+                        .get(LOCALE_SETTINGS_LAYOUT_HISTORY_MINUTES_TEXT)
+                        .unwrap(),
+                    event.disconnect_reason
+                ))
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .with_text_size(MESSAGE_TEXT_SIZE),
+            );
+            content.add_default_spacer();
+        }
+
+        Scroll::new(content).vertical().expand()
+    }
     fn get_about_content() -> impl Widget<ApplicationState> {
         Padding::new(
             10.0,
@@ -514,6 +2654,48 @@ impl SettingsLayout {
                     .with_line_break_mode(LineBreaking::WordWrap)
                     .with_text_size(TEXT_SIZE),
                 )
+                .with_child(
+                    Flex::row()
+                        .with_child(
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_CHECK_UPDATES_BUTTON_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(SettingsLayout::on_check_for_updates_clicked)
+                            .disabled_if(|data: &ApplicationState, _env: &Env| {
+                                data.settings_layout.is_checking_for_updates
+                            }),
+                        )
+                        .with_default_spacer()
+                        .with_child(
+                            Label::new(|data: &ApplicationState, _env: &Env| {
+                                data.settings_layout.update_check_text.clone()
+                            })
+                            .with_text_size(TEXT_SIZE),
+                        )
+                        .with_default_spacer()
+                        .with_child(Either::new(
+                            |data: &ApplicationState, _env| data.settings_layout.is_update_available,
+                            Button::from_label(
+                                Label::new(|data: &ApplicationState, _env: &Env| {
+                                    data.localization
+                                        .get(LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_DOWNLOAD_UPDATE_BUTTON_TEXT)
+                                        .unwrap()
+                                        .clone()
+                                })
+                                .with_text_size(TEXT_SIZE),
+                            )
+                            .on_click(|_ctx, _data, _env| {
+                                opener::open(RELEASES_PAGE_URL).unwrap();
+                            }),
+                            SizedBox::empty(),
+                        )),
+                )
                 .with_child(
                     Flex::row()
                         .with_child(
@@ -555,6 +2737,65 @@ impl SettingsLayout {
                 ),
         )
     }
+    fn on_check_for_updates_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        if data.settings_layout.is_checking_for_updates {
+            return;
+        }
+
+        data.settings_layout.is_checking_for_updates = true;
+        data.settings_layout.is_update_available = false;
+        data.settings_layout.update_check_text = data
+            .localization
+            .get(LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_CHECKING_UPDATES_TEXT)
+            .unwrap()
+            .clone();
+
+        let event_sink = ctx.get_external_handle();
+        thread::spawn(move || {
+            event_sink
+                .submit_command(
+                    SETTINGS_UPDATE_CHECK_RESULT,
+                    UpdateChecker::check_for_updates(),
+                    Target::Auto,
+                )
+                .expect("failed to submit SETTINGS_UPDATE_CHECK_RESULT command");
+        });
+    }
+    pub fn set_update_check_result(
+        &mut self,
+        result: &Result<UpdateCheckResult, String>,
+        localization: &HashMap<String, String>,
+    ) {
+        self.is_checking_for_updates = false;
+
+        match result {
+            Ok(result) => {
+                self.is_update_available = result.is_newer;
+                if result.is_newer {
+                    self.update_check_text = format!(
+                        "{}: {}",
+                        localization
+                            .get(LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_UPDATE_AVAILABLE_TEXT)
+                            .unwrap(),
+                        result.latest
+                    );
+                } else {
+                    self.update_check_text = localization
+                        .get(LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_UP_TO_DATE_TEXT)
+                        .unwrap()
+                        .clone();
+                }
+            }
+            Err(msg) => {
+                warn!("failed to check for updates: {}", msg);
+                self.is_update_available = false;
+                self.update_check_text = localization
+                    .get(LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_UPDATE_CHECK_FAILED_TEXT)
+                    .unwrap()
+                    .clone();
+            }
+        }
+    }
     pub fn push_to_talk_key_change_event(data: &mut ApplicationState, key: &String) {
         if key == "" {
             data.settings_layout.push_to_talk_key_text =
@@ -567,16 +2808,30 @@ impl SettingsLayout {
             let mut config_guard = data.user_config.lock().unwrap();
             config_guard.push_to_talk_button = string_to_key(key);
 
-            if let Err(err) = config_guard.save() {
-                let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+            config_guard.mark_dirty();
+        }
+    }
+    pub fn apply_theme_event(data: &mut ApplicationState, theme_name: &str) {
+        match ThemeManager::load_by_name(theme_name) {
+            Ok(theme) => data.theme = theme,
+            Err(msg) => {
+                let error_msg = format!("{} at [{}, {}]", msg, file!(), line!());
                 if !data.is_connected {
                     data.connect_layout
                         .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
                 } else {
                     data.main_layout.add_system_message(error_msg);
                 }
+                return;
             }
         }
+        data.settings_layout.selected_theme = String::from(theme_name);
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.theme_name = String::from(theme_name);
+
+        config_guard.mark_dirty();
     }
     pub fn master_volume_slider_moved_event(
         data: &mut ApplicationState,
@@ -586,15 +2841,7 @@ impl SettingsLayout {
         let mut config_guard = data.user_config.lock().unwrap();
         config_guard.master_volume = info.value;
 
-        if let Err(err) = config_guard.save() {
-            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
-            if !data.is_connected {
-                data.connect_layout
-                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
-            } else {
-                data.main_layout.add_system_message(error_msg);
-            }
-        }
+        config_guard.mark_dirty();
     }
     pub fn microphone_volume_slider_moved_event(
         data: &mut ApplicationState,
@@ -604,15 +2851,120 @@ impl SettingsLayout {
         let mut config_guard = data.user_config.lock().unwrap();
         config_guard.microphone_volume = info.value;
 
-        if let Err(err) = config_guard.save() {
-            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
-            if !data.is_connected {
-                data.connect_layout
-                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
-            } else {
-                data.main_layout.add_system_message(error_msg);
-            }
-        }
+        config_guard.mark_dirty();
+    }
+    pub fn microphone_boost_slider_moved_event(data: &mut ApplicationState) {
+        // the slider info is unsigned so take the value that the slider has already written
+        let boost_db = data.settings_layout.microphone_boost_db.round() as f32;
+
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().microphone_boost_db = boost_db;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.microphone_boost_db = boost_db;
+
+        config_guard.mark_dirty();
+    }
+    pub fn vad_sensitivity_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        let sensitivity = info.value.min(VAD_MAX_SENSITIVITY as u16) as u8;
+
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().vad_sensitivity = sensitivity;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.vad_sensitivity = sensitivity;
+
+        config_guard.mark_dirty();
+    }
+    pub fn audio_play_buffer_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        let chunks = info
+            .value
+            .max(MIN_AUDIO_PLAY_BUFFER_CHUNKS as u16)
+            .min(MAX_AUDIO_PLAY_BUFFER_CHUNKS as u16) as u8;
+
+        // applied to the next voice message of each user (when the player thread starts)
+        data.audio_service.lock().unwrap().audio_play_buffer_chunks = chunks;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.audio_play_buffer_chunks = chunks;
+
+        config_guard.mark_dirty();
+    }
+    pub fn audio_record_buffer_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        let chunks = info.value.min(MAX_AUDIO_RECORD_BUFFER_CHUNKS as u16) as u8;
+
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service
+            .lock()
+            .unwrap()
+            .audio_record_buffer_chunks = chunks;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.audio_record_buffer_chunks = chunks;
+
+        config_guard.mark_dirty();
+    }
+    pub fn ptt_release_delay_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().ptt_release_delay_ms = info.value;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.ptt_release_delay_ms = info.value;
+
+        config_guard.mark_dirty();
+    }
+    pub fn aec_delay_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().aec_delay_ms = info.value as u32;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.aec_delay_ms = info.value as u32;
+
+        config_guard.mark_dirty();
+    }
+    pub fn silence_threshold_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        // applied on the next connection (when the recording thread starts)
+        data.audio_service.lock().unwrap().silence_threshold = info.value as f32 / 100.0;
+
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.silence_threshold = info.value as f32 / 100.0;
+
+        config_guard.mark_dirty();
+    }
+    pub fn agc_target_level_slider_moved_event(
+        data: &mut ApplicationState,
+        info: &OnCustomSliderMovedInfo,
+    ) {
+        // Save to config.
+        let mut config_guard = data.user_config.lock().unwrap();
+        config_guard.agc_target_rms = info.value as f32 / 100.0;
+
+        config_guard.mark_dirty();
     }
 }
 
@@ -665,3 +3017,70 @@ fn convert_rdev_key(key: rdev::Key) -> Option<system_wide_key_state::KeyCode> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_application_state;
+
+    #[test]
+    fn advanced_section_is_collapsed_by_default() {
+        let data = test_application_state();
+
+        assert!(!data.settings_layout.is_advanced_expanded);
+        assert!(!UserConfig::empty().advanced_settings_expanded);
+    }
+
+    #[test]
+    fn advanced_toggle_is_saved_to_config() {
+        let mut data = test_application_state();
+
+        SettingsLayout::toggle_advanced_expanded(&mut data);
+        assert!(data.settings_layout.is_advanced_expanded);
+        {
+            let config_guard = data.user_config.lock().unwrap();
+            assert!(config_guard.advanced_settings_expanded);
+            assert!(config_guard.is_dirty());
+        }
+
+        SettingsLayout::toggle_advanced_expanded(&mut data);
+        assert!(!data.settings_layout.is_advanced_expanded);
+        assert!(!data.user_config.lock().unwrap().advanced_settings_expanded);
+    }
+
+    #[test]
+    fn advanced_state_is_read_from_config() {
+        let mut config = UserConfig::empty();
+        config.advanced_settings_expanded = true;
+        let mut settings_layout = SettingsLayout::new();
+
+        settings_layout.read_user_config(&config);
+
+        assert!(settings_layout.is_advanced_expanded);
+    }
+
+    #[test]
+    fn advanced_toggle_text_shows_state() {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_SETTINGS_LAYOUT_ADVANCED_SECTION_TEXT),
+            String::from("Advanced"),
+        );
+
+        assert_eq!(
+            SettingsLayout::get_advanced_toggle_text(&localization, false),
+            "Advanced ▶"
+        );
+        assert_eq!(
+            SettingsLayout::get_advanced_toggle_text(&localization, true),
+            "Advanced ▼"
+        );
+    }
+
+    #[test]
+    fn general_and_advanced_content_is_built() {
+        // widgets are only built (there is no window to render them in tests)
+        let _advanced_content = SettingsLayout::get_advanced_content();
+        let _general_content = SettingsLayout::get_general_content();
+    }
+}
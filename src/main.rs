@@ -5,44 +5,79 @@ use druid::WindowHandle;
 // External
 use csv::Reader;
 use druid::widget::prelude::*;
-use druid::widget::ViewSwitcher;
-use druid::Lens;
+use druid::widget::{EnvScope, ViewSwitcher};
 use druid::{
-    AppDelegate, AppLauncher, Command, Data, DelegateCtx, Env, Handled, Target, WindowDesc,
+    AppDelegate, AppLauncher, Application, Color, Command, Data, DelegateCtx, Env, Handled, Target,
+    WindowDesc, WindowId, WindowState,
 };
+use druid::{Lens, WidgetExt};
 use rdev::display_size;
 use system_wide_key_state::*;
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 // Std
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 // Custom.
+mod error;
 mod global_params;
 mod layouts;
 mod misc;
 mod services;
+#[cfg(test)]
+mod tests;
 mod theme;
 mod widgets;
 use global_params::*;
 use layouts::connect_layout::*;
 use layouts::main_layout::*;
 use layouts::settings_layout::*;
+use misc::accessibility_controller::*;
+use misc::activity_controller::*;
+use misc::chat_scroll_shortcut_controller::*;
+use misc::config_save_controller::*;
+use misc::connected_list_controller::*;
 use misc::custom_data_button_controller::*;
 use misc::custom_slider_controller::*;
 use misc::custom_text_box_controller::*;
+use misc::fullscreen_controller::*;
+use misc::locale_keys::*;
+use misc::new_badge_controller::*;
+use misc::room_invite_controller::*;
+use misc::search_highlight_controller::*;
+use misc::search_shortcut_controller::*;
+use misc::settings_shortcut_controller::*;
+use misc::user_drag_controller::*;
 use services::audio_service::audio_service::*;
 use services::config_service::*;
+use services::image_fetcher::*;
 use services::net_service::*;
 use services::user_tcp_service::*;
 use services::user_udp_service::*;
 use theme::*;
+use widgets::chat_list::ChatImage;
+use widgets::connected_list::UserStatus;
+use widgets::reaction_bar::{CHAT_LIST_PIN_CLICKED, CHAT_LIST_REACTION_CLICKED};
+use widgets::room_info_popup::CONNECTED_LIST_VIEW_ROOM_INFO;
+use widgets::search_panel::SEARCH_RESULTS_READY;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum CustomSliderID {
     MasterVolumeSlider,
     UserVolumeSlider,
     MicrophoneVolumeSlider,
+    MicrophoneBoostSlider,
+    AgcTargetLevelSlider,
+    PttReleaseDelaySlider,
+    AecDelaySlider,
+    SilenceThresholdSlider,
+    VadSensitivitySlider,
+    AudioPlayBufferSlider,
+    AudioRecordBufferSlider,
 }
 
 #[derive(Clone, Copy, Data, PartialEq)]
@@ -55,6 +90,7 @@ pub enum Layout {
 #[derive(Clone, Data, Lens)]
 pub struct ApplicationState {
     current_layout: Layout,
+    previous_layout: Layout, // where to return from the settings
     connect_layout: ConnectLayout,
     settings_layout: SettingsLayout,
     main_layout: MainLayout,
@@ -72,12 +108,43 @@ pub struct ApplicationState {
     network_service: Arc<Mutex<NetService>>,
     #[data(ignore)]
     user_config: Arc<Mutex<UserConfig>>,
+    #[data(ignore)]
+    last_activity: Instant, // last keyboard/mouse input
 }
 
 pub fn main() {
+    // setup logging ('RUST_LOG' overrides the log level from the config)
+    let env_log_level = std::env::var("RUST_LOG").ok();
+    let log_subscriber = FmtSubscriber::builder()
+        .with_env_filter(EnvFilter::new(
+            env_log_level.as_deref().unwrap_or(DEFAULT_LOG_LEVEL),
+        ))
+        .with_filter_reloading();
+    let log_filter_handle = log_subscriber.reload_handle();
+    log_subscriber.init();
+
+    // load config
+    let config = UserConfig::open_or_recover();
+    if let Err(err) = config {
+        panic!("{} at [{}, {}]", err, file!(), line!());
+    }
+    let (config, config_load_result) = config.unwrap();
+
+    if env_log_level.is_none() {
+        if let Err(e) = log_filter_handle.reload(EnvFilter::new(&config.log_level)) {
+            warn!(
+                "failed to apply log level '{}', error: {} at [{}, {}]",
+                config.log_level,
+                e,
+                file!(),
+                line!()
+            );
+        }
+    }
+
     let window_size = Size {
-        width: 650.0,
-        height: 500.0,
+        width: config.window_width as f64,
+        height: config.window_height as f64,
     };
 
     let (w, h) = display_size().unwrap();
@@ -91,25 +158,23 @@ pub fn main() {
             h as f64 / 2.0 - window_size.height / 2.0,
         ));
 
-    // load config
-    let config = UserConfig::new();
-    if let Err(err) = config {
-        panic!("{} at [{}, {}]", err, file!(), line!());
-    }
+    let user_config = Arc::new(Mutex::new(config));
 
     // create the initial app state
     let mut initial_state: ApplicationState = ApplicationState {
         current_layout: Layout::Connect,
+        previous_layout: Layout::Connect,
         connect_layout: ConnectLayout::new(),
         settings_layout: SettingsLayout::new(),
         main_layout: MainLayout::new(),
         theme: ApplicationTheme::default(),
         is_connected: false,
         audio_service: Arc::new(Mutex::new(AudioService::default())),
-        network_service: Arc::new(Mutex::new(NetService::new())),
-        user_config: Arc::new(Mutex::new(config.unwrap())),
+        network_service: Arc::new(Mutex::new(NetService::new(Arc::clone(&user_config)))),
+        user_config,
         window_handle: Arc::new(None),
         localization: Arc::new(HashMap::new()),
+        last_activity: Instant::now(),
     };
 
     apply_config(&mut initial_state);
@@ -119,16 +184,59 @@ pub fn main() {
     }
     read_localization(&_needed_locale, &mut initial_state);
 
+    let should_auto_connect = initial_state
+        .connect_layout
+        .should_auto_connect(&initial_state.user_config.lock().unwrap());
+    if should_auto_connect {
+        initial_state
+            .connect_layout
+            .start_auto_connect(&initial_state.localization);
+    }
+
+    // the microphone might have been unplugged since the last launch
+    let audio_input_device = initial_state
+        .user_config
+        .lock()
+        .unwrap()
+        .audio_input_device
+        .clone();
+    if !AudioService::is_audio_input_device_available(&audio_input_device) {
+        warn!(
+            "audio input device \"{}\" is not available, using the default one",
+            audio_input_device
+        );
+        // keep it in the config in case it will be plugged in again
+        initial_state
+            .audio_service
+            .lock()
+            .unwrap()
+            .audio_input_device
+            .clear();
+        let message = initial_state
+            .localization
+            .get(LOCALE_CONNECT_LAYOUT_AUDIO_INPUT_DEVICE_NOT_FOUND_TEXT)
+            .unwrap()
+            .clone();
+        initial_state
+            .connect_layout
+            .set_connect_result(ConnectResult::Err(message), &initial_state.localization);
+    }
+
+    if let ConfigLoadResult::Recovered { backup_path } = config_load_result {
+        initial_state
+            .connect_layout
+            .show_config_recovered_message(&backup_path, &initial_state.localization);
+    }
+
     // start the application. Here we pass in the application state.
     AppLauncher::with_window(main_window)
         .delegate(Delegate {})
-        .log_to_console()
         .configure_env(apply_theme)
         .launch(initial_state)
         .expect("Failed to launch the application.");
 }
 
-fn apply_config(data: &mut ApplicationState) {
+pub fn apply_config(data: &mut ApplicationState) {
     let config_guard = data.user_config.lock().unwrap();
 
     // Fill connect fields from config.
@@ -142,21 +250,48 @@ fn apply_config(data: &mut ApplicationState) {
 
     //data.settings_layout.ui_scaling_slider_value = config.ui_scaling as i32;
     //data.ui_scaling = config.ui_scaling as f64 / 100.0;
-    data.settings_layout.master_volume = config_guard.master_volume as f64;
-    data.settings_layout.microphone_volume = config_guard.microphone_volume as f64;
-    data.settings_layout.push_to_talk_key_text = get_key_name(config_guard.push_to_talk_button);
-    data.settings_layout.push_to_talk_keycode = config_guard.push_to_talk_button;
-    data.settings_layout.show_message_notification = config_guard.show_message_notification;
-    if config_guard.locale == "en" {
-        data.settings_layout.selected_locale = SupportedLocale::En;
-    } else if config_guard.locale == "ru" {
-        data.settings_layout.selected_locale = SupportedLocale::Ru;
+    data.settings_layout.read_user_config(&config_guard);
+    data.main_layout.connected_list.is_compact = config_guard.compact_user_list;
+    data.main_layout
+        .chat_list
+        .set_max_messages(config_guard.chat_max_messages);
+
+    match ThemeManager::find_themes() {
+        Ok(themes) => data.settings_layout.available_themes = Rc::new(themes),
+        Err(msg) => warn!("{} at [{}, {}]", msg, file!(), line!()),
+    }
+    match ThemeManager::load_by_name(&config_guard.theme_name) {
+        Ok(theme) => data.theme = theme,
+        Err(msg) => warn!(
+            "{}, using the default theme instead at [{}, {}]",
+            msg,
+            file!(),
+            line!()
+        ),
     }
 
     data.audio_service.lock().unwrap().init(
         Arc::clone(&data.network_service),
         config_guard.master_volume as i32,
         config_guard.microphone_volume as i32,
+        config_guard.microphone_boost_db,
+        config_guard.agc_enabled,
+        config_guard.agc_target_rms,
+        config_guard.ptt_release_delay_ms,
+        config_guard.aec_enabled,
+        config_guard.aec_delay_ms,
+        config_guard.silence_suppression_enabled,
+        config_guard.silence_threshold,
+        config_guard.spatial_audio_enabled,
+        &config_guard.ptt_press_sound,
+        &config_guard.ptt_release_sound,
+        config_guard.microphone_channels,
+        config_guard.push_to_talk_mode,
+        config_guard.vad_sensitivity,
+        config_guard.voice_codec,
+        &config_guard.audio_input_device,
+        config_guard.audio_play_buffer_chunks,
+        config_guard.audio_record_buffer_chunks,
     );
 }
 
@@ -222,18 +357,72 @@ fn read_localization(needed_locale: &str, data: &mut ApplicationState) {
 
     data.localization = Arc::new(localization);
 
-    println!(
-        "SILENT_NOTIFICATION: using locale '{}' from config.",
-        needed_locale
-    );
+    info!("using locale '{}' from config.", needed_locale);
+}
+
+/// Disconnects from the server and shows the connect layout
+/// (the users are removed by NETWORK_SERVICE_CLEAR_ALL_USERS).
+fn disconnect_to_connect_layout(data: &mut ApplicationState) {
+    data.main_layout.stop_reconnecting();
+    data.network_service.lock().unwrap().disconnect();
+
+    data.is_connected = false;
+    data.main_layout.current_user_name = String::new();
+    data.main_layout.clear_text_chat();
+    data.connect_layout.connect_result = String::new();
+    data.current_layout = Layout::Connect;
+
+    // don't wait for the delayed save
+    if let Err(err) = data.user_config.lock().unwrap().save_if_dirty() {
+        data.connect_layout.set_connect_result(
+            ConnectResult::Err(format!("{} at [{}, {}]", err, file!(), line!())),
+            &data.localization,
+        );
+    }
 }
 
 struct Delegate;
 
 impl AppDelegate<ApplicationState> for Delegate {
-    fn command(
+    fn event(
+        &mut self,
+        _ctx: &mut DelegateCtx,
+        _window_id: WindowId,
+        event: Event,
+        data: &mut ApplicationState,
+        _env: &Env,
+    ) -> Option<Event> {
+        if let Event::WindowSize(_) = event {
+            // minimizing/restoring the window triggers a resize
+            if data.is_connected && data.user_config.lock().unwrap().pause_on_minimize {
+                if let Some(window_handle) = data.window_handle.as_ref() {
+                    let net_service_guard = data.network_service.lock().unwrap();
+                    if matches!(window_handle.get_window_state(), WindowState::MINIMIZED) {
+                        net_service_guard.pause_voice_transmission();
+                    } else {
+                        net_service_guard.resume_voice_transmission();
+                    }
+                }
+            }
+        }
+
+        Some(event)
+    }
+    fn window_removed(
         &mut self,
+        _id: WindowId,
+        data: &mut ApplicationState,
+        _env: &Env,
         _ctx: &mut DelegateCtx,
+    ) {
+        // the delayed save would never happen
+        if let Err(err) = data.user_config.lock().unwrap().save_if_dirty() {
+            error!("{} at [{}, {}]", err, file!(), line!());
+        }
+    }
+    fn command(
+        &mut self,
+        ctx: &mut DelegateCtx,
         _target: Target,
         cmd: &Command,
         data: &mut ApplicationState,
@@ -242,6 +431,29 @@ impl AppDelegate<ApplicationState> for Delegate {
         if let Some(key) = cmd.get(PUSH_TO_TALK_KEY_CHANGE_EVENT) {
             SettingsLayout::push_to_talk_key_change_event(data, key);
             Handled::Yes
+        } else if let Some(result) = cmd.get(SETTINGS_UPDATE_CHECK_RESULT) {
+            data.settings_layout
+                .set_update_check_result(result, &data.localization);
+            Handled::Yes
+        } else if let Some(results) = cmd.get(SEARCH_RESULTS_READY) {
+            data.main_layout.set_search_results(results.clone());
+            Handled::Yes
+        } else if cmd.get(SEARCH_HIGHLIGHT_EXPIRED).is_some() {
+            data.main_layout.chat_list.clear_highlight();
+            Handled::Yes
+        } else if let Some(username) = cmd.get(NEW_BADGE_EXPIRED) {
+            // the user might be already disconnected
+            data.main_layout.connected_list.clear_new_badge(username);
+            Handled::Yes
+        } else if let Some(label) = cmd.get(ACCESSIBILITY_ANNOUNCE) {
+            AccessibilityController::announce(label, data);
+            Handled::Yes
+        } else if let Some(file_info) = cmd.get(SETTINGS_PTT_PRESS_SOUND_SELECTED) {
+            SettingsLayout::on_ptt_sound_file_selected(data, file_info, true);
+            Handled::Yes
+        } else if let Some(file_info) = cmd.get(SETTINGS_PTT_RELEASE_SOUND_SELECTED) {
+            SettingsLayout::on_ptt_sound_file_selected(data, file_info, false);
+            Handled::Yes
         } else if cmd.get(CUSTOM_TEXT_BOX_RETURN_PRESSED).is_some() {
             MainLayout::send_message_event(data);
             Handled::Yes
@@ -258,6 +470,67 @@ impl AppDelegate<ApplicationState> for Delegate {
                 }
             }
             Handled::Yes
+        } else if let Some(status) = cmd.get(USER_STATUS_CHANGED) {
+            data.main_layout.set_current_user_status(*status);
+
+            if *status == UserStatus::Away && data.is_connected {
+                let auto_away_message = data.user_config.lock().unwrap().auto_away_message.clone();
+                if !auto_away_message.is_empty() {
+                    if let Err(err) = data
+                        .network_service
+                        .lock()
+                        .unwrap()
+                        .send_status_message(auto_away_message)
+                    {
+                        if let ActionError::SystemError(msg) = err {
+                            data.main_layout.add_system_message(format!(
+                                "{} at [{}, {}]",
+                                msg,
+                                file!(),
+                                line!()
+                            ));
+                        } else {
+                            warn!("auto-away message was not sent (send queue is full)");
+                        }
+                    }
+                }
+            }
+            Handled::Yes
+        } else if let Some(jitter_ms) = cmd.get(USER_UDP_SERVICE_JITTER_UPDATE) {
+            data.main_layout
+                .set_udp_jitter(*jitter_ms, &data.localization);
+            Handled::Yes
+        } else if let Some(failure_count) = cmd.get(USER_UDP_SERVICE_DECRYPTION_FAILED) {
+            data.main_layout.udp_decryption_failures = *failure_count;
+            Handled::Yes
+        } else if let Some(drop_info) = cmd.get(CONNECTED_LIST_USER_DROPPED) {
+            MainLayout::user_dropped_on_room_event(data, drop_info);
+            Handled::Yes
+        } else if cmd.get(CONNECTED_LIST_USER_DRAG_ENDED).is_some() {
+            data.main_layout.connected_list.end_user_drag();
+            Handled::Yes
+        } else if let Some(room_name) = cmd.get(CONNECTED_LIST_VIEW_ROOM_INFO) {
+            data.main_layout
+                .connected_list
+                .open_room_info(room_name, &data.main_layout.current_user_room);
+            Handled::Yes
+        } else if cmd.get(CONNECTED_LIST_COPY_ALL_USERNAMES).is_some() {
+            Application::global()
+                .clipboard()
+                .put_string(data.main_layout.connected_list.get_all_usernames());
+            Handled::Yes
+        } else if cmd.get(CONNECT_LAYOUT_PREFETCH_SERVER_ADDRESS).is_some() {
+            if !data.is_connected {
+                data.connect_layout
+                    .prefetch_server_address(ctx.get_external_handle().into());
+            }
+            Handled::Yes
+        } else if let Some(resolved) = cmd.get(CONNECT_LAYOUT_DNS_RESOLVED) {
+            data.connect_layout.set_resolved_server_address(resolved);
+            Handled::Yes
+        } else if let Some(theme_name) = cmd.get(THEME_MANAGER_APPLY_THEME) {
+            SettingsLayout::apply_theme_event(data, theme_name);
+            Handled::Yes
         } else if let Some(info) = cmd.get(CUSTOM_SLIDER_ON_VALUE_CHANGED) {
             match info.custom_slider_id {
                 CustomSliderID::MasterVolumeSlider => {
@@ -266,16 +539,68 @@ impl AppDelegate<ApplicationState> for Delegate {
                 CustomSliderID::MicrophoneVolumeSlider => {
                     SettingsLayout::microphone_volume_slider_moved_event(data, info);
                 }
+                CustomSliderID::MicrophoneBoostSlider => {
+                    SettingsLayout::microphone_boost_slider_moved_event(data);
+                }
+                CustomSliderID::AgcTargetLevelSlider => {
+                    SettingsLayout::agc_target_level_slider_moved_event(data, info);
+                }
+                CustomSliderID::PttReleaseDelaySlider => {
+                    SettingsLayout::ptt_release_delay_slider_moved_event(data, info);
+                }
+                CustomSliderID::AecDelaySlider => {
+                    SettingsLayout::aec_delay_slider_moved_event(data, info);
+                }
+                CustomSliderID::SilenceThresholdSlider => {
+                    SettingsLayout::silence_threshold_slider_moved_event(data, info);
+                }
+                CustomSliderID::VadSensitivitySlider => {
+                    SettingsLayout::vad_sensitivity_slider_moved_event(data, info);
+                }
+                CustomSliderID::AudioPlayBufferSlider => {
+                    SettingsLayout::audio_play_buffer_slider_moved_event(data, info);
+                }
+                CustomSliderID::AudioRecordBufferSlider => {
+                    SettingsLayout::audio_record_buffer_slider_moved_event(data, info);
+                }
                 CustomSliderID::UserVolumeSlider => {
                     MainLayout::user_volume_slider_moved_event(data);
                 }
             }
             Handled::Yes
+        } else if cmd.get(AUDIO_SERVICE_RECORDING_STARTED).is_some() {
+            data.main_layout.set_ptt_recording(true);
+            Handled::Yes
+        } else if cmd.get(AUDIO_SERVICE_RECORDING_STOPPED).is_some() {
+            data.main_layout.set_ptt_recording(false);
+            Handled::Yes
         } else if let Some(username) = cmd.get(AUDIO_SERVICE_ON_USER_TALK_START) {
             data.main_layout.set_user_talking(username, true);
             Handled::Yes
-        } else if let Some(username) = cmd.get(AUDIO_SERVICE_ON_USER_TALK_END) {
-            data.main_layout.set_user_talking(username, false);
+        } else if let Some(talk_end_info) = cmd.get(AUDIO_SERVICE_ON_USER_TALK_END) {
+            data.main_layout
+                .set_user_talking(&talk_end_info.username, false);
+            if let Err(_) = data.main_layout.add_user_voice_session(
+                &talk_end_info.username,
+                talk_end_info.talking_ms,
+                talk_end_info.bytes_received,
+            ) {
+                warn!(
+                    "can't find user {} to add voice session stats, at [{}:{}]",
+                    talk_end_info.username,
+                    file!(),
+                    line!()
+                );
+            }
+            Handled::Yes
+        } else if let Some(username) = cmd.get(AUDIO_SERVICE_BUFFER_OVERFLOW) {
+            data.main_layout.add_system_message(format!(
+                "{} {}",
+                username,
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_MESSAGE_VOICE_BUFFER_OVERFLOW_TEXT)
+                    .unwrap()
+            ));
             Handled::Yes
         } else if let Some(error_msg) = cmd.get(NETWORK_SERVICE_SYSTEM_IO_ERROR) {
             data.main_layout.add_system_message(error_msg.clone());
@@ -289,7 +614,7 @@ impl AppDelegate<ApplicationState> for Delegate {
                 .set_user_ping(&ping_data.username, ping_data.ping_ms)
             {
                 if ping_data.try_again_count == 0 {
-                    println!("SILENT_WARNING: Ping of user '{}' was received but no info about the user was received (ping of unknown user) [failed after {} attempts to wait for user info].",
+                    warn!("Ping of user '{}' was received but no info about the user was received (ping of unknown user) [failed after {} attempts to wait for user info].",
                                     &ping_data.username,
                                     USER_CONNECT_FIRST_UDP_PING_RETRY_MAX_COUNT);
                 } else {
@@ -325,6 +650,13 @@ impl AppDelegate<ApplicationState> for Delegate {
             }
             Handled::Yes
         } else if let Some(username) = cmd.get(USER_TCP_SERVICE_USER_DISCONNECTED) {
+            data.network_service
+                .lock()
+                .unwrap()
+                .user_udp_service
+                .lock()
+                .unwrap()
+                .remove_replay_window(username);
             {
                 let audio_guard = data.audio_service.lock().unwrap();
 
@@ -358,8 +690,103 @@ impl AppDelegate<ApplicationState> for Delegate {
                 data.main_layout.add_system_message(msg);
             }
             Handled::Yes
+        } else if let Some(status_message) = cmd.get(USER_TCP_SERVICE_USER_STATUS_MESSAGE) {
+            data.main_layout
+                .add_system_message(MainLayout::format_user_status_message(
+                    &status_message.username,
+                    &status_message.text,
+                    &data.localization,
+                ));
+            Handled::Yes
+        } else if let Some(reaction) = cmd.get(USER_TCP_SERVICE_MESSAGE_REACTION) {
+            // The message might be already removed from the chat, ignore it then.
+            data.main_layout.chat_list.toggle_reaction(
+                reaction.message_id,
+                &reaction.username,
+                &reaction.emoji,
+            );
+            Handled::Yes
+        } else if let Some((message_id, emoji)) = cmd.get(CHAT_LIST_REACTION_CLICKED) {
+            if let Err(err) = data
+                .network_service
+                .lock()
+                .unwrap()
+                .send_message_reaction(*message_id, emoji.clone())
+            {
+                if let ActionError::SystemError(msg) = err {
+                    data.main_layout.add_system_message(format!(
+                        "{} at [{}, {}]",
+                        msg,
+                        file!(),
+                        line!()
+                    ));
+                } else {
+                    warn!("message reaction was not sent (send queue is full)");
+                }
+            }
+            Handled::Yes
+        } else if let Some(pinned) = cmd.get(USER_TCP_SERVICE_MESSAGE_PINNED) {
+            data.main_layout.chat_list.pin_message(
+                pinned.message_id,
+                pinned.author.clone(),
+                pinned.text.clone(),
+            );
+            Handled::Yes
+        } else if let Some(invite) = cmd.get(USER_TCP_SERVICE_ROOM_INVITE) {
+            let message_id = data.main_layout.chat_list.add_room_invite(
+                format!(
+                    "{} {} '{}'.",
+                    invite.inviter_username,
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_ROOM_INVITE_TEXT)
+                        .unwrap(),
+                    invite.room_name
+                ),
+                invite.room_name.clone(),
+            );
+            ctx.submit_command(ROOM_INVITE_EXPIRY_TIMER_START.with(message_id));
+            Handled::Yes
+        } else if let Some(message_id) = cmd.get(CHAT_LIST_PIN_CLICKED) {
+            if let Err(err) = data
+                .network_service
+                .lock()
+                .unwrap()
+                .send_pin_message(*message_id)
+            {
+                if let ActionError::SystemError(msg) = err {
+                    data.main_layout.add_system_message(format!(
+                        "{} at [{}, {}]",
+                        msg,
+                        file!(),
+                        line!()
+                    ));
+                } else {
+                    warn!("pin message request was not sent (send queue is full)");
+                }
+            }
+            Handled::Yes
+        } else if let Some(room_info) = cmd.get(USER_TCP_SERVICE_ROOM_DESCRIPTION) {
+            if let Err(msg) = data
+                .main_layout
+                .set_room_description(&room_info.room_name, room_info.description.clone())
+            {
+                data.main_layout.add_system_message(msg);
+            }
+            Handled::Yes
+        } else if cmd.get(NETWORK_SERVICE_CONNECTION_LOST).is_some() {
+            MainLayout::on_connection_lost(data);
+            Handled::Yes
+        } else if cmd.get(DISCONNECT_REQUESTED).is_some() {
+            disconnect_to_connect_layout(data);
+            ctx.submit_command(NETWORK_SERVICE_CLEAR_ALL_USERS);
+            Handled::Yes
         } else if cmd.get(NETWORK_SERVICE_CLEAR_ALL_USERS).is_some() {
+            MainLayout::stop_voice_recording(data);
             data.main_layout.clear_all_users();
+            data.main_layout.send_queue_depth = 0; // queue is closed on disconnect
+            Handled::Yes
+        } else if let Some(depth) = cmd.get(NETWORK_SERVICE_QUEUE_DEPTH_CHANGED) {
+            data.main_layout.send_queue_depth = *depth;
             Handled::Yes
         } else if let Some(user_message_info) = cmd.get(USER_TCP_SERVICE_USER_MESSAGE) {
             // TODO: when #1997 is resolved implement:
@@ -367,20 +794,54 @@ impl AppDelegate<ApplicationState> for Delegate {
             // 2. if there are unread messages (while minimized) show notification about them every minute.
             // 3. add an option to turn on/off unread messages notifications (p. 2)
             let todo_notifications_variable = 42;
-            println!(
-                "{:?}",
+            debug!(
+                "window state: {:?}",
                 data.window_handle
                     .as_ref()
                     .as_ref()
                     .unwrap()
                     .get_window_state()
             );
-            data.main_layout.add_message(
+            let (show_notification, show_inline_images) = {
+                let config_guard = data.user_config.lock().unwrap();
+                (
+                    config_guard.show_message_notification,
+                    config_guard.show_inline_images,
+                )
+            };
+            let message_id = data.main_layout.add_message(
                 user_message_info.message.clone(),
                 user_message_info.username.clone(),
-                data.user_config.lock().unwrap().show_message_notification,
+                user_message_info.message_id,
+                show_notification,
+                data.current_layout == Layout::Main,
+            );
+            if show_inline_images {
+                if let Some(url) = ImageFetcher::find_image_url(&user_message_info.message) {
+                    if data.main_layout.chat_list.start_image_loading(message_id) {
+                        ImageFetcher::fetch(message_id, url, ctx.get_external_handle());
+                    }
+                }
+            }
+            Handled::Yes
+        } else if let Some((message_id, pixels, width, height)) = cmd.get(CHAT_IMAGE_LOADED) {
+            data.main_layout.chat_list.set_message_image(
+                *message_id,
+                ChatImage::from_rgba_pixels(pixels.clone(), *width, *height),
             );
             Handled::Yes
+        } else if let Some((message_id, error)) = cmd.get(CHAT_IMAGE_FAILED) {
+            let image = match error {
+                ImageFetchError::TooLarge => ChatImage::TooLarge,
+                ImageFetchError::Other(msg) => {
+                    warn!("{} at [{}, {}]", msg, file!(), line!());
+                    ChatImage::Failed
+                }
+            };
+            data.main_layout
+                .chat_list
+                .set_message_image(*message_id, image);
+            Handled::Yes
         } else if let Some(user_message_info) = cmd.get(USER_TCP_SERVICE_MOVE_USER_TO_ROOM) {
             if let Err(msg) = data
                 .main_layout
@@ -401,62 +862,86 @@ impl AppDelegate<ApplicationState> for Delegate {
 }
 
 fn apply_theme(env: &mut Env, data: &ApplicationState) {
+    let high_contrast_theme;
+    let theme = if data.settings_layout.accessibility_mode {
+        high_contrast_theme = ApplicationTheme::high_contrast();
+        &high_contrast_theme
+    } else {
+        &data.theme
+    };
+
     env.set(
         druid::theme::WINDOW_BACKGROUND_COLOR,
-        data.theme.background_color.clone(),
+        theme.background_color.clone(),
     );
-    env.set(
-        druid::theme::TEXTBOX_BORDER_RADIUS,
-        data.theme.border_radius,
-    );
-    env.set(druid::theme::BUTTON_BORDER_RADIUS, data.theme.border_radius);
+    env.set(druid::theme::TEXTBOX_BORDER_RADIUS, theme.border_radius);
+    env.set(druid::theme::BUTTON_BORDER_RADIUS, theme.border_radius);
     env.set(
         druid::theme::PLACEHOLDER_COLOR,
-        data.theme.placeholder_color.clone(),
+        theme.placeholder_color.clone(),
     );
     env.set(
         druid::theme::BACKGROUND_LIGHT,
-        data.theme.textbox_background_color.clone(),
+        theme.textbox_background_color.clone(),
     );
     env.set(
         druid::theme::BORDER_DARK,
-        data.theme.inactive_border_color.clone(),
+        theme.inactive_border_color.clone(),
     );
     env.set(
         druid::theme::SELECTED_TEXT_BACKGROUND_COLOR,
-        data.theme.text_selection_color.clone(),
+        theme.text_selection_color.clone(),
     );
     env.set(
         druid::theme::PRIMARY_LIGHT,
-        data.theme.active_border_color.clone(),
-    );
-    env.set(
-        druid::theme::BUTTON_DARK,
-        data.theme.button_dark_color.clone(),
-    );
-    env.set(
-        druid::theme::BUTTON_LIGHT,
-        data.theme.button_light_color.clone(),
+        theme.active_border_color.clone(),
     );
+    env.set(druid::theme::BUTTON_DARK, theme.button_dark_color.clone());
+    env.set(druid::theme::BUTTON_LIGHT, theme.button_light_color.clone());
 
     env.set(
         BACKGROUND_SPECIAL_COLOR,
-        data.theme.background_special_color.clone(),
+        theme.background_special_color.clone(),
     );
+
+    if data.settings_layout.accessibility_mode {
+        env.set(druid::theme::TEXT_COLOR, Color::WHITE);
+        // bigger buttons are easier to hit
+        env.set(
+            druid::theme::BORDERED_WIDGET_HEIGHT,
+            ACCESSIBILITY_MIN_BUTTON_HEIGHT,
+        );
+        env.set(
+            druid::theme::BASIC_WIDGET_HEIGHT,
+            ACCESSIBILITY_MIN_BUTTON_HEIGHT,
+        );
+    }
 }
 
 fn build_root_widget() -> impl Widget<ApplicationState> {
-    ViewSwitcher::new(
-        |data: &ApplicationState, _env| data.current_layout,
-        |selector, data, _env| match *selector {
-            Layout::Connect => Box::new(ConnectLayout::build_ui()),
-            Layout::Settings => Box::new(SettingsLayout::build_ui()),
-            Layout::Main => {
-                if data.window_handle.as_ref().is_none() {
-                    panic!("No window handle set!");
+    // EnvScope re-applies the theme when it's changed at runtime
+    // (configure_env is only called once on launch).
+    EnvScope::new(
+        apply_theme,
+        ViewSwitcher::new(
+            |data: &ApplicationState, _env| data.current_layout,
+            |selector, data, _env| match *selector {
+                Layout::Connect => Box::new(ConnectLayout::build_ui()),
+                Layout::Settings => Box::new(SettingsLayout::build_ui()),
+                Layout::Main => {
+                    if data.window_handle.as_ref().is_none() {
+                        panic!("No window handle set!");
+                    }
+                    Box::new(MainLayout::build_ui())
                 }
-                Box::new(MainLayout::build_ui())
-            }
-        },
+            },
+        )
+        .background(druid::theme::WINDOW_BACKGROUND_COLOR),
     )
+    .controller(FullscreenController::new())
+    .controller(ActivityController::new())
+    .controller(SearchShortcutController::new())
+    .controller(ChatScrollShortcutController::new())
+    .controller(SettingsShortcutController::new())
+    .controller(ConfigSaveController::new())
 }
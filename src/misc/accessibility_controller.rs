@@ -0,0 +1,155 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::Selector;
+use tracing::info;
+
+use crate::ApplicationState;
+
+// Submitted when a described widget is hovered or focused, handled by the delegate.
+pub const ACCESSIBILITY_ANNOUNCE: Selector<String> = Selector::new("accessibility_announce");
+
+// Describes the wrapped widget for screen readers (like an ARIA label on the web).
+//
+// The druid version we use has no AccessKit integration (there is no accessibility
+// tree to put the description into), so for now the description is announced
+// (written to the "accessibility" log target) when the widget is hovered or focused
+// while the accessibility mode is enabled.
+pub struct AccessibilityController {
+    label: String,
+}
+
+impl AccessibilityController {
+    pub fn new(label: String) -> Self {
+        AccessibilityController { label }
+    }
+    /// Example: "Room: Lobby, 3 users".
+    pub fn room_label(room_name: &str, user_count: usize) -> String {
+        format!(
+            "Room: {}, {} {}",
+            room_name,
+            user_count,
+            if user_count == 1 { "user" } else { "users" }
+        )
+    }
+    /// Example: "User: alice, 42 ms ping, 100% volume".
+    pub fn user_label(username: &str, ping_ms: u16, volume: f64) -> String {
+        format!(
+            "User: {}, {} ms ping, {}% volume",
+            username,
+            ping_ms,
+            volume.round() as i32
+        )
+    }
+    /// Used by controllers that have the label as an option.
+    pub fn on_lifecycle(label: &Option<String>, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let Some(label) = label {
+            match event {
+                LifeCycle::HotChanged(true) | LifeCycle::FocusChanged(true) => {
+                    ctx.submit_command(ACCESSIBILITY_ANNOUNCE.with(label.clone()));
+                }
+                _ => {}
+            }
+        }
+    }
+    /// Called by the delegate on ACCESSIBILITY_ANNOUNCE.
+    pub fn announce(label: &str, data: &ApplicationState) {
+        if data.settings_layout.accessibility_mode {
+            info!(target: "accessibility", "{}", label);
+        }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for AccessibilityController {
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        AccessibilityController::on_lifecycle(&Some(self.label.clone()), ctx, event);
+
+        child.lifecycle(ctx, event, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::custom_data_button_controller::*;
+
+    #[test]
+    fn room_label_has_user_count() {
+        assert_eq!(
+            AccessibilityController::room_label("Lobby", 3),
+            "Room: Lobby, 3 users"
+        );
+        assert_eq!(
+            AccessibilityController::room_label("Lobby", 0),
+            "Room: Lobby, 0 users"
+        );
+    }
+
+    #[test]
+    fn room_label_single_user() {
+        assert_eq!(
+            AccessibilityController::room_label("Gaming", 1),
+            "Room: Gaming, 1 user"
+        );
+    }
+
+    #[test]
+    fn room_label_keeps_non_ascii_name() {
+        assert_eq!(
+            AccessibilityController::room_label("Комната 🎮, тест", 2),
+            "Room: Комната 🎮, тест, 2 users"
+        );
+    }
+
+    #[test]
+    fn user_label_has_ping_and_volume() {
+        assert_eq!(
+            AccessibilityController::user_label("alice", 42, 100.0),
+            "User: alice, 42 ms ping, 100% volume"
+        );
+        assert_eq!(
+            AccessibilityController::user_label("пользователь", 0, 0.0),
+            "User: пользователь, 0 ms ping, 0% volume"
+        );
+        assert_eq!(
+            AccessibilityController::user_label("bob", u16::MAX, 200.0),
+            "User: bob, 65535 ms ping, 200% volume"
+        );
+    }
+
+    #[test]
+    fn user_label_volume_is_rounded() {
+        assert_eq!(
+            AccessibilityController::user_label("alice", 10, 57.4),
+            "User: alice, 10 ms ping, 57% volume"
+        );
+        assert_eq!(
+            AccessibilityController::user_label("alice", 10, 57.5),
+            "User: alice, 10 ms ping, 58% volume"
+        );
+    }
+
+    #[test]
+    fn button_label_is_optional() {
+        let data = CustomButtonData::ConnectedListData {
+            is_room: true,
+            button_name: String::from("Lobby"),
+        };
+        assert!(CustomDataButtonController::new(data.clone())
+            .aria_label
+            .is_none());
+
+        let controller = CustomDataButtonController::new(data)
+            .with_aria_label(AccessibilityController::room_label("Lobby", 3));
+        assert_eq!(
+            controller.aria_label,
+            Some(String::from("Room: Lobby, 3 users"))
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Selector, TimerToken};
+use std::time::{Duration, Instant};
+
+use crate::global_params::*;
+use crate::widgets::connected_list::UserStatus;
+use crate::ApplicationState;
+
+pub const USER_STATUS_CHANGED: Selector<UserStatus> = Selector::new("user_status_changed");
+
+// Wraps the whole widget tree, tracks the last user input and
+// switches the local user to "away" after the configured idle timeout.
+pub struct ActivityController {
+    idle_check_timer: TimerToken,
+}
+
+impl ActivityController {
+    pub fn new() -> Self {
+        ActivityController {
+            idle_check_timer: TimerToken::INVALID,
+        }
+    }
+    /// 0 minutes timeout means that the idle timeout is disabled.
+    pub fn is_idle(last_activity: Instant, now: Instant, idle_timeout_min: u8) -> bool {
+        idle_timeout_min != 0
+            && now.duration_since(last_activity)
+                >= Duration::from_secs(idle_timeout_min as u64 * 60)
+    }
+    /// Returns the new status if it should be changed on the periodic idle check.
+    pub fn get_status_after_idle_check(
+        status: UserStatus,
+        last_activity: Instant,
+        now: Instant,
+        idle_timeout_min: u8,
+    ) -> Option<UserStatus> {
+        if status == UserStatus::Active
+            && ActivityController::is_idle(last_activity, now, idle_timeout_min)
+        {
+            Some(UserStatus::Away)
+        } else {
+            None
+        }
+    }
+    /// Returns the new status if it should be changed on the user input.
+    pub fn get_status_after_input(status: UserStatus) -> Option<UserStatus> {
+        if status == UserStatus::Away {
+            Some(UserStatus::Active)
+        } else {
+            None
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for ActivityController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::WindowConnected => {
+                self.idle_check_timer =
+                    ctx.request_timer(Duration::from_secs(IDLE_CHECK_INTERVAL_SEC));
+            }
+            Event::Timer(token) if *token == self.idle_check_timer => {
+                self.idle_check_timer =
+                    ctx.request_timer(Duration::from_secs(IDLE_CHECK_INTERVAL_SEC));
+
+                let idle_timeout_min = data.user_config.lock().unwrap().idle_timeout_min;
+                if let Some(status) = ActivityController::get_status_after_idle_check(
+                    data.main_layout.current_user_status,
+                    data.last_activity,
+                    Instant::now(),
+                    idle_timeout_min,
+                ) {
+                    ctx.submit_command(USER_STATUS_CHANGED.with(status));
+                }
+                ctx.set_handled();
+                return;
+            }
+            Event::KeyDown(_) | Event::MouseDown(_) | Event::MouseMove(_) | Event::Wheel(_) => {
+                data.last_activity = Instant::now();
+                if let Some(status) =
+                    ActivityController::get_status_after_input(data.main_layout.current_user_status)
+                {
+                    ctx.submit_command(USER_STATUS_CHANGED.with(status));
+                }
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDLE_TIMEOUT_MIN: u8 = 5;
+
+    fn get_status_after(
+        status: UserStatus,
+        idle_sec: u64,
+        idle_timeout_min: u8,
+    ) -> Option<UserStatus> {
+        let last_activity = Instant::now();
+
+        ActivityController::get_status_after_idle_check(
+            status,
+            last_activity,
+            last_activity + Duration::from_secs(idle_sec),
+            idle_timeout_min,
+        )
+    }
+
+    #[test]
+    fn away_after_idle_timeout() {
+        let timeout_sec = IDLE_TIMEOUT_MIN as u64 * 60;
+
+        assert_eq!(
+            get_status_after(UserStatus::Active, 0, IDLE_TIMEOUT_MIN),
+            None
+        );
+        assert_eq!(
+            get_status_after(UserStatus::Active, timeout_sec - 1, IDLE_TIMEOUT_MIN),
+            None
+        );
+        assert_eq!(
+            get_status_after(UserStatus::Active, timeout_sec, IDLE_TIMEOUT_MIN),
+            Some(UserStatus::Away)
+        );
+        assert_eq!(
+            get_status_after(UserStatus::Active, timeout_sec * 10, IDLE_TIMEOUT_MIN),
+            Some(UserStatus::Away)
+        );
+    }
+
+    #[test]
+    fn away_is_not_changed_again() {
+        assert_eq!(
+            get_status_after(
+                UserStatus::Away,
+                IDLE_TIMEOUT_MIN as u64 * 60 * 2,
+                IDLE_TIMEOUT_MIN
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_timeout_is_disabled() {
+        assert_eq!(get_status_after(UserStatus::Active, 0, 0), None);
+        assert_eq!(get_status_after(UserStatus::Active, 24 * 60 * 60, 0), None);
+    }
+
+    #[test]
+    fn input_reverts_to_active() {
+        assert_eq!(
+            ActivityController::get_status_after_input(UserStatus::Away),
+            Some(UserStatus::Active)
+        );
+        assert_eq!(
+            ActivityController::get_status_after_input(UserStatus::Active),
+            None
+        );
+    }
+
+    #[test]
+    fn idle_again_after_input() {
+        let last_input = Instant::now();
+        let timeout = Duration::from_secs(IDLE_TIMEOUT_MIN as u64 * 60);
+
+        // became away, then the input reverted the status
+        let status = ActivityController::get_status_after_input(UserStatus::Away).unwrap();
+
+        // the timeout is counted from the last input
+        assert_eq!(
+            ActivityController::get_status_after_idle_check(
+                status,
+                last_input,
+                last_input + timeout / 2,
+                IDLE_TIMEOUT_MIN
+            ),
+            None
+        );
+        assert_eq!(
+            ActivityController::get_status_after_idle_check(
+                status,
+                last_input,
+                last_input + timeout,
+                IDLE_TIMEOUT_MIN
+            ),
+            Some(UserStatus::Away)
+        );
+    }
+}
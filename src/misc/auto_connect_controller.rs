@@ -0,0 +1,64 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::TimerToken;
+use std::time::Duration;
+
+use crate::global_params::*;
+use crate::layouts::connect_layout::ConnectLayout;
+use crate::ApplicationState;
+
+// Wraps the connect layout, connects after AUTO_CONNECT_DELAY_MS if the auto-connect
+// (connect on launch) was started and not cancelled. The delay gives the user
+// a chance to cancel (the UI is blocked while connecting).
+pub struct AutoConnectController {
+    timer: TimerToken,
+}
+
+impl AutoConnectController {
+    pub fn new() -> Self {
+        AutoConnectController {
+            timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for AutoConnectController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer {
+                self.timer = TimerToken::INVALID;
+                if data.connect_layout.is_auto_connecting {
+                    ConnectLayout::on_connect_clicked(ctx, data, env);
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if data.connect_layout.is_auto_connecting {
+                self.timer = ctx.request_timer(Duration::from_millis(AUTO_CONNECT_DELAY_MS));
+            }
+        }
+
+        child.lifecycle(ctx, event, data, env)
+    }
+}
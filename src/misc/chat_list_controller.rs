@@ -0,0 +1,97 @@
+use chrono::Local;
+use druid::widget::prelude::*;
+use druid::widget::{Controller, Scroll};
+use druid::{Selector, TimerToken, Vec2};
+
+use crate::widgets::chat_list::ChatList;
+use crate::ApplicationState;
+
+pub const CHAT_LIST_SCROLL_TO_TOP: Selector<()> = Selector::new("chat_list_scroll_to_top");
+pub const CHAT_LIST_SCROLL_TO_BOTTOM: Selector<()> = Selector::new("chat_list_scroll_to_bottom");
+
+// Wraps the chat's Scroll: handles "scroll to top/bottom" commands
+// and keeps the scroll position in the ChatList, because the Scroll is recreated
+// each time the chat is rebuilt (new message, returning from the settings, etc.).
+// Also rebuilds the chat at midnight so that the date separators show the new relative dates
+// and clears the unread badge once the user returned to the chat (moved the mouse over it).
+pub struct ChatListController {
+    is_position_restored: bool,
+    midnight_timer: TimerToken,
+}
+
+impl ChatListController {
+    pub fn new() -> Self {
+        ChatListController {
+            is_position_restored: false,
+            midnight_timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, Scroll<ApplicationState, W>>
+    for ChatListController
+{
+    fn event(
+        &mut self,
+        child: &mut Scroll<ApplicationState, W>,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::AnimFrame(_) if !self.is_position_restored => {
+                if ctx.size().height > 0.0 {
+                    // the layout is finished now, we can scroll
+                    let offset = data.main_layout.chat_list.restore_scroll_position();
+                    child.scroll_by(ctx, offset.to_vec2() - child.offset());
+                    self.is_position_restored = true;
+                } else {
+                    ctx.request_anim_frame();
+                }
+            }
+            Event::Timer(token) if *token == self.midnight_timer => {
+                // the rebuilt chat will start a new timer
+                data.main_layout.chat_list.update_date_separators();
+                return;
+            }
+            Event::MouseMove(_) if data.main_layout.chat_list.unread_count > 0 => {
+                data.main_layout.chat_list.clear_unread_count();
+            }
+            Event::Command(command) if command.is(CHAT_LIST_SCROLL_TO_TOP) => {
+                child.scroll_by(ctx, Vec2::new(0.0, -child.offset().y));
+            }
+            Event::Command(command) if command.is(CHAT_LIST_SCROLL_TO_BOTTOM) => {
+                child.scroll_by(ctx, Vec2::new(0.0, child.child_size().height));
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+
+        if self.is_position_restored {
+            let offset = child.offset().to_point();
+            if offset != data.main_layout.chat_list.scroll_offset {
+                data.main_layout.chat_list.save_scroll_position(offset);
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut Scroll<ApplicationState, W>,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.request_anim_frame();
+            self.midnight_timer = ctx.request_timer(ChatList::get_time_until_next_day(
+                Local::now().naive_local(),
+            ));
+        }
+
+        child.lifecycle(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,44 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{HotKey, KbKey, SysMods};
+
+use crate::misc::chat_list_controller::*;
+use crate::ApplicationState;
+use crate::Layout;
+
+// Wraps the whole widget tree (the chat usually does not have focus, the message box does),
+// scrolls the chat to the top on Ctrl+Home and to the bottom on Ctrl+End.
+pub struct ChatScrollShortcutController {}
+
+impl ChatScrollShortcutController {
+    pub fn new() -> Self {
+        ChatScrollShortcutController {}
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for ChatScrollShortcutController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::KeyDown(key) = event {
+            if data.current_layout == Layout::Main {
+                if HotKey::new(SysMods::Cmd, KbKey::Home).matches(key) {
+                    ctx.submit_command(CHAT_LIST_SCROLL_TO_TOP);
+                    ctx.set_handled();
+                    return;
+                } else if HotKey::new(SysMods::Cmd, KbKey::End).matches(key) {
+                    ctx.submit_command(CHAT_LIST_SCROLL_TO_BOTTOM);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,51 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Selector, TimerToken};
+use std::time::Duration;
+
+use crate::global_params::*;
+use crate::ApplicationState;
+
+// Not handled by the delegate, goes to the widget tree.
+pub const CLEAR_CHAT_UNDO_TIMER_START: Selector<()> = Selector::new("clear_chat_undo_timer_start");
+
+// Hides the "Undo" button (drops the undo buffer) after CLEAR_CHAT_UNDO_TIMEOUT_SEC.
+pub struct ClearChatUndoController {
+    timer: TimerToken,
+}
+
+impl ClearChatUndoController {
+    pub fn new() -> Self {
+        ClearChatUndoController {
+            timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for ClearChatUndoController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(command) if command.is(CLEAR_CHAT_UNDO_TIMER_START) => {
+                self.timer = ctx.request_timer(Duration::from_secs(CLEAR_CHAT_UNDO_TIMEOUT_SEC));
+                ctx.set_handled();
+                return;
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                data.main_layout.chat_list.drop_undo_buffer();
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
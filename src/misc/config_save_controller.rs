@@ -0,0 +1,307 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::TimerToken;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::global_params::*;
+use crate::services::user_tcp_service::ConnectResult;
+use crate::ApplicationState;
+
+// Decides when the changed config should be written: CONFIG_SAVE_DELAY_MS after
+// the last change, each change restarts the delay (so moving a slider does not
+// write the file on each step).
+pub struct ConfigSaveDebounce {
+    delay: Duration,
+    last_change_count: u64,
+    save_time: Option<Instant>, // None if nothing to save
+}
+
+impl ConfigSaveDebounce {
+    pub fn new(delay: Duration, change_count: u64) -> Self {
+        ConfigSaveDebounce {
+            delay,
+            last_change_count: change_count,
+            save_time: None,
+        }
+    }
+    /// Returns 'true' if the config was changed since the last call
+    /// (the save is then delayed until 'delay' after 'now').
+    pub fn on_change_count(&mut self, change_count: u64, now: Instant) -> bool {
+        if change_count == self.last_change_count {
+            return false;
+        }
+
+        self.last_change_count = change_count;
+        self.save_time = Some(now + self.delay);
+
+        true
+    }
+    /// Returns 'None' if there is nothing to save, zero if the config should be saved now.
+    pub fn get_time_until_save(&self, now: Instant) -> Option<Duration> {
+        self.save_time
+            .map(|save_time| save_time.saturating_duration_since(now))
+    }
+    pub fn on_saved(&mut self) {
+        self.save_time = None;
+    }
+}
+
+// Wraps the whole widget tree, writes the config file using 'ConfigSaveDebounce'
+// (settings handlers only mark the config as dirty).
+pub struct ConfigSaveController {
+    save_timer: TimerToken,
+    change_count: Option<Arc<AtomicU64>>, // taken from the config when the widget is added
+    debounce: ConfigSaveDebounce,
+}
+
+impl ConfigSaveController {
+    pub fn new() -> Self {
+        ConfigSaveController {
+            save_timer: TimerToken::INVALID,
+            change_count: None,
+            debounce: ConfigSaveDebounce::new(Duration::from_millis(CONFIG_SAVE_DELAY_MS), 0),
+        }
+    }
+    fn save(data: &mut ApplicationState) {
+        let result = data.user_config.lock().unwrap().save_if_dirty();
+        if let Err(err) = result {
+            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+            if !data.is_connected {
+                data.connect_layout
+                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
+            } else {
+                data.main_layout.add_system_message(error_msg);
+            }
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for ConfigSaveController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if *token == self.save_timer {
+                self.save_timer = TimerToken::INVALID;
+
+                match self.debounce.get_time_until_save(Instant::now()) {
+                    Some(time_left) if time_left == Duration::ZERO => {
+                        self.debounce.on_saved();
+                        ConfigSaveController::save(data);
+                    }
+                    Some(time_left) => self.save_timer = ctx.request_timer(time_left),
+                    None => {}
+                }
+
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            let change_count = data.user_config.lock().unwrap().get_change_count();
+            self.debounce = ConfigSaveDebounce::new(
+                Duration::from_millis(CONFIG_SAVE_DELAY_MS),
+                change_count.load(Ordering::SeqCst),
+            );
+            self.change_count = Some(change_count);
+        }
+
+        child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        // settings are shown in the data, so a setting change always triggers an update,
+        // the old timer is then ignored and a new one is started
+        if let Some(change_count) = &self.change_count {
+            if self
+                .debounce
+                .on_change_count(change_count.load(Ordering::SeqCst), Instant::now())
+            {
+                self.save_timer = ctx.request_timer(Duration::from_millis(CONFIG_SAVE_DELAY_MS));
+            }
+        }
+
+        child.update(ctx, old_data, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::config_service::UserConfig;
+
+    const DELAY: Duration = Duration::from_millis(CONFIG_SAVE_DELAY_MS);
+
+    /// Marks the config as dirty and passes the change to the debounce.
+    fn change_config(config: &mut UserConfig, debounce: &mut ConfigSaveDebounce, now: Instant) {
+        config.mark_dirty();
+        assert!(debounce.on_change_count(config.get_change_count().load(Ordering::SeqCst), now));
+    }
+
+    /// Saves the config if the debounce allows it, returns the number of saves.
+    fn save_if_due(
+        config: &mut UserConfig,
+        debounce: &mut ConfigSaveDebounce,
+        now: Instant,
+    ) -> usize {
+        if debounce.get_time_until_save(now) != Some(Duration::ZERO) {
+            return 0;
+        }
+        debounce.on_saved();
+
+        let mut save_count = 0;
+        config
+            .save_if_dirty_with(|_config| {
+                save_count += 1;
+                Ok(())
+            })
+            .unwrap();
+
+        save_count
+    }
+
+    #[test]
+    fn nothing_is_saved_without_changes() {
+        let mut config = UserConfig::empty();
+        let start = Instant::now();
+        let mut debounce = ConfigSaveDebounce::new(DELAY, 0);
+
+        assert!(!debounce.on_change_count(0, start));
+        assert_eq!(debounce.get_time_until_save(start + DELAY * 10), None);
+        assert_eq!(
+            save_if_due(&mut config, &mut debounce, start + DELAY * 10),
+            0
+        );
+        assert!(!config.is_dirty());
+    }
+
+    #[test]
+    fn change_is_saved_after_delay() {
+        let mut config = UserConfig::empty();
+        let start = Instant::now();
+        let mut debounce = ConfigSaveDebounce::new(DELAY, 0);
+
+        change_config(&mut config, &mut debounce, start);
+
+        assert_eq!(debounce.get_time_until_save(start), Some(DELAY));
+        let almost = start + DELAY - Duration::from_millis(1);
+        assert_eq!(save_if_due(&mut config, &mut debounce, almost), 0);
+        assert!(config.is_dirty());
+
+        assert_eq!(save_if_due(&mut config, &mut debounce, start + DELAY), 1);
+        assert!(!config.is_dirty());
+
+        // saved only once
+        assert_eq!(
+            save_if_due(&mut config, &mut debounce, start + DELAY * 2),
+            0
+        );
+    }
+
+    #[test]
+    fn each_change_restarts_delay() {
+        let mut config = UserConfig::empty();
+        let start = Instant::now();
+        let mut debounce = ConfigSaveDebounce::new(DELAY, 0);
+
+        // a slider is moved every 100 ms for 3 seconds
+        let step = Duration::from_millis(100);
+        let mut now = start;
+        for _ in 0..30 {
+            change_config(&mut config, &mut debounce, now);
+            assert_eq!(save_if_due(&mut config, &mut debounce, now), 0);
+            now += step;
+        }
+        let last_change = now - step;
+
+        assert_eq!(
+            debounce.get_time_until_save(last_change + step),
+            Some(DELAY - step)
+        );
+        assert_eq!(
+            save_if_due(
+                &mut config,
+                &mut debounce,
+                last_change + DELAY - Duration::from_millis(1)
+            ),
+            0
+        );
+        assert_eq!(
+            save_if_due(&mut config, &mut debounce, last_change + DELAY),
+            1
+        );
+        assert!(!config.is_dirty());
+    }
+
+    #[test]
+    fn change_after_save_is_saved_again() {
+        let mut config = UserConfig::empty();
+        let start = Instant::now();
+        let mut debounce = ConfigSaveDebounce::new(DELAY, 0);
+        change_config(&mut config, &mut debounce, start);
+        assert_eq!(save_if_due(&mut config, &mut debounce, start + DELAY), 1);
+
+        let second_change = start + DELAY * 3;
+        change_config(&mut config, &mut debounce, second_change);
+
+        assert_eq!(save_if_due(&mut config, &mut debounce, second_change), 0);
+        assert_eq!(
+            save_if_due(&mut config, &mut debounce, second_change + DELAY),
+            1
+        );
+    }
+
+    #[test]
+    fn immediate_save_leaves_nothing_to_save() {
+        let mut config = UserConfig::empty();
+        let start = Instant::now();
+        let mut debounce = ConfigSaveDebounce::new(DELAY, 0);
+        change_config(&mut config, &mut debounce, start);
+
+        // disconnect or window close
+        config.save_if_dirty_with(|_config| Ok(())).unwrap();
+
+        assert_eq!(save_if_due(&mut config, &mut debounce, start + DELAY), 0);
+    }
+
+    #[test]
+    fn failed_save_keeps_config_dirty() {
+        let mut config = UserConfig::empty();
+        config.mark_dirty();
+
+        let result = config.save_if_dirty_with(|_config| {
+            Err(crate::error::SilentError::ConfigError(String::from(
+                "disk is full",
+            )))
+        });
+
+        assert!(result.is_err());
+        assert!(config.is_dirty());
+    }
+}
@@ -0,0 +1,252 @@
+// External.
+use druid::widget::prelude::*;
+use druid::widget::{Either, EnvScope, Flex, Label, LineBreaking, SizedBox};
+use druid::{Color, WidgetExt};
+
+// Std.
+use std::rc::Rc;
+
+// Custom.
+use crate::error::SilentError;
+use crate::global_params::*;
+use crate::layouts::settings_layout::SettingsLayout;
+use crate::misc::locale_keys::*;
+use crate::services::config_service::{ConfigField, UserConfig};
+use crate::services::user_tcp_service::ConnectResult;
+use crate::widgets::confirm_dialog::ConfirmDialog;
+use crate::ApplicationState;
+
+// Checks the config when the settings are closed, if some values are invalid
+// asks the user to fix them or to discard the changes.
+pub struct ConfigValidatorUi;
+
+impl ConfigValidatorUi {
+    /// Returns 'true' if the settings can be closed, otherwise shows the summary
+    /// of the invalid values (see 'build_summary_ui').
+    pub fn check_before_close(data: &mut ApplicationState) -> bool {
+        let result = data.user_config.lock().unwrap().validate();
+
+        match result {
+            Ok(()) => {
+                data.settings_layout.config_errors = Rc::new(Vec::new());
+                data.settings_layout.is_fixing_config = false;
+                true
+            }
+            Err(errors) => {
+                data.settings_layout.config_errors = Rc::new(errors);
+                false
+            }
+        }
+    }
+    /// List of the invalid values with "Fix Now" and "Discard Changes" buttons,
+    /// empty if 'check_before_close' did not find any problems.
+    pub fn build_summary_ui() -> impl Widget<ApplicationState> {
+        Either::new(
+            |data: &ApplicationState, _env| !data.settings_layout.config_errors.is_empty(),
+            Flex::column()
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        format!("- {}", data.settings_layout.config_errors.join("\n- "))
+                    })
+                    .with_text_color(Color::RED)
+                    .with_line_break_mode(LineBreaking::WordWrap)
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_default_spacer()
+                .with_child(ConfirmDialog::with_answers(
+                    LOCALE_SETTINGS_LAYOUT_CONFIG_INVALID_ON_CLOSE_TEXT,
+                    LOCALE_SETTINGS_LAYOUT_CONFIG_FIX_NOW_TEXT,
+                    LOCALE_SETTINGS_LAYOUT_CONFIG_DISCARD_CHANGES_TEXT,
+                    ConfigValidatorUi::on_fix_now_clicked,
+                    ConfigValidatorUi::on_discard_changes_clicked,
+                ))
+                .padding(10.0),
+            SizedBox::empty(),
+        )
+    }
+    /// Draws red borders in the widget while the user is fixing the invalid values
+    /// (after "Fix Now" was clicked) and the value of 'field' is still invalid.
+    pub fn highlight_if_invalid(
+        field: ConfigField,
+        widget: impl Widget<ApplicationState> + 'static,
+    ) -> impl Widget<ApplicationState> {
+        EnvScope::new(
+            move |env, data: &ApplicationState| {
+                if ConfigValidatorUi::is_highlighted(data, field) {
+                    env.set(druid::theme::BORDER_DARK, Color::RED);
+                    env.set(druid::theme::BORDER_LIGHT, Color::RED);
+                }
+            },
+            widget,
+        )
+    }
+    pub fn is_highlighted(data: &ApplicationState, field: ConfigField) -> bool {
+        if !data.settings_layout.is_fixing_config {
+            return false;
+        }
+
+        data.user_config
+            .lock()
+            .unwrap()
+            .validate_fields()
+            .iter()
+            .any(|(invalid_field, _)| *invalid_field == field)
+    }
+    /// Hides the summary and highlights the invalid values.
+    pub fn start_fixing(data: &mut ApplicationState) {
+        data.settings_layout.config_errors = Rc::new(Vec::new());
+        data.settings_layout.is_fixing_config = true;
+    }
+    /// Replaces the config with the saved one (it should be applied then),
+    /// returns 'false' if the saved config could not be opened (the error is shown).
+    pub fn discard_changes(
+        data: &mut ApplicationState,
+        saved_config: Result<UserConfig, SilentError>,
+    ) -> bool {
+        data.settings_layout.config_errors = Rc::new(Vec::new());
+        data.settings_layout.is_fixing_config = false;
+
+        match saved_config {
+            Ok(saved_config) => {
+                data.user_config.lock().unwrap().replace_with(saved_config);
+                true
+            }
+            Err(err) => {
+                let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+                if !data.is_connected {
+                    data.connect_layout
+                        .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
+                } else {
+                    data.main_layout.add_system_message(error_msg);
+                }
+                false
+            }
+        }
+    }
+    fn on_fix_now_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        ConfigValidatorUi::start_fixing(data);
+    }
+    fn on_discard_changes_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
+        // invalid values are never saved so the config file has the last valid settings
+        if ConfigValidatorUi::discard_changes(data, UserConfig::open()) {
+            crate::apply_config(data);
+            SettingsLayout::close(ctx, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{temp_file_path, test_application_state};
+    use std::sync::Arc;
+
+    fn state_with_invalid_volume() -> ApplicationState {
+        let data = test_application_state();
+        data.user_config.lock().unwrap().master_volume = 150;
+        data
+    }
+
+    #[test]
+    fn valid_config_closes_settings() {
+        let mut data = test_application_state();
+
+        assert!(ConfigValidatorUi::check_before_close(&mut data));
+        assert!(data.settings_layout.config_errors.is_empty());
+        assert!(!data.settings_layout.is_fixing_config);
+    }
+
+    #[test]
+    fn invalid_config_shows_summary() {
+        let mut data = state_with_invalid_volume();
+        data.user_config.lock().unwrap().microphone_volume = 150;
+
+        assert!(!ConfigValidatorUi::check_before_close(&mut data));
+
+        // all problems are listed
+        assert_eq!(data.settings_layout.config_errors.len(), 2);
+        assert!(data.settings_layout.config_errors[0].contains("master volume (150)"));
+        // nothing is highlighted until "Fix Now" is clicked
+        assert!(!ConfigValidatorUi::is_highlighted(
+            &data,
+            ConfigField::MasterVolume
+        ));
+    }
+
+    #[test]
+    fn fix_now_highlights_invalid_fields() {
+        let mut data = state_with_invalid_volume();
+        assert!(!ConfigValidatorUi::check_before_close(&mut data));
+
+        ConfigValidatorUi::start_fixing(&mut data);
+
+        assert!(data.settings_layout.config_errors.is_empty());
+        assert!(ConfigValidatorUi::is_highlighted(
+            &data,
+            ConfigField::MasterVolume
+        ));
+        assert!(!ConfigValidatorUi::is_highlighted(
+            &data,
+            ConfigField::MicrophoneVolume
+        ));
+
+        // fixed value is not highlighted anymore
+        data.user_config.lock().unwrap().master_volume = 100;
+        assert!(!ConfigValidatorUi::is_highlighted(
+            &data,
+            ConfigField::MasterVolume
+        ));
+
+        assert!(ConfigValidatorUi::check_before_close(&mut data));
+        assert!(!data.settings_layout.is_fixing_config);
+    }
+
+    #[test]
+    fn discard_changes_reverts_to_saved_config() {
+        let path = temp_file_path("discard_changes.cfg");
+        let mut saved_config = UserConfig::empty();
+        saved_config.master_volume = 70;
+        saved_config.save_to_path(&path).unwrap();
+
+        let mut data = state_with_invalid_volume();
+        assert!(!ConfigValidatorUi::check_before_close(&mut data));
+        let change_count = data.user_config.lock().unwrap().get_change_count();
+
+        let discarded =
+            ConfigValidatorUi::discard_changes(&mut data, UserConfig::open_from_path(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(discarded);
+        assert!(data.settings_layout.config_errors.is_empty());
+        assert!(!data.settings_layout.is_fixing_config);
+
+        let config_guard = data.user_config.lock().unwrap();
+        assert_eq!(config_guard.master_volume, 70);
+        assert!(config_guard.validate().is_ok());
+        // 'ConfigSaveController' still sees new changes
+        assert!(Arc::ptr_eq(&config_guard.get_change_count(), &change_count));
+    }
+
+    #[test]
+    fn discard_changes_keeps_settings_open_if_saved_config_is_broken() {
+        let mut data = state_with_invalid_volume();
+        data.is_connected = true;
+        assert!(!ConfigValidatorUi::check_before_close(&mut data));
+
+        let discarded = ConfigValidatorUi::discard_changes(
+            &mut data,
+            Err(SilentError::ConfigError(String::from("broken config"))),
+        );
+
+        assert!(!discarded);
+        assert_eq!(data.user_config.lock().unwrap().master_volume, 150);
+        assert!(data
+            .main_layout
+            .chat_list
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|message| message.message.contains("broken config")));
+    }
+}
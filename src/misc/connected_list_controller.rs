@@ -0,0 +1,45 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{ContextMenu, LocalizedString, MenuDesc, MenuItem, Selector};
+
+use crate::misc::locale_keys::*;
+use crate::ApplicationState;
+
+pub const CONNECTED_LIST_COPY_ALL_USERNAMES: Selector<()> =
+    Selector::new("connected_list_copy_all_usernames");
+
+// Shows a context menu on right click on the list's empty space
+// (room and user buttons mark right clicks as handled).
+pub struct ConnectedListController;
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for ConnectedListController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        child.event(ctx, event, data, env);
+
+        if let Event::MouseDown(mouse) = event {
+            if mouse.button.is_right() && !ctx.is_handled() {
+                let copy_text = data
+                    .localization
+                    .get(LOCALE_MAIN_LAYOUT_COPY_ALL_USERNAMES_TEXT)
+                    .unwrap()
+                    .clone();
+
+                let menu = MenuDesc::<ApplicationState>::empty().append(MenuItem::new(
+                    LocalizedString::new("connected-list-copy-all-usernames")
+                        .with_placeholder(copy_text),
+                    CONNECTED_LIST_COPY_ALL_USERNAMES,
+                ));
+
+                ctx.show_context_menu(ContextMenu::new(menu, mouse.window_pos));
+                ctx.set_handled();
+            }
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use druid::widget::prelude::*;
 use druid::widget::{Button, Controller};
-use druid::{Selector, Target};
+use druid::{ContextMenu, LocalizedString, MenuDesc, MenuItem, Selector, Target};
 
+use crate::misc::accessibility_controller::AccessibilityController;
+use crate::misc::locale_keys::*;
+use crate::widgets::room_info_popup::CONNECTED_LIST_VIEW_ROOM_INFO;
 use crate::ApplicationState;
 
 pub const CUSTOM_DATA_BUTTON_CLICKED: Selector<CustomButtonData> =
@@ -15,11 +18,19 @@ pub enum CustomButtonData {
 
 pub struct CustomDataButtonController {
     data: CustomButtonData,
+    pub aria_label: Option<String>, // description for screen readers
 }
 
 impl CustomDataButtonController {
     pub fn new(data: CustomButtonData) -> Self {
-        CustomDataButtonController { data }
+        CustomDataButtonController {
+            data,
+            aria_label: None,
+        }
+    }
+    pub fn with_aria_label(mut self, aria_label: String) -> Self {
+        self.aria_label = Some(aria_label);
+        self
     }
 }
 
@@ -33,6 +44,30 @@ impl Controller<ApplicationState, Button<ApplicationState>> for CustomDataButton
         env: &Env,
     ) {
         match event {
+            Event::MouseDown(mouse) if mouse.button.is_right() => {
+                if let CustomButtonData::ConnectedListData {
+                    is_room: true,
+                    button_name,
+                } = &self.data
+                {
+                    let view_info_text = data
+                        .localization
+                        .get(LOCALE_MAIN_LAYOUT_VIEW_ROOM_INFO_TEXT)
+                        .unwrap()
+                        .clone();
+
+                    let menu = MenuDesc::<ApplicationState>::empty().append(MenuItem::new(
+                        LocalizedString::new("connected-list-view-room-info")
+                            .with_placeholder(view_info_text),
+                        CONNECTED_LIST_VIEW_ROOM_INFO.with(button_name.clone()),
+                    ));
+
+                    ctx.show_context_menu(ContextMenu::new(menu, mouse.window_pos));
+                }
+
+                // don't let parents (list context menu) handle it
+                ctx.set_handled();
+            }
             Event::MouseUp(_) => {
                 ctx.get_external_handle()
                     .submit_command(CUSTOM_DATA_BUTTON_CLICKED, self.data.clone(), Target::Auto)
@@ -42,4 +77,17 @@ impl Controller<ApplicationState, Button<ApplicationState>> for CustomDataButton
             _ => child.event(ctx, event, data, env),
         }
     }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut Button<ApplicationState>,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        AccessibilityController::on_lifecycle(&self.aria_label, ctx, event);
+
+        child.lifecycle(ctx, event, data, env)
+    }
 }
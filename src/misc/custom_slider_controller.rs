@@ -2,6 +2,7 @@ use druid::widget::prelude::*;
 use druid::widget::{Controller, SizedBox};
 use druid::{Selector, Target};
 
+use crate::misc::accessibility_controller::AccessibilityController;
 use crate::CustomSliderID;
 
 pub const CUSTOM_SLIDER_ON_VALUE_CHANGED: Selector<OnCustomSliderMovedInfo> =
@@ -15,6 +16,7 @@ pub struct OnCustomSliderMovedInfo {
 pub struct CustomSliderController {
     is_lmb_pressed: bool,
     pub custom_slider_id: CustomSliderID,
+    pub aria_label: Option<String>, // description for screen readers
 }
 
 impl CustomSliderController {
@@ -22,8 +24,13 @@ impl CustomSliderController {
         CustomSliderController {
             is_lmb_pressed: false,
             custom_slider_id,
+            aria_label: None,
         }
     }
+    pub fn with_aria_label(mut self, aria_label: String) -> Self {
+        self.aria_label = Some(aria_label);
+        self
+    }
 }
 
 impl Controller<f64, SizedBox<f64>> for CustomSliderController {
@@ -60,4 +67,17 @@ impl Controller<f64, SizedBox<f64>> for CustomSliderController {
             _ => child.event(ctx, event, data, env),
         }
     }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut SizedBox<f64>,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &f64,
+        env: &Env,
+    ) {
+        AccessibilityController::on_lifecycle(&self.aria_label, ctx, event);
+
+        child.lifecycle(ctx, event, data, env)
+    }
 }
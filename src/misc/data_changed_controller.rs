@@ -0,0 +1,44 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+
+use crate::ApplicationState;
+
+// Calls the callback when the value returned by 'get_value' was changed
+// by an event of the child (like a radio group click), used to save the new value.
+pub struct DataChangedController<V: PartialEq> {
+    get_value: fn(&ApplicationState) -> V,
+    on_changed: fn(&mut ApplicationState),
+}
+
+impl<V: PartialEq> DataChangedController<V> {
+    pub fn new(
+        get_value: fn(&ApplicationState) -> V,
+        on_changed: fn(&mut ApplicationState),
+    ) -> Self {
+        DataChangedController {
+            get_value,
+            on_changed,
+        }
+    }
+}
+
+impl<V: PartialEq, W: Widget<ApplicationState>> Controller<ApplicationState, W>
+    for DataChangedController<V>
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        let old_value = (self.get_value)(data);
+
+        child.event(ctx, event, data, env);
+
+        if (self.get_value)(data) != old_value {
+            (self.on_changed)(data);
+        }
+    }
+}
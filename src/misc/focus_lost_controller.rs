@@ -0,0 +1,30 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::Selector;
+
+// Submits the command when a child widget (like a text box) loses focus.
+pub struct FocusLostController {
+    command: Selector,
+}
+
+impl FocusLostController {
+    pub fn new(command: Selector) -> Self {
+        FocusLostController { command }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for FocusLostController {
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::FocusChanged(false) = event {
+            ctx.submit_command(self.command);
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
@@ -1,13 +1,48 @@
 use druid::text::{Formatter, Selection, Validation, ValidationError};
+use unicode_segmentation::UnicodeSegmentation;
+
+// How the length of the text is measured.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MaxCharactersFormatterMode {
+    Bytes,        // UTF-8 bytes, text is only cut on the char boundary
+    UnicodeChars, // Unicode scalar values
+    Graphemes,    // user-perceived characters (one emoji with modifiers is one grapheme)
+}
 
 // Formatter that sets the maximum length of the text.
 pub struct MaxCharactersFormatter {
     max_chars: usize,
+    mode: MaxCharactersFormatterMode,
 }
 
 impl MaxCharactersFormatter {
-    pub fn new(max_chars: usize) -> Self {
-        Self { max_chars }
+    pub fn new(max_chars: usize, mode: MaxCharactersFormatterMode) -> Self {
+        Self { max_chars, mode }
+    }
+    /// Returns the byte length of the longest prefix of the input that fits into the limit.
+    pub fn get_allowed_len(&self, input: &str) -> usize {
+        match self.mode {
+            MaxCharactersFormatterMode::Bytes => {
+                if input.len() <= self.max_chars {
+                    return input.len();
+                }
+                let mut len = self.max_chars;
+                while !input.is_char_boundary(len) {
+                    len -= 1;
+                }
+                len
+            }
+            MaxCharactersFormatterMode::UnicodeChars => input
+                .char_indices()
+                .nth(self.max_chars)
+                .map(|(i, _)| i)
+                .unwrap_or(input.len()),
+            MaxCharactersFormatterMode::Graphemes => input
+                .grapheme_indices(true)
+                .nth(self.max_chars)
+                .map(|(i, _)| i)
+                .unwrap_or(input.len()),
+        }
     }
 }
 
@@ -21,17 +56,157 @@ impl Formatter<String> for MaxCharactersFormatter {
     }
 
     fn validate_partial_input(&self, input: &str, _sel: &Selection) -> Validation {
-        if input.chars().count() > self.max_chars {
-            Validation::success().change_text(
-                input[..input
-                    .chars()
-                    .map(|c| c.len_utf8())
-                    .take(self.max_chars)
-                    .sum()]
-                    .to_string(),
-            )
+        let allowed_len = self.get_allowed_len(input);
+        if allowed_len < input.len() {
+            Validation::success().change_text(input[..allowed_len].to_string())
         } else {
             Validation::success()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THUMBS_UP_DARK: &str = "👍🏿"; // emoji + skin tone modifier (2 chars, 8 bytes)
+    const FAMILY: &str = "👨‍👩‍👧"; // joined with ZWJ (5 chars, 18 bytes)
+
+    fn allowed(input: &str, max_chars: usize, mode: MaxCharactersFormatterMode) -> &str {
+        &input[..MaxCharactersFormatter::new(max_chars, mode).get_allowed_len(input)]
+    }
+
+    #[test]
+    fn ascii_is_same_in_all_modes() {
+        for mode in [
+            MaxCharactersFormatterMode::Bytes,
+            MaxCharactersFormatterMode::UnicodeChars,
+            MaxCharactersFormatterMode::Graphemes,
+        ]
+        .iter()
+        {
+            assert_eq!(allowed("username", 8, *mode), "username");
+            assert_eq!(allowed("username1", 8, *mode), "username");
+            assert_eq!(allowed("user", 8, *mode), "user");
+            assert_eq!(allowed("", 8, *mode), "");
+            assert_eq!(allowed("user", 0, *mode), "");
+        }
+    }
+
+    #[test]
+    fn latin_extended_at_boundary() {
+        let text = "Çağrı Łukasz";
+
+        assert_eq!(
+            allowed(text, 5, MaxCharactersFormatterMode::UnicodeChars),
+            "Çağrı"
+        );
+        assert_eq!(
+            allowed(text, 5, MaxCharactersFormatterMode::Graphemes),
+            "Çağrı"
+        );
+        // 'Ç', 'ğ' and 'ı' take 2 bytes each
+        assert_eq!(allowed(text, 4, MaxCharactersFormatterMode::Bytes), "Ça");
+        assert_eq!(allowed(text, 5, MaxCharactersFormatterMode::Bytes), "Çağ");
+        assert_eq!(allowed(text, 8, MaxCharactersFormatterMode::Bytes), "Çağrı");
+    }
+
+    #[test]
+    fn combining_mark_is_part_of_grapheme() {
+        let text = "cafe\u{301}s"; // "e" + combining acute accent
+
+        assert_eq!(
+            allowed(text, 4, MaxCharactersFormatterMode::Graphemes),
+            "cafe\u{301}"
+        );
+        assert_eq!(
+            allowed(text, 4, MaxCharactersFormatterMode::UnicodeChars),
+            "cafe"
+        );
+    }
+
+    #[test]
+    fn cjk_at_boundary() {
+        let text = "你好世界";
+
+        assert_eq!(
+            allowed(text, 3, MaxCharactersFormatterMode::UnicodeChars),
+            "你好世"
+        );
+        assert_eq!(
+            allowed(text, 3, MaxCharactersFormatterMode::Graphemes),
+            "你好世"
+        );
+        assert_eq!(
+            allowed(text, 4, MaxCharactersFormatterMode::Graphemes),
+            text
+        );
+        // 3 bytes each
+        assert_eq!(allowed(text, 6, MaxCharactersFormatterMode::Bytes), "你好");
+        assert_eq!(allowed(text, 12, MaxCharactersFormatterMode::Bytes), text);
+    }
+
+    #[test]
+    fn bytes_mode_cuts_on_char_boundary() {
+        let text = "你好";
+
+        for max_bytes in 3..6 {
+            assert_eq!(
+                allowed(text, max_bytes, MaxCharactersFormatterMode::Bytes),
+                "你"
+            );
+        }
+        assert_eq!(allowed(text, 2, MaxCharactersFormatterMode::Bytes), "");
+
+        let text = format!("a{}", THUMBS_UP_DARK);
+        for max_bytes in 1..5 {
+            assert_eq!(
+                allowed(&text, max_bytes, MaxCharactersFormatterMode::Bytes),
+                "a"
+            );
+        }
+        assert_eq!(allowed(&text, 5, MaxCharactersFormatterMode::Bytes), "a👍");
+    }
+
+    #[test]
+    fn emoji_with_modifiers_is_one_grapheme() {
+        let text = format!("{}{}!", THUMBS_UP_DARK, FAMILY);
+
+        assert_eq!(
+            allowed(&text, 1, MaxCharactersFormatterMode::Graphemes),
+            THUMBS_UP_DARK
+        );
+        assert_eq!(
+            allowed(&text, 2, MaxCharactersFormatterMode::Graphemes),
+            format!("{}{}", THUMBS_UP_DARK, FAMILY)
+        );
+        assert_eq!(
+            allowed(&text, 3, MaxCharactersFormatterMode::Graphemes),
+            text
+        );
+
+        // the modifier is a separate char
+        assert_eq!(
+            allowed(&text, 1, MaxCharactersFormatterMode::UnicodeChars),
+            "👍"
+        );
+        assert_eq!(
+            allowed(&text, 3, MaxCharactersFormatterMode::UnicodeChars),
+            format!("{}👨", THUMBS_UP_DARK)
+        );
+    }
+
+    #[test]
+    fn regional_indicator_flag_is_one_grapheme() {
+        let text = "🇺🇦🇯🇵";
+
+        assert_eq!(
+            allowed(text, 1, MaxCharactersFormatterMode::Graphemes),
+            "🇺🇦"
+        );
+        assert_eq!(
+            allowed(text, 1, MaxCharactersFormatterMode::UnicodeChars),
+            "🇺"
+        );
+    }
+}
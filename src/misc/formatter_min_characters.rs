@@ -0,0 +1,90 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+// Checks the minimum length of the text (unlike formatters it can't block the input
+// because the text is always too short while it's being typed).
+pub struct MinCharactersValidator {
+    min_chars: usize,
+}
+
+impl MinCharactersValidator {
+    pub fn new(min_chars: usize) -> Self {
+        Self { min_chars }
+    }
+    /// Empty input is not considered too short (nothing was typed yet).
+    /// Length is measured in graphemes (like the username formatter does).
+    pub fn is_too_short(&self, input: &str) -> bool {
+        let grapheme_count = input.graphemes(true).count();
+        grapheme_count != 0 && grapheme_count < self.min_chars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::global_params::*;
+    use crate::misc::formatter_max_characters::*;
+
+    // Returns the text that the username field will contain after the input
+    // and whether the "too short" notice is shown.
+    fn enter_username(input: &str) -> (String, bool) {
+        let formatter =
+            MaxCharactersFormatter::new(MAX_USERNAME_SIZE, MaxCharactersFormatterMode::Graphemes);
+        let text = &input[..formatter.get_allowed_len(input)];
+
+        (
+            String::from(text),
+            MinCharactersValidator::new(MIN_USERNAME_SIZE).is_too_short(text),
+        )
+    }
+
+    #[test]
+    fn empty_input_is_not_too_short() {
+        assert_eq!(enter_username(""), (String::new(), false));
+    }
+
+    #[test]
+    fn notice_is_shown_until_min_length_is_typed() {
+        let username = "a".repeat(MAX_USERNAME_SIZE);
+
+        for len in 1..=MAX_USERNAME_SIZE {
+            let (text, is_too_short) = enter_username(&username[..len]);
+            assert_eq!(text, &username[..len]);
+            assert_eq!(is_too_short, len < MIN_USERNAME_SIZE, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn input_is_cut_at_max_length() {
+        let (text, is_too_short) = enter_username(&"a".repeat(MAX_USERNAME_SIZE + 5));
+
+        assert_eq!(text, "a".repeat(MAX_USERNAME_SIZE));
+        assert!(!is_too_short);
+    }
+
+    #[test]
+    fn multibyte_input_is_measured_in_graphemes() {
+        // one character, but 2 bytes
+        assert_eq!(enter_username("é"), (String::from("é"), true));
+        assert_eq!(enter_username("éé"), (String::from("éé"), false));
+
+        // one grapheme, but 2 chars
+        assert_eq!(enter_username("👍🏽"), (String::from("👍🏽"), true));
+        assert_eq!(enter_username("👍🏽👍🏽"), (String::from("👍🏽👍🏽"), false));
+
+        // max length in graphemes, not bytes
+        let (text, is_too_short) = enter_username(&"👍🏽".repeat(MAX_USERNAME_SIZE + 1));
+        assert_eq!(text, "👍🏽".repeat(MAX_USERNAME_SIZE));
+        assert!(!is_too_short);
+    }
+
+    #[test]
+    fn custom_min_length() {
+        let validator = MinCharactersValidator::new(4);
+
+        assert!(!validator.is_too_short(""));
+        assert!(validator.is_too_short("abc"));
+        assert!(!validator.is_too_short("abcd"));
+
+        assert!(!MinCharactersValidator::new(0).is_too_short("a"));
+    }
+}
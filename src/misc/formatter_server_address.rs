@@ -0,0 +1,315 @@
+use druid::text::{Formatter, Selection, Validation, ValidationError};
+
+// Std.
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+const MAX_HOSTNAME_LENGTH: usize = 253;
+const MAX_HOSTNAME_LABEL_LENGTH: usize = 63;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerAddressError {
+    Empty,
+    InvalidCharacter(char),
+    TooLong,
+    InvalidIpv4,
+    InvalidIpv6,
+    InvalidHostname,
+}
+
+impl std::fmt::Display for ServerAddressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerAddressError::Empty => write!(f, "the address is empty"),
+            ServerAddressError::InvalidCharacter(c) => {
+                write!(f, "the character '{}' is not allowed in an address", c)
+            }
+            ServerAddressError::TooLong => write!(f, "the address is too long"),
+            ServerAddressError::InvalidIpv4 => write!(f, "invalid IPv4 address"),
+            ServerAddressError::InvalidIpv6 => write!(f, "invalid IPv6 address"),
+            ServerAddressError::InvalidHostname => write!(f, "invalid hostname"),
+        }
+    }
+}
+
+impl std::error::Error for ServerAddressError {}
+
+// Formatter that only allows characters used in hostnames, IPv4 and (bracketed) IPv6 addresses.
+// The full address is checked by 'validate_full_input' (on connect).
+pub struct ServerAddressFormatter;
+
+impl ServerAddressFormatter {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn validate_partial_input(input: &str) -> Result<(), ServerAddressError> {
+        if input.len() > MAX_HOSTNAME_LENGTH + 2 {
+            // + 2 for IPv6 brackets
+            return Err(ServerAddressError::TooLong);
+        }
+
+        if input.starts_with('[') {
+            // IPv6 literal
+            let inner = &input[1..];
+            let inner = inner.strip_suffix(']').unwrap_or(inner);
+            for c in inner.chars() {
+                if !(c.is_ascii_hexdigit() || c == ':' || c == '.') {
+                    return Err(ServerAddressError::InvalidCharacter(c));
+                }
+            }
+            return Ok(());
+        }
+
+        for c in input.chars() {
+            if !(c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+                return Err(ServerAddressError::InvalidCharacter(c));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_full_input(input: &str) -> Result<(), ServerAddressError> {
+        if input.is_empty() {
+            return Err(ServerAddressError::Empty);
+        }
+
+        if let Err(e) = ServerAddressFormatter::validate_partial_input(input) {
+            return Err(e);
+        }
+
+        if input.starts_with('[') {
+            if !input.ends_with(']') || input.len() < 2 {
+                return Err(ServerAddressError::InvalidIpv6);
+            }
+            if Ipv6Addr::from_str(&input[1..input.len() - 1]).is_err() {
+                return Err(ServerAddressError::InvalidIpv6);
+            }
+            return Ok(());
+        }
+
+        if input.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return ServerAddressFormatter::validate_ipv4(input);
+        }
+
+        ServerAddressFormatter::validate_hostname(input)
+    }
+
+    fn validate_ipv4(input: &str) -> Result<(), ServerAddressError> {
+        let octets: Vec<&str> = input.split('.').collect();
+        if octets.len() != 4 {
+            return Err(ServerAddressError::InvalidIpv4);
+        }
+
+        for octet in octets {
+            if octet.is_empty() || octet.len() > 3 {
+                return Err(ServerAddressError::InvalidIpv4);
+            }
+            if octet.parse::<u8>().is_err() {
+                // more than 255
+                return Err(ServerAddressError::InvalidIpv4);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_hostname(input: &str) -> Result<(), ServerAddressError> {
+        if input.len() > MAX_HOSTNAME_LENGTH {
+            return Err(ServerAddressError::TooLong);
+        }
+
+        for label in input.split('.') {
+            if label.is_empty()
+                || label.len() > MAX_HOSTNAME_LABEL_LENGTH
+                || label.starts_with('-')
+                || label.ends_with('-')
+            {
+                return Err(ServerAddressError::InvalidHostname);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Formatter<String> for ServerAddressFormatter {
+    fn format(&self, value: &String) -> String {
+        value.to_owned()
+    }
+
+    fn value(&self, input: &str) -> Result<String, ValidationError> {
+        // don't block unfinished input (like "192.168."),
+        // full validation happens on connect
+        Ok(input.to_owned())
+    }
+
+    fn validate_partial_input(&self, input: &str, _sel: &Selection) -> Validation {
+        match ServerAddressFormatter::validate_partial_input(input) {
+            Ok(()) => Validation::success(),
+            Err(e) => Validation::failure(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_hostnames() {
+        for input in [
+            "localhost",
+            "example.com",
+            "my-server.example.com",
+            "a.b.c.d.e",
+        ] {
+            assert_eq!(
+                ServerAddressFormatter::validate_full_input(input),
+                Ok(()),
+                "{}",
+                input
+            );
+        }
+
+        let longest_label = "a".repeat(MAX_HOSTNAME_LABEL_LENGTH);
+        assert_eq!(
+            ServerAddressFormatter::validate_full_input(&longest_label),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_hostnames() {
+        for input in [
+            "-server.com",
+            "server-.com",
+            "server..com",
+            ".com",
+            "server.",
+        ] {
+            assert_eq!(
+                ServerAddressFormatter::validate_full_input(input),
+                Err(ServerAddressError::InvalidHostname),
+                "{}",
+                input
+            );
+        }
+
+        let too_long_label = "a".repeat(MAX_HOSTNAME_LABEL_LENGTH + 1);
+        assert_eq!(
+            ServerAddressFormatter::validate_full_input(&too_long_label),
+            Err(ServerAddressError::InvalidHostname)
+        );
+
+        let too_long_hostname = vec!["a".repeat(50); 6].join(".");
+        assert_eq!(
+            ServerAddressFormatter::validate_full_input(&too_long_hostname),
+            Err(ServerAddressError::TooLong)
+        );
+    }
+
+    #[test]
+    fn valid_ipv4() {
+        for input in ["127.0.0.1", "0.0.0.0", "255.255.255.255", "192.168.001.010"] {
+            assert_eq!(
+                ServerAddressFormatter::validate_full_input(input),
+                Ok(()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_ipv4() {
+        for input in [
+            "256.0.0.1",
+            "1.2.3",
+            "1.2.3.4.5",
+            "1..2.3",
+            "1.2.3.0001",
+            "...",
+        ] {
+            assert_eq!(
+                ServerAddressFormatter::validate_full_input(input),
+                Err(ServerAddressError::InvalidIpv4),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn valid_ipv6() {
+        for input in [
+            "[::1]",
+            "[::]",
+            "[2001:db8::8a2e:370:7334]",
+            "[::ffff:192.168.0.1]",
+        ] {
+            assert_eq!(
+                ServerAddressFormatter::validate_full_input(input),
+                Ok(()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_ipv6() {
+        for input in ["[", "[]", "[::1", "[1:2:3:4:5:6:7:8:9]", "[12345::]"] {
+            assert_eq!(
+                ServerAddressFormatter::validate_full_input(input),
+                Err(ServerAddressError::InvalidIpv6),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(
+            ServerAddressFormatter::validate_full_input(""),
+            Err(ServerAddressError::Empty)
+        );
+
+        // the user is still typing
+        assert_eq!(ServerAddressFormatter::validate_partial_input(""), Ok(()));
+    }
+
+    #[test]
+    fn invalid_characters() {
+        assert_eq!(
+            ServerAddressFormatter::validate_partial_input("server com"),
+            Err(ServerAddressError::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            ServerAddressFormatter::validate_partial_input("сервер"),
+            Err(ServerAddressError::InvalidCharacter('с'))
+        );
+        assert_eq!(
+            ServerAddressFormatter::validate_partial_input("server:51337"),
+            Err(ServerAddressError::InvalidCharacter(':'))
+        );
+        assert_eq!(
+            ServerAddressFormatter::validate_partial_input("[::g]"),
+            Err(ServerAddressError::InvalidCharacter('g'))
+        );
+    }
+
+    #[test]
+    fn unfinished_input_is_accepted_while_typing() {
+        for input in ["192.168.", "example.", "[2001:db8", "[::1]", "my-"] {
+            assert_eq!(
+                ServerAddressFormatter::validate_partial_input(input),
+                Ok(()),
+                "{}",
+                input
+            );
+        }
+    }
+}
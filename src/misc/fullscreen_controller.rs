@@ -0,0 +1,185 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::Point;
+use druid_shell::keyboard_types::Key;
+use rdev::display_size;
+
+use crate::services::user_tcp_service::ConnectResult;
+use crate::ApplicationState;
+
+// Wraps the whole widget tree, toggles fullscreen on F11.
+pub struct FullscreenController {
+    is_fullscreen: bool,
+    windowed_position: Point,
+}
+
+impl FullscreenController {
+    pub fn new() -> Self {
+        FullscreenController {
+            is_fullscreen: false,
+            windowed_position: Point::ORIGIN,
+        }
+    }
+    /// Returns the fullscreen state to switch to ('None' if the key press does not toggle it).
+    fn get_requested_state(&self, key: &Key, is_repeat: bool) -> Option<bool> {
+        if *key != Key::F11 || is_repeat {
+            // holding F11 must not flicker the window
+            return None;
+        }
+
+        Some(!self.is_fullscreen)
+    }
+    /// Returns 'false' if already in this state.
+    fn switch_state(&mut self, enable: bool) -> bool {
+        if self.is_fullscreen == enable {
+            return false;
+        }
+
+        self.is_fullscreen = enable;
+
+        true
+    }
+    fn set_fullscreen(&mut self, ctx: &mut EventCtx, data: &mut ApplicationState, enable: bool) {
+        if !self.switch_state(enable) {
+            return;
+        }
+
+        let window = ctx.window().clone();
+
+        if enable {
+            // Remember windowed size to restore it later.
+            let windowed_size = window.get_size();
+            self.windowed_position = window.get_position();
+            data.user_config.lock().unwrap().window_width = windowed_size.width as u16;
+            data.user_config.lock().unwrap().window_height = windowed_size.height as u16;
+
+            let (w, h) = display_size().unwrap();
+            window.show_titlebar(false);
+            window.set_position(Point::ORIGIN);
+            window.set_size(Size::new(w as f64, h as f64));
+        } else {
+            let config_guard = data.user_config.lock().unwrap();
+            window.show_titlebar(true);
+            window.set_size(Size::new(
+                config_guard.window_width as f64,
+                config_guard.window_height as f64,
+            ));
+            window.set_position(self.windowed_position);
+        }
+
+        let config_guard = data.user_config.lock().unwrap();
+        if let Err(err) = config_guard.save() {
+            let error_msg = format!("{} at [{}, {}]", err, file!(), line!());
+            if !data.is_connected {
+                data.connect_layout
+                    .set_connect_result(ConnectResult::Err(error_msg), &data.localization);
+            } else {
+                data.main_layout.add_system_message(error_msg);
+            }
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for FullscreenController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::WindowConnected => {
+                let start_fullscreen = data.user_config.lock().unwrap().start_fullscreen;
+                if start_fullscreen {
+                    self.set_fullscreen(ctx, data, true);
+                }
+            }
+            Event::KeyDown(k) => {
+                if let Some(enable) = self.get_requested_state(&k.key, k.repeat) {
+                    self.set_fullscreen(ctx, data, enable);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Applies a key press like 'event' does (without the window).
+    fn press(controller: &mut FullscreenController, key: Key, is_repeat: bool) {
+        if let Some(enable) = controller.get_requested_state(&key, is_repeat) {
+            assert!(controller.switch_state(enable));
+        }
+    }
+
+    #[test]
+    fn f11_toggles_fullscreen() {
+        let mut controller = FullscreenController::new();
+
+        let mut states = Vec::new();
+        for _ in 0..4 {
+            press(&mut controller, Key::F11, false);
+            states.push(controller.is_fullscreen);
+        }
+
+        assert_eq!(states, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn held_f11_is_ignored() {
+        let mut controller = FullscreenController::new();
+
+        press(&mut controller, Key::F11, false);
+        for _ in 0..10 {
+            press(&mut controller, Key::F11, true);
+        }
+        assert!(controller.is_fullscreen);
+
+        press(&mut controller, Key::F11, false);
+        press(&mut controller, Key::F11, true);
+        assert!(!controller.is_fullscreen);
+    }
+
+    #[test]
+    fn other_keys_are_ignored() {
+        let controller = FullscreenController::new();
+
+        assert_eq!(controller.get_requested_state(&Key::F10, false), None);
+        assert_eq!(controller.get_requested_state(&Key::Escape, false), None);
+        assert_eq!(
+            controller.get_requested_state(&Key::Character(String::from("f")), false),
+            None
+        );
+    }
+
+    #[test]
+    fn f11_after_start_in_fullscreen_exits_fullscreen() {
+        let mut controller = FullscreenController::new();
+
+        // 'start_fullscreen' in the config
+        assert!(controller.switch_state(true));
+
+        assert_eq!(
+            controller.get_requested_state(&Key::F11, false),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn switching_to_current_state_does_nothing() {
+        let mut controller = FullscreenController::new();
+
+        assert!(!controller.switch_state(false));
+        assert!(controller.switch_state(true));
+        assert!(!controller.switch_state(true));
+        assert!(controller.is_fullscreen);
+    }
+}
@@ -0,0 +1,82 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::TimerToken;
+use std::time::Duration;
+
+use crate::ApplicationState;
+use crate::Layout;
+
+const INPUT_LEVEL_POLL_INTERVAL_MS: u64 = 50;
+
+// Copies the microphone level (peak since the last poll) measured by the audio service
+// to SettingsLayout::input_level while the settings are opened.
+pub struct InputLevelController {
+    poll_timer: Option<TimerToken>,
+}
+
+impl InputLevelController {
+    pub fn new() -> Self {
+        InputLevelController { poll_timer: None }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for InputLevelController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if Some(*token) == self.poll_timer {
+                if data.current_layout == Layout::Settings {
+                    data.settings_layout.input_level =
+                        data.audio_service.lock().unwrap().take_input_level() as f64;
+                    self.poll_timer = Some(
+                        ctx.request_timer(Duration::from_millis(INPUT_LEVEL_POLL_INTERVAL_MS)),
+                    );
+                } else {
+                    self.poll_timer = None;
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            self.poll_timer =
+                Some(ctx.request_timer(Duration::from_millis(INPUT_LEVEL_POLL_INTERVAL_MS)));
+        }
+
+        child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if data.current_layout == Layout::Settings && self.poll_timer.is_none() {
+            self.poll_timer =
+                Some(ctx.request_timer(Duration::from_millis(INPUT_LEVEL_POLL_INTERVAL_MS)));
+        }
+
+        child.update(ctx, old_data, data, env)
+    }
+}
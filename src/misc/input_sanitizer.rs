@@ -0,0 +1,194 @@
+const ESCAPE_CHARACTER: char = '\u{1b}';
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+// Removes characters that break the chat's layout or can be used to spoof text
+// (null bytes, ANSI escape sequences, bidi overrides, invisible characters).
+pub struct InputSanitizer;
+
+impl InputSanitizer {
+    /// Removes control (Cc) and format (Cf) characters and ANSI escape sequences,
+    /// collapses multiple spaces/tabs into one space. Line breaks are kept
+    /// and the zero width joiner is kept because emoji sequences use it.
+    pub fn sanitize_message(message: &str) -> String {
+        let mut sanitized = String::with_capacity(message.len());
+        let mut previous_is_space = false;
+
+        for character in InputSanitizer::skip_escape_sequences(message) {
+            if character == ' ' || character == '\t' {
+                if !previous_is_space {
+                    sanitized.push(' ');
+                }
+                previous_is_space = true;
+                continue;
+            }
+
+            if character != '\n' && InputSanitizer::is_control_or_format(character) {
+                continue;
+            }
+
+            sanitized.push(character);
+            previous_is_space = false;
+        }
+
+        sanitized
+    }
+    /// Same as 'sanitize_message' but only keeps letters, digits, printable ASCII
+    /// and spaces (no line breaks, emoji or other symbols).
+    pub fn sanitize_username(username: &str) -> String {
+        let mut sanitized = String::with_capacity(username.len());
+        let mut previous_is_space = false;
+
+        for character in InputSanitizer::skip_escape_sequences(username) {
+            if character.is_whitespace() {
+                if !previous_is_space {
+                    sanitized.push(' ');
+                }
+                previous_is_space = true;
+                continue;
+            }
+
+            if !character.is_ascii_graphic() && !character.is_alphanumeric() {
+                continue;
+            }
+
+            sanitized.push(character);
+            previous_is_space = false;
+        }
+
+        sanitized
+    }
+    /// Returns characters without ANSI escape sequences ("ESC [ ... final byte")
+    /// and without single escape characters.
+    fn skip_escape_sequences(text: &str) -> impl Iterator<Item = char> + '_ {
+        let mut characters = text.chars().peekable();
+
+        std::iter::from_fn(move || loop {
+            let character = characters.next()?;
+            if character != ESCAPE_CHARACTER {
+                return Some(character);
+            }
+
+            if characters.peek() == Some(&'[') {
+                // skip parameters until the final byte (in range '@'..='~')
+                characters.next();
+                for sequence_character in characters.by_ref() {
+                    if ('@'..='~').contains(&sequence_character) {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+    fn is_control_or_format(character: char) -> bool {
+        if character.is_control() {
+            return true;
+        }
+
+        if character == ZERO_WIDTH_JOINER {
+            return false;
+        }
+
+        // format (Cf) characters that can appear in the text (std has no check for them)
+        matches!(
+            character,
+            '\u{ad}'
+                | '\u{600}'..='\u{605}'
+                | '\u{61c}'
+                | '\u{6dd}'
+                | '\u{70f}'
+                | '\u{180e}'
+                | '\u{200b}'..='\u{200f}'
+                | '\u{202a}'..='\u{202e}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{206f}'
+                | '\u{feff}'
+                | '\u{fff9}'..='\u{fffb}'
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY_EMOJI: &str = "👨\u{200d}👩\u{200d}👧";
+
+    #[test]
+    fn message_null_bytes_are_removed() {
+        assert_eq!(InputSanitizer::sanitize_message("he\0llo\0"), "hello");
+    }
+
+    #[test]
+    fn message_ansi_codes_are_removed() {
+        assert_eq!(
+            InputSanitizer::sanitize_message("\u{1b}[31mred\u{1b}[0m \u{1b}[1;4mbold\u{1b}[m"),
+            "red bold"
+        );
+        // a single escape character without a sequence
+        assert_eq!(InputSanitizer::sanitize_message("a\u{1b}b"), "ab");
+        // unfinished sequence at the end
+        assert_eq!(InputSanitizer::sanitize_message("text\u{1b}[12"), "text");
+    }
+
+    #[test]
+    fn message_keeps_emoji_and_zero_width_joiner() {
+        let message = format!("hi {} 👋🏽", FAMILY_EMOJI);
+
+        assert_eq!(InputSanitizer::sanitize_message(&message), message);
+    }
+
+    #[test]
+    fn message_invisible_characters_are_removed() {
+        assert_eq!(
+            InputSanitizer::sanitize_message("\u{202e}abc\u{202c}\u{200b}d\u{feff}\u{ad}e"),
+            "abcde"
+        );
+    }
+
+    #[test]
+    fn message_whitespace_is_collapsed() {
+        assert_eq!(
+            InputSanitizer::sanitize_message("a  \t b\t\tc\nd\r\ne"),
+            "a b c\nd\ne"
+        );
+    }
+
+    #[test]
+    fn message_non_ascii_text_is_kept() {
+        assert_eq!(
+            InputSanitizer::sanitize_message("привет, 世界! ¿qué?"),
+            "привет, 世界! ¿qué?"
+        );
+    }
+
+    #[test]
+    fn username_null_bytes_and_ansi_codes_are_removed() {
+        assert_eq!(
+            InputSanitizer::sanitize_username("\u{1b}[32mus\0er\u{1b}[0m"),
+            "user"
+        );
+    }
+
+    #[test]
+    fn username_emoji_and_zero_width_joiner_are_removed() {
+        let username = format!("user{}name 👋", FAMILY_EMOJI);
+
+        assert_eq!(InputSanitizer::sanitize_username(&username), "username ");
+    }
+
+    #[test]
+    fn username_keeps_letters_digits_and_ascii_symbols() {
+        assert_eq!(
+            InputSanitizer::sanitize_username("Имя_42 [clan]-é"),
+            "Имя_42 [clan]-é"
+        );
+    }
+
+    #[test]
+    fn username_line_breaks_become_spaces() {
+        assert_eq!(
+            InputSanitizer::sanitize_username("a\n\n\tb\u{3000}c"),
+            "a b c"
+        );
+    }
+}
@@ -100,3 +100,272 @@ pub const LOCALE_MAIN_LAYOUT_MESSAGE_COPIED_NOTICE_TEXT: &str =
     "MAIN_LAYOUT_MESSAGE_COPIED_NOTICE_TEXT";
 pub const LOCALE_CONNECT_LAYOUT_CONNECT_RESULT_ERR_SERVER_IS_FULL: &str =
     "CONNECT_LAYOUT_CONNECT_RESULT_ERR_SERVER_IS_FULL";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_BUTTON_ON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_BUTTON_OFF_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AGC_TEXT: &str = "SETTINGS_LAYOUT_SETTING_AGC_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AGC_TARGET_LEVEL_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AGC_TARGET_LEVEL_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PAUSE_ON_MINIMIZE_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PAUSE_ON_MINIMIZE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_RESTORE_DEFAULTS_BUTTON_TEXT: &str =
+    "SETTINGS_LAYOUT_RESTORE_DEFAULTS_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_RESTORE_DEFAULTS_CONFIRMATION_TEXT: &str =
+    "SETTINGS_LAYOUT_RESTORE_DEFAULTS_CONFIRMATION_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_HISTORY_SECTION_TEXT: &str =
+    "SETTINGS_LAYOUT_HISTORY_SECTION_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_HISTORY_CLEAR_BUTTON_TEXT: &str =
+    "SETTINGS_LAYOUT_HISTORY_CLEAR_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_HISTORY_EMPTY_TEXT: &str = "SETTINGS_LAYOUT_HISTORY_EMPTY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_HISTORY_CONNECTED_FOR_TEXT: &str =
+    "SETTINGS_LAYOUT_HISTORY_CONNECTED_FOR_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_HISTORY_MINUTES_TEXT: &str =
+    "SETTINGS_LAYOUT_HISTORY_MINUTES_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_INVALID_SERVER_ADDRESS_TEXT: &str =
+    "CONNECT_LAYOUT_INVALID_SERVER_ADDRESS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MESSAGE_VOICE_BUFFER_OVERFLOW_TEXT: &str =
+    "MAIN_LAYOUT_MESSAGE_VOICE_BUFFER_OVERFLOW_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DISCONNECT_BUTTON_TEXT: &str = "MAIN_LAYOUT_DISCONNECT_BUTTON_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_CONFIG_RECOVERED_TEXT: &str =
+    "CONNECT_LAYOUT_CONFIG_RECOVERED_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_VOICE_SESSIONS_TEXT: &str =
+    "MAIN_LAYOUT_USER_INFO_VOICE_SESSIONS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_TALKING_TIME_TEXT: &str =
+    "MAIN_LAYOUT_USER_INFO_TALKING_TIME_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_RECEIVED_TEXT: &str = "MAIN_LAYOUT_USER_INFO_RECEIVED_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_KILOBYTES_TEXT: &str =
+    "MAIN_LAYOUT_USER_INFO_KILOBYTES_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_START_FULLSCREEN_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_START_FULLSCREEN_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_CHECK_UPDATES_BUTTON_TEXT: &str =
+    "SETTINGS_LAYOUT_ABOUT_SECTION_CHECK_UPDATES_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_CHECKING_UPDATES_TEXT: &str =
+    "SETTINGS_LAYOUT_ABOUT_SECTION_CHECKING_UPDATES_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_UP_TO_DATE_TEXT: &str =
+    "SETTINGS_LAYOUT_ABOUT_SECTION_UP_TO_DATE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_UPDATE_AVAILABLE_TEXT: &str =
+    "SETTINGS_LAYOUT_ABOUT_SECTION_UPDATE_AVAILABLE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_UPDATE_CHECK_FAILED_TEXT: &str =
+    "SETTINGS_LAYOUT_ABOUT_SECTION_UPDATE_CHECK_FAILED_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ABOUT_SECTION_DOWNLOAD_UPDATE_BUTTON_TEXT: &str =
+    "SETTINGS_LAYOUT_ABOUT_SECTION_DOWNLOAD_UPDATE_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_CONFIG_VALIDATION_ERRORS_TEXT: &str =
+    "SETTINGS_LAYOUT_CONFIG_VALIDATION_ERRORS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_SEND_QUEUE_DEPTH_TEXT: &str = "MAIN_LAYOUT_SEND_QUEUE_DEPTH_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MESSAGE_SEND_QUEUE_FULL_TEXT: &str =
+    "MAIN_LAYOUT_MESSAGE_SEND_QUEUE_FULL_TEXT";
+pub const LOCALE_MAIN_LAYOUT_COPY_ALL_USERNAMES_TEXT: &str = "MAIN_LAYOUT_COPY_ALL_USERNAMES_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_USER_COUNT_TEXT: &str = "MAIN_LAYOUT_ROOM_USER_COUNT_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_USERNAME_TOO_SHORT_TEXT: &str =
+    "CONNECT_LAYOUT_USERNAME_TOO_SHORT_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_SERVER_TEXT: &str = "MAIN_LAYOUT_INFO_BAR_SERVER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_ROOM_TEXT: &str = "MAIN_LAYOUT_INFO_BAR_ROOM_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_USERS_TEXT: &str = "MAIN_LAYOUT_INFO_BAR_USERS_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_MESSAGE_COOLDOWN_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_MESSAGE_COOLDOWN_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_ROOM_CHANGE_COOLDOWN_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_ROOM_CHANGE_COOLDOWN_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_SECONDS_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_SECONDS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MESSAGE_MOVE_USER_NO_PERMISSION_TEXT: &str =
+    "MAIN_LAYOUT_MESSAGE_MOVE_USER_NO_PERMISSION_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MESSAGE_MOVE_USER_NOT_SUPPORTED_TEXT: &str =
+    "MAIN_LAYOUT_MESSAGE_MOVE_USER_NOT_SUPPORTED_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PTT_RELEASE_DELAY_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PTT_RELEASE_DELAY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_MILLISECONDS_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_MILLISECONDS_TEXT";
+pub const LOCALE_CONFIRM_DIALOG_YES_TEXT: &str = "CONFIRM_DIALOG_YES_TEXT";
+pub const LOCALE_CONFIRM_DIALOG_NO_TEXT: &str = "CONFIRM_DIALOG_NO_TEXT";
+pub const LOCALE_MAIN_LAYOUT_CLEAR_CHAT_BUTTON_TEXT: &str = "MAIN_LAYOUT_CLEAR_CHAT_BUTTON_TEXT";
+pub const LOCALE_MAIN_LAYOUT_UNDO_CLEAR_CHAT_BUTTON_TEXT: &str =
+    "MAIN_LAYOUT_UNDO_CLEAR_CHAT_BUTTON_TEXT";
+pub const LOCALE_MAIN_LAYOUT_CLEAR_CHAT_CONFIRMATION_TEXT: &str =
+    "MAIN_LAYOUT_CLEAR_CHAT_CONFIRMATION_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_UDP_JITTER_TEXT: &str =
+    "MAIN_LAYOUT_INFO_BAR_UDP_JITTER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MESSAGE_HIGH_UDP_JITTER_TEXT: &str =
+    "MAIN_LAYOUT_MESSAGE_HIGH_UDP_JITTER_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_IDLE_TIMEOUT_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_IDLE_TIMEOUT_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_MINUTES_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_MINUTES_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_STATUS_TEXT: &str = "MAIN_LAYOUT_INFO_BAR_STATUS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_STATUS_ACTIVE_TEXT: &str = "MAIN_LAYOUT_USER_STATUS_ACTIVE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_STATUS_AWAY_TEXT: &str = "MAIN_LAYOUT_USER_STATUS_AWAY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_THEME_TEXT: &str = "SETTINGS_LAYOUT_SETTING_THEME_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_THEME_DEFAULT_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_THEME_DEFAULT_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_SHOW_INLINE_IMAGES_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_SHOW_INLINE_IMAGES_TEXT";
+pub const LOCALE_MAIN_LAYOUT_IMAGE_LOADING_TEXT: &str = "MAIN_LAYOUT_IMAGE_LOADING_TEXT";
+pub const LOCALE_MAIN_LAYOUT_IMAGE_TOO_LARGE_TEXT: &str = "MAIN_LAYOUT_IMAGE_TOO_LARGE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_IMAGE_FAILED_TEXT: &str = "MAIN_LAYOUT_IMAGE_FAILED_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_REMEMBER_PASSWORD_TEXT: &str =
+    "CONNECT_LAYOUT_REMEMBER_PASSWORD_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AEC_TEXT: &str = "SETTINGS_LAYOUT_SETTING_AEC_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AEC_DELAY_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AEC_DELAY_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_RESOLVING_TEXT: &str = "CONNECT_LAYOUT_RESOLVING_TEXT";
+pub const LOCALE_MAIN_LAYOUT_PTT_RECORDING_TEXT: &str = "MAIN_LAYOUT_PTT_RECORDING_TEXT";
+pub const LOCALE_MAIN_LAYOUT_PTT_SECONDS_TEXT: &str = "MAIN_LAYOUT_PTT_SECONDS_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_SPATIAL_AUDIO_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_SPATIAL_AUDIO_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AUTO_AWAY_MESSAGE_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AUTO_AWAY_MESSAGE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MESSAGE_USER_IS_AWAY_TEXT: &str =
+    "MAIN_LAYOUT_MESSAGE_USER_IS_AWAY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PTT_PRESS_SOUND_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PTT_PRESS_SOUND_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PTT_RELEASE_SOUND_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PTT_RELEASE_SOUND_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_CHOOSE_FILE_BUTTON_TEXT: &str =
+    "SETTINGS_LAYOUT_CHOOSE_FILE_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_PREVIEW_BUTTON_TEXT: &str = "SETTINGS_LAYOUT_PREVIEW_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_INVALID_SOUND_FILE_TEXT: &str =
+    "SETTINGS_LAYOUT_INVALID_SOUND_FILE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_ACCESSIBILITY_MODE_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_ACCESSIBILITY_MODE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_NEW_USER_BADGE_TEXT: &str = "MAIN_LAYOUT_NEW_USER_BADGE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_SEARCH_TITLE_TEXT: &str = "MAIN_LAYOUT_SEARCH_TITLE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_SEARCH_MESSAGES_SECTION_TEXT: &str =
+    "MAIN_LAYOUT_SEARCH_MESSAGES_SECTION_TEXT";
+pub const LOCALE_MAIN_LAYOUT_SEARCH_USERS_SECTION_TEXT: &str =
+    "MAIN_LAYOUT_SEARCH_USERS_SECTION_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_MICROPHONE_CHANNELS_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_MICROPHONE_CHANNELS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_VOICE_ACTIVE_TEXT: &str =
+    "MAIN_LAYOUT_INFO_BAR_VOICE_ACTIVE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_ADVANCED_SECTION_TEXT: &str =
+    "SETTINGS_LAYOUT_ADVANCED_SECTION_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_UDP_RECV_BUFFER_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_UDP_RECV_BUFFER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_COMPACT_USER_LIST_BUTTON_TEXT: &str =
+    "MAIN_LAYOUT_COMPACT_USER_LIST_BUTTON_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_VOLUME_MUTE_BUTTON_TEXT: &str =
+    "MAIN_LAYOUT_USER_INFO_VOLUME_MUTE_BUTTON_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_VOLUME_AUTO_BUTTON_TEXT: &str =
+    "MAIN_LAYOUT_USER_INFO_VOLUME_AUTO_BUTTON_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_SILENCE_SUPPRESSION_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_SILENCE_SUPPRESSION_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_SILENCE_THRESHOLD_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_SILENCE_THRESHOLD_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_TODAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_TODAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_YESTERDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_YESTERDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FORMAT_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_FORMAT_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_MONDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_MONDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_TUESDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_TUESDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_WEDNESDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_WEDNESDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_THURSDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_THURSDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FRIDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_FRIDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_SATURDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_SATURDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_SUNDAY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_SUNDAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_JANUARY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_JANUARY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FEBRUARY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_FEBRUARY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_MARCH_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_MARCH_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_APRIL_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_APRIL_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_MAY_TEXT: &str = "MAIN_LAYOUT_DATE_SEPARATOR_MAY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_JUNE_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_JUNE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_JULY_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_JULY_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_AUGUST_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_AUGUST_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_SEPTEMBER_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_SEPTEMBER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_OCTOBER_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_OCTOBER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_NOVEMBER_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_NOVEMBER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_DECEMBER_TEXT: &str =
+    "MAIN_LAYOUT_DATE_SEPARATOR_DECEMBER_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_UDP_HEALTH_CHECK_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_UDP_HEALTH_CHECK_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_UDP_HEALTH_CHECK_TIMEOUT_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_UDP_HEALTH_CHECK_TIMEOUT_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_TALKING_TEXT: &str = "MAIN_LAYOUT_ROOM_TALKING_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_SHOW_PASSWORD_TEXT: &str = "CONNECT_LAYOUT_SHOW_PASSWORD_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_HIDE_PASSWORD_TEXT: &str = "CONNECT_LAYOUT_HIDE_PASSWORD_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_AUTO_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_NETWORK_INTERFACE_AUTO_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_CONNECT_RESULT_ERR_HANDSHAKE_TIMEOUT: &str =
+    "CONNECT_LAYOUT_CONNECT_RESULT_ERR_HANDSHAKE_TIMEOUT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_CONNECT_ON_LAUNCH_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_CONNECT_ON_LAUNCH_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_AUTO_CONNECTING_TEXT: &str = "CONNECT_LAYOUT_AUTO_CONNECTING_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_CANCEL_TEXT: &str = "CONNECT_LAYOUT_CANCEL_TEXT";
+pub const LOCALE_MAIN_LAYOUT_VIEW_ROOM_INFO_TEXT: &str = "MAIN_LAYOUT_VIEW_ROOM_INFO_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_INFO_CURRENT_ROOM_TEXT: &str =
+    "MAIN_LAYOUT_ROOM_INFO_CURRENT_ROOM_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_INFO_VOICE_ACTIVITY_TEXT: &str =
+    "MAIN_LAYOUT_ROOM_INFO_VOICE_ACTIVITY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_ROOM_CHANGE_LOBBY_COOLDOWN_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_ROOM_CHANGE_LOBBY_COOLDOWN_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_INVITE_TEXT: &str = "MAIN_LAYOUT_ROOM_INVITE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_INVITE_ACCEPT_TEXT: &str = "MAIN_LAYOUT_ROOM_INVITE_ACCEPT_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_INVITE_DECLINE_TEXT: &str =
+    "MAIN_LAYOUT_ROOM_INVITE_DECLINE_TEXT";
+pub const LOCALE_MAIN_LAYOUT_USER_INFO_INVITE_TO_ROOM_TEXT: &str =
+    "MAIN_LAYOUT_USER_INFO_INVITE_TO_ROOM_TEXT";
+pub const LOCALE_MAIN_LAYOUT_ROOM_INVITE_SENT_TEXT: &str = "MAIN_LAYOUT_ROOM_INVITE_SENT_TEXT";
+pub const LOCALE_MAIN_LAYOUT_MENTION_NOTIFIED_TEXT: &str = "MAIN_LAYOUT_MENTION_NOTIFIED_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_MICROPHONE_BOOST_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_MICROPHONE_BOOST_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_CONFIG_INVALID_ON_CLOSE_TEXT: &str =
+    "SETTINGS_LAYOUT_CONFIG_INVALID_ON_CLOSE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_CONFIG_FIX_NOW_TEXT: &str = "SETTINGS_LAYOUT_CONFIG_FIX_NOW_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_CONFIG_DISCARD_CHANGES_TEXT: &str =
+    "SETTINGS_LAYOUT_CONFIG_DISCARD_CHANGES_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_CHAT_MAX_MESSAGES_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_CHAT_MAX_MESSAGES_TEXT";
+pub const LOCALE_MAIN_LAYOUT_OLDER_MESSAGES_HIDDEN_TEXT: &str =
+    "MAIN_LAYOUT_OLDER_MESSAGES_HIDDEN_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_HOLD_KEY_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_HOLD_KEY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_VOICE_ACTIVATED_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_PUSH_TO_TALK_MODE_VOICE_ACTIVATED_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_VAD_SENSITIVITY_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_VAD_SENSITIVITY_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_INPUT_DEVICE_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AUDIO_INPUT_DEVICE_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_DEVICE_DEFAULT_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AUDIO_DEVICE_DEFAULT_TEXT";
+pub const LOCALE_CONNECT_LAYOUT_AUDIO_INPUT_DEVICE_NOT_FOUND_TEXT: &str =
+    "CONNECT_LAYOUT_AUDIO_INPUT_DEVICE_NOT_FOUND_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_INPUT_LEVEL_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_INPUT_LEVEL_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AUTO_RECORD_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AUTO_RECORD_TEXT";
+pub const LOCALE_MAIN_LAYOUT_RECORD_BUTTON_TEXT: &str = "MAIN_LAYOUT_RECORD_BUTTON_TEXT";
+pub const LOCALE_MAIN_LAYOUT_STOP_RECORDING_BUTTON_TEXT: &str =
+    "MAIN_LAYOUT_STOP_RECORDING_BUTTON_TEXT";
+pub const LOCALE_MAIN_LAYOUT_RECORDING_STARTED_TEXT: &str = "MAIN_LAYOUT_RECORDING_STARTED_TEXT";
+pub const LOCALE_MAIN_LAYOUT_RECORDING_SAVED_TEXT: &str = "MAIN_LAYOUT_RECORDING_SAVED_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_PLAY_BUFFER_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AUDIO_PLAY_BUFFER_TEXT";
+pub const LOCALE_SETTINGS_LAYOUT_SETTING_AUDIO_RECORD_BUFFER_TEXT: &str =
+    "SETTINGS_LAYOUT_SETTING_AUDIO_RECORD_BUFFER_TEXT";
+pub const LOCALE_MAIN_LAYOUT_INFO_BAR_UDP_DECRYPTION_ERRORS_TEXT: &str =
+    "MAIN_LAYOUT_INFO_BAR_UDP_DECRYPTION_ERRORS_TEXT";
+pub const LOCALE_MAIN_LAYOUT_RECONNECTING_IN_TEXT: &str = "MAIN_LAYOUT_RECONNECTING_IN_TEXT";
+pub const LOCALE_MAIN_LAYOUT_RECONNECT_ATTEMPT_TEXT: &str = "MAIN_LAYOUT_RECONNECT_ATTEMPT_TEXT";
+pub const LOCALE_MAIN_LAYOUT_RECONNECTED_TEXT: &str = "MAIN_LAYOUT_RECONNECTED_TEXT";
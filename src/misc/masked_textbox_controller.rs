@@ -0,0 +1,211 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Application, HotKey, KbKey, Lens, SysMods};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::ApplicationState;
+
+const MASK_CHARACTER: &str = "•";
+
+// Wraps a text box that shows bullets instead of the text (druid does not have a password field).
+// The text box shows 'display', typed keys are applied to 'value' by this controller
+// (as if the cursor is always at the end) and 'display' is rebuilt from it,
+// so 'value' always holds the real text.
+pub struct MaskedTextBoxController<V, D> {
+    value_lens: V,
+    display_lens: D,
+    max_graphemes: usize,
+}
+
+/// Returns one bullet per grapheme of 'value'.
+pub fn mask_text(value: &str) -> String {
+    MASK_CHARACTER.repeat(value.graphemes(true).count())
+}
+
+impl<V, D> MaskedTextBoxController<V, D> {
+    pub fn new(value_lens: V, display_lens: D, max_graphemes: usize) -> Self {
+        MaskedTextBoxController {
+            value_lens,
+            display_lens,
+            max_graphemes,
+        }
+    }
+}
+
+impl<V: Lens<ApplicationState, String>, D: Lens<ApplicationState, String>>
+    MaskedTextBoxController<V, D>
+{
+    fn append(&self, data: &mut ApplicationState, text: &str) {
+        let max_graphemes = self.max_graphemes;
+        self.value_lens.with_mut(data, |value| {
+            let allowed_count = max_graphemes.saturating_sub(value.graphemes(true).count());
+            value.extend(text.graphemes(true).take(allowed_count));
+        });
+    }
+    fn remove_last(&self, data: &mut ApplicationState, remove_all: bool) {
+        self.value_lens.with_mut(data, |value| {
+            if remove_all {
+                value.clear();
+            } else if let Some((index, _)) = value.grapheme_indices(true).last() {
+                value.truncate(index);
+            }
+        });
+    }
+    fn sync_display(&self, data: &mut ApplicationState) {
+        let masked = self.value_lens.with(data, |value| mask_text(value));
+        self.display_lens.with_mut(data, |display| {
+            if *display != masked {
+                *display = masked;
+            }
+        });
+    }
+}
+
+impl<W, V, D> Controller<ApplicationState, W> for MaskedTextBoxController<V, D>
+where
+    W: Widget<ApplicationState>,
+    V: Lens<ApplicationState, String>,
+    D: Lens<ApplicationState, String>,
+{
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::KeyDown(key) => {
+                if HotKey::new(SysMods::Cmd, "v").matches(key) {
+                    if let Some(text) = Application::global().clipboard().get_string() {
+                        self.append(data, &text);
+                    }
+                } else if HotKey::new(SysMods::Cmd, "x").matches(key) {
+                    // don't let the text box cut the bullets
+                } else {
+                    match &key.key {
+                        KbKey::Character(text)
+                            if !key.mods.ctrl() && !key.mods.alt() && !key.mods.meta() =>
+                        {
+                            self.append(data, text);
+                        }
+                        KbKey::Backspace => self.remove_last(data, key.mods.ctrl()),
+                        KbKey::Delete => {} // nothing after the cursor
+                        _ => {
+                            // navigation, tab, enter, etc.
+                            child.event(ctx, event, data, env);
+                            return;
+                        }
+                    }
+                }
+
+                self.sync_display(data);
+                ctx.set_handled();
+                return;
+            }
+            Event::Paste(clipboard) => {
+                if let Some(text) = clipboard.get_string() {
+                    self.append(data, &text);
+                }
+
+                self.sync_display(data);
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::connect_layout::ConnectLayout;
+    use crate::tests::test_application_state;
+    use druid::LensExt;
+
+    fn password_controller(
+        max_graphemes: usize,
+    ) -> MaskedTextBoxController<
+        impl Lens<ApplicationState, String>,
+        impl Lens<ApplicationState, String>,
+    > {
+        MaskedTextBoxController::new(
+            ApplicationState::connect_layout.then(ConnectLayout::password),
+            ApplicationState::connect_layout.then(ConnectLayout::password_display),
+            max_graphemes,
+        )
+    }
+
+    #[test]
+    fn one_bullet_per_grapheme() {
+        assert_eq!(mask_text(""), "");
+        assert_eq!(mask_text("abc"), "•••");
+        assert_eq!(mask_text("пароль"), "••••••");
+        // combining character and an emoji with a modifier
+        assert_eq!(mask_text("e\u{301}👍🏽"), "••");
+    }
+
+    #[test]
+    fn typed_text_is_masked() {
+        let controller = password_controller(32);
+        let mut data = test_application_state();
+
+        for key in ["p", "а", "s", "s"].iter() {
+            controller.append(&mut data, key);
+            controller.sync_display(&mut data);
+        }
+
+        assert_eq!(data.connect_layout.password, "pаss");
+        assert_eq!(data.connect_layout.password_display, "••••");
+    }
+
+    #[test]
+    fn pasted_text_is_cut_to_max_graphemes() {
+        let controller = password_controller(4);
+        let mut data = test_application_state();
+
+        controller.append(&mut data, "ab");
+        controller.append(&mut data, "c👍🏽de");
+        controller.sync_display(&mut data);
+
+        assert_eq!(data.connect_layout.password, "abc👍🏽");
+        assert_eq!(data.connect_layout.password_display, "••••");
+    }
+
+    #[test]
+    fn backspace_removes_last_grapheme() {
+        let controller = password_controller(32);
+        let mut data = test_application_state();
+        controller.append(&mut data, "ab👍🏽");
+
+        controller.remove_last(&mut data, false);
+        controller.sync_display(&mut data);
+        assert_eq!(data.connect_layout.password, "ab");
+        assert_eq!(data.connect_layout.password_display, "••");
+
+        // ctrl + backspace
+        controller.remove_last(&mut data, true);
+        controller.sync_display(&mut data);
+        assert_eq!(data.connect_layout.password, "");
+        assert_eq!(data.connect_layout.password_display, "");
+
+        // nothing to remove
+        controller.remove_last(&mut data, false);
+        assert_eq!(data.connect_layout.password, "");
+    }
+
+    #[test]
+    fn display_is_synced_with_changed_value() {
+        let controller = password_controller(32);
+        let mut data = test_application_state();
+        data.connect_layout.password = String::from("from config");
+
+        controller.sync_display(&mut data);
+
+        assert_eq!(data.connect_layout.password_display, "•••••••••••");
+    }
+}
@@ -0,0 +1,60 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::TimerToken;
+use std::time::Duration;
+
+use crate::global_params::*;
+use crate::ApplicationState;
+
+// Wraps the "Notified @username" label, hides it after MENTION_NOTICE_DURATION_SEC.
+pub struct MentionNoticeController {
+    hide_timer: TimerToken,
+}
+
+impl MentionNoticeController {
+    pub fn new() -> Self {
+        MentionNoticeController {
+            hide_timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for MentionNoticeController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if *token == self.hide_timer {
+                self.hide_timer = TimerToken::INVALID;
+                data.main_layout.mention_notice.clear();
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        // restart the timer on each new notice
+        if old_data.main_layout.mention_notice != data.main_layout.mention_notice
+            && !data.main_layout.mention_notice.is_empty()
+        {
+            self.hide_timer = ctx.request_timer(Duration::from_secs(MENTION_NOTICE_DURATION_SEC));
+        }
+
+        child.update(ctx, old_data, data, env)
+    }
+}
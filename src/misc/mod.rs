@@ -1,5 +1,34 @@
+pub mod accessibility_controller;
+pub mod activity_controller;
+pub mod auto_connect_controller;
+pub mod chat_list_controller;
+pub mod chat_scroll_shortcut_controller;
+pub mod clear_chat_undo_controller;
+pub mod config_save_controller;
+pub mod config_validator_ui;
+pub mod connected_list_controller;
 pub mod custom_data_button_controller;
 pub mod custom_slider_controller;
 pub mod custom_text_box_controller;
+pub mod data_changed_controller;
+pub mod focus_lost_controller;
 pub mod formatter_max_characters;
+pub mod formatter_min_characters;
+pub mod formatter_server_address;
+pub mod fullscreen_controller;
+pub mod input_level_controller;
+pub mod input_sanitizer;
 pub mod locale_keys;
+pub mod masked_textbox_controller;
+pub mod mention_notice_controller;
+pub mod new_badge_controller;
+pub mod packet_builder;
+pub mod password_obfuscator;
+pub mod reconnect_controller;
+pub mod room_invite_controller;
+pub mod search_highlight_controller;
+pub mod search_shortcut_controller;
+pub mod settings_shortcut_controller;
+pub mod text_highlighter;
+pub mod user_drag_controller;
+pub mod voice_session_controller;
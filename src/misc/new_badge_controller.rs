@@ -0,0 +1,139 @@
+use chrono::prelude::*;
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Selector, TimerToken};
+use std::time::Duration;
+
+use crate::global_params::*;
+
+// Submitted when the "NEW" badge of the user (username) expired.
+pub const NEW_BADGE_EXPIRED: Selector<String> = Selector::new("new_badge_expired");
+
+// Hides the "NEW" badge of the user after NEW_USER_BADGE_DURATION_SEC.
+// The connected list is rebuilt quite often, so the remaining time
+// is calculated from the user's connect time instead of being stored here.
+pub struct NewBadgeController {
+    username: String,
+    connected_time_point: DateTime<Local>,
+    expire_timer: TimerToken,
+}
+
+impl NewBadgeController {
+    pub fn new(username: String, connected_time_point: DateTime<Local>) -> Self {
+        NewBadgeController {
+            username,
+            connected_time_point,
+            expire_timer: TimerToken::INVALID,
+        }
+    }
+    /// Returns 'None' if the badge already expired.
+    pub fn get_time_left(
+        connected_time_point: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Option<Duration> {
+        let elapsed_ms = now
+            .signed_duration_since(connected_time_point)
+            .num_milliseconds()
+            .max(0) as u64;
+        let duration_ms = NEW_USER_BADGE_DURATION_SEC * 1000;
+
+        if elapsed_ms >= duration_ms {
+            None
+        } else {
+            Some(Duration::from_millis(duration_ms - elapsed_ms))
+        }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for NewBadgeController {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Timer(token) = event {
+            if *token == self.expire_timer {
+                self.expire_timer = TimerToken::INVALID;
+                ctx.submit_command(NEW_BADGE_EXPIRED.with(self.username.clone()));
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            match NewBadgeController::get_time_left(self.connected_time_point, Local::now()) {
+                Some(time_left) => self.expire_timer = ctx.request_timer(time_left),
+                None => ctx.submit_command(NEW_BADGE_EXPIRED.with(self.username.clone())),
+            }
+        }
+
+        child.lifecycle(ctx, event, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connected_at() -> DateTime<Local> {
+        let time = NaiveDate::from_ymd_opt(2026, 10, 16)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        Local.from_local_datetime(&time).unwrap()
+    }
+
+    #[test]
+    fn badge_is_shown_for_full_duration_after_connect() {
+        let connected = connected_at();
+
+        assert_eq!(
+            NewBadgeController::get_time_left(connected, connected),
+            Some(Duration::from_secs(NEW_USER_BADGE_DURATION_SEC))
+        );
+        assert_eq!(
+            NewBadgeController::get_time_left(connected, connected + chrono::Duration::seconds(10)),
+            Some(Duration::from_secs(NEW_USER_BADGE_DURATION_SEC - 10))
+        );
+    }
+
+    #[test]
+    fn badge_expires_after_duration() {
+        let connected = connected_at();
+        let duration = chrono::Duration::seconds(NEW_USER_BADGE_DURATION_SEC as i64);
+
+        assert_eq!(
+            NewBadgeController::get_time_left(
+                connected,
+                connected + duration - chrono::Duration::milliseconds(1)
+            ),
+            Some(Duration::from_millis(1))
+        );
+        assert_eq!(
+            NewBadgeController::get_time_left(connected, connected + duration),
+            None
+        );
+        assert_eq!(
+            NewBadgeController::get_time_left(connected, connected + duration * 10),
+            None
+        );
+    }
+
+    #[test]
+    fn connect_time_in_future_shows_full_duration() {
+        let connected = connected_at();
+
+        // the clock was changed
+        assert_eq!(
+            NewBadgeController::get_time_left(connected, connected - chrono::Duration::seconds(5)),
+            Some(Duration::from_secs(NEW_USER_BADGE_DURATION_SEC))
+        );
+    }
+}
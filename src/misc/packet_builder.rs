@@ -0,0 +1,550 @@
+// External.
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::Aes256;
+use cmac::{Cmac, Mac};
+use rand::RngCore;
+use serde::{de::DeserializeOwned, Serialize};
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+// Std.
+use std::convert::TryInto;
+
+// Custom.
+use crate::error::SilentError;
+use crate::global_params::*;
+use crate::services::tcp_packets::*;
+use crate::services::user_tcp_service::{HandleMessageResult, IoResult, SECRET_KEY_SIZE};
+
+#[derive(Debug, PartialEq)]
+pub enum PacketBuilderError {
+    DataTooSmall,
+    Serialize(String),
+    Deserialize(String),
+    Decrypt(String),
+    IncorrectTag(String),
+    Other(String),
+}
+
+impl From<PacketBuilderError> for HandleMessageResult {
+    fn from(error: PacketBuilderError) -> Self {
+        match error {
+            PacketBuilderError::DataTooSmall => HandleMessageResult::IOError(IoResult::Err(
+                format!("received data is too small, at [{}, {}].", file!(), line!()),
+            )),
+            PacketBuilderError::Decrypt(msg) | PacketBuilderError::Deserialize(msg) => {
+                HandleMessageResult::IOError(IoResult::Err(msg))
+            }
+            PacketBuilderError::Serialize(_)
+            | PacketBuilderError::IncorrectTag(_)
+            | PacketBuilderError::Other(_) => {
+                HandleMessageResult::OtherErr(SilentError::from(error))
+            }
+        }
+    }
+}
+
+impl From<PacketBuilderError> for SilentError {
+    fn from(error: PacketBuilderError) -> Self {
+        match error {
+            PacketBuilderError::DataTooSmall => SilentError::InternalError(format!(
+                "data is too small, at [{}, {}]",
+                file!(),
+                line!()
+            )),
+            PacketBuilderError::Serialize(msg) | PacketBuilderError::Deserialize(msg) => {
+                SilentError::SerializationError(msg)
+            }
+            PacketBuilderError::Decrypt(msg) | PacketBuilderError::IncorrectTag(msg) => {
+                SilentError::CryptoError(msg)
+            }
+            PacketBuilderError::Other(msg) => SilentError::InternalError(msg),
+        }
+    }
+}
+
+// How the payload that follows a frame header should be read.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FrameFormat {
+    Legacy,          // TCP_FRAME_VERSION_LEGACY
+    Unsupported(u8), // the payload is skipped (sent by a newer server)
+}
+
+// Builds and parses packets in format:
+// frame header (with the size of the data that follows), IV, encrypted (serialized payload + CMAC tag).
+// The connect packet uses the legacy format: (u16) size instead of the frame header,
+// so that a server with a different protocol version can still read it.
+pub struct PacketBuilder;
+
+impl PacketBuilder {
+    /// Returns a buffer ready to be sent (including the frame header).
+    pub fn build_encrypted_packet<T: Serialize>(
+        payload: &T,
+        secret_key: &[u8; SECRET_KEY_SIZE],
+    ) -> Result<Vec<u8>, SilentError> {
+        match PacketBuilder::build(payload, secret_key, Some(TCP_FRAME_VERSION_LEGACY)) {
+            Ok(buffer) => Ok(buffer),
+            Err(e) => Err(SilentError::from(e)),
+        }
+    }
+    /// Returns a buffer ready to be sent (including the (u16) size prefix), only used for the connect packet.
+    pub fn build_legacy_encrypted_packet<T: Serialize>(
+        payload: &T,
+        secret_key: &[u8; SECRET_KEY_SIZE],
+    ) -> Result<Vec<u8>, SilentError> {
+        match PacketBuilder::build(payload, secret_key, None) {
+            Ok(buffer) => Ok(buffer),
+            Err(e) => Err(SilentError::from(e)),
+        }
+    }
+    pub fn build_frame_header(version: u8, payload_size: u16) -> Result<Vec<u8>, SilentError> {
+        let header = FrameHeader {
+            magic: TCP_FRAME_MAGIC,
+            version,
+            payload_size,
+        };
+
+        match bincode::serialize(&header) {
+            Ok(buffer) => Ok(buffer),
+            Err(e) => Err(SilentError::SerializationError(format!(
+                "bincode::serialize failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ))),
+        }
+    }
+    /// Expects `data` to be TCP_FRAME_HEADER_SIZE bytes long.
+    pub fn parse_frame_header(data: &[u8]) -> Result<FrameHeader, SilentError> {
+        let header = bincode::deserialize::<FrameHeader>(data);
+        if let Err(e) = header {
+            return Err(SilentError::SerializationError(format!(
+                "bincode::deserialize failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let header = header.unwrap();
+
+        if header.magic != TCP_FRAME_MAGIC {
+            return Err(SilentError::InternalError(format!(
+                "received frame header has wrong magic number (0x{:04X}, expected 0x{:04X}), at [{}, {}]",
+                header.magic,
+                TCP_FRAME_MAGIC,
+                file!(),
+                line!()
+            )));
+        }
+
+        Ok(header)
+    }
+    pub fn get_frame_format(header: &FrameHeader) -> FrameFormat {
+        match header.version {
+            TCP_FRAME_VERSION_LEGACY => FrameFormat::Legacy,
+            version => FrameFormat::Unsupported(version),
+        }
+    }
+    /// Expects `data` to start with IV (without the size prefix).
+    pub fn parse_encrypted_packet<T: DeserializeOwned>(
+        data: Vec<u8>,
+        secret_key: &[u8; SECRET_KEY_SIZE],
+    ) -> Result<T, HandleMessageResult> {
+        match PacketBuilder::parse(data, secret_key) {
+            Ok(packet) => Ok(packet),
+            Err(e) => Err(HandleMessageResult::from(e)),
+        }
+    }
+    /// Uses the legacy size prefix if `frame_version` is `None`.
+    fn build<T: Serialize>(
+        payload: &T,
+        secret_key: &[u8; SECRET_KEY_SIZE],
+        frame_version: Option<u8>,
+    ) -> Result<Vec<u8>, PacketBuilderError> {
+        // Serialize packet.
+        let binary_packet = bincode::serialize(payload);
+        if let Err(e) = binary_packet {
+            return Err(PacketBuilderError::Serialize(format!(
+                "bincode::serialize failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let mut binary_packet = binary_packet.unwrap();
+
+        // CMAC.
+        let mut mac = Cmac::<Aes256>::new_from_slice(secret_key).unwrap();
+        mac.update(&binary_packet);
+        let result = mac.finalize();
+        let mut tag_bytes = result.into_bytes().to_vec();
+        if tag_bytes.len() != CMAC_TAG_LENGTH {
+            return Err(PacketBuilderError::Other(format!(
+                "unexpected tag length: {} != {} at [{}, {}]",
+                tag_bytes.len(),
+                CMAC_TAG_LENGTH,
+                file!(),
+                line!()
+            )));
+        }
+
+        binary_packet.append(&mut tag_bytes);
+
+        // Encrypt packet.
+        let mut rng = rand::thread_rng();
+        let mut iv = [0u8; IV_LENGTH];
+        rng.fill_bytes(&mut iv);
+        let mut encrypted_packet = Aes256CbcEnc::new(&(*secret_key).into(), &iv.into())
+            .encrypt_padded_vec_mut::<Pkcs7>(&binary_packet);
+
+        if encrypted_packet.len() + IV_LENGTH + std::mem::size_of::<u16>() > std::u16::MAX as usize
+        {
+            return Err(PacketBuilderError::Other(format!(
+                "encrypted packet size + size_of::<u16> exceeded u16::MAX at [{}, {}]",
+                file!(),
+                line!()
+            )));
+        }
+
+        // Prepare frame header (or encrypted packet len buffer).
+        let encrypted_len = (encrypted_packet.len() + IV_LENGTH) as u16;
+        let header_buf = match frame_version {
+            Some(version) => PacketBuilder::build_frame_header(version, encrypted_len)
+                .map_err(|e| PacketBuilderError::Serialize(e.to_string())),
+            None => bincode::serialize(&encrypted_len).map_err(|e| {
+                PacketBuilderError::Serialize(format!(
+                    "bincode::serialize failed, error: {} at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                ))
+            }),
+        };
+        if let Err(e) = header_buf {
+            return Err(e);
+        }
+        let mut send_buffer = header_buf.unwrap();
+
+        // Merge all to one buffer.
+        send_buffer.append(&mut Vec::from(iv));
+        send_buffer.append(&mut encrypted_packet);
+
+        Ok(send_buffer)
+    }
+    fn parse<T: DeserializeOwned>(
+        mut data: Vec<u8>,
+        secret_key: &[u8; SECRET_KEY_SIZE],
+    ) -> Result<T, PacketBuilderError> {
+        // Get IV.
+        if data.len() < IV_LENGTH {
+            return Err(PacketBuilderError::DataTooSmall);
+        }
+        let iv = data[..IV_LENGTH].to_vec();
+        data = data[IV_LENGTH..].to_vec();
+
+        // Convert IV.
+        let iv = iv.try_into();
+        if iv.is_err() {
+            return Err(PacketBuilderError::Other(format!(
+                "failed to convert iv to generic array, at [{}, {}].",
+                file!(),
+                line!()
+            )));
+        }
+        let iv: [u8; IV_LENGTH] = iv.unwrap();
+
+        // Decrypt packet.
+        let binary_packet = Aes256CbcDec::new(&(*secret_key).into(), &iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&data);
+        if let Err(e) = binary_packet {
+            return Err(PacketBuilderError::Decrypt(format!(
+                "unable to decrypt a packet (error: {}), at [{}, {}].",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let mut binary_packet = binary_packet.unwrap();
+
+        // CMAC
+        let mut mac = Cmac::<Aes256>::new_from_slice(secret_key).unwrap();
+        let tag: Vec<u8> = binary_packet
+            .drain(binary_packet.len().saturating_sub(CMAC_TAG_LENGTH)..)
+            .collect();
+        mac.update(&binary_packet);
+
+        // Convert tag.
+        let tag = tag.try_into();
+        if tag.is_err() {
+            return Err(PacketBuilderError::Other(format!(
+                "failed to convert cmac tag to generic array, at [{}, {}]",
+                file!(),
+                line!()
+            )));
+        }
+        let tag: [u8; CMAC_TAG_LENGTH] = tag.unwrap();
+
+        if let Err(e) = mac.verify(&tag.into()) {
+            return Err(PacketBuilderError::IncorrectTag(format!(
+                "Incorrect tag (error: {}), at [{}, {}].",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Deserialize.
+        let packet = bincode::deserialize::<T>(&binary_packet);
+        if let Err(e) = packet {
+            return Err(PacketBuilderError::Deserialize(format!(
+                "Unable to deserialize a packet (error: {}), at [{}, {}].",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+
+        Ok(packet.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET_KEY: [u8; SECRET_KEY_SIZE] = [7; SECRET_KEY_SIZE];
+
+    /// Checks the frame header and returns the data that follows it.
+    fn strip_frame_header(packet: &[u8]) -> Vec<u8> {
+        let header = PacketBuilder::parse_frame_header(&packet[..TCP_FRAME_HEADER_SIZE]).unwrap();
+        assert_eq!(header.version, TCP_FRAME_VERSION_LEGACY);
+        assert_eq!(
+            header.payload_size as usize,
+            packet.len() - TCP_FRAME_HEADER_SIZE
+        );
+
+        packet[TCP_FRAME_HEADER_SIZE..].to_vec()
+    }
+
+    /// Encrypts 'plain_data' followed by 'tag' like 'build' does (returns IV + encrypted data).
+    fn encrypt_with_tag(plain_data: &[u8], tag: &[u8]) -> Vec<u8> {
+        let mut binary_packet = plain_data.to_vec();
+        binary_packet.extend_from_slice(tag);
+
+        let iv = [3u8; IV_LENGTH];
+        let mut packet = Vec::from(iv);
+        packet.append(
+            &mut Aes256CbcEnc::new(&SECRET_KEY.into(), &iv.into())
+                .encrypt_padded_vec_mut::<Pkcs7>(&binary_packet),
+        );
+
+        packet
+    }
+
+    fn cmac_tag(data: &[u8], key: &[u8; SECRET_KEY_SIZE]) -> Vec<u8> {
+        let mut mac = Cmac::<Aes256>::new_from_slice(key).unwrap();
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn frame_header_is_5_bytes() {
+        let header = PacketBuilder::build_frame_header(TCP_FRAME_VERSION_LEGACY, 0x1234).unwrap();
+
+        assert_eq!(header.len(), TCP_FRAME_HEADER_SIZE);
+        assert_eq!(header.len(), 5);
+        // little endian magic, version, payload size
+        assert_eq!(
+            header,
+            vec![0x1E, 0x5E, TCP_FRAME_VERSION_LEGACY, 0x34, 0x12]
+        );
+    }
+
+    #[test]
+    fn frame_header_roundtrip() {
+        for version in [
+            TCP_FRAME_VERSION_LEGACY,
+            TCP_FRAME_VERSION_EXTENDED,
+            u8::MAX,
+        ] {
+            for payload_size in [0, 1, TCP_PACKET_MAX_SIZE, u16::MAX] {
+                let data = PacketBuilder::build_frame_header(version, payload_size).unwrap();
+                let header = PacketBuilder::parse_frame_header(&data).unwrap();
+
+                assert_eq!(
+                    header,
+                    FrameHeader {
+                        magic: TCP_FRAME_MAGIC,
+                        version,
+                        payload_size
+                    }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn frame_header_with_wrong_magic_is_rejected() {
+        let mut data = PacketBuilder::build_frame_header(TCP_FRAME_VERSION_LEGACY, 10).unwrap();
+        data[0] ^= 0xFF;
+
+        assert!(PacketBuilder::parse_frame_header(&data).is_err());
+
+        // the legacy format starts with the (u16) size
+        let legacy_data = bincode::serialize(&100u16).unwrap();
+        assert!(PacketBuilder::parse_frame_header(&legacy_data).is_err());
+    }
+
+    #[test]
+    fn too_short_frame_header_is_rejected() {
+        let data = PacketBuilder::build_frame_header(TCP_FRAME_VERSION_LEGACY, 10).unwrap();
+
+        assert!(PacketBuilder::parse_frame_header(&data[..TCP_FRAME_HEADER_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn frame_format_dispatch() {
+        let header = |version| FrameHeader {
+            magic: TCP_FRAME_MAGIC,
+            version,
+            payload_size: 0,
+        };
+
+        assert_eq!(
+            PacketBuilder::get_frame_format(&header(TCP_FRAME_VERSION_LEGACY)),
+            FrameFormat::Legacy
+        );
+        // reserved, not supported yet
+        assert_eq!(
+            PacketBuilder::get_frame_format(&header(TCP_FRAME_VERSION_EXTENDED)),
+            FrameFormat::Unsupported(TCP_FRAME_VERSION_EXTENDED)
+        );
+        assert_eq!(
+            PacketBuilder::get_frame_format(&header(u8::MAX)),
+            FrameFormat::Unsupported(u8::MAX)
+        );
+    }
+
+    #[test]
+    fn built_packet_is_dispatched_as_legacy() {
+        let packet = PacketBuilder::build_encrypted_packet(&1u32, &SECRET_KEY).unwrap();
+        let header = PacketBuilder::parse_frame_header(&packet[..TCP_FRAME_HEADER_SIZE]).unwrap();
+
+        assert_eq!(
+            PacketBuilder::get_frame_format(&header),
+            FrameFormat::Legacy
+        );
+    }
+
+    #[test]
+    fn build_parse_roundtrip() {
+        let message = ClientTcpMessage::UserMessage {
+            message: String::from("hello, привет 👋"),
+        };
+
+        let packet = PacketBuilder::build_encrypted_packet(&message, &SECRET_KEY).unwrap();
+        let data = strip_frame_header(&packet);
+        let parsed: ClientTcpMessage = PacketBuilder::parse(data, &SECRET_KEY).unwrap();
+
+        match parsed {
+            ClientTcpMessage::UserMessage { message } => assert_eq!(message, "hello, привет 👋"),
+            _ => panic!("parsed a different message"),
+        }
+    }
+
+    #[test]
+    fn legacy_build_parse_roundtrip() {
+        let payload = (42u64, String::from("room"));
+
+        let packet = PacketBuilder::build_legacy_encrypted_packet(&payload, &SECRET_KEY).unwrap();
+        let size = bincode::deserialize::<u16>(&packet[..2]).unwrap();
+        assert_eq!(size as usize, packet.len() - 2);
+
+        let parsed: (u64, String) =
+            PacketBuilder::parse(packet[2..].to_vec(), &SECRET_KEY).unwrap();
+
+        assert_eq!(parsed, payload);
+    }
+
+    #[test]
+    fn same_payload_is_encrypted_with_different_iv() {
+        let first = PacketBuilder::build_encrypted_packet(&1u32, &SECRET_KEY).unwrap();
+        let second = PacketBuilder::build_encrypted_packet(&1u32, &SECRET_KEY).unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn forged_tag_is_rejected() {
+        let plain_data = bincode::serialize(&String::from("forged")).unwrap();
+        let packet = encrypt_with_tag(&plain_data, &[0u8; CMAC_TAG_LENGTH]);
+
+        let result = PacketBuilder::parse::<String>(packet, &SECRET_KEY);
+
+        assert!(matches!(result, Err(PacketBuilderError::IncorrectTag(_))));
+    }
+
+    #[test]
+    fn tag_from_another_key_is_rejected() {
+        let plain_data = bincode::serialize(&String::from("forged")).unwrap();
+        let tag = cmac_tag(&plain_data, &[8; SECRET_KEY_SIZE]);
+        let packet = encrypt_with_tag(&plain_data, &tag);
+
+        let result = PacketBuilder::parse::<String>(packet, &SECRET_KEY);
+
+        assert!(matches!(result, Err(PacketBuilderError::IncorrectTag(_))));
+    }
+
+    #[test]
+    fn replaced_payload_is_rejected() {
+        // valid tag of the original message, but the message was replaced
+        let original = bincode::serialize(&String::from("original")).unwrap();
+        let replaced = bincode::serialize(&String::from("replaced")).unwrap();
+        let packet = encrypt_with_tag(&replaced, &cmac_tag(&original, &SECRET_KEY));
+
+        let result = PacketBuilder::parse::<String>(packet, &SECRET_KEY);
+
+        assert!(matches!(result, Err(PacketBuilderError::IncorrectTag(_))));
+    }
+
+    #[test]
+    fn valid_tag_is_accepted() {
+        let plain_data = bincode::serialize(&String::from("valid")).unwrap();
+        let packet = encrypt_with_tag(&plain_data, &cmac_tag(&plain_data, &SECRET_KEY));
+
+        let result = PacketBuilder::parse::<String>(packet, &SECRET_KEY);
+
+        assert_eq!(result, Ok(String::from("valid")));
+    }
+
+    #[test]
+    fn modified_ciphertext_is_rejected() {
+        let packet =
+            PacketBuilder::build_encrypted_packet(&String::from("text"), &SECRET_KEY).unwrap();
+        let data = strip_frame_header(&packet);
+
+        for i in IV_LENGTH..data.len() {
+            let mut modified = data.clone();
+            modified[i] ^= 0x01;
+
+            assert!(PacketBuilder::parse::<String>(modified, &SECRET_KEY).is_err());
+        }
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let packet =
+            PacketBuilder::build_encrypted_packet(&String::from("text"), &SECRET_KEY).unwrap();
+        let data = strip_frame_header(&packet);
+
+        assert!(PacketBuilder::parse::<String>(data, &[8; SECRET_KEY_SIZE]).is_err());
+    }
+
+    #[test]
+    fn too_small_data_is_rejected() {
+        let result = PacketBuilder::parse::<String>(vec![0u8; IV_LENGTH - 1], &SECRET_KEY);
+
+        assert_eq!(result, Err(PacketBuilderError::DataTooSmall));
+    }
+}
@@ -0,0 +1,130 @@
+// Std.
+use std::fmt::Write;
+
+const FALLBACK_KEY: &str = "silent-rs"; // used if the machine ID is not available
+
+// XORs the password with a key derived from the machine ID and stores it as a hex string.
+// This is NOT encryption (the key can be easily recovered), it just keeps the password
+// from being readable when somebody looks at the config file.
+pub struct PasswordObfuscator;
+
+impl PasswordObfuscator {
+    pub fn obfuscate(password: &str) -> String {
+        let key = PasswordObfuscator::get_key();
+
+        let mut hex = String::with_capacity(password.len() * 2);
+        for (i, byte) in password.bytes().enumerate() {
+            write!(hex, "{:02x}", byte ^ key[i % key.len()]).unwrap();
+        }
+
+        hex
+    }
+    pub fn deobfuscate(hex: &str) -> Result<String, String> {
+        if hex.len() % 2 != 0 {
+            return Err(format!(
+                "obfuscated password has invalid length ({}) at [{}, {}]",
+                hex.len(),
+                file!(),
+                line!()
+            ));
+        }
+        // 'from_str_radix' also accepts a sign ("+f")
+        if !hex.chars().all(|character| character.is_ascii_hexdigit()) {
+            return Err(format!(
+                "obfuscated password is not a hex string at [{}, {}]",
+                file!(),
+                line!()
+            ));
+        }
+
+        let key = PasswordObfuscator::get_key();
+
+        let mut bytes = Vec::with_capacity(hex.len() / 2);
+        for i in 0..hex.len() / 2 {
+            let byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16);
+            if let Err(e) = byte {
+                return Err(format!(
+                    "u8::from_str_radix() failed, error: {} at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                ));
+            }
+            bytes.push(byte.unwrap() ^ key[i % key.len()]);
+        }
+
+        // a config from another machine will produce garbage here
+        let password = String::from_utf8(bytes);
+        if let Err(e) = password {
+            return Err(format!(
+                "String::from_utf8() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(password.unwrap())
+    }
+    fn get_key() -> Vec<u8> {
+        match machine_uid::get() {
+            Ok(id) if !id.is_empty() => id.into_bytes(),
+            _ => Vec::from(FALLBACK_KEY.as_bytes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obfuscate_deobfuscate_roundtrip() {
+        for password in ["password", "пароль 🔑", "a", "0123456789abcdefghij"] {
+            let hex = PasswordObfuscator::obfuscate(password);
+
+            assert_eq!(hex.len(), password.len() * 2);
+            assert_ne!(hex, password);
+            assert_eq!(
+                PasswordObfuscator::deobfuscate(&hex),
+                Ok(String::from(password))
+            );
+        }
+    }
+
+    #[test]
+    fn empty_password() {
+        let hex = PasswordObfuscator::obfuscate("");
+
+        assert!(hex.is_empty());
+        assert_eq!(PasswordObfuscator::deobfuscate(&hex), Ok(String::new()));
+    }
+
+    #[test]
+    fn password_longer_than_key() {
+        let password = "x".repeat(PasswordObfuscator::get_key().len() * 3 + 1);
+
+        let hex = PasswordObfuscator::obfuscate(&password);
+
+        assert_eq!(PasswordObfuscator::deobfuscate(&hex), Ok(password));
+    }
+
+    #[test]
+    fn odd_length_is_rejected() {
+        let hex = PasswordObfuscator::obfuscate("password");
+
+        assert!(PasswordObfuscator::deobfuscate(&hex[..hex.len() - 1]).is_err());
+        assert!(PasswordObfuscator::deobfuscate("a").is_err());
+    }
+
+    #[test]
+    fn non_hex_input_is_rejected() {
+        for hex in ["zz", "0g", "+f", "-1", " 1", "ф1", "0x"] {
+            assert!(
+                PasswordObfuscator::deobfuscate(hex).is_err(),
+                "'{}' was accepted",
+                hex
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::TimerToken;
+use std::time::Duration;
+
+use crate::layouts::main_layout::MainLayout;
+use crate::ApplicationState;
+
+// Counts down MainLayout::reconnect_countdown_sec every second while reconnecting
+// (after the connection was lost) and tries to reconnect when it reaches zero.
+pub struct ReconnectController {
+    countdown_timer: Option<TimerToken>,
+}
+
+impl ReconnectController {
+    pub fn new() -> Self {
+        ReconnectController {
+            countdown_timer: None,
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for ReconnectController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if Some(*token) == self.countdown_timer {
+                self.countdown_timer = None;
+                if data.main_layout.is_reconnecting() {
+                    data.main_layout.reconnect_countdown_sec =
+                        data.main_layout.reconnect_countdown_sec.saturating_sub(1);
+                    if data.main_layout.reconnect_countdown_sec == 0 {
+                        // blocks the UI like the "connect" button
+                        MainLayout::reconnect(ctx, data, env);
+                    }
+                    if data.main_layout.is_reconnecting() {
+                        self.countdown_timer = Some(ctx.request_timer(Duration::from_secs(1)));
+                    }
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if data.main_layout.is_reconnecting() && self.countdown_timer.is_none() {
+            self.countdown_timer = Some(ctx.request_timer(Duration::from_secs(1)));
+        } else if !data.main_layout.is_reconnecting() {
+            self.countdown_timer = None;
+        }
+
+        child.update(ctx, old_data, data, env)
+    }
+}
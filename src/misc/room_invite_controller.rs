@@ -0,0 +1,54 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Selector, TimerToken};
+use std::time::Duration;
+
+use crate::global_params::*;
+use crate::ApplicationState;
+
+// Not handled by the delegate, goes to the widget tree (contains ID of the invite's chat message).
+pub const ROOM_INVITE_EXPIRY_TIMER_START: Selector<u64> =
+    Selector::new("room_invite_expiry_timer_start");
+
+// Hides the "Accept" and "Decline" buttons of room invites after ROOM_INVITE_EXPIRY_SEC.
+pub struct RoomInviteController {
+    timers: Vec<(TimerToken, u64)>, // timer - chat message ID
+}
+
+impl RoomInviteController {
+    pub fn new() -> Self {
+        RoomInviteController { timers: Vec::new() }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for RoomInviteController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::Command(command) if command.is(ROOM_INVITE_EXPIRY_TIMER_START) => {
+                let message_id = *command.get_unchecked(ROOM_INVITE_EXPIRY_TIMER_START);
+                let timer = ctx.request_timer(Duration::from_secs(ROOM_INVITE_EXPIRY_SEC));
+                self.timers.push((timer, message_id));
+                ctx.set_handled();
+                return;
+            }
+            Event::Timer(token) => {
+                if let Some(index) = self.timers.iter().position(|(timer, _)| timer == token) {
+                    let (_, message_id) = self.timers.remove(index);
+                    data.main_layout.chat_list.close_room_invite(message_id);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
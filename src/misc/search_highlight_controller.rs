@@ -0,0 +1,58 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Selector, TimerToken};
+use std::time::Duration;
+
+use crate::global_params::*;
+
+pub const SEARCH_HIGHLIGHT_EXPIRED: Selector<()> = Selector::new("search_highlight_expired");
+
+// Wraps the highlighted chat message (found by the search):
+// scrolls the chat to it and removes the highlight after SEARCH_HIGHLIGHT_DURATION_SEC.
+pub struct SearchHighlightController {
+    expire_timer: TimerToken,
+}
+
+impl SearchHighlightController {
+    pub fn new() -> Self {
+        SearchHighlightController {
+            expire_timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for SearchHighlightController {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::AnimFrame(_) if self.expire_timer == TimerToken::INVALID => {
+                // the layout is finished now, we know where to scroll
+                ctx.scroll_to_view();
+                self.expire_timer =
+                    ctx.request_timer(Duration::from_secs(SEARCH_HIGHLIGHT_DURATION_SEC));
+            }
+            Event::Timer(token) if *token == self.expire_timer => {
+                ctx.submit_command(SEARCH_HIGHLIGHT_EXPIRED);
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.request_anim_frame();
+        }
+
+        child.lifecycle(ctx, event, data, env)
+    }
+}
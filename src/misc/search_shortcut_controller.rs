@@ -0,0 +1,37 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{HotKey, SysMods};
+
+use crate::widgets::search_panel::SEARCH_PANEL_OPEN;
+use crate::ApplicationState;
+use crate::Layout;
+
+// Wraps the whole widget tree, opens the search panel on Ctrl+F.
+pub struct SearchShortcutController {}
+
+impl SearchShortcutController {
+    pub fn new() -> Self {
+        SearchShortcutController {}
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for SearchShortcutController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::KeyDown(key) = event {
+            if HotKey::new(SysMods::Cmd, "f").matches(key) && data.current_layout == Layout::Main {
+                ctx.submit_command(SEARCH_PANEL_OPEN);
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,100 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{HotKey, SysMods};
+
+use crate::layouts::settings_layout::SettingsLayout;
+use crate::ApplicationState;
+use crate::Layout;
+
+// Wraps the whole widget tree, opens the settings on Ctrl+, from any layout.
+pub struct SettingsShortcutController {}
+
+impl SettingsShortcutController {
+    pub fn new() -> Self {
+        SettingsShortcutController {}
+    }
+    /// Returns 'false' if the settings are already opened.
+    fn on_shortcut_pressed(data: &mut ApplicationState) -> bool {
+        if data.current_layout == Layout::Settings {
+            return false;
+        }
+
+        SettingsLayout::open(data);
+        true
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for SettingsShortcutController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::KeyDown(key) = event {
+            if HotKey::new(SysMods::Cmd, ",").matches(key)
+                && SettingsShortcutController::on_shortcut_pressed(data)
+            {
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_application_state;
+
+    fn press_shortcut_from(layout: Layout, is_connected: bool) -> ApplicationState {
+        let mut data = test_application_state();
+        data.current_layout = layout;
+        data.is_connected = is_connected;
+
+        assert!(SettingsShortcutController::on_shortcut_pressed(&mut data));
+        assert!(data.current_layout == Layout::Settings);
+
+        data
+    }
+
+    #[test]
+    fn shortcut_from_connect_layout_returns_to_it() {
+        let data = press_shortcut_from(Layout::Connect, false);
+
+        assert!(data.previous_layout == Layout::Connect);
+        assert!(SettingsLayout::get_layout_to_return_to(&data) == Layout::Connect);
+    }
+
+    #[test]
+    fn shortcut_from_main_layout_returns_to_it() {
+        let data = press_shortcut_from(Layout::Main, true);
+
+        assert!(data.previous_layout == Layout::Main);
+        assert!(SettingsLayout::get_layout_to_return_to(&data) == Layout::Main);
+    }
+
+    #[test]
+    fn disconnect_while_in_settings_returns_to_connect_layout() {
+        let mut data = press_shortcut_from(Layout::Main, true);
+
+        data.is_connected = false;
+
+        assert!(SettingsLayout::get_layout_to_return_to(&data) == Layout::Connect);
+    }
+
+    #[test]
+    fn shortcut_in_settings_keeps_previous_layout() {
+        let mut data = press_shortcut_from(Layout::Main, true);
+
+        assert!(!SettingsShortcutController::on_shortcut_pressed(&mut data));
+
+        assert!(data.current_layout == Layout::Settings);
+        assert!(data.previous_layout == Layout::Main);
+        assert!(SettingsLayout::get_layout_to_return_to(&data) == Layout::Main);
+    }
+}
@@ -0,0 +1,192 @@
+const MENTION_CHARACTER: char = '@';
+
+// Finds "@username" mentions of the connected users in the chat messages.
+pub struct TextHighlighter;
+
+impl TextHighlighter {
+    /// Returns sorted byte ranges (start, end) of "@username" in the text (including '@').
+    /// Matching is case-insensitive, if multiple usernames match ("@al" and "@alice")
+    /// the longest one is used, the mention must not continue with a letter or a digit
+    /// and must not follow one (so emails are not matched).
+    pub fn find_mentions(text: &str, users: &[String]) -> Vec<(usize, usize)> {
+        let mut mentions = Vec::new();
+
+        let mut search_from = 0;
+        while let Some(offset) = text[search_from..].find(MENTION_CHARACTER) {
+            let start = search_from + offset;
+            let name_start = start + MENTION_CHARACTER.len_utf8();
+            search_from = name_start;
+
+            let follows_word = text[..start]
+                .chars()
+                .last()
+                .is_some_and(|character| character.is_alphanumeric());
+            if follows_word {
+                continue;
+            }
+
+            let longest_match = users
+                .iter()
+                .filter_map(|username| TextHighlighter::match_username(text, name_start, username))
+                .max();
+            if let Some(end) = longest_match {
+                mentions.push((start, end));
+                search_from = end;
+            }
+        }
+
+        mentions
+    }
+    /// Returns usernames (without '@') mentioned in the text, without duplicates.
+    pub fn get_mentioned_users(text: &str, users: &[String]) -> Vec<String> {
+        let mut mentioned: Vec<String> = Vec::new();
+
+        for (start, end) in TextHighlighter::find_mentions(text, users) {
+            let mention = &text[start + MENTION_CHARACTER.len_utf8()..end];
+            if let Some(username) = users
+                .iter()
+                .find(|username| TextHighlighter::is_same_name(username, mention))
+            {
+                if !mentioned.contains(username) {
+                    mentioned.push(username.clone());
+                }
+            }
+        }
+
+        mentioned
+    }
+    /// Returns the byte offset where the username ends if the text at 'name_start'
+    /// starts with the username (case-insensitive) and the username is not followed
+    /// by a letter or a digit.
+    fn match_username(text: &str, name_start: usize, username: &str) -> Option<usize> {
+        if username.is_empty() {
+            return None;
+        }
+
+        let mut text_characters = text[name_start..].char_indices();
+        for username_character in username.chars() {
+            let (_, text_character) = text_characters.next()?;
+            if !text_character
+                .to_lowercase()
+                .eq(username_character.to_lowercase())
+            {
+                return None;
+            }
+        }
+
+        match text_characters.next() {
+            Some((offset, character)) => {
+                if character.is_alphanumeric() {
+                    None
+                } else {
+                    Some(name_start + offset)
+                }
+            }
+            None => Some(text.len()),
+        }
+    }
+    fn is_same_name(username: &str, mention: &str) -> bool {
+        username
+            .chars()
+            .flat_map(char::to_lowercase)
+            .eq(mention.chars().flat_map(char::to_lowercase))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users(usernames: &[&str]) -> Vec<String> {
+        usernames
+            .iter()
+            .map(|username| String::from(*username))
+            .collect()
+    }
+
+    #[test]
+    fn mentions_at_start_and_end() {
+        let users = users(&["alice", "bob"]);
+
+        assert_eq!(
+            TextHighlighter::find_mentions("@alice hi @bob", &users),
+            vec![(0, 6), (10, 14)]
+        );
+    }
+
+    #[test]
+    fn mention_followed_by_punctuation() {
+        let users = users(&["alice"]);
+
+        assert_eq!(
+            TextHighlighter::find_mentions("hi @alice, how are you?", &users),
+            vec![(3, 9)]
+        );
+    }
+
+    #[test]
+    fn longest_username_is_matched() {
+        let users = users(&["al", "alice", "ali"]);
+
+        assert_eq!(
+            TextHighlighter::find_mentions("@alice and @al", &users),
+            vec![(0, 6), (11, 14)]
+        );
+    }
+
+    #[test]
+    fn username_must_not_continue_with_letter_or_digit() {
+        let users = users(&["al"]);
+
+        assert!(TextHighlighter::find_mentions("@alice @al2", &users).is_empty());
+    }
+
+    #[test]
+    fn matching_is_case_insensitive() {
+        let users = users(&["Alice"]);
+
+        assert_eq!(
+            TextHighlighter::find_mentions("@alice @ALICE", &users),
+            vec![(0, 6), (7, 13)]
+        );
+    }
+
+    #[test]
+    fn emails_are_not_matched() {
+        let users = users(&["example"]);
+
+        assert!(TextHighlighter::find_mentions("write to me@example.com", &users).is_empty());
+    }
+
+    #[test]
+    fn non_ascii_usernames_use_byte_ranges() {
+        let users = users(&["Jürgen", "Влад"]);
+        let text = "привет @влад и @JÜRGEN";
+
+        let mentions = TextHighlighter::find_mentions(text, &users);
+
+        let mentioned: Vec<&str> = mentions
+            .iter()
+            .map(|(start, end)| &text[*start..*end])
+            .collect();
+        assert_eq!(mentioned, vec!["@влад", "@JÜRGEN"]);
+    }
+
+    #[test]
+    fn unknown_users_and_lone_mention_character_are_ignored() {
+        let users = users(&["alice"]);
+
+        assert!(TextHighlighter::find_mentions("@bob @ @", &users).is_empty());
+        assert!(TextHighlighter::find_mentions("@alice", &[]).is_empty());
+    }
+
+    #[test]
+    fn mentioned_users_are_not_duplicated() {
+        let users = users(&["Alice", "bob", "carol"]);
+
+        assert_eq!(
+            TextHighlighter::get_mentioned_users("@alice @bob @ALICE", &users),
+            vec![String::from("Alice"), String::from("bob")]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{Cursor, Point, Selector};
+
+use crate::global_params::*;
+use crate::ApplicationState;
+
+pub const CONNECTED_LIST_USER_DROPPED: Selector<UserDropInfo> =
+    Selector::new("connected_list_user_dropped");
+
+pub const CONNECTED_LIST_USER_DRAG_ENDED: Selector<()> =
+    Selector::new("connected_list_user_drag_ended");
+
+pub struct UserDropInfo {
+    pub username: String,
+    pub from_room: String,
+    pub to_room: String,
+}
+
+// Starts dragging a user (from the connected list) when the mouse
+// moves further than USER_DRAG_THRESHOLD with the left button held.
+pub struct UserDragController {
+    username: String,
+    from_room: String,
+    mouse_down_pos: Option<Point>,
+    is_dragging: bool,
+}
+
+impl UserDragController {
+    pub fn new(username: String, from_room: String) -> Self {
+        UserDragController {
+            username,
+            from_room,
+            mouse_down_pos: None,
+            is_dragging: false,
+        }
+    }
+    pub fn is_drag_threshold_exceeded(from: Point, to: Point) -> bool {
+        from.distance(to) > USER_DRAG_THRESHOLD
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for UserDragController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                self.mouse_down_pos = Some(mouse.window_pos);
+            }
+            Event::MouseMove(mouse) if self.is_dragging => {
+                ctx.set_cursor(&Cursor::OpenHand);
+                if !mouse.buttons.has_left() {
+                    // released outside of the window
+                    self.is_dragging = false;
+                    self.mouse_down_pos = None;
+                    ctx.submit_command(CONNECTED_LIST_USER_DRAG_ENDED);
+                }
+                return;
+            }
+            Event::MouseMove(mouse) if mouse.buttons.has_left() => {
+                if let Some(mouse_down_pos) = self.mouse_down_pos {
+                    if UserDragController::is_drag_threshold_exceeded(
+                        mouse_down_pos,
+                        mouse.window_pos,
+                    ) {
+                        self.is_dragging = true;
+                        data.main_layout.connected_list.dragged_username = self.username.clone();
+                        data.main_layout.connected_list.dragged_from_room = self.from_room.clone();
+                        ctx.set_cursor(&Cursor::OpenHand);
+                        return;
+                    }
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button.is_left() => {
+                self.mouse_down_pos = None;
+                if self.is_dragging {
+                    // don't treat this as a click on the user
+                    self.is_dragging = false;
+                    ctx.set_active(false);
+                    ctx.submit_command(CONNECTED_LIST_USER_DRAG_ENDED);
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
+
+// Accepts users dragged by UserDragController.
+pub struct RoomDropController {
+    room_name: String,
+}
+
+impl RoomDropController {
+    pub fn new(room_name: String) -> Self {
+        RoomDropController { room_name }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for RoomDropController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        let connected_list = &mut data.main_layout.connected_list;
+        if !connected_list.dragged_username.is_empty() {
+            match event {
+                Event::MouseMove(_) => {
+                    if ctx.is_hot() {
+                        if connected_list.drop_target_room != self.room_name {
+                            connected_list.drop_target_room = self.room_name.clone();
+                        }
+                    } else if connected_list.drop_target_room == self.room_name {
+                        connected_list.drop_target_room = String::new();
+                    }
+                }
+                Event::MouseUp(mouse) if mouse.button.is_left() && ctx.is_hot() => {
+                    ctx.submit_command(CONNECTED_LIST_USER_DROPPED.with(UserDropInfo {
+                        username: connected_list.dragged_username.clone(),
+                        from_room: connected_list.dragged_from_room.clone(),
+                        to_room: self.room_name.clone(),
+                    }));
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        child.event(ctx, event, data, env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_starts_after_threshold() {
+        let from = Point::new(100.0, 100.0);
+
+        for (to, is_exceeded) in [
+            (Point::new(100.0, 100.0), false),
+            (Point::new(100.0 + USER_DRAG_THRESHOLD, 100.0), false),
+            (Point::new(100.0 + USER_DRAG_THRESHOLD + 0.1, 100.0), true),
+            (Point::new(100.0, 100.0 - USER_DRAG_THRESHOLD - 0.1), true),
+            (Point::new(95.0, 106.0), false), // ~7.8 pixels
+            (Point::new(94.0, 106.0), true),  // ~8.5 pixels
+        ]
+        .iter()
+        {
+            assert_eq!(
+                UserDragController::is_drag_threshold_exceeded(from, *to),
+                *is_exceeded,
+                "moved to {:?}",
+                to
+            );
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::TimerToken;
+use std::time::Duration;
+
+use crate::ApplicationState;
+
+// Counts the session voice time (MainLayout::session_voice_seconds):
+// ticks every second while somebody (except us) is talking.
+pub struct VoiceSessionController {
+    session_timer: Option<TimerToken>,
+}
+
+impl VoiceSessionController {
+    pub fn new() -> Self {
+        VoiceSessionController {
+            session_timer: None,
+        }
+    }
+}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for VoiceSessionController {
+    fn event(
+        &mut self,
+        child: &mut W,
+        ctx: &mut EventCtx,
+        event: &Event,
+        data: &mut ApplicationState,
+        env: &Env,
+    ) {
+        if let Event::Timer(token) = event {
+            if Some(*token) == self.session_timer {
+                if data.main_layout.on_voice_session_tick() {
+                    self.session_timer = Some(ctx.request_timer(Duration::from_secs(1)));
+                } else {
+                    self.session_timer = None;
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        child.event(ctx, event, data, env)
+    }
+
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if data.main_layout.talking_user_count > 0 && self.session_timer.is_none() {
+            self.session_timer = Some(ctx.request_timer(Duration::from_secs(1)));
+        } else if data.main_layout.talking_user_count == 0 {
+            self.session_timer = None;
+        }
+
+        child.update(ctx, old_data, data, env)
+    }
+}
@@ -0,0 +1,181 @@
+// Std.
+use std::collections::VecDeque;
+
+pub const AGC_DEFAULT_TARGET_RMS: f32 = 0.1; // relative to i16::MAX
+pub const AGC_DEFAULT_MAX_GAIN: f32 = 10.0;
+const AGC_CHUNKS_TO_TRACK: usize = 25; // ~0.5 sec with 20 ms chunks
+
+// Peak-following automatic gain control.
+// Tracks the level of the last N chunks and scales new chunks
+// so that the output RMS moves towards 'target_rms'.
+pub struct AgcProcessor {
+    target_rms: f32,
+    max_gain: f32,
+    recent_rms: VecDeque<f32>,
+    recent_peaks: VecDeque<f32>,
+}
+
+impl AgcProcessor {
+    pub fn new(target_rms: f32, max_gain: f32) -> Self {
+        AgcProcessor {
+            target_rms: target_rms.max(0.0).min(1.0),
+            max_gain: max_gain.max(1.0),
+            recent_rms: VecDeque::with_capacity(AGC_CHUNKS_TO_TRACK),
+            recent_peaks: VecDeque::with_capacity(AGC_CHUNKS_TO_TRACK),
+        }
+    }
+    pub fn process(&mut self, samples: &mut [i16]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        // Measure input level (normalized to [0.0; 1.0]).
+        let mut sum_of_squares = 0.0f64;
+        let mut peak = 0.0f32;
+        for sample in samples.iter() {
+            let value = *sample as f32 / std::i16::MAX as f32;
+            sum_of_squares += (value * value) as f64;
+            if value.abs() > peak {
+                peak = value.abs();
+            }
+        }
+        let rms = (sum_of_squares / samples.len() as f64).sqrt() as f32;
+
+        self.recent_rms.push_back(rms);
+        self.recent_peaks.push_back(peak);
+        if self.recent_rms.len() > AGC_CHUNKS_TO_TRACK {
+            self.recent_rms.pop_front();
+            self.recent_peaks.pop_front();
+        }
+
+        let gain = self.calculate_gain();
+
+        // Apply gain.
+        samples.iter_mut().for_each(|sample| {
+            let mut new_sample = *sample as f32 * gain;
+            if new_sample > std::i16::MAX as f32 {
+                new_sample = std::i16::MAX as f32;
+            } else if new_sample < std::i16::MIN as f32 {
+                new_sample = std::i16::MIN as f32;
+            }
+            *sample = new_sample as i16;
+        });
+    }
+    fn calculate_gain(&self) -> f32 {
+        let average_rms = self.recent_rms.iter().sum::<f32>() / self.recent_rms.len() as f32;
+        let max_peak = self.recent_peaks.iter().cloned().fold(0.0f32, f32::max);
+
+        if average_rms <= std::f32::EPSILON || max_peak <= std::f32::EPSILON {
+            // silence, don't amplify it
+            return 1.0;
+        }
+
+        let mut gain = self.target_rms / average_rms;
+
+        // don't amplify silence/noise too much
+        if gain > self.max_gain {
+            gain = self.max_gain;
+        }
+
+        // keep recent peaks below full scale
+        let peak_limit = 1.0 / max_peak;
+        if gain > peak_limit {
+            gain = peak_limit;
+        }
+
+        gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const CHUNK_SIZE: usize = 480;
+    const CHUNK_COUNT: usize = 100;
+
+    fn white_noise(rng: &mut StdRng, amplitude: f32) -> Vec<i16> {
+        (0..CHUNK_SIZE)
+            .map(|_| (rng.gen_range(-amplitude..=amplitude) * i16::MAX as f32) as i16)
+            .collect()
+    }
+
+    fn rms(samples: &[i16]) -> f32 {
+        let sum_of_squares: f64 = samples
+            .iter()
+            .map(|sample| {
+                let value = *sample as f64 / i16::MAX as f64;
+                value * value
+            })
+            .sum();
+        (sum_of_squares / samples.len() as f64).sqrt() as f32
+    }
+
+    /// Feeds 'CHUNK_COUNT' chunks of white noise (uniform in [-amplitude; amplitude])
+    /// and returns the RMS of the last output chunk.
+    fn output_rms(amplitude: f32, target_rms: f32, max_gain: f32) -> f32 {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut agc = AgcProcessor::new(target_rms, max_gain);
+
+        let mut last_rms = 0.0;
+        for _ in 0..CHUNK_COUNT {
+            let mut chunk = white_noise(&mut rng, amplitude);
+            agc.process(&mut chunk);
+            last_rms = rms(&chunk);
+        }
+
+        last_rms
+    }
+
+    #[test]
+    fn output_rms_converges_to_target() {
+        // the RMS of uniform noise is 'amplitude / sqrt(3)'
+        for amplitude in [0.05, 0.1, 0.2, 0.5, 0.9] {
+            let result = output_rms(amplitude, AGC_DEFAULT_TARGET_RMS, AGC_DEFAULT_MAX_GAIN);
+            assert!(
+                (result - AGC_DEFAULT_TARGET_RMS).abs() < AGC_DEFAULT_TARGET_RMS * 0.1,
+                "amplitude {}: output RMS {}",
+                amplitude,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn gain_is_limited_by_max_gain() {
+        let amplitude = 0.002;
+        let input_rms = amplitude / 3.0f32.sqrt();
+
+        let result = output_rms(amplitude, AGC_DEFAULT_TARGET_RMS, AGC_DEFAULT_MAX_GAIN);
+
+        assert!(result < AGC_DEFAULT_TARGET_RMS);
+        assert!(result <= input_rms * AGC_DEFAULT_MAX_GAIN * 1.1);
+    }
+
+    #[test]
+    fn loud_input_does_not_overflow() {
+        let mut agc = AgcProcessor::new(0.9, AGC_DEFAULT_MAX_GAIN);
+
+        for _ in 0..CHUNK_COUNT {
+            let mut chunk = vec![16000, -16000];
+            chunk.resize(CHUNK_SIZE, 0);
+            agc.process(&mut chunk);
+
+            // the gain is limited by the peaks so the samples don't wrap around
+            assert!(chunk[0] > 16000 && chunk[1] < -16000);
+            assert_eq!(chunk[0], -chunk[1]);
+        }
+    }
+
+    #[test]
+    fn silence_is_not_amplified() {
+        let mut agc = AgcProcessor::new(AGC_DEFAULT_TARGET_RMS, AGC_DEFAULT_MAX_GAIN);
+        let mut chunk = vec![0i16; CHUNK_SIZE];
+
+        agc.process(&mut chunk);
+
+        assert!(chunk.iter().all(|sample| *sample == 0));
+    }
+}
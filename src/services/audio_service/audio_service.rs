@@ -1,43 +1,106 @@
 // External.
-use druid::{ExtEventSink, Selector, Target};
-use sfml::audio::SoundRecorderDriver;
+use druid::{Selector, Target};
 use sfml::audio::SoundSource;
 use sfml::audio::SoundStreamPlayer;
+use sfml::audio::{capture, SoundRecorderDriver};
 use sfml::audio::{Sound, SoundBuffer, SoundStatus};
 use system_wide_key_state::*;
+use tracing::warn;
 
 // Std.
 use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Custom.
+use super::agc::*;
+use super::echo_canceller::*;
+use super::microphone_boost::*;
+use super::silence_detector::*;
+use super::spatial_audio::*;
+use super::voice_activity_detector::*;
+use super::voice_codec::*;
 use super::voice_player::*;
 use super::voice_recorder::*;
+use super::voice_session_recording::*;
 use crate::global_params::*;
+use crate::services::event_sink::EventSink;
 use crate::services::net_service::*;
+use crate::services::tcp_packets::VoiceCodec;
 
 const INTERVAL_PROCESS_VOICE_MS: i32 = 10;
 const INTERVAL_CHECK_PUSH_TO_TALK_MS: u64 = 5;
-const SAMPLE_RATE: u32 = 34000;
-// if changing SAMPLES_IN_CHUNK you probably also need to change MIN_CHUNKS_TO_START_PLAY, MIN_CHUNKS_TO_RECORD and CHUNKS_TO_RECORD_AFTER_STOP
-const SAMPLES_IN_CHUNK: usize = 679; // ~20 ms with 34000 sample rate
-const MIN_CHUNKS_TO_RECORD: usize = 6;
+pub const SAMPLE_RATE: u32 = 24000; // Opus only supports 8, 12, 16, 24 and 48 kHz
+
+// if changing SAMPLES_IN_CHUNK you probably also need to change the default audio buffers
+// (see 'global_params') and CHUNKS_TO_RECORD_AFTER_STOP
+pub const SAMPLES_IN_CHUNK: usize = 480; // 20 ms with 24000 sample rate (Opus frames are 2.5-60 ms)
 const CHUNKS_TO_RECORD_AFTER_STOP: usize = 5; // after the push-to-talk button is unpressed, we will record N more chunks
-const MIN_CHUNKS_TO_START_PLAY: usize = 3;
+const MAX_BUFFER_CHUNKS_MULTIPLIER: usize = 10; // older chunks are dropped if the buffer has (play buffer * N) chunks
 const INTERVAL_WAIT_FOR_NEW_CHUNKS_MS: u64 = 10;
 
 pub const AUDIO_SERVICE_ON_USER_TALK_START: Selector<String> =
     Selector::new("audio_service_on_user_talk_start");
-pub const AUDIO_SERVICE_ON_USER_TALK_END: Selector<String> =
+pub const AUDIO_SERVICE_ON_USER_TALK_END: Selector<UserTalkEndInfo> =
     Selector::new("audio_service_on_user_talk_end");
+pub const AUDIO_SERVICE_BUFFER_OVERFLOW: Selector<String> =
+    Selector::new("audio_service_buffer_overflow");
+pub const AUDIO_SERVICE_RECORDING_STARTED: Selector<()> =
+    Selector::new("audio_service_recording_started");
+pub const AUDIO_SERVICE_RECORDING_STOPPED: Selector<()> =
+    Selector::new("audio_service_recording_stopped");
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PushToTalkState {
+    Pressed,
+    Releasing { since: Instant }, // released but still within the release delay
+    Released,
+}
+
+impl PushToTalkState {
+    /// Re-pressing the key while `Releasing` cancels the release.
+    pub fn next(self, is_key_pressed: bool, release_delay: Duration, now: Instant) -> Self {
+        match self {
+            PushToTalkState::Released => PushToTalkState::Released,
+            _ if is_key_pressed => PushToTalkState::Pressed,
+            PushToTalkState::Pressed => {
+                if release_delay.as_millis() == 0 {
+                    PushToTalkState::Released
+                } else {
+                    PushToTalkState::Releasing { since: now }
+                }
+            }
+            PushToTalkState::Releasing { since } => {
+                if now.duration_since(since) >= release_delay {
+                    PushToTalkState::Released
+                } else {
+                    PushToTalkState::Releasing { since }
+                }
+            }
+        }
+    }
+}
+
+// Statistics of a finished voice session.
+pub struct UserTalkEndInfo {
+    pub username: String,
+    pub talking_ms: u64,
+    pub bytes_received: u64,
+}
 
 pub struct UserVoiceData {
     pub username: String,
     pub user_volume: i32,
+    pub max_buffer_chunks: usize,
     chunks: VecDeque<Vec<i16>>,
     mtx_output_playing: Mutex<bool>,
+    dropped_chunks: u64,
+    is_dropping_chunks: bool,
+    opus_decoder: Option<OpusVoiceDecoder>, // created on the first Opus chunk of a voice session
+    pub spatial_config: SpatialConfig,
 }
 
 impl UserVoiceData {
@@ -47,7 +110,37 @@ impl UserVoiceData {
             chunks: VecDeque::new(),
             mtx_output_playing: Mutex::new(false),
             user_volume: 100,
+            max_buffer_chunks: DEFAULT_AUDIO_PLAY_BUFFER_CHUNKS as usize
+                * MAX_BUFFER_CHUNKS_MULTIPLIER,
+            dropped_chunks: 0,
+            is_dropping_chunks: false,
+            opus_decoder: None,
+            spatial_config: SpatialConfig::default(),
+        }
+    }
+    // Adds a chunk, drops the oldest one if the buffer is full (playback is stalled).
+    // Returns 'true' if started dropping chunks (wasn't dropping before).
+    pub fn push_chunk(&mut self, chunk: Vec<i16>) -> bool {
+        let mut started_dropping = false;
+
+        if self.chunks.len() >= self.max_buffer_chunks {
+            self.chunks.pop_front();
+            self.dropped_chunks += 1;
+
+            if !self.is_dropping_chunks {
+                self.is_dropping_chunks = true;
+                started_dropping = true;
+            }
+        } else {
+            self.is_dropping_chunks = false;
         }
+
+        self.chunks.push_back(chunk);
+
+        started_dropping
+    }
+    pub fn get_dropped_chunk_count(&self) -> u64 {
+        self.dropped_chunks
     }
 }
 
@@ -56,6 +149,28 @@ pub struct AudioService {
     pub users_voice_data: Arc<Mutex<Vec<Arc<Mutex<UserVoiceData>>>>>,
     pub net_service: Option<Arc<Mutex<NetService>>>,
     pub microphone_volume: i32,
+    pub microphone_boost_db: f32,
+    pub agc_enabled: bool,
+    pub agc_target_rms: f32,
+    pub ptt_release_delay_ms: u16,
+    pub aec_enabled: bool,
+    pub aec_delay_ms: u32,
+    pub silence_suppression_enabled: bool,
+    pub silence_threshold: f32,
+    pub spatial_audio_enabled: bool,
+    pub ptt_press_sound: String,
+    pub ptt_release_sound: String,
+    pub microphone_channels: u8,
+    pub push_to_talk_mode: PushToTalkMode,
+    pub vad_sensitivity: u8,
+    pub voice_codec: VoiceCodec,
+    pub audio_input_device: String, // empty to use the system default
+    pub audio_play_buffer_chunks: u8,
+    pub audio_record_buffer_chunks: u8,
+    input_level: Arc<Mutex<f32>>, // see 'take_input_level'
+    input_level_monitor_stop: Option<Arc<AtomicBool>>, // None if the monitor is not running
+    recording_session: Option<VoiceSessionRecording>, // None if not recording
+    echo_canceller: Arc<Mutex<Option<EchoCanceller>>>, // None if AEC is disabled
     mtx_listen_push_to_talk: Arc<Mutex<bool>>, // because Mutex does not implement Clone
     master_output_volume: i32,
 }
@@ -68,6 +183,28 @@ impl Default for AudioService {
             users_voice_data: Arc::new(Mutex::new(Vec::new())),
             master_output_volume: 0,
             microphone_volume: 0,
+            microphone_boost_db: 0.0,
+            agc_enabled: false,
+            agc_target_rms: AGC_DEFAULT_TARGET_RMS,
+            ptt_release_delay_ms: DEFAULT_PTT_RELEASE_DELAY_MS,
+            aec_enabled: false,
+            aec_delay_ms: DEFAULT_AEC_DELAY_MS,
+            silence_suppression_enabled: false,
+            silence_threshold: SILENCE_DEFAULT_THRESHOLD,
+            spatial_audio_enabled: false,
+            ptt_press_sound: String::from(PUSH_TO_TALK_PRESS_SOUND),
+            ptt_release_sound: String::from(PUSH_TO_TALK_UNPRESS_SOUND),
+            microphone_channels: DEFAULT_MICROPHONE_CHANNELS,
+            push_to_talk_mode: PushToTalkMode::HoldKey,
+            vad_sensitivity: VAD_DEFAULT_SENSITIVITY,
+            voice_codec: VoiceCodec::Opus,
+            audio_input_device: String::new(),
+            audio_play_buffer_chunks: DEFAULT_AUDIO_PLAY_BUFFER_CHUNKS,
+            audio_record_buffer_chunks: DEFAULT_AUDIO_RECORD_BUFFER_CHUNKS,
+            input_level: Arc::new(Mutex::new(0.0)),
+            input_level_monitor_stop: None,
+            recording_session: None,
+            echo_canceller: Arc::new(Mutex::new(None)),
         }
     }
 }
@@ -78,22 +215,207 @@ impl AudioService {
         net_service: Arc<Mutex<NetService>>,
         master_volume: i32,
         microphone_volume: i32,
+        microphone_boost_db: f32,
+        agc_enabled: bool,
+        agc_target_rms: f32,
+        ptt_release_delay_ms: u16,
+        aec_enabled: bool,
+        aec_delay_ms: u32,
+        silence_suppression_enabled: bool,
+        silence_threshold: f32,
+        spatial_audio_enabled: bool,
+        ptt_press_sound: &str,
+        ptt_release_sound: &str,
+        microphone_channels: u8,
+        push_to_talk_mode: PushToTalkMode,
+        vad_sensitivity: u8,
+        voice_codec: VoiceCodec,
+        audio_input_device: &str,
+        audio_play_buffer_chunks: u8,
+        audio_record_buffer_chunks: u8,
     ) {
         self.net_service = Some(net_service);
         self.master_output_volume = master_volume;
         self.microphone_volume = microphone_volume;
+        self.microphone_boost_db = microphone_boost_db;
+        self.agc_enabled = agc_enabled;
+        self.agc_target_rms = agc_target_rms;
+        self.ptt_release_delay_ms = ptt_release_delay_ms;
+        self.aec_enabled = aec_enabled;
+        self.aec_delay_ms = aec_delay_ms;
+        self.silence_suppression_enabled = silence_suppression_enabled;
+        self.silence_threshold = silence_threshold;
+        self.spatial_audio_enabled = spatial_audio_enabled;
+        self.ptt_press_sound =
+            AudioService::get_valid_sound_path(ptt_press_sound, PUSH_TO_TALK_PRESS_SOUND);
+        self.ptt_release_sound =
+            AudioService::get_valid_sound_path(ptt_release_sound, PUSH_TO_TALK_UNPRESS_SOUND);
+        self.microphone_channels = microphone_channels;
+        self.push_to_talk_mode = push_to_talk_mode;
+        self.vad_sensitivity = vad_sensitivity;
+        self.voice_codec = voice_codec;
+        self.audio_input_device = String::from(audio_input_device);
+        self.audio_play_buffer_chunks = audio_play_buffer_chunks;
+        self.audio_record_buffer_chunks = audio_record_buffer_chunks;
+    }
+    /// Returns names of the connected microphones.
+    pub fn list_audio_input_devices() -> Vec<String> {
+        if !capture::is_available() {
+            return Vec::new();
+        }
+
+        capture::available_devices()
+    }
+    /// Starts recording the microphone (without sending anything) to measure its level
+    /// regardless of the push-to-talk state, does nothing if already started.
+    /// If the voice recording thread is running the level is taken from its recorder instead
+    /// (the microphone is not opened twice).
+    pub fn start_input_level_monitor(&mut self) {
+        let is_recording_thread_started = *self.mtx_listen_push_to_talk.lock().unwrap();
+        if self.input_level_monitor_stop.is_some() || is_recording_thread_started {
+            return;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        self.input_level_monitor_stop = Some(Arc::clone(&stop));
+
+        let input_level = Arc::clone(&self.input_level);
+        let microphone_volume = self.microphone_volume;
+        let microphone_channels = self.microphone_channels;
+        let audio_input_device = self.audio_input_device.clone();
+
+        thread::spawn(move || {
+            let (sample_sender, sample_receiver) = mpsc::channel();
+            let mut voice_recorder =
+                VoiceRecorder::new(sample_sender, microphone_volume, microphone_channels as u32)
+                    .with_input_level(input_level);
+            let mut driver = SoundRecorderDriver::new(&mut voice_recorder);
+            AudioService::start_recorder_driver(
+                &mut driver,
+                microphone_channels,
+                &audio_input_device,
+            );
+
+            while !stop.load(Ordering::Relaxed) {
+                // the samples are not needed, only the level
+                let _ = sample_receiver
+                    .recv_timeout(Duration::from_millis(INTERVAL_PROCESS_VOICE_MS as u64));
+            }
+
+            driver.stop();
+        });
+    }
+    pub fn stop_input_level_monitor(&mut self) {
+        if let Some(stop) = self.input_level_monitor_stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+
+        *self.input_level.lock().unwrap() = 0.0;
+    }
+    /// Returns the peak microphone level (1.0 is 0 dBFS, above 1.0 if clipping)
+    /// since the last call.
+    pub fn take_input_level(&self) -> f32 {
+        let mut input_level_guard = self.input_level.lock().unwrap();
+        let input_level = *input_level_guard;
+        *input_level_guard = 0.0;
+
+        input_level
+    }
+    /// Starts mixing the received voice chunks of all users into 'path' (WAV),
+    /// the file is finished in 'stop_recording_session'.
+    pub fn start_recording_session(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(recording) = self.recording_session.as_ref() {
+            return Err(format!(
+                "already recording to \"{}\" at [{}, {}]",
+                recording.get_path().display(),
+                file!(),
+                line!()
+            ));
+        }
+
+        match VoiceSessionRecording::new(path) {
+            Ok(recording) => self.recording_session = Some(recording),
+            Err(msg) => return Err(format!("{} at [{}, {}]", msg, file!(), line!())),
+        }
+
+        Ok(())
+    }
+    /// Returns the path of the saved file, None if the recording was not started
+    /// or nobody talked (the file is removed then).
+    pub fn stop_recording_session(&mut self) -> Result<Option<PathBuf>, String> {
+        let recording = self.recording_session.take();
+        if recording.is_none() {
+            return Ok(None);
+        }
+        let recording = recording.unwrap();
+
+        if recording.is_empty() {
+            if let Err(msg) = recording.discard() {
+                return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
+            }
+            return Ok(None);
+        }
+
+        let path = recording.get_path().to_path_buf();
+        if let Err(msg) = recording.finish() {
+            return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
+        }
+
+        Ok(Some(path))
+    }
+    pub fn is_recording_session_started(&self) -> bool {
+        self.recording_session.is_some()
+    }
+    /// Empty name (system default) is always available.
+    pub fn is_audio_input_device_available(device_name: &str) -> bool {
+        device_name.is_empty()
+            || AudioService::list_audio_input_devices()
+                .iter()
+                .any(|name| name == device_name)
+    }
+    /// Returns 'true' if the file exists and SFML can load it.
+    pub fn is_sound_file_valid(path: &str) -> bool {
+        Path::new(path).is_file() && SoundBuffer::from_file(path).is_some()
+    }
+    /// Returns the specified path if the sound file is valid, otherwise the default path.
+    pub fn get_valid_sound_path(path: &str, default_path: &str) -> String {
+        if AudioService::is_sound_file_valid(path) {
+            return String::from(path);
+        }
+
+        warn!(
+            "sound file \"{}\" can't be loaded, using \"{}\" instead",
+            path, default_path
+        );
+        String::from(default_path)
+    }
+    /// Plays the sound file in a separate thread.
+    pub fn play_sound_file(path: String) {
+        thread::spawn(move || {
+            let buffer = SoundBuffer::from_file(&path);
+            if buffer.is_none() {
+                warn!(
+                    "failed to load sound file \"{}\" at [{}, {}]",
+                    path,
+                    file!(),
+                    line!()
+                );
+                return;
+            }
+            let buffer = buffer.unwrap();
+            let mut sound = Sound::with_buffer(&buffer);
+            sound.play();
+            while sound.status() == SoundStatus::PLAYING {
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        });
     }
     pub fn add_user_voice_chunk(
         &mut self,
         username: String,
-        voice_data: Vec<i16>,
-        event_sink: ExtEventSink,
+        voice_chunk: VoiceChunk,
+        event_sink: EventSink,
     ) {
-        if voice_data.len() != 0 && voice_data.len() != SAMPLES_IN_CHUNK {
-            println!("SILENT_WARNING: received chunk of samples has incompatible size: expected {}, received {}. The client that's sending this voice message ({}) has an incompatible (probably modified) version.", SAMPLES_IN_CHUNK, voice_data.len(), username);
-            return;
-        }
-
         let users_voice_data_guard = self.users_voice_data.lock().unwrap();
 
         let mut found = false;
@@ -109,7 +431,53 @@ impl AudioService {
 
         if found {
             let mut user_guard = users_voice_data_guard[found_index].lock().unwrap();
-            user_guard.chunks.push_back(voice_data);
+
+            let voice_data = match voice_chunk {
+                VoiceChunk::Pcm(samples) => {
+                    if samples.is_empty() {
+                        // the voice session is finished, the next one starts with a new decoder
+                        user_guard.opus_decoder = None;
+                    }
+                    samples
+                }
+                VoiceChunk::Opus(data) => {
+                    let res = AudioService::decode_opus_chunk(&mut user_guard, &data);
+                    if let Err(msg) = res {
+                        warn!(
+                            "failed to decode voice chunk of user '{}': {}",
+                            username, msg
+                        );
+                        return;
+                    }
+                    res.unwrap()
+                }
+            };
+
+            if voice_data.len() != 0 && voice_data.len() != SAMPLES_IN_CHUNK {
+                warn!("received chunk of samples has incompatible size: expected {}, received {}. The client that's sending this voice message ({}) has an incompatible (probably modified) version.", SAMPLES_IN_CHUNK, voice_data.len(), username);
+                return;
+            }
+
+            if !voice_data.is_empty() {
+                if let Some(recording) = self.recording_session.as_mut() {
+                    if let Err(msg) = recording.add_chunk(&username, &voice_data, Instant::now()) {
+                        warn!("{}", msg);
+                    }
+                }
+            }
+
+            user_guard.max_buffer_chunks =
+                self.audio_play_buffer_chunks as usize * MAX_BUFFER_CHUNKS_MULTIPLIER;
+            if user_guard.push_chunk(voice_data) {
+                warn!(
+                    "voice buffer of user '{}' is full, dropping old chunks (dropped {} chunk(s) in total).",
+                    username,
+                    user_guard.get_dropped_chunk_count()
+                );
+                event_sink
+                    .submit_command(AUDIO_SERVICE_BUFFER_OVERFLOW, username, Target::Auto)
+                    .expect("failed to submit AUDIO_SERVICE_BUFFER_OVERFLOW command");
+            }
             if user_guard.chunks.len() == 1 {
                 let mut play_guard = user_guard.mtx_output_playing.lock().unwrap();
                 if *play_guard == false {
@@ -117,25 +485,51 @@ impl AudioService {
                     *play_guard = true; // playing
                     let user_copy = Arc::clone(&users_voice_data_guard[found_index]);
                     let master_volume = self.master_output_volume;
+                    let echo_canceller = Arc::clone(&self.echo_canceller);
+                    let spatial_audio_enabled = self.spatial_audio_enabled;
+                    let play_buffer_chunks = self.audio_play_buffer_chunks as usize;
                     thread::spawn(move || {
-                        AudioService::play_user_voice(user_copy, master_volume, event_sink);
+                        let event_sink_copy = event_sink.clone();
+                        NetService::run_catching_panic(
+                            &event_sink_copy,
+                            "voice player",
+                            move || {
+                                AudioService::play_user_voice(
+                                    user_copy,
+                                    master_volume,
+                                    echo_canceller,
+                                    spatial_audio_enabled,
+                                    play_buffer_chunks,
+                                    event_sink,
+                                );
+                            },
+                        );
                     });
                 }
             }
         } else {
-            println!(
-                "warning: can't find user ('{}') to add voice chunk at [{}, {}]",
+            warn!(
+                "can't find user ('{}') to add voice chunk at [{}, {}]",
                 username,
                 file!(),
                 line!()
             );
         }
     }
+    /// Fills the gap left by suppressed silent chunks with comfort noise.
+    pub fn add_user_silence_marker(&mut self, username: String, event_sink: EventSink) {
+        self.add_user_voice_chunk(
+            username,
+            VoiceChunk::Pcm(SilenceDetector::generate_comfort_noise(SAMPLES_IN_CHUNK)),
+            event_sink,
+        );
+    }
     pub fn start_waiting_for_voice(
         &self,
         push_to_talk_key: KeyCode,
         net_service: Arc<Mutex<NetService>>,
         microphone_volume: i32,
+        event_sink: EventSink,
     ) {
         let mut guard = self.mtx_listen_push_to_talk.lock().unwrap();
         if *guard {
@@ -145,17 +539,100 @@ impl AudioService {
             *guard = true;
         }
 
+        // the recording thread will update the input level
+        if let Some(stop) = self.input_level_monitor_stop.as_ref() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        let input_level = Arc::clone(&self.input_level);
+
+        let microphone_boost = MicrophoneBoost::new(self.microphone_boost_db);
+        let agc_enabled = self.agc_enabled;
+        let agc_target_rms = self.agc_target_rms;
+        let ptt_release_delay = Duration::from_millis(self.ptt_release_delay_ms as u64);
+        let ptt_press_sound = self.ptt_press_sound.clone();
+        let ptt_release_sound = self.ptt_release_sound.clone();
+        let microphone_channels = self.microphone_channels;
+        let silence_detector = if self.silence_suppression_enabled {
+            Some(SilenceDetector::new(self.silence_threshold))
+        } else {
+            None
+        };
+
+        // AEC settings are applied on the next connection (when the recording thread starts)
+        if self.aec_enabled {
+            *self.echo_canceller.lock().unwrap() = Some(EchoCanceller::new(
+                self.aec_delay_ms,
+                SAMPLE_RATE,
+                AEC_DEFAULT_SUPPRESSION_FACTOR,
+            ));
+        } else {
+            *self.echo_canceller.lock().unwrap() = None;
+        }
+        let echo_canceller = Arc::clone(&self.echo_canceller);
+        let voice_codec = self.voice_codec;
+        let audio_input_device = self.audio_input_device.clone();
+        let record_buffer_chunks = self.audio_record_buffer_chunks as usize;
+        let recorder_event_sink = event_sink.clone();
+
+        if self.push_to_talk_mode == PushToTalkMode::VoiceActivated {
+            let vad_sensitivity = self.vad_sensitivity;
+            thread::spawn(move || {
+                NetService::run_catching_panic(&event_sink, "voice recorder", move || {
+                    AudioService::record_voice_activated(
+                        net_service,
+                        microphone_volume,
+                        microphone_boost,
+                        agc_enabled,
+                        agc_target_rms,
+                        microphone_channels,
+                        echo_canceller,
+                        silence_detector,
+                        vad_sensitivity,
+                        voice_codec,
+                        audio_input_device,
+                        input_level,
+                        recorder_event_sink,
+                    );
+                });
+            });
+            return;
+        }
+
         thread::spawn(move || {
-            AudioService::record_voice(push_to_talk_key, net_service, microphone_volume);
+            NetService::run_catching_panic(&event_sink, "voice recorder", move || {
+                AudioService::record_voice(
+                    push_to_talk_key,
+                    net_service,
+                    microphone_volume,
+                    microphone_boost,
+                    agc_enabled,
+                    agc_target_rms,
+                    ptt_release_delay,
+                    ptt_press_sound,
+                    ptt_release_sound,
+                    microphone_channels,
+                    echo_canceller,
+                    silence_detector,
+                    voice_codec,
+                    audio_input_device,
+                    record_buffer_chunks,
+                    input_level,
+                    recorder_event_sink,
+                );
+            });
         });
     }
 }
 
 impl AudioService {
+    #[tracing::instrument(skip_all)]
     pub fn play_user_voice(
         user: Arc<Mutex<UserVoiceData>>,
         master_volume: i32,
-        event_sink: ExtEventSink,
+        echo_canceller: Arc<Mutex<Option<EchoCanceller>>>,
+        spatial_audio_enabled: bool,
+        play_buffer_chunks: usize, // chunks to receive before starting to play
+        event_sink: EventSink,
     ) {
         let mut stop = false;
         let mut last_time_recv_chunk = chrono::Local::now();
@@ -175,7 +652,7 @@ impl AudioService {
                     }
                 }
 
-                if user_guard.chunks.len() >= MIN_CHUNKS_TO_START_PLAY {
+                if user_guard.chunks.len() >= play_buffer_chunks {
                     sleep = false;
                 }
             }
@@ -205,16 +682,35 @@ impl AudioService {
 
         // Ready to play audio.
         let (sample_sender, sample_receiver) = mpsc::channel();
-        let mut voice_player = VoicePlayer::new(sample_receiver, SAMPLE_RATE);
+        let mut spatial_processor = if spatial_audio_enabled {
+            Some(SpatialAudioProcessor::new())
+        } else {
+            None
+        };
+        let mut voice_player = VoicePlayer::new(
+            sample_receiver,
+            SAMPLE_RATE,
+            if spatial_audio_enabled { 2 } else { 1 },
+        );
+        // always plays to the system default output device: unlike capture devices
+        // SFML 2.5 (and CSFML that we link to) can't list or select playback devices
         let mut player = SoundStreamPlayer::new(&mut voice_player);
 
+        let talk_start_time = chrono::Local::now();
         let mut _sent_chunks: usize = 0;
         let mut _user_volume = 100;
         // Send initial chunks to player.
         {
             let mut user_guard = user.lock().unwrap();
             for chunk in user_guard.chunks.iter() {
-                sample_sender.send(chunk.clone()).unwrap();
+                if let Some(echo_canceller) = echo_canceller.lock().unwrap().as_mut() {
+                    echo_canceller.add_reference(chunk);
+                }
+                let mut chunk = chunk.clone();
+                if let Some(spatial_processor) = spatial_processor.as_mut() {
+                    spatial_processor.apply(&mut chunk, &user_guard.spatial_config);
+                }
+                sample_sender.send(chunk).unwrap();
                 _sent_chunks += 1;
             }
             user_guard.chunks.clear();
@@ -262,8 +758,17 @@ impl AudioService {
                             // last chunk
                             stop = true;
                             // don't 'break' here, we need to send this to voice player
+                        } else {
+                            _sent_chunks += 1;
+                        }
+                        if let Some(echo_canceller) = echo_canceller.lock().unwrap().as_mut() {
+                            echo_canceller.add_reference(chunk);
                         }
-                        sample_sender.send(chunk.clone()).unwrap();
+                        let mut chunk = chunk.clone();
+                        if let Some(spatial_processor) = spatial_processor.as_mut() {
+                            spatial_processor.apply(&mut chunk, &user_guard.spatial_config);
+                        }
+                        sample_sender.send(chunk).unwrap();
                     }
                     user_guard.chunks.clear();
                 }
@@ -291,10 +796,18 @@ impl AudioService {
             *user_guard.mtx_output_playing.lock().unwrap() = false;
 
             {
+                let talking_ms = (chrono::Local::now() - talk_start_time).num_milliseconds();
                 event_sink
                     .submit_command(
                         AUDIO_SERVICE_ON_USER_TALK_END,
-                        user_guard.username.clone(),
+                        UserTalkEndInfo {
+                            username: user_guard.username.clone(),
+                            talking_ms: talking_ms.max(0) as u64,
+                            bytes_received: (_sent_chunks
+                                * SAMPLES_IN_CHUNK
+                                * std::mem::size_of::<i16>())
+                                as u64,
+                        },
                         Target::Auto,
                     )
                     .expect("failed to submit AUDIO_SERVICE_ON_USER_TALK_END command");
@@ -305,36 +818,58 @@ impl AudioService {
         push_to_talk_key: KeyCode,
         network_service: Arc<Mutex<NetService>>,
         microphone_volume: i32,
+        microphone_boost: MicrophoneBoost,
+        agc_enabled: bool,
+        agc_target_rms: f32,
+        ptt_release_delay: Duration,
+        ptt_press_sound: String,
+        ptt_release_sound: String,
+        microphone_channels: u8,
+        echo_canceller: Arc<Mutex<Option<EchoCanceller>>>,
+        mut silence_detector: Option<SilenceDetector>, // None if silence suppression is disabled
+        voice_codec: VoiceCodec,
+        audio_input_device: String,
+        record_buffer_chunks: usize, // chunks to send before checking the push-to-talk button
+        input_level: Arc<Mutex<f32>>,
+        event_sink: EventSink,
     ) {
         let mut push_to_talk_pressed = false;
+        let mut agc = AgcProcessor::new(agc_target_rms, AGC_DEFAULT_MAX_GAIN);
 
         loop {
             if is_key_pressed(push_to_talk_key) && push_to_talk_pressed == false {
                 let (sample_sender, sample_receiver) = mpsc::channel();
-                let mut voice_recorder = VoiceRecorder::new(sample_sender, microphone_volume);
+                let mut voice_recorder = VoiceRecorder::new(
+                    sample_sender,
+                    microphone_volume,
+                    microphone_channels as u32,
+                )
+                .with_input_level(Arc::clone(&input_level));
                 let mut driver = SoundRecorderDriver::new(&mut voice_recorder);
-
-                driver.set_processing_interval(sfml::system::Time::milliseconds(
-                    INTERVAL_PROCESS_VOICE_MS,
-                ));
-                driver.set_channel_count(1);
-                driver.start(SAMPLE_RATE);
+                AudioService::start_recorder_driver(
+                    &mut driver,
+                    microphone_channels,
+                    &audio_input_device,
+                );
                 push_to_talk_pressed = true;
 
+                event_sink
+                    .submit_command(AUDIO_SERVICE_RECORDING_STARTED, (), Target::Auto)
+                    .expect("failed to submit AUDIO_SERVICE_RECORDING_STARTED command");
+
                 // Play push-to-talk sound.
-                thread::spawn(move || {
-                    let buffer = SoundBuffer::from_file(PUSH_TO_TALK_PRESS_SOUND).unwrap();
-                    let mut sound = Sound::with_buffer(&buffer);
-                    sound.play();
-                    while sound.status() == SoundStatus::PLAYING {
-                        std::thread::sleep(Duration::from_secs(1));
-                    }
-                });
+                AudioService::play_sound_file(ptt_press_sound.clone());
+
+                if let Some(detector) = silence_detector.as_mut() {
+                    detector.reset();
+                }
+
+                let mut opus_encoder = AudioService::create_voice_encoder(voice_codec);
 
                 let mut recorded_chunk_count = 0usize;
                 let mut samples: Vec<i16> = Vec::new();
                 let mut end_recording = false;
-                let mut push_to_talk_unpressed = false;
+                let mut push_to_talk_state = PushToTalkState::Pressed;
                 let mut stopped_driver = false;
                 let mut chunk_count_to_record_after_unpress = CHUNKS_TO_RECORD_AFTER_STOP;
 
@@ -346,27 +881,39 @@ impl AudioService {
 
                     let mut current_chunk = res.unwrap();
 
+                    AudioService::process_recorded_chunk(
+                        &mut current_chunk,
+                        &echo_canceller,
+                        &microphone_boost,
+                        if agc_enabled { Some(&mut agc) } else { None },
+                    );
+
                     samples.append(&mut current_chunk);
 
                     while samples.len() >= SAMPLES_IN_CHUNK {
                         let voice_chunk: Vec<i16> = samples.drain(0..SAMPLES_IN_CHUNK).collect();
 
-                        {
-                            // Send to net service.
-                            let net_service_guard = network_service.lock().unwrap();
+                        let chunk_kind = match silence_detector.as_mut() {
+                            Some(detector) => detector.process(&voice_chunk),
+                            None => VoiceChunkKind::Voice,
+                        };
 
-                            net_service_guard
-                                .user_udp_service
-                                .lock()
-                                .unwrap()
-                                .send_voice_message(voice_chunk);
-                        }
+                        AudioService::send_voice_chunk(
+                            &network_service,
+                            voice_chunk,
+                            chunk_kind,
+                            opus_encoder.as_mut(),
+                        );
 
-                        if recorded_chunk_count >= MIN_CHUNKS_TO_RECORD {
+                        if recorded_chunk_count >= record_buffer_chunks {
                             // see if we need to stop
-                            if push_to_talk_unpressed || is_key_pressed(push_to_talk_key) == false {
-                                push_to_talk_unpressed = true;
+                            push_to_talk_state = push_to_talk_state.next(
+                                is_key_pressed(push_to_talk_key),
+                                ptt_release_delay,
+                                Instant::now(),
+                            );
 
+                            if push_to_talk_state == PushToTalkState::Released {
                                 if chunk_count_to_record_after_unpress == 0
                                     && stopped_driver == false
                                 {
@@ -393,25 +940,13 @@ impl AudioService {
                 }
 
                 // Play push-to-talk sound.
-                thread::spawn(move || {
-                    let buffer = SoundBuffer::from_file(PUSH_TO_TALK_UNPRESS_SOUND).unwrap();
-                    let mut sound = Sound::with_buffer(&buffer);
-                    sound.play();
-                    while sound.status() == SoundStatus::PLAYING {
-                        std::thread::sleep(Duration::from_secs(1));
-                    }
-                });
+                AudioService::play_sound_file(ptt_release_sound.clone());
 
-                // Send emtpy packet as final
-                {
-                    let empty_data: Vec<i16> = Vec::new();
-                    let net_service_guard = network_service.lock().unwrap();
-                    net_service_guard
-                        .user_udp_service
-                        .lock()
-                        .unwrap()
-                        .send_voice_message(empty_data);
-                }
+                AudioService::send_voice_end(&network_service);
+
+                event_sink
+                    .submit_command(AUDIO_SERVICE_RECORDING_STOPPED, (), Target::Auto)
+                    .expect("failed to submit AUDIO_SERVICE_RECORDING_STOPPED command");
             } else if push_to_talk_pressed {
                 push_to_talk_pressed = false;
             }
@@ -419,4 +954,351 @@ impl AudioService {
             thread::sleep(Duration::from_millis(INTERVAL_CHECK_PUSH_TO_TALK_MS));
         }
     }
+    /// Voice activated mode: the microphone is always recorded, chunks are only sent
+    /// while the voice activity detector says so (the push-to-talk key is ignored).
+    pub fn record_voice_activated(
+        network_service: Arc<Mutex<NetService>>,
+        microphone_volume: i32,
+        microphone_boost: MicrophoneBoost,
+        agc_enabled: bool,
+        agc_target_rms: f32,
+        microphone_channels: u8,
+        echo_canceller: Arc<Mutex<Option<EchoCanceller>>>,
+        mut silence_detector: Option<SilenceDetector>, // None if silence suppression is disabled
+        vad_sensitivity: u8,
+        voice_codec: VoiceCodec,
+        audio_input_device: String,
+        input_level: Arc<Mutex<f32>>,
+        event_sink: EventSink,
+    ) {
+        let mut agc = AgcProcessor::new(agc_target_rms, AGC_DEFAULT_MAX_GAIN);
+        let mut voice_activity_detector = VoiceActivityDetector::new(vad_sensitivity);
+
+        let (sample_sender, sample_receiver) = mpsc::channel();
+        let mut voice_recorder =
+            VoiceRecorder::new(sample_sender, microphone_volume, microphone_channels as u32)
+                .with_input_level(input_level);
+        let mut driver = SoundRecorderDriver::new(&mut voice_recorder);
+        AudioService::start_recorder_driver(&mut driver, microphone_channels, &audio_input_device);
+
+        let mut samples: Vec<i16> = Vec::new();
+        let mut is_transmitting = false;
+        let mut opus_encoder: Option<OpusVoiceEncoder> = None;
+
+        loop {
+            let res = sample_receiver.recv();
+            if let Err(e) = res {
+                panic!("error: {} at [{}, {}]", e, file!(), line!());
+            }
+
+            let mut current_chunk = res.unwrap();
+
+            AudioService::process_recorded_chunk(
+                &mut current_chunk,
+                &echo_canceller,
+                &microphone_boost,
+                if agc_enabled { Some(&mut agc) } else { None },
+            );
+
+            samples.append(&mut current_chunk);
+
+            while samples.len() >= SAMPLES_IN_CHUNK {
+                let voice_chunk: Vec<i16> = samples.drain(0..SAMPLES_IN_CHUNK).collect();
+
+                let is_voice = voice_activity_detector.process(&voice_chunk, Instant::now());
+                if is_voice && !is_transmitting {
+                    is_transmitting = true;
+
+                    if let Some(detector) = silence_detector.as_mut() {
+                        detector.reset();
+                    }
+
+                    opus_encoder = AudioService::create_voice_encoder(voice_codec);
+
+                    event_sink
+                        .submit_command(AUDIO_SERVICE_RECORDING_STARTED, (), Target::Auto)
+                        .expect("failed to submit AUDIO_SERVICE_RECORDING_STARTED command");
+                } else if !is_voice && is_transmitting {
+                    is_transmitting = false;
+
+                    AudioService::send_voice_end(&network_service);
+
+                    event_sink
+                        .submit_command(AUDIO_SERVICE_RECORDING_STOPPED, (), Target::Auto)
+                        .expect("failed to submit AUDIO_SERVICE_RECORDING_STOPPED command");
+                }
+
+                if !is_transmitting {
+                    continue;
+                }
+
+                let chunk_kind = match silence_detector.as_mut() {
+                    Some(detector) => detector.process(&voice_chunk),
+                    None => VoiceChunkKind::Voice,
+                };
+
+                AudioService::send_voice_chunk(
+                    &network_service,
+                    voice_chunk,
+                    chunk_kind,
+                    opus_encoder.as_mut(),
+                );
+            }
+        }
+    }
+    /// Uses the system default device if 'audio_input_device' is empty or can't be opened.
+    fn start_recorder_driver(
+        driver: &mut SoundRecorderDriver<VoiceRecorder>,
+        microphone_channels: u8,
+        audio_input_device: &str,
+    ) {
+        driver.set_processing_interval(sfml::system::Time::milliseconds(INTERVAL_PROCESS_VOICE_MS));
+        // some microphones only work with stereo capture (downmixed to mono by the recorder)
+        driver.set_channel_count(microphone_channels as u32);
+
+        if !audio_input_device.is_empty() && driver.set_device(audio_input_device).is_err() {
+            warn!(
+                "failed to open the audio input device \"{}\", using the default one instead",
+                audio_input_device
+            );
+            let _ = driver.set_device(&capture::default_device());
+        }
+
+        driver.start(SAMPLE_RATE);
+    }
+    /// Applies the echo cancellation and the gain (boost and AGC) to the recorded samples.
+    fn process_recorded_chunk(
+        samples: &mut Vec<i16>,
+        echo_canceller: &Arc<Mutex<Option<EchoCanceller>>>,
+        microphone_boost: &MicrophoneBoost,
+        agc: Option<&mut AgcProcessor>, // None if AGC is disabled
+    ) {
+        // remove the echo before the gain is changed
+        if let Some(echo_canceller) = echo_canceller.lock().unwrap().as_mut() {
+            echo_canceller.process(samples);
+        }
+
+        if microphone_boost.is_enabled() {
+            microphone_boost.process(samples);
+        }
+
+        if let Some(agc) = agc {
+            agc.process(samples);
+        }
+    }
+    fn send_voice_chunk(
+        network_service: &Arc<Mutex<NetService>>,
+        voice_chunk: Vec<i16>,
+        chunk_kind: VoiceChunkKind,
+        opus_encoder: Option<&mut OpusVoiceEncoder>, // None to send raw samples
+    ) {
+        let net_service_guard = network_service.lock().unwrap();
+
+        // keep recording while paused, just don't send anything
+        if net_service_guard.is_voice_transmission_paused() {
+            return;
+        }
+
+        let mut udp_service_guard = net_service_guard.user_udp_service.lock().unwrap();
+        match chunk_kind {
+            VoiceChunkKind::Voice => {
+                udp_service_guard.send_voice_message(voice_chunk, opus_encoder)
+            }
+            VoiceChunkKind::SilenceMarker => udp_service_guard.send_silence_marker(),
+            VoiceChunkKind::Silence => {}
+        }
+    }
+    /// Sends an empty voice packet that tells the other users that we stopped talking.
+    fn send_voice_end(network_service: &Arc<Mutex<NetService>>) {
+        let empty_data: Vec<i16> = Vec::new();
+        let net_service_guard = network_service.lock().unwrap();
+        net_service_guard
+            .user_udp_service
+            .lock()
+            .unwrap()
+            .send_voice_message(empty_data, None);
+    }
+    /// Returns None if the raw samples should be sent (PCM codec or the encoder failed).
+    fn create_voice_encoder(voice_codec: VoiceCodec) -> Option<OpusVoiceEncoder> {
+        if voice_codec != VoiceCodec::Opus {
+            return None;
+        }
+
+        match OpusVoiceEncoder::new() {
+            Ok(encoder) => Some(encoder),
+            Err(msg) => {
+                warn!("{}, sending raw samples instead", msg);
+                None
+            }
+        }
+    }
+    fn decode_opus_chunk(user: &mut UserVoiceData, data: &[u8]) -> Result<Vec<i16>, String> {
+        if user.opus_decoder.is_none() {
+            user.opus_decoder = Some(OpusVoiceDecoder::new()?);
+        }
+
+        user.opus_decoder.as_mut().unwrap().decode(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_BUFFER_CHUNKS: usize = 3;
+
+    fn user_with_full_buffer() -> UserVoiceData {
+        let mut user = UserVoiceData::new(String::from("user"));
+        user.max_buffer_chunks = MAX_BUFFER_CHUNKS;
+
+        for i in 0..MAX_BUFFER_CHUNKS {
+            assert!(!user.push_chunk(vec![i as i16]));
+        }
+
+        user
+    }
+
+    #[test]
+    fn nothing_is_dropped_until_buffer_is_full() {
+        let user = user_with_full_buffer();
+
+        assert_eq!(user.chunks.len(), MAX_BUFFER_CHUNKS);
+        assert_eq!(user.get_dropped_chunk_count(), 0);
+    }
+
+    #[test]
+    fn oldest_chunk_is_dropped_at_max_buffer_chunks() {
+        let mut user = user_with_full_buffer();
+
+        user.push_chunk(vec![100]);
+
+        assert_eq!(user.chunks.len(), MAX_BUFFER_CHUNKS);
+        assert_eq!(
+            user.chunks.iter().cloned().collect::<Vec<_>>(),
+            vec![vec![1], vec![2], vec![100]]
+        );
+        assert_eq!(user.get_dropped_chunk_count(), 1);
+    }
+
+    #[test]
+    fn started_dropping_is_reported_once() {
+        let mut user = user_with_full_buffer();
+
+        assert!(user.push_chunk(vec![100]));
+        assert!(!user.push_chunk(vec![101]));
+        assert!(!user.push_chunk(vec![102]));
+
+        assert_eq!(user.get_dropped_chunk_count(), 3);
+    }
+
+    #[test]
+    fn started_dropping_is_reported_again_after_recovery() {
+        let mut user = user_with_full_buffer();
+        assert!(user.push_chunk(vec![100]));
+
+        // playback caught up
+        user.chunks.pop_front();
+        assert!(!user.push_chunk(vec![101]));
+
+        assert!(user.push_chunk(vec![102]));
+        assert_eq!(user.get_dropped_chunk_count(), 2);
+    }
+
+    const RELEASE_DELAY: Duration = Duration::from_millis(200);
+
+    #[test]
+    fn held_key_stays_pressed() {
+        let now = Instant::now();
+
+        let state = PushToTalkState::Pressed.next(true, RELEASE_DELAY, now);
+
+        assert_eq!(state, PushToTalkState::Pressed);
+    }
+
+    #[test]
+    fn re_press_during_release_delay_continues_recording() {
+        let pressed_at = Instant::now();
+
+        let state = PushToTalkState::Pressed.next(false, RELEASE_DELAY, pressed_at);
+        assert_eq!(state, PushToTalkState::Releasing { since: pressed_at });
+
+        let state = state.next(
+            false,
+            RELEASE_DELAY,
+            pressed_at + Duration::from_millis(100),
+        );
+        assert_eq!(state, PushToTalkState::Releasing { since: pressed_at });
+
+        let state = state.next(true, RELEASE_DELAY, pressed_at + Duration::from_millis(150));
+        assert_eq!(state, PushToTalkState::Pressed);
+
+        // the delay starts again on the next release
+        let released_at = pressed_at + Duration::from_millis(300);
+        let state = state.next(false, RELEASE_DELAY, released_at);
+        assert_eq!(state, PushToTalkState::Releasing { since: released_at });
+        let state = state.next(
+            false,
+            RELEASE_DELAY,
+            released_at + Duration::from_millis(199),
+        );
+        assert_eq!(state, PushToTalkState::Releasing { since: released_at });
+    }
+
+    #[test]
+    fn released_after_delay() {
+        let released_at = Instant::now();
+
+        let state = PushToTalkState::Pressed.next(false, RELEASE_DELAY, released_at);
+        let state = state.next(false, RELEASE_DELAY, released_at + RELEASE_DELAY);
+        assert_eq!(state, PushToTalkState::Released);
+
+        // pressing the key again starts a new recording (not this one)
+        let state = state.next(true, RELEASE_DELAY, released_at + RELEASE_DELAY * 2);
+        assert_eq!(state, PushToTalkState::Released);
+    }
+
+    #[test]
+    fn zero_delay_releases_right_away() {
+        let now = Instant::now();
+
+        let state = PushToTalkState::Pressed.next(false, Duration::from_millis(0), now);
+
+        assert_eq!(state, PushToTalkState::Released);
+    }
+
+    #[test]
+    fn default_sounds_are_valid() {
+        assert!(AudioService::is_sound_file_valid(PUSH_TO_TALK_PRESS_SOUND));
+        assert!(AudioService::is_sound_file_valid(
+            PUSH_TO_TALK_UNPRESS_SOUND
+        ));
+    }
+
+    #[test]
+    fn missing_or_broken_sound_file_is_invalid() {
+        assert!(!AudioService::is_sound_file_valid("missing.wav"));
+        assert!(!AudioService::is_sound_file_valid(""));
+
+        let path = crate::tests::temp_file_path("broken_sound.wav");
+        std::fs::write(&path, b"definitely not a wav file").unwrap();
+        let is_valid = AudioService::is_sound_file_valid(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!is_valid);
+    }
+
+    #[test]
+    fn invalid_sound_path_falls_back_to_default() {
+        assert_eq!(
+            AudioService::get_valid_sound_path("missing.wav", PUSH_TO_TALK_PRESS_SOUND),
+            PUSH_TO_TALK_PRESS_SOUND
+        );
+        assert_eq!(
+            AudioService::get_valid_sound_path(
+                PUSH_TO_TALK_UNPRESS_SOUND,
+                PUSH_TO_TALK_PRESS_SOUND
+            ),
+            PUSH_TO_TALK_UNPRESS_SOUND
+        );
+    }
 }
@@ -0,0 +1,254 @@
+// Std.
+use std::collections::VecDeque;
+
+pub const AEC_DEFAULT_SUPPRESSION_FACTOR: f32 = 0.8;
+const AEC_MAX_REFERENCE_GAIN: f32 = 4.0; // the echo can be louder than the reference (playback volume)
+
+// Delay-and-subtract acoustic echo canceller.
+// Stores the signal that was sent to the speakers ('reference') and removes
+// the reference played 'delay_samples' ago from the microphone signal.
+// The reference is scaled by the least-squares gain estimated for each chunk
+// (clamped to be non-negative), so the output of a chunk never has more energy
+// than the input: if the microphone does not pick up the speakers nothing is changed.
+pub struct EchoCanceller {
+    reference_buffer: VecDeque<i16>,
+    delay_samples: usize,
+    suppression_factor: f32,
+    max_buffer_samples: usize,
+}
+
+impl EchoCanceller {
+    pub fn new(delay_ms: u32, sample_rate: u32, suppression_factor: f32) -> Self {
+        let delay_samples = (delay_ms as u64 * sample_rate as u64 / 1000) as usize;
+
+        EchoCanceller {
+            reference_buffer: VecDeque::new(),
+            delay_samples,
+            suppression_factor: suppression_factor.max(0.0).min(1.0),
+            // don't keep the reference while we are not recording
+            max_buffer_samples: delay_samples + sample_rate as usize,
+        }
+    }
+    /// Adds samples that were sent to the speakers.
+    pub fn add_reference(&mut self, samples: &[i16]) {
+        self.reference_buffer.extend(samples.iter());
+
+        if self.reference_buffer.len() > self.max_buffer_samples {
+            let to_remove = self.reference_buffer.len() - self.max_buffer_samples;
+            self.reference_buffer.drain(..to_remove);
+        }
+    }
+    /// Removes the echo from the microphone samples.
+    pub fn process(&mut self, samples: &mut [i16]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        // Take the reference that was played 'delay_samples' ago
+        // (silence if nothing was played).
+        let mut reference = Vec::with_capacity(samples.len());
+        for _ in 0..samples.len() {
+            if self.reference_buffer.len() > self.delay_samples {
+                reference.push(self.reference_buffer.pop_front().unwrap() as f32);
+            } else {
+                reference.push(0.0);
+            }
+        }
+
+        // Estimate how loud the echo is.
+        let mut cross_energy = 0.0f64;
+        let mut reference_energy = 0.0f64;
+        for (sample, reference_sample) in samples.iter().zip(reference.iter()) {
+            cross_energy += *sample as f64 * *reference_sample as f64;
+            reference_energy += *reference_sample as f64 * *reference_sample as f64;
+        }
+        if reference_energy <= std::f64::EPSILON || cross_energy <= 0.0 {
+            // nothing was played or the microphone signal is not related to it
+            return;
+        }
+
+        let gain = ((cross_energy / reference_energy) as f32).min(AEC_MAX_REFERENCE_GAIN)
+            * self.suppression_factor;
+
+        // Subtract.
+        for (sample, reference_sample) in samples.iter_mut().zip(reference.iter()) {
+            let mut new_sample = *sample as f32 - reference_sample * gain;
+            if new_sample > std::i16::MAX as f32 {
+                new_sample = std::i16::MAX as f32;
+            } else if new_sample < std::i16::MIN as f32 {
+                new_sample = std::i16::MIN as f32;
+            }
+            *sample = new_sample as i16;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const SAMPLE_RATE: u32 = 48000;
+    const DELAY_MS: u32 = 20;
+    const CHUNK_SIZE: usize = 480;
+    const CHUNK_COUNT: usize = 50;
+
+    fn white_noise(rng: &mut StdRng, amplitude: i16, len: usize) -> Vec<i16> {
+        (0..len)
+            .map(|_| rng.gen_range(-amplitude..=amplitude))
+            .collect()
+    }
+
+    fn energy(samples: &[i16]) -> f64 {
+        samples
+            .iter()
+            .map(|sample| *sample as f64 * *sample as f64)
+            .sum()
+    }
+
+    /// Plays 'reference' chunk by chunk and records the delayed and attenuated
+    /// reference plus 'near_end' (what the user says), returns input and output energy of each chunk.
+    fn run(
+        echo_canceller: &mut EchoCanceller,
+        reference: &[i16],
+        echo_gain: f32,
+        near_end: &[i16],
+    ) -> Vec<(f64, f64)> {
+        let delay_samples = (DELAY_MS * SAMPLE_RATE / 1000) as usize;
+
+        let mut energies = Vec::new();
+        for chunk_start in (0..reference.len()).step_by(CHUNK_SIZE) {
+            let chunk_end = chunk_start + CHUNK_SIZE;
+            echo_canceller.add_reference(&reference[chunk_start..chunk_end]);
+
+            let mut microphone: Vec<i16> = (chunk_start..chunk_end)
+                .map(|i| {
+                    let echo = if i >= delay_samples {
+                        reference[i - delay_samples] as f32 * echo_gain
+                    } else {
+                        0.0
+                    };
+                    (echo + near_end[i] as f32) as i16
+                })
+                .collect();
+
+            let input_energy = energy(&microphone);
+            echo_canceller.process(&mut microphone);
+            energies.push((input_energy, energy(&microphone)));
+        }
+
+        energies
+    }
+
+    #[test]
+    fn echo_is_attenuated() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let reference = white_noise(&mut rng, 10000, CHUNK_SIZE * CHUNK_COUNT);
+        let silence = vec![0; reference.len()];
+
+        let mut echo_canceller =
+            EchoCanceller::new(DELAY_MS, SAMPLE_RATE, AEC_DEFAULT_SUPPRESSION_FACTOR);
+        let energies = run(&mut echo_canceller, &reference, 0.5, &silence);
+
+        // Each chunk (including the first ones, when the echo did not arrive yet).
+        for (input_energy, output_energy) in energies {
+            // -10 dB at least ('suppression_factor' leaves 20% of the echo amplitude)
+            assert!(output_energy <= input_energy * 0.1);
+        }
+    }
+
+    #[test]
+    fn echo_is_removed_with_full_suppression() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let reference = white_noise(&mut rng, 10000, CHUNK_SIZE * CHUNK_COUNT);
+        let silence = vec![0; reference.len()];
+
+        let mut echo_canceller = EchoCanceller::new(DELAY_MS, SAMPLE_RATE, 1.0);
+        let energies = run(&mut echo_canceller, &reference, 1.5, &silence);
+
+        let input_energy: f64 = energies.iter().map(|(input, _)| input).sum();
+        let output_energy: f64 = energies.iter().map(|(_, output)| output).sum();
+        assert!(output_energy <= input_energy * 0.001); // -30 dB
+    }
+
+    #[test]
+    fn input_is_not_changed_without_reference() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let near_end = white_noise(&mut rng, 10000, CHUNK_SIZE);
+
+        let mut echo_canceller =
+            EchoCanceller::new(DELAY_MS, SAMPLE_RATE, AEC_DEFAULT_SUPPRESSION_FACTOR);
+        let mut samples = near_end.clone();
+        echo_canceller.process(&mut samples);
+
+        assert_eq!(samples, near_end);
+    }
+
+    #[test]
+    fn unrelated_input_is_not_amplified() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let reference = white_noise(&mut rng, 10000, CHUNK_SIZE * CHUNK_COUNT);
+        let near_end = white_noise(&mut rng, 10000, reference.len());
+
+        // The microphone does not pick up the speakers (headphones).
+        let mut echo_canceller =
+            EchoCanceller::new(DELAY_MS, SAMPLE_RATE, AEC_DEFAULT_SUPPRESSION_FACTOR);
+        let energies = run(&mut echo_canceller, &reference, 0.0, &near_end);
+
+        for (input_energy, output_energy) in energies {
+            assert!(output_energy <= input_energy);
+            assert!(output_energy >= input_energy * 0.9); // no audible change
+        }
+    }
+
+    #[test]
+    fn near_end_speech_is_kept() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let reference = white_noise(&mut rng, 10000, CHUNK_SIZE * CHUNK_COUNT);
+        let near_end = white_noise(&mut rng, 5000, reference.len());
+        let silence = vec![0; reference.len()];
+
+        let mut echo_canceller = EchoCanceller::new(DELAY_MS, SAMPLE_RATE, 1.0);
+        let energies = run(&mut echo_canceller, &reference, 0.5, &near_end);
+
+        let near_end_energy = energy(&near_end);
+        let output_energy: f64 = energies.iter().map(|(_, output)| output).sum();
+        assert!(output_energy >= near_end_energy * 0.9);
+        assert!(output_energy <= near_end_energy * 1.1);
+
+        // Same echo without the user speaking is removed.
+        let mut echo_canceller = EchoCanceller::new(DELAY_MS, SAMPLE_RATE, 1.0);
+        let energies = run(&mut echo_canceller, &reference, 0.5, &silence);
+        let output_energy: f64 = energies.iter().map(|(_, output)| output).sum();
+        assert!(output_energy <= near_end_energy * 0.01);
+    }
+
+    #[test]
+    fn reference_buffer_is_limited() {
+        let mut echo_canceller =
+            EchoCanceller::new(DELAY_MS, SAMPLE_RATE, AEC_DEFAULT_SUPPRESSION_FACTOR);
+
+        // Played for 3 seconds while not recording.
+        for _ in 0..(SAMPLE_RATE as usize * 3 / CHUNK_SIZE) {
+            echo_canceller.add_reference(&[1000; CHUNK_SIZE]);
+        }
+
+        assert_eq!(
+            echo_canceller.reference_buffer.len(),
+            echo_canceller.max_buffer_samples
+        );
+    }
+
+    #[test]
+    fn suppression_factor_is_clamped() {
+        assert_eq!(
+            EchoCanceller::new(0, SAMPLE_RATE, 2.0).suppression_factor,
+            1.0
+        );
+        assert_eq!(
+            EchoCanceller::new(0, SAMPLE_RATE, -1.0).suppression_factor,
+            0.0
+        );
+    }
+}
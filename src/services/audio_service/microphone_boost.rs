@@ -0,0 +1,94 @@
+pub const MIN_MICROPHONE_BOOST_DB: f32 = -10.0;
+pub const MAX_MICROPHONE_BOOST_DB: f32 = 20.0;
+
+// Fixed gain for microphones that are too quiet (or too loud)
+// even with the microphone volume at the maximum (minimum).
+pub struct MicrophoneBoost {
+    gain: f32, // linear
+}
+
+impl MicrophoneBoost {
+    pub fn new(boost_db: f32) -> Self {
+        MicrophoneBoost {
+            gain: MicrophoneBoost::db_to_gain(boost_db),
+        }
+    }
+    /// Returns 'false' if the boost is 0 dB and the samples will not be changed.
+    pub fn is_enabled(&self) -> bool {
+        self.gain != 1.0
+    }
+    pub fn process(&self, samples: &mut [i16]) {
+        for sample in samples.iter_mut() {
+            // saturate instead of wrapping around on loud samples
+            *sample = (*sample as f32 * self.gain)
+                .round()
+                .clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+    }
+    fn db_to_gain(boost_db: f32) -> f32 {
+        let boost_db = boost_db.clamp(MIN_MICROPHONE_BOOST_DB, MAX_MICROPHONE_BOOST_DB);
+
+        10.0f32.powf(boost_db / 20.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the output amplitude divided by the input one.
+    fn get_amplitude_ratio(boost_db: f32) -> f32 {
+        let input: Vec<i16> = (0..480)
+            .map(|i| ((i as f32 * 0.1).sin() * 1000.0) as i16)
+            .collect();
+        let mut output = input.clone();
+
+        MicrophoneBoost::new(boost_db).process(&mut output);
+
+        let max_input = input.iter().map(|sample| sample.abs()).max().unwrap();
+        let max_output = output.iter().map(|sample| sample.abs()).max().unwrap();
+        max_output as f32 / max_input as f32
+    }
+
+    fn assert_within_1_percent(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= expected * 0.01,
+            "expected {} got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn low_signal_is_amplified_by_linear_gain() {
+        assert_within_1_percent(get_amplitude_ratio(6.0), 1.995);
+        assert_within_1_percent(get_amplitude_ratio(20.0), 10.0);
+        assert_within_1_percent(get_amplitude_ratio(-6.0), 0.501);
+    }
+
+    #[test]
+    fn zero_boost_does_not_change_samples() {
+        let boost = MicrophoneBoost::new(0.0);
+        let mut samples = vec![-100, 0, 1, 12345];
+
+        boost.process(&mut samples);
+
+        assert!(!boost.is_enabled());
+        assert_eq!(samples, vec![-100, 0, 1, 12345]);
+    }
+
+    #[test]
+    fn boost_is_clamped() {
+        assert_within_1_percent(get_amplitude_ratio(40.0), 10.0);
+        assert_within_1_percent(get_amplitude_ratio(-30.0), 0.316);
+    }
+
+    #[test]
+    fn loud_samples_are_saturated() {
+        let mut samples = vec![i16::MAX, i16::MIN, 5000, -5000];
+
+        MicrophoneBoost::new(20.0).process(&mut samples);
+
+        assert_eq!(samples, vec![i16::MAX, i16::MIN, i16::MAX, i16::MIN]);
+    }
+}
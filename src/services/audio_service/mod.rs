@@ -1,3 +1,11 @@
+pub mod agc;
 pub mod audio_service;
+pub mod echo_canceller;
+pub mod microphone_boost;
+pub mod silence_detector;
+pub mod spatial_audio;
+pub mod voice_activity_detector;
+pub mod voice_codec;
 pub mod voice_player;
 pub mod voice_recorder;
+pub mod voice_session_recording;
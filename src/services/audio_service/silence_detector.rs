@@ -0,0 +1,229 @@
+// External.
+use rand::Rng;
+
+pub const SILENCE_DEFAULT_THRESHOLD: f32 = 0.01; // RMS relative to i16::MAX
+const SILENT_CHUNKS_BEFORE_SUPPRESSION: usize = 10; // ~200 ms with 20 ms chunks
+const SILENCE_MARKER_RESEND_INTERVAL_CHUNKS: usize = 25; // ~0.5 sec, keeps the receiver's player waiting
+const COMFORT_NOISE_AMPLITUDE: f32 = 0.002; // relative to i16::MAX
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoiceChunkKind {
+    Voice,         // send the chunk as usual
+    SilenceMarker, // send a silence marker instead of the chunk
+    Silence,       // send nothing (a marker was sent recently)
+}
+
+// Tells which recorded chunks can be replaced by a silence marker.
+// A chunk is silent if its RMS is below 'threshold'. The first silent chunks
+// are still sent (so that word endings are not cut), after that a marker
+// is sent once in a while so that the receiver fills the gap with comfort noise.
+pub struct SilenceDetector {
+    threshold: f32,
+    silent_chunk_count: usize,
+}
+
+impl SilenceDetector {
+    pub fn new(threshold: f32) -> Self {
+        SilenceDetector {
+            threshold: threshold.max(0.0).min(1.0),
+            silent_chunk_count: 0,
+        }
+    }
+    pub fn process(&mut self, samples: &[i16]) -> VoiceChunkKind {
+        if samples.is_empty() {
+            return VoiceChunkKind::Voice;
+        }
+
+        if SilenceDetector::calculate_rms(samples) >= self.threshold {
+            self.silent_chunk_count = 0;
+            return VoiceChunkKind::Voice;
+        }
+
+        self.silent_chunk_count += 1;
+        if self.silent_chunk_count <= SILENT_CHUNKS_BEFORE_SUPPRESSION {
+            return VoiceChunkKind::Voice;
+        }
+
+        if (self.silent_chunk_count - SILENT_CHUNKS_BEFORE_SUPPRESSION - 1)
+            % SILENCE_MARKER_RESEND_INTERVAL_CHUNKS
+            == 0
+        {
+            VoiceChunkKind::SilenceMarker
+        } else {
+            VoiceChunkKind::Silence
+        }
+    }
+    pub fn reset(&mut self) {
+        self.silent_chunk_count = 0;
+    }
+    /// Returns low-level noise that is played instead of the suppressed chunks
+    /// (complete silence sounds like a dropped connection).
+    pub fn generate_comfort_noise(sample_count: usize) -> Vec<i16> {
+        let max_amplitude = (std::i16::MAX as f32 * COMFORT_NOISE_AMPLITUDE) as i16;
+        let mut rng = rand::thread_rng();
+
+        (0..sample_count)
+            .map(|_| rng.gen_range(-max_amplitude..=max_amplitude))
+            .collect()
+    }
+    /// Expects 'samples' to be not empty.
+    pub fn calculate_rms(samples: &[i16]) -> f32 {
+        let mut sum_of_squares = 0.0f64;
+        for sample in samples.iter() {
+            let value = *sample as f64 / std::i16::MAX as f64;
+            sum_of_squares += value * value;
+        }
+
+        (sum_of_squares / samples.len() as f64).sqrt() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_SIZE: usize = 480;
+
+    fn voiced_chunk() -> Vec<i16> {
+        // square wave, RMS is 0.3
+        (0..CHUNK_SIZE)
+            .map(|i| {
+                let value = (i16::MAX as f32 * 0.3) as i16;
+                if i % 40 < 20 {
+                    value
+                } else {
+                    -value
+                }
+            })
+            .collect()
+    }
+
+    fn silent_chunk() -> Vec<i16> {
+        vec![5; CHUNK_SIZE]
+    }
+
+    /// Returns the kind of each chunk, 'true' in 'sequence' means a voiced chunk.
+    fn process_sequence(detector: &mut SilenceDetector, sequence: &[bool]) -> Vec<VoiceChunkKind> {
+        sequence
+            .iter()
+            .map(|is_voiced| {
+                if *is_voiced {
+                    detector.process(&voiced_chunk())
+                } else {
+                    detector.process(&silent_chunk())
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn voiced_chunks_are_sent() {
+        let mut detector = SilenceDetector::new(SILENCE_DEFAULT_THRESHOLD);
+
+        let kinds = process_sequence(&mut detector, &[true; 100]);
+
+        assert!(kinds.iter().all(|kind| *kind == VoiceChunkKind::Voice));
+    }
+
+    #[test]
+    fn long_silence_is_replaced_by_markers() {
+        let mut detector = SilenceDetector::new(SILENCE_DEFAULT_THRESHOLD);
+        let silent_count =
+            SILENT_CHUNKS_BEFORE_SUPPRESSION + SILENCE_MARKER_RESEND_INTERVAL_CHUNKS * 2;
+
+        let mut sequence = vec![true; 3];
+        sequence.extend(vec![false; silent_count]);
+        let kinds = process_sequence(&mut detector, &sequence);
+
+        let mut expected = vec![VoiceChunkKind::Voice; 3 + SILENT_CHUNKS_BEFORE_SUPPRESSION];
+        for _ in 0..2 {
+            expected.push(VoiceChunkKind::SilenceMarker);
+            expected.extend(vec![
+                VoiceChunkKind::Silence;
+                SILENCE_MARKER_RESEND_INTERVAL_CHUNKS - 1
+            ]);
+        }
+        assert_eq!(kinds, expected);
+    }
+
+    #[test]
+    fn voice_after_silence_is_sent_immediately() {
+        let mut detector = SilenceDetector::new(SILENCE_DEFAULT_THRESHOLD);
+
+        let mut sequence = vec![false; SILENT_CHUNKS_BEFORE_SUPPRESSION + 5];
+        sequence.push(true);
+        sequence.extend(vec![false; SILENT_CHUNKS_BEFORE_SUPPRESSION + 1]);
+        let kinds = process_sequence(&mut detector, &sequence);
+
+        // the voiced chunk resets the silence counter
+        let voiced_index = SILENT_CHUNKS_BEFORE_SUPPRESSION + 5;
+        assert_eq!(kinds[voiced_index - 1], VoiceChunkKind::Silence);
+        assert_eq!(kinds[voiced_index], VoiceChunkKind::Voice);
+        assert!(
+            kinds[voiced_index + 1..voiced_index + 1 + SILENT_CHUNKS_BEFORE_SUPPRESSION]
+                .iter()
+                .all(|kind| *kind == VoiceChunkKind::Voice)
+        );
+        assert_eq!(*kinds.last().unwrap(), VoiceChunkKind::SilenceMarker);
+    }
+
+    #[test]
+    fn short_pauses_between_words_are_not_suppressed() {
+        let mut detector = SilenceDetector::new(SILENCE_DEFAULT_THRESHOLD);
+
+        let mut sequence = Vec::new();
+        for _ in 0..5 {
+            sequence.extend(vec![true; 7]);
+            sequence.extend(vec![false; SILENT_CHUNKS_BEFORE_SUPPRESSION]);
+        }
+        let kinds = process_sequence(&mut detector, &sequence);
+
+        assert!(kinds.iter().all(|kind| *kind == VoiceChunkKind::Voice));
+    }
+
+    #[test]
+    fn reset_restarts_silence_counting() {
+        let mut detector = SilenceDetector::new(SILENCE_DEFAULT_THRESHOLD);
+        process_sequence(&mut detector, &[false; SILENT_CHUNKS_BEFORE_SUPPRESSION]);
+
+        detector.reset();
+
+        assert_eq!(detector.process(&silent_chunk()), VoiceChunkKind::Voice);
+    }
+
+    #[test]
+    fn empty_chunk_is_not_silence() {
+        let mut detector = SilenceDetector::new(SILENCE_DEFAULT_THRESHOLD);
+        process_sequence(&mut detector, &[false; SILENT_CHUNKS_BEFORE_SUPPRESSION]);
+
+        assert_eq!(detector.process(&[]), VoiceChunkKind::Voice);
+        assert_eq!(
+            detector.process(&silent_chunk()),
+            VoiceChunkKind::SilenceMarker
+        );
+    }
+
+    #[test]
+    fn threshold_decides_what_is_silence() {
+        // RMS of the voiced chunk is 0.3
+        let mut detector = SilenceDetector::new(0.5);
+        let kinds = process_sequence(&mut detector, &[true; SILENT_CHUNKS_BEFORE_SUPPRESSION + 1]);
+        assert_eq!(*kinds.last().unwrap(), VoiceChunkKind::SilenceMarker);
+
+        // suppression is effectively disabled
+        let mut detector = SilenceDetector::new(0.0);
+        let kinds = process_sequence(
+            &mut detector,
+            &[false; SILENT_CHUNKS_BEFORE_SUPPRESSION + 1],
+        );
+        assert!(kinds.iter().all(|kind| *kind == VoiceChunkKind::Voice));
+    }
+
+    #[test]
+    fn comfort_noise_is_below_default_threshold() {
+        let noise = SilenceDetector::generate_comfort_noise(CHUNK_SIZE);
+
+        assert_eq!(noise.len(), CHUNK_SIZE);
+        assert!(SilenceDetector::calculate_rms(&noise) < SILENCE_DEFAULT_THRESHOLD);
+    }
+}
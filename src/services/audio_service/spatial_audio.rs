@@ -0,0 +1,198 @@
+// Std.
+use std::collections::VecDeque;
+
+pub const ITD_MAX_SAMPLES: usize = 16; // ~0.66 ms (max interaural time difference) with 24000 sample rate
+
+// Position of the user's voice relative to the listener
+// (virtual room layout UI is not implemented yet so everyone is in front of the listener).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpatialConfig {
+    pub azimuth_deg: f32, // 0 - front, 90 - left, -90 - right
+    pub elevation_deg: f32,
+    pub distance: f32,
+}
+
+impl Default for SpatialConfig {
+    fn default() -> Self {
+        SpatialConfig {
+            azimuth_deg: 0.0,
+            elevation_deg: 0.0,
+            distance: 1.0,
+        }
+    }
+}
+
+// Converts mono voice to stereo and applies an interaural time difference (ITD)
+// effect: the ear that is farther from the source receives the sound later.
+// Keeps delayed samples between chunks so that there are no clicks on chunk borders.
+pub struct SpatialAudioProcessor {
+    left_delay_line: VecDeque<i16>,
+    right_delay_line: VecDeque<i16>,
+}
+
+impl SpatialAudioProcessor {
+    pub fn new() -> Self {
+        SpatialAudioProcessor {
+            left_delay_line: VecDeque::new(),
+            right_delay_line: VecDeque::new(),
+        }
+    }
+    /// Returns the delay of the right channel relative to the left channel
+    /// (negative if the left channel should be delayed).
+    pub fn get_itd_samples(azimuth_deg: f32) -> i32 {
+        (azimuth_deg.to_radians().sin() * ITD_MAX_SAMPLES as f32).round() as i32
+    }
+    /// Converts mono 'samples' to interleaved stereo samples (L, R, L, R, ...).
+    pub fn apply(&mut self, samples: &mut Vec<i16>, config: &SpatialConfig) {
+        let itd_samples = SpatialAudioProcessor::get_itd_samples(config.azimuth_deg);
+
+        let left_delay = (-itd_samples).max(0) as usize;
+        let right_delay = itd_samples.max(0) as usize;
+
+        let mut stereo_samples = Vec::with_capacity(samples.len() * 2);
+        for sample in samples.iter() {
+            stereo_samples.push(SpatialAudioProcessor::delay(
+                &mut self.left_delay_line,
+                *sample,
+                left_delay,
+            ));
+            stereo_samples.push(SpatialAudioProcessor::delay(
+                &mut self.right_delay_line,
+                *sample,
+                right_delay,
+            ));
+        }
+
+        *samples = stereo_samples;
+    }
+    fn delay(delay_line: &mut VecDeque<i16>, sample: i16, delay: usize) -> i16 {
+        delay_line.push_back(sample);
+
+        // the delay might have been changed (the user was moved)
+        while delay_line.len() > delay + 1 {
+            delay_line.pop_front();
+        }
+
+        if delay_line.len() == delay + 1 {
+            delay_line.pop_front().unwrap()
+        } else {
+            0 // still filling the delay line
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_at(azimuth_deg: f32) -> SpatialConfig {
+        SpatialConfig {
+            azimuth_deg,
+            ..SpatialConfig::default()
+        }
+    }
+
+    // 1, 2, 3, ... (so that the delay can be seen in the output)
+    fn ramp(from: i16, len: usize) -> Vec<i16> {
+        (0..len as i16).map(|i| from + i).collect()
+    }
+
+    fn split_channels(stereo_samples: &[i16]) -> (Vec<i16>, Vec<i16>) {
+        let left = stereo_samples.iter().step_by(2).copied().collect();
+        let right = stereo_samples.iter().skip(1).step_by(2).copied().collect();
+        (left, right)
+    }
+
+    // 'samples' delayed by 'delay' samples (zeros first).
+    fn delayed(samples: &[i16], delay: usize) -> Vec<i16> {
+        let mut expected = vec![0; delay.min(samples.len())];
+        expected.extend_from_slice(&samples[..samples.len() - expected.len()]);
+        expected
+    }
+
+    #[test]
+    fn no_itd_in_front() {
+        assert_eq!(SpatialAudioProcessor::get_itd_samples(0.0), 0);
+    }
+
+    #[test]
+    fn max_itd_at_90_degrees() {
+        assert_eq!(
+            SpatialAudioProcessor::get_itd_samples(90.0),
+            ITD_MAX_SAMPLES as i32
+        );
+        assert_eq!(
+            SpatialAudioProcessor::get_itd_samples(-90.0),
+            -(ITD_MAX_SAMPLES as i32)
+        );
+    }
+
+    #[test]
+    fn no_itd_behind() {
+        assert_eq!(SpatialAudioProcessor::get_itd_samples(180.0), 0);
+        assert_eq!(SpatialAudioProcessor::get_itd_samples(-180.0), 0);
+    }
+
+    #[test]
+    fn itd_follows_sine_of_azimuth() {
+        // sin(30) = 0.5
+        assert_eq!(
+            SpatialAudioProcessor::get_itd_samples(30.0),
+            ITD_MAX_SAMPLES as i32 / 2
+        );
+        assert_eq!(
+            SpatialAudioProcessor::get_itd_samples(150.0),
+            ITD_MAX_SAMPLES as i32 / 2
+        );
+    }
+
+    #[test]
+    fn mono_is_duplicated_in_front() {
+        let mut processor = SpatialAudioProcessor::new();
+        let input = ramp(1, 100);
+
+        let mut samples = input.clone();
+        processor.apply(&mut samples, &config_at(0.0));
+
+        assert_eq!(samples.len(), input.len() * 2);
+        let (left, right) = split_channels(&samples);
+        assert_eq!(left, input);
+        assert_eq!(right, input);
+    }
+
+    #[test]
+    fn far_ear_is_delayed() {
+        let input = ramp(1, 100);
+
+        // left: the right ear is farther
+        let mut processor = SpatialAudioProcessor::new();
+        let mut samples = input.clone();
+        processor.apply(&mut samples, &config_at(90.0));
+        let (left, right) = split_channels(&samples);
+        assert_eq!(left, input);
+        assert_eq!(right, delayed(&input, ITD_MAX_SAMPLES));
+
+        // right: the left ear is farther
+        let mut processor = SpatialAudioProcessor::new();
+        let mut samples = input.clone();
+        processor.apply(&mut samples, &config_at(-90.0));
+        let (left, right) = split_channels(&samples);
+        assert_eq!(left, delayed(&input, ITD_MAX_SAMPLES));
+        assert_eq!(right, input);
+    }
+
+    #[test]
+    fn delay_continues_between_chunks() {
+        let mut processor = SpatialAudioProcessor::new();
+        let input = ramp(1, 100);
+
+        let mut right = Vec::new();
+        for chunk in input.chunks(10) {
+            let mut samples = chunk.to_vec();
+            processor.apply(&mut samples, &config_at(90.0));
+            right.extend(split_channels(&samples).1);
+        }
+
+        assert_eq!(right, delayed(&input, ITD_MAX_SAMPLES));
+    }
+}
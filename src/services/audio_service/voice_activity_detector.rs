@@ -0,0 +1,64 @@
+// External.
+use druid::Data;
+use num_derive::{FromPrimitive, ToPrimitive};
+
+// Std.
+use std::time::{Duration, Instant};
+
+// Custom.
+use super::silence_detector::SilenceDetector;
+
+pub const VAD_DEFAULT_SENSITIVITY: u8 = 50; // percent
+pub const VAD_MAX_SENSITIVITY: u8 = 100;
+const VAD_MIN_THRESHOLD: f32 = 0.005; // RMS relative to i16::MAX (at the maximum sensitivity)
+const VAD_MAX_THRESHOLD: f32 = 0.1; // RMS relative to i16::MAX (at the minimum sensitivity)
+const VAD_HOLD_OPEN_MS: u64 = 300; // short pauses between words don't stop the transmission
+
+#[derive(Clone, Copy, Data, PartialEq, Debug, FromPrimitive, ToPrimitive)]
+pub enum PushToTalkMode {
+    HoldKey = 0,
+    VoiceActivated = 1, // the push-to-talk key is ignored
+}
+
+// Decides when to transmit in the voice activated mode.
+// Transmission starts when a chunk's RMS reaches the threshold
+// and stops when there was no such chunk for VAD_HOLD_OPEN_MS.
+pub struct VoiceActivityDetector {
+    threshold: f32,
+    last_voice_time: Option<Instant>, // None if not transmitting
+}
+
+impl VoiceActivityDetector {
+    pub fn new(sensitivity: u8) -> Self {
+        VoiceActivityDetector {
+            threshold: VoiceActivityDetector::sensitivity_to_threshold(sensitivity),
+            last_voice_time: None,
+        }
+    }
+    /// Higher sensitivity - lower threshold (quieter voice starts the transmission).
+    pub fn sensitivity_to_threshold(sensitivity: u8) -> f32 {
+        let sensitivity = sensitivity.min(VAD_MAX_SENSITIVITY) as f32 / VAD_MAX_SENSITIVITY as f32;
+
+        VAD_MAX_THRESHOLD - (VAD_MAX_THRESHOLD - VAD_MIN_THRESHOLD) * sensitivity
+    }
+    /// Returns 'true' if the chunk should be transmitted.
+    pub fn process(&mut self, samples: &[i16], now: Instant) -> bool {
+        if !samples.is_empty() && SilenceDetector::calculate_rms(samples) >= self.threshold {
+            self.last_voice_time = Some(now);
+            return true;
+        }
+
+        match self.last_voice_time {
+            Some(last_voice_time)
+                if now.duration_since(last_voice_time)
+                    < Duration::from_millis(VAD_HOLD_OPEN_MS) =>
+            {
+                true
+            }
+            _ => {
+                self.last_voice_time = None;
+                false
+            }
+        }
+    }
+}
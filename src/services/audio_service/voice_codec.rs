@@ -0,0 +1,80 @@
+// External.
+use opus::{Application, Channels, Decoder, Encoder};
+
+// Custom.
+use super::audio_service::{SAMPLES_IN_CHUNK, SAMPLE_RATE};
+use crate::services::udp_packets::UDP_PACKET_MAX_SIZE;
+
+// Voice chunk as it was received from the server.
+pub enum VoiceChunk {
+    Pcm(Vec<i16>), // empty if the user stopped talking
+    Opus(Vec<u8>),
+}
+
+// Keeps the encoder state between chunks so it should be created once per recording session.
+pub struct OpusVoiceEncoder {
+    encoder: Encoder,
+}
+
+impl OpusVoiceEncoder {
+    pub fn new() -> Result<Self, String> {
+        match Encoder::new(SAMPLE_RATE, Channels::Mono, Application::Voip) {
+            Ok(encoder) => Ok(OpusVoiceEncoder { encoder }),
+            Err(e) => Err(format!(
+                "Encoder::new() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )),
+        }
+    }
+    pub fn encode(&mut self, samples: &[i16]) -> Result<Vec<u8>, String> {
+        match self
+            .encoder
+            .encode_vec(samples, UDP_PACKET_MAX_SIZE as usize)
+        {
+            Ok(data) => Ok(data),
+            Err(e) => Err(format!(
+                "Encoder::encode_vec() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )),
+        }
+    }
+}
+
+// One per talking user, keeps the decoder state between chunks of a voice session.
+pub struct OpusVoiceDecoder {
+    decoder: Decoder,
+}
+
+impl OpusVoiceDecoder {
+    pub fn new() -> Result<Self, String> {
+        match Decoder::new(SAMPLE_RATE, Channels::Mono) {
+            Ok(decoder) => Ok(OpusVoiceDecoder { decoder }),
+            Err(e) => Err(format!(
+                "Decoder::new() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )),
+        }
+    }
+    pub fn decode(&mut self, data: &[u8]) -> Result<Vec<i16>, String> {
+        let mut samples = vec![0i16; SAMPLES_IN_CHUNK];
+
+        match self.decoder.decode(data, &mut samples, false) {
+            Ok(decoded_count) => {
+                samples.truncate(decoded_count);
+                Ok(samples)
+            }
+            Err(e) => Err(format!(
+                "Decoder::decode() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )),
+        }
+    }
+}
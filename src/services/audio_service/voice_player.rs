@@ -13,17 +13,23 @@ use crate::global_params::*;
 pub struct VoicePlayer {
     sample_receiver: mpsc::Receiver<Vec<i16>>,
     sample_rate: u32,
+    channel_count: u32, // samples are interleaved if more than 1
     sample_chunks: VecDeque<Vec<i16>>,
     finish_chunk: Vec<i16>,
 }
 
 impl VoicePlayer {
-    pub fn new(sample_receiver: mpsc::Receiver<Vec<i16>>, sample_rate: u32) -> Self {
+    pub fn new(
+        sample_receiver: mpsc::Receiver<Vec<i16>>,
+        sample_rate: u32,
+        channel_count: u32,
+    ) -> Self {
         VoicePlayer {
             sample_receiver,
             sample_rate,
+            channel_count,
             sample_chunks: VecDeque::new(),
-            finish_chunk: vec![0i16; 1],
+            finish_chunk: vec![0i16; channel_count as usize],
         }
     }
 }
@@ -69,7 +75,7 @@ impl SoundStream for VoicePlayer {
         // dont need
     }
     fn channel_count(&self) -> u32 {
-        1
+        self.channel_count
     }
     fn sample_rate(&self) -> u32 {
         self.sample_rate
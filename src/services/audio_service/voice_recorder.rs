@@ -2,29 +2,63 @@
 use sfml::audio::SoundRecorder;
 
 // Std.
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 
 pub struct VoiceRecorder {
     sample_sender: mpsc::Sender<Vec<i16>>,
     microphone_volume_multiplier: f64,
+    channel_count: u32, // recorded channels, the sent samples are always mono
+    input_level: Option<Arc<Mutex<f32>>>, // peak since it was last reset, above 1.0 if clipping
 }
 
 impl VoiceRecorder {
-    pub fn new(sample_sender: mpsc::Sender<Vec<i16>>, microphone_volume: i32) -> Self {
+    pub fn new(
+        sample_sender: mpsc::Sender<Vec<i16>>,
+        microphone_volume: i32,
+        channel_count: u32,
+    ) -> Self {
         VoiceRecorder {
             sample_sender,
             microphone_volume_multiplier: microphone_volume as f64 / 100.0,
+            channel_count,
+            input_level: None,
         }
     }
+    /// Updates 'input_level' with the peak amplitude of the recorded samples
+    /// (after the microphone volume is applied).
+    pub fn with_input_level(mut self, input_level: Arc<Mutex<f32>>) -> Self {
+        self.input_level = Some(input_level);
+        self
+    }
+    /// Averages left and right samples of the interleaved stereo data
+    /// (an odd trailing sample is kept as is).
+    pub fn downmix_to_mono(samples: &[i16]) -> Vec<i16> {
+        samples
+            .chunks(2)
+            .map(|frame| {
+                if frame.len() == 2 {
+                    ((frame[0] as i32 + frame[1] as i32) / 2) as i16
+                } else {
+                    frame[0]
+                }
+            })
+            .collect()
+    }
 }
 
 impl SoundRecorder for VoiceRecorder {
     fn on_process_samples(&mut self, samples: &[i16]) -> bool {
-        let mut sample_vec = Vec::from(samples);
+        let mut sample_vec = if self.channel_count == 2 {
+            VoiceRecorder::downmix_to_mono(samples)
+        } else {
+            Vec::from(samples)
+        };
 
         // apply microphone multiplier
+        let mut peak = 0.0f64;
         sample_vec.iter_mut().for_each(|sample| {
             let mut new_sample = *sample as f64 * self.microphone_volume_multiplier;
+            peak = peak.max(new_sample.abs() / std::i16::MAX as f64);
             if new_sample > std::i16::MAX as f64 {
                 new_sample = std::i16::MAX as f64;
             } else if new_sample < std::i16::MIN as f64 {
@@ -33,9 +67,72 @@ impl SoundRecorder for VoiceRecorder {
             *sample = new_sample as i16;
         });
 
+        if let Some(input_level) = self.input_level.as_ref() {
+            let mut input_level_guard = input_level.lock().unwrap();
+            *input_level_guard = input_level_guard.max(peak as f32);
+        }
+
         // ignore send errors
         let _result = self.sample_sender.send(sample_vec);
 
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(samples: &[i16], microphone_volume: i32, channel_count: u32) -> Vec<i16> {
+        let (sender, receiver) = mpsc::channel();
+        let mut recorder = VoiceRecorder::new(sender, microphone_volume, channel_count);
+
+        assert!(recorder.on_process_samples(samples));
+
+        receiver.try_recv().unwrap()
+    }
+
+    #[test]
+    fn stereo_frames_are_averaged() {
+        assert_eq!(
+            VoiceRecorder::downmix_to_mono(&[100, 200, -100, -300, 0, 0, 7, -7]),
+            vec![150, -200, 0, 0]
+        );
+    }
+
+    #[test]
+    fn downmix_does_not_overflow() {
+        assert_eq!(
+            VoiceRecorder::downmix_to_mono(&[
+                i16::MAX,
+                i16::MAX,
+                i16::MIN,
+                i16::MIN,
+                i16::MAX,
+                i16::MIN
+            ]),
+            vec![i16::MAX, i16::MIN, 0]
+        );
+    }
+
+    #[test]
+    fn odd_trailing_sample_is_kept() {
+        assert_eq!(VoiceRecorder::downmix_to_mono(&[10, 20, 30]), vec![15, 30]);
+        assert!(VoiceRecorder::downmix_to_mono(&[]).is_empty());
+    }
+
+    #[test]
+    fn stereo_recording_is_sent_as_mono() {
+        let samples = [100, 200, -100, -300];
+
+        assert_eq!(record(&samples, 100, 2), vec![150, -200]);
+        assert_eq!(record(&samples, 100, 1), samples.to_vec());
+    }
+
+    #[test]
+    fn volume_is_applied_after_downmix() {
+        // 20000 and 30000 would clip before the downmix
+        assert_eq!(record(&[20000, 30000, -2, -3], 200, 2), vec![i16::MAX, -4]);
+        assert_eq!(record(&[100, 300], 50, 2), vec![100]);
+    }
+}
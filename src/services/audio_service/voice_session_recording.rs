@@ -0,0 +1,387 @@
+// External.
+use chrono::prelude::*;
+
+// Std.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// Custom.
+use super::audio_service::SAMPLE_RATE;
+
+const WAV_HEADER_SIZE: u32 = 44;
+const WAV_BITS_PER_SAMPLE: u16 = 16;
+const FLUSH_INTERVAL_SAMPLES: usize = SAMPLE_RATE as usize; // write to the file every second
+
+// Mixes voice chunks of all users into one mono track that is written to a WAV file.
+// Chunks are placed at the time they were received so the pauses between
+// the voice sessions are kept, chunks of one voice session follow each other.
+// Chunks are never placed before the time they were received so only the samples
+// after it are kept in memory, the rest is written to the file.
+pub struct VoiceSessionRecording {
+    path: PathBuf,
+    file: BufWriter<File>,
+    start_time: Instant,
+    samples: Vec<i16>, // not written yet, starts at 'written_sample_count'
+    written_sample_count: usize, // in the file
+    user_positions: HashMap<String, usize>, // username - where the user's next chunk starts
+}
+
+impl VoiceSessionRecording {
+    /// Creates the file, the WAV header is finished in 'finish'.
+    pub fn new(path: &Path) -> Result<Self, String> {
+        let file = File::create(path);
+        if let Err(e) = file {
+            return Err(format!(
+                "File::create() failed, can't create \"{}\" (error: {}) at [{}, {}]",
+                path.display(),
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        let mut recording = VoiceSessionRecording {
+            path: path.to_path_buf(),
+            file: BufWriter::new(file.unwrap()),
+            start_time: Instant::now(),
+            samples: Vec::new(),
+            written_sample_count: 0,
+            user_positions: HashMap::new(),
+        };
+
+        // sizes are written in 'finish'
+        if let Err(msg) = recording.write_header(0) {
+            return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
+        }
+
+        Ok(recording)
+    }
+    /// "server_2022-06-01T18-30-00+03-00.wav" (RFC 3339 time with '-' instead of ':'
+    /// and other characters that can't be used in file names).
+    pub fn get_file_name(server_address: &str, time: DateTime<Local>) -> String {
+        let name = format!(
+            "{}_{}",
+            server_address,
+            time.to_rfc3339_opts(SecondsFormat::Secs, false)
+        );
+
+        let name: String = name
+            .chars()
+            .map(|character| {
+                if character.is_alphanumeric() || "._+-".contains(character) {
+                    character
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+
+        name + ".wav"
+    }
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+    pub fn is_empty(&self) -> bool {
+        self.written_sample_count == 0 && self.samples.is_empty()
+    }
+    /// Returns an error if failed to write the finished samples to the file.
+    pub fn add_chunk(
+        &mut self,
+        username: &str,
+        samples: &[i16],
+        now: Instant,
+    ) -> Result<(), String> {
+        // the written samples can't be changed
+        let received_position = ((now.duration_since(self.start_time).as_secs_f64()
+            * SAMPLE_RATE as f64) as usize)
+            .max(self.written_sample_count);
+
+        // continue the user's voice session if the chunk came earlier than expected
+        let position = match self.user_positions.get(username) {
+            Some(user_position) => received_position.max(*user_position),
+            None => received_position,
+        };
+        self.user_positions
+            .insert(String::from(username), position + samples.len());
+
+        // positions are absolute, 'self.samples' starts after the written samples
+        let start = position - self.written_sample_count;
+        if self.samples.len() < start + samples.len() {
+            self.samples.resize(start + samples.len(), 0);
+        }
+
+        for (mixed, sample) in self.samples[start..].iter_mut().zip(samples.iter()) {
+            *mixed = mixed.saturating_add(*sample);
+        }
+
+        // the next chunks will not be placed before the time this one was received
+        let finished_sample_count = received_position - self.written_sample_count;
+        if finished_sample_count >= FLUSH_INTERVAL_SAMPLES {
+            if let Err(msg) = self.write_samples(finished_sample_count) {
+                return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
+            }
+        }
+
+        Ok(())
+    }
+    /// Writes the rest of the samples and the sizes in the WAV header.
+    pub fn finish(mut self) -> Result<(), String> {
+        if let Err(msg) = self.write_samples(self.samples.len()) {
+            return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
+        }
+
+        let data_size = self.written_sample_count * std::mem::size_of::<i16>();
+        if data_size > (u32::MAX - WAV_HEADER_SIZE) as usize {
+            return Err(format!(
+                "the recording is too long for a WAV file ({} samples) at [{}, {}]",
+                self.written_sample_count,
+                file!(),
+                line!()
+            ));
+        }
+
+        if let Err(e) = self.file.seek(SeekFrom::Start(0)) {
+            return Err(format!(
+                "BufWriter::seek() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+        if let Err(msg) = self.write_header(data_size as u32) {
+            return Err(format!("{} at [{}, {}]", msg, file!(), line!()));
+        }
+        if let Err(e) = self.file.flush() {
+            return Err(format!(
+                "BufWriter::flush() failed, can't save \"{}\" (error: {}) at [{}, {}]",
+                self.path.display(),
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(())
+    }
+    /// Removes the file (used if nobody talked).
+    pub fn discard(self) -> Result<(), String> {
+        let path = self.path.clone();
+        drop(self.file);
+
+        if let Err(e) = std::fs::remove_file(&path) {
+            return Err(format!(
+                "std::fs::remove_file() failed, can't remove \"{}\" (error: {}) at [{}, {}]",
+                path.display(),
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(())
+    }
+    /// Writes the first 'count' samples of 'self.samples' to the file.
+    fn write_samples(&mut self, count: usize) -> Result<(), String> {
+        let bytes: Vec<u8> = self
+            .samples
+            .drain(..count)
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        self.written_sample_count += count;
+
+        if let Err(e) = self.file.write_all(&bytes) {
+            return Err(format!(
+                "BufWriter::write_all() failed, can't write to \"{}\" (error: {}) at [{}, {}]",
+                self.path.display(),
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(())
+    }
+    /// Mono 16 bit PCM.
+    fn write_header(&mut self, data_size: u32) -> Result<(), String> {
+        let byte_rate = SAMPLE_RATE * WAV_BITS_PER_SAMPLE as u32 / 8;
+
+        let mut header = Vec::with_capacity(WAV_HEADER_SIZE as usize);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(WAV_HEADER_SIZE - 8 + data_size).to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes()); // format chunk size
+        header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        header.extend_from_slice(&1u16.to_le_bytes()); // channels
+        header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        header.extend_from_slice(&byte_rate.to_le_bytes());
+        header.extend_from_slice(&(WAV_BITS_PER_SAMPLE / 8).to_le_bytes()); // block align
+        header.extend_from_slice(&WAV_BITS_PER_SAMPLE.to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_size.to_le_bytes());
+
+        if let Err(e) = self.file.write_all(&header) {
+            return Err(format!(
+                "BufWriter::write_all() failed, can't write WAV header to \"{}\" (error: {}) at [{}, {}]",
+                self.path.display(),
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_file_path;
+    use std::time::Duration;
+
+    const CHUNK_SIZE: usize = 480;
+
+    fn read_samples(path: &Path) -> Vec<i16> {
+        std::fs::read(path).unwrap()[WAV_HEADER_SIZE as usize..]
+            .chunks(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn finished_file_is_wav() {
+        let path = PathBuf::from(temp_file_path("recording_wav.wav"));
+        let mut recording = VoiceSessionRecording::new(&path).unwrap();
+        let start_time = recording.start_time;
+
+        recording
+            .add_chunk("user", &[1, -1, i16::MAX, i16::MIN], start_time)
+            .unwrap();
+        recording.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), WAV_HEADER_SIZE as usize + 4 * 2);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(bytes[4..8], (WAV_HEADER_SIZE - 8 + 4 * 2).to_le_bytes());
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(bytes[24..28], SAMPLE_RATE.to_le_bytes());
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(bytes[40..44], (4u32 * 2).to_le_bytes());
+        assert_eq!(read_samples(&path), vec![1, -1, i16::MAX, i16::MIN]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn chunks_are_placed_at_receive_time_and_mixed() {
+        let path = PathBuf::from(temp_file_path("recording_mix.wav"));
+        let mut recording = VoiceSessionRecording::new(&path).unwrap();
+        let start_time = recording.start_time;
+
+        recording
+            .add_chunk("user1", &[100; CHUNK_SIZE], start_time)
+            .unwrap();
+        // came earlier than expected, continues the voice session
+        recording
+            .add_chunk("user1", &[200; CHUNK_SIZE], start_time)
+            .unwrap();
+        recording
+            .add_chunk("user2", &[i16::MAX; CHUNK_SIZE], start_time)
+            .unwrap();
+        // one second of silence before it
+        recording
+            .add_chunk(
+                "user2",
+                &[300; CHUNK_SIZE],
+                start_time + Duration::from_secs(1),
+            )
+            .unwrap();
+        recording.finish().unwrap();
+
+        let samples = read_samples(&path);
+        assert_eq!(samples.len(), SAMPLE_RATE as usize + CHUNK_SIZE);
+        assert!(samples[..CHUNK_SIZE].iter().all(|&s| s == i16::MAX)); // saturated
+        assert!(samples[CHUNK_SIZE..CHUNK_SIZE * 2]
+            .iter()
+            .all(|&s| s == 200));
+        assert!(samples[CHUNK_SIZE * 2..SAMPLE_RATE as usize]
+            .iter()
+            .all(|&s| s == 0));
+        assert!(samples[SAMPLE_RATE as usize..].iter().all(|&s| s == 300));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn long_recording_is_not_kept_in_memory() {
+        let path = PathBuf::from(temp_file_path("recording_long.wav"));
+        let mut recording = VoiceSessionRecording::new(&path).unwrap();
+        let start_time = recording.start_time;
+
+        let chunk_duration = Duration::from_secs_f64(CHUNK_SIZE as f64 / SAMPLE_RATE as f64);
+        let chunk_count = 60 * SAMPLE_RATE as usize / CHUNK_SIZE; // a minute
+        for i in 0..chunk_count {
+            recording
+                .add_chunk(
+                    "user",
+                    &[1; CHUNK_SIZE],
+                    start_time + chunk_duration * i as u32,
+                )
+                .unwrap();
+
+            assert!(recording.samples.len() <= FLUSH_INTERVAL_SAMPLES + CHUNK_SIZE);
+        }
+        assert!(recording.written_sample_count > 0);
+
+        recording.finish().unwrap();
+
+        assert!(read_samples(&path).len() >= chunk_count * CHUNK_SIZE);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn late_chunk_is_placed_after_written_samples() {
+        let path = PathBuf::from(temp_file_path("recording_late.wav"));
+        let mut recording = VoiceSessionRecording::new(&path).unwrap();
+        let start_time = recording.start_time;
+
+        recording
+            .add_chunk(
+                "user1",
+                &[1; CHUNK_SIZE],
+                start_time + Duration::from_secs(2),
+            )
+            .unwrap();
+        let written_sample_count = recording.written_sample_count;
+        assert!(written_sample_count > 0);
+
+        recording
+            .add_chunk("user2", &[2; CHUNK_SIZE], start_time)
+            .unwrap();
+        recording.finish().unwrap();
+
+        let samples = read_samples(&path);
+        assert!(
+            samples[written_sample_count..written_sample_count + CHUNK_SIZE]
+                .iter()
+                .all(|&s| s == 3)
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty_recording_is_discarded() {
+        let path = PathBuf::from(temp_file_path("recording_empty.wav"));
+        let recording = VoiceSessionRecording::new(&path).unwrap();
+
+        assert!(recording.is_empty());
+        recording.discard().unwrap();
+
+        assert!(!path.exists());
+    }
+}
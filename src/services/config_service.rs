@@ -4,14 +4,59 @@ use num_traits::cast::ToPrimitive;
 #[cfg(target_os = "windows")]
 use platform_dirs::UserDirs;
 use system_wide_key_state::*;
+use tracing::warn;
 
 // Std.
 use std::io::prelude::*;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::{fs::*, u16};
 
 // Custom.
+use crate::error::SilentError;
 use crate::global_params::*;
+use crate::misc::password_obfuscator::PasswordObfuscator;
+use crate::services::audio_service::agc::AGC_DEFAULT_TARGET_RMS;
+use crate::services::audio_service::microphone_boost::*;
+use crate::services::audio_service::silence_detector::SILENCE_DEFAULT_THRESHOLD;
+use crate::services::audio_service::voice_activity_detector::*;
+use crate::services::tcp_packets::VoiceCodec;
+
+pub enum ConfigLoadResult {
+    Ok,
+    Recovered { backup_path: String }, // the config was corrupted and was reset to defaults
+}
+
+// Settings that are checked in 'UserConfig::validate_fields'.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConfigField {
+    Username,
+    MasterVolume,
+    MicrophoneVolume,
+    MicrophoneBoost,
+    ServerPort,
+    Locale,
+    MessageCooldown,
+    RoomChangeSameTierCooldown,
+    RoomChangeLobbyCooldown,
+    IdleTimeout,
+    UdpHealthCheckTimeout,
+    PttReleaseDelay,
+    AecDelay,
+    AutoAwayMessage,
+    LogLevel,
+    PttPressSound,
+    PttReleaseSound,
+    MicrophoneChannels,
+    UdpRecvBuffer,
+    UdpSendBuffer,
+    PushToTalkButton,
+    ChatMaxMessages,
+    VadSensitivity,
+    AudioPlayBufferChunks,
+    AudioRecordBufferChunks,
+}
 
 pub struct UserConfig {
     pub username: String,
@@ -24,41 +69,239 @@ pub struct UserConfig {
     pub push_to_talk_button: KeyCode,
     pub show_message_notification: bool,
     pub locale: String,
+    pub agc_enabled: bool,
+    pub agc_target_rms: f32,
+    pub pause_on_minimize: bool,
+    pub max_voice_upload_kbps: u32,
+    pub start_fullscreen: bool,
+    pub window_width: u16,
+    pub window_height: u16,
+    pub message_cooldown_sec: u8,
+    pub room_change_same_tier_cooldown_sec: u8,
+    pub ptt_release_delay_ms: u16,
+    pub idle_timeout_min: u8,
+    pub theme_name: String, // empty for the bundled default theme
+    pub max_voice_packets_per_sec: u32,
+    pub show_inline_images: bool,
+    pub password_remember: bool,
+    pub aec_enabled: bool,
+    pub aec_delay_ms: u32,
+    pub log_level: String,
+    pub spatial_audio_enabled: bool,
+    pub auto_away_message: String,
+    pub ptt_press_sound: String,   // path to the sound file
+    pub ptt_release_sound: String, // path to the sound file
+    pub accessibility_mode: bool,
+    pub udp_recv_buffer_kb: u32,
+    pub udp_send_buffer_kb: u32,
+    pub microphone_channels: u8,
+    pub advanced_settings_expanded: bool,
+    pub compact_user_list: bool,
+    pub silence_suppression_enabled: bool,
+    pub silence_threshold: f32,
+    pub udp_health_check_enabled: bool,
+    pub udp_health_check_timeout_sec: u8,
+    pub preferred_network_interface: String, // empty to bind to all interfaces
+    dirty: bool,                             // changed but not saved yet
+    change_count: Arc<AtomicU64>, // read by 'ConfigSaveController' without locking the config
+    pub connect_on_launch: bool,
+    pub room_change_lobby_cooldown_sec: u8,
+    pub microphone_boost_db: f32,
+    pub chat_max_messages: usize,
+    pub push_to_talk_mode: PushToTalkMode,
+    pub vad_sensitivity: u8,
+    pub voice_codec: VoiceCodec,
+    pub audio_input_device: String,
+    pub auto_record: bool,
+    pub audio_play_buffer_chunks: u8,
+    pub audio_record_buffer_chunks: u8,
 }
 
 impl UserConfig {
-    pub fn new() -> Result<UserConfig, String> {
-        UserConfig::open()
+    // Reads the config file, if the config file is corrupted
+    // renames it to CLIENT_CONFIG_BACKUP_FILE_NAME and returns default config.
+    pub fn open_or_recover() -> Result<(UserConfig, ConfigLoadResult), SilentError> {
+        let config_path = UserConfig::get_config_file_path();
+        if let Err(e) = config_path {
+            return Err(SilentError::ConfigError(format!(
+                "{} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+
+        UserConfig::open_or_recover_from_path(&config_path.unwrap())
+    }
+
+    // Reads the config file (used to discard the changes that were not saved).
+    pub fn open() -> Result<UserConfig, SilentError> {
+        let config_path = UserConfig::get_config_file_path();
+        if let Err(e) = config_path {
+            return Err(SilentError::ConfigError(format!(
+                "{} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+
+        UserConfig::open_from_path(&config_path.unwrap())
+    }
+
+    pub fn open_or_recover_from_path(
+        config_path: &str,
+    ) -> Result<(UserConfig, ConfigLoadResult), SilentError> {
+        let open_error = match UserConfig::open_from_path(config_path) {
+            Ok(config) => {
+                if let Err(errors) = config.validate() {
+                    // the settings layout will show these errors
+                    warn!(
+                        "the config file '{}' contains invalid values: {}",
+                        config_path,
+                        errors.join("; ")
+                    );
+                }
+                return Ok((config, ConfigLoadResult::Ok));
+            }
+            Err(SilentError::UnsupportedConfigVersion(msg)) => {
+                // the file is not corrupted, don't reset the settings of the newer app
+                return Err(SilentError::UnsupportedConfigVersion(msg));
+            }
+            Err(msg) => msg,
+        };
+
+        warn!(
+            "failed to read the config file '{}' (error: {}), resetting it to defaults at [{}, {}]",
+            config_path,
+            open_error,
+            file!(),
+            line!()
+        );
+
+        let backup_path = match Path::new(config_path).parent() {
+            Some(dir) => dir.join(CLIENT_CONFIG_BACKUP_FILE_NAME),
+            None => Path::new(CLIENT_CONFIG_BACKUP_FILE_NAME).to_path_buf(),
+        };
+        let backup_path = String::from(backup_path.to_str().unwrap());
+
+        if Path::new(&backup_path).exists() {
+            if let Err(e) = std::fs::remove_file(&backup_path) {
+                return Err(SilentError::ConfigError(format!(
+                    "std::fs::remove_file() failed, error: can't remove old config backup '{}' (error: {}) (config error: {}) at [{}, {}]",
+                    backup_path, e, open_error, file!(), line!()
+                )));
+            }
+        }
+
+        if let Err(e) = std::fs::rename(config_path, &backup_path) {
+            return Err(SilentError::ConfigError(format!(
+                "std::fs::rename() failed, error: can't rename corrupted config to '{}' (error: {}) (config error: {}) at [{}, {}]",
+                backup_path, e, open_error, file!(), line!()
+            )));
+        }
+
+        let config = UserConfig::empty();
+        if let Err(msg) = config.save_to_path(config_path) {
+            return Err(SilentError::ConfigError(format!(
+                "{} at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        Ok((config, ConfigLoadResult::Recovered { backup_path }))
+    }
+
+    /// The config will be saved later (see 'save_if_dirty').
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.change_count.fetch_add(1, Ordering::SeqCst);
+    }
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    /// Returns the counter of 'mark_dirty' calls.
+    pub fn get_change_count(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.change_count)
+    }
+    /// Replaces all settings with 'config' but keeps the counter of 'mark_dirty' calls
+    /// (it might be watched, see 'get_change_count').
+    pub fn replace_with(&mut self, config: UserConfig) {
+        let change_count = Arc::clone(&self.change_count);
+
+        *self = config;
+        self.change_count = change_count;
+    }
+    /// Saves the config if it was changed since the last save.
+    pub fn save_if_dirty(&mut self) -> Result<(), SilentError> {
+        self.save_if_dirty_with(UserConfig::save)
+    }
+    /// Same as 'save_if_dirty' but 'save' is used to write the config.
+    pub fn save_if_dirty_with<F>(&mut self, save: F) -> Result<(), SilentError>
+    where
+        F: FnOnce(&UserConfig) -> Result<(), SilentError>,
+    {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Err(err) = save(self) {
+            return Err(err);
+        }
+        self.dirty = false;
+
+        Ok(())
     }
+    pub fn save(&self) -> Result<(), SilentError> {
+        if let Err(errors) = self.validate() {
+            return Err(SilentError::ConfigError(format!(
+                "the config contains invalid values: {} at [{}, {}]",
+                errors.join("; "),
+                file!(),
+                line!()
+            )));
+        }
 
-    pub fn save(&self) -> Result<(), String> {
         let config_path = UserConfig::get_config_file_path();
         if let Err(e) = config_path {
-            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+            return Err(SilentError::ConfigError(format!(
+                "{} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
         }
-        let mut config_path = config_path.unwrap();
+
+        self.save_to_path(&config_path.unwrap())
+    }
+
+    // Saves the config to the specified file (used to export/import the config).
+    pub fn save_to_path(&self, config_path: &str) -> Result<(), SilentError> {
+        let mut config_path = String::from(config_path);
         config_path += "~"; // save this first, then delete old one and rename this file
 
         if Path::new(&config_path).exists() {
             // Remove old temp file.
             if let Err(e) = std::fs::remove_file(&config_path) {
-                return Err(format!("std::fs::remove_file() failed, error: can't remove temp config file (error: {}) at [{}, {}]",
+                return Err(SilentError::ConfigError(format!("std::fs::remove_file() failed, error: can't remove temp config file (error: {}) at [{}, {}]",
                 e,
                 file!(),
-                line!()));
+                line!())));
             }
         }
 
         // Create and write.
         let config_file = File::create(&config_path);
         if let Err(e) = config_file {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::create() failed, error: can't open config file '{}' (error: {}) at [{}, {}]",
                 config_path,
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         let mut config_file = config_file.unwrap();
 
@@ -66,156 +309,162 @@ impl UserConfig {
         let magic_number = CONFIG_FILE_MAGIC_NUMBER;
         let res = UserConfig::write_u16_to_file(&mut config_file, magic_number);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing magic number) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write config version.
         let config_version = CONFIG_FILE_VERSION;
         let buf = bincode::serialize(&config_version);
         if let Err(e) = buf {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "u64::encode::<u64>() failed, error: {} at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         let buf = buf.unwrap();
         if let Err(e) = config_file.write(&buf) {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::write() failed, error: can't write config version to config file (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write username len.
         let res = UserConfig::write_u16_to_file(&mut config_file, self.username.len() as u16);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing username len) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write username.
         let res = UserConfig::write_string_to_file(&mut config_file, &self.username);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing username) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write server len.
         let res = UserConfig::write_u16_to_file(&mut config_file, self.server.len() as u16);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing server len) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write server.
         let res = UserConfig::write_string_to_file(&mut config_file, &self.server);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing server) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write server port.
         let res = UserConfig::write_u16_to_file(&mut config_file, self.server_port);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing server port) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
+        // Password is stored obfuscated (or not stored at all).
+        let stored_password = if self.password_remember {
+            PasswordObfuscator::obfuscate(&self.server_password)
+        } else {
+            String::new()
+        };
+
         // Write password len.
-        let res =
-            UserConfig::write_u16_to_file(&mut config_file, self.server_password.len() as u16);
+        let res = UserConfig::write_u16_to_file(&mut config_file, stored_password.len() as u16);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing password len) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
-        if self.server_password.len() > 0 {
+        if stored_password.len() > 0 {
             // Write password.
-            let res = UserConfig::write_string_to_file(&mut config_file, &self.server_password);
+            let res = UserConfig::write_string_to_file(&mut config_file, &stored_password);
             if let Err(msg) = res {
-                return Err(format!(
+                return Err(SilentError::ConfigError(format!(
                     "{} (writing password) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
         }
 
         // Write ui scaling.
         let res = UserConfig::write_u16_to_file(&mut config_file, self.ui_scaling);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing ui scaling) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write push-to-talk key.
         let key: u64 = self.push_to_talk_button.to_u64().unwrap();
         let buf = bincode::serialize(&key);
         if let Err(e) = buf {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "u64::encode::<u64>() failed, error: {} at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         let buf = buf.unwrap();
         if let Err(e) = config_file.write(&buf) {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::write() failed, error: can't write (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write master volume.
         let res = UserConfig::write_u16_to_file(&mut config_file, self.master_volume);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing master volume) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         let mut buf = vec![0u8; std::mem::size_of::<bool>()];
@@ -223,336 +472,2136 @@ impl UserConfig {
             buf[0] = 1;
         }
         if let Err(e) = config_file.write(&mut buf) {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::read() failed, error: can't write bool (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write locale
         let mut locale_vec = self.locale.as_bytes();
         if locale_vec.len() != 2 {
-            println!(
-                "SILENT_WARNING: locale string length is not 2, locale: {}, using 'en' locale instead.",
+            warn!(
+                "locale string length is not 2, locale: {}, using 'en' locale instead.",
                 &self.locale
             );
             locale_vec = "en".as_bytes();
         }
         if let Err(e) = config_file.write(&mut locale_vec) {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::read() failed, error: can't write locale (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
         // Write microphone volume
         let res = UserConfig::write_u16_to_file(&mut config_file, self.microphone_volume);
         if let Err(msg) = res {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "{} (writing microphone volume) at [{}, {}]",
                 msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
-        // new settings go here...
-        //
-        // also update CONFIG_FILE_VERSION if new options are added
-        //
+        // Write AGC enabled.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.agc_enabled);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing agc enabled) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-        // Finished.
-        drop(config_file);
+        // Write AGC target RMS.
+        let res = UserConfig::write_f32_to_file(&mut config_file, self.agc_target_rms);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing agc target rms) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-        config_path.pop(); // pop '~'
-        if Path::new(&config_path).exists() {
-            // Remove old config file.
-            if let Err(e) = std::fs::remove_file(&config_path) {
-                return Err(format!("std::fs::remove_file() failed, error: can't remove old config file (error: {}) at [{}, {}]",
-                e,
+        // Write pause on minimize.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.pause_on_minimize);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing pause on minimize) at [{}, {}]",
+                msg,
                 file!(),
-                line!()));
-            }
+                line!()
+            )));
         }
 
-        // Rename temp config file (with '~' to new config file).
-        let old_name = String::from(&config_path) + "~";
-        if let Err(e) = std::fs::rename(&old_name, &config_path) {
-            return Err(format!(
-                "std::fs::rename() failed, error: failed to rename temp file ({}) to ({}) (error: {}) at [{}, {}]",
-                old_name,
-                config_path,
-                e,
+        // Write max voice upload kbps.
+        let res = UserConfig::write_u32_to_file(&mut config_file, self.max_voice_upload_kbps);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing max voice upload kbps) at [{}, {}]",
+                msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
-        Ok(())
-    }
+        // Write start fullscreen.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.start_fullscreen);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing start fullscreen) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-    fn empty() -> UserConfig {
-        let locale =
-            String::from(&sys_locale::get_locale().unwrap_or_else(|| String::from("en"))[..2]);
+        // Write window width.
+        let res = UserConfig::write_u16_to_file(&mut config_file, self.window_width);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing window width) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-        UserConfig {
-            username: String::from(""),
-            server: String::from(""),
-            server_port: DEFAULT_SERVER_PORT,
-            server_password: String::from(""),
-            ui_scaling: 100,
-            master_volume: 100,
-            microphone_volume: 100,
-            push_to_talk_button: KeyCode::KT,
-            show_message_notification: true,
-            locale,
+        // Write window height.
+        let res = UserConfig::write_u16_to_file(&mut config_file, self.window_height);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing window height) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
         }
-    }
 
-    fn open() -> Result<UserConfig, String> {
-        let config_path = UserConfig::get_config_file_path();
-        if let Err(e) = config_path {
-            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        // Write message cooldown.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.message_cooldown_sec);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing message cooldown) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
         }
-        let config_path = config_path.unwrap();
 
-        if Path::new(&config_path).exists() {
-            // Open and read existing file.
-            let config_file = File::open(&config_path);
-            if let Err(e) = config_file {
-                return Err(format!(
-                    "File::open() failed, error: can't open config file '{}' (error: {}) at [{}, {}]",
-                    config_path,
-                    e,
-                    file!(),
-                    line!()
-                ));
-            }
-            let mut config_file = config_file.unwrap();
+        // Write room change cooldown.
+        let res =
+            UserConfig::write_u8_to_file(&mut config_file, self.room_change_same_tier_cooldown_sec);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing room change cooldown) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-            let mut user_config = UserConfig::empty();
+        // Write push-to-talk release delay.
+        let res = UserConfig::write_u16_to_file(&mut config_file, self.ptt_release_delay_ms);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing push-to-talk release delay) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-            // Read magic number.
-            let magic_number = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = magic_number {
-                return Err(format!(
-                    "{} (reading magic number) at [{}, {}]",
-                    msg,
-                    file!(),
-                    line!()
-                ));
-            }
-            let magic_number = magic_number.unwrap();
-            if magic_number != CONFIG_FILE_MAGIC_NUMBER {
-                return Err(format!(
-                    "An error occurred: file magic number ({}) != config magic number ({}) at [{}, {}]",
-                    magic_number,
-                    CONFIG_FILE_MAGIC_NUMBER,
-                    file!(),
-                    line!(),
-                ));
-            }
+        // Write idle timeout.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.idle_timeout_min);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing idle timeout) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-            // Read config version.
-            let mut buf = vec![0u8; std::mem::size_of::<u64>()];
-            if let Err(e) = config_file.read(&mut buf) {
-                return Err(format!(
-                    "File::read() failed, error: can't read config version from config file (error: {}) at [{}, {}]",
-                    e,
-                    file!(),
-                    line!()
-                ));
-            }
-            // use it to handle old config versions...
-            let config_version = bincode::deserialize::<u64>(&buf).unwrap();
+        // Write theme name len.
+        let res = UserConfig::write_u16_to_file(&mut config_file, self.theme_name.len() as u16);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing theme name len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-            // Read username len.
-            let username_len = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = username_len {
-                return Err(format!(
-                    "{} (reading username len) at [{}, {}]",
+        if self.theme_name.len() > 0 {
+            // Write theme name.
+            let res = UserConfig::write_string_to_file(&mut config_file, &self.theme_name);
+            if let Err(msg) = res {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (writing theme name) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            let username_len = username_len.unwrap();
+        }
 
-            // Read username.
-            let username = UserConfig::read_string_from_file(&mut config_file, username_len);
-            if let Err(msg) = username {
-                return Err(format!(
-                    "{} (reading username) at [{}, {}]",
-                    msg,
-                    file!(),
-                    line!()
-                ));
-            }
-            user_config.username = username.unwrap();
+        // Write max received voice packets per second.
+        let res = UserConfig::write_u32_to_file(&mut config_file, self.max_voice_packets_per_sec);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing max received voice packets per second) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-            // Read server len.
-            let server_len = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = server_len {
-                return Err(format!(
-                    "{} (reading server len) at [{}, {}]",
-                    msg,
-                    file!(),
-                    line!()
-                ));
-            }
-            let server_len = server_len.unwrap();
+        // Write show inline images.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.show_inline_images);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing show inline images) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-            // Read server.
-            let server = UserConfig::read_string_from_file(&mut config_file, server_len);
-            if let Err(msg) = server {
-                return Err(format!(
-                    "{} (reading server) at [{}, {}]",
-                    msg,
-                    file!(),
+        // Write remember password.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.password_remember);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing remember password) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write AEC enabled.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.aec_enabled);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing AEC enabled) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write AEC delay.
+        let res = UserConfig::write_u32_to_file(&mut config_file, self.aec_delay_ms);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing AEC delay) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write log level len.
+        let res = UserConfig::write_u16_to_file(&mut config_file, self.log_level.len() as u16);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing log level len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write log level.
+        let res = UserConfig::write_string_to_file(&mut config_file, &self.log_level);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing log level) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write spatial audio enabled.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.spatial_audio_enabled);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing spatial audio enabled) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write auto-away message len.
+        let res =
+            UserConfig::write_u16_to_file(&mut config_file, self.auto_away_message.len() as u16);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing auto-away message len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        if self.auto_away_message.len() > 0 {
+            // Write auto-away message.
+            let res = UserConfig::write_string_to_file(&mut config_file, &self.auto_away_message);
+            if let Err(msg) = res {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (writing auto-away message) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+        }
+
+        // Write push-to-talk press sound len.
+        let res =
+            UserConfig::write_u16_to_file(&mut config_file, self.ptt_press_sound.len() as u16);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing push-to-talk press sound len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write push-to-talk press sound.
+        let res = UserConfig::write_string_to_file(&mut config_file, &self.ptt_press_sound);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing push-to-talk press sound) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write push-to-talk release sound len.
+        let res =
+            UserConfig::write_u16_to_file(&mut config_file, self.ptt_release_sound.len() as u16);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing push-to-talk release sound len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write push-to-talk release sound.
+        let res = UserConfig::write_string_to_file(&mut config_file, &self.ptt_release_sound);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing push-to-talk release sound) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write accessibility mode.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.accessibility_mode);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing accessibility mode) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write UDP receive buffer size.
+        let res = UserConfig::write_u32_to_file(&mut config_file, self.udp_recv_buffer_kb);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing UDP receive buffer size) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write UDP send buffer size.
+        let res = UserConfig::write_u32_to_file(&mut config_file, self.udp_send_buffer_kb);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing UDP send buffer size) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write microphone channels.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.microphone_channels);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing microphone channels) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write advanced settings expanded.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.advanced_settings_expanded);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing advanced settings expanded) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write compact user list.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.compact_user_list);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing compact user list) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write silence suppression enabled.
+        let res =
+            UserConfig::write_bool_to_file(&mut config_file, self.silence_suppression_enabled);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing silence suppression enabled) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write silence threshold.
+        let res = UserConfig::write_f32_to_file(&mut config_file, self.silence_threshold);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing silence threshold) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write UDP health check enabled.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.udp_health_check_enabled);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing UDP health check enabled) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write UDP health check timeout.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.udp_health_check_timeout_sec);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing UDP health check timeout) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write preferred network interface len.
+        let res = UserConfig::write_u16_to_file(
+            &mut config_file,
+            self.preferred_network_interface.len() as u16,
+        );
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing preferred network interface len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        if self.preferred_network_interface.len() > 0 {
+            // Write preferred network interface.
+            let res = UserConfig::write_string_to_file(
+                &mut config_file,
+                &self.preferred_network_interface,
+            );
+            if let Err(msg) = res {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (writing preferred network interface) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+        }
+
+        // Write connect on launch.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.connect_on_launch);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing connect on launch) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write lobby room change cooldown.
+        let res =
+            UserConfig::write_u8_to_file(&mut config_file, self.room_change_lobby_cooldown_sec);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing lobby room change cooldown) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write microphone boost.
+        let res = UserConfig::write_f32_to_file(&mut config_file, self.microphone_boost_db);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing microphone boost) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write chat max messages.
+        let res = UserConfig::write_u32_to_file(&mut config_file, self.chat_max_messages as u32);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing chat max messages) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write push-to-talk mode.
+        let res =
+            UserConfig::write_u8_to_file(&mut config_file, self.push_to_talk_mode.to_u8().unwrap());
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing push-to-talk mode) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write voice activation sensitivity.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.vad_sensitivity);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing voice activation sensitivity) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write voice codec.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.voice_codec.to_u8().unwrap());
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing voice codec) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write audio input device len.
+        let res =
+            UserConfig::write_u16_to_file(&mut config_file, self.audio_input_device.len() as u16);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing audio input device len) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        if self.audio_input_device.len() > 0 {
+            // Write audio input device.
+            let res = UserConfig::write_string_to_file(&mut config_file, &self.audio_input_device);
+            if let Err(msg) = res {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (writing audio input device) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+        }
+
+        // Write auto record.
+        let res = UserConfig::write_bool_to_file(&mut config_file, self.auto_record);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing auto record) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write audio play buffer chunks.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.audio_play_buffer_chunks);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing audio play buffer chunks) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // Write audio record buffer chunks.
+        let res = UserConfig::write_u8_to_file(&mut config_file, self.audio_record_buffer_chunks);
+        if let Err(msg) = res {
+            return Err(SilentError::ConfigError(format!(
+                "{} (writing audio record buffer chunks) at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
+
+        // new settings go here...
+        //
+        // also update CONFIG_FILE_VERSION if new options are added
+        //
+
+        // Finished.
+        drop(config_file);
+
+        config_path.pop(); // pop '~'
+        if Path::new(&config_path).exists() {
+            // Remove old config file.
+            if let Err(e) = std::fs::remove_file(&config_path) {
+                return Err(SilentError::ConfigError(format!("std::fs::remove_file() failed, error: can't remove old config file (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!())));
+            }
+        }
+
+        // Rename temp config file (with '~' to new config file).
+        let old_name = String::from(&config_path) + "~";
+        if let Err(e) = std::fs::rename(&old_name, &config_path) {
+            return Err(SilentError::ConfigError(format!(
+                "std::fs::rename() failed, error: failed to rename temp file ({}) to ({}) (error: {}) at [{}, {}]",
+                old_name,
+                config_path,
+                e,
+                file!(),
+                line!()
+            )));
+        }
+
+        Ok(())
+    }
+
+    // Returns all found problems (not just the first one).
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let errors = self.validate_fields();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.into_iter().map(|(_, error)| error).collect())
+        }
+    }
+
+    /// Same as 'validate' but also returns the setting that each problem belongs to.
+    pub fn validate_fields(&self) -> Vec<(ConfigField, String)> {
+        let mut errors = Vec::new();
+
+        // The lower bound (1 character) is not checked here: a new config has an empty
+        // username until the user connects for the first time (the connect layout
+        // does not allow to connect with an empty username) but the settings can
+        // be changed and saved before that.
+        let username_len = self.username.chars().count();
+        if username_len > MAX_USERNAME_SIZE {
+            errors.push((
+                ConfigField::Username,
+                format!(
+                    "username length ({}) exceeds the maximum ({})",
+                    username_len, MAX_USERNAME_SIZE
+                ),
+            ));
+        }
+
+        if self.master_volume > 100 {
+            errors.push((
+                ConfigField::MasterVolume,
+                format!(
+                    "master volume ({}) is not in range 0-100",
+                    self.master_volume
+                ),
+            ));
+        }
+
+        if self.microphone_volume > 100 {
+            errors.push((
+                ConfigField::MicrophoneVolume,
+                format!(
+                    "microphone volume ({}) is not in range 0-100",
+                    self.microphone_volume
+                ),
+            ));
+        }
+
+        if !(self.microphone_boost_db >= MIN_MICROPHONE_BOOST_DB
+            && self.microphone_boost_db <= MAX_MICROPHONE_BOOST_DB)
+        {
+            errors.push((
+                ConfigField::MicrophoneBoost,
+                format!(
+                    "microphone boost ({} dB) is not in range {}-{} dB",
+                    self.microphone_boost_db, MIN_MICROPHONE_BOOST_DB, MAX_MICROPHONE_BOOST_DB
+                ),
+            ));
+        }
+
+        if self.server_port == 0 {
+            errors.push((
+                ConfigField::ServerPort,
+                String::from("server port is not in range 1-65535"),
+            ));
+        }
+
+        if self.locale.len() != 2 || !self.locale.is_ascii() {
+            errors.push((
+                ConfigField::Locale,
+                format!(
+                    "locale '{}' is not a 2-character language code",
+                    self.locale
+                ),
+            ));
+        }
+
+        if self.message_cooldown_sec > MAX_MESSAGE_COOLDOWN_SEC {
+            errors.push((
+                ConfigField::MessageCooldown,
+                format!(
+                    "message cooldown ({}) is not in range 0-{}",
+                    self.message_cooldown_sec, MAX_MESSAGE_COOLDOWN_SEC
+                ),
+            ));
+        }
+
+        if self.room_change_same_tier_cooldown_sec > MAX_ROOM_CHANGE_COOLDOWN_SEC {
+            errors.push((
+                ConfigField::RoomChangeSameTierCooldown,
+                format!(
+                    "room change cooldown ({}) is not in range 0-{}",
+                    self.room_change_same_tier_cooldown_sec, MAX_ROOM_CHANGE_COOLDOWN_SEC
+                ),
+            ));
+        }
+
+        if self.room_change_lobby_cooldown_sec > MAX_ROOM_CHANGE_COOLDOWN_SEC {
+            errors.push((
+                ConfigField::RoomChangeLobbyCooldown,
+                format!(
+                    "lobby room change cooldown ({}) is not in range 0-{}",
+                    self.room_change_lobby_cooldown_sec, MAX_ROOM_CHANGE_COOLDOWN_SEC
+                ),
+            ));
+        }
+
+        if self.idle_timeout_min > MAX_IDLE_TIMEOUT_MIN {
+            errors.push((
+                ConfigField::IdleTimeout,
+                format!(
+                    "idle timeout ({} min) is not in range 0-{} min",
+                    self.idle_timeout_min, MAX_IDLE_TIMEOUT_MIN
+                ),
+            ));
+        }
+
+        if self.udp_health_check_timeout_sec < MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC
+            || self.udp_health_check_timeout_sec > MAX_UDP_HEALTH_CHECK_TIMEOUT_SEC
+        {
+            errors.push((
+                ConfigField::UdpHealthCheckTimeout,
+                format!(
+                    "UDP health check timeout ({} sec) is not in range {}-{} sec",
+                    self.udp_health_check_timeout_sec,
+                    MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC,
+                    MAX_UDP_HEALTH_CHECK_TIMEOUT_SEC
+                ),
+            ));
+        }
+
+        if self.ptt_release_delay_ms > MAX_PTT_RELEASE_DELAY_MS {
+            errors.push((
+                ConfigField::PttReleaseDelay,
+                format!(
+                    "push-to-talk release delay ({} ms) is not in range 0-{} ms",
+                    self.ptt_release_delay_ms, MAX_PTT_RELEASE_DELAY_MS
+                ),
+            ));
+        }
+
+        if self.aec_delay_ms > MAX_AEC_DELAY_MS {
+            errors.push((
+                ConfigField::AecDelay,
+                format!(
+                    "echo cancellation delay ({} ms) is not in range 0-{} ms",
+                    self.aec_delay_ms, MAX_AEC_DELAY_MS
+                ),
+            ));
+        }
+
+        if self.auto_away_message.chars().count() > MAX_MESSAGE_SIZE {
+            errors.push((
+                ConfigField::AutoAwayMessage,
+                format!(
+                    "auto-away message is longer than {} characters",
+                    MAX_MESSAGE_SIZE
+                ),
+            ));
+        }
+
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            errors.push((
+                ConfigField::LogLevel,
+                format!(
+                    "log level '{}' is not one of: {}",
+                    self.log_level,
+                    LOG_LEVELS.join(", ")
+                ),
+            ));
+        }
+
+        // Empty path is not checked (the default sound is used),
+        // 'AudioService' also falls back to the default sound if the file can't be loaded.
+        for (field, name, sound_path) in [
+            (
+                ConfigField::PttPressSound,
+                "push-to-talk press sound",
+                &self.ptt_press_sound,
+            ),
+            (
+                ConfigField::PttReleaseSound,
+                "push-to-talk release sound",
+                &self.ptt_release_sound,
+            ),
+        ] {
+            if !sound_path.is_empty() && !Path::new(sound_path).exists() {
+                errors.push((
+                    field,
+                    format!("{} file '{}' does not exist", name, sound_path),
+                ));
+            }
+        }
+
+        if self.microphone_channels != 1 && self.microphone_channels != 2 {
+            errors.push((
+                ConfigField::MicrophoneChannels,
+                format!(
+                    "microphone channels ({}) should be 1 or 2",
+                    self.microphone_channels
+                ),
+            ));
+        }
+
+        for (field, name, size_kb) in [
+            (
+                ConfigField::UdpRecvBuffer,
+                "UDP receive buffer size",
+                self.udp_recv_buffer_kb,
+            ),
+            (
+                ConfigField::UdpSendBuffer,
+                "UDP send buffer size",
+                self.udp_send_buffer_kb,
+            ),
+        ] {
+            if size_kb < MIN_UDP_SOCKET_BUFFER_KB || size_kb > MAX_UDP_SOCKET_BUFFER_KB {
+                errors.push((
+                    field,
+                    format!(
+                        "{} ({} KB) is not in range {}-{} KB",
+                        name, size_kb, MIN_UDP_SOCKET_BUFFER_KB, MAX_UDP_SOCKET_BUFFER_KB
+                    ),
+                ));
+            }
+        }
+
+        if self.push_to_talk_button.to_u64().is_none() {
+            errors.push((
+                ConfigField::PushToTalkButton,
+                String::from("push-to-talk button can't be encoded"),
+            ));
+        }
+
+        if self.chat_max_messages < MIN_CHAT_MAX_MESSAGES
+            || self.chat_max_messages > MAX_CHAT_MAX_MESSAGES
+        {
+            errors.push((
+                ConfigField::ChatMaxMessages,
+                format!(
+                    "chat max messages ({}) is not in range {}-{}",
+                    self.chat_max_messages, MIN_CHAT_MAX_MESSAGES, MAX_CHAT_MAX_MESSAGES
+                ),
+            ));
+        }
+
+        if self.vad_sensitivity > VAD_MAX_SENSITIVITY {
+            errors.push((
+                ConfigField::VadSensitivity,
+                format!(
+                    "voice activation sensitivity ({}) is not in range 0-{}",
+                    self.vad_sensitivity, VAD_MAX_SENSITIVITY
+                ),
+            ));
+        }
+
+        if self.audio_play_buffer_chunks < MIN_AUDIO_PLAY_BUFFER_CHUNKS
+            || self.audio_play_buffer_chunks > MAX_AUDIO_PLAY_BUFFER_CHUNKS
+        {
+            errors.push((
+                ConfigField::AudioPlayBufferChunks,
+                format!(
+                    "audio play buffer ({} chunks) is not in range {}-{}",
+                    self.audio_play_buffer_chunks,
+                    MIN_AUDIO_PLAY_BUFFER_CHUNKS,
+                    MAX_AUDIO_PLAY_BUFFER_CHUNKS
+                ),
+            ));
+        }
+
+        if self.audio_record_buffer_chunks > MAX_AUDIO_RECORD_BUFFER_CHUNKS {
+            errors.push((
+                ConfigField::AudioRecordBufferChunks,
+                format!(
+                    "audio record buffer ({} chunks) is not in range 0-{}",
+                    self.audio_record_buffer_chunks, MAX_AUDIO_RECORD_BUFFER_CHUNKS
+                ),
+            ));
+        }
+
+        errors
+    }
+
+    // Resets all settings to their default values
+    // except for the connection fields (username, server, port, password, remember password).
+    pub fn restore_defaults(&mut self) {
+        let mut default_config = UserConfig::empty();
+
+        default_config.username = self.username.clone();
+        default_config.server = self.server.clone();
+        default_config.server_port = self.server_port;
+        default_config.server_password = self.server_password.clone();
+        default_config.password_remember = self.password_remember;
+
+        *self = default_config;
+    }
+
+    pub fn empty() -> UserConfig {
+        let locale =
+            String::from(&sys_locale::get_locale().unwrap_or_else(|| String::from("en"))[..2]);
+
+        UserConfig {
+            username: String::from(""),
+            server: String::from(""),
+            server_port: DEFAULT_SERVER_PORT,
+            server_password: String::from(""),
+            ui_scaling: 100,
+            master_volume: 100,
+            microphone_volume: 100,
+            push_to_talk_button: KeyCode::KT,
+            show_message_notification: true,
+            locale,
+            agc_enabled: false,
+            agc_target_rms: AGC_DEFAULT_TARGET_RMS,
+            pause_on_minimize: false,
+            max_voice_upload_kbps: 0,
+            start_fullscreen: false,
+            window_width: DEFAULT_WINDOW_WIDTH,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+            message_cooldown_sec: DEFAULT_MESSAGE_COOLDOWN_SEC,
+            room_change_same_tier_cooldown_sec: DEFAULT_ROOM_CHANGE_SAME_TIER_COOLDOWN_SEC,
+            ptt_release_delay_ms: DEFAULT_PTT_RELEASE_DELAY_MS,
+            idle_timeout_min: DEFAULT_IDLE_TIMEOUT_MIN,
+            theme_name: String::new(),
+            max_voice_packets_per_sec: DEFAULT_MAX_VOICE_PACKETS_PER_SEC,
+            show_inline_images: true,
+            password_remember: true,
+            aec_enabled: false,
+            aec_delay_ms: DEFAULT_AEC_DELAY_MS,
+            log_level: String::from(DEFAULT_LOG_LEVEL),
+            spatial_audio_enabled: false,
+            auto_away_message: String::from(DEFAULT_AUTO_AWAY_MESSAGE),
+            ptt_press_sound: String::from(PUSH_TO_TALK_PRESS_SOUND),
+            ptt_release_sound: String::from(PUSH_TO_TALK_UNPRESS_SOUND),
+            accessibility_mode: false,
+            udp_recv_buffer_kb: DEFAULT_UDP_RECV_BUFFER_KB,
+            udp_send_buffer_kb: DEFAULT_UDP_SEND_BUFFER_KB,
+            microphone_channels: DEFAULT_MICROPHONE_CHANNELS,
+            advanced_settings_expanded: false,
+            compact_user_list: false,
+            silence_suppression_enabled: false,
+            silence_threshold: SILENCE_DEFAULT_THRESHOLD,
+            udp_health_check_enabled: true,
+            udp_health_check_timeout_sec: DEFAULT_UDP_HEALTH_CHECK_TIMEOUT_SEC,
+            preferred_network_interface: String::new(),
+            dirty: false,
+            change_count: Arc::new(AtomicU64::new(0)),
+            connect_on_launch: false,
+            room_change_lobby_cooldown_sec: DEFAULT_ROOM_CHANGE_LOBBY_COOLDOWN_SEC,
+            microphone_boost_db: 0.0,
+            chat_max_messages: DEFAULT_CHAT_MAX_MESSAGES,
+            push_to_talk_mode: PushToTalkMode::HoldKey,
+            vad_sensitivity: VAD_DEFAULT_SENSITIVITY,
+            voice_codec: VoiceCodec::Opus,
+            audio_input_device: String::new(),
+            auto_record: false,
+            audio_play_buffer_chunks: DEFAULT_AUDIO_PLAY_BUFFER_CHUNKS,
+            audio_record_buffer_chunks: DEFAULT_AUDIO_RECORD_BUFFER_CHUNKS,
+        }
+    }
+
+    // Reads the config from the specified file (returns default config if the file does not exist).
+    pub fn open_from_path(config_path: &str) -> Result<UserConfig, SilentError> {
+        if Path::new(&config_path).exists() {
+            // Open and read existing file.
+            let config_file = File::open(&config_path);
+            if let Err(e) = config_file {
+                return Err(SilentError::ConfigError(format!(
+                    "File::open() failed, error: can't open config file '{}' (error: {}) at [{}, {}]",
+                    config_path,
+                    e,
+                    file!(),
+                    line!()
+                )));
+            }
+            let mut config_file = config_file.unwrap();
+
+            let mut user_config = UserConfig::empty();
+
+            // Read magic number.
+            let magic_number = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = magic_number {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading magic number) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let magic_number = magic_number.unwrap();
+            if magic_number != CONFIG_FILE_MAGIC_NUMBER {
+                return Err(SilentError::ConfigError(format!(
+                    "An error occurred: file magic number ({}) != config magic number ({}) at [{}, {}]",
+                    magic_number,
+                    CONFIG_FILE_MAGIC_NUMBER,
+                    file!(),
+                    line!(),
+                )));
+            }
+
+            // Read config version.
+            let mut buf = vec![0u8; std::mem::size_of::<u64>()];
+            if let Err(e) = config_file.read_exact(&mut buf) {
+                return Err(SilentError::ConfigError(format!(
+                    "File::read_exact() failed, error: can't read config version from config file (error: {}) at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                )));
+            }
+            // use it to handle old config versions...
+            let config_version = bincode::deserialize::<u64>(&buf).unwrap();
+            if config_version > CONFIG_FILE_VERSION {
+                return Err(SilentError::UnsupportedConfigVersion(format!(
+                    "unsupported config version {} (the newest known version is {}), the config was probably created by a newer version of the app at [{}, {}]",
+                    config_version,
+                    CONFIG_FILE_VERSION,
+                    file!(),
+                    line!()
+                )));
+            }
+
+            // Read username len.
+            let username_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = username_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading username len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let username_len = username_len.unwrap();
+
+            // Read username.
+            let username = UserConfig::read_string_from_file(&mut config_file, username_len);
+            if let Err(msg) = username {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading username) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.username = username.unwrap();
+
+            // Read server len.
+            let server_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = server_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading server len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let server_len = server_len.unwrap();
+
+            // Read server.
+            let server = UserConfig::read_string_from_file(&mut config_file, server_len);
+            if let Err(msg) = server {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading server) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.server = server.unwrap();
+
+            // Read server port.
+            let server_port = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = server_port {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading server port) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.server_port = server_port.unwrap();
+
+            // Read password len.
+            let password_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = password_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading password len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let password_len = password_len.unwrap();
+
+            if password_len > 0 {
+                // Read password.
+                let password = UserConfig::read_string_from_file(&mut config_file, password_len);
+                if let Err(msg) = password {
+                    return Err(SilentError::ConfigError(format!(
+                        "{} (reading password) at [{}, {}]",
+                        msg,
+                        file!(),
+                        line!()
+                    )));
+                }
+                let password = password.unwrap();
+
+                if config_version < 13 {
+                    // stored as plain text
+                    user_config.server_password = password;
+                } else {
+                    match PasswordObfuscator::deobfuscate(&password) {
+                        Ok(password) => user_config.server_password = password,
+                        Err(msg) => warn!(
+                            "can't read the saved password, error: {} at [{}, {}]",
+                            msg,
+                            file!(),
+                            line!()
+                        ),
+                    }
+                }
+            }
+
+            // Read ui scaling.
+            let ui_scaling = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = ui_scaling {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading ui scaling) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.ui_scaling = ui_scaling.unwrap();
+
+            // Read push-to-talk button.
+            let mut buf = vec![0u8; std::mem::size_of::<u64>()];
+            if let Err(e) = config_file.read_exact(&mut buf) {
+                return Err(SilentError::ConfigError(format!(
+                    "File::read_exact() failed, error: can't read (error: {}) at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                )));
+            }
+            let key_code = bincode::deserialize::<u64>(&buf).unwrap();
+            match FromPrimitive::from_u64(key_code) {
+                Some(v) => user_config.push_to_talk_button = v,
+                None => {
+                    return Err(SilentError::ConfigError(format!(
+                        "FromPrimitive::from_u64 failed at [{}, {}]",
+                        file!(),
+                        line!()
+                    )));
+                }
+            }
+
+            // Read master volume.
+            let master_volume = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = master_volume {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading master volume) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.master_volume = master_volume.unwrap();
+
+            if config_version == 0 {
+                return Ok(user_config);
+            }
+
+            // Config file version #1 below...
+
+            // Read 'show message notification'.
+            let mut buf = vec![0u8; std::mem::size_of::<bool>()];
+            if let Err(e) = config_file.read_exact(&mut buf) {
+                return Err(SilentError::ConfigError(format!(
+                    "File::read_exact() failed, error: can't read bool (error: {}) at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                )));
+            }
+            if buf[0] == 1 {
+                user_config.show_message_notification = true;
+            } else {
+                user_config.show_message_notification = false;
+            }
+
+            if config_version == 1 {
+                return Ok(user_config);
+            }
+
+            // Config file version #2 below...
+
+            // Read locale
+            let mut buf = vec![0u8; 2];
+            if let Err(e) = config_file.read_exact(&mut buf) {
+                return Err(SilentError::ConfigError(format!(
+                    "File::read_exact() failed, error: can't read locale (error: {}) at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                )));
+            }
+            let locale = String::from_utf8(buf);
+            if let Err(e) = locale {
+                return Err(SilentError::ConfigError(format!(
+                    "String::from_utf8() failed, error: can't convert locale (error: {}) at [{}, {}]",
+                    e,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.locale = locale.unwrap();
+
+            // Read microphone volume
+            let microphone_volume = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = microphone_volume {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading microphone volume) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.microphone_volume = microphone_volume.unwrap();
+
+            if config_version == 2 {
+                return Ok(user_config);
+            }
+
+            // Config file version #3 below...
+
+            // Read AGC enabled.
+            let agc_enabled = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = agc_enabled {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading agc enabled) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.agc_enabled = agc_enabled.unwrap();
+
+            // Read AGC target RMS.
+            let agc_target_rms = UserConfig::read_f32_from_file(&mut config_file);
+            if let Err(msg) = agc_target_rms {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading agc target rms) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.agc_target_rms = agc_target_rms.unwrap();
+
+            if config_version == 3 {
+                return Ok(user_config);
+            }
+
+            // Config file version #4 below...
+
+            // Read pause on minimize.
+            let pause_on_minimize = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = pause_on_minimize {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading pause on minimize) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.pause_on_minimize = pause_on_minimize.unwrap();
+
+            if config_version == 4 {
+                return Ok(user_config);
+            }
+
+            // Config file version #5 below...
+
+            // Read max voice upload kbps.
+            let max_voice_upload_kbps = UserConfig::read_u32_from_file(&mut config_file);
+            if let Err(msg) = max_voice_upload_kbps {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading max voice upload kbps) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.max_voice_upload_kbps = max_voice_upload_kbps.unwrap();
+
+            if config_version == 5 {
+                return Ok(user_config);
+            }
+
+            // Config file version #6 below...
+
+            // Read start fullscreen.
+            let start_fullscreen = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = start_fullscreen {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading start fullscreen) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.start_fullscreen = start_fullscreen.unwrap();
+
+            // Read window width.
+            let window_width = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = window_width {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading window width) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.window_width = window_width.unwrap();
+
+            // Read window height.
+            let window_height = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = window_height {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading window height) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.window_height = window_height.unwrap();
+
+            if config_version == 6 {
+                return Ok(user_config);
+            }
+
+            // Config file version #7 below...
+
+            // Read message cooldown.
+            let message_cooldown_sec = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = message_cooldown_sec {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading message cooldown) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.message_cooldown_sec = message_cooldown_sec.unwrap();
+
+            // Read room change cooldown.
+            let room_change_same_tier_cooldown_sec =
+                UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = room_change_same_tier_cooldown_sec {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading room change cooldown) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.room_change_same_tier_cooldown_sec =
+                room_change_same_tier_cooldown_sec.unwrap();
+
+            if config_version == 7 {
+                return Ok(user_config);
+            }
+
+            // Config file version #8 below...
+
+            // Read push-to-talk release delay.
+            let ptt_release_delay_ms = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = ptt_release_delay_ms {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading push-to-talk release delay) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.ptt_release_delay_ms = ptt_release_delay_ms.unwrap();
+
+            if config_version == 8 {
+                return Ok(user_config);
+            }
+
+            // Config file version #9 below...
+
+            // Read idle timeout.
+            let idle_timeout_min = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = idle_timeout_min {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading idle timeout) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.idle_timeout_min = idle_timeout_min.unwrap();
+
+            if config_version == 9 {
+                return Ok(user_config);
+            }
+
+            // Config file version #10 below...
+
+            // Read theme name len.
+            let theme_name_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = theme_name_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading theme name len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let theme_name_len = theme_name_len.unwrap();
+
+            if theme_name_len > 0 {
+                // Read theme name.
+                let theme_name =
+                    UserConfig::read_string_from_file(&mut config_file, theme_name_len);
+                if let Err(msg) = theme_name {
+                    return Err(SilentError::ConfigError(format!(
+                        "{} (reading theme name) at [{}, {}]",
+                        msg,
+                        file!(),
+                        line!()
+                    )));
+                }
+                user_config.theme_name = theme_name.unwrap();
+            }
+
+            if config_version == 10 {
+                return Ok(user_config);
+            }
+
+            // Config file version #11 below...
+
+            // Read max received voice packets per second.
+            let max_voice_packets_per_sec = UserConfig::read_u32_from_file(&mut config_file);
+            if let Err(msg) = max_voice_packets_per_sec {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading max received voice packets per second) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.max_voice_packets_per_sec = max_voice_packets_per_sec.unwrap();
+
+            if config_version == 11 {
+                return Ok(user_config);
+            }
+
+            // Config file version #12 below...
+
+            // Read show inline images.
+            let show_inline_images = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = show_inline_images {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading show inline images) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.show_inline_images = show_inline_images.unwrap();
+
+            if config_version == 12 {
+                return Ok(user_config);
+            }
+
+            // Config file version #13 below...
+
+            // Read remember password.
+            let password_remember = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = password_remember {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading remember password) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.password_remember = password_remember.unwrap();
+
+            if config_version == 13 {
+                return Ok(user_config);
+            }
+
+            // Config file version #14 below...
+
+            // Read AEC enabled.
+            let aec_enabled = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = aec_enabled {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading AEC enabled) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.aec_enabled = aec_enabled.unwrap();
+
+            // Read AEC delay.
+            let aec_delay_ms = UserConfig::read_u32_from_file(&mut config_file);
+            if let Err(msg) = aec_delay_ms {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading AEC delay) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.aec_delay_ms = aec_delay_ms.unwrap();
+
+            if config_version == 14 {
+                return Ok(user_config);
+            }
+
+            // Config file version #15 below...
+
+            // Read log level len.
+            let log_level_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = log_level_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading log level len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+
+            // Read log level.
+            let log_level =
+                UserConfig::read_string_from_file(&mut config_file, log_level_len.unwrap());
+            if let Err(msg) = log_level {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading log level) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.log_level = log_level.unwrap();
+
+            if config_version == 15 {
+                return Ok(user_config);
+            }
+
+            // Config file version #16 below...
+
+            // Read spatial audio enabled.
+            let spatial_audio_enabled = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = spatial_audio_enabled {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading spatial audio enabled) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.spatial_audio_enabled = spatial_audio_enabled.unwrap();
+
+            if config_version == 16 {
+                return Ok(user_config);
+            }
+
+            // Config file version #17 below...
+
+            // Read auto-away message len.
+            let auto_away_message_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = auto_away_message_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading auto-away message len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let auto_away_message_len = auto_away_message_len.unwrap();
+
+            // empty message means "don't send anything"
+            user_config.auto_away_message = String::new();
+            if auto_away_message_len > 0 {
+                // Read auto-away message.
+                let auto_away_message =
+                    UserConfig::read_string_from_file(&mut config_file, auto_away_message_len);
+                if let Err(msg) = auto_away_message {
+                    return Err(SilentError::ConfigError(format!(
+                        "{} (reading auto-away message) at [{}, {}]",
+                        msg,
+                        file!(),
+                        line!()
+                    )));
+                }
+                user_config.auto_away_message = auto_away_message.unwrap();
+            }
+
+            if config_version == 17 {
+                return Ok(user_config);
+            }
+
+            // Config file version #18 below...
+
+            // Read push-to-talk press sound len.
+            let ptt_press_sound_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = ptt_press_sound_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading push-to-talk press sound len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+
+            // Read push-to-talk press sound.
+            let ptt_press_sound =
+                UserConfig::read_string_from_file(&mut config_file, ptt_press_sound_len.unwrap());
+            if let Err(msg) = ptt_press_sound {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading push-to-talk press sound) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.ptt_press_sound = ptt_press_sound.unwrap();
+
+            // Read push-to-talk release sound len.
+            let ptt_release_sound_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = ptt_release_sound_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading push-to-talk release sound len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+
+            // Read push-to-talk release sound.
+            let ptt_release_sound =
+                UserConfig::read_string_from_file(&mut config_file, ptt_release_sound_len.unwrap());
+            if let Err(msg) = ptt_release_sound {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading push-to-talk release sound) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.ptt_release_sound = ptt_release_sound.unwrap();
+
+            if config_version == 18 {
+                return Ok(user_config);
+            }
+
+            // Config file version #19 below...
+
+            // Read accessibility mode.
+            let accessibility_mode = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = accessibility_mode {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading accessibility mode) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.accessibility_mode = accessibility_mode.unwrap();
+
+            if config_version == 19 {
+                return Ok(user_config);
+            }
+
+            // Config file version #20 below...
+
+            // Read UDP receive buffer size.
+            let udp_recv_buffer_kb = UserConfig::read_u32_from_file(&mut config_file);
+            if let Err(msg) = udp_recv_buffer_kb {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading UDP receive buffer size) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.udp_recv_buffer_kb = udp_recv_buffer_kb.unwrap();
+
+            // Read UDP send buffer size.
+            let udp_send_buffer_kb = UserConfig::read_u32_from_file(&mut config_file);
+            if let Err(msg) = udp_send_buffer_kb {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading UDP send buffer size) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.udp_send_buffer_kb = udp_send_buffer_kb.unwrap();
+
+            if config_version == 20 {
+                return Ok(user_config);
+            }
+
+            // Config file version #21 below...
+
+            // Read microphone channels.
+            let microphone_channels = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = microphone_channels {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading microphone channels) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.microphone_channels = microphone_channels.unwrap();
+
+            if config_version == 21 {
+                return Ok(user_config);
+            }
+
+            // Config file version #22 below...
+
+            // Read advanced settings expanded.
+            let advanced_settings_expanded = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = advanced_settings_expanded {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading advanced settings expanded) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.advanced_settings_expanded = advanced_settings_expanded.unwrap();
+
+            if config_version == 22 {
+                return Ok(user_config);
+            }
+
+            // Config file version #23 below...
+
+            // Read compact user list.
+            let compact_user_list = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = compact_user_list {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading compact user list) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.compact_user_list = compact_user_list.unwrap();
+
+            if config_version == 23 {
+                return Ok(user_config);
+            }
+
+            // Config file version #24 below...
+
+            // Read silence suppression enabled.
+            let silence_suppression_enabled = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = silence_suppression_enabled {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading silence suppression enabled) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.silence_suppression_enabled = silence_suppression_enabled.unwrap();
+
+            // Read silence threshold.
+            let silence_threshold = UserConfig::read_f32_from_file(&mut config_file);
+            if let Err(msg) = silence_threshold {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading silence threshold) at [{}, {}]",
+                    msg,
+                    file!(),
                     line!()
-                ));
+                )));
             }
-            user_config.server = server.unwrap();
+            user_config.silence_threshold = silence_threshold.unwrap();
 
-            // Read server port.
-            let server_port = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = server_port {
-                return Err(format!(
-                    "{} (reading server port) at [{}, {}]",
+            if config_version == 24 {
+                return Ok(user_config);
+            }
+
+            // Config file version #25 below...
+
+            // Read UDP health check enabled.
+            let udp_health_check_enabled = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = udp_health_check_enabled {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading UDP health check enabled) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            user_config.server_port = server_port.unwrap();
+            user_config.udp_health_check_enabled = udp_health_check_enabled.unwrap();
 
-            // Read password len.
-            let password_len = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = password_len {
-                return Err(format!(
-                    "{} (reading password len) at [{}, {}]",
+            // Read UDP health check timeout.
+            let udp_health_check_timeout_sec = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = udp_health_check_timeout_sec {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading UDP health check timeout) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            let password_len = password_len.unwrap();
+            user_config.udp_health_check_timeout_sec = udp_health_check_timeout_sec.unwrap();
 
-            if password_len > 0 {
-                // Read password.
-                let password = UserConfig::read_string_from_file(&mut config_file, password_len);
-                if let Err(msg) = password {
-                    return Err(format!(
-                        "{} (reading password) at [{}, {}]",
+            if config_version == 25 {
+                return Ok(user_config);
+            }
+
+            // Config file version #26 below...
+
+            // Read preferred network interface len.
+            let interface_name_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = interface_name_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading preferred network interface len) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            let interface_name_len = interface_name_len.unwrap();
+
+            if interface_name_len > 0 {
+                // Read preferred network interface.
+                let interface_name =
+                    UserConfig::read_string_from_file(&mut config_file, interface_name_len);
+                if let Err(msg) = interface_name {
+                    return Err(SilentError::ConfigError(format!(
+                        "{} (reading preferred network interface) at [{}, {}]",
                         msg,
                         file!(),
                         line!()
-                    ));
+                    )));
                 }
-                user_config.server_password = password.unwrap();
+                user_config.preferred_network_interface = interface_name.unwrap();
             }
 
-            // Read ui scaling.
-            let ui_scaling = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = ui_scaling {
-                return Err(format!(
-                    "{} (reading ui scaling) at [{}, {}]",
+            if config_version == 26 {
+                return Ok(user_config);
+            }
+
+            // Config file version #27 below...
+
+            // Read connect on launch.
+            let connect_on_launch = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = connect_on_launch {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading connect on launch) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            user_config.ui_scaling = ui_scaling.unwrap();
+            user_config.connect_on_launch = connect_on_launch.unwrap();
 
-            // Read push-to-talk button.
-            let mut buf = vec![0u8; std::mem::size_of::<u64>()];
-            if let Err(e) = config_file.read(&mut buf) {
-                return Err(format!(
-                    "File::read() failed, error: can't read (error: {}) at [{}, {}]",
-                    e,
+            if config_version == 27 {
+                return Ok(user_config);
+            }
+
+            // Config file version #28 below...
+
+            // Read lobby room change cooldown.
+            let room_change_lobby_cooldown_sec = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = room_change_lobby_cooldown_sec {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading lobby room change cooldown) at [{}, {}]",
+                    msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            let key_code = bincode::deserialize::<u64>(&buf).unwrap();
-            match FromPrimitive::from_u64(key_code) {
-                Some(v) => user_config.push_to_talk_button = v,
+            user_config.room_change_lobby_cooldown_sec = room_change_lobby_cooldown_sec.unwrap();
+
+            if config_version == 28 {
+                return Ok(user_config);
+            }
+
+            // Config file version #29 below...
+
+            // Read microphone boost.
+            let microphone_boost_db = UserConfig::read_f32_from_file(&mut config_file);
+            if let Err(msg) = microphone_boost_db {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading microphone boost) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.microphone_boost_db = microphone_boost_db.unwrap();
+
+            if config_version == 29 {
+                return Ok(user_config);
+            }
+
+            // Config file version #30 below...
+
+            // Read chat max messages.
+            let chat_max_messages = UserConfig::read_u32_from_file(&mut config_file);
+            if let Err(msg) = chat_max_messages {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading chat max messages) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.chat_max_messages = chat_max_messages.unwrap() as usize;
+
+            if config_version == 30 {
+                return Ok(user_config);
+            }
+
+            // Config file version #31 below...
+
+            // Read push-to-talk mode.
+            let push_to_talk_mode = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = push_to_talk_mode {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading push-to-talk mode) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            match FromPrimitive::from_u8(push_to_talk_mode.unwrap()) {
+                Some(v) => user_config.push_to_talk_mode = v,
                 None => {
-                    return Err(format!(
-                        "FromPrimitive::from_u64 failed at [{}, {}]",
+                    return Err(SilentError::ConfigError(format!(
+                        "FromPrimitive::from_u8 failed (reading push-to-talk mode) at [{}, {}]",
                         file!(),
                         line!()
-                    ));
+                    )));
                 }
             }
 
-            // Read master volume.
-            let master_volume = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = master_volume {
-                return Err(format!(
-                    "{} (reading master volume) at [{}, {}]",
+            // Read voice activation sensitivity.
+            let vad_sensitivity = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = vad_sensitivity {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading voice activation sensitivity) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            user_config.master_volume = master_volume.unwrap();
+            user_config.vad_sensitivity = vad_sensitivity.unwrap();
 
-            if config_version == 0 {
+            if config_version == 31 {
                 return Ok(user_config);
             }
 
-            // Config file version #1 below...
+            // Config file version #32 below...
 
-            // Read 'show message notification'.
-            let mut buf = vec![0u8; std::mem::size_of::<bool>()];
-            if let Err(e) = config_file.read(&mut buf) {
-                return Err(format!(
-                    "File::read() failed, error: can't read bool (error: {}) at [{}, {}]",
-                    e,
+            // Read voice codec.
+            let voice_codec = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = voice_codec {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading voice codec) at [{}, {}]",
+                    msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            if buf[0] == 1 {
-                user_config.show_message_notification = true;
-            } else {
-                user_config.show_message_notification = false;
+            match FromPrimitive::from_u8(voice_codec.unwrap()) {
+                Some(v) => user_config.voice_codec = v,
+                None => {
+                    return Err(SilentError::ConfigError(format!(
+                        "FromPrimitive::from_u8 failed (reading voice codec) at [{}, {}]",
+                        file!(),
+                        line!()
+                    )));
+                }
             }
 
-            if config_version == 1 {
+            if config_version == 32 {
                 return Ok(user_config);
             }
 
-            // Config file version #2 below...
+            // Config file version #33 below...
 
-            // Read locale
-            let mut buf = vec![0u8; 2];
-            if let Err(e) = config_file.read(&mut buf) {
-                return Err(format!(
-                    "File::read() failed, error: can't read locale (error: {}) at [{}, {}]",
-                    e,
+            // Read audio input device len.
+            let audio_input_device_len = UserConfig::read_u16_from_file(&mut config_file);
+            if let Err(msg) = audio_input_device_len {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading audio input device len) at [{}, {}]",
+                    msg,
                     file!(),
                     line!()
-                ));
+                )));
+            }
+            let audio_input_device_len = audio_input_device_len.unwrap();
+
+            if audio_input_device_len > 0 {
+                // Read audio input device.
+                let audio_input_device =
+                    UserConfig::read_string_from_file(&mut config_file, audio_input_device_len);
+                if let Err(msg) = audio_input_device {
+                    return Err(SilentError::ConfigError(format!(
+                        "{} (reading audio input device) at [{}, {}]",
+                        msg,
+                        file!(),
+                        line!()
+                    )));
+                }
+                user_config.audio_input_device = audio_input_device.unwrap();
             }
-            user_config.locale = String::from_utf8(buf).unwrap();
 
-            // Read microphone volume
-            let microphone_volume = UserConfig::read_u16_from_file(&mut config_file);
-            if let Err(msg) = microphone_volume {
-                return Err(format!(
-                    "{} (reading microphone volume) at [{}, {}]",
+            if config_version == 33 {
+                return Ok(user_config);
+            }
+
+            // Config file version #34 below...
+
+            // Read auto record.
+            let auto_record = UserConfig::read_bool_from_file(&mut config_file);
+            if let Err(msg) = auto_record {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading auto record) at [{}, {}]",
                     msg,
                     file!(),
                     line!()
-                ));
+                )));
             }
-            user_config.microphone_volume = microphone_volume.unwrap();
+            user_config.auto_record = auto_record.unwrap();
+
+            if config_version == 34 {
+                return Ok(user_config);
+            }
+
+            // Config file version #35 below...
+
+            // Read audio play buffer chunks.
+            let audio_play_buffer_chunks = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = audio_play_buffer_chunks {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading audio play buffer chunks) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.audio_play_buffer_chunks = audio_play_buffer_chunks.unwrap();
+
+            // Read audio record buffer chunks.
+            let audio_record_buffer_chunks = UserConfig::read_u8_from_file(&mut config_file);
+            if let Err(msg) = audio_record_buffer_chunks {
+                return Err(SilentError::ConfigError(format!(
+                    "{} (reading audio record buffer chunks) at [{}, {}]",
+                    msg,
+                    file!(),
+                    line!()
+                )));
+            }
+            user_config.audio_record_buffer_chunks = audio_record_buffer_chunks.unwrap();
 
             //
             // please use 'config_version' variable to handle old config versions...
@@ -566,17 +2615,32 @@ impl UserConfig {
         }
     }
 
-    fn get_config_file_path() -> Result<String, String> {
+    fn get_config_file_path() -> Result<String, SilentError> {
+        let config_dir = UserConfig::get_config_directory();
+        if let Err(e) = config_dir {
+            return Err(SilentError::ConfigError(format!(
+                "{} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+
+        Ok(config_dir.unwrap() + CLIENT_CONFIG_FILE_NAME)
+    }
+
+    // Returns path to the directory with the config file (ends with a path separator).
+    pub fn get_config_directory() -> Result<String, SilentError> {
         let mut _config_dir = String::new();
         #[cfg(target_os = "windows")]
         {
             let user_dirs = UserDirs::new();
             if user_dirs.is_none() {
-                return Err(format!(
+                return Err(SilentError::ConfigError(format!(
                     "UserDirs::new() failed, error: can't read user dirs at [{}, {}]",
                     file!(),
                     line!(),
-                ));
+                )));
             }
             let user_dirs = user_dirs.unwrap();
             _config_dir = String::from(user_dirs.document_dir.to_str().unwrap());
@@ -608,77 +2672,559 @@ impl UserConfig {
             _config_dir += "/";
         }
 
-        _config_dir += CLIENT_CONFIG_FILE_NAME;
-
         Ok(_config_dir)
     }
 
-    fn read_u16_from_file(file: &mut File) -> Result<u16, String> {
+    fn read_u16_from_file(file: &mut File) -> Result<u16, SilentError> {
         let mut buf = vec![0u8; std::mem::size_of::<u16>()];
-        if let Err(e) = file.read(&mut buf) {
-            return Err(format!(
-                "File::read() failed, error: can't read u16 (error: {}) at [{}, {}]",
+        if let Err(e) = file.read_exact(&mut buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::read_exact() failed, error: can't read u16 (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         Ok(bincode::deserialize::<u16>(&buf).unwrap())
     }
 
-    fn read_string_from_file(file: &mut File, string_len: u16) -> Result<String, String> {
+    fn read_u8_from_file(file: &mut File) -> Result<u8, SilentError> {
+        let mut buf = vec![0u8; std::mem::size_of::<u8>()];
+        if let Err(e) = file.read_exact(&mut buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::read_exact() failed, error: can't read u8 (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(buf[0])
+    }
+
+    fn read_bool_from_file(file: &mut File) -> Result<bool, SilentError> {
+        let mut buf = vec![0u8; std::mem::size_of::<bool>()];
+        if let Err(e) = file.read_exact(&mut buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::read_exact() failed, error: can't read bool (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(buf[0] == 1)
+    }
+
+    fn read_f32_from_file(file: &mut File) -> Result<f32, SilentError> {
+        let mut buf = vec![0u8; std::mem::size_of::<f32>()];
+        if let Err(e) = file.read_exact(&mut buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::read_exact() failed, error: can't read f32 (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(bincode::deserialize::<f32>(&buf).unwrap())
+    }
+
+    fn read_string_from_file(file: &mut File, string_len: u16) -> Result<String, SilentError> {
         let mut buf = vec![0u8; string_len as usize];
-        if let Err(e) = file.read(&mut buf) {
-            return Err(format!(
-                "File::read() failed, error: can't read string from config file (error: {}) at [{}, {}]",
+        if let Err(e) = file.read_exact(&mut buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::read_exact() failed, error: can't read string from config file (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         let string = String::from_utf8(buf);
         if let Err(e) = string {
-            return Err(format!("String::from_utf8() failed, error: can't convert raw bytes (error: {}) at [{}, {}]",
+            return Err(SilentError::ConfigError(format!("String::from_utf8() failed, error: can't convert raw bytes (error: {}) at [{}, {}]",
             e,
             file!(),
-            line!()));
+            line!())));
         }
         Ok(string.unwrap())
     }
 
-    fn write_u16_to_file(file: &mut File, val: u16) -> Result<(), String> {
+    fn write_u16_to_file(file: &mut File, val: u16) -> Result<(), SilentError> {
         let buf = bincode::serialize(&val);
         if let Err(e) = buf {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "u16::encode::<u16>() failed, error: {} at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         let buf = buf.unwrap();
 
         if let Err(e) = file.write(&buf) {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::write() failed, error: can't write u16 to config file (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
+        }
+        Ok(())
+    }
+
+    fn write_u8_to_file(file: &mut File, val: u8) -> Result<(), SilentError> {
+        if let Err(e) = file.write(&[val]) {
+            return Err(SilentError::ConfigError(format!(
+                "File::write() failed, error: can't write u8 to config file (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(())
+    }
+
+    fn write_bool_to_file(file: &mut File, val: bool) -> Result<(), SilentError> {
+        let mut buf = vec![0u8; std::mem::size_of::<bool>()];
+        if val {
+            buf[0] = 1;
+        }
+        if let Err(e) = file.write(&buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::write() failed, error: can't write bool to config file (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
         }
         Ok(())
     }
 
-    fn write_string_to_file(file: &mut File, string: &str) -> Result<(), String> {
+    fn read_u32_from_file(file: &mut File) -> Result<u32, SilentError> {
+        let mut buf = vec![0u8; std::mem::size_of::<u32>()];
+        if let Err(e) = file.read_exact(&mut buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::read_exact() failed, error: can't read u32 (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(bincode::deserialize::<u32>(&buf).unwrap())
+    }
+    fn write_f32_to_file(file: &mut File, val: f32) -> Result<(), SilentError> {
+        let buf = bincode::serialize(&val);
+        if let Err(e) = buf {
+            return Err(SilentError::ConfigError(format!(
+                "f32::encode::<f32>() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let buf = buf.unwrap();
+
+        if let Err(e) = file.write(&buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::write() failed, error: can't write f32 to config file (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(())
+    }
+
+    fn write_u32_to_file(file: &mut File, val: u32) -> Result<(), SilentError> {
+        let buf = bincode::serialize(&val);
+        if let Err(e) = buf {
+            return Err(SilentError::ConfigError(format!(
+                "u32::encode::<u32>() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let buf = buf.unwrap();
+
+        if let Err(e) = file.write(&buf) {
+            return Err(SilentError::ConfigError(format!(
+                "File::write() failed, error: can't write u32 to config file (error: {}) at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        Ok(())
+    }
+    fn write_string_to_file(file: &mut File, string: &str) -> Result<(), SilentError> {
         let buf = string.as_bytes();
         if let Err(e) = file.write(&buf) {
-            return Err(format!(
+            return Err(SilentError::ConfigError(format!(
                 "File::write() failed, error: can't write string to config file (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            ));
+            )));
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_defaults_keeps_connection_fields() {
+        let mut config = UserConfig::empty();
+        config.username = String::from("user");
+        config.server = String::from("example.com");
+        config.server_port = 1234;
+        config.server_password = String::from("password");
+        config.password_remember = false;
+
+        config.master_volume = 10;
+        config.microphone_volume = 20;
+        config.microphone_boost_db = MAX_MICROPHONE_BOOST_DB;
+        config.push_to_talk_button = KeyCode::KA;
+        config.show_message_notification = false;
+        config.locale = if config.locale == "ru" {
+            String::from("en")
+        } else {
+            String::from("ru")
+        };
+        config.agc_enabled = true;
+        config.theme_name = String::from("dark");
+        config.compact_user_list = true;
+        config.chat_max_messages = MAX_CHAT_MAX_MESSAGES;
+
+        config.restore_defaults();
+
+        assert_eq!(config.username, "user");
+        assert_eq!(config.server, "example.com");
+        assert_eq!(config.server_port, 1234);
+        assert_eq!(config.server_password, "password");
+        assert!(!config.password_remember);
+
+        let defaults = UserConfig::empty();
+        assert_eq!(config.master_volume, defaults.master_volume);
+        assert_eq!(config.microphone_volume, defaults.microphone_volume);
+        assert_eq!(config.microphone_boost_db, defaults.microphone_boost_db);
+        assert_eq!(
+            config.push_to_talk_button.to_u64(),
+            defaults.push_to_talk_button.to_u64()
+        );
+        assert_eq!(
+            config.show_message_notification,
+            defaults.show_message_notification
+        );
+        assert_eq!(config.locale, defaults.locale);
+        assert_eq!(config.agc_enabled, defaults.agc_enabled);
+        assert_eq!(config.theme_name, defaults.theme_name);
+        assert_eq!(config.compact_user_list, defaults.compact_user_list);
+        assert_eq!(config.chat_max_messages, defaults.chat_max_messages);
+        assert!(config.validate().is_ok());
+    }
+
+    // Sets an invalid value to one of the settings.
+    type InvalidateFn = fn(&mut UserConfig);
+
+    fn get_invalid_field_cases() -> Vec<(ConfigField, InvalidateFn, String)> {
+        vec![
+            (
+                ConfigField::Username,
+                |config| config.username = "a".repeat(MAX_USERNAME_SIZE + 1),
+                format!(
+                    "username length ({}) exceeds the maximum ({})",
+                    MAX_USERNAME_SIZE + 1,
+                    MAX_USERNAME_SIZE
+                ),
+            ),
+            (
+                ConfigField::MasterVolume,
+                |config| config.master_volume = 101,
+                String::from("master volume (101) is not in range 0-100"),
+            ),
+            (
+                ConfigField::MicrophoneVolume,
+                |config| config.microphone_volume = 101,
+                String::from("microphone volume (101) is not in range 0-100"),
+            ),
+            (
+                ConfigField::MicrophoneBoost,
+                |config| config.microphone_boost_db = MAX_MICROPHONE_BOOST_DB + 1.0,
+                format!(
+                    "microphone boost ({} dB) is not in range {}-{} dB",
+                    MAX_MICROPHONE_BOOST_DB + 1.0,
+                    MIN_MICROPHONE_BOOST_DB,
+                    MAX_MICROPHONE_BOOST_DB
+                ),
+            ),
+            (
+                ConfigField::ServerPort,
+                |config| config.server_port = 0,
+                String::from("server port is not in range 1-65535"),
+            ),
+            (
+                ConfigField::Locale,
+                |config| config.locale = String::from("eng"),
+                String::from("locale 'eng' is not a 2-character language code"),
+            ),
+            (
+                ConfigField::MessageCooldown,
+                |config| config.message_cooldown_sec = MAX_MESSAGE_COOLDOWN_SEC + 1,
+                format!(
+                    "message cooldown ({}) is not in range 0-{}",
+                    MAX_MESSAGE_COOLDOWN_SEC + 1,
+                    MAX_MESSAGE_COOLDOWN_SEC
+                ),
+            ),
+            (
+                ConfigField::RoomChangeSameTierCooldown,
+                |config| {
+                    config.room_change_same_tier_cooldown_sec = MAX_ROOM_CHANGE_COOLDOWN_SEC + 1
+                },
+                format!(
+                    "room change cooldown ({}) is not in range 0-{}",
+                    MAX_ROOM_CHANGE_COOLDOWN_SEC + 1,
+                    MAX_ROOM_CHANGE_COOLDOWN_SEC
+                ),
+            ),
+            (
+                ConfigField::RoomChangeLobbyCooldown,
+                |config| config.room_change_lobby_cooldown_sec = MAX_ROOM_CHANGE_COOLDOWN_SEC + 1,
+                format!(
+                    "lobby room change cooldown ({}) is not in range 0-{}",
+                    MAX_ROOM_CHANGE_COOLDOWN_SEC + 1,
+                    MAX_ROOM_CHANGE_COOLDOWN_SEC
+                ),
+            ),
+            (
+                ConfigField::IdleTimeout,
+                |config| config.idle_timeout_min = MAX_IDLE_TIMEOUT_MIN + 1,
+                format!(
+                    "idle timeout ({} min) is not in range 0-{} min",
+                    MAX_IDLE_TIMEOUT_MIN + 1,
+                    MAX_IDLE_TIMEOUT_MIN
+                ),
+            ),
+            (
+                ConfigField::UdpHealthCheckTimeout,
+                |config| config.udp_health_check_timeout_sec = MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC - 1,
+                format!(
+                    "UDP health check timeout ({} sec) is not in range {}-{} sec",
+                    MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC - 1,
+                    MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC,
+                    MAX_UDP_HEALTH_CHECK_TIMEOUT_SEC
+                ),
+            ),
+            (
+                ConfigField::PttReleaseDelay,
+                |config| config.ptt_release_delay_ms = MAX_PTT_RELEASE_DELAY_MS + 1,
+                format!(
+                    "push-to-talk release delay ({} ms) is not in range 0-{} ms",
+                    MAX_PTT_RELEASE_DELAY_MS + 1,
+                    MAX_PTT_RELEASE_DELAY_MS
+                ),
+            ),
+            (
+                ConfigField::AecDelay,
+                |config| config.aec_delay_ms = MAX_AEC_DELAY_MS + 1,
+                format!(
+                    "echo cancellation delay ({} ms) is not in range 0-{} ms",
+                    MAX_AEC_DELAY_MS + 1,
+                    MAX_AEC_DELAY_MS
+                ),
+            ),
+            (
+                ConfigField::AutoAwayMessage,
+                |config| config.auto_away_message = "a".repeat(MAX_MESSAGE_SIZE + 1),
+                format!(
+                    "auto-away message is longer than {} characters",
+                    MAX_MESSAGE_SIZE
+                ),
+            ),
+            (
+                ConfigField::LogLevel,
+                |config| config.log_level = String::from("verbose"),
+                format!(
+                    "log level 'verbose' is not one of: {}",
+                    LOG_LEVELS.join(", ")
+                ),
+            ),
+            (
+                ConfigField::PttPressSound,
+                |config| config.ptt_press_sound = String::from("missing_press.wav"),
+                String::from("push-to-talk press sound file 'missing_press.wav' does not exist"),
+            ),
+            (
+                ConfigField::PttReleaseSound,
+                |config| config.ptt_release_sound = String::from("missing_release.wav"),
+                String::from(
+                    "push-to-talk release sound file 'missing_release.wav' does not exist",
+                ),
+            ),
+            (
+                ConfigField::MicrophoneChannels,
+                |config| config.microphone_channels = 3,
+                String::from("microphone channels (3) should be 1 or 2"),
+            ),
+            (
+                ConfigField::UdpRecvBuffer,
+                |config| config.udp_recv_buffer_kb = MAX_UDP_SOCKET_BUFFER_KB + 1,
+                format!(
+                    "UDP receive buffer size ({} KB) is not in range {}-{} KB",
+                    MAX_UDP_SOCKET_BUFFER_KB + 1,
+                    MIN_UDP_SOCKET_BUFFER_KB,
+                    MAX_UDP_SOCKET_BUFFER_KB
+                ),
+            ),
+            (
+                ConfigField::UdpSendBuffer,
+                |config| config.udp_send_buffer_kb = MIN_UDP_SOCKET_BUFFER_KB - 1,
+                format!(
+                    "UDP send buffer size ({} KB) is not in range {}-{} KB",
+                    MIN_UDP_SOCKET_BUFFER_KB - 1,
+                    MIN_UDP_SOCKET_BUFFER_KB,
+                    MAX_UDP_SOCKET_BUFFER_KB
+                ),
+            ),
+            (
+                ConfigField::ChatMaxMessages,
+                |config| config.chat_max_messages = MAX_CHAT_MAX_MESSAGES + 1,
+                format!(
+                    "chat max messages ({}) is not in range {}-{}",
+                    MAX_CHAT_MAX_MESSAGES + 1,
+                    MIN_CHAT_MAX_MESSAGES,
+                    MAX_CHAT_MAX_MESSAGES
+                ),
+            ),
+            (
+                ConfigField::VadSensitivity,
+                |config| config.vad_sensitivity = VAD_MAX_SENSITIVITY + 1,
+                format!(
+                    "voice activation sensitivity ({}) is not in range 0-{}",
+                    VAD_MAX_SENSITIVITY + 1,
+                    VAD_MAX_SENSITIVITY
+                ),
+            ),
+            (
+                ConfigField::AudioPlayBufferChunks,
+                |config| config.audio_play_buffer_chunks = MIN_AUDIO_PLAY_BUFFER_CHUNKS - 1,
+                format!(
+                    "audio play buffer ({} chunks) is not in range {}-{}",
+                    MIN_AUDIO_PLAY_BUFFER_CHUNKS - 1,
+                    MIN_AUDIO_PLAY_BUFFER_CHUNKS,
+                    MAX_AUDIO_PLAY_BUFFER_CHUNKS
+                ),
+            ),
+            (
+                ConfigField::AudioRecordBufferChunks,
+                |config| config.audio_record_buffer_chunks = MAX_AUDIO_RECORD_BUFFER_CHUNKS + 1,
+                format!(
+                    "audio record buffer ({} chunks) is not in range 0-{}",
+                    MAX_AUDIO_RECORD_BUFFER_CHUNKS + 1,
+                    MAX_AUDIO_RECORD_BUFFER_CHUNKS
+                ),
+            ),
+        ]
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        // empty username is allowed (the user did not connect yet)
+        let config = UserConfig::empty();
+        assert!(config.username.is_empty());
+        assert!(config.validate_fields().is_empty());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn username_length_is_counted_in_characters() {
+        let mut config = UserConfig::empty();
+
+        config.username = "ё".repeat(MAX_USERNAME_SIZE);
+        assert!(config.validate().is_ok());
+
+        config.username.push('ё');
+        let errors = config.validate_fields();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, ConfigField::Username);
+    }
+
+    #[test]
+    fn existing_or_empty_sound_paths_are_valid() {
+        let mut config = UserConfig::empty();
+
+        config.ptt_press_sound = String::new();
+        config.ptt_release_sound = String::new();
+        assert!(config.validate().is_ok());
+
+        let sound_path = crate::tests::temp_file_path("config_service_sound.wav");
+        std::fs::write(&sound_path, [0u8; 4]).unwrap();
+        config.ptt_press_sound = sound_path.clone();
+        config.ptt_release_sound = sound_path.clone();
+        assert!(config.validate().is_ok());
+
+        std::fs::remove_file(&sound_path).unwrap();
+        assert_eq!(
+            config
+                .validate_fields()
+                .into_iter()
+                .map(|(field, _)| field)
+                .collect::<Vec<ConfigField>>(),
+            vec![ConfigField::PttPressSound, ConfigField::PttReleaseSound]
+        );
+    }
+
+    #[test]
+    fn each_invalid_field_is_reported() {
+        for (field, invalidate, message) in get_invalid_field_cases() {
+            let mut config = UserConfig::empty();
+            invalidate(&mut config);
+
+            assert_eq!(config.validate_fields(), vec![(field, message.clone())]);
+            assert_eq!(config.validate(), Err(vec![message]));
+        }
+    }
+
+    #[test]
+    fn all_violations_are_returned() {
+        let cases = get_invalid_field_cases();
+
+        let mut config = UserConfig::empty();
+        for (_, invalidate, _) in cases.iter() {
+            invalidate(&mut config);
+        }
+
+        let expected: Vec<(ConfigField, String)> = cases
+            .into_iter()
+            .map(|(field, _, message)| (field, message))
+            .collect();
+        assert_eq!(config.validate_fields(), expected);
+        assert_eq!(
+            config.validate(),
+            Err(expected.into_iter().map(|(_, message)| message).collect())
+        );
+    }
+
+    #[test]
+    fn every_pair_of_invalid_fields_is_reported() {
+        let cases = get_invalid_field_cases();
+
+        for (i, (first_field, first_invalidate, first_message)) in cases.iter().enumerate() {
+            for (second_field, second_invalidate, second_message) in cases.iter().skip(i + 1) {
+                let mut config = UserConfig::empty();
+                first_invalidate(&mut config);
+                second_invalidate(&mut config);
+
+                assert_eq!(
+                    config.validate_fields(),
+                    vec![
+                        (*first_field, first_message.clone()),
+                        (*second_field, second_message.clone())
+                    ]
+                );
+            }
+        }
+    }
+}
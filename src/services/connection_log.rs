@@ -0,0 +1,228 @@
+// External.
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Std.
+use std::fs::*;
+use std::path::Path;
+
+// Custom.
+use crate::global_params::*;
+use crate::services::config_service::UserConfig;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConnectionEvent {
+    pub timestamp: DateTime<Local>, // when disconnected
+    pub server: String,
+    pub port: u16,
+    pub connected_for_sec: u64,
+    pub disconnect_reason: String,
+}
+
+// Human-readable (JSON) log of the past connections,
+// stored next to the config file.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct ConnectionLog {
+    pub events: Vec<ConnectionEvent>,
+}
+
+impl ConnectionLog {
+    pub fn open() -> Result<ConnectionLog, String> {
+        let log_path = ConnectionLog::get_log_file_path();
+        if let Err(e) = log_path {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+
+        ConnectionLog::open_from_path(&log_path.unwrap())
+    }
+
+    // Returns empty log if the file does not exist.
+    pub fn open_from_path(log_path: &str) -> Result<ConnectionLog, String> {
+        if !Path::new(log_path).exists() {
+            return Ok(ConnectionLog::default());
+        }
+
+        let content = read_to_string(log_path);
+        if let Err(e) = content {
+            return Err(format!(
+                "read_to_string() failed, error: can't read connection log '{}' (error: {}) at [{}, {}]",
+                log_path,
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        let log = serde_json::from_str::<ConnectionLog>(&content.unwrap());
+        if let Err(e) = log {
+            return Err(format!(
+                "serde_json::from_str() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(log.unwrap())
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let log_path = ConnectionLog::get_log_file_path();
+        if let Err(e) = log_path {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+
+        self.save_to_path(&log_path.unwrap())
+    }
+
+    pub fn save_to_path(&self, log_path: &str) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self);
+        if let Err(e) = content {
+            return Err(format!(
+                "serde_json::to_string_pretty() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        if let Err(e) = write(log_path, content.unwrap()) {
+            return Err(format!(
+                "write() failed, error: can't write connection log '{}' (error: {}) at [{}, {}]",
+                log_path,
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Keeps only the last CONNECTION_LOG_MAX_ENTRIES events.
+    pub fn append(&mut self, event: ConnectionEvent) {
+        self.events.push(event);
+
+        if self.events.len() > CONNECTION_LOG_MAX_ENTRIES {
+            let to_remove = self.events.len() - CONNECTION_LOG_MAX_ENTRIES;
+            self.events.drain(..to_remove);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    // Opens the log, appends the event and saves the log.
+    pub fn append_to_file(event: ConnectionEvent) -> Result<(), String> {
+        let log = ConnectionLog::open();
+        if let Err(e) = log {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+        let mut log = log.unwrap();
+
+        log.append(event);
+
+        log.save()
+    }
+
+    fn get_log_file_path() -> Result<String, String> {
+        if cfg!(test) {
+            // tests that disconnect (see 'tests::integration') must not change the user's log
+            let path = std::env::temp_dir().join(format!(
+                "silent_{}_{}",
+                std::process::id(),
+                CONNECTION_LOG_FILE_NAME
+            ));
+            return Ok(String::from(path.to_str().unwrap()));
+        }
+
+        let config_dir = UserConfig::get_config_directory();
+        if let Err(e) = config_dir {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+
+        Ok(config_dir.unwrap() + CONNECTION_LOG_FILE_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_file_path;
+
+    fn event(port: u16) -> ConnectionEvent {
+        ConnectionEvent {
+            timestamp: Local::now(),
+            server: String::from("сервер.example.com"),
+            port,
+            connected_for_sec: 3600,
+            disconnect_reason: String::from("connection lost"),
+        }
+    }
+
+    #[test]
+    fn append_keeps_order() {
+        let mut log = ConnectionLog::default();
+
+        log.append(event(1));
+        log.append(event(2));
+
+        let ports: Vec<u16> = log.events.iter().map(|event| event.port).collect();
+        assert_eq!(ports, vec![1, 2]);
+    }
+
+    #[test]
+    fn log_is_rotated_at_max_entries() {
+        let mut log = ConnectionLog::default();
+
+        for port in 0..CONNECTION_LOG_MAX_ENTRIES as u16 {
+            log.append(event(port));
+        }
+        assert_eq!(log.events.len(), CONNECTION_LOG_MAX_ENTRIES);
+        assert_eq!(log.events[0].port, 0);
+
+        // the oldest event is removed
+        log.append(event(100));
+        assert_eq!(log.events.len(), CONNECTION_LOG_MAX_ENTRIES);
+        assert_eq!(log.events[0].port, 1);
+        assert_eq!(log.events.last().unwrap().port, 100);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let path = temp_file_path("connection_log_roundtrip.json");
+        let mut log = ConnectionLog::default();
+        log.append(event(51337));
+        log.append(event(u16::MAX));
+
+        log.save_to_path(&path).unwrap();
+        let loaded = ConnectionLog::open_from_path(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, log);
+    }
+
+    #[test]
+    fn missing_file_is_empty_log() {
+        let path = temp_file_path("connection_log_missing.json");
+
+        assert_eq!(
+            ConnectionLog::open_from_path(&path).unwrap(),
+            ConnectionLog::default()
+        );
+    }
+
+    #[test]
+    fn invalid_json_is_error() {
+        let path = temp_file_path("connection_log_invalid.json");
+        std::fs::write(&path, "{\"events\": [").unwrap();
+
+        let result = ConnectionLog::open_from_path(&path);
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,62 @@
+// External.
+use druid::{ExtEventError, ExtEventSink, Selector, Target};
+
+// Std.
+#[cfg(test)]
+use std::any::Any;
+#[cfg(test)]
+use std::sync::mpsc;
+
+// Command that was submitted to 'EventSink::Channel'.
+#[cfg(test)]
+pub struct SubmittedCommand {
+    pub selector: String,
+    pub payload: Box<dyn Any + Send>,
+}
+
+#[cfg(test)]
+impl SubmittedCommand {
+    /// Returns the payload if the command was submitted with this selector.
+    pub fn get<T: Any>(&self, selector: Selector<T>) -> Option<&T> {
+        if self.selector != selector.to_string() {
+            return None;
+        }
+
+        self.payload.downcast_ref::<T>()
+    }
+}
+
+// Used by the services to submit commands to the UI,
+// tests receive the commands from a channel instead (there is no window).
+#[derive(Clone)]
+pub enum EventSink {
+    Ui(ExtEventSink),
+    #[cfg(test)]
+    Channel(mpsc::Sender<SubmittedCommand>),
+}
+
+impl EventSink {
+    pub fn submit_command<T: std::any::Any + Send>(
+        &self,
+        selector: Selector<T>,
+        payload: T,
+        target: Target,
+    ) -> Result<(), ExtEventError> {
+        match self {
+            EventSink::Ui(event_sink) => event_sink.submit_command(selector, payload, target),
+            #[cfg(test)]
+            EventSink::Channel(sender) => sender
+                .send(SubmittedCommand {
+                    selector: selector.to_string(),
+                    payload: Box::new(payload),
+                })
+                .map_err(|_| ExtEventError),
+        }
+    }
+}
+
+impl From<ExtEventSink> for EventSink {
+    fn from(event_sink: ExtEventSink) -> Self {
+        EventSink::Ui(event_sink)
+    }
+}
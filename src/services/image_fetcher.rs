@@ -0,0 +1,258 @@
+// External.
+use druid::{ExtEventSink, Selector, Target};
+
+// Std.
+use std::io::Read;
+use std::thread;
+
+// Custom.
+use crate::global_params::*;
+
+const IMAGE_URL_EXTENSIONS: [&str; 5] = [".png", ".jpg", ".jpeg", ".gif", ".webp"];
+
+// (message id, RGBA pixels, width, height)
+pub const CHAT_IMAGE_LOADED: Selector<(u64, Vec<u8>, u32, u32)> =
+    Selector::new("image_fetcher_chat_image_loaded");
+pub const CHAT_IMAGE_FAILED: Selector<(u64, ImageFetchError)> =
+    Selector::new("image_fetcher_chat_image_failed");
+
+pub enum ImageFetchError {
+    TooLarge,
+    Other(String),
+}
+
+pub struct ImageFetcher;
+
+impl ImageFetcher {
+    /// Returns the first URL in the message that points directly to an image.
+    pub fn find_image_url(message: &str) -> Option<String> {
+        message
+            .split_whitespace()
+            .find(|word| ImageFetcher::is_image_url(word))
+            .map(String::from)
+    }
+
+    pub fn is_image_url(word: &str) -> bool {
+        let lowercase = word.to_lowercase();
+        if !lowercase.starts_with("http://") && !lowercase.starts_with("https://") {
+            return false;
+        }
+
+        // ignore query and fragment ("image.png?size=big")
+        let path = lowercase.split(|c| c == '?' || c == '#').next().unwrap();
+
+        IMAGE_URL_EXTENSIONS
+            .iter()
+            .any(|extension| path.ends_with(extension))
+    }
+
+    /// Downloads and decodes the image in a separate thread,
+    /// the result is sent as CHAT_IMAGE_LOADED or CHAT_IMAGE_FAILED.
+    pub fn fetch(message_id: u64, url: String, event_sink: ExtEventSink) {
+        thread::spawn(move || match ImageFetcher::fetch_thumbnail(&url) {
+            Ok((pixels, width, height)) => {
+                event_sink
+                    .submit_command(
+                        CHAT_IMAGE_LOADED,
+                        (message_id, pixels, width, height),
+                        Target::Auto,
+                    )
+                    .expect("failed to submit CHAT_IMAGE_LOADED command");
+            }
+            Err(error) => {
+                event_sink
+                    .submit_command(CHAT_IMAGE_FAILED, (message_id, error), Target::Auto)
+                    .expect("failed to submit CHAT_IMAGE_FAILED command");
+            }
+        });
+    }
+
+    /// Blocking, returns RGBA pixels that fit into MAX_INLINE_IMAGE_DIMENSION.
+    fn fetch_thumbnail(url: &str) -> Result<(Vec<u8>, u32, u32), ImageFetchError> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(format!("silent-rs/{}", env!("CARGO_PKG_VERSION")))
+            .timeout(std::time::Duration::from_secs(IMAGE_FETCH_TIMEOUT_SEC))
+            .build();
+        if let Err(e) = client {
+            return Err(ImageFetchError::Other(format!(
+                "reqwest::blocking::Client::builder() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let client = client.unwrap();
+
+        let response = client.get(url).send();
+        if let Err(e) = response {
+            return Err(ImageFetchError::Other(format!(
+                "failed to send request to '{}', error: {} at [{}, {}]",
+                url,
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let response = response.unwrap();
+
+        if !response.status().is_success() {
+            return Err(ImageFetchError::Other(format!(
+                "'{}' returned status {} at [{}, {}]",
+                url,
+                response.status(),
+                file!(),
+                line!()
+            )));
+        }
+
+        if let Some(content_length) = response.content_length() {
+            if content_length > MAX_INLINE_IMAGE_SIZE {
+                return Err(ImageFetchError::TooLarge);
+            }
+        }
+
+        // Content-Length might be missing or wrong, so also limit the actual body.
+        let mut body = Vec::new();
+        if let Err(e) = response
+            .take(MAX_INLINE_IMAGE_SIZE + 1)
+            .read_to_end(&mut body)
+        {
+            return Err(ImageFetchError::Other(format!(
+                "failed to read response body, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        if body.len() as u64 > MAX_INLINE_IMAGE_SIZE {
+            return Err(ImageFetchError::TooLarge);
+        }
+
+        let image = image::load_from_memory(&body);
+        if let Err(e) = image {
+            return Err(ImageFetchError::Other(format!(
+                "image::load_from_memory() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )));
+        }
+        let image = image.unwrap();
+
+        // thumbnail() keeps the aspect ratio
+        let image = if image.width() > MAX_INLINE_IMAGE_DIMENSION
+            || image.height() > MAX_INLINE_IMAGE_DIMENSION
+        {
+            image.thumbnail(MAX_INLINE_IMAGE_DIMENSION, MAX_INLINE_IMAGE_DIMENSION)
+        } else {
+            image
+        };
+
+        let image = image.to_rgba8();
+        let (width, height) = (image.width(), image.height());
+
+        Ok((image.into_raw(), width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_extensions_are_detected() {
+        for url in [
+            "https://example.com/image.png",
+            "https://example.com/image.jpg",
+            "https://example.com/image.jpeg",
+            "https://example.com/image.gif",
+            "http://example.com/path/to/image.webp",
+        ]
+        .iter()
+        {
+            assert!(ImageFetcher::is_image_url(url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn detection_is_case_insensitive() {
+        assert!(ImageFetcher::is_image_url("HTTPS://EXAMPLE.COM/IMAGE.PNG"));
+        assert!(ImageFetcher::is_image_url("https://example.com/Image.JpEg"));
+    }
+
+    #[test]
+    fn query_and_fragment_are_ignored() {
+        assert!(ImageFetcher::is_image_url(
+            "https://example.com/image.png?size=big"
+        ));
+        assert!(ImageFetcher::is_image_url(
+            "https://example.com/image.gif#top"
+        ));
+        assert!(!ImageFetcher::is_image_url(
+            "https://example.com/page?file=image.png"
+        ));
+        assert!(!ImageFetcher::is_image_url(
+            "https://example.com/page#image.png"
+        ));
+    }
+
+    #[test]
+    fn non_image_urls_are_ignored() {
+        for word in [
+            "https://example.com/",
+            "https://example.com/image.png/page",
+            "https://example.com/png",
+            "https://example.com/image.pngx",
+            "https://example.com/image.svg",
+            "https://example.com/image.png.", // end of the sentence
+        ]
+        .iter()
+        {
+            assert!(!ImageFetcher::is_image_url(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn only_http_urls_are_detected() {
+        for word in [
+            "image.png",
+            "www.example.com/image.png",
+            "ftp://example.com/image.png",
+            "file:///home/user/image.png",
+            "xhttps://example.com/image.png",
+        ]
+        .iter()
+        {
+            assert!(!ImageFetcher::is_image_url(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn first_image_url_in_message_is_found() {
+        assert_eq!(
+            ImageFetcher::find_image_url(
+                "look https://example.com/page and https://example.com/A.PNG \
+                or https://example.com/b.jpg"
+            ),
+            Some(String::from("https://example.com/A.PNG"))
+        );
+        assert_eq!(
+            ImageFetcher::find_image_url("https://example.com/image.gif"),
+            Some(String::from("https://example.com/image.gif"))
+        );
+        assert_eq!(
+            ImageFetcher::find_image_url("line\nhttps://example.com/image.webp\tnext"),
+            Some(String::from("https://example.com/image.webp"))
+        );
+    }
+
+    #[test]
+    fn message_without_image_url() {
+        assert_eq!(ImageFetcher::find_image_url(""), None);
+        assert_eq!(ImageFetcher::find_image_url("image.png"), None);
+        assert_eq!(
+            ImageFetcher::find_image_url("see https://example.com/page"),
+            None
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// Std.
+use std::time::{Duration, Instant};
+
+const JITTER_STREAM_GAP_MS: u64 = 500; // longer pause means that the next packet starts a new stream
+
+// Interarrival jitter estimate (RFC 3550, section A.8) for received voice packets.
+// We don't have sender timestamps so the difference between two consecutive
+// interarrival times is used as the transit time difference 'D'.
+#[derive(Debug)]
+pub struct JitterStats {
+    last_arrival: Option<Instant>,
+    last_interarrival: Option<Duration>,
+    jitter_ms: f64,
+}
+
+impl JitterStats {
+    pub fn new() -> Self {
+        JitterStats {
+            last_arrival: None,
+            last_interarrival: None,
+            jitter_ms: 0.0,
+        }
+    }
+    pub fn add_arrival(&mut self, now: Instant) {
+        let last_arrival = self.last_arrival.replace(now);
+        if last_arrival.is_none() {
+            return;
+        }
+
+        let interarrival = now.duration_since(last_arrival.unwrap());
+        if interarrival > Duration::from_millis(JITTER_STREAM_GAP_MS) {
+            // nobody was talking, this is a new stream
+            self.last_interarrival = None;
+            return;
+        }
+
+        if let Some(last_interarrival) = self.last_interarrival {
+            let d_ms =
+                (interarrival.as_secs_f64() - last_interarrival.as_secs_f64()).abs() * 1000.0;
+            // J(i) = J(i-1) + (|D(i-1,i)| - J(i-1))/16
+            self.jitter_ms += (d_ms - self.jitter_ms) / 16.0;
+        }
+
+        self.last_interarrival = Some(interarrival);
+    }
+    pub fn get_jitter_ms(&self) -> f64 {
+        self.jitter_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 0.000_001;
+
+    // Adds arrivals after the specified intervals (in milliseconds).
+    fn add_arrivals(stats: &mut JitterStats, now: &mut Instant, intervals_ms: &[u64]) {
+        for interval_ms in intervals_ms {
+            *now += Duration::from_millis(*interval_ms);
+            stats.add_arrival(*now);
+        }
+    }
+
+    // Returns stats and time of the first arrival.
+    fn stats_with_first_arrival() -> (JitterStats, Instant) {
+        let mut stats = JitterStats::new();
+        let now = Instant::now();
+        stats.add_arrival(now);
+
+        (stats, now)
+    }
+
+    #[test]
+    fn constant_intervals_have_no_jitter() {
+        let (mut stats, mut now) = stats_with_first_arrival();
+
+        add_arrivals(&mut stats, &mut now, &[20; 100]);
+
+        assert!(stats.get_jitter_ms().abs() < EPSILON);
+    }
+
+    #[test]
+    fn two_arrivals_have_no_jitter() {
+        let (mut stats, mut now) = stats_with_first_arrival();
+
+        add_arrivals(&mut stats, &mut now, &[35]);
+
+        assert!(stats.get_jitter_ms().abs() < EPSILON);
+    }
+
+    #[test]
+    fn one_late_packet() {
+        let (mut stats, mut now) = stats_with_first_arrival();
+
+        // D = |30 - 20| = 10, J = 0 + (10 - 0) / 16
+        add_arrivals(&mut stats, &mut now, &[20, 20, 30]);
+        assert!((stats.get_jitter_ms() - 0.625).abs() < EPSILON);
+
+        // D = |20 - 30| = 10, J = 0.625 + (10 - 0.625) / 16
+        add_arrivals(&mut stats, &mut now, &[20]);
+        assert!((stats.get_jitter_ms() - 1.210_937_5).abs() < EPSILON);
+
+        // D = 0, J = 1.2109375 - 1.2109375 / 16
+        add_arrivals(&mut stats, &mut now, &[20]);
+        assert!((stats.get_jitter_ms() - 1.135_253_906_25).abs() < EPSILON);
+    }
+
+    #[test]
+    fn alternating_intervals_follow_rfc3550_estimator() {
+        let (mut stats, mut now) = stats_with_first_arrival();
+        add_arrivals(&mut stats, &mut now, &[20]);
+
+        // each interarrival time differs from the previous one by 20 ms
+        let mut expected_jitter_ms = 0.0;
+        for i in 0..200 {
+            add_arrivals(&mut stats, &mut now, &[if i % 2 == 0 { 40 } else { 20 }]);
+
+            expected_jitter_ms += (20.0 - expected_jitter_ms) / 16.0;
+            assert!(
+                (stats.get_jitter_ms() - expected_jitter_ms).abs() < EPSILON,
+                "packet {}",
+                i
+            );
+        }
+
+        // converges to |D|
+        assert!((stats.get_jitter_ms() - 20.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn long_gap_starts_new_stream() {
+        let (mut stats, mut now) = stats_with_first_arrival();
+        add_arrivals(&mut stats, &mut now, &[20, 20, 30]);
+        let jitter_ms = stats.get_jitter_ms();
+
+        // the gap and the first interval after it are not compared with other intervals
+        add_arrivals(&mut stats, &mut now, &[JITTER_STREAM_GAP_MS + 1, 20]);
+        assert!((stats.get_jitter_ms() - jitter_ms).abs() < EPSILON);
+
+        // D = |20 - 20| = 0
+        add_arrivals(&mut stats, &mut now, &[20]);
+        assert!((stats.get_jitter_ms() - (jitter_ms - jitter_ms / 16.0)).abs() < EPSILON);
+    }
+
+    #[test]
+    fn gap_at_limit_is_same_stream() {
+        let (mut stats, mut now) = stats_with_first_arrival();
+
+        // D = |500 - 20| = 480, J = 480 / 16
+        add_arrivals(&mut stats, &mut now, &[20, JITTER_STREAM_GAP_MS]);
+
+        assert!((stats.get_jitter_ms() - 30.0).abs() < EPSILON);
+    }
+}
@@ -1,7 +1,19 @@
 pub mod audio_service;
 pub mod config_service;
+pub mod connection_log;
+pub mod event_sink;
+pub mod image_fetcher;
+pub mod jitter_stats;
 pub mod net_service;
+pub mod network_interfaces;
+pub mod priority_udp_queue;
+pub mod replay_window;
+pub mod send_queue;
+pub mod tcp_connect_state_machine;
 pub mod tcp_packets;
+pub mod token_bucket;
+pub mod udp_health_check;
 pub mod udp_packets;
+pub mod update_checker;
 pub mod user_tcp_service;
 pub mod user_udp_service;
@@ -1,22 +1,39 @@
 // External.
 use chrono::prelude::*;
-use druid::{ExtEventSink, Selector, Target};
+use druid::{Selector, Target};
 use system_wide_key_state::*;
+use tracing::warn;
 
 // Std.
-use std::convert::TryInto;
 use std::net::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 // Custom.
 use super::tcp_packets::*;
+use crate::error::SilentError;
 use crate::global_params::*;
+use crate::misc::packet_builder::{FrameFormat, PacketBuilder};
 use crate::services::audio_service::audio_service::*;
+use crate::services::config_service::UserConfig;
+use crate::services::connection_log::*;
+use crate::services::event_sink::EventSink;
+use crate::services::priority_udp_queue::*;
+use crate::services::send_queue::*;
+use crate::services::udp_health_check::UdpHealthCheck;
 use crate::services::user_tcp_service::*;
 use crate::services::user_udp_service::*;
 
+const EVENT_SINK_NOT_INITIALIZED_MESSAGE: &str =
+    "event_sink is None; NetService::start() must be called first";
+const AUDIO_SERVICE_NOT_INITIALIZED_MESSAGE: &str =
+    "audio_service is None; NetService::init_audio_service() must be called first";
+const NOT_INITIALIZED_MESSAGE: &str =
+    "NetService is not initialized; NetService::init_audio_service() and NetService::start() must be called first";
+
 pub const NETWORK_SERVICE_SYSTEM_IO_ERROR: Selector<String> =
     Selector::new("network_service_system_io_error");
 
@@ -26,10 +43,17 @@ pub const NETWORK_SERVICE_UPDATE_CONNECTED_USERS_COUNT: Selector<usize> =
 pub const NETWORK_SERVICE_CLEAR_ALL_USERS: Selector<()> =
     Selector::new("network_service_clear_all_users");
 
+pub const NETWORK_SERVICE_CONNECTION_LOST: Selector<()> =
+    Selector::new("network_service_connection_lost");
+
+pub const NETWORK_SERVICE_QUEUE_DEPTH_CHANGED: Selector<usize> =
+    Selector::new("network_service_queue_depth_changed");
+
 pub enum ActionError {
     ChangeRoomsTooQuick,
     SendMessagesTooQuick,
-    SystemError(String),
+    SendQueueFull,
+    SystemError(SilentError),
 }
 
 pub struct ClientConfig {
@@ -38,6 +62,17 @@ pub struct ClientConfig {
     pub server_port: String,
     pub server_password: String,
     pub push_to_talk_key: KeyCode,
+    pub voice_codec: VoiceCodec,
+    pub server_addr: Option<SocketAddr>, // prefetched address (None to resolve on connect)
+}
+
+impl ClientConfig {
+    pub fn get_server_address(&self) -> String {
+        match self.server_addr {
+            Some(addr) => addr.to_string(),
+            None => format!("{}:{}", self.server_name, self.server_port),
+        }
+    }
 }
 
 #[derive(Clone)] // for ApplicationState
@@ -47,40 +82,128 @@ pub struct PasswordRetrySleep {
     pub sleep: bool,
 }
 
+// Delays between the attempts to reconnect after the connection was lost.
+#[derive(Clone, Copy)]
+pub struct ReconnectPolicy {
+    pub max_attempts: u8,
+    pub base_delay_ms: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_attempts: DEFAULT_RECONNECT_MAX_ATTEMPTS,
+            base_delay_ms: DEFAULT_RECONNECT_BASE_DELAY_MS,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns 'base_delay_ms * 2^attempt' (the first attempt is 0)
+    /// but not more than MAX_RECONNECT_DELAY_MS.
+    pub fn get_delay_ms(&self, attempt: u8) -> u64 {
+        let multiplier = 1u64.checked_shl(attempt as u32).unwrap_or(u64::MAX);
+
+        self.base_delay_ms
+            .saturating_mul(multiplier)
+            .min(MAX_RECONNECT_DELAY_MS)
+    }
+}
+
 #[derive(Clone)] // for ApplicationState
 pub struct NetService {
     pub user_tcp_service: Arc<Mutex<UserTcpService>>,
     pub user_udp_service: Arc<Mutex<UserUdpService>>,
     pub audio_service: Option<Arc<Mutex<AudioService>>>,
+    pub send_queue: Arc<SendQueue>,
+    user_config: Arc<Mutex<UserConfig>>, // for spam protection settings
     pub password_retry: PasswordRetrySleep,
-    pub event_sink: Option<ExtEventSink>,
+    pub reconnect_policy: ReconnectPolicy,
+    pub event_sink: Option<EventSink>,
+    is_voice_transmission_paused: Arc<AtomicBool>,
     last_time_text_message_sent: DateTime<Local>,
     last_time_entered_room: DateTime<Local>,
+    tcp_thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+    udp_thread: Arc<Mutex<Option<JoinHandle<()>>>>, // started by the TCP thread
+}
+
+// Asks the UDP thread to stop when the TCP thread finishes (for any reason).
+struct UdpStopGuard(Arc<AtomicBool>);
+
+impl Drop for UdpStopGuard {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
 }
 
 impl NetService {
-    pub fn new() -> Self {
+    pub fn new(user_config: Arc<Mutex<UserConfig>>) -> Self {
         Self {
+            user_config,
             user_tcp_service: Arc::new(Mutex::new(UserTcpService::new(String::from("")))),
             user_udp_service: Arc::new(Mutex::new(UserUdpService::new())),
             last_time_text_message_sent: Local::now(),
             last_time_entered_room: Local::now(),
             audio_service: None,
+            send_queue: Arc::new(SendQueue::new(SEND_QUEUE_CAPACITY)),
             password_retry: PasswordRetrySleep {
                 sleep_time_start: Local::now(),
                 sleep_time_sec: 0,
                 sleep: false,
             },
+            reconnect_policy: ReconnectPolicy::default(),
             event_sink: None,
+            is_voice_transmission_paused: Arc::new(AtomicBool::new(false)),
+            tcp_thread: Arc::new(Mutex::new(None)),
+            udp_thread: Arc::new(Mutex::new(None)),
         }
     }
 
     pub fn init_audio_service(&mut self, audio_service: Arc<Mutex<AudioService>>) {
         self.audio_service = Some(audio_service);
     }
+    /// Returns 'true' if both 'init_audio_service()' and 'start()' were called.
+    pub fn is_initialized(&self) -> bool {
+        self.event_sink.is_some() && self.audio_service.is_some()
+    }
+
+    /// Suppresses sending of recorded voice (the recording itself continues).
+    pub fn pause_voice_transmission(&self) {
+        if self
+            .is_voice_transmission_paused
+            .swap(true, Ordering::SeqCst)
+        {
+            // already paused
+            return;
+        }
+
+        self.user_udp_service
+            .lock()
+            .unwrap()
+            .send_voice_transmission_state(true);
+    }
+    pub fn resume_voice_transmission(&self) {
+        if !self
+            .is_voice_transmission_paused
+            .swap(false, Ordering::SeqCst)
+        {
+            // not paused
+            return;
+        }
 
+        self.user_udp_service
+            .lock()
+            .unwrap()
+            .send_voice_transmission_state(false);
+    }
+    pub fn is_voice_transmission_paused(&self) -> bool {
+        self.is_voice_transmission_paused.load(Ordering::SeqCst)
+    }
     pub fn resend_ping_later(&self, ping_data: UserPingInfo) {
-        let event_sink_clone = self.event_sink.clone().unwrap();
+        let event_sink_clone = self
+            .event_sink
+            .clone()
+            .expect(EVENT_SINK_NOT_INITIALIZED_MESSAGE);
         thread::spawn(move || {
             thread::sleep(Duration::from_millis(
                 USER_CONNECT_FIRST_UDP_PING_RETRY_INTERVAL_MS as u64,
@@ -105,7 +228,7 @@ impl NetService {
         username: String,
         server_password: String,
         connect_layout_sender: std::sync::mpsc::Sender<ConnectResult>,
-        event_sink: ExtEventSink,
+        event_sink: EventSink,
     ) {
         self.event_sink = Some(event_sink.clone());
 
@@ -118,24 +241,145 @@ impl NetService {
 
         // Start TCP service.
         self.user_tcp_service = Arc::new(Mutex::new(UserTcpService::new(server_password)));
+        self.send_queue = Arc::new(SendQueue::new(SEND_QUEUE_CAPACITY));
+        self.user_udp_service.lock().unwrap().request_stop = Arc::new(AtomicBool::new(false));
+        let udp_thread_copy = Arc::clone(&self.udp_thread);
         let user_tcp_service_copy = Arc::clone(&self.user_tcp_service);
         let user_udp_service_copy = Arc::clone(&self.user_udp_service);
-        let audio_service_copy = Arc::clone(self.audio_service.as_ref().unwrap());
-        thread::spawn(move || {
-            NetService::tcp_service(
-                config,
-                username,
-                user_tcp_service_copy,
-                user_udp_service_copy,
-                connect_layout_sender,
-                event_sink,
-                audio_service_copy,
-            )
-        });
+        let send_queue_copy = Arc::clone(&self.send_queue);
+        let audio_service_copy = Arc::clone(
+            self.audio_service
+                .as_ref()
+                .expect(AUDIO_SERVICE_NOT_INITIALIZED_MESSAGE),
+        );
+        *self.tcp_thread.lock().unwrap() = Some(thread::spawn(move || {
+            let event_sink_copy = event_sink.clone();
+            NetService::run_catching_panic(&event_sink_copy, "TCP", move || {
+                NetService::tcp_service(
+                    config,
+                    username,
+                    user_tcp_service_copy,
+                    user_udp_service_copy,
+                    udp_thread_copy,
+                    send_queue_copy,
+                    connect_layout_sender,
+                    event_sink,
+                    audio_service_copy,
+                )
+            });
+        }));
+    }
+    /// Runs `f` and if it panics shows the panic message in the chat
+    /// (instead of silently losing the thread).
+    pub fn run_catching_panic<F: FnOnce()>(event_sink: &EventSink, thread_name: &str, f: F) {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        if let Err(panic_payload) = result {
+            let details = if let Some(msg) = panic_payload.downcast_ref::<&str>() {
+                String::from(*msg)
+            } else if let Some(msg) = panic_payload.downcast_ref::<String>() {
+                msg.clone()
+            } else {
+                String::from("unknown panic")
+            };
+
+            event_sink
+                .submit_command(
+                    NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                    format!("Internal error ({} thread): {}", thread_name, details),
+                    Target::Auto,
+                )
+                .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+        }
+    }
+    /// Closes the connection to the server and waits for
+    /// the TCP and UDP threads to finish.
+    pub fn disconnect(&mut self) {
+        {
+            let mut user_tcp_service_guard = self.user_tcp_service.lock().unwrap();
+            if matches!(user_tcp_service_guard.user_state, UserState::NotConnected) {
+                return;
+            }
+
+            user_tcp_service_guard.is_disconnect_requested = true;
+            user_tcp_service_guard.user_state = UserState::NotConnected;
+            user_tcp_service_guard
+                .request_stop
+                .store(true, Ordering::SeqCst);
+
+            if let Some(tcp_socket) = user_tcp_service_guard.tcp_socket.as_ref() {
+                if let Err(e) = tcp_socket.shutdown(Shutdown::Both) {
+                    warn!(
+                        "TcpStream::shutdown() failed, error: {} at [{}, {}]",
+                        e,
+                        file!(),
+                        line!()
+                    );
+                }
+            }
+        }
+        self.user_udp_service
+            .lock()
+            .unwrap()
+            .request_stop
+            .store(true, Ordering::SeqCst);
+
+        // Wait for threads to finish (the services must not be locked here).
+        let tcp_thread = self.tcp_thread.lock().unwrap().take();
+        if let Some(tcp_thread) = tcp_thread {
+            if tcp_thread.join().is_err() {
+                warn!(
+                    "failed to join the TCP thread at [{}, {}]",
+                    file!(),
+                    line!()
+                );
+            }
+        }
+        let udp_thread = self.udp_thread.lock().unwrap().take();
+        if let Some(udp_thread) = udp_thread {
+            if udp_thread.join().is_err() {
+                warn!(
+                    "failed to join the UDP thread at [{}, {}]",
+                    file!(),
+                    line!()
+                );
+            }
+        }
+
+        self.user_tcp_service.lock().unwrap().zero_secret_key();
+        self.user_udp_service.lock().unwrap().zero_secret_key();
+    }
+    /// Number of threads (TCP and UDP) that were started and not joined yet.
+    #[cfg(test)]
+    pub fn get_started_thread_count(&self) -> usize {
+        self.tcp_thread.lock().unwrap().is_some() as usize
+            + self.udp_thread.lock().unwrap().is_some() as usize
+    }
+    /// Returns the cooldown that applies to moving from 'current_room' to 'room':
+    /// moving to or from the lobby uses the lobby cooldown, other moves use the same tier cooldown.
+    pub fn get_room_change_cooldown_sec(config: &UserConfig, current_room: &str, room: &str) -> u8 {
+        if current_room == DEFAULT_ROOM_NAME || room == DEFAULT_ROOM_NAME {
+            config.room_change_lobby_cooldown_sec
+        } else {
+            config.room_change_same_tier_cooldown_sec
+        }
     }
-    pub fn enter_room(&mut self, room: &str) -> Result<(), ActionError> {
-        let time_diff = Local::now() - self.last_time_entered_room;
-        if time_diff.num_seconds() < SPAM_PROTECTION_SEC as i64 {
+    /// Returns 'true' if less than 'cooldown_sec' passed since 'last_time' (zero cooldown is disabled).
+    pub fn is_on_cooldown(
+        last_time: DateTime<Local>,
+        now: DateTime<Local>,
+        cooldown_sec: u8,
+    ) -> bool {
+        (now - last_time).num_seconds() < cooldown_sec as i64
+    }
+    pub fn enter_room(&mut self, room: &str, current_room: &str) -> Result<(), ActionError> {
+        assert!(self.is_initialized(), "{}", NOT_INITIALIZED_MESSAGE);
+
+        let cooldown_sec = NetService::get_room_change_cooldown_sec(
+            &self.user_config.lock().unwrap(),
+            current_room,
+            room,
+        );
+        if NetService::is_on_cooldown(self.last_time_entered_room, Local::now(), cooldown_sec) {
             return Err(ActionError::ChangeRoomsTooQuick);
         }
 
@@ -143,23 +387,18 @@ impl NetService {
             HandleMessageResult::Ok => {}
             HandleMessageResult::IOError(err) => match err {
                 IoResult::Err(msg) => {
-                    return Err(ActionError::SystemError(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
+                    return Err(ActionError::SystemError(SilentError::InternalError(
+                        format!("{} at [{}, {}]", msg, file!(), line!()),
                     )));
                 }
                 _ => {}
             },
             HandleMessageResult::OtherErr(msg) => {
-                return Err(ActionError::SystemError(format!(
-                    "{} at [{}, {}]",
-                    msg,
-                    file!(),
-                    line!()
+                return Err(ActionError::SystemError(SilentError::InternalError(
+                    format!("{} at [{}, {}]", msg, file!(), line!()),
                 )));
             }
+            HandleMessageResult::Timeout => unreachable!(), // only during the handshake
         }
 
         self.last_time_entered_room = Local::now();
@@ -167,55 +406,136 @@ impl NetService {
         Ok(())
     }
     pub fn send_user_message(&mut self, message: String) -> Result<(), ActionError> {
-        let time_diff = Local::now() - self.last_time_text_message_sent;
-        if time_diff.num_seconds() < SPAM_PROTECTION_SEC as i64 {
+        assert!(self.is_initialized(), "{}", NOT_INITIALIZED_MESSAGE);
+
+        let cooldown_sec = self.user_config.lock().unwrap().message_cooldown_sec;
+        if NetService::is_on_cooldown(self.last_time_text_message_sent, Local::now(), cooldown_sec)
+        {
             return Err(ActionError::SendMessagesTooQuick);
         }
 
-        match self
+        let packet = self
             .user_tcp_service
             .lock()
             .unwrap()
-            .send_user_text_message(message)
-        {
-            HandleMessageResult::Ok => {}
-            HandleMessageResult::IOError(err) => match err {
-                IoResult::Err(msg) => {
-                    return Err(ActionError::SystemError(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
-                    )));
+            .build_user_text_message_packet(message);
+        if let Err(err) = packet {
+            return Err(ActionError::SystemError(SilentError::InternalError(
+                format!("{} at [{}, {}]", err, file!(), line!()),
+            )));
+        }
+
+        // The sender thread will write it to the socket.
+        match self.send_queue.push(packet.unwrap()) {
+            Ok(depth) => {
+                if let Some(event_sink) = self.event_sink.as_ref() {
+                    event_sink
+                        .submit_command(NETWORK_SERVICE_QUEUE_DEPTH_CHANGED, depth, Target::Auto)
+                        .expect("failed to submit NETWORK_SERVICE_QUEUE_DEPTH_CHANGED command");
                 }
-                _ => {}
-            },
-            HandleMessageResult::OtherErr(msg) => {
-                return Err(ActionError::SystemError(format!(
-                    "{} at [{}, {}]",
-                    msg,
-                    file!(),
-                    line!()
-                )));
             }
+            Err(()) => return Err(ActionError::SendQueueFull),
         }
 
         self.last_time_text_message_sent = Local::now();
 
         Ok(())
     }
+    /// Sends the status message (like the auto-away message), the server broadcasts it to other users.
+    pub fn send_status_message(&mut self, text: String) -> Result<(), ActionError> {
+        let packet = self
+            .user_tcp_service
+            .lock()
+            .unwrap()
+            .build_status_message_packet(text);
+        if let Err(err) = packet {
+            return Err(ActionError::SystemError(SilentError::InternalError(
+                format!("{} at [{}, {}]", err, file!(), line!()),
+            )));
+        }
+
+        self.push_to_send_queue(packet.unwrap())
+    }
+    /// Adds (or removes, if already added) our reaction to the message with the specified server ID.
+    pub fn send_message_reaction(
+        &mut self,
+        message_id: u64,
+        emoji: String,
+    ) -> Result<(), ActionError> {
+        let packet = self
+            .user_tcp_service
+            .lock()
+            .unwrap()
+            .build_message_reaction_packet(message_id, emoji);
+        if let Err(err) = packet {
+            return Err(ActionError::SystemError(SilentError::InternalError(
+                format!("{} at [{}, {}]", err, file!(), line!()),
+            )));
+        }
+
+        self.push_to_send_queue(packet.unwrap())
+    }
+    pub fn send_pin_message(&mut self, message_id: u64) -> Result<(), ActionError> {
+        let packet = self
+            .user_tcp_service
+            .lock()
+            .unwrap()
+            .build_pin_message_packet(message_id);
+        if let Err(err) = packet {
+            return Err(ActionError::SystemError(SilentError::InternalError(
+                format!("{} at [{}, {}]", err, file!(), line!()),
+            )));
+        }
+
+        self.push_to_send_queue(packet.unwrap())
+    }
+    /// Asks the server to suggest the user to join the room.
+    pub fn send_room_invite(
+        &mut self,
+        target_username: String,
+        room_name: String,
+    ) -> Result<(), ActionError> {
+        let packet = self
+            .user_tcp_service
+            .lock()
+            .unwrap()
+            .build_room_invite_packet(target_username, room_name);
+        if let Err(err) = packet {
+            return Err(ActionError::SystemError(SilentError::InternalError(
+                format!("{} at [{}, {}]", err, file!(), line!()),
+            )));
+        }
+
+        self.push_to_send_queue(packet.unwrap())
+    }
+    fn push_to_send_queue(&self, packet: Vec<u8>) -> Result<(), ActionError> {
+        // The sender thread will write it to the socket.
+        match self.send_queue.push(packet) {
+            Ok(depth) => {
+                if let Some(event_sink) = self.event_sink.as_ref() {
+                    event_sink
+                        .submit_command(NETWORK_SERVICE_QUEUE_DEPTH_CHANGED, depth, Target::Auto)
+                        .expect("failed to submit NETWORK_SERVICE_QUEUE_DEPTH_CHANGED command");
+                }
+                Ok(())
+            }
+            Err(()) => Err(ActionError::SendQueueFull),
+        }
+    }
 
+    #[tracing::instrument(skip_all, fields(username = %username))]
     fn tcp_service(
         config: ClientConfig,
         username: String,
         user_tcp_service: Arc<Mutex<UserTcpService>>,
         user_udp_service: Arc<Mutex<UserUdpService>>,
+        udp_thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+        send_queue: Arc<SendQueue>,
         connect_layout_sender: std::sync::mpsc::Sender<ConnectResult>,
-        event_sink: ExtEventSink,
+        event_sink: EventSink,
         audio_service: Arc<Mutex<AudioService>>,
     ) {
-        let tcp_socket =
-            TcpStream::connect(format!("{}:{}", config.server_name, config.server_port));
+        let tcp_socket = TcpStream::connect(config.get_server_address());
 
         if tcp_socket.is_err() {
             connect_layout_sender
@@ -241,6 +561,10 @@ impl NetService {
                 .unwrap();
             return;
         }
+        if let Err(msg) = UserTcpService::enable_keepalive(&tcp_socket) {
+            // not critical, the server's KeepAliveCheck packets still work
+            warn!("{}", msg);
+        }
 
         let (sender, receiver) = mpsc::channel();
 
@@ -252,27 +576,16 @@ impl NetService {
         }
 
         // Connect.
+        let mut udp_secret_key = [0u8; SECRET_KEY_SIZE];
         {
             let mut user_service_guard = user_tcp_service.lock().unwrap();
 
-            match user_service_guard.establish_secure_connection() {
-                Ok(key) => {
-                    let result = key.try_into();
-                    if result.is_err() {
-                        event_sink
-                            .submit_command(
-                                NETWORK_SERVICE_SYSTEM_IO_ERROR,
-                                format!(
-                                    "failed to convert Vec<u8> to generic array at [{}, {}]",
-                                    file!(),
-                                    line!()
-                                ),
-                                Target::Auto,
-                            )
-                            .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
-                        return;
-                    }
-                    user_service_guard.secret_key = result.unwrap();
+            match user_service_guard
+                .establish_secure_connection(&config.server_name, &config.server_port)
+            {
+                Ok(session_keys) => {
+                    user_service_guard.secret_key = session_keys.tcp_key;
+                    udp_secret_key = session_keys.udp_key;
                 }
                 Err(e) => match e {
                     HandleMessageResult::Ok => {}
@@ -309,10 +622,16 @@ impl NetService {
                             .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
                         return;
                     }
+                    HandleMessageResult::Timeout => {
+                        connect_layout_sender
+                            .send(ConnectResult::ErrTimeout)
+                            .unwrap();
+                        return;
+                    }
                 },
             }
 
-            match user_service_guard.connect_user(sender) {
+            match user_service_guard.connect_user(config.voice_codec, sender) {
                 ConnectResult::Ok => {
                     // Get info about all other users.
                     let mut connected_users = 0usize;
@@ -367,32 +686,55 @@ impl NetService {
             }
         }
 
+        let connected_at = Local::now();
+        let _send_queue_close_guard = SendQueueCloseGuard(Arc::clone(&send_queue));
+        let _udp_stop_guard =
+            UdpStopGuard(Arc::clone(&user_udp_service.lock().unwrap().request_stop));
+
+        // Start sender thread.
+        {
+            let user_tcp_service_copy = Arc::clone(&user_tcp_service);
+            let event_sink_copy = event_sink.clone();
+            thread::spawn(move || {
+                let event_sink = event_sink_copy.clone();
+                NetService::run_catching_panic(&event_sink, "TCP sender", move || {
+                    NetService::send_queue_service(
+                        send_queue,
+                        user_tcp_service_copy,
+                        event_sink_copy,
+                    )
+                });
+            });
+        }
+
         // Start UDP service
         {
             let username_copy = username.clone();
-            let server_name_copy = config.server_name.clone();
-            let server_port_copy = config.server_port.clone();
+            let server_address = config.get_server_address();
             let push_to_talk_button = config.push_to_talk_key;
-            let secret_key_copy = user_tcp_service.lock().unwrap().secret_key.clone();
+            let secret_key_copy = udp_secret_key;
             let event_sink_copy = event_sink.clone();
-            thread::spawn(move || {
-                NetService::udp_service(
-                    username_copy,
-                    server_name_copy,
-                    server_port_copy,
-                    event_sink_copy,
-                    user_udp_service,
-                    audio_service,
-                    push_to_talk_button,
-                    secret_key_copy,
-                )
-            });
+            *udp_thread.lock().unwrap() = Some(thread::spawn(move || {
+                let event_sink = event_sink_copy.clone();
+                NetService::run_catching_panic(&event_sink, "UDP", move || {
+                    NetService::udp_service(
+                        username_copy,
+                        server_address,
+                        event_sink_copy,
+                        user_udp_service,
+                        audio_service,
+                        push_to_talk_button,
+                        secret_key_copy,
+                    )
+                });
+            }));
         }
 
         // Read messages from server.
+        let mut disconnect_reason = String::new();
         loop {
             let mut _fin = false;
-            let mut in_buf = vec![0u8; std::mem::size_of::<u16>()];
+            let mut in_buf = vec![0u8; TCP_FRAME_HEADER_SIZE];
             loop {
                 {
                     let mut user_service_guard = user_tcp_service.lock().unwrap();
@@ -404,10 +746,17 @@ impl NetService {
                         }
                         IoResult::Ok(_) => {}
                         IoResult::FIN => {
+                            _fin = true;
+                            disconnect_reason = String::from("The server closed connection.");
+                            break;
+                        }
+                        IoResult::Err(_) if user_service_guard.is_disconnect_requested => {
+                            // the socket was shut down by us
                             _fin = true;
                             break;
                         }
                         IoResult::Err(msg) => {
+                            drop(user_service_guard);
                             event_sink
                                 .submit_command(
                                     NETWORK_SERVICE_SYSTEM_IO_ERROR,
@@ -415,30 +764,42 @@ impl NetService {
                                     Target::Auto,
                                 )
                                 .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
-                            event_sink
-                                .submit_command(NETWORK_SERVICE_CLEAR_ALL_USERS, (), Target::Auto)
-                                .expect("failed to submit NETWORK_SERVICE_CLEAR_ALL_USERS command");
+                            NetService::on_connection_closed(
+                                &config,
+                                connected_at,
+                                msg,
+                                &user_tcp_service,
+                                &event_sink,
+                            );
                             return;
                         }
                     }
                 }
 
                 // Got something.
-                let message_size = bincode::deserialize::<u16>(&in_buf);
-                if let Err(e) = message_size {
+                let frame_header = PacketBuilder::parse_frame_header(&in_buf);
+                if let Err(e) = frame_header {
                     event_sink
                         .submit_command(
                             NETWORK_SERVICE_SYSTEM_IO_ERROR,
                             format!(
-                        "bincode::deserialize failed, error: failed to decode on 'in_buf' (error: {}) at [{}, {}].\nClosing connection...",
+                        "failed to read the frame header (error: {}) at [{}, {}].\nClosing connection...",
                         e, file!(), line!()
                     ),
                             Target::Auto,
                         )
                         .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+                    NetService::on_connection_closed(
+                        &config,
+                        connected_at,
+                        format!("failed to read the frame header, error: {}", e),
+                        &user_tcp_service,
+                        &event_sink,
+                    );
                     return;
                 }
-                let message_size = message_size.unwrap();
+                let frame_header = frame_header.unwrap();
+                let message_size = frame_header.payload_size;
 
                 if message_size > TCP_PACKET_MAX_SIZE {
                     event_sink
@@ -451,17 +812,33 @@ impl NetService {
                             Target::Auto,
                         )
                         .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+                    NetService::on_connection_closed(
+                        &config,
+                        connected_at,
+                        String::from("incoming packet size exceeds the maximum size"),
+                        &user_tcp_service,
+                        &event_sink,
+                    );
                     return;
                 }
 
                 // Handle message.
                 {
                     let mut user_service_guard = user_tcp_service.lock().unwrap();
-                    match user_service_guard.handle_message(message_size, event_sink.clone()) {
+                    let result = match PacketBuilder::get_frame_format(&frame_header) {
+                        FrameFormat::Legacy => {
+                            user_service_guard.handle_message(message_size, event_sink.clone())
+                        }
+                        FrameFormat::Unsupported(version) => {
+                            user_service_guard.skip_message(message_size, version)
+                        }
+                    };
+                    match result {
                         HandleMessageResult::Ok => {}
                         HandleMessageResult::IOError(err) => match err {
                             IoResult::FIN => {
                                 _fin = true;
+                                disconnect_reason = String::from("The server closed connection.");
                                 break;
                             }
                             IoResult::Err(msg) => {
@@ -475,6 +852,7 @@ impl NetService {
                                     .expect(
                                         "failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command",
                                     );
+                                disconnect_reason = msg;
                                 break;
                             }
                             _ => {}
@@ -488,8 +866,10 @@ impl NetService {
                                     Target::Auto,
                                 )
                                 .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+                            disconnect_reason = msg.to_string();
                             break;
                         }
+                        HandleMessageResult::Timeout => unreachable!(), // only during the handshake
                     }
                 }
             }
@@ -499,37 +879,179 @@ impl NetService {
             }
         }
 
+        if user_tcp_service.lock().unwrap().is_disconnect_requested {
+            disconnect_reason = String::from("Disconnected by the user.");
+        }
+
+        NetService::on_connection_closed(
+            &config,
+            connected_at,
+            disconnect_reason,
+            &user_tcp_service,
+            &event_sink,
+        );
+    }
+    /// Clears the users and logs the disconnect, if the connection was not closed
+    /// by the user the UI will try to reconnect (see 'ReconnectPolicy').
+    fn on_connection_closed(
+        config: &ClientConfig,
+        connected_at: DateTime<Local>,
+        disconnect_reason: String,
+        user_tcp_service: &Arc<Mutex<UserTcpService>>,
+        event_sink: &EventSink,
+    ) {
+        if !user_tcp_service.lock().unwrap().is_disconnect_requested {
+            event_sink
+                .submit_command(NETWORK_SERVICE_CONNECTION_LOST, (), Target::Auto)
+                .expect("failed to submit NETWORK_SERVICE_CONNECTION_LOST command");
+        }
+
         event_sink
             .submit_command(NETWORK_SERVICE_CLEAR_ALL_USERS, (), Target::Auto)
             .expect("failed to submit NETWORK_SERVICE_CLEAR_ALL_USERS command");
+
+        NetService::log_disconnect(config, connected_at, disconnect_reason, event_sink);
+    }
+    fn send_queue_service(
+        send_queue: Arc<SendQueue>,
+        user_tcp_service: Arc<Mutex<UserTcpService>>,
+        event_sink: EventSink,
+    ) {
+        while !send_queue.is_closed() {
+            let packet = send_queue.wait_front(Duration::from_millis(SEND_QUEUE_WAIT_TIMEOUT_MS));
+            if packet.is_none() {
+                continue;
+            }
+
+            let result = user_tcp_service
+                .lock()
+                .unwrap()
+                .send_packet(packet.unwrap());
+
+            let depth = send_queue.remove_front();
+            event_sink
+                .submit_command(NETWORK_SERVICE_QUEUE_DEPTH_CHANGED, depth, Target::Auto)
+                .expect("failed to submit NETWORK_SERVICE_QUEUE_DEPTH_CHANGED command");
+
+            let error_msg = match result {
+                HandleMessageResult::Ok => continue,
+                HandleMessageResult::IOError(IoResult::Err(msg)) => msg,
+                HandleMessageResult::IOError(_) => return, // the TCP thread will handle FIN
+                HandleMessageResult::OtherErr(err) => err.to_string(),
+                HandleMessageResult::Timeout => unreachable!(), // only during the handshake
+            };
+
+            if user_tcp_service.lock().unwrap().is_disconnect_requested {
+                // the socket was shut down by us
+                return;
+            }
+
+            event_sink
+                .submit_command(
+                    NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                    format!("{} at [{}, {}]", error_msg, file!(), line!()),
+                    Target::Auto,
+                )
+                .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+            return;
+        }
+    }
+    fn log_disconnect(
+        config: &ClientConfig,
+        connected_at: DateTime<Local>,
+        disconnect_reason: String,
+        event_sink: &EventSink,
+    ) {
+        let now = Local::now();
+        let event = ConnectionEvent {
+            timestamp: now,
+            server: config.server_name.clone(),
+            port: config.server_port.parse::<u16>().unwrap_or(0),
+            connected_for_sec: (now - connected_at).num_seconds().max(0) as u64,
+            disconnect_reason,
+        };
+
+        if let Err(msg) = ConnectionLog::append_to_file(event) {
+            event_sink
+                .submit_command(
+                    NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                    format!("{} at [{}, {}]", msg, file!(), line!()),
+                    Target::Auto,
+                )
+                .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+        }
+    }
+    fn udp_send_queue_service(
+        send_queue: Arc<PriorityUdpQueue>,
+        udp_socket: UdpSocket,
+        health_check: Option<Arc<Mutex<UdpHealthCheck>>>,
+        event_sink: EventSink,
+    ) {
+        while !send_queue.is_closed() {
+            let packet = send_queue.wait_pop(Duration::from_millis(SEND_QUEUE_WAIT_TIMEOUT_MS));
+            if packet.is_none() {
+                continue;
+            }
+
+            if let Err(msg) = UserUdpService::send_to_socket(
+                &udp_socket,
+                &packet.unwrap(),
+                health_check.as_deref(),
+            ) {
+                if send_queue.is_closed() {
+                    // disconnected
+                    return;
+                }
+
+                event_sink
+                    .submit_command(
+                        NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                        format!("{}, at [{}, {}]", msg, file!(), line!()),
+                        Target::Auto,
+                    )
+                    .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+                return;
+            }
+        }
     }
     fn udp_service(
         username: String,
-        server_name: String,
-        server_port: String,
-        event_sink: ExtEventSink,
+        server_address: String,
+        event_sink: EventSink,
         user_udp_service: Arc<Mutex<UserUdpService>>,
         audio_service: Arc<Mutex<AudioService>>,
         push_to_talk_key: KeyCode,
         secret_key: [u8; SECRET_KEY_SIZE],
     ) {
-        let udp_socket = UdpSocket::bind("0.0.0.0:0");
-        if let Err(e) = udp_socket {
+        let request_stop = Arc::clone(&user_udp_service.lock().unwrap().request_stop);
+
+        let udp_socket = user_udp_service
+            .lock()
+            .unwrap()
+            .create_socket(&server_address);
+        if let Err(msg) = udp_socket {
             event_sink
                 .submit_command(
                     NETWORK_SERVICE_SYSTEM_IO_ERROR,
-                    format!(
-                        "UdpSocket::bind() failed, error: {}, at [{}, {}]",
-                        e,
-                        file!(),
-                        line!()
-                    ),
+                    format!("{}, at [{}, {}]", msg, file!(), line!()),
                     Target::Auto,
                 )
                 .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
             return;
         }
-        let udp_socket = udp_socket.unwrap();
+        let (udp_socket, warnings) = udp_socket.unwrap();
+
+        // not critical, the socket works (with the default or capped buffer sizes)
+        for warning in warnings {
+            warn!("{}", warning);
+            event_sink
+                .submit_command(
+                    NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                    format!("warning: {}", warning),
+                    Target::Auto,
+                )
+                .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+        }
 
         if let Err(e) = udp_socket.set_nonblocking(true) {
             event_sink
@@ -547,7 +1069,7 @@ impl NetService {
             return;
         }
 
-        if let Err(e) = udp_socket.connect(format!("{}:{}", server_name, server_port)) {
+        if let Err(e) = udp_socket.connect(&server_address) {
             event_sink
                 .submit_command(
                     NETWORK_SERVICE_SYSTEM_IO_ERROR,
@@ -601,6 +1123,56 @@ impl NetService {
             }
         }
 
+        // connect() received the first ping check, so the path is fine for now
+        let health_check = user_udp_service
+            .lock()
+            .unwrap()
+            .create_health_check()
+            .map(|health_check| Arc::new(Mutex::new(health_check)));
+
+        // Start sender thread.
+        let send_queue = Arc::new(PriorityUdpQueue::new(UDP_SEND_QUEUE_MAX_NORMAL_DEPTH));
+        let _send_queue_close_guard = PriorityUdpQueueCloseGuard(Arc::clone(&send_queue));
+        {
+            let res = udp_socket.try_clone();
+            if let Err(e) = res {
+                event_sink
+                    .submit_command(
+                        NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                        format!(
+                            "udp_socket.try_clone() failed, error: {}, at [{}, {}]",
+                            e,
+                            file!(),
+                            line!()
+                        ),
+                        Target::Auto,
+                    )
+                    .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+                return;
+            }
+            let sender_socket = res.unwrap();
+
+            user_udp_service
+                .lock()
+                .unwrap()
+                .set_send_queue(Arc::clone(&send_queue));
+
+            let send_queue_copy = Arc::clone(&send_queue);
+            let health_check_copy = health_check.clone();
+            let event_sink_copy = event_sink.clone();
+            thread::spawn(move || {
+                let event_sink = event_sink_copy.clone();
+                NetService::run_catching_panic(&event_sink, "UDP sender", move || {
+                    NetService::udp_send_queue_service(
+                        send_queue_copy,
+                        sender_socket,
+                        health_check_copy,
+                        event_sink_copy,
+                    )
+                });
+            });
+        }
+
         // Ready.
         {
             let audio_service_guard = audio_service.lock().unwrap();
@@ -608,10 +1180,11 @@ impl NetService {
                 push_to_talk_key,
                 Arc::clone(audio_service_guard.net_service.as_ref().unwrap()),
                 audio_service_guard.microphone_volume,
+                event_sink.clone(),
             );
         }
 
-        loop {
+        while !request_stop.load(Ordering::SeqCst) {
             let mut packet_size_buf = vec![0u8; std::mem::size_of::<u16>()];
             let mut _peek_len = 0usize;
 
@@ -625,9 +1198,31 @@ impl NetService {
                 }
                 match _res {
                     Ok(_bytes) => {
+                        if let Some(health_check) = health_check.as_ref() {
+                            health_check
+                                .lock()
+                                .unwrap()
+                                .on_packet_received(Instant::now());
+                        }
                         break;
                     }
                     Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        if request_stop.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        if let Some(health_check) = health_check.as_ref() {
+                            if health_check.lock().unwrap().check(Instant::now()) {
+                                // not critical, TCP still works (text chat, rooms, etc.)
+                                warn!("UDP connection appears blocked");
+                                event_sink
+                                    .submit_command(
+                                        NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                                        String::from("UDP connection appears blocked. Voice chat may not work."),
+                                        Target::Auto,
+                                    )
+                                    .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+                            }
+                        }
                         thread::sleep(Duration::from_millis(INTERVAL_UDP_MESSAGE_MS));
                         continue;
                     }
@@ -671,3 +1266,343 @@ impl NetService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::event_sink::SubmittedCommand;
+
+    #[test]
+    fn reconnect_delay_is_doubled() {
+        let policy = ReconnectPolicy {
+            max_attempts: 10,
+            base_delay_ms: 1000,
+        };
+
+        let delays: Vec<u64> = (0..7).map(|attempt| policy.get_delay_ms(attempt)).collect();
+
+        assert_eq!(delays, vec![1000, 2000, 4000, 8000, 16000, 32000, 60000]);
+    }
+
+    #[test]
+    fn reconnect_delay_is_capped_at_60_sec() {
+        let policy = ReconnectPolicy {
+            max_attempts: u8::MAX,
+            base_delay_ms: 1000,
+        };
+
+        for attempt in 6..=u8::MAX {
+            assert_eq!(policy.get_delay_ms(attempt), MAX_RECONNECT_DELAY_MS);
+        }
+
+        let policy = ReconnectPolicy {
+            max_attempts: 1,
+            base_delay_ms: u64::MAX,
+        };
+        assert_eq!(policy.get_delay_ms(0), MAX_RECONNECT_DELAY_MS);
+        assert_eq!(policy.get_delay_ms(1), MAX_RECONNECT_DELAY_MS);
+    }
+
+    #[test]
+    fn default_reconnect_policy() {
+        let policy = ReconnectPolicy::default();
+
+        assert_eq!(policy.get_delay_ms(0), DEFAULT_RECONNECT_BASE_DELAY_MS);
+        assert!(policy.get_delay_ms(DEFAULT_RECONNECT_MAX_ATTEMPTS - 1) <= MAX_RECONNECT_DELAY_MS);
+    }
+
+    fn connected_net_service(send_queue: &Arc<PriorityUdpQueue>) -> NetService {
+        let net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+
+        {
+            let mut user_udp_service_guard = net_service.user_udp_service.lock().unwrap();
+            user_udp_service_guard.assign_socket_and_name(
+                UdpSocket::bind("127.0.0.1:0").unwrap(),
+                String::from("user"),
+            );
+            user_udp_service_guard.set_send_queue(Arc::clone(send_queue));
+        }
+
+        net_service
+    }
+
+    fn get_queued_packet_count(send_queue: &PriorityUdpQueue) -> usize {
+        let mut count = 0;
+        while send_queue.wait_pop(Duration::from_millis(0)).is_some() {
+            count += 1;
+        }
+
+        count
+    }
+
+    #[test]
+    fn rapid_window_events_send_only_state_changes() {
+        let send_queue = Arc::new(PriorityUdpQueue::new(UDP_SEND_QUEUE_MAX_NORMAL_DEPTH));
+        let net_service = connected_net_service(&send_queue);
+
+        // 'true' if the window was minimized (see 'Delegate::event')
+        let window_events = [
+            true, true, false, false, true, false, true, true, true, false,
+        ];
+        for &is_minimized in window_events.iter() {
+            if is_minimized {
+                net_service.pause_voice_transmission();
+            } else {
+                net_service.resume_voice_transmission();
+            }
+
+            assert_eq!(net_service.is_voice_transmission_paused(), is_minimized);
+        }
+
+        // 3 pauses and 3 resumes
+        assert_eq!(get_queued_packet_count(&send_queue), 6);
+    }
+
+    #[test]
+    fn resume_without_pause_sends_nothing() {
+        let send_queue = Arc::new(PriorityUdpQueue::new(UDP_SEND_QUEUE_MAX_NORMAL_DEPTH));
+        let net_service = connected_net_service(&send_queue);
+
+        net_service.resume_voice_transmission();
+        net_service.resume_voice_transmission();
+
+        assert!(!net_service.is_voice_transmission_paused());
+        assert_eq!(get_queued_packet_count(&send_queue), 0);
+    }
+
+    fn get_submitted_errors(receiver: &mpsc::Receiver<SubmittedCommand>) -> Vec<String> {
+        receiver
+            .try_iter()
+            .filter_map(|command| command.get(NETWORK_SERVICE_SYSTEM_IO_ERROR).cloned())
+            .collect()
+    }
+
+    #[test]
+    fn panic_message_is_submitted() {
+        let (sender, receiver) = mpsc::channel();
+        let event_sink = EventSink::Channel(sender);
+
+        NetService::run_catching_panic(&event_sink, "TCP", || panic!("static message"));
+        NetService::run_catching_panic(&event_sink, "UDP", || panic!("formatted message {}", 42));
+        NetService::run_catching_panic(&event_sink, "UDP sender", || std::panic::panic_any(42u8));
+
+        assert_eq!(
+            get_submitted_errors(&receiver),
+            vec![
+                String::from("Internal error (TCP thread): static message"),
+                String::from("Internal error (UDP thread): formatted message 42"),
+                String::from("Internal error (UDP sender thread): unknown panic"),
+            ]
+        );
+    }
+
+    #[test]
+    fn panic_in_spawned_thread_is_submitted() {
+        let (sender, receiver) = mpsc::channel();
+        let event_sink = EventSink::Channel(sender);
+
+        let thread = thread::spawn(move || {
+            NetService::run_catching_panic(&event_sink, "voice recorder", || {
+                let chunk: Vec<i16> = Vec::new();
+                let _ = chunk[1]; // index out of bounds
+            });
+        });
+
+        // the thread finishes normally
+        assert!(thread.join().is_ok());
+
+        let errors = get_submitted_errors(&receiver);
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("Internal error (voice recorder thread): index out of bounds")
+        );
+    }
+
+    #[test]
+    fn nothing_is_submitted_without_panic() {
+        let (sender, receiver) = mpsc::channel();
+        let event_sink = EventSink::Channel(sender);
+        let mut is_called = false;
+
+        NetService::run_catching_panic(&event_sink, "TCP", || is_called = true);
+
+        assert!(is_called);
+        assert!(get_submitted_errors(&receiver).is_empty());
+    }
+
+    #[test]
+    fn custom_cooldown_gates_until_it_passes() {
+        let last_time = Local::now();
+
+        for (elapsed_ms, is_on_cooldown) in [
+            (0, true),
+            (1000, true),
+            (4999, true),
+            (5000, false),
+            (60000, false),
+        ]
+        .iter()
+        {
+            let now = last_time + chrono::Duration::milliseconds(*elapsed_ms);
+            assert_eq!(
+                NetService::is_on_cooldown(last_time, now, 5),
+                *is_on_cooldown,
+                "elapsed {} ms",
+                elapsed_ms
+            );
+        }
+    }
+
+    #[test]
+    fn zero_cooldown_is_disabled() {
+        let last_time = Local::now();
+
+        assert!(!NetService::is_on_cooldown(last_time, last_time, 0));
+    }
+
+    // Also returns the receiver of the submitted commands (keep it alive).
+    fn initialized_net_service(
+        user_config: UserConfig,
+    ) -> (NetService, mpsc::Receiver<SubmittedCommand>) {
+        let mut net_service = NetService::new(Arc::new(Mutex::new(user_config)));
+        let (sender, receiver) = mpsc::channel();
+        net_service.event_sink = Some(EventSink::Channel(sender));
+        net_service.audio_service = Some(Arc::new(Mutex::new(AudioService::default())));
+
+        (net_service, receiver)
+    }
+
+    #[test]
+    fn second_message_is_sent_after_custom_cooldown() {
+        let mut user_config = UserConfig::empty();
+        user_config.message_cooldown_sec = 5;
+        let (mut net_service, _receiver) = initialized_net_service(user_config);
+        net_service.last_time_text_message_sent = Local::now() - chrono::Duration::seconds(5);
+
+        assert!(net_service.send_user_message(String::from("first")).is_ok());
+        assert!(matches!(
+            net_service.send_user_message(String::from("second")),
+            Err(ActionError::SendMessagesTooQuick)
+        ));
+
+        // the first message was sent 4 seconds ago
+        net_service.last_time_text_message_sent = Local::now() - chrono::Duration::seconds(4);
+        assert!(matches!(
+            net_service.send_user_message(String::from("second")),
+            Err(ActionError::SendMessagesTooQuick)
+        ));
+
+        // and now 5 seconds ago
+        net_service.last_time_text_message_sent = Local::now() - chrono::Duration::seconds(5);
+        assert!(net_service
+            .send_user_message(String::from("second"))
+            .is_ok());
+
+        assert_eq!(net_service.send_queue.remove_front(), 1);
+    }
+
+    #[test]
+    fn messages_are_not_gated_without_cooldown() {
+        let mut user_config = UserConfig::empty();
+        user_config.message_cooldown_sec = 0;
+        let (mut net_service, _receiver) = initialized_net_service(user_config);
+
+        for i in 0..3 {
+            assert!(net_service
+                .send_user_message(format!("message {}", i))
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn is_initialized_requires_event_sink_and_audio_service() {
+        let mut net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+        assert!(!net_service.is_initialized());
+
+        net_service.audio_service = Some(Arc::new(Mutex::new(AudioService::default())));
+        assert!(!net_service.is_initialized());
+
+        let (net_service, _receiver) = initialized_net_service(UserConfig::empty());
+        assert!(net_service.is_initialized());
+    }
+
+    #[test]
+    #[should_panic(expected = "NetService is not initialized")]
+    fn enter_room_before_start_panics() {
+        let mut net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+
+        let _ = net_service.enter_room("Room", DEFAULT_ROOM_NAME);
+    }
+
+    #[test]
+    #[should_panic(expected = "NetService is not initialized")]
+    fn send_user_message_before_start_panics() {
+        let mut net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+
+        let _ = net_service.send_user_message(String::from("hello"));
+    }
+
+    #[test]
+    #[should_panic(expected = "event_sink is None; NetService::start() must be called first")]
+    fn resend_ping_before_start_panics() {
+        let net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+
+        net_service.resend_ping_later(UserPingInfo {
+            username: String::from("user"),
+            ping_ms: 0,
+            try_again_count: 1,
+        });
+    }
+
+    fn room_cooldown_config() -> UserConfig {
+        let mut user_config = UserConfig::empty();
+        user_config.room_change_lobby_cooldown_sec = 10;
+        user_config.room_change_same_tier_cooldown_sec = 3;
+        user_config
+    }
+
+    #[test]
+    fn lobby_cooldown_is_used_for_lobby_transitions() {
+        let user_config = room_cooldown_config();
+
+        assert_eq!(
+            NetService::get_room_change_cooldown_sec(&user_config, DEFAULT_ROOM_NAME, "Gaming"),
+            10
+        );
+        assert_eq!(
+            NetService::get_room_change_cooldown_sec(&user_config, "Gaming", DEFAULT_ROOM_NAME),
+            10
+        );
+    }
+
+    #[test]
+    fn same_tier_cooldown_is_used_between_rooms() {
+        let user_config = room_cooldown_config();
+
+        assert_eq!(
+            NetService::get_room_change_cooldown_sec(&user_config, "Gaming", "Music"),
+            3
+        );
+    }
+
+    #[test]
+    fn enter_room_applies_cooldown_of_transition() {
+        let (mut net_service, _receiver) = initialized_net_service(room_cooldown_config());
+        net_service.last_time_entered_room = Local::now() - chrono::Duration::seconds(5);
+
+        // 5 seconds passed: enough for room -> room, not enough for the lobby
+        assert!(matches!(
+            net_service.enter_room("Gaming", DEFAULT_ROOM_NAME),
+            Err(ActionError::ChangeRoomsTooQuick)
+        ));
+        assert!(matches!(
+            net_service.enter_room(DEFAULT_ROOM_NAME, "Gaming"),
+            Err(ActionError::ChangeRoomsTooQuick)
+        ));
+        // not connected, so the request itself fails
+        assert!(matches!(
+            net_service.enter_room("Music", "Gaming"),
+            Err(ActionError::SystemError(_))
+        ));
+    }
+}
@@ -0,0 +1,144 @@
+// External.
+use if_addrs::get_if_addrs;
+
+// Std.
+use std::net::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub ip: Ipv4Addr,
+}
+
+/// Returns non-loopback interfaces that have an IPv4 address
+/// (an interface with multiple addresses is returned multiple times).
+pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
+    let interfaces = get_if_addrs();
+    if let Err(e) = interfaces {
+        return Err(format!(
+            "failed to get network interfaces (error: {}) at [{}, {}]",
+            e,
+            file!(),
+            line!()
+        ));
+    }
+
+    Ok(interfaces
+        .unwrap()
+        .into_iter()
+        .filter(|interface| !interface.is_loopback())
+        .filter_map(|interface| match interface.ip() {
+            IpAddr::V4(ip) => Some(NetworkInterface {
+                name: interface.name,
+                ip,
+            }),
+            IpAddr::V6(_) => None,
+        })
+        .collect())
+}
+
+/// Returns sorted interface names without duplicates.
+pub fn get_network_interface_names(interfaces: &[NetworkInterface]) -> Vec<String> {
+    let mut names: Vec<String> = interfaces
+        .iter()
+        .map(|interface| interface.name.clone())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    names
+}
+
+/// Returns the IP of the interface with the specified name
+/// (the first one if the interface has multiple addresses).
+pub fn resolve_interface_ip(
+    interface_name: &str,
+    interfaces: &[NetworkInterface],
+) -> Option<Ipv4Addr> {
+    interfaces
+        .iter()
+        .find(|interface| interface.name == interface_name)
+        .map(|interface| interface.ip)
+}
+
+/// Returns the local IP that the OS uses to reach the server.
+/// Nothing is sent, 'connect' on a UDP socket only selects the route.
+pub fn get_route_ip(server_address: &str) -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect(server_address).ok()?;
+
+    socket.local_addr().ok().map(|address| address.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface(name: &str, ip: [u8; 4]) -> NetworkInterface {
+        NetworkInterface {
+            name: String::from(name),
+            ip: Ipv4Addr::from(ip),
+        }
+    }
+
+    #[test]
+    fn interface_ip_is_resolved_by_name() {
+        let interfaces = vec![
+            interface("Ethernet", [192, 168, 1, 10]),
+            interface("VPN", [10, 8, 0, 2]),
+        ];
+
+        assert_eq!(
+            resolve_interface_ip("VPN", &interfaces),
+            Some(Ipv4Addr::new(10, 8, 0, 2))
+        );
+        assert_eq!(resolve_interface_ip("vpn", &interfaces), None);
+        assert_eq!(resolve_interface_ip("", &interfaces), None);
+        assert_eq!(resolve_interface_ip("VPN", &[]), None);
+    }
+
+    #[test]
+    fn first_ip_of_interface_is_resolved() {
+        let interfaces = vec![
+            interface("Ethernet", [192, 168, 1, 10]),
+            interface("Ethernet", [192, 168, 1, 11]),
+        ];
+
+        assert_eq!(
+            resolve_interface_ip("Ethernet", &interfaces),
+            Some(Ipv4Addr::new(192, 168, 1, 10))
+        );
+    }
+
+    #[test]
+    fn interface_names_are_sorted_without_duplicates() {
+        let interfaces = vec![
+            interface("Wi-Fi", [192, 168, 0, 5]),
+            interface("Ethernet", [192, 168, 1, 10]),
+            interface("Ethernet", [192, 168, 1, 11]),
+        ];
+
+        assert_eq!(
+            get_network_interface_names(&interfaces),
+            vec![String::from("Ethernet"), String::from("Wi-Fi")]
+        );
+    }
+
+    #[test]
+    fn loopback_is_not_listed() {
+        let interfaces = get_network_interfaces().unwrap();
+
+        assert!(interfaces
+            .iter()
+            .all(|interface| !interface.ip.is_loopback()));
+    }
+
+    #[test]
+    fn route_to_local_server_goes_through_loopback() {
+        assert_eq!(
+            get_route_ip("127.0.0.1:51337"),
+            Some(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(get_route_ip("not an address"), None);
+    }
+}
@@ -0,0 +1,219 @@
+// Std.
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UdpPacketPriority {
+    High,   // ping answers and control packets
+    Normal, // voice
+}
+
+#[derive(Debug)]
+struct PriorityQueues {
+    high: VecDeque<Vec<u8>>,
+    normal: VecDeque<Vec<u8>>,
+}
+
+// Queue of already built UDP packets, drained by a dedicated sender thread.
+// High priority packets are always sent before normal ones so that
+// ping answers are not delayed by voice data (which would show a higher ping).
+#[derive(Debug)]
+pub struct PriorityUdpQueue {
+    queues: Mutex<PriorityQueues>,
+    new_packet: Condvar,
+    max_normal_queue_depth: usize,
+    is_closed: Mutex<bool>,
+}
+
+impl PriorityUdpQueue {
+    pub fn new(max_normal_queue_depth: usize) -> Self {
+        PriorityUdpQueue {
+            queues: Mutex::new(PriorityQueues {
+                high: VecDeque::new(),
+                normal: VecDeque::with_capacity(max_normal_queue_depth),
+            }),
+            new_packet: Condvar::new(),
+            max_normal_queue_depth,
+            is_closed: Mutex::new(false),
+        }
+    }
+    /// Returns 'Err' if the queue is closed or if the normal queue is full
+    /// (the sender can't keep up, the caller should drop the packet).
+    pub fn push(&self, packet: Vec<u8>, priority: UdpPacketPriority) -> Result<(), ()> {
+        if self.is_closed() {
+            return Err(());
+        }
+
+        let mut queues_guard = self.queues.lock().unwrap();
+        match priority {
+            UdpPacketPriority::High => queues_guard.high.push_back(packet),
+            UdpPacketPriority::Normal => {
+                if queues_guard.normal.len() >= self.max_normal_queue_depth {
+                    return Err(());
+                }
+                queues_guard.normal.push_back(packet);
+            }
+        }
+
+        self.new_packet.notify_one();
+
+        Ok(())
+    }
+    /// Waits up to 'timeout' for a packet, returns 'None' if nothing was queued
+    /// or if the queue was closed while waiting.
+    pub fn wait_pop(&self, timeout: Duration) -> Option<Vec<u8>> {
+        let queues_guard = self.queues.lock().unwrap();
+        let (mut queues_guard, _) = self
+            .new_packet
+            .wait_timeout_while(queues_guard, timeout, |queues| {
+                queues.high.is_empty() && queues.normal.is_empty() && !self.is_closed()
+            })
+            .unwrap();
+
+        if let Some(packet) = queues_guard.high.pop_front() {
+            return Some(packet);
+        }
+
+        queues_guard.normal.pop_front()
+    }
+    pub fn close(&self) {
+        *self.is_closed.lock().unwrap() = true;
+
+        let mut queues_guard = self.queues.lock().unwrap();
+        queues_guard.high.clear();
+        queues_guard.normal.clear();
+
+        self.new_packet.notify_all();
+    }
+    pub fn is_closed(&self) -> bool {
+        *self.is_closed.lock().unwrap()
+    }
+}
+
+// Closes the queue when the owner (UDP thread) finishes (on any return path),
+// this also stops the sender thread.
+pub struct PriorityUdpQueueCloseGuard(pub Arc<PriorityUdpQueue>);
+
+impl Drop for PriorityUdpQueueCloseGuard {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+
+    const NO_WAIT: Duration = Duration::from_millis(0);
+
+    fn drain(queue: &PriorityUdpQueue) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        while let Some(packet) = queue.wait_pop(NO_WAIT) {
+            packets.push(packet);
+        }
+        packets
+    }
+
+    #[test]
+    fn high_priority_is_popped_first() {
+        let queue = PriorityUdpQueue::new(10);
+
+        queue.push(vec![1], UdpPacketPriority::Normal).unwrap();
+        queue.push(vec![2], UdpPacketPriority::Normal).unwrap();
+        queue.push(vec![3], UdpPacketPriority::High).unwrap();
+        queue.push(vec![4], UdpPacketPriority::Normal).unwrap();
+        queue.push(vec![5], UdpPacketPriority::High).unwrap();
+
+        // in order within the same priority
+        assert_eq!(
+            drain(&queue),
+            vec![vec![3], vec![5], vec![1], vec![2], vec![4]]
+        );
+    }
+
+    #[test]
+    fn push_fails_when_normal_queue_is_full() {
+        let queue = PriorityUdpQueue::new(2);
+
+        queue.push(vec![1], UdpPacketPriority::Normal).unwrap();
+        queue.push(vec![2], UdpPacketPriority::Normal).unwrap();
+        assert!(queue.push(vec![3], UdpPacketPriority::Normal).is_err());
+
+        // high priority packets are not limited
+        queue.push(vec![4], UdpPacketPriority::High).unwrap();
+
+        // the dropped packet is not in the queue
+        assert_eq!(drain(&queue), vec![vec![4], vec![1], vec![2]]);
+
+        // there is space again
+        queue.push(vec![5], UdpPacketPriority::Normal).unwrap();
+        assert_eq!(drain(&queue), vec![vec![5]]);
+    }
+
+    #[test]
+    fn push_fails_when_closed() {
+        let queue = PriorityUdpQueue::new(10);
+        queue.push(vec![1], UdpPacketPriority::Normal).unwrap();
+        queue.push(vec![2], UdpPacketPriority::High).unwrap();
+
+        queue.close();
+
+        assert!(queue.is_closed());
+        assert!(queue.push(vec![3], UdpPacketPriority::Normal).is_err());
+        assert!(queue.push(vec![4], UdpPacketPriority::High).is_err());
+        assert_eq!(queue.wait_pop(NO_WAIT), None); // cleared
+    }
+
+    #[test]
+    fn wait_pop_times_out_on_empty_queue() {
+        let queue = PriorityUdpQueue::new(10);
+
+        let started = Instant::now();
+        assert_eq!(queue.wait_pop(Duration::from_millis(50)), None);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn wait_pop_wakes_up_on_push() {
+        let queue = Arc::new(PriorityUdpQueue::new(10));
+
+        let queue_copy = Arc::clone(&queue);
+        let pusher = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            queue_copy.push(vec![1], UdpPacketPriority::High).unwrap();
+        });
+
+        assert_eq!(queue.wait_pop(Duration::from_secs(10)), Some(vec![1]));
+        pusher.join().unwrap();
+    }
+
+    #[test]
+    fn close_wakes_up_waiting_sender() {
+        let queue = Arc::new(PriorityUdpQueue::new(10));
+
+        let queue_copy = Arc::clone(&queue);
+        let sender = thread::spawn(move || queue_copy.wait_pop(Duration::from_secs(10)));
+
+        thread::sleep(Duration::from_millis(20));
+        let started = Instant::now();
+        queue.close();
+
+        assert_eq!(sender.join().unwrap(), None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn close_guard_closes_queue() {
+        let queue = Arc::new(PriorityUdpQueue::new(10));
+
+        {
+            let _close_guard = PriorityUdpQueueCloseGuard(Arc::clone(&queue));
+            assert!(!queue.is_closed());
+        }
+
+        assert!(queue.is_closed());
+    }
+}
@@ -0,0 +1,144 @@
+const REPLAY_WINDOW_SIZE: u32 = 10; // older sequence numbers are rejected
+
+// Rejects replayed (or too old) packets of one sender by their sequence number.
+// Packets that came out of order are accepted if they are not older than
+// REPLAY_WINDOW_SIZE and were not received before.
+#[derive(Debug)]
+pub struct ReplayWindow {
+    last_seq: Option<u32>, // the biggest accepted sequence number
+    received: u64,         // bit N is set if 'last_seq - N' was accepted
+}
+
+impl ReplayWindow {
+    pub fn new() -> Self {
+        ReplayWindow {
+            last_seq: None,
+            received: 0,
+        }
+    }
+    /// Returns 'true' if the packet should be processed (and remembers it),
+    /// 'false' if it was already received or is too old.
+    pub fn accept(&mut self, seq: u32) -> bool {
+        let last_seq = match self.last_seq {
+            Some(last_seq) => last_seq,
+            None => {
+                self.last_seq = Some(seq);
+                self.received = 1;
+                return true;
+            }
+        };
+
+        if seq > last_seq {
+            let shift = seq - last_seq;
+            self.received = if shift >= u64::BITS {
+                0
+            } else {
+                self.received << shift
+            };
+            self.received |= 1;
+            self.last_seq = Some(seq);
+            return true;
+        }
+
+        let age = last_seq - seq;
+        if age > REPLAY_WINDOW_SIZE || self.received & (1 << age) != 0 {
+            return false;
+        }
+
+        self.received |= 1 << age;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_packet_is_accepted() {
+        let mut window = ReplayWindow::new();
+
+        assert!(window.accept(1000));
+    }
+
+    #[test]
+    fn duplicate_is_rejected() {
+        let mut window = ReplayWindow::new();
+
+        assert!(window.accept(5));
+        assert!(!window.accept(5));
+        assert!(window.accept(6));
+        assert!(!window.accept(6));
+        assert!(!window.accept(5));
+    }
+
+    #[test]
+    fn out_of_order_inside_window_is_accepted_once() {
+        let mut window = ReplayWindow::new();
+
+        assert!(window.accept(100));
+        assert!(window.accept(110));
+
+        for seq in 101..110 {
+            assert!(window.accept(seq));
+            assert!(!window.accept(seq));
+        }
+    }
+
+    #[test]
+    fn window_is_plus_minus_10() {
+        let mut window = ReplayWindow::new();
+
+        assert!(window.accept(50));
+        assert!(window.accept(50 + REPLAY_WINDOW_SIZE)); // newer packets are always accepted
+
+        assert!(window.accept(51));
+        assert!(!window.accept(50)); // already received, age is exactly REPLAY_WINDOW_SIZE
+
+        let mut window = ReplayWindow::new();
+        assert!(window.accept(50));
+        assert!(window.accept(50 + REPLAY_WINDOW_SIZE + 1));
+        assert!(!window.accept(50)); // too old
+        assert!(window.accept(51)); // age is exactly REPLAY_WINDOW_SIZE
+        assert!(!window.accept(49)); // too old, never received
+    }
+
+    #[test]
+    fn old_packets_are_rejected_after_shift_of_64_and_more() {
+        for shift in [63, 64, 65, 1000] {
+            let mut window = ReplayWindow::new();
+
+            assert!(window.accept(10));
+            assert!(window.accept(10 + shift));
+
+            // the old bits are gone, recent sequence numbers are still accepted
+            assert!(!window.accept(10));
+            assert!(window.accept(10 + shift - 1));
+            assert!(!window.accept(10 + shift));
+        }
+    }
+
+    #[test]
+    fn shift_near_u32_max_does_not_overflow() {
+        let mut window = ReplayWindow::new();
+
+        assert!(window.accept(0));
+        assert!(window.accept(u32::MAX));
+        assert!(!window.accept(u32::MAX));
+        assert!(!window.accept(0));
+        assert!(window.accept(u32::MAX - REPLAY_WINDOW_SIZE));
+    }
+
+    #[test]
+    fn new_window_accepts_restarted_sequence() {
+        let mut window = ReplayWindow::new();
+
+        assert!(window.accept(500));
+        assert!(!window.accept(0));
+
+        // the user connected again (see 'UserUdpService::remove_replay_window')
+        let mut window = ReplayWindow::new();
+        assert!(window.accept(0));
+        assert!(window.accept(1));
+    }
+}
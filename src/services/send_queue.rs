@@ -0,0 +1,208 @@
+// Std.
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// Bounded queue of already built TCP packets, drained by a dedicated sender thread
+// so that the UI thread never blocks on the socket.
+pub struct SendQueue {
+    packets: Mutex<VecDeque<Vec<u8>>>,
+    new_packet: Condvar,
+    capacity: usize,
+    is_closed: Mutex<bool>,
+}
+
+impl SendQueue {
+    pub fn new(capacity: usize) -> Self {
+        SendQueue {
+            packets: Mutex::new(VecDeque::with_capacity(capacity)),
+            new_packet: Condvar::new(),
+            capacity,
+            is_closed: Mutex::new(false),
+        }
+    }
+    /// Returns queue depth after the push or 'Err' if the queue is full (or closed).
+    pub fn push(&self, packet: Vec<u8>) -> Result<usize, ()> {
+        if self.is_closed() {
+            return Err(());
+        }
+
+        let mut packets_guard = self.packets.lock().unwrap();
+        if packets_guard.len() >= self.capacity {
+            return Err(());
+        }
+
+        packets_guard.push_back(packet);
+        let depth = packets_guard.len();
+
+        self.new_packet.notify_one();
+
+        Ok(depth)
+    }
+    /// Waits up to 'timeout' for a packet, returns 'None' if nothing was queued.
+    /// The packet stays in the queue until 'remove_front' is called so that
+    /// the depth reflects packets that were not sent yet.
+    pub fn wait_front(&self, timeout: Duration) -> Option<Vec<u8>> {
+        let packets_guard = self.packets.lock().unwrap();
+        let (packets_guard, _) = self
+            .new_packet
+            .wait_timeout_while(packets_guard, timeout, |packets| packets.is_empty())
+            .unwrap();
+
+        packets_guard.front().cloned()
+    }
+    /// Returns queue depth after the removal.
+    pub fn remove_front(&self) -> usize {
+        let mut packets_guard = self.packets.lock().unwrap();
+        packets_guard.pop_front();
+        packets_guard.len()
+    }
+    pub fn close(&self) {
+        *self.is_closed.lock().unwrap() = true;
+        self.packets.lock().unwrap().clear();
+        self.new_packet.notify_all();
+    }
+    pub fn is_closed(&self) -> bool {
+        *self.is_closed.lock().unwrap()
+    }
+}
+
+// Closes the queue when the owner (TCP thread) finishes (on any return path),
+// this also stops the sender thread.
+pub struct SendQueueCloseGuard(pub Arc<SendQueue>);
+
+impl Drop for SendQueueCloseGuard {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    const WAIT_TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn full_queue_rejects_packets() {
+        let queue = SendQueue::new(32);
+
+        for i in 0..32 {
+            assert_eq!(queue.push(vec![i as u8]), Ok(i + 1));
+        }
+        assert_eq!(queue.push(vec![32]), Err(()));
+
+        // one free slot after a packet is sent
+        assert_eq!(queue.remove_front(), 31);
+        assert_eq!(queue.push(vec![32]), Ok(32));
+        assert_eq!(queue.push(vec![33]), Err(()));
+    }
+
+    #[test]
+    fn packets_are_drained_in_order() {
+        let queue = SendQueue::new(32);
+
+        for round in 0..10u8 {
+            for i in 0..32u8 {
+                queue.push(vec![round, i]).unwrap();
+            }
+            for i in 0..32u8 {
+                assert_eq!(queue.wait_front(WAIT_TIMEOUT), Some(vec![round, i]));
+                assert_eq!(queue.remove_front(), 31 - i as usize);
+            }
+        }
+
+        assert_eq!(queue.wait_front(Duration::from_millis(1)), None);
+    }
+
+    #[test]
+    fn front_stays_until_removed() {
+        let queue = SendQueue::new(4);
+        queue.push(vec![1]).unwrap();
+        queue.push(vec![2]).unwrap();
+
+        assert_eq!(queue.wait_front(WAIT_TIMEOUT), Some(vec![1]));
+        assert_eq!(queue.wait_front(WAIT_TIMEOUT), Some(vec![1]));
+        assert_eq!(queue.remove_front(), 1);
+        assert_eq!(queue.wait_front(WAIT_TIMEOUT), Some(vec![2]));
+    }
+
+    #[test]
+    fn stress_producers_and_sender_thread() {
+        const PRODUCER_COUNT: u8 = 4;
+        const PACKETS_PER_PRODUCER: u8 = 200;
+
+        let queue = Arc::new(SendQueue::new(32));
+
+        let sender_queue = Arc::clone(&queue);
+        let sender = thread::spawn(move || {
+            let mut sent = Vec::new();
+            while sent.len() < PRODUCER_COUNT as usize * PACKETS_PER_PRODUCER as usize {
+                if let Some(packet) = sender_queue.wait_front(WAIT_TIMEOUT) {
+                    sent.push(packet);
+                    sender_queue.remove_front();
+                }
+            }
+            sent
+        });
+
+        let producers: Vec<_> = (0..PRODUCER_COUNT)
+            .map(|producer| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for i in 0..PACKETS_PER_PRODUCER {
+                        // retry like the UI would when the queue is full
+                        while queue.push(vec![producer, i]).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        let sent = sender.join().unwrap();
+
+        // every packet is sent once and the order of each producer is kept
+        for producer in 0..PRODUCER_COUNT {
+            let producer_packets: Vec<u8> = sent
+                .iter()
+                .filter(|packet| packet[0] == producer)
+                .map(|packet| packet[1])
+                .collect();
+            assert_eq!(
+                producer_packets,
+                (0..PACKETS_PER_PRODUCER).collect::<Vec<u8>>()
+            );
+        }
+        assert_eq!(queue.remove_front(), 0);
+    }
+
+    #[test]
+    fn closed_queue_rejects_and_drops_packets() {
+        let queue = SendQueue::new(32);
+        queue.push(vec![1]).unwrap();
+
+        queue.close();
+
+        assert!(queue.is_closed());
+        assert_eq!(queue.push(vec![2]), Err(()));
+        assert_eq!(queue.wait_front(Duration::from_millis(1)), None);
+    }
+
+    #[test]
+    fn close_guard_closes_queue_on_drop() {
+        let queue = Arc::new(SendQueue::new(32));
+
+        {
+            let _guard = SendQueueCloseGuard(Arc::clone(&queue));
+            assert!(!queue.is_closed());
+        }
+
+        assert!(queue.is_closed());
+    }
+}
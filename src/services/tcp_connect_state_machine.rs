@@ -0,0 +1,542 @@
+// Std.
+use std::collections::VecDeque;
+use std::sync::mpsc::Sender;
+
+// Custom.
+use super::tcp_packets::*;
+use super::user_tcp_service::*;
+use crate::global_params::*;
+use crate::misc::packet_builder::PacketBuilder;
+
+#[derive(Debug, PartialEq)]
+pub enum TcpConnectState {
+    SendingConnectPacket,
+    ReceivingAnswerSize,
+    ReceivingAnswer(u64),             // answer size
+    ReceivingRoomInfo(usize),         // rooms left in the answer
+    ReceivingUserInfo(String, usize), // room, users left in this room
+    Done,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StatePollResult {
+    Ok,         // switched to the next state
+    WouldBlock, // socket is not ready, poll again later
+    Finished(ConnectResult),
+}
+
+// Connect request as a sequence of states, each 'poll' does one non-blocking step
+// so that it can be driven by an event loop (see UserTcpService::connect_user for a simple loop).
+pub struct TcpConnectStateMachine {
+    state: TcpConnectState,
+    connect_packet: Vec<u8>,
+    rooms: VecDeque<RoomNetInfo>,
+    users: VecDeque<UserNetInfo>, // of the current room
+}
+
+impl TcpConnectStateMachine {
+    pub fn new(connect_packet: Vec<u8>) -> Self {
+        TcpConnectStateMachine {
+            state: TcpConnectState::SendingConnectPacket,
+            connect_packet,
+            rooms: VecDeque::new(),
+            users: VecDeque::new(),
+        }
+    }
+    pub fn get_state(&self) -> &TcpConnectState {
+        &self.state
+    }
+    pub fn poll(
+        &mut self,
+        tcp_service: &mut UserTcpService,
+        info_sender: &Sender<ConnectInfo>,
+    ) -> StatePollResult {
+        match &self.state {
+            TcpConnectState::SendingConnectPacket => self.send_connect_packet(tcp_service),
+            TcpConnectState::ReceivingAnswerSize => self.receive_answer_size(tcp_service),
+            TcpConnectState::ReceivingAnswer(answer_size) => {
+                let answer_size = *answer_size;
+                self.receive_answer(tcp_service, answer_size)
+            }
+            TcpConnectState::ReceivingRoomInfo(_) => {
+                self.receive_room_info(tcp_service, info_sender)
+            }
+            TcpConnectState::ReceivingUserInfo(room_name, _) => {
+                let room_name = room_name.clone();
+                self.receive_user_info(room_name, info_sender)
+            }
+            TcpConnectState::Done => StatePollResult::Finished(ConnectResult::Ok),
+        }
+    }
+    fn send_connect_packet(&mut self, tcp_service: &mut UserTcpService) -> StatePollResult {
+        match tcp_service.write_to_socket(&self.connect_packet) {
+            IoResult::WouldBlock => StatePollResult::WouldBlock,
+            IoResult::Ok(_bytes) => {
+                self.state = TcpConnectState::ReceivingAnswerSize;
+                StatePollResult::Ok
+            }
+            res => StatePollResult::Finished(ConnectResult::IoErr(res)),
+        }
+    }
+    fn receive_answer_size(&mut self, tcp_service: &mut UserTcpService) -> StatePollResult {
+        // We usually use 'u16' as size of the data
+        // but this "packet" is an exception.
+        let mut data_size_buf = vec![0u8; std::mem::size_of::<u64>()];
+        match tcp_service.read_from_socket(&mut data_size_buf) {
+            IoResult::WouldBlock => return StatePollResult::WouldBlock,
+            IoResult::Ok(_bytes) => {}
+            res => return StatePollResult::Finished(ConnectResult::IoErr(res)),
+        }
+
+        let data_size = bincode::deserialize::<u64>(&data_size_buf).unwrap();
+        if data_size > TCP_CONNECT_ANSWER_PACKET_MAX_SIZE {
+            return StatePollResult::Finished(ConnectResult::IoErr(IoResult::Err(format!(
+                "The data size received from the server ({}) exceeds the maximum ({}), at [{}, {}].",
+                data_size,
+                TCP_CONNECT_ANSWER_PACKET_MAX_SIZE,
+                file!(),
+                line!()
+            ))));
+        }
+
+        self.state = TcpConnectState::ReceivingAnswer(data_size);
+        StatePollResult::Ok
+    }
+    fn receive_answer(
+        &mut self,
+        tcp_service: &mut UserTcpService,
+        answer_size: u64,
+    ) -> StatePollResult {
+        let mut data_buf = vec![0u8; answer_size as usize];
+        match tcp_service.read_from_socket(&mut data_buf) {
+            IoResult::WouldBlock => return StatePollResult::WouldBlock,
+            IoResult::Ok(_bytes) => {}
+            res => return StatePollResult::Finished(ConnectResult::IoErr(res)),
+        }
+
+        let server_connect_packet = PacketBuilder::parse_encrypted_packet::<ServerTcpConnectPacket>(
+            data_buf,
+            &tcp_service.secret_key,
+        );
+        if let Err(e) = server_connect_packet {
+            let result = match e {
+                HandleMessageResult::IOError(err) => ConnectResult::IoErr(err),
+                HandleMessageResult::OtherErr(msg) => ConnectResult::Err(msg.to_string()),
+                HandleMessageResult::Timeout => ConnectResult::ErrTimeout,
+                HandleMessageResult::Ok => unreachable!(),
+            };
+            return StatePollResult::Finished(result);
+        }
+        let server_connect_packet = server_connect_packet.unwrap();
+
+        // See answer.
+        let result = match server_connect_packet.answer {
+            ConnectServerAnswer::Ok => None,
+            ConnectServerAnswer::WrongPassword => {
+                Some(ConnectResult::SleepWithErr(PASSWORD_RETRY_DELAY_SEC))
+            }
+            ConnectServerAnswer::WrongVersion => Some(ConnectResult::WrongProtocol(
+                server_connect_packet.correct_net_protocol.unwrap(),
+            )),
+            ConnectServerAnswer::UsernameTaken => Some(ConnectResult::UsernameTaken),
+            ConnectServerAnswer::ServerIsFull => Some(ConnectResult::ErrServerIsFull),
+        };
+        if let Some(result) = result {
+            return StatePollResult::Finished(result);
+        }
+
+        // Info about all rooms and users.
+        self.rooms = server_connect_packet.connected_info.unwrap().into();
+
+        self.state = TcpConnectState::ReceivingRoomInfo(self.rooms.len());
+        StatePollResult::Ok
+    }
+    fn receive_room_info(
+        &mut self,
+        tcp_service: &mut UserTcpService,
+        info_sender: &Sender<ConnectInfo>,
+    ) -> StatePollResult {
+        let room_info = self.rooms.pop_front();
+        if room_info.is_none() {
+            info_sender.send(ConnectInfo::End).unwrap(); // End.
+
+            tcp_service.user_state = UserState::Connected;
+
+            self.state = TcpConnectState::Done;
+            return StatePollResult::Finished(ConnectResult::Ok);
+        }
+        let room_info = room_info.unwrap();
+
+        info_sender
+            .send(ConnectInfo::RoomInfo(room_info.room_name.clone()))
+            .unwrap();
+
+        self.users = room_info.users.into();
+
+        self.state = TcpConnectState::ReceivingUserInfo(room_info.room_name, self.users.len());
+        StatePollResult::Ok
+    }
+    fn receive_user_info(
+        &mut self,
+        room_name: String,
+        info_sender: &Sender<ConnectInfo>,
+    ) -> StatePollResult {
+        let user = self.users.pop_front();
+        if user.is_none() {
+            self.state = TcpConnectState::ReceivingRoomInfo(self.rooms.len());
+            return StatePollResult::Ok;
+        }
+        let user = user.unwrap();
+
+        info_sender
+            .send(ConnectInfo::UserInfo(
+                UserInfo::new(user.username),
+                room_name.clone(),
+                user.ping,
+            ))
+            .unwrap();
+
+        self.state = TcpConnectState::ReceivingUserInfo(room_name, self.users.len());
+        StatePollResult::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::prelude::*;
+    use std::net::{Shutdown, TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const SECRET_KEY: [u8; SECRET_KEY_SIZE] = [7; SECRET_KEY_SIZE];
+    const POLL_TIMEOUT: Duration = Duration::from_secs(5);
+
+    // Non-blocking client socket (owned by the service) and the server side of the connection.
+    fn connected_service() -> (UserTcpService, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let mut tcp_service = UserTcpService::new(String::new());
+        tcp_service.tcp_socket = Some(client);
+        tcp_service.secret_key = SECRET_KEY;
+
+        (tcp_service, server)
+    }
+
+    // Polls again while the data sent by the server has not arrived yet.
+    fn poll_until_ready(
+        state_machine: &mut TcpConnectStateMachine,
+        tcp_service: &mut UserTcpService,
+        info_sender: &Sender<ConnectInfo>,
+    ) -> StatePollResult {
+        let deadline = Instant::now() + POLL_TIMEOUT;
+        loop {
+            let result = state_machine.poll(tcp_service, info_sender);
+            if result != StatePollResult::WouldBlock {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "the socket was never ready");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    // Sends the answer like the server does: (u64) size and the encrypted packet.
+    fn send_answer(server: &mut TcpStream, answer: &ServerTcpConnectPacket) {
+        let packet = PacketBuilder::build_legacy_encrypted_packet(answer, &SECRET_KEY).unwrap();
+        let packet = &packet[std::mem::size_of::<u16>()..];
+        server
+            .write_all(&bincode::serialize(&(packet.len() as u64)).unwrap())
+            .unwrap();
+        server.write_all(packet).unwrap();
+    }
+
+    fn ok_answer(rooms: Vec<RoomNetInfo>) -> ServerTcpConnectPacket {
+        ServerTcpConnectPacket {
+            answer: ConnectServerAnswer::Ok,
+            correct_net_protocol: None,
+            connected_info: Some(rooms),
+        }
+    }
+
+    fn room(room_name: &str, usernames: &[&str]) -> RoomNetInfo {
+        RoomNetInfo {
+            room_name: String::from(room_name),
+            users: usernames
+                .iter()
+                .map(|username| UserNetInfo {
+                    username: String::from(*username),
+                    ping: 42,
+                })
+                .collect(),
+        }
+    }
+
+    // Runs the state machine from the start until it finishes.
+    fn run_until_finished(
+        state_machine: &mut TcpConnectStateMachine,
+        tcp_service: &mut UserTcpService,
+        info_sender: &Sender<ConnectInfo>,
+    ) -> ConnectResult {
+        loop {
+            if let StatePollResult::Finished(result) =
+                poll_until_ready(state_machine, tcp_service, info_sender)
+            {
+                return result;
+            }
+        }
+    }
+
+    fn received_info(info_receiver: &Receiver<ConnectInfo>) -> Vec<String> {
+        info_receiver
+            .try_iter()
+            .map(|info| match info {
+                ConnectInfo::RoomInfo(room_name) => format!("room {}", room_name),
+                ConnectInfo::UserInfo(user_info, room_name, ping) => {
+                    format!("user {} in {} ({})", user_info.username, room_name, ping)
+                }
+                ConnectInfo::End => String::from("end"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn connect_packet_is_sent() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (info_sender, _info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![1, 2, 3, 4]);
+
+        assert_eq!(
+            state_machine.poll(&mut tcp_service, &info_sender),
+            StatePollResult::Ok
+        );
+        assert_eq!(
+            state_machine.get_state(),
+            &TcpConnectState::ReceivingAnswerSize
+        );
+
+        let mut buf = [0u8; 4];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn would_block_until_answer_size_arrives() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (info_sender, _info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+        state_machine.poll(&mut tcp_service, &info_sender);
+
+        // The server did not answer yet, the state should not change.
+        for _ in 0..3 {
+            assert_eq!(
+                state_machine.poll(&mut tcp_service, &info_sender),
+                StatePollResult::WouldBlock
+            );
+            assert_eq!(
+                state_machine.get_state(),
+                &TcpConnectState::ReceivingAnswerSize
+            );
+        }
+
+        server
+            .write_all(&bincode::serialize(&100u64).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            poll_until_ready(&mut state_machine, &mut tcp_service, &info_sender),
+            StatePollResult::Ok
+        );
+        assert_eq!(
+            state_machine.get_state(),
+            &TcpConnectState::ReceivingAnswer(100)
+        );
+
+        // The answer itself was not sent yet.
+        assert_eq!(
+            state_machine.poll(&mut tcp_service, &info_sender),
+            StatePollResult::WouldBlock
+        );
+        assert_eq!(
+            state_machine.get_state(),
+            &TcpConnectState::ReceivingAnswer(100)
+        );
+    }
+
+    #[test]
+    fn too_big_answer_size_finishes_with_error() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (info_sender, _info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+        state_machine.poll(&mut tcp_service, &info_sender);
+
+        server
+            .write_all(&bincode::serialize(&(TCP_CONNECT_ANSWER_PACKET_MAX_SIZE + 1)).unwrap())
+            .unwrap();
+
+        match poll_until_ready(&mut state_machine, &mut tcp_service, &info_sender) {
+            StatePollResult::Finished(ConnectResult::IoErr(IoResult::Err(_))) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn closed_connection_finishes_with_fin() {
+        let (mut tcp_service, server) = connected_service();
+        let (info_sender, _info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+        state_machine.poll(&mut tcp_service, &info_sender);
+
+        server.shutdown(Shutdown::Write).unwrap();
+
+        assert_eq!(
+            poll_until_ready(&mut state_machine, &mut tcp_service, &info_sender),
+            StatePollResult::Finished(ConnectResult::IoErr(IoResult::FIN))
+        );
+    }
+
+    #[test]
+    fn rooms_and_users_are_reported_in_order() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (info_sender, info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+
+        send_answer(
+            &mut server,
+            &ok_answer(vec![
+                room("Lobby", &["user1", "user2"]),
+                room("Empty", &[]),
+                room("Room 2", &["user3"]),
+            ]),
+        );
+
+        // Each room and each user takes one step.
+        let expected_states = vec![
+            TcpConnectState::ReceivingAnswerSize,
+            TcpConnectState::ReceivingAnswer(0), // size is checked below
+            TcpConnectState::ReceivingRoomInfo(3),
+            TcpConnectState::ReceivingUserInfo(String::from("Lobby"), 2),
+            TcpConnectState::ReceivingUserInfo(String::from("Lobby"), 1),
+            TcpConnectState::ReceivingUserInfo(String::from("Lobby"), 0),
+            TcpConnectState::ReceivingRoomInfo(2),
+            TcpConnectState::ReceivingUserInfo(String::from("Empty"), 0),
+            TcpConnectState::ReceivingRoomInfo(1),
+            TcpConnectState::ReceivingUserInfo(String::from("Room 2"), 1),
+            TcpConnectState::ReceivingUserInfo(String::from("Room 2"), 0),
+            TcpConnectState::ReceivingRoomInfo(0),
+        ];
+        for expected_state in expected_states {
+            assert_eq!(
+                poll_until_ready(&mut state_machine, &mut tcp_service, &info_sender),
+                StatePollResult::Ok
+            );
+            match (state_machine.get_state(), &expected_state) {
+                (TcpConnectState::ReceivingAnswer(size), TcpConnectState::ReceivingAnswer(_)) => {
+                    assert!(*size > 0)
+                }
+                (state, expected_state) => assert_eq!(state, expected_state),
+            }
+        }
+
+        assert!(matches!(tcp_service.user_state, UserState::NotConnected));
+        assert_eq!(
+            state_machine.poll(&mut tcp_service, &info_sender),
+            StatePollResult::Finished(ConnectResult::Ok)
+        );
+        assert_eq!(state_machine.get_state(), &TcpConnectState::Done);
+        assert!(matches!(tcp_service.user_state, UserState::Connected));
+
+        assert_eq!(
+            received_info(&info_receiver),
+            vec![
+                "room Lobby",
+                "user user1 in Lobby (42)",
+                "user user2 in Lobby (42)",
+                "room Empty",
+                "room Room 2",
+                "user user3 in Room 2 (42)",
+                "end",
+            ]
+        );
+    }
+
+    #[test]
+    fn not_ok_answer_finishes_without_info() {
+        let cases = vec![
+            (
+                ConnectServerAnswer::UsernameTaken,
+                ConnectResult::UsernameTaken,
+            ),
+            (
+                ConnectServerAnswer::ServerIsFull,
+                ConnectResult::ErrServerIsFull,
+            ),
+            (
+                ConnectServerAnswer::WrongPassword,
+                ConnectResult::SleepWithErr(PASSWORD_RETRY_DELAY_SEC),
+            ),
+        ];
+
+        for (answer, expected_result) in cases {
+            let (mut tcp_service, mut server) = connected_service();
+            let (info_sender, info_receiver) = mpsc::channel();
+            let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+
+            send_answer(
+                &mut server,
+                &ServerTcpConnectPacket {
+                    answer,
+                    correct_net_protocol: None,
+                    connected_info: None,
+                },
+            );
+
+            assert_eq!(
+                run_until_finished(&mut state_machine, &mut tcp_service, &info_sender),
+                expected_result
+            );
+            assert!(received_info(&info_receiver).is_empty());
+            assert!(matches!(tcp_service.user_state, UserState::NotConnected));
+        }
+    }
+
+    #[test]
+    fn wrong_version_answer_returns_needed_protocol() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (info_sender, _info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+
+        send_answer(
+            &mut server,
+            &ServerTcpConnectPacket {
+                answer: ConnectServerAnswer::WrongVersion,
+                correct_net_protocol: Some(12),
+                connected_info: None,
+            },
+        );
+
+        assert_eq!(
+            run_until_finished(&mut state_machine, &mut tcp_service, &info_sender),
+            ConnectResult::WrongProtocol(12)
+        );
+    }
+
+    #[test]
+    fn answer_with_wrong_key_finishes_with_error() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (info_sender, info_receiver) = mpsc::channel();
+        let mut state_machine = TcpConnectStateMachine::new(vec![0]);
+        tcp_service.secret_key = [8; SECRET_KEY_SIZE];
+
+        send_answer(&mut server, &ok_answer(vec![room("Lobby", &["user1"])]));
+
+        match run_until_finished(&mut state_machine, &mut tcp_service, &info_sender) {
+            ConnectResult::Err(_) | ConnectResult::IoErr(_) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(received_info(&info_receiver).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 // This file should be exactly like in the server.
 
+use num_derive::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
 
 // there's no such thing as a packet in TCP (it's all just a stream)
@@ -13,6 +14,25 @@ pub const TCP_PACKET_MAX_SIZE: u16 = 1400;
 // that will be enough
 pub const TCP_CONNECT_ANSWER_PACKET_MAX_SIZE: u64 = std::u16::MAX as u64;
 
+// every packet after the connect packet starts with a frame header,
+// the version tells how to read the encrypted payload so that new payload formats
+// can be added without breaking old clients (they skip packets of unknown versions)
+pub const TCP_FRAME_MAGIC: u16 = 0x5E1E;
+pub const TCP_FRAME_HEADER_SIZE: usize = 5; // (bincode) magic + version + payload size
+pub const TCP_FRAME_VERSION_LEGACY: u8 = 0; // IV + encrypted (serialized message + CMAC tag)
+pub const TCP_FRAME_VERSION_EXTENDED: u8 = 1; // reserved for new payload formats (e.g. Opus)
+
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+// ----------------------------------------------------------------------------
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct FrameHeader {
+    pub magic: u16,
+    pub version: u8,
+    pub payload_size: u16,
+}
+
 // ----------------------------------------------------------------------------
 // ----------------------------------------------------------------------------
 // ----------------------------------------------------------------------------
@@ -30,11 +50,18 @@ pub enum ConnectServerAnswer {
 // ----------------------------------------------------------------------------
 // ----------------------------------------------------------------------------
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, FromPrimitive, ToPrimitive)]
+pub enum VoiceCodec {
+    Pcm = 0, // raw samples
+    Opus = 1,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ClientConnectPacket {
     pub net_protocol_version: u64,
     pub username: String,
     pub password: String,
+    pub voice_codec: VoiceCodec, // last so that the old servers can still check the version
 }
 
 // ----------------------------------------------------------------------------
@@ -72,13 +99,36 @@ pub enum ServerTcpMessage {
     },
     UserMessage {
         username: String,
+        message_id: u64, // assigned by the server, used to reference the message (for reactions)
         message: String,
     },
     UserEntersRoom {
         username: String,
         room_enters: String,
     },
+    RoomDescription {
+        room_name: String,
+        description: String,
+    },
     KeepAliveCheck,
+    UserStatusMessage {
+        username: String,
+        text: String,
+    },
+    MessageReaction {
+        message_id: u64,
+        username: String,
+        emoji: String,
+    },
+    MessagePinned {
+        message_id: u64,
+        author: String,
+        text: String,
+    },
+    RoomInvite {
+        inviter_username: String,
+        room_name: String,
+    },
 }
 
 // ----------------------------------------------------------------------------
@@ -87,9 +137,27 @@ pub enum ServerTcpMessage {
 
 #[derive(Serialize, Deserialize)]
 pub enum ClientTcpMessage {
-    UserMessage { message: String },
-    UserEnterRoom { room_name: String },
+    UserMessage {
+        message: String,
+    },
+    UserEnterRoom {
+        room_name: String,
+    },
     KeepAliveCheck,
+    StatusMessage {
+        text: String,
+    },
+    MessageReaction {
+        message_id: u64,
+        emoji: String,
+    },
+    PinMessage {
+        message_id: u64,
+    },
+    InviteToRoom {
+        target_username: String,
+        room_name: String,
+    },
 }
 
 // ----------------------------------------------------------------------------
@@ -0,0 +1,159 @@
+// Std.
+use std::time::{Duration, Instant};
+
+// Classic token bucket: 'capacity' bytes at most, refilled with 'refill_rate' bytes per second.
+// Refill is calculated from the elapsed time on each check (no background timer).
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: u64,
+    refill_rate: u64,
+    tokens: u64,
+    last_refill: Instant,
+    nanos_carry: u128, // elapsed time that was not enough for a whole token
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u64, refill_rate: u64) -> Self {
+        TokenBucket {
+            capacity,
+            refill_rate,
+            tokens: capacity,
+            last_refill: Instant::now(),
+            nanos_carry: 0,
+        }
+    }
+    // Returns 'true' if there were enough tokens (they are consumed),
+    // otherwise leaves the bucket untouched and returns 'false'.
+    pub fn try_consume(&mut self, amount: u64) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill);
+        self.last_refill = now;
+        self.refill(elapsed);
+
+        if self.tokens < amount {
+            return false;
+        }
+
+        self.tokens -= amount;
+
+        true
+    }
+    fn refill(&mut self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos() + self.nanos_carry;
+        let new_tokens = nanos * self.refill_rate as u128 / 1_000_000_000;
+
+        if self.refill_rate == 0 {
+            self.nanos_carry = 0;
+        } else {
+            self.nanos_carry = nanos - new_tokens * 1_000_000_000 / self.refill_rate as u128;
+        }
+
+        let tokens = self.tokens as u128 + new_tokens;
+        if tokens >= self.capacity as u128 {
+            self.tokens = self.capacity;
+            self.nanos_carry = 0;
+        } else {
+            self.tokens = tokens as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPACITY: u64 = 1000;
+    const REFILL_RATE: u64 = 1000; // bytes per second
+
+    #[test]
+    fn new_bucket_is_full() {
+        let mut bucket = TokenBucket::new(CAPACITY, REFILL_RATE);
+
+        assert!(bucket.try_consume(CAPACITY));
+    }
+
+    #[test]
+    fn drained_bucket_rejects() {
+        let mut bucket = TokenBucket::new(CAPACITY, REFILL_RATE);
+
+        assert!(bucket.try_consume(600));
+        assert!(bucket.try_consume(400));
+        assert!(!bucket.try_consume(100));
+    }
+
+    #[test]
+    fn rejected_consume_keeps_tokens() {
+        let mut bucket = TokenBucket::new(CAPACITY, REFILL_RATE);
+        assert!(bucket.try_consume(600));
+
+        assert!(!bucket.try_consume(500));
+
+        assert!(bucket.try_consume(400));
+    }
+
+    #[test]
+    fn amount_above_capacity_is_never_consumed() {
+        let mut bucket = TokenBucket::new(CAPACITY, REFILL_RATE);
+        bucket.refill(Duration::from_secs(10));
+
+        assert!(!bucket.try_consume(CAPACITY + 1));
+    }
+
+    #[test]
+    fn refills_with_elapsed_time() {
+        let mut bucket = TokenBucket::new(CAPACITY, REFILL_RATE);
+        assert!(bucket.try_consume(CAPACITY));
+
+        bucket.refill(Duration::from_millis(250));
+        assert_eq!(bucket.tokens, 250);
+
+        bucket.refill(Duration::from_millis(500));
+        assert_eq!(bucket.tokens, 750);
+    }
+
+    #[test]
+    fn partial_tokens_are_carried_over() {
+        let mut bucket = TokenBucket::new(10, 1);
+        assert!(bucket.try_consume(10));
+
+        bucket.refill(Duration::from_millis(600));
+        assert_eq!(bucket.tokens, 0);
+
+        bucket.refill(Duration::from_millis(600));
+        assert_eq!(bucket.tokens, 1);
+    }
+
+    #[test]
+    fn refill_does_not_overflow_capacity() {
+        let mut bucket = TokenBucket::new(CAPACITY, REFILL_RATE);
+        assert!(bucket.try_consume(CAPACITY));
+
+        bucket.refill(Duration::from_secs(3600));
+        assert_eq!(bucket.tokens, CAPACITY);
+
+        // the carry is dropped when the bucket is full
+        bucket.refill(Duration::from_nanos(999_999));
+        assert_eq!(bucket.tokens, CAPACITY);
+        assert_eq!(bucket.nanos_carry, 0);
+    }
+
+    #[test]
+    fn huge_rate_does_not_overflow() {
+        let mut bucket = TokenBucket::new(u64::MAX, u64::MAX);
+        assert!(bucket.try_consume(u64::MAX));
+
+        bucket.refill(Duration::from_secs(3600));
+
+        assert_eq!(bucket.tokens, u64::MAX);
+    }
+
+    #[test]
+    fn zero_rate_never_refills() {
+        let mut bucket = TokenBucket::new(CAPACITY, 0);
+        assert!(bucket.try_consume(CAPACITY));
+
+        bucket.refill(Duration::from_secs(3600));
+
+        assert!(!bucket.try_consume(1));
+    }
+}
@@ -0,0 +1,146 @@
+// Std.
+use std::time::{Duration, Instant};
+
+// Detects a blocked UDP path (symmetric NAT, corporate firewalls, etc.).
+// The path is considered blocked if nothing (neither voice nor ping) was received
+// for 'timeout' or if sending keeps failing with 'WouldBlock' for 'timeout'.
+#[derive(Debug)]
+pub struct UdpHealthCheck {
+    timeout: Duration,
+    last_received: Instant,
+    would_block_since: Option<Instant>, // None if the last send succeeded
+    is_reported: bool,
+}
+
+impl UdpHealthCheck {
+    pub fn new(timeout: Duration, now: Instant) -> Self {
+        UdpHealthCheck {
+            timeout,
+            last_received: now,
+            would_block_since: None,
+            is_reported: false,
+        }
+    }
+    pub fn on_packet_received(&mut self, now: Instant) {
+        self.last_received = now;
+    }
+    pub fn on_packet_sent(&mut self) {
+        self.would_block_since = None;
+    }
+    pub fn on_send_would_block(&mut self, now: Instant) {
+        if self.would_block_since.is_none() {
+            self.would_block_since = Some(now);
+        }
+    }
+    /// Returns 'true' only once when the path becomes blocked
+    /// (and again if it becomes blocked after it was fine).
+    pub fn check(&mut self, now: Instant) -> bool {
+        let is_receive_blocked = now.duration_since(self.last_received) >= self.timeout;
+        let is_send_blocked = match self.would_block_since {
+            Some(since) => now.duration_since(since) >= self.timeout,
+            None => false,
+        };
+
+        if !is_receive_blocked && !is_send_blocked {
+            self.is_reported = false;
+            return false;
+        }
+
+        if self.is_reported {
+            return false;
+        }
+        self.is_reported = true;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn not_blocked_before_timeout() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+
+        assert!(!health_check.check(start));
+        assert!(!health_check.check(start + TIMEOUT - Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn blocked_if_nothing_received_for_timeout() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+
+        assert!(health_check.check(start + TIMEOUT));
+    }
+
+    #[test]
+    fn received_packets_delay_timeout() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+
+        health_check.on_packet_received(start + Duration::from_secs(8));
+
+        assert!(!health_check.check(start + TIMEOUT));
+        assert!(!health_check.check(start + Duration::from_secs(17)));
+        assert!(health_check.check(start + Duration::from_secs(18)));
+    }
+
+    #[test]
+    fn blocked_path_is_reported_once() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+
+        assert!(health_check.check(start + TIMEOUT));
+        assert!(!health_check.check(start + TIMEOUT + Duration::from_secs(1)));
+        assert!(!health_check.check(start + TIMEOUT * 5));
+    }
+
+    #[test]
+    fn blocked_again_after_recovery_is_reported() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+        assert!(health_check.check(start + TIMEOUT));
+
+        let recovered = start + TIMEOUT + Duration::from_secs(1);
+        health_check.on_packet_received(recovered);
+        assert!(!health_check.check(recovered));
+
+        assert!(health_check.check(recovered + TIMEOUT));
+    }
+
+    #[test]
+    fn blocked_if_sending_would_block_for_timeout() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+        let would_block_start = start + Duration::from_secs(1);
+
+        for second in 1..TIMEOUT.as_secs() {
+            let now = start + Duration::from_secs(second);
+            health_check.on_packet_received(now);
+            health_check.on_send_would_block(now);
+            assert!(!health_check.check(now));
+        }
+
+        health_check.on_packet_received(would_block_start + TIMEOUT);
+        assert!(health_check.check(would_block_start + TIMEOUT));
+    }
+
+    #[test]
+    fn successful_send_resets_would_block() {
+        let start = Instant::now();
+        let mut health_check = UdpHealthCheck::new(TIMEOUT, start);
+
+        health_check.on_send_would_block(start);
+        health_check.on_packet_sent();
+        health_check.on_send_would_block(start + Duration::from_secs(5));
+        health_check.on_packet_received(start + TIMEOUT);
+
+        assert!(!health_check.check(start + TIMEOUT));
+        assert!(health_check.check(start + Duration::from_secs(5) + TIMEOUT));
+    }
+}
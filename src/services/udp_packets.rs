@@ -12,7 +12,11 @@ pub const UDP_PACKET_MAX_SIZE: u16 = 1430;
 pub enum ClientUdpMessage {
     Connect { username: String },
     PingCheck,
-    VoiceMessage { samples: Vec<i16> },
+    VoiceMessage { seq: u32, samples: Vec<i16> },
+    VoicePaused,
+    VoiceResumed,
+    SilenceMarker, // sent instead of silent voice chunks
+    OpusVoiceMessage { seq: u32, data: Vec<u8> },
 }
 
 // ----------------------------------------------------------------------------
@@ -22,8 +26,23 @@ pub enum ClientUdpMessage {
 #[derive(Serialize, Deserialize)]
 pub enum ServerUdpMessage {
     PingCheck,
-    UserPing { username: String, ping_ms: u16 }, // ping check result
-    VoiceMessage { username: String, samples: Vec<i16> },
+    UserPing {
+        username: String,
+        ping_ms: u16,
+    }, // ping check result
+    VoiceMessage {
+        username: String,
+        seq: u32,
+        samples: Vec<i16>,
+    },
+    SilenceMarker {
+        username: String,
+    },
+    OpusVoiceMessage {
+        username: String,
+        seq: u32,
+        data: Vec<u8>,
+    },
 }
 
 // ----------------------------------------------------------------------------
@@ -0,0 +1,158 @@
+// External.
+use serde::Deserialize;
+
+// Custom.
+use crate::global_params::*;
+
+pub struct UpdateCheckResult {
+    pub latest: String,
+    pub is_newer: bool,
+}
+
+#[derive(Deserialize)]
+struct LatestRelease {
+    tag_name: String,
+}
+
+pub struct UpdateChecker;
+
+impl UpdateChecker {
+    /// Blocking, should be called from a separate thread.
+    pub fn check_for_updates() -> Result<UpdateCheckResult, String> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(format!("silent-rs/{}", env!("CARGO_PKG_VERSION"))) // required by GitHub API
+            .timeout(std::time::Duration::from_secs(UPDATE_CHECK_TIMEOUT_SEC))
+            .build();
+        if let Err(e) = client {
+            return Err(format!(
+                "reqwest::blocking::Client::builder() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+        let client = client.unwrap();
+
+        let response = client.get(LATEST_RELEASE_API_URL).send();
+        if let Err(e) = response {
+            return Err(format!(
+                "failed to send request to '{}', error: {} at [{}, {}]",
+                LATEST_RELEASE_API_URL,
+                e,
+                file!(),
+                line!()
+            ));
+        }
+        let response = response.unwrap();
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "'{}' returned status {} at [{}, {}]",
+                LATEST_RELEASE_API_URL,
+                response.status(),
+                file!(),
+                line!()
+            ));
+        }
+
+        let body = response.text();
+        if let Err(e) = body {
+            return Err(format!(
+                "failed to read response body, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        let release = serde_json::from_str::<LatestRelease>(&body.unwrap());
+        if let Err(e) = release {
+            return Err(format!(
+                "serde_json::from_str() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+        let release = release.unwrap();
+
+        let latest = String::from(release.tag_name.trim_start_matches('v'));
+        let is_newer = UpdateChecker::is_version_newer(&latest, env!("CARGO_PKG_VERSION"));
+
+        Ok(UpdateCheckResult { latest, is_newer })
+    }
+
+    /// Compares versions like "2.2.1" (missing or non-numeric parts are treated as 0).
+    pub fn is_version_newer(latest: &str, current: &str) -> bool {
+        let parse = |version: &str| -> Vec<u64> {
+            version
+                .trim_start_matches('v')
+                .split('.')
+                .map(|part| part.trim().parse::<u64>().unwrap_or(0))
+                .collect()
+        };
+
+        let latest = parse(latest);
+        let current = parse(current);
+
+        for i in 0..latest.len().max(current.len()) {
+            let latest_part = latest.get(i).copied().unwrap_or(0);
+            let current_part = current.get(i).copied().unwrap_or(0);
+            if latest_part != current_part {
+                return latest_part > current_part;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_versions_are_not_newer() {
+        assert!(!UpdateChecker::is_version_newer("2.2.1", "2.2.1"));
+        assert!(!UpdateChecker::is_version_newer("0.0.0", "0.0.0"));
+    }
+
+    #[test]
+    fn greater_part_is_newer() {
+        assert!(UpdateChecker::is_version_newer("2.2.2", "2.2.1"));
+        assert!(UpdateChecker::is_version_newer("2.3.0", "2.2.9"));
+        assert!(UpdateChecker::is_version_newer("3.0.0", "2.9.9"));
+        assert!(!UpdateChecker::is_version_newer("2.2.1", "2.2.2"));
+        assert!(!UpdateChecker::is_version_newer("2.9.9", "3.0.0"));
+    }
+
+    #[test]
+    fn parts_are_compared_as_numbers() {
+        assert!(UpdateChecker::is_version_newer("2.10.0", "2.9.0"));
+        assert!(!UpdateChecker::is_version_newer("2.9.0", "2.10.0"));
+    }
+
+    #[test]
+    fn missing_parts_are_zero() {
+        assert!(UpdateChecker::is_version_newer("2.2.1", "2.2"));
+        assert!(!UpdateChecker::is_version_newer("2.2", "2.2.1"));
+        assert!(!UpdateChecker::is_version_newer("2.2", "2.2.0"));
+        assert!(!UpdateChecker::is_version_newer("2.2.0", "2.2"));
+    }
+
+    #[test]
+    fn v_prefix_is_ignored() {
+        assert!(!UpdateChecker::is_version_newer("v2.2.1", "2.2.1"));
+        assert!(UpdateChecker::is_version_newer("v2.3.0", "2.2.1"));
+        assert!(!UpdateChecker::is_version_newer("2.2.1", "v2.3.0"));
+    }
+
+    #[test]
+    fn non_numeric_parts_are_zero() {
+        assert!(!UpdateChecker::is_version_newer("2.2.beta", "2.2.0"));
+        assert!(UpdateChecker::is_version_newer("2.2.1", "2.2.beta"));
+        assert!(!UpdateChecker::is_version_newer("2.2.1-rc1", "2.2.0"));
+        assert!(!UpdateChecker::is_version_newer("", "0.0.0"));
+        assert!(UpdateChecker::is_version_newer("0.0.1", ""));
+    }
+}
@@ -1,29 +1,32 @@
 // External.
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
-use aes::Aes256;
-use cmac::{Cmac, Mac};
-use druid::{ExtEventSink, Selector, Target};
-use num_bigint::{BigUint, RandomBits};
-use rand::{Rng, RngCore};
-
-type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
-type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+use druid::{Selector, Target};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use socket2::{SockRef, TcpKeepalive};
+use tracing::warn;
+use x25519_dalek::{EphemeralSecret, PublicKey};
 
 pub const SECRET_KEY_SIZE: usize = 32;
 
 // Std.
-use std::convert::TryInto;
 use std::io::prelude::*;
 use std::net::*;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Custom.
+use super::event_sink::EventSink;
+use super::tcp_connect_state_machine::*;
 use super::tcp_packets::*;
+use crate::error::SilentError;
 use crate::global_params::*;
+use crate::misc::packet_builder::PacketBuilder;
 
-const A_B_BITS: u64 = 2048;
+const X25519_PUBLIC_KEY_SIZE: usize = 32;
+const SESSION_KEY_INFO: &[u8] = b"silent-rs session key v1"; // HKDF info
 
 pub const USER_TCP_SERVICE_USER_CONNECTED: Selector<String> =
     Selector::new("user_tcp_service_user_connected");
@@ -37,6 +40,21 @@ pub const USER_TCP_SERVICE_USER_MESSAGE: Selector<UserMessageInfo> =
 pub const USER_TCP_SERVICE_MOVE_USER_TO_ROOM: Selector<UserMoveInfo> =
     Selector::new("user_tcp_service_move_user_to_room");
 
+pub const USER_TCP_SERVICE_ROOM_DESCRIPTION: Selector<RoomDescriptionInfo> =
+    Selector::new("user_tcp_service_room_description");
+
+pub const USER_TCP_SERVICE_USER_STATUS_MESSAGE: Selector<UserStatusMessageInfo> =
+    Selector::new("user_tcp_service_user_status_message");
+
+pub const USER_TCP_SERVICE_MESSAGE_REACTION: Selector<MessageReactionInfo> =
+    Selector::new("user_tcp_service_message_reaction");
+
+pub const USER_TCP_SERVICE_MESSAGE_PINNED: Selector<PinnedMessageInfo> =
+    Selector::new("user_tcp_service_message_pinned");
+
+pub const USER_TCP_SERVICE_ROOM_INVITE: Selector<RoomInviteInfo> =
+    Selector::new("user_tcp_service_room_invite");
+
 #[derive(Debug)]
 pub enum UserState {
     NotConnected,
@@ -45,6 +63,7 @@ pub enum UserState {
 
 pub struct UserMessageInfo {
     pub username: String,
+    pub message_id: u64,
     pub message: String,
 }
 
@@ -53,6 +72,33 @@ pub struct UserMoveInfo {
     pub room_to: String,
 }
 
+pub struct RoomDescriptionInfo {
+    pub room_name: String,
+    pub description: String,
+}
+
+pub struct UserStatusMessageInfo {
+    pub username: String,
+    pub text: String,
+}
+
+pub struct MessageReactionInfo {
+    pub message_id: u64,
+    pub username: String,
+    pub emoji: String,
+}
+
+pub struct PinnedMessageInfo {
+    pub message_id: u64,
+    pub author: String,
+    pub text: String,
+}
+
+pub struct RoomInviteInfo {
+    pub inviter_username: String,
+    pub room_name: String,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct UserInfo {
     pub username: String,
@@ -74,6 +120,7 @@ pub enum ConnectResult {
     SleepWithErr(usize), // sleep time in sec.
     WrongProtocol(u64),  // needed protocol
     Err(String),
+    ErrTimeout,
     InfoAboutOtherUser(UserInfo, String, u16),
     InfoAboutRoom(String),
 }
@@ -92,11 +139,18 @@ pub enum IoResult {
     Err(String),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub enum HandleMessageResult {
     Ok,
     IOError(IoResult),
-    OtherErr(String),
+    OtherErr(SilentError),
+    Timeout, // only returned by 'establish_secure_connection'
+}
+
+// AES-256 keys derived from the key exchange.
+pub struct SessionKeys {
+    pub tcp_key: [u8; SECRET_KEY_SIZE],
+    pub udp_key: [u8; SECRET_KEY_SIZE],
 }
 
 #[derive(Debug)]
@@ -107,6 +161,8 @@ pub struct UserTcpService {
     pub tcp_socket: Option<TcpStream>,
     pub io_tcp_mutex: Mutex<()>,
     pub secret_key: [u8; SECRET_KEY_SIZE],
+    pub is_disconnect_requested: bool,
+    pub request_stop: Arc<AtomicBool>, // set by NetService::disconnect
 }
 
 impl UserTcpService {
@@ -120,209 +176,70 @@ impl UserTcpService {
             },
             io_tcp_mutex: Mutex::new(()),
             secret_key: [0; SECRET_KEY_SIZE],
+            is_disconnect_requested: false,
+            request_stop: Arc::new(AtomicBool::new(false)),
         }
     }
-    pub fn establish_secure_connection(&mut self) -> Result<Vec<u8>, HandleMessageResult> {
-        // Generate secret key 'b'.
-        let mut rng = rand::thread_rng();
-        let b: BigUint = rng.sample(RandomBits::new(A_B_BITS));
-
-        // Receive 2 values: p (BigUint), g (BigUint) values.
-        // Get 'p' len.
-        let mut p_len_buf = vec![0u8; std::mem::size_of::<u64>()];
-        loop {
-            match self.read_from_socket(&mut p_len_buf) {
-                IoResult::FIN => {
-                    return Err(HandleMessageResult::IOError(IoResult::FIN));
-                }
-                IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
-                    )));
-                }
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_) => {
-                    break;
-                }
-            }
-        }
-        let p_len = bincode::deserialize::<u64>(&p_len_buf);
-        if let Err(e) = p_len {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::deserialize failed, error: {}, at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let p_len = p_len.unwrap();
-
-        // Get 'p' value.
-        let mut p_buf = vec![0u8; p_len as usize];
-        loop {
-            match self.read_from_socket(&mut p_buf) {
-                IoResult::FIN => {
-                    return Err(HandleMessageResult::IOError(IoResult::FIN));
-                }
-                IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
-                    )));
-                }
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_) => {
-                    break;
-                }
-            }
-        }
-        let p_buf = bincode::deserialize::<BigUint>(&p_buf);
-        if let Err(e) = p_buf {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::deserialize failed, error: {}, at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let p = p_buf.unwrap();
-
-        // Get 'g' len.
-        let mut g_len_buf = vec![0u8; std::mem::size_of::<u64>()];
-        loop {
-            match self.read_from_socket(&mut g_len_buf) {
-                IoResult::FIN => {
-                    return Err(HandleMessageResult::IOError(IoResult::FIN));
-                }
-                IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
-                    )));
-                }
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_) => {
-                    break;
-                }
-            }
-        }
-        let g_len = bincode::deserialize::<u64>(&g_len_buf);
-        if let Err(e) = g_len {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::deserialize failed, error: {}, at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let g_len = g_len.unwrap();
-
-        // Get 'g' value.
-        let mut g_buf = vec![0u8; g_len as usize];
-        loop {
-            match self.read_from_socket(&mut g_buf) {
-                IoResult::FIN => {
-                    return Err(HandleMessageResult::IOError(IoResult::FIN));
-                }
-                IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
-                    )));
-                }
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_) => {
-                    break;
-                }
-            }
-        }
-        let g_buf = bincode::deserialize::<BigUint>(&g_buf);
-        if let Err(e) = g_buf {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::deserialize failed, error: {}, at [{}, {}]",
+    /// Overwrites the key so that it does not stay in memory after disconnect.
+    pub fn zero_secret_key(&mut self) {
+        self.secret_key = [0; SECRET_KEY_SIZE];
+    }
+    /// Enables TCP keep-alive so that the OS detects a half-open connection
+    /// (server machine is gone without closing the connection) even if the server
+    /// stops sending KeepAliveCheck packets, reading from the socket will then fail.
+    pub fn enable_keepalive(tcp_socket: &TcpStream) -> Result<(), String> {
+        let keepalive = TcpKeepalive::new().with_time(Duration::from_secs(TCP_KEEPALIVE_IDLE_SEC));
+
+        // Windows sets all values using WSAIoctl(SIO_KEEPALIVE_VALS),
+        // Linux sets SO_KEEPALIVE, TCP_KEEPIDLE, TCP_KEEPINTVL and TCP_KEEPCNT.
+        #[cfg(any(target_os = "linux", windows))]
+        let keepalive = keepalive.with_interval(Duration::from_secs(TCP_KEEPALIVE_INTERVAL_SEC));
+        #[cfg(target_os = "linux")]
+        let keepalive = keepalive.with_retries(TCP_KEEPALIVE_RETRIES);
+
+        if let Err(e) = SockRef::from(tcp_socket).set_tcp_keepalive(&keepalive) {
+            return Err(format!(
+                "failed to enable TCP keep-alive (error: {}) at [{}, {}]",
                 e,
                 file!(),
                 line!()
-            )));
+            ));
         }
-        let g = g_buf.unwrap();
-
-        // Calculate the open key B.
-        let b_open = g.modpow(&b, &p);
 
-        // Receive the open key A size.
-        let mut a_open_len_buf = vec![0u8; std::mem::size_of::<u64>()];
-        loop {
-            match self.read_from_socket(&mut a_open_len_buf) {
-                IoResult::FIN => {
-                    return Err(HandleMessageResult::IOError(IoResult::FIN));
-                }
-                IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
-                    )));
-                }
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_) => {
-                    break;
-                }
-            }
-        }
+        Ok(())
+    }
+    /// X25519 key exchange: receives the server's public key, sends ours
+    /// and derives the session keys from the shared secret (see 'derive_session_keys'),
+    /// a new key pair is generated for each connection.
+    /// Returns 'HandleMessageResult::Timeout' if the server does not answer
+    /// for HANDSHAKE_TIMEOUT_SEC (the socket is non-blocking, so no read timeout is used).
+    pub fn establish_secure_connection(
+        &mut self,
+        server_name: &str,
+        server_port: &str,
+    ) -> Result<SessionKeys, HandleMessageResult> {
+        let deadline = Instant::now() + Duration::from_secs(HANDSHAKE_TIMEOUT_SEC);
 
-        let a_open_len = bincode::deserialize::<u64>(&a_open_len_buf);
-        if let Err(e) = a_open_len {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::deserialize failed, error: {}, at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let a_open_len = a_open_len.unwrap();
+        // Generate our key pair.
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public_key = PublicKey::from(&secret);
 
-        // Receive the open key A.
-        let mut a_open_buf = vec![0u8; a_open_len as usize];
+        // Receive the server's public key.
+        let mut server_public_key = [0u8; X25519_PUBLIC_KEY_SIZE];
         loop {
-            match self.read_from_socket(&mut a_open_buf) {
+            match self.read_from_socket(&mut server_public_key) {
                 IoResult::FIN => {
                     return Err(HandleMessageResult::IOError(IoResult::FIN));
                 }
                 IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
+                    return Err(HandleMessageResult::OtherErr(SilentError::InternalError(
+                        format!("{} at [{}, {}]", msg, file!(), line!()),
                     )));
                 }
                 IoResult::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(HandleMessageResult::Timeout);
+                    }
                     thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
                     continue;
                 }
@@ -332,47 +249,21 @@ impl UserTcpService {
             }
         }
 
-        let a_open_big = bincode::deserialize::<BigUint>(&a_open_buf);
-        if let Err(e) = a_open_big {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::deserialize failed, error: {}, at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let a_open_big = a_open_big.unwrap();
-
-        // Prepare to send open key B.
-        let mut b_open_buf = bincode::serialize(&b_open).unwrap();
-
-        // Send open key 'B'.
-        let b_open_len = b_open_buf.len() as u64;
-        let b_open_len_buf = bincode::serialize(&b_open_len);
-        if let Err(e) = b_open_len_buf {
-            return Err(HandleMessageResult::OtherErr(format!(
-                "bincode::serialize failed, error: {}, at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let mut b_open_len_buf = b_open_len_buf.unwrap();
-        b_open_len_buf.append(&mut b_open_buf);
+        // Send our public key.
         loop {
-            match self.write_to_socket(&mut b_open_len_buf) {
+            match self.write_to_socket(public_key.as_bytes()) {
                 IoResult::FIN => {
                     return Err(HandleMessageResult::IOError(IoResult::FIN));
                 }
                 IoResult::Err(msg) => {
-                    return Err(HandleMessageResult::OtherErr(format!(
-                        "{} at [{}, {}]",
-                        msg,
-                        file!(),
-                        line!()
+                    return Err(HandleMessageResult::OtherErr(SilentError::InternalError(
+                        format!("{} at [{}, {}]", msg, file!(), line!()),
                     )));
                 }
                 IoResult::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(HandleMessageResult::Timeout);
+                    }
                     thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
                     continue;
                 }
@@ -383,98 +274,72 @@ impl UserTcpService {
         }
 
         // Calculate the secret key.
-        let secret_key = a_open_big.modpow(&b, &p);
-        let mut secret_key_str = secret_key.to_str_radix(10);
-
-        let key_length = 32;
-
-        if secret_key_str.len() < key_length {
-            if secret_key_str.is_empty() {
-                return Err(HandleMessageResult::OtherErr(format!(
-                    "generated secret key is empty, at [{}, {}].",
+        let shared_secret = secret.diffie_hellman(&PublicKey::from(server_public_key));
+        if !shared_secret.was_contributory() {
+            // low order point, the secret does not depend on our key
+            return Err(HandleMessageResult::OtherErr(SilentError::CryptoError(
+                format!(
+                    "the server sent an invalid public key, at [{}, {}].",
                     file!(),
                     line!()
-                )));
-            }
-
-            loop {
-                secret_key_str += &secret_key_str.clone();
-
-                if secret_key_str.len() >= key_length {
-                    break;
-                }
-            }
+                ),
+            )));
         }
 
-        Ok(Vec::from(&secret_key_str[0..key_length]))
+        let salt = format!("{}{}", server_name, server_port);
+
+        Ok(UserTcpService::derive_session_keys(
+            shared_secret.as_bytes(),
+            salt.as_bytes(),
+        ))
+    }
+    /// HKDF-SHA256 with the server's hostname and port as salt,
+    /// the first half of the output is the TCP key and the second one is the UDP key.
+    pub fn derive_session_keys(shared_secret: &[u8], salt: &[u8]) -> SessionKeys {
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), shared_secret);
+
+        let mut output = [0u8; SECRET_KEY_SIZE * 2];
+        hkdf.expand(SESSION_KEY_INFO, &mut output)
+            .expect("HKDF output length is invalid");
+
+        let mut session_keys = SessionKeys {
+            tcp_key: [0; SECRET_KEY_SIZE],
+            udp_key: [0; SECRET_KEY_SIZE],
+        };
+        session_keys
+            .tcp_key
+            .copy_from_slice(&output[..SECRET_KEY_SIZE]);
+        session_keys
+            .udp_key
+            .copy_from_slice(&output[SECRET_KEY_SIZE..]);
+
+        session_keys
     }
     pub fn enter_room(&mut self, room: &str) -> HandleMessageResult {
         if self.tcp_socket.is_none() {
-            return HandleMessageResult::OtherErr(format!(
+            return HandleMessageResult::OtherErr(SilentError::InternalError(format!(
                 "UserTcpService::send_user_text_message() failed, error: tcp_socket was None at [{}, {}]", file!(), line!()
-            ));
+            )));
         }
 
         let client_packet = ClientTcpMessage::UserEnterRoom {
             room_name: String::from(room),
         };
 
-        // Serialize packet.
-        let binary_client_packet = bincode::serialize(&client_packet);
-        if let Err(e) = binary_client_packet {
-            return HandleMessageResult::OtherErr(format!(
-                "bincode::serialize failed, error: {} at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            ));
-        }
-        let mut binary_client_packet = binary_client_packet.unwrap();
-
-        // CMAC.
-        let mut mac = Cmac::<Aes256>::new_from_slice(&self.secret_key).unwrap();
-        mac.update(&binary_client_packet);
-        let result = mac.finalize();
-        let mut tag_bytes = result.into_bytes().to_vec();
-        if tag_bytes.len() != CMAC_TAG_LENGTH {
-            return HandleMessageResult::OtherErr(format!(
-                "unexpected tag length: {} != {} at [{}, {}]",
-                tag_bytes.len(),
-                CMAC_TAG_LENGTH,
+        let send_buffer = PacketBuilder::build_encrypted_packet(&client_packet, &self.secret_key);
+        if let Err(msg) = send_buffer {
+            return HandleMessageResult::OtherErr(SilentError::InternalError(format!(
+                "{} at [{}, {}]",
+                msg,
                 file!(),
                 line!()
-            ));
-        }
-
-        binary_client_packet.append(&mut tag_bytes);
-
-        // Encrypt packet.
-        let mut rng = rand::thread_rng();
-        let mut iv = [0u8; IV_LENGTH];
-        rng.fill_bytes(&mut iv);
-        let mut encrypted_packet = Aes256CbcEnc::new(&self.secret_key.into(), &iv.into())
-            .encrypt_padded_vec_mut::<Pkcs7>(&binary_client_packet);
-
-        // Prepare encrypted packet len buffer.
-        let encrypted_len = (encrypted_packet.len() + IV_LENGTH) as u16;
-        let encrypted_len_buf = bincode::serialize(&encrypted_len);
-        if let Err(e) = encrypted_len_buf {
-            return HandleMessageResult::OtherErr(format!(
-                "bincode::serialize failed, error: {} at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            ));
+            )));
         }
-        let mut send_buffer = encrypted_len_buf.unwrap();
-
-        // Merge all to one buffer.
-        send_buffer.append(&mut Vec::from(iv));
-        send_buffer.append(&mut encrypted_packet);
+        let send_buffer = send_buffer.unwrap();
 
         // Send to server.
         loop {
-            match self.write_to_socket(&mut send_buffer) {
+            match self.write_to_socket(&send_buffer) {
                 IoResult::WouldBlock => {
                     thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
                     continue;
@@ -486,76 +351,105 @@ impl UserTcpService {
 
         HandleMessageResult::Ok
     }
-    pub fn send_user_text_message(&mut self, message: String) -> HandleMessageResult {
-        if self.tcp_socket.is_none() {
-            return HandleMessageResult::OtherErr(format!(
-                "tcp_socket was None at [{}, {}]",
+    pub fn build_user_text_message_packet(&self, message: String) -> Result<Vec<u8>, SilentError> {
+        let client_message_packet = ClientTcpMessage::UserMessage { message };
+
+        let out_buffer =
+            PacketBuilder::build_encrypted_packet(&client_message_packet, &self.secret_key);
+        if let Err(msg) = out_buffer {
+            return Err(SilentError::InternalError(format!(
+                "{} at [{}, {}]",
+                msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
-        let client_message_packet = ClientTcpMessage::UserMessage { message };
+        Ok(out_buffer.unwrap())
+    }
+    pub fn build_status_message_packet(&self, text: String) -> Result<Vec<u8>, SilentError> {
+        let client_packet = ClientTcpMessage::StatusMessage { text };
+
+        let out_buffer = PacketBuilder::build_encrypted_packet(&client_packet, &self.secret_key);
+        if let Err(msg) = out_buffer {
+            return Err(SilentError::InternalError(format!(
+                "{} at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
+        }
 
-        // Serialize packet.
-        let binary_client_message_packet = bincode::serialize(&client_message_packet);
-        if let Err(e) = binary_client_message_packet {
-            return HandleMessageResult::OtherErr(format!(
-                "bincode::serialize failed, error: {} at [{}, {}]",
-                e,
+        Ok(out_buffer.unwrap())
+    }
+    pub fn build_message_reaction_packet(
+        &self,
+        message_id: u64,
+        emoji: String,
+    ) -> Result<Vec<u8>, SilentError> {
+        let client_packet = ClientTcpMessage::MessageReaction { message_id, emoji };
+
+        let out_buffer = PacketBuilder::build_encrypted_packet(&client_packet, &self.secret_key);
+        if let Err(msg) = out_buffer {
+            return Err(SilentError::InternalError(format!(
+                "{} at [{}, {}]",
+                msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
-        let mut binary_client_message_packet = binary_client_message_packet.unwrap();
-
-        // CMAC.
-        let mut mac = Cmac::<Aes256>::new_from_slice(&self.secret_key).unwrap();
-        mac.update(&binary_client_message_packet);
-        let result = mac.finalize();
-        let mut tag_bytes = result.into_bytes().to_vec();
-        if tag_bytes.len() != CMAC_TAG_LENGTH {
-            return HandleMessageResult::OtherErr(format!(
-                "unexpected tag length: {} != {} at [{}, {}]",
-                tag_bytes.len(),
-                CMAC_TAG_LENGTH,
+
+        Ok(out_buffer.unwrap())
+    }
+    pub fn build_pin_message_packet(&self, message_id: u64) -> Result<Vec<u8>, SilentError> {
+        let client_packet = ClientTcpMessage::PinMessage { message_id };
+
+        let out_buffer = PacketBuilder::build_encrypted_packet(&client_packet, &self.secret_key);
+        if let Err(msg) = out_buffer {
+            return Err(SilentError::InternalError(format!(
+                "{} at [{}, {}]",
+                msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
 
-        binary_client_message_packet.append(&mut tag_bytes);
-
-        // Encrypt packet.
-        let mut rng = rand::thread_rng();
-        let mut iv = [0u8; IV_LENGTH];
-        rng.fill_bytes(&mut iv);
-        let mut encrypted_message_packet =
-            Aes256CbcEnc::new(&self.secret_key.into(), &iv.into())
-                .encrypt_padded_vec_mut::<Pkcs7>(&binary_client_message_packet);
-
-        // Prepare encrypted packet len buffer.
-        let encrypted_message_len = (encrypted_message_packet.len() + IV_LENGTH) as u16;
-        let encrypted_message_len_buf = bincode::serialize(&encrypted_message_len);
-        if let Err(e) = encrypted_message_len_buf {
-            return HandleMessageResult::OtherErr(format!(
-                "bincode::serialize failed, error: {} at [{}, {}]",
-                e,
+        Ok(out_buffer.unwrap())
+    }
+    pub fn build_room_invite_packet(
+        &self,
+        target_username: String,
+        room_name: String,
+    ) -> Result<Vec<u8>, SilentError> {
+        let client_packet = ClientTcpMessage::InviteToRoom {
+            target_username,
+            room_name,
+        };
+
+        let out_buffer = PacketBuilder::build_encrypted_packet(&client_packet, &self.secret_key);
+        if let Err(msg) = out_buffer {
+            return Err(SilentError::InternalError(format!(
+                "{} at [{}, {}]",
+                msg,
                 file!(),
                 line!()
-            ));
+            )));
         }
-        let mut encrypted_message_len_buf = encrypted_message_len_buf.unwrap();
 
-        // Merge all to one buffer.
-        let mut out_buffer: Vec<u8> = Vec::new();
-        out_buffer.append(&mut encrypted_message_len_buf);
-        out_buffer.append(&mut Vec::from(iv));
-        out_buffer.append(&mut encrypted_message_packet);
+        Ok(out_buffer.unwrap())
+    }
+    /// Writes the whole (already built) packet, used by the send queue thread.
+    pub fn send_packet(&mut self, packet: Vec<u8>) -> HandleMessageResult {
+        if self.tcp_socket.is_none() {
+            return HandleMessageResult::OtherErr(SilentError::InternalError(format!(
+                "tcp_socket was None at [{}, {}]",
+                file!(),
+                line!()
+            )));
+        }
 
-        // Send to server.
         loop {
-            match self.write_to_socket(&mut out_buffer) {
+            match self.write_to_socket(&packet) {
                 IoResult::WouldBlock => {
                     thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
                     continue;
@@ -600,6 +494,10 @@ impl UserTcpService {
                 return IoResult::Ok(n);
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if self.request_stop.load(Ordering::SeqCst) {
+                    // nothing to read and we are asked to stop, finish as if the connection is closed
+                    return IoResult::FIN;
+                }
                 return IoResult::WouldBlock;
             }
             Err(e) => {
@@ -612,7 +510,12 @@ impl UserTcpService {
             }
         };
     }
-    pub fn write_to_socket(&mut self, buf: &mut [u8]) -> IoResult {
+    /// Writes the whole buffer to the socket.
+    ///
+    /// A non-blocking write may send only a part of the buffer, in this case
+    /// we wait and write the rest (the other side expects the whole packet).
+    /// Returns `WouldBlock` only if nothing was written.
+    pub fn write_to_socket(&mut self, buf: &[u8]) -> IoResult {
         if self.tcp_socket.is_none() {
             return IoResult::Err(format!(
                 "UserTcpService::write_to_socket_tcp() failed, error: tcp_socket was None at [{}, {}]",
@@ -623,123 +526,64 @@ impl UserTcpService {
 
         let _io_tcp_guard = self.io_tcp_mutex.lock().unwrap();
 
-        // (non-blocking)
-        match self.tcp_socket.as_mut().unwrap().write(buf) {
-            Ok(0) => {
-                return IoResult::FIN;
-            }
-            Ok(n) => {
-                if n != buf.len() {
+        UserTcpService::write_whole_buffer(self.tcp_socket.as_mut().unwrap(), buf)
+    }
+    /// Partial write loop of 'write_to_socket' (generic so that it can be tested
+    /// with a writer that does short writes).
+    fn write_whole_buffer<W: Write>(writer: &mut W, mut buf: &[u8]) -> IoResult {
+        let mut total_bytes_written: usize = 0;
+
+        while !buf.is_empty() {
+            // (non-blocking)
+            match writer.write(buf) {
+                Ok(0) => {
+                    return IoResult::FIN;
+                }
+                Ok(bytes_written) => {
+                    total_bytes_written += bytes_written;
+                    buf = &buf[bytes_written..];
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    if total_bytes_written == 0 {
+                        return IoResult::WouldBlock;
+                    }
+
+                    // The packet is partially sent, finish it.
+                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                    continue;
+                }
+                Err(e) => {
                     return IoResult::Err(format!(
-                        "TcpStream::write() failed, error: failed to write to 'buf' (got: {}, expected: {}) at [{}, {}]",
-                        n, buf.len(), file!(), line!()
+                        "TcpStream::write() failed, error: {} (written {} bytes) at [{}, {}]",
+                        e,
+                        total_bytes_written,
+                        file!(),
+                        line!()
                     ));
                 }
+            };
+        }
 
-                return IoResult::Ok(n);
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                return IoResult::WouldBlock;
-            }
-            Err(e) => {
-                return IoResult::Err(format!(
-                    "TcpStream::write() failed, error: {} at [{}, {}]",
-                    e,
-                    file!(),
-                    line!()
-                ));
-            }
-        };
+        IoResult::Ok(total_bytes_written)
     }
     pub fn handle_message(
         &mut self,
         message_size: u16,
-        event_sink: ExtEventSink,
+        event_sink: EventSink,
     ) -> HandleMessageResult {
         // Receive packet.
-        let mut packet_buf = vec![0u8; message_size as usize];
-        loop {
-            match self.read_from_socket(&mut packet_buf) {
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_bytes) => break,
-                res => return HandleMessageResult::IOError(res),
-            }
-        }
-
-        // Get IV.
-        if packet_buf.len() < IV_LENGTH {
-            return HandleMessageResult::IOError(IoResult::Err(format!(
-                "received data is too small, at [{}, {}].",
-                file!(),
-                line!()
-            )));
-        }
-        let iv = packet_buf[..IV_LENGTH].to_vec();
-        packet_buf = packet_buf[IV_LENGTH..].to_vec();
-
-        // Convert IV.
-        let iv = iv.try_into();
-        if iv.is_err() {
-            return HandleMessageResult::OtherErr(format!(
-                "failed to convert iv to generic array, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv: [u8; IV_LENGTH] = iv.unwrap();
-
-        // Decrypt packet.
-        let binary_server_packet = Aes256CbcDec::new(&self.secret_key.into(), &iv.into())
-            .decrypt_padded_vec_mut::<Pkcs7>(&packet_buf);
-        if let Err(e) = binary_server_packet {
-            return HandleMessageResult::IOError(IoResult::Err(format!(
-                "unable to decrypt a packet (error: {}), at [{}, {}].",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let mut binary_server_packet = binary_server_packet.unwrap();
-
-        // CMAC
-        let mut mac = Cmac::<Aes256>::new_from_slice(&self.secret_key).unwrap();
-        let tag: Vec<u8> = binary_server_packet
-            .drain(binary_server_packet.len().saturating_sub(CMAC_TAG_LENGTH)..)
-            .collect();
-        mac.update(&binary_server_packet);
-
-        // Convert tag.
-        let tag = tag.try_into();
-        if tag.is_err() {
-            return HandleMessageResult::OtherErr(format!(
-                "failed to convert cmac tag to generic array, at [{}, {}]",
-                file!(),
-                line!()
-            ));
-        }
-        let tag: [u8; CMAC_TAG_LENGTH] = tag.unwrap();
-
-        if let Err(e) = mac.verify(&tag.into()) {
-            return HandleMessageResult::OtherErr(format!(
-                "Incorrect tag (error: {}), at [{}, {}].",
-                e,
-                file!(),
-                line!()
-            ));
+        let packet_buf = self.read_packet(message_size);
+        if let Err(e) = packet_buf {
+            return e;
         }
+        let packet_buf = packet_buf.unwrap();
 
-        // Deserialize.
-        let server_packet = bincode::deserialize::<ServerTcpMessage>(&binary_server_packet);
+        let server_packet =
+            PacketBuilder::parse_encrypted_packet::<ServerTcpMessage>(packet_buf, &self.secret_key);
         if let Err(e) = server_packet {
-            return HandleMessageResult::IOError(IoResult::Err(format!(
-                "Unable to deserialize a packet (error: {}), at [{}, {}].",
-                e,
-                file!(),
-                line!()
-            )));
+            return e;
         }
         let server_packet = server_packet.unwrap();
 
@@ -761,11 +605,19 @@ impl UserTcpService {
                     .submit_command(USER_TCP_SERVICE_USER_DISCONNECTED, username, Target::Auto)
                     .expect("failed to submit USER_TCP_SERVICE_USER_DISCONNECTED command");
             }
-            ServerTcpMessage::UserMessage { username, message } => {
+            ServerTcpMessage::UserMessage {
+                username,
+                message_id,
+                message,
+            } => {
                 event_sink
                     .submit_command(
                         USER_TCP_SERVICE_USER_MESSAGE,
-                        UserMessageInfo { username, message },
+                        UserMessageInfo {
+                            username,
+                            message_id,
+                            message,
+                        },
                         Target::Auto,
                     )
                     .expect("failed to submit USER_TCP_SERVICE_USER_MESSAGE command");
@@ -785,302 +637,487 @@ impl UserTcpService {
                     )
                     .expect("failed to submit USER_TCP_SERVICE_MOVE_USER_TO_ROOM command");
             }
+            ServerTcpMessage::RoomDescription {
+                room_name,
+                description,
+            } => {
+                // sent by the server right after the connect answer (for each room)
+                event_sink
+                    .submit_command(
+                        USER_TCP_SERVICE_ROOM_DESCRIPTION,
+                        RoomDescriptionInfo {
+                            room_name,
+                            description,
+                        },
+                        Target::Auto,
+                    )
+                    .expect("failed to submit USER_TCP_SERVICE_ROOM_DESCRIPTION command");
+            }
+            ServerTcpMessage::UserStatusMessage { username, text } => {
+                event_sink
+                    .submit_command(
+                        USER_TCP_SERVICE_USER_STATUS_MESSAGE,
+                        UserStatusMessageInfo { username, text },
+                        Target::Auto,
+                    )
+                    .expect("failed to submit USER_TCP_SERVICE_USER_STATUS_MESSAGE command");
+            }
+            ServerTcpMessage::MessageReaction {
+                message_id,
+                username,
+                emoji,
+            } => {
+                event_sink
+                    .submit_command(
+                        USER_TCP_SERVICE_MESSAGE_REACTION,
+                        MessageReactionInfo {
+                            message_id,
+                            username,
+                            emoji,
+                        },
+                        Target::Auto,
+                    )
+                    .expect("failed to submit USER_TCP_SERVICE_MESSAGE_REACTION command");
+            }
+            ServerTcpMessage::MessagePinned {
+                message_id,
+                author,
+                text,
+            } => {
+                event_sink
+                    .submit_command(
+                        USER_TCP_SERVICE_MESSAGE_PINNED,
+                        PinnedMessageInfo {
+                            message_id,
+                            author,
+                            text,
+                        },
+                        Target::Auto,
+                    )
+                    .expect("failed to submit USER_TCP_SERVICE_MESSAGE_PINNED command");
+            }
+            ServerTcpMessage::RoomInvite {
+                inviter_username,
+                room_name,
+            } => {
+                event_sink
+                    .submit_command(
+                        USER_TCP_SERVICE_ROOM_INVITE,
+                        RoomInviteInfo {
+                            inviter_username,
+                            room_name,
+                        },
+                        Target::Auto,
+                    )
+                    .expect("failed to submit USER_TCP_SERVICE_ROOM_INVITE command");
+            }
         }
 
         HandleMessageResult::Ok
     }
 
+    /// Reads the payload of a packet that has a frame version we don't know
+    /// (sent by a newer server) so that the next packet can be read.
+    pub fn skip_message(&mut self, message_size: u16, frame_version: u8) -> HandleMessageResult {
+        if let Err(e) = self.read_packet(message_size) {
+            return e;
+        }
+
+        warn!(
+            "skipped a packet with unsupported frame version {} ({} bytes)",
+            frame_version, message_size
+        );
+
+        HandleMessageResult::Ok
+    }
+    fn read_packet(&mut self, message_size: u16) -> Result<Vec<u8>, HandleMessageResult> {
+        let mut packet_buf = vec![0u8; message_size as usize];
+        loop {
+            match self.read_from_socket(&mut packet_buf) {
+                IoResult::WouldBlock => {
+                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
+                    continue;
+                }
+                IoResult::Ok(_bytes) => break,
+                res => return Err(HandleMessageResult::IOError(res)),
+            }
+        }
+
+        Ok(packet_buf)
+    }
     pub fn connect_user(
         &mut self,
+        voice_codec: VoiceCodec,
         info_sender: std::sync::mpsc::Sender<ConnectInfo>,
     ) -> ConnectResult {
         let packet = ClientConnectPacket {
             net_protocol_version: NETWORK_PROTOCOL_VERSION,
             username: self.user_info.username.clone(),
             password: self.server_password.clone(),
+            voice_codec,
         };
 
-        let mut binary_packet = bincode::serialize(&packet).unwrap();
-
-        // CMAC.
-        let mut mac = Cmac::<Aes256>::new_from_slice(&self.secret_key).unwrap();
-        mac.update(&binary_packet);
-        let result = mac.finalize();
-        let mut tag_bytes = result.into_bytes().to_vec();
-        if tag_bytes.len() != CMAC_TAG_LENGTH {
-            return ConnectResult::Err(format!(
-                "unexpected tag length: {} != {} at [{}, {}]",
-                tag_bytes.len(),
-                CMAC_TAG_LENGTH,
-                file!(),
-                line!()
-            ));
-        }
-
-        binary_packet.append(&mut tag_bytes);
-
-        // Encrypt binary packet.
-        let mut rng = rand::thread_rng();
-        let mut iv = [0u8; IV_LENGTH];
-        rng.fill_bytes(&mut iv);
-        let mut encrypted_binary_packet = Aes256CbcEnc::new(&self.secret_key.into(), &iv.into())
-            .encrypt_padded_vec_mut::<Pkcs7>(&binary_packet);
-
-        if encrypted_binary_packet.len() + IV_LENGTH + std::mem::size_of::<u16>()
-            > std::u16::MAX as usize
-        {
-            // should never happen
-            // using std::mem::size_of::<u16>() as packet size
-            panic!(
-                "Encrypted binary packet size + size_of::<u16> exceeded u16::MAX at [{}, {}].",
-                file!(),
-                line!()
-            );
+        // the server checks our protocol version in this packet so it uses the legacy format
+        let send_buffer = PacketBuilder::build_legacy_encrypted_packet(&packet, &self.secret_key);
+        if let Err(msg) = send_buffer {
+            return ConnectResult::Err(format!("{} at [{}, {}]", msg, file!(), line!()));
         }
-        let packet_size: u16 = (encrypted_binary_packet.len() + IV_LENGTH) as u16;
-        let mut packet_size = bincode::serialize(&packet_size).unwrap();
 
-        let mut send_buffer: Vec<u8> = Vec::new();
-        send_buffer.append(&mut packet_size);
-        send_buffer.append(&mut Vec::from(iv));
-        send_buffer.append(&mut encrypted_binary_packet);
-
-        // Send this buffer.
+        let mut state_machine = TcpConnectStateMachine::new(send_buffer.unwrap());
         loop {
-            match self.write_to_socket(&mut send_buffer) {
-                IoResult::WouldBlock => {
+            match state_machine.poll(self, &info_sender) {
+                StatePollResult::Ok => {}
+                StatePollResult::WouldBlock => {
                     thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
                 }
-                IoResult::Ok(_bytes) => break,
-                res => return ConnectResult::IoErr(res),
-            };
-        }
-
-        // Wait for answer.
-        // We usually use 'u16' as size of the data
-        // but this "packet" is an exception.
-        let mut data_size_buf = vec![0u8; std::mem::size_of::<u64>()];
-        loop {
-            match self.read_from_socket(&mut data_size_buf) {
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_bytes) => break,
-                res => return ConnectResult::IoErr(res),
+                StatePollResult::Finished(result) => return result,
             }
         }
+    }
+    fn send_keep_alive_check(&mut self) -> Result<(), IoResult> {
+        let client_packet = ClientTcpMessage::KeepAliveCheck;
 
-        let data_size = bincode::deserialize::<u64>(&data_size_buf).unwrap();
-        if data_size > TCP_CONNECT_ANSWER_PACKET_MAX_SIZE {
-            return ConnectResult::IoErr(IoResult::Err(format!("The data size received from the server ({}) exceeds the maximum ({}), at [{}, {}].",
-            data_size, TCP_CONNECT_ANSWER_PACKET_MAX_SIZE, file!(), line!())));
+        let out_buffer = PacketBuilder::build_encrypted_packet(&client_packet, &self.secret_key);
+        if let Err(msg) = out_buffer {
+            return Err(IoResult::Err(format!(
+                "{} at [{}, {}]",
+                msg,
+                file!(),
+                line!()
+            )));
         }
+        let out_buffer = out_buffer.unwrap();
 
-        let mut data_buf = vec![0u8; data_size as usize];
+        // Send to server.
         loop {
-            match self.read_from_socket(&mut data_buf) {
+            match self.write_to_socket(&out_buffer) {
                 IoResult::WouldBlock => {
                     thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
                     continue;
                 }
                 IoResult::Ok(_bytes) => break,
-                res => return ConnectResult::IoErr(res),
+                res => return Err(res),
             }
         }
 
-        // Get IV.
-        if data_buf.len() < IV_LENGTH {
-            return ConnectResult::Err(format!(
-                "received data is too small, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv = data_buf[..IV_LENGTH].to_vec();
-        data_buf = data_buf[IV_LENGTH..].to_vec();
-
-        // Convert IV.
-        let iv = iv.try_into();
-        if iv.is_err() {
-            return ConnectResult::Err(format!(
-                "failed to convert iv to generic array, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv: [u8; IV_LENGTH] = iv.unwrap();
-
-        // Decrypt packet.
-        let binary_server_connect_packet = Aes256CbcDec::new(&self.secret_key.into(), &iv.into())
-            .decrypt_padded_vec_mut::<Pkcs7>(&data_buf);
-        if let Err(e) = binary_server_connect_packet {
-            return ConnectResult::IoErr(IoResult::Err(format!(
-                "Unable to decrypt a packet (error: {}), at [{}, {}].",
-                e,
-                file!(),
-                line!()
-            )));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::event_sink::SubmittedCommand;
+    use std::sync::mpsc;
+
+    const SHARED_SECRET: [u8; X25519_PUBLIC_KEY_SIZE] = [0x0b; X25519_PUBLIC_KEY_SIZE];
+    const SALT: &[u8] = b"localhost51337"; // server name and port
+    const SECRET_KEY: [u8; SECRET_KEY_SIZE] = [7; SECRET_KEY_SIZE];
+
+    // Non-blocking client socket (owned by the service) and the server side of the connection.
+    fn connected_service() -> (UserTcpService, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let mut tcp_service = UserTcpService::new(String::new());
+        tcp_service.tcp_socket = Some(client);
+        tcp_service.secret_key = SECRET_KEY;
+
+        (tcp_service, server)
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn session_keys_match_test_vector() {
+        let session_keys = UserTcpService::derive_session_keys(&SHARED_SECRET, SALT);
+
+        // HKDF-SHA256 (RFC 5869) with the same IKM, salt and info
+        assert_eq!(
+            session_keys.tcp_key.to_vec(),
+            from_hex("69b50d39b510cc2bed9a057ab4ff22d15130046807f6dc9d4a140c5efbfea495")
+        );
+        assert_eq!(
+            session_keys.udp_key.to_vec(),
+            from_hex("d7e84208d309f3330a156983dcfbcb38e78118c1b38ec9c393d5691f224fa4eb")
+        );
+    }
+
+    #[test]
+    fn tcp_and_udp_keys_are_different() {
+        let session_keys = UserTcpService::derive_session_keys(&SHARED_SECRET, SALT);
+
+        assert_ne!(session_keys.tcp_key, session_keys.udp_key);
+    }
+
+    #[test]
+    fn session_keys_are_deterministic() {
+        let first = UserTcpService::derive_session_keys(&SHARED_SECRET, SALT);
+        let second = UserTcpService::derive_session_keys(&SHARED_SECRET, SALT);
+
+        assert_eq!(first.tcp_key, second.tcp_key);
+        assert_eq!(first.udp_key, second.udp_key);
+    }
+
+    #[test]
+    fn session_keys_depend_on_secret_and_salt() {
+        let session_keys = UserTcpService::derive_session_keys(&SHARED_SECRET, SALT);
+
+        let other_secret =
+            UserTcpService::derive_session_keys(&[0x0c; X25519_PUBLIC_KEY_SIZE], SALT);
+        assert_ne!(session_keys.tcp_key, other_secret.tcp_key);
+        assert_ne!(session_keys.udp_key, other_secret.udp_key);
+
+        let other_salt = UserTcpService::derive_session_keys(&SHARED_SECRET, b"localhost51338");
+        assert_ne!(session_keys.tcp_key, other_salt.tcp_key);
+        assert_ne!(session_keys.udp_key, other_salt.udp_key);
+    }
+
+    #[test]
+    fn status_message_packet_roundtrip() {
+        let tcp_service = connected_service().0;
+
+        let packet = tcp_service
+            .build_status_message_packet(String::from("Away for lunch"))
+            .unwrap();
+
+        let header = PacketBuilder::parse_frame_header(&packet[..TCP_FRAME_HEADER_SIZE]).unwrap();
+        let payload = packet[TCP_FRAME_HEADER_SIZE..].to_vec();
+        assert_eq!(header.payload_size as usize, payload.len());
+
+        match PacketBuilder::parse_encrypted_packet::<ClientTcpMessage>(payload, &SECRET_KEY) {
+            Ok(ClientTcpMessage::StatusMessage { text }) => assert_eq!(text, "Away for lunch"),
+            Ok(_) => panic!("unexpected packet"),
+            Err(e) => panic!("failed to parse the packet: {:?}", e),
         }
-        let mut binary_server_connect_packet = binary_server_connect_packet.unwrap();
-
-        // CMAC
-        let mut mac = Cmac::<Aes256>::new_from_slice(&self.secret_key).unwrap();
-        let tag: Vec<u8> = binary_server_connect_packet
-            .drain(
-                binary_server_connect_packet
-                    .len()
-                    .saturating_sub(CMAC_TAG_LENGTH)..,
-            )
-            .collect();
-        mac.update(&binary_server_connect_packet);
-
-        // Convert tag.
-        let tag = tag.try_into();
-        if tag.is_err() {
-            return ConnectResult::Err(format!(
-                "failed to convert cmac tag to generic array, at [{}, {}]",
-                file!(),
-                line!()
-            ));
+    }
+
+    #[test]
+    fn status_message_variants_keep_old_encoding() {
+        // the new variants are appended, older packets are encoded as before
+        assert_eq!(
+            bincode::serialize(&ClientTcpMessage::KeepAliveCheck).unwrap(),
+            vec![2, 0, 0, 0]
+        );
+        assert_eq!(
+            bincode::serialize(&ServerTcpMessage::KeepAliveCheck).unwrap(),
+            vec![5, 0, 0, 0]
+        );
+
+        // variant index, string length (u64) and the string
+        assert_eq!(
+            bincode::serialize(&ClientTcpMessage::StatusMessage {
+                text: String::from("hi"),
+            })
+            .unwrap(),
+            vec![3, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i']
+        );
+        assert_eq!(
+            bincode::serialize(&ServerTcpMessage::UserStatusMessage {
+                username: String::from("a"),
+                text: String::from("hi"),
+            })
+            .unwrap(),
+            vec![6, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, b'a', 2, 0, 0, 0, 0, 0, 0, 0, b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn received_status_message_is_submitted() {
+        let (mut tcp_service, mut server) = connected_service();
+        let (sender, receiver) = mpsc::channel::<SubmittedCommand>();
+
+        let packet = PacketBuilder::build_encrypted_packet(
+            &ServerTcpMessage::UserStatusMessage {
+                username: String::from("user1"),
+                text: String::from("Away"),
+            },
+            &SECRET_KEY,
+        )
+        .unwrap();
+        let payload = &packet[TCP_FRAME_HEADER_SIZE..];
+        server.write_all(payload).unwrap();
+
+        match tcp_service.handle_message(payload.len() as u16, EventSink::Channel(sender)) {
+            HandleMessageResult::Ok => {}
+            result => panic!("unexpected result: {:?}", result),
         }
-        let tag: [u8; CMAC_TAG_LENGTH] = tag.unwrap();
 
-        if let Err(e) = mac.verify(&tag.into()) {
-            return ConnectResult::Err(format!(
-                "Incorrect tag (error: {}), at [{}, {}].",
-                e,
-                file!(),
-                line!()
-            ));
+        let command = receiver.try_recv().unwrap();
+        let info = command.get(USER_TCP_SERVICE_USER_STATUS_MESSAGE).unwrap();
+        assert_eq!(info.username, "user1");
+        assert_eq!(info.text, "Away");
+    }
+
+    enum WriteStep {
+        Short(usize), // writes at most N bytes
+        WouldBlock,
+        Interrupted,
+        Closed,
+        Error,
+    }
+
+    // Writer that follows the steps, then writes everything.
+    struct ScriptedWriter {
+        steps: std::collections::VecDeque<WriteStep>,
+        written: Vec<u8>,
+        write_calls: usize,
+    }
+
+    impl ScriptedWriter {
+        fn new(steps: Vec<WriteStep>) -> Self {
+            ScriptedWriter {
+                steps: steps.into(),
+                written: Vec::new(),
+                write_calls: 0,
+            }
         }
+    }
 
-        // Deserialize.
-        let server_connect_packet =
-            bincode::deserialize::<ServerTcpConnectPacket>(&binary_server_connect_packet);
-        if let Err(e) = server_connect_packet {
-            return ConnectResult::IoErr(IoResult::Err(format!(
-                "Unable to deserialize a packet (error: {}), at [{}, {}].",
-                e,
-                file!(),
-                line!()
-            )));
+    impl Write for ScriptedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.write_calls += 1;
+
+            let max_len = match self.steps.pop_front() {
+                None => buf.len(),
+                Some(WriteStep::Short(max_len)) => max_len,
+                Some(WriteStep::WouldBlock) => return Err(std::io::ErrorKind::WouldBlock.into()),
+                Some(WriteStep::Interrupted) => return Err(std::io::ErrorKind::Interrupted.into()),
+                Some(WriteStep::Closed) => return Ok(0),
+                Some(WriteStep::Error) => return Err(std::io::ErrorKind::ConnectionReset.into()),
+            };
+
+            let len = max_len.min(buf.len());
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
         }
-        let server_connect_packet = server_connect_packet.unwrap();
 
-        // See answer.
-        match server_connect_packet.answer {
-            ConnectServerAnswer::Ok => {}
-            ConnectServerAnswer::WrongPassword => {
-                return ConnectResult::SleepWithErr(PASSWORD_RETRY_DELAY_SEC);
-            }
-            ConnectServerAnswer::WrongVersion => {
-                return ConnectResult::WrongProtocol(
-                    server_connect_packet.correct_net_protocol.unwrap(),
-                );
-            }
-            ConnectServerAnswer::UsernameTaken => return ConnectResult::UsernameTaken,
-            ConnectServerAnswer::ServerIsFull => return ConnectResult::ErrServerIsFull,
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
         }
+    }
 
-        // Read info about all rooms and users.
-        for room_info in server_connect_packet
-            .connected_info
-            .as_ref()
-            .unwrap()
-            .iter()
-        {
-            info_sender
-                .send(ConnectInfo::RoomInfo(room_info.room_name.clone()))
-                .unwrap();
-
-            for user in room_info.users.iter() {
-                info_sender
-                    .send(ConnectInfo::UserInfo(
-                        UserInfo::new(user.username.clone()),
-                        String::from(room_info.room_name.clone()),
-                        user.ping,
-                    ))
-                    .unwrap();
-            }
+    fn test_packet() -> Vec<u8> {
+        (0..100).collect()
+    }
+
+    #[test]
+    fn partial_writes_are_continued_at_any_offset() {
+        let packet = test_packet();
+
+        for offset in [1, 2, 50, 98, 99].iter() {
+            let mut writer = ScriptedWriter::new(vec![WriteStep::Short(*offset)]);
+
+            let result = UserTcpService::write_whole_buffer(&mut writer, &packet);
+
+            assert_eq!(result, IoResult::Ok(packet.len()), "offset {}", offset);
+            assert_eq!(writer.written, packet, "offset {}", offset);
+            assert_eq!(writer.write_calls, 2);
         }
+    }
 
-        info_sender.send(ConnectInfo::End).unwrap(); // End.
+    #[test]
+    fn many_short_writes() {
+        let packet = test_packet();
+        let mut writer = ScriptedWriter::new((0..33).map(|_| WriteStep::Short(3)).collect());
 
-        self.user_state = UserState::Connected;
+        let result = UserTcpService::write_whole_buffer(&mut writer, &packet);
 
-        ConnectResult::Ok
+        assert_eq!(result, IoResult::Ok(packet.len()));
+        assert_eq!(writer.written, packet);
+        assert_eq!(writer.write_calls, 34);
     }
-    fn send_keep_alive_check(&mut self) -> Result<(), IoResult> {
-        let client_packet = ClientTcpMessage::KeepAliveCheck;
 
-        // Serialize packet.
-        let binary_client_packet = bincode::serialize(&client_packet);
-        if let Err(e) = binary_client_packet {
-            return Err(IoResult::Err(format!(
-                "bincode::serialize failed, error: {} at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
-        }
-        let mut binary_client_packet = binary_client_packet.unwrap();
-
-        // CMAC.
-        let mut mac = Cmac::<Aes256>::new_from_slice(&self.secret_key).unwrap();
-        mac.update(&binary_client_packet);
-        let result = mac.finalize();
-        let mut tag_bytes = result.into_bytes().to_vec();
-        if tag_bytes.len() != CMAC_TAG_LENGTH {
-            return Err(IoResult::Err(format!(
-                "unexpected tag length: {} != {} at [{}, {}]",
-                tag_bytes.len(),
-                CMAC_TAG_LENGTH,
-                file!(),
-                line!()
-            )));
-        }
+    #[test]
+    fn would_block_after_partial_write_is_waited_out() {
+        let packet = test_packet();
 
-        binary_client_packet.append(&mut tag_bytes);
+        for offset in [1, 37, 99].iter() {
+            let mut writer = ScriptedWriter::new(vec![
+                WriteStep::Short(*offset),
+                WriteStep::WouldBlock,
+                WriteStep::Interrupted,
+                WriteStep::WouldBlock,
+            ]);
 
-        // Encrypt packet.
-        let mut rng = rand::thread_rng();
-        let mut iv = [0u8; IV_LENGTH];
-        rng.fill_bytes(&mut iv);
-        let mut encrypted_packet = Aes256CbcEnc::new(&self.secret_key.into(), &iv.into())
-            .encrypt_padded_vec_mut::<Pkcs7>(&binary_client_packet);
+            let result = UserTcpService::write_whole_buffer(&mut writer, &packet);
 
-        // Prepare encrypted packet len buffer.
-        let encrypted_packet_len = (encrypted_packet.len() + IV_LENGTH) as u16;
-        let encrypted_packet_len_buf = bincode::serialize(&encrypted_packet_len);
-        if let Err(e) = encrypted_packet_len_buf {
-            return Err(IoResult::Err(format!(
-                "bincode::serialize failed, error: {} at [{}, {}]",
-                e,
-                file!(),
-                line!()
-            )));
+            assert_eq!(result, IoResult::Ok(packet.len()), "offset {}", offset);
+            assert_eq!(writer.written, packet, "offset {}", offset);
         }
-        let mut encrypted_packet_len_buf = encrypted_packet_len_buf.unwrap();
+    }
 
-        // Merge all to one buffer.
-        let mut out_buffer: Vec<u8> = Vec::new();
-        out_buffer.append(&mut encrypted_packet_len_buf);
-        out_buffer.append(&mut Vec::from(iv));
-        out_buffer.append(&mut encrypted_packet);
+    #[test]
+    fn would_block_before_any_write_is_returned() {
+        let mut writer = ScriptedWriter::new(vec![WriteStep::WouldBlock]);
 
-        // Send to server.
-        loop {
-            match self.write_to_socket(&mut out_buffer) {
-                IoResult::WouldBlock => {
-                    thread::sleep(Duration::from_millis(INTERVAL_TCP_MESSAGE_MS));
-                    continue;
-                }
-                IoResult::Ok(_bytes) => break,
-                res => return Err(res),
-            }
+        let result = UserTcpService::write_whole_buffer(&mut writer, &test_packet());
+
+        assert_eq!(result, IoResult::WouldBlock);
+        assert!(writer.written.is_empty());
+        assert_eq!(writer.write_calls, 1);
+    }
+
+    #[test]
+    fn closed_or_failed_socket_stops_partial_write() {
+        let packet = test_packet();
+
+        let mut writer = ScriptedWriter::new(vec![WriteStep::Short(40), WriteStep::Closed]);
+        assert_eq!(
+            UserTcpService::write_whole_buffer(&mut writer, &packet),
+            IoResult::FIN
+        );
+        assert_eq!(writer.written, &packet[..40]);
+
+        let mut writer = ScriptedWriter::new(vec![WriteStep::Short(40), WriteStep::Error]);
+        match UserTcpService::write_whole_buffer(&mut writer, &packet) {
+            IoResult::Err(msg) => assert!(msg.contains("written 40 bytes"), "{}", msg),
+            result => panic!("unexpected result {:?}", result),
         }
+    }
 
-        Ok(())
+    #[test]
+    fn packet_is_written_to_socket() {
+        let (mut service, mut server) = connected_service();
+        let packet = test_packet();
+
+        assert_eq!(service.write_to_socket(&packet), IoResult::Ok(packet.len()));
+
+        let mut received = vec![0u8; packet.len()];
+        server.read_exact(&mut received).unwrap();
+        assert_eq!(received, packet);
+    }
+
+    #[test]
+    fn keepalive_is_enabled() {
+        let (tcp_service, _server) = connected_service();
+        let tcp_socket = tcp_service.tcp_socket.as_ref().unwrap();
+        assert!(!SockRef::from(tcp_socket).keepalive().unwrap());
+
+        UserTcpService::enable_keepalive(tcp_socket).unwrap();
+
+        let socket = SockRef::from(tcp_socket);
+        assert!(socket.keepalive().unwrap());
+        #[cfg(not(windows))]
+        assert_eq!(
+            socket.keepalive_time().unwrap(),
+            Duration::from_secs(TCP_KEEPALIVE_IDLE_SEC)
+        );
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(
+                socket.keepalive_interval().unwrap(),
+                Duration::from_secs(TCP_KEEPALIVE_INTERVAL_SEC)
+            );
+            assert_eq!(socket.keepalive_retries().unwrap(), TCP_KEEPALIVE_RETRIES);
+        }
     }
 }
@@ -1,28 +1,50 @@
 // External.
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
-use druid::{ExtEventSink, Selector, Target};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use druid::{Selector, Target};
 use rand::RngCore;
-
-type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
-type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use tracing::{debug, error, warn};
 
 // Std.
-use std::convert::TryInto;
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::net::*;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Custom.
+use super::event_sink::EventSink;
+use super::jitter_stats::JitterStats;
+use super::network_interfaces::*;
+use super::priority_udp_queue::*;
+use super::replay_window::ReplayWindow;
+use super::token_bucket::TokenBucket;
+use super::udp_health_check::UdpHealthCheck;
 use super::udp_packets::*;
 use super::user_tcp_service::SECRET_KEY_SIZE;
 use crate::global_params::*;
 use crate::services::audio_service::audio_service::*;
+use crate::services::audio_service::voice_codec::*;
+use crate::services::net_service::NETWORK_SERVICE_SYSTEM_IO_ERROR;
+
+const VOICE_UPLOAD_DROP_LOG_INTERVAL: u64 = 100; // log every N dropped chunks
+const JITTER_UPDATE_INTERVAL_SEC: u64 = 5;
+const FLOOD_DROP_WARNING_COUNT: u64 = 100; // warn the user after N consecutive dropped packets
+const NONCE_LENGTH: usize = 12; // AES-GCM nonce, random for each packet
+const TAG_LENGTH: usize = 16; // AES-GCM authentication tag, appended to the ciphertext
 
 pub const USER_UDP_SERVICE_UPDATE_USER_PING: Selector<UserPingInfo> =
     Selector::new("user_udp_servce_update_user_ping");
 
+pub const USER_UDP_SERVICE_JITTER_UPDATE: Selector<f64> =
+    Selector::new("user_udp_service_jitter_update");
+
+pub const USER_UDP_SERVICE_DECRYPTION_FAILED: Selector<u64> =
+    Selector::new("user_udp_service_decryption_failed");
+
 #[derive(Clone)]
 pub struct UserPingInfo {
     pub username: String,
@@ -36,6 +58,23 @@ pub struct UserUdpService {
     udp_socket_copy: Option<UdpSocket>,
     username: String,
     pub secret_key: [u8; SECRET_KEY_SIZE],
+    voice_upload_limiter: Option<TokenBucket>, // None if unlimited
+    dropped_voice_chunks: u64,
+    receive_limiter: Option<TokenBucket>, // None if unlimited, protects from packet floods
+    dropped_flood_packets: u64,
+    consecutive_flood_drops: u64,
+    decryption_failures: u64,
+    next_voice_seq: u32, // sequence number of the next sent voice packet
+    replay_windows: HashMap<String, ReplayWindow>, // username - received voice packets
+    replayed_packets: u64,
+    jitter_stats: JitterStats,
+    last_jitter_update: Instant,
+    send_queue: Option<Arc<PriorityUdpQueue>>, // None until connected
+    recv_buffer_kb: u32,
+    send_buffer_kb: u32,
+    health_check_timeout: Option<Duration>, // None if the health check is disabled
+    preferred_network_interface: String,    // empty to bind to all interfaces
+    pub request_stop: Arc<AtomicBool>, // checked by the UDP thread, new one for each connection
 }
 
 impl UserUdpService {
@@ -45,47 +84,343 @@ impl UserUdpService {
             udp_socket_copy: None,
             username: String::from(""),
             secret_key: [0; SECRET_KEY_SIZE],
+            voice_upload_limiter: None,
+            dropped_voice_chunks: 0,
+            receive_limiter: None,
+            dropped_flood_packets: 0,
+            consecutive_flood_drops: 0,
+            decryption_failures: 0,
+            next_voice_seq: 0,
+            replay_windows: HashMap::new(),
+            replayed_packets: 0,
+            jitter_stats: JitterStats::new(),
+            last_jitter_update: Instant::now(),
+            send_queue: None,
+            recv_buffer_kb: DEFAULT_UDP_RECV_BUFFER_KB,
+            send_buffer_kb: DEFAULT_UDP_SEND_BUFFER_KB,
+            health_check_timeout: Some(Duration::from_secs(
+                DEFAULT_UDP_HEALTH_CHECK_TIMEOUT_SEC as u64,
+            )),
+            preferred_network_interface: String::new(),
+            request_stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    /// Overwrites the key so that it does not stay in memory after disconnect.
+    pub fn zero_secret_key(&mut self) {
+        self.secret_key = [0; SECRET_KEY_SIZE];
+    }
+    /// Used for the next socket created by 'create_socket'.
+    pub fn set_socket_buffer_sizes(&mut self, recv_buffer_kb: u32, send_buffer_kb: u32) {
+        self.recv_buffer_kb = recv_buffer_kb;
+        self.send_buffer_kb = send_buffer_kb;
+    }
+    /// Used for the next connection.
+    pub fn set_health_check(&mut self, enabled: bool, timeout_sec: u8) {
+        self.health_check_timeout = if enabled {
+            Some(Duration::from_secs(timeout_sec as u64))
+        } else {
+            None
+        };
+    }
+    /// Used for the next socket created by 'create_socket', empty to bind to all interfaces.
+    pub fn set_preferred_network_interface(&mut self, interface_name: String) {
+        self.preferred_network_interface = interface_name;
+    }
+    /// Returns a new health check if it's enabled.
+    pub fn create_health_check(&self) -> Option<UdpHealthCheck> {
+        self.health_check_timeout
+            .map(|timeout| UdpHealthCheck::new(timeout, Instant::now()))
+    }
+    /// Creates a UDP socket with SO_RCVBUF and SO_SNDBUF set to the configured values.
+    /// Returns the socket and warnings (the OS might refuse or cap the requested sizes).
+    /// If socket2 fails the socket is created with the default buffer sizes.
+    /// The socket is bound to the preferred network interface (if it's set and found).
+    pub fn create_socket(&self, server_address: &str) -> Result<(UdpSocket, Vec<String>), String> {
+        let mut warnings = Vec::new();
+
+        let bind_ip = self.get_bind_ip(server_address, &mut warnings);
+
+        let socket = match Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)) {
+            Ok(socket) => socket,
+            Err(e) => {
+                warnings.push(format!(
+                    "failed to create a UDP socket with custom buffer sizes (error: {}), using default buffer sizes",
+                    e
+                ));
+                return UserUdpService::create_default_socket(bind_ip, warnings);
+            }
+        };
+
+        for (name, requested_kb, set_result, actual_size) in [
+            (
+                "SO_RCVBUF",
+                self.recv_buffer_kb,
+                socket.set_recv_buffer_size(self.recv_buffer_kb as usize * 1024),
+                socket.recv_buffer_size(),
+            ),
+            (
+                "SO_SNDBUF",
+                self.send_buffer_kb,
+                socket.set_send_buffer_size(self.send_buffer_kb as usize * 1024),
+                socket.send_buffer_size(),
+            ),
+        ] {
+            if let Err(e) = set_result {
+                warnings.push(format!(
+                    "failed to set {} to {} KB (error: {})",
+                    name, requested_kb, e
+                ));
+                continue;
+            }
+
+            // some OSes cap the value (note that Linux reports double the size that was set)
+            if let Ok(actual_size) = actual_size {
+                if actual_size < requested_kb as usize * 1024 {
+                    warnings.push(format!(
+                        "{} was set to {} KB instead of the requested {} KB (capped by the OS)",
+                        name,
+                        actual_size / 1024,
+                        requested_kb
+                    ));
+                }
+            }
+        }
+
+        let address = SocketAddr::new(bind_ip, 0);
+        if let Err(e) = socket.bind(&SockAddr::from(address)) {
+            warnings.push(format!(
+                "failed to bind a UDP socket with custom buffer sizes (error: {}), using default buffer sizes",
+                e
+            ));
+            return UserUdpService::create_default_socket(bind_ip, warnings);
+        }
+
+        Ok((socket.into(), warnings))
+    }
+    fn create_default_socket(
+        bind_ip: IpAddr,
+        warnings: Vec<String>,
+    ) -> Result<(UdpSocket, Vec<String>), String> {
+        match UdpSocket::bind(SocketAddr::new(bind_ip, 0)) {
+            Ok(socket) => Ok((socket, warnings)),
+            Err(e) => Err(format!(
+                "UdpSocket::bind() failed, error: {}, at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            )),
+        }
+    }
+    /// Returns the IP of the preferred network interface or 0.0.0.0 (all interfaces)
+    /// if the interface is not set or not found.
+    fn get_bind_ip(&self, server_address: &str, warnings: &mut Vec<String>) -> IpAddr {
+        if self.preferred_network_interface.is_empty() {
+            return IpAddr::V4(Ipv4Addr::UNSPECIFIED);
         }
+
+        self.select_bind_ip(server_address, get_network_interfaces(), warnings)
+    }
+    fn select_bind_ip(
+        &self,
+        server_address: &str,
+        interfaces: Result<Vec<NetworkInterface>, String>,
+        warnings: &mut Vec<String>,
+    ) -> IpAddr {
+        let any_ip = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        if self.preferred_network_interface.is_empty() {
+            return any_ip;
+        }
+
+        let interfaces = match interfaces {
+            Ok(interfaces) => interfaces,
+            Err(msg) => {
+                warnings.push(format!("{}, binding to all network interfaces", msg));
+                return any_ip;
+            }
+        };
+
+        let interface_ip = match resolve_interface_ip(
+            &self.preferred_network_interface,
+            &interfaces,
+        ) {
+            Some(ip) => IpAddr::V4(ip),
+            None => {
+                warnings.push(format!(
+                        "network interface \"{}\" was not found (or has no IPv4 address), binding to all network interfaces",
+                        self.preferred_network_interface
+                    ));
+                return any_ip;
+            }
+        };
+
+        if let Some(route_ip) = get_route_ip(server_address) {
+            if route_ip != interface_ip {
+                warnings.push(format!(
+                    "the server is not routed through the network interface \"{}\" (the system uses {}), voice chat may not work",
+                    self.preferred_network_interface, route_ip
+                ));
+            }
+        }
+
+        interface_ip
+    }
+    // 0 means unlimited.
+    pub fn set_max_voice_upload_kbps(&mut self, max_kbps: u32) {
+        if max_kbps == 0 {
+            self.voice_upload_limiter = None;
+            return;
+        }
+
+        let bytes_per_sec = max_kbps as u64 * 1000 / 8;
+        self.voice_upload_limiter = Some(TokenBucket::new(bytes_per_sec, bytes_per_sec));
+    }
+    // 0 means unlimited.
+    pub fn set_max_voice_packets_per_sec(&mut self, max_packets: u32) {
+        if max_packets == 0 {
+            self.receive_limiter = None;
+            return;
+        }
+
+        // one token per packet, allow a burst of one second
+        self.receive_limiter = Some(TokenBucket::new(max_packets as u64, max_packets as u64));
     }
+
     pub fn assign_socket_and_name(&mut self, socket: UdpSocket, username: String) {
         self.udp_socket_copy = Some(socket);
         self.username = username;
     }
-    pub fn send_voice_message(&mut self, voice_chunk: Vec<i16>) {
-        let packet = ClientUdpMessage::VoiceMessage {
-            samples: voice_chunk,
+    /// After this call all packets are sent through the queue (by the sender thread).
+    pub fn set_send_queue(&mut self, send_queue: Arc<PriorityUdpQueue>) {
+        self.send_queue = Some(send_queue);
+    }
+    /// Encodes the chunk if 'opus_encoder' is specified (the empty chunk that finishes
+    /// the voice session is always sent as raw samples).
+    pub fn send_voice_message(
+        &mut self,
+        voice_chunk: Vec<i16>,
+        opus_encoder: Option<&mut OpusVoiceEncoder>,
+    ) {
+        let seq = self.next_voice_seq;
+        self.next_voice_seq = self.next_voice_seq.wrapping_add(1);
+
+        let packet = match opus_encoder {
+            Some(encoder) if !voice_chunk.is_empty() => match encoder.encode(&voice_chunk) {
+                Ok(data) => ClientUdpMessage::OpusVoiceMessage { seq, data },
+                Err(msg) => {
+                    warn!("{}, sending raw samples instead", msg);
+                    ClientUdpMessage::VoiceMessage {
+                        seq,
+                        samples: voice_chunk,
+                    }
+                }
+            },
+            _ => ClientUdpMessage::VoiceMessage {
+                seq,
+                samples: voice_chunk,
+            },
         };
 
         let binary_packet = bincode::serialize(&packet).unwrap();
-        if binary_packet.len() + std::mem::size_of::<u16>() > UDP_PACKET_MAX_SIZE as usize {
+        if binary_packet.len() + std::mem::size_of::<u16>() + NONCE_LENGTH + TAG_LENGTH
+            > UDP_PACKET_MAX_SIZE as usize
+        {
             // using std::mem::size_of::<u16>() as packet size
             panic!(
-                "Binary packet size + size_of::<u16> exceeded the limit ({}) at [{}, {}].",
+                "Binary packet size + size_of::<u16> + nonce + tag exceeded the limit ({}) at [{}, {}].",
                 UDP_PACKET_MAX_SIZE,
                 file!(),
                 line!()
             );
         }
 
-        // Encrypt.
-        let mut rng = rand::thread_rng();
-        let mut iv = [0u8; IV_LENGTH];
-        rng.fill_bytes(&mut iv);
-        let mut encrypted_packet = Aes256CbcEnc::new(&self.secret_key.into(), &iv.into())
-            .encrypt_padded_vec_mut::<Pkcs7>(&binary_packet);
+        let packet_size = self.encrypt_packet(&binary_packet);
 
-        let packet_size: u16 = (encrypted_packet.len() + IV_LENGTH) as u16;
-        let mut packet_size = bincode::serialize(&packet_size).unwrap();
+        // Check upload limit.
+        if let Some(limiter) = self.voice_upload_limiter.as_mut() {
+            if !limiter.try_consume(packet_size.len() as u64) {
+                // dropping is better than adding latency
+                self.dropped_voice_chunks += 1;
+                if self.dropped_voice_chunks % VOICE_UPLOAD_DROP_LOG_INTERVAL == 1 {
+                    warn!(
+                        "voice upload limit reached, dropped {} chunk(s) in total.",
+                        self.dropped_voice_chunks
+                    );
+                }
+                return;
+            }
+        }
+
+        // Queue this buffer.
+        let send_queue = self.send_queue.as_ref();
+        if send_queue.is_none() || send_queue.unwrap().is_closed() {
+            // not connected
+            return;
+        }
+        if send_queue
+            .unwrap()
+            .push(packet_size, UdpPacketPriority::Normal)
+            .is_err()
+        {
+            // the sender can't keep up, dropping is better than adding latency
+            self.dropped_voice_chunks += 1;
+            if self.dropped_voice_chunks % VOICE_UPLOAD_DROP_LOG_INTERVAL == 1 {
+                warn!(
+                    "voice send queue is full, dropped {} chunk(s) in total.",
+                    self.dropped_voice_chunks
+                );
+            }
+        }
+    }
+    /// Sent instead of silent voice chunks, the receiver plays comfort noise.
+    pub fn send_silence_marker(&mut self) {
+        let binary_packet = bincode::serialize(&ClientUdpMessage::SilenceMarker).unwrap();
+
+        let packet_size = self.encrypt_packet(&binary_packet);
+
+        // Queue this buffer (same priority as voice to keep the order).
+        let send_queue = self.send_queue.as_ref();
+        if send_queue.is_none() || send_queue.unwrap().is_closed() {
+            // not connected
+            return;
+        }
+        if send_queue
+            .unwrap()
+            .push(packet_size, UdpPacketPriority::Normal)
+            .is_err()
+        {
+            // the sender can't keep up, the next marker will be sent soon
+            self.dropped_voice_chunks += 1;
+        }
+    }
+    pub fn send_voice_transmission_state(&mut self, paused: bool) {
+        if self.udp_socket_copy.is_none() {
+            // not connected yet
+            return;
+        }
+
+        let packet = if paused {
+            ClientUdpMessage::VoicePaused
+        } else {
+            ClientUdpMessage::VoiceResumed
+        };
+
+        let binary_packet = bincode::serialize(&packet).unwrap();
 
-        packet_size.append(&mut Vec::from(iv));
-        packet_size.append(&mut encrypted_packet);
+        let packet_size = self.encrypt_packet(&binary_packet);
 
         // Send this buffer.
-        if let Err(msg) = self.send(&self.udp_socket_copy.as_ref().unwrap(), &packet_size) {
-            print!("{}, at [{}, {}]", msg, file!(), line!());
+        if let Err(msg) = self.send_with_priority(
+            &self.udp_socket_copy.as_ref().unwrap(),
+            packet_size,
+            UdpPacketPriority::High,
+        ) {
+            error!("{} at [{}, {}]", msg, file!(), line!());
         }
     }
     pub fn connect(&mut self, udp_socket: &UdpSocket) -> Result<(), String> {
+        self.next_voice_seq = 0;
+        self.replay_windows.clear();
+
         let packet = ClientUdpMessage::Connect {
             username: self.username.clone(),
         };
@@ -149,33 +484,10 @@ impl UserUdpService {
             }
         }
 
-        // Get IV.
-        if recv_buffer.len() < IV_LENGTH {
-            return Err(format!(
-                "received data is too small, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv = recv_buffer[..IV_LENGTH].to_vec();
-        recv_buffer = recv_buffer[IV_LENGTH..].to_vec();
-
-        // Convert IV.
-        let iv = iv.try_into();
-        if iv.is_err() {
-            return Err(format!(
-                "failed to convert iv to generic array, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv: [u8; IV_LENGTH] = iv.unwrap();
-
         // Decrypt packet.
-        let decrypted_packet = Aes256CbcDec::new(&self.secret_key.into(), &iv.into())
-            .decrypt_padded_vec_mut::<Pkcs7>(&recv_buffer);
-        if let Err(e) = decrypted_packet {
-            return Err(format!("{:?}, at [{}, {}]", e, file!(), line!()));
+        let decrypted_packet = self.decrypt_packet(&recv_buffer);
+        if let Err(msg) = decrypted_packet {
+            return Err(format!("{}, at [{}, {}]", msg, file!(), line!()));
         }
         let decrypted_packet = decrypted_packet.unwrap();
 
@@ -203,10 +515,34 @@ impl UserUdpService {
     pub fn handle_message(
         &mut self,
         udp_socket: &UdpSocket,
-        event_sink: ExtEventSink,
+        event_sink: EventSink,
         audio_service: Arc<Mutex<AudioService>>,
     ) -> Result<(), String> {
         let mut recv_buffer = vec![0u8; UDP_PACKET_MAX_SIZE as usize];
+
+        // Check receive limit.
+        if !self.is_receive_allowed() {
+            // receive the packet to remove it from the socket but don't process it
+            if let Err(msg) = self.recv(udp_socket, &mut recv_buffer) {
+                return Err(format!("{}, at [{}, {}]", msg, file!(), line!()));
+            }
+
+            if self.count_dropped_flood_packet() {
+                event_sink
+                    .submit_command(
+                        NETWORK_SERVICE_SYSTEM_IO_ERROR,
+                        format!(
+                            "Receiving too many UDP packets from the server, dropped {} packet(s) in total.",
+                            self.dropped_flood_packets
+                        ),
+                        Target::Auto,
+                    )
+                    .expect("failed to submit NETWORK_SERVICE_SYSTEM_IO_ERROR command");
+            }
+            return Ok(());
+        }
+        self.consecutive_flood_drops = 0;
+
         match self.recv(udp_socket, &mut recv_buffer) {
             Ok(byte_count) => {
                 if byte_count < std::mem::size_of::<u16>() {
@@ -244,33 +580,23 @@ impl UserUdpService {
             }
         }
 
-        // Get IV.
-        if recv_buffer.len() < IV_LENGTH {
-            return Err(format!(
-                "received data is too small, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv = recv_buffer[..IV_LENGTH].to_vec();
-        recv_buffer = recv_buffer[IV_LENGTH..].to_vec();
-
-        // Convert IV.
-        let iv = iv.try_into();
-        if iv.is_err() {
-            return Err(format!(
-                "failed to convert iv to generic array, at [{}, {}].",
-                file!(),
-                line!()
-            ));
-        }
-        let iv: [u8; IV_LENGTH] = iv.unwrap();
-
         // Decrypt packet.
-        let decrypted_packet = Aes256CbcDec::new(&self.secret_key.into(), &iv.into())
-            .decrypt_padded_vec_mut::<Pkcs7>(&recv_buffer);
-        if let Err(e) = decrypted_packet {
-            return Err(format!("{:?}, at [{}, {}]", e, file!(), line!()));
+        let decrypted_packet = self.decrypt_packet(&recv_buffer);
+        if let Err(msg) = decrypted_packet {
+            // forged or corrupted packet, not a reason to disconnect
+            self.decryption_failures += 1;
+            warn!(
+                "failed to decrypt UDP packet ({} failure(s) in total): {}",
+                self.decryption_failures, msg
+            );
+            event_sink
+                .submit_command(
+                    USER_UDP_SERVICE_DECRYPTION_FAILED,
+                    self.decryption_failures,
+                    Target::Auto,
+                )
+                .expect("failed to submit USER_UDP_SERVICE_DECRYPTION_FAILED command");
+            return Ok(());
         }
         let decrypted_packet = decrypted_packet.unwrap();
 
@@ -283,8 +609,28 @@ impl UserUdpService {
 
         match packet_buf {
             ServerUdpMessage::PingCheck => {
+                let received_time = Instant::now();
+
                 // Send it back.
-                return self.answer_ping(udp_socket);
+                if let Err(msg) = self.answer_ping(udp_socket) {
+                    return Err(format!("{}, at [{}, {}]", msg, file!(), line!()));
+                }
+
+                // the server does not tell us our own ping
+                event_sink
+                    .submit_command(
+                        USER_UDP_SERVICE_UPDATE_USER_PING,
+                        UserPingInfo {
+                            username: self.username.clone(),
+                            ping_ms: UserUdpService::get_local_rtt_ms(
+                                received_time,
+                                Instant::now(),
+                            ),
+                            try_again_count: 0, // the next ping check will update it
+                        },
+                        Target::Auto,
+                    )
+                    .expect("failed to submit USER_UDP_SERVICE_UPDATE_USER_PING command");
             }
             ServerUdpMessage::UserPing { username, ping_ms } => {
                 event_sink
@@ -299,56 +645,221 @@ impl UserUdpService {
                     )
                     .expect("failed to submit USER_UDP_SERVICE_UPDATE_USER_PING command");
             }
-            ServerUdpMessage::VoiceMessage { username, samples } => {
+            ServerUdpMessage::VoiceMessage {
+                username,
+                seq,
+                samples,
+            } => {
+                if !self.accept_voice_seq(&username, seq) {
+                    return Ok(());
+                }
+                self.on_voice_message_received(&event_sink);
+
+                audio_service.lock().unwrap().add_user_voice_chunk(
+                    username,
+                    VoiceChunk::Pcm(samples),
+                    event_sink,
+                );
+            }
+            ServerUdpMessage::OpusVoiceMessage {
+                username,
+                seq,
+                data,
+            } => {
+                if !self.accept_voice_seq(&username, seq) {
+                    return Ok(());
+                }
+                self.on_voice_message_received(&event_sink);
+
+                audio_service.lock().unwrap().add_user_voice_chunk(
+                    username,
+                    VoiceChunk::Opus(data),
+                    event_sink,
+                );
+            }
+            ServerUdpMessage::SilenceMarker { username } => {
                 audio_service
                     .lock()
                     .unwrap()
-                    .add_user_voice_chunk(username, samples, event_sink);
+                    .add_user_silence_marker(username, event_sink);
             }
         }
 
         Ok(())
     }
+    /// Returns 'false' if the voice packet was already received or is too old
+    /// (replayed by an attacker or delayed by the network), such packets are dropped.
+    fn accept_voice_seq(&mut self, username: &str, seq: u32) -> bool {
+        let accepted = self
+            .replay_windows
+            .entry(String::from(username))
+            .or_insert_with(ReplayWindow::new)
+            .accept(seq);
+
+        if !accepted {
+            self.replayed_packets += 1;
+            debug!(
+                "dropped old voice packet #{} of user '{}' ({} packet(s) in total).",
+                seq, username, self.replayed_packets
+            );
+        }
+
+        accepted
+    }
+    /// The user's sequence numbers start from zero when the user connects again.
+    pub fn remove_replay_window(&mut self, username: &str) {
+        self.replay_windows.remove(username);
+    }
+    /// Updates the jitter statistics.
+    fn on_voice_message_received(&mut self, event_sink: &EventSink) {
+        let now = Instant::now();
+        self.jitter_stats.add_arrival(now);
+        if now.duration_since(self.last_jitter_update).as_secs() >= JITTER_UPDATE_INTERVAL_SEC {
+            self.last_jitter_update = now;
+            event_sink
+                .submit_command(
+                    USER_UDP_SERVICE_JITTER_UPDATE,
+                    self.get_jitter_ms(),
+                    Target::Auto,
+                )
+                .expect("failed to submit USER_UDP_SERVICE_JITTER_UPDATE command");
+        }
+    }
+    /// Returns the packet ready to be sent: size (u16), nonce, ciphertext and authentication tag
+    /// (the size does not include itself).
+    fn encrypt_packet(&self, binary_packet: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let cipher = Aes256Gcm::new(&self.secret_key.into());
+        let mut encrypted_packet = cipher
+            .encrypt(Nonce::from_slice(&nonce), binary_packet)
+            .expect("AES-GCM encryption failed");
+
+        let packet_size: u16 = (NONCE_LENGTH + encrypted_packet.len()) as u16;
+        let mut packet = bincode::serialize(&packet_size).unwrap();
+
+        packet.append(&mut Vec::from(nonce));
+        packet.append(&mut encrypted_packet);
+
+        packet
+    }
+    /// Expects the nonce, ciphertext and authentication tag (without the packet size),
+    /// fails if the packet was modified or encrypted with a different key.
+    fn decrypt_packet(&self, encrypted_packet: &[u8]) -> Result<Vec<u8>, String> {
+        if encrypted_packet.len() < NONCE_LENGTH + TAG_LENGTH {
+            return Err(format!(
+                "received data is too small ({} bytes), at [{}, {}]",
+                encrypted_packet.len(),
+                file!(),
+                line!()
+            ));
+        }
+
+        let (nonce, ciphertext) = encrypted_packet.split_at(NONCE_LENGTH);
+        let cipher = Aes256Gcm::new(&self.secret_key.into());
+        match cipher.decrypt(Nonce::from_slice(nonce), ciphertext) {
+            Ok(decrypted_packet) => Ok(decrypted_packet),
+            Err(_) => Err(format!(
+                "AES-GCM authentication failed, at [{}, {}]",
+                file!(),
+                line!()
+            )),
+        }
+    }
+    /// Returns 'true' if the user should be warned about the flood.
+    fn count_dropped_flood_packet(&mut self) -> bool {
+        self.dropped_flood_packets += 1;
+        self.consecutive_flood_drops += 1;
+
+        self.consecutive_flood_drops == FLOOD_DROP_WARNING_COUNT
+    }
+    fn is_receive_allowed(&mut self) -> bool {
+        match self.receive_limiter.as_mut() {
+            Some(limiter) => limiter.try_consume(1),
+            None => true,
+        }
+    }
+    pub fn get_jitter_ms(&self) -> f64 {
+        self.jitter_stats.get_jitter_ms()
+    }
+    /// Returns the time between receiving the server's ping check and answering it
+    /// (clamped to u16::MAX), this is the client's part of the round trip.
+    pub fn get_local_rtt_ms(ping_check_received: Instant, answer_sent: Instant) -> u16 {
+        answer_sent
+            .saturating_duration_since(ping_check_received)
+            .as_millis()
+            .min(u16::MAX as u128) as u16
+    }
     fn answer_ping(&self, udp_socket: &UdpSocket) -> Result<(), String> {
         let packet = ClientUdpMessage::PingCheck {};
 
         let binary_packet = bincode::serialize(&packet).unwrap();
-        if binary_packet.len() + std::mem::size_of::<u16>() > UDP_PACKET_MAX_SIZE as usize {
+        if binary_packet.len() + std::mem::size_of::<u16>() + NONCE_LENGTH + TAG_LENGTH
+            > UDP_PACKET_MAX_SIZE as usize
+        {
             // using std::mem::size_of::<u16>() as packet size
             panic!(
-                "Binary packet size + size_of::<u16> exceeded the limit ({}) at [{}, {}].",
+                "Binary packet size + size_of::<u16> + nonce + tag exceeded the limit ({}) at [{}, {}].",
                 UDP_PACKET_MAX_SIZE,
                 file!(),
                 line!()
             );
         }
 
-        // Encrypt.
-        let mut rng = rand::thread_rng();
-        let mut iv = [0u8; IV_LENGTH];
-        rng.fill_bytes(&mut iv);
-        let mut encrypted_packet = Aes256CbcEnc::new(&self.secret_key.into(), &iv.into())
-            .encrypt_padded_vec_mut::<Pkcs7>(&binary_packet);
-
-        let packet_size: u16 = (encrypted_packet.len() + IV_LENGTH) as u16;
-        let mut packet_size = bincode::serialize(&packet_size).unwrap();
-
-        packet_size.append(&mut Vec::from(iv));
-        packet_size.append(&mut encrypted_packet);
+        let packet_size = self.encrypt_packet(&binary_packet);
 
         // Send this buffer.
-        if let Err(msg) = self.send(udp_socket, &packet_size) {
+        if let Err(msg) = self.send_with_priority(udp_socket, packet_size, UdpPacketPriority::High)
+        {
             return Err(format!("{}, at [{}, {}]", msg, file!(), line!()));
         }
 
         Ok(())
     }
+    /// Puts the packet in the send queue or sends it right away if the queue is not created yet
+    /// (while connecting).
+    fn send_with_priority(
+        &self,
+        udp_socket: &UdpSocket,
+        packet: Vec<u8>,
+        priority: UdpPacketPriority,
+    ) -> Result<(), String> {
+        match self.send_queue.as_ref() {
+            Some(send_queue) => {
+                if send_queue.push(packet, priority).is_err() {
+                    return Err(format!(
+                        "failed to queue a packet (the queue is full or closed), at [{}, {}]",
+                        file!(),
+                        line!()
+                    ));
+                }
+                Ok(())
+            }
+            None => self.send(udp_socket, &packet),
+        }
+    }
     pub fn send(&self, udp_socket: &UdpSocket, buf: &[u8]) -> Result<(), String> {
         let _io_guard = self.io_udp_mutex.lock().unwrap();
 
+        UserUdpService::send_to_socket(udp_socket, buf, None)
+    }
+    /// Used by the sender thread, which is the only writer after the queue is created.
+    /// The health check (if specified) is notified about 'WouldBlock' results.
+    pub fn send_to_socket(
+        udp_socket: &UdpSocket,
+        buf: &[u8],
+        health_check: Option<&Mutex<UdpHealthCheck>>,
+    ) -> Result<(), String> {
         loop {
             match udp_socket.send(buf) {
                 Ok(n) => {
+                    if let Some(health_check) = health_check {
+                        health_check.lock().unwrap().on_packet_sent();
+                    }
+
+                    // A datagram is sent as a whole, we can't send "the rest" of it
+                    // as a separate datagram, so a partial send is an error.
                     if n != buf.len() {
                         return Err(format!("udp_socket.send() failed, error: sent only {} bytes out of {}, at [{}, {}]",
                         n, buf.len(), file!(), line!()));
@@ -357,6 +868,12 @@ impl UserUdpService {
                     }
                 }
                 Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    if let Some(health_check) = health_check {
+                        health_check
+                            .lock()
+                            .unwrap()
+                            .on_send_would_block(Instant::now());
+                    }
                     thread::sleep(Duration::from_millis(INTERVAL_UDP_MESSAGE_MS));
                     continue;
                 }
@@ -425,3 +942,260 @@ impl UserUdpService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_replay_window_accepts_restarted_sequence() {
+        let mut service = UserUdpService::new();
+
+        assert!(service.accept_voice_seq("user", 100));
+        assert!(service.accept_voice_seq("other", 100));
+        assert!(!service.accept_voice_seq("user", 0));
+
+        // the user reconnected
+        service.remove_replay_window("user");
+
+        assert!(service.accept_voice_seq("user", 0));
+        assert!(service.accept_voice_seq("user", 1));
+        assert!(!service.accept_voice_seq("other", 0));
+        assert_eq!(service.replayed_packets, 2);
+    }
+
+    #[test]
+    fn packets_over_limit_are_not_allowed() {
+        const MAX_PACKETS_PER_SEC: u32 = 50;
+        let mut service = UserUdpService::new();
+        service.set_max_voice_packets_per_sec(MAX_PACKETS_PER_SEC);
+
+        let start = Instant::now();
+        let allowed_count = (0..MAX_PACKETS_PER_SEC * 2)
+            .filter(|_| service.is_receive_allowed())
+            .count();
+
+        // the bucket is refilled while the test runs
+        let max_refilled_count =
+            (start.elapsed().as_secs_f64() * MAX_PACKETS_PER_SEC as f64).ceil() as usize;
+        assert!(allowed_count >= MAX_PACKETS_PER_SEC as usize);
+        assert!(allowed_count <= MAX_PACKETS_PER_SEC as usize + max_refilled_count);
+    }
+
+    #[test]
+    fn zero_limit_allows_all_packets() {
+        let mut service = UserUdpService::new();
+        service.set_max_voice_packets_per_sec(10);
+        service.set_max_voice_packets_per_sec(0);
+
+        assert!((0..10000).all(|_| service.is_receive_allowed()));
+    }
+
+    #[test]
+    fn flood_warning_after_consecutive_drops() {
+        let mut service = UserUdpService::new();
+
+        for _ in 1..FLOOD_DROP_WARNING_COUNT {
+            assert!(!service.count_dropped_flood_packet());
+        }
+        assert!(service.count_dropped_flood_packet());
+
+        // shown once per flood
+        for _ in 0..FLOOD_DROP_WARNING_COUNT * 2 {
+            assert!(!service.count_dropped_flood_packet());
+        }
+        assert_eq!(service.dropped_flood_packets, FLOOD_DROP_WARNING_COUNT * 3);
+    }
+
+    #[test]
+    fn accepted_packet_resets_flood_warning() {
+        let mut service = UserUdpService::new();
+
+        for _ in 1..FLOOD_DROP_WARNING_COUNT {
+            service.count_dropped_flood_packet();
+        }
+
+        // what 'handle_message' does when the packet is allowed
+        service.consecutive_flood_drops = 0;
+
+        for _ in 1..FLOOD_DROP_WARNING_COUNT {
+            assert!(!service.count_dropped_flood_packet());
+        }
+        assert!(service.count_dropped_flood_packet());
+        assert_eq!(
+            service.dropped_flood_packets,
+            (FLOOD_DROP_WARNING_COUNT - 1) * 2 + 1
+        );
+    }
+
+    fn get_buffer_sizes(socket: &UdpSocket) -> (usize, usize) {
+        let socket = socket2::SockRef::from(socket);
+        (
+            socket.recv_buffer_size().unwrap(),
+            socket.send_buffer_size().unwrap(),
+        )
+    }
+
+    #[test]
+    fn configured_buffer_sizes_are_set() {
+        let mut service = UserUdpService::new();
+        service.set_socket_buffer_sizes(MIN_UDP_SOCKET_BUFFER_KB * 4, MIN_UDP_SOCKET_BUFFER_KB * 2);
+
+        let (socket, warnings) = service.create_socket("127.0.0.1:51337").unwrap();
+
+        let (recv_size, send_size) = get_buffer_sizes(&socket);
+        assert!(recv_size >= MIN_UDP_SOCKET_BUFFER_KB as usize * 4 * 1024);
+        assert!(send_size >= MIN_UDP_SOCKET_BUFFER_KB as usize * 2 * 1024);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert!(socket.local_addr().unwrap().ip().is_unspecified());
+    }
+
+    #[test]
+    fn default_buffer_sizes_are_used_by_new_service() {
+        let (socket, warnings) = UserUdpService::new()
+            .create_socket("127.0.0.1:51337")
+            .unwrap();
+
+        let (recv_size, send_size) = get_buffer_sizes(&socket);
+        if recv_size < DEFAULT_UDP_RECV_BUFFER_KB as usize * 1024 {
+            assert!(warnings.iter().any(|warning| warning.contains("SO_RCVBUF")));
+        }
+        if send_size < DEFAULT_UDP_SEND_BUFFER_KB as usize * 1024 {
+            assert!(warnings.iter().any(|warning| warning.contains("SO_SNDBUF")));
+        }
+    }
+
+    #[test]
+    fn capped_buffer_size_is_reported() {
+        let mut service = UserUdpService::new();
+        service.set_socket_buffer_sizes(MAX_UDP_SOCKET_BUFFER_KB, MIN_UDP_SOCKET_BUFFER_KB);
+
+        // the socket is created even if the OS caps the size
+        let (socket, warnings) = service.create_socket("127.0.0.1:51337").unwrap();
+
+        let (recv_size, _) = get_buffer_sizes(&socket);
+        let is_capped = recv_size < MAX_UDP_SOCKET_BUFFER_KB as usize * 1024;
+        assert_eq!(
+            warnings
+                .iter()
+                .any(|warning| warning.contains("SO_RCVBUF") && warning.contains("capped")),
+            is_capped
+        );
+        assert!(!warnings.iter().any(|warning| warning.contains("SO_SNDBUF")));
+    }
+
+    fn test_interfaces() -> Vec<NetworkInterface> {
+        vec![
+            NetworkInterface {
+                name: String::from("Ethernet"),
+                ip: Ipv4Addr::LOCALHOST,
+            },
+            NetworkInterface {
+                name: String::from("VPN (сеть)"),
+                ip: Ipv4Addr::new(10, 8, 0, 2),
+            },
+        ]
+    }
+
+    fn select_bind_ip(
+        interface_name: &str,
+        interfaces: Result<Vec<NetworkInterface>, String>,
+    ) -> (IpAddr, Vec<String>) {
+        let mut service = UserUdpService::new();
+        service.set_preferred_network_interface(String::from(interface_name));
+
+        let mut warnings = Vec::new();
+        let bind_ip = service.select_bind_ip("127.0.0.1:51337", interfaces, &mut warnings);
+
+        (bind_ip, warnings)
+    }
+
+    #[test]
+    fn all_interfaces_are_used_by_default() {
+        let (bind_ip, warnings) = select_bind_ip("", Ok(test_interfaces()));
+
+        assert_eq!(bind_ip, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn preferred_interface_ip_is_used() {
+        let (bind_ip, warnings) = select_bind_ip("Ethernet", Ok(test_interfaces()));
+
+        assert_eq!(bind_ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn interface_without_route_to_server_is_used_with_warning() {
+        let (bind_ip, warnings) = select_bind_ip("VPN (сеть)", Ok(test_interfaces()));
+
+        assert_eq!(bind_ip, IpAddr::V4(Ipv4Addr::new(10, 8, 0, 2)));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("is not routed through the network interface"));
+    }
+
+    #[test]
+    fn missing_interface_falls_back_to_all_interfaces() {
+        let (bind_ip, warnings) = select_bind_ip("Wi-Fi", Ok(test_interfaces()));
+
+        assert_eq!(bind_ip, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("\"Wi-Fi\" was not found"));
+    }
+
+    #[test]
+    fn failed_enumeration_falls_back_to_all_interfaces() {
+        let (bind_ip, warnings) = select_bind_ip(
+            "Ethernet",
+            Err(String::from("failed to get network interfaces")),
+        );
+
+        assert_eq!(bind_ip, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(
+            warnings,
+            vec![String::from(
+                "failed to get network interfaces, binding to all network interfaces"
+            )]
+        );
+    }
+
+    #[test]
+    fn local_rtt_is_time_between_ping_check_and_answer() {
+        let received = Instant::now();
+
+        for delay_ms in [0, 1, 37, 250].iter() {
+            let answered = received + Duration::from_millis(*delay_ms);
+
+            assert_eq!(
+                UserUdpService::get_local_rtt_ms(received, answered),
+                *delay_ms as u16
+            );
+        }
+    }
+
+    #[test]
+    fn local_rtt_of_delayed_answer() {
+        let received = Instant::now();
+        std::thread::sleep(Duration::from_millis(20));
+
+        let rtt_ms = UserUdpService::get_local_rtt_ms(received, Instant::now());
+
+        assert!((20..1000).contains(&rtt_ms), "rtt: {} ms", rtt_ms);
+    }
+
+    #[test]
+    fn local_rtt_is_clamped() {
+        let received = Instant::now();
+
+        assert_eq!(
+            UserUdpService::get_local_rtt_ms(received, received + Duration::from_secs(70)),
+            u16::MAX
+        );
+        // the clock can't go backwards but the result should not underflow anyway
+        assert_eq!(
+            UserUdpService::get_local_rtt_ms(received + Duration::from_millis(5), received),
+            0
+        );
+    }
+}
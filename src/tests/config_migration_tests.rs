@@ -0,0 +1,170 @@
+// Std.
+use std::fs;
+use std::path::Path;
+
+// Custom.
+use super::temp_file_path;
+use crate::error::SilentError;
+use crate::global_params::*;
+use crate::services::config_service::{ConfigLoadResult, UserConfig};
+
+/// Config with non-default values in the fields that were added in versions 1 and 2.
+fn changed_config() -> UserConfig {
+    let mut config = UserConfig::empty();
+
+    config.show_message_notification = false;
+    config.locale = String::from(if config.locale == "ru" { "en" } else { "ru" });
+    config.microphone_volume = 50;
+
+    config
+}
+
+/// Saves the config and overwrites the version in the file (older versions are
+/// a prefix of the current format).
+fn save_with_version(config: &UserConfig, version: u64, path: &str) {
+    config.save_to_path(path).unwrap();
+
+    // the version follows the magic number (u16)
+    let mut bytes = fs::read(path).unwrap();
+    bytes[2..10].copy_from_slice(&bincode::serialize(&version).unwrap());
+    fs::write(path, &bytes).unwrap();
+}
+
+fn open_with_version(config: &UserConfig, version: u64, file_name: &str) -> UserConfig {
+    let path = temp_file_path(file_name);
+
+    save_with_version(config, version, &path);
+    let loaded = UserConfig::open_from_path(&path).unwrap();
+
+    fs::remove_file(&path).unwrap();
+
+    loaded
+}
+
+#[test]
+fn version_0_uses_defaults_for_newer_fields() {
+    let config = changed_config();
+
+    let loaded = open_with_version(&config, 0, "config_migration_v0");
+
+    assert!(loaded.show_message_notification);
+    assert_eq!(loaded.locale, UserConfig::empty().locale); // system locale
+    assert_eq!(loaded.microphone_volume, 100);
+}
+
+#[test]
+fn version_1_uses_system_locale() {
+    let config = changed_config();
+
+    let loaded = open_with_version(&config, 1, "config_migration_v1");
+
+    assert!(!loaded.show_message_notification);
+    assert_eq!(loaded.locale, UserConfig::empty().locale);
+    assert_eq!(loaded.microphone_volume, 100);
+}
+
+#[test]
+fn version_2_reads_locale_and_microphone_volume() {
+    let config = changed_config();
+
+    let loaded = open_with_version(&config, 2, "config_migration_v2");
+
+    assert!(!loaded.show_message_notification);
+    assert_eq!(loaded.locale, config.locale);
+    assert_eq!(loaded.microphone_volume, 50);
+}
+
+#[test]
+fn future_version_is_rejected_and_kept() {
+    // own directory to check that no backup is created next to the config
+    let dir = temp_file_path("config_migration_future");
+    fs::create_dir(&dir).unwrap();
+    let path = String::from(
+        Path::new(&dir)
+            .join(CLIENT_CONFIG_FILE_NAME)
+            .to_str()
+            .unwrap(),
+    );
+
+    save_with_version(&UserConfig::empty(), CONFIG_FILE_VERSION + 1, &path);
+    let bytes = fs::read(&path).unwrap();
+
+    match UserConfig::open_from_path(&path) {
+        Err(SilentError::UnsupportedConfigVersion(_)) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("a config of a newer version was opened"),
+    }
+
+    match UserConfig::open_or_recover_from_path(&path) {
+        Err(SilentError::UnsupportedConfigVersion(_)) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("a config of a newer version was opened or reset"),
+    }
+
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+    assert!(!Path::new(&dir)
+        .join(CLIENT_CONFIG_BACKUP_FILE_NAME)
+        .exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn corrupted_config_is_recovered() {
+    let dir = temp_file_path("config_migration_corrupted");
+    fs::create_dir(&dir).unwrap();
+    let path = String::from(
+        Path::new(&dir)
+            .join(CLIENT_CONFIG_FILE_NAME)
+            .to_str()
+            .unwrap(),
+    );
+
+    // wrong magic number
+    fs::write(&path, [0u8; 16]).unwrap();
+
+    match UserConfig::open_or_recover_from_path(&path) {
+        Ok((_, ConfigLoadResult::Recovered { backup_path })) => {
+            assert_eq!(fs::read(&backup_path).unwrap(), vec![0u8; 16]);
+        }
+        Ok((_, ConfigLoadResult::Ok)) => panic!("a corrupted config was opened"),
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+
+    assert!(UserConfig::open_from_path(&path).is_ok());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn truncated_config_is_recovered() {
+    let dir = temp_file_path("config_migration_truncated");
+    fs::create_dir(&dir).unwrap();
+    let path = String::from(
+        Path::new(&dir)
+            .join(CLIENT_CONFIG_FILE_NAME)
+            .to_str()
+            .unwrap(),
+    );
+
+    changed_config().save_to_path(&path).unwrap();
+    let bytes = fs::read(&path).unwrap();
+
+    // cut in the header, in every field and right before the end
+    for len in 0..bytes.len() {
+        fs::write(&path, &bytes[..len]).unwrap();
+
+        match UserConfig::open_or_recover_from_path(&path) {
+            Ok((config, ConfigLoadResult::Recovered { backup_path })) => {
+                assert_eq!(fs::read(&backup_path).unwrap(), &bytes[..len]);
+                assert_eq!(config.microphone_volume, 100); // defaults
+            }
+            Ok((_, ConfigLoadResult::Ok)) => {
+                panic!("a config truncated to {} bytes was opened", len)
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
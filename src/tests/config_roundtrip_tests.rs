@@ -0,0 +1,308 @@
+// External.
+use num_traits::{FromPrimitive, ToPrimitive};
+use system_wide_key_state::KeyCode;
+
+// Std.
+use std::fs;
+
+// Custom.
+use super::temp_file_path;
+use crate::global_params::*;
+use crate::services::audio_service::microphone_boost::*;
+use crate::services::audio_service::voice_activity_detector::*;
+use crate::services::config_service::UserConfig;
+use crate::services::tcp_packets::VoiceCodec;
+
+/// Config with the smallest valid value in every field.
+fn min_config() -> UserConfig {
+    let mut config = UserConfig::empty();
+
+    config.username = "ab".repeat(MIN_USERNAME_SIZE / 2);
+    config.server = String::new();
+    config.server_port = 1;
+    config.server_password = String::new();
+    config.ui_scaling = 0;
+    config.master_volume = 0;
+    config.microphone_volume = 0;
+    config.push_to_talk_button = KeyCode::K0;
+    config.show_message_notification = false;
+    config.locale = String::from("en");
+    config.agc_enabled = false;
+    config.agc_target_rms = 0.0;
+    config.pause_on_minimize = false;
+    config.max_voice_upload_kbps = 0;
+    config.start_fullscreen = false;
+    config.window_width = 0;
+    config.window_height = 0;
+    config.message_cooldown_sec = 0;
+    config.room_change_same_tier_cooldown_sec = 0;
+    config.ptt_release_delay_ms = 0;
+    config.idle_timeout_min = 0;
+    config.theme_name = String::new();
+    config.max_voice_packets_per_sec = 0;
+    config.show_inline_images = false;
+    config.password_remember = false;
+    config.aec_enabled = false;
+    config.aec_delay_ms = 0;
+    config.log_level = String::from(LOG_LEVELS[0]);
+    config.spatial_audio_enabled = false;
+    config.auto_away_message = String::new();
+    config.ptt_press_sound = String::new();
+    config.ptt_release_sound = String::new();
+    config.accessibility_mode = false;
+    config.udp_recv_buffer_kb = MIN_UDP_SOCKET_BUFFER_KB;
+    config.udp_send_buffer_kb = MIN_UDP_SOCKET_BUFFER_KB;
+    config.microphone_channels = 1;
+    config.advanced_settings_expanded = false;
+    config.compact_user_list = false;
+    config.silence_suppression_enabled = false;
+    config.silence_threshold = 0.0;
+    config.udp_health_check_enabled = false;
+    config.udp_health_check_timeout_sec = MIN_UDP_HEALTH_CHECK_TIMEOUT_SEC;
+    config.preferred_network_interface = String::new();
+    config.connect_on_launch = false;
+    config.room_change_lobby_cooldown_sec = 0;
+    config.microphone_boost_db = MIN_MICROPHONE_BOOST_DB;
+    config.chat_max_messages = MIN_CHAT_MAX_MESSAGES;
+    config.push_to_talk_mode = PushToTalkMode::HoldKey;
+    config.vad_sensitivity = 0;
+    config.voice_codec = VoiceCodec::Pcm;
+    config.audio_input_device = String::new();
+    config.auto_record = false;
+    config.audio_play_buffer_chunks = MIN_AUDIO_PLAY_BUFFER_CHUNKS;
+    config.audio_record_buffer_chunks = 0;
+
+    config
+}
+
+/// Config with the biggest valid value in every field (strings contain non-ASCII characters).
+fn max_config() -> UserConfig {
+    let mut config = UserConfig::empty();
+
+    config.username = "й".repeat(MAX_USERNAME_SIZE);
+    config.server = String::from("сервер.例え.テスト");
+    config.server_port = u16::MAX;
+    config.server_password = "п".repeat(MAX_PASSWORD_SIZE);
+    config.ui_scaling = u16::MAX;
+    config.master_volume = 100;
+    config.microphone_volume = 100;
+    config.push_to_talk_button = KeyCode::KZ;
+    config.show_message_notification = true;
+    config.locale = String::from("ru");
+    config.agc_enabled = true;
+    config.agc_target_rms = 1.0;
+    config.pause_on_minimize = true;
+    config.max_voice_upload_kbps = u32::MAX;
+    config.start_fullscreen = true;
+    config.window_width = u16::MAX;
+    config.window_height = u16::MAX;
+    config.message_cooldown_sec = MAX_MESSAGE_COOLDOWN_SEC;
+    config.room_change_same_tier_cooldown_sec = MAX_ROOM_CHANGE_COOLDOWN_SEC;
+    config.ptt_release_delay_ms = MAX_PTT_RELEASE_DELAY_MS;
+    config.idle_timeout_min = MAX_IDLE_TIMEOUT_MIN;
+    config.theme_name = String::from("тёмная тема 🌙");
+    config.max_voice_packets_per_sec = u32::MAX;
+    config.show_inline_images = true;
+    config.password_remember = true;
+    config.aec_enabled = true;
+    config.aec_delay_ms = MAX_AEC_DELAY_MS;
+    config.log_level = String::from(LOG_LEVELS[LOG_LEVELS.len() - 1]);
+    config.spatial_audio_enabled = true;
+    config.auto_away_message = "ж".repeat(MAX_MESSAGE_SIZE);
+    config.ptt_press_sound = temp_file_path("звук_нажатия.wav");
+    config.ptt_release_sound = temp_file_path("用户_release.wav");
+    config.accessibility_mode = true;
+    config.udp_recv_buffer_kb = MAX_UDP_SOCKET_BUFFER_KB;
+    config.udp_send_buffer_kb = MAX_UDP_SOCKET_BUFFER_KB;
+    config.microphone_channels = 2;
+    config.advanced_settings_expanded = true;
+    config.compact_user_list = true;
+    config.silence_suppression_enabled = true;
+    config.silence_threshold = 1.0;
+    config.udp_health_check_enabled = true;
+    config.udp_health_check_timeout_sec = MAX_UDP_HEALTH_CHECK_TIMEOUT_SEC;
+    config.preferred_network_interface = String::from("Ethernet 2 (сеть)");
+    config.connect_on_launch = true;
+    config.room_change_lobby_cooldown_sec = MAX_ROOM_CHANGE_COOLDOWN_SEC;
+    config.microphone_boost_db = MAX_MICROPHONE_BOOST_DB;
+    config.chat_max_messages = MAX_CHAT_MAX_MESSAGES;
+    config.push_to_talk_mode = PushToTalkMode::VoiceActivated;
+    config.vad_sensitivity = VAD_MAX_SENSITIVITY;
+    config.voice_codec = VoiceCodec::Opus;
+    config.audio_input_device = String::from("Микрофон (USB Audio Device)");
+    config.auto_record = true;
+    config.audio_play_buffer_chunks = MAX_AUDIO_PLAY_BUFFER_CHUNKS;
+    config.audio_record_buffer_chunks = MAX_AUDIO_RECORD_BUFFER_CHUNKS;
+
+    config
+}
+
+/// Compares fields that were added in config versions up to 'version' with 'saved'
+/// and fields from newer versions with 'defaults'.
+macro_rules! assert_fields_by_version {
+    ($loaded:expr, $saved:expr, $defaults:expr, $version:expr, {
+        $($added_in:expr => [$($field:ident),+],)+
+    }) => {
+        $($(
+            let added_in: u64 = $added_in;
+            let expected = if $version >= added_in {
+                &$saved.$field
+            } else {
+                &$defaults.$field
+            };
+            assert_eq!(
+                &$loaded.$field,
+                expected,
+                "field '{}' (added in config version {}) read from config version {}",
+                stringify!($field),
+                added_in,
+                $version
+            );
+        )+)+
+    };
+}
+
+/// Checks every field, fields that were added after 'version' should have default values.
+fn assert_config_eq(loaded: &UserConfig, saved: &UserConfig, version: u64) {
+    let defaults = UserConfig::empty();
+
+    // KeyCode is compared by its code
+    assert_eq!(
+        loaded.push_to_talk_button.to_u64(),
+        saved.push_to_talk_button.to_u64()
+    );
+
+    assert_fields_by_version!(loaded, saved, defaults, version, {
+        0 => [username, server, server_port, server_password, ui_scaling, master_volume],
+        1 => [show_message_notification],
+        2 => [locale, microphone_volume],
+        3 => [agc_enabled, agc_target_rms],
+        4 => [pause_on_minimize],
+        5 => [max_voice_upload_kbps],
+        6 => [start_fullscreen, window_width, window_height],
+        7 => [message_cooldown_sec, room_change_same_tier_cooldown_sec],
+        8 => [ptt_release_delay_ms],
+        9 => [idle_timeout_min],
+        10 => [theme_name],
+        11 => [max_voice_packets_per_sec],
+        12 => [show_inline_images],
+        13 => [password_remember],
+        14 => [aec_enabled, aec_delay_ms],
+        15 => [log_level],
+        16 => [spatial_audio_enabled],
+        17 => [auto_away_message],
+        18 => [ptt_press_sound, ptt_release_sound],
+        19 => [accessibility_mode],
+        20 => [udp_recv_buffer_kb, udp_send_buffer_kb],
+        21 => [microphone_channels],
+        22 => [advanced_settings_expanded],
+        23 => [compact_user_list],
+        24 => [silence_suppression_enabled, silence_threshold],
+        25 => [udp_health_check_enabled, udp_health_check_timeout_sec],
+        26 => [preferred_network_interface],
+        27 => [connect_on_launch],
+        28 => [room_change_lobby_cooldown_sec],
+        29 => [microphone_boost_db],
+        30 => [chat_max_messages],
+        31 => [push_to_talk_mode, vad_sensitivity],
+        32 => [voice_codec],
+        33 => [audio_input_device],
+        34 => [auto_record],
+        35 => [audio_play_buffer_chunks, audio_record_buffer_chunks],
+    });
+}
+
+fn save_and_open(config: &UserConfig, file_name: &str) -> UserConfig {
+    let path = temp_file_path(file_name);
+
+    config.save_to_path(&path).unwrap();
+    let loaded = UserConfig::open_from_path(&path).unwrap();
+
+    fs::remove_file(&path).unwrap();
+
+    loaded
+}
+
+#[test]
+fn min_values_roundtrip() {
+    let config = min_config();
+    assert!(config.validate().is_ok());
+
+    let loaded = save_and_open(&config, "config_min_values");
+
+    assert_config_eq(&loaded, &config, CONFIG_FILE_VERSION);
+}
+
+#[test]
+fn max_values_roundtrip() {
+    let config = max_config();
+
+    // Sound paths are only valid if the files exist.
+    fs::write(&config.ptt_press_sound, []).unwrap();
+    fs::write(&config.ptt_release_sound, []).unwrap();
+    let result = config.validate();
+    fs::remove_file(&config.ptt_press_sound).unwrap();
+    fs::remove_file(&config.ptt_release_sound).unwrap();
+    assert!(result.is_ok());
+
+    let loaded = save_and_open(&config, "config_max_values");
+
+    assert_config_eq(&loaded, &config, CONFIG_FILE_VERSION);
+}
+
+#[test]
+fn forgotten_password_is_not_saved() {
+    let mut config = max_config();
+    config.password_remember = false;
+
+    let loaded = save_and_open(&config, "config_forgotten_password");
+
+    assert_eq!(loaded.server_password, "");
+    assert!(!loaded.password_remember);
+}
+
+#[test]
+fn every_key_code_roundtrip() {
+    let mut config = UserConfig::empty();
+    let mut key_count = 0;
+
+    for code in 0..=u16::MAX as u64 {
+        let key: Option<KeyCode> = FromPrimitive::from_u64(code);
+        if key.is_none() {
+            continue;
+        }
+        config.push_to_talk_button = key.unwrap();
+        key_count += 1;
+
+        let loaded = save_and_open(&config, "config_key_code");
+
+        assert_eq!(loaded.push_to_talk_button.to_u64(), Some(code));
+    }
+
+    assert!(key_count > 0);
+}
+
+#[test]
+fn every_old_version_is_migrated() {
+    // older versions are a prefix of the current format, the reader stops after
+    // the fields of the version that is stored in the file
+    let mut config = max_config();
+    config.server_password = String::new(); // stored as plain text before version 13
+
+    let path = temp_file_path("config_old_versions");
+    config.save_to_path(&path).unwrap();
+    let current_bytes = fs::read(&path).unwrap();
+
+    for version in 0..=CONFIG_FILE_VERSION {
+        // the version follows the magic number (u16)
+        let mut bytes = current_bytes.clone();
+        bytes[2..10].copy_from_slice(&bincode::serialize(&version).unwrap());
+        fs::write(&path, &bytes).unwrap();
+
+        let loaded = UserConfig::open_from_path(&path).unwrap();
+
+        assert_config_eq(&loaded, &config, version);
+    }
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,194 @@
+// External.
+use druid::Selector;
+use system_wide_key_state::KeyCode;
+
+// Std.
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Custom.
+use super::mock_server::*;
+use crate::global_params::*;
+use crate::services::audio_service::audio_service::AudioService;
+use crate::services::config_service::UserConfig;
+use crate::services::event_sink::{EventSink, SubmittedCommand};
+use crate::services::net_service::*;
+use crate::services::tcp_packets::VoiceCodec;
+use crate::services::user_tcp_service::*;
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_millis(100);
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn expect_connect_result(receiver: &mpsc::Receiver<ConnectResult>, expected: ConnectResult) {
+    let result = receiver
+        .recv_timeout(WAIT_TIMEOUT)
+        .expect("no connect result from the TCP thread");
+
+    assert_eq!(result, expected);
+}
+
+/// Waits for the UI command with the specified payload (other commands are skipped).
+fn wait_for_command<T: std::any::Any + PartialEq>(
+    receiver: &mpsc::Receiver<SubmittedCommand>,
+    selector: Selector<T>,
+    payload: T,
+    received: &mut Vec<SubmittedCommand>,
+) {
+    let deadline = Instant::now() + WAIT_TIMEOUT;
+    while Instant::now() < deadline {
+        if let Ok(command) = receiver.recv_timeout(deadline - Instant::now()) {
+            let is_expected = command.get(selector) == Some(&payload);
+            received.push(command);
+            if is_expected {
+                return;
+            }
+        }
+    }
+
+    panic!("the command '{}' was not submitted", selector);
+}
+
+#[test]
+fn test_full_connect_flow() {
+    let server = MockServer::start(KEEP_ALIVE_INTERVAL);
+
+    let mut net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+    net_service.init_audio_service(Arc::new(Mutex::new(AudioService::default())));
+
+    let (connect_sender, connect_receiver) = mpsc::channel();
+    let (event_sender, event_receiver) = mpsc::channel();
+    net_service.start(
+        ClientConfig {
+            username: String::from("test_user"),
+            server_name: String::from(MOCK_SERVER_NAME),
+            server_port: server.port.to_string(),
+            server_password: String::from("test_password"),
+            push_to_talk_key: KeyCode::KT,
+            voice_codec: VoiceCodec::Opus,
+            server_addr: None,
+        },
+        String::from("test_user"),
+        String::from("test_password"),
+        connect_sender,
+        EventSink::Channel(event_sender),
+    );
+
+    // Connected.
+    expect_connect_result(
+        &connect_receiver,
+        ConnectResult::InfoAboutRoom(String::from(MOCK_ROOM_NAME)),
+    );
+    expect_connect_result(
+        &connect_receiver,
+        ConnectResult::InfoAboutOtherUser(
+            UserInfo::new(String::from(MOCK_USER_NAME)),
+            String::from(MOCK_ROOM_NAME),
+            MOCK_USER_PING_MS,
+        ),
+    );
+    expect_connect_result(&connect_receiver, ConnectResult::Ok);
+    assert!(matches!(
+        net_service.user_tcp_service.lock().unwrap().user_state,
+        UserState::Connected
+    ));
+
+    // Server messages are received.
+    let mut received = Vec::new();
+    wait_for_command(
+        &event_receiver,
+        USER_TCP_SERVICE_USER_CONNECTED,
+        String::from(MOCK_NEW_USER_NAME),
+        &mut received,
+    );
+
+    let deadline = Instant::now() + WAIT_TIMEOUT;
+    while server.get_keep_alive_answer_count() == 0 {
+        assert!(
+            Instant::now() < deadline,
+            "the client did not answer 'KeepAliveCheck'"
+        );
+        thread::sleep(KEEP_ALIVE_INTERVAL);
+    }
+
+    // Disconnect.
+    net_service.disconnect();
+    let report = server.wait();
+
+    assert_eq!(report.connect_packet.username, "test_user");
+    assert_eq!(report.connect_packet.password, "test_password");
+    assert_eq!(
+        report.connect_packet.net_protocol_version,
+        NETWORK_PROTOCOL_VERSION
+    );
+    assert_eq!(report.connect_packet.voice_codec, VoiceCodec::Opus);
+
+    assert!(matches!(
+        net_service.user_tcp_service.lock().unwrap().user_state,
+        UserState::NotConnected
+    ));
+
+    // the threads are finished, all commands are in the channel
+    received.extend(event_receiver.try_iter());
+    assert!(received
+        .iter()
+        .any(|command| command.get(NETWORK_SERVICE_CLEAR_ALL_USERS).is_some()));
+    assert!(!received
+        .iter()
+        .any(|command| command.get(NETWORK_SERVICE_CONNECTION_LOST).is_some()));
+}
+
+#[test]
+fn test_handshake_timeout_with_silent_server() {
+    // accepts the connection but never sends anything
+    let listener = std::net::TcpListener::bind((MOCK_SERVER_NAME, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+    let server_thread = thread::spawn(move || {
+        let _stream = listener.accept().unwrap();
+        let _ = stop_receiver.recv();
+    });
+
+    let mut net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+    net_service.init_audio_service(Arc::new(Mutex::new(AudioService::default())));
+
+    let (connect_sender, connect_receiver) = mpsc::channel();
+    let (event_sender, _event_receiver) = mpsc::channel();
+    let start = Instant::now();
+    net_service.start(
+        ClientConfig {
+            username: String::from("test_user"),
+            server_name: String::from(MOCK_SERVER_NAME),
+            server_port: port.to_string(),
+            server_password: String::new(),
+            push_to_talk_key: KeyCode::KT,
+            voice_codec: VoiceCodec::Opus,
+            server_addr: None,
+        },
+        String::from("test_user"),
+        String::new(),
+        connect_sender,
+        EventSink::Channel(event_sender),
+    );
+
+    let handshake_timeout = Duration::from_secs(HANDSHAKE_TIMEOUT_SEC);
+    let result = connect_receiver
+        .recv_timeout(handshake_timeout + WAIT_TIMEOUT)
+        .expect("the handshake did not time out");
+    let elapsed = start.elapsed();
+
+    assert_eq!(result, ConnectResult::ErrTimeout);
+    assert!(
+        elapsed >= handshake_timeout,
+        "gave up too early ({:?})",
+        elapsed
+    );
+    assert!(
+        elapsed < handshake_timeout + Duration::from_secs(2),
+        "gave up too late ({:?})",
+        elapsed
+    );
+
+    stop_sender.send(()).unwrap();
+    server_thread.join().unwrap();
+}
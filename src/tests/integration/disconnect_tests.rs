@@ -0,0 +1,131 @@
+// Std.
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Custom.
+use super::mock_server::*;
+use super::*;
+use crate::services::config_service::UserConfig;
+use crate::services::user_tcp_service::UserState;
+use crate::tests::test_application_state;
+use crate::{disconnect_to_connect_layout, Layout};
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_millis(100);
+
+#[test]
+fn disconnect_returns_to_connect_layout() {
+    let server = MockServer::start(KEEP_ALIVE_INTERVAL);
+    let mut data = test_application_state();
+
+    let event_receiver = connect_to_mock_server(&mut data.network_service.lock().unwrap(), &server);
+    data.is_connected = true;
+    data.current_layout = Layout::Main;
+    data.main_layout.current_user_name = String::from(TEST_USER_NAME);
+    data.main_layout
+        .chat_list
+        .add_info_message(String::from("message"));
+    data.connect_layout.connect_result = String::from("connected");
+
+    disconnect_to_connect_layout(&mut data);
+
+    // the server sees the disconnect
+    server.wait();
+
+    assert!(data.current_layout == Layout::Connect);
+    assert!(!data.is_connected);
+    assert!(data.main_layout.current_user_name.is_empty());
+    assert!(data
+        .main_layout
+        .chat_list
+        .messages
+        .lock()
+        .unwrap()
+        .is_empty());
+    assert!(data.connect_layout.connect_result.is_empty());
+    assert!(matches!(
+        data.network_service
+            .lock()
+            .unwrap()
+            .user_tcp_service
+            .lock()
+            .unwrap()
+            .user_state,
+        UserState::NotConnected
+    ));
+
+    // the disconnect was requested, not lost
+    assert!(!event_receiver
+        .try_iter()
+        .any(|command| command.get(NETWORK_SERVICE_CONNECTION_LOST).is_some()));
+}
+
+#[test]
+fn disconnect_without_connection_returns_to_connect_layout() {
+    let mut data = test_application_state();
+    data.current_layout = Layout::Main;
+
+    disconnect_to_connect_layout(&mut data);
+
+    assert!(data.current_layout == Layout::Connect);
+    assert!(!data.is_connected);
+}
+
+#[test]
+fn disconnect_stops_tcp_and_udp_threads() {
+    let server = MockServer::start(KEEP_ALIVE_INTERVAL);
+    let net_service = Arc::new(Mutex::new(NetService::new(Arc::new(Mutex::new(
+        UserConfig::empty(),
+    )))));
+
+    let _event_receiver = connect_to_mock_server(&mut net_service.lock().unwrap(), &server);
+
+    // the UDP thread is started by the TCP thread after the connection is established
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    while net_service.lock().unwrap().get_started_thread_count() != 2 {
+        assert!(Instant::now() < deadline, "the UDP thread was not started");
+        thread::sleep(KEEP_ALIVE_INTERVAL);
+    }
+
+    // 'disconnect' joins both threads, it should not hang
+    let (finished_sender, finished_receiver) = mpsc::channel();
+    let net_service_copy = Arc::clone(&net_service);
+    thread::spawn(move || {
+        net_service_copy.lock().unwrap().disconnect();
+        finished_sender.send(()).unwrap();
+    });
+    finished_receiver
+        .recv_timeout(CONNECT_TIMEOUT)
+        .expect("the TCP or UDP thread did not exit on disconnect");
+
+    server.wait();
+
+    let net_service_guard = net_service.lock().unwrap();
+    assert_eq!(net_service_guard.get_started_thread_count(), 0);
+    assert!(matches!(
+        net_service_guard
+            .user_tcp_service
+            .lock()
+            .unwrap()
+            .user_state,
+        UserState::NotConnected
+    ));
+    assert!(net_service_guard
+        .user_udp_service
+        .lock()
+        .unwrap()
+        .request_stop
+        .load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[test]
+fn second_disconnect_does_nothing() {
+    let server = MockServer::start(KEEP_ALIVE_INTERVAL);
+    let mut net_service = NetService::new(Arc::new(Mutex::new(UserConfig::empty())));
+    let _event_receiver = connect_to_mock_server(&mut net_service, &server);
+
+    net_service.disconnect();
+    server.wait();
+    net_service.disconnect();
+
+    assert_eq!(net_service.get_started_thread_count(), 0);
+}
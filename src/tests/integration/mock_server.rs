@@ -0,0 +1,219 @@
+// External.
+use rand::rngs::OsRng;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+// Std.
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::net::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Custom.
+use crate::misc::packet_builder::PacketBuilder;
+use crate::services::tcp_packets::*;
+use crate::services::udp_packets::UDP_PACKET_MAX_SIZE;
+use crate::services::user_tcp_service::*;
+
+pub const MOCK_SERVER_NAME: &str = "127.0.0.1";
+pub const MOCK_ROOM_NAME: &str = "Lobby";
+pub const MOCK_USER_NAME: &str = "mock_user"; // already connected
+pub const MOCK_USER_PING_MS: u16 = 42;
+pub const MOCK_NEW_USER_NAME: &str = "new_user"; // connects after us
+
+const MOCK_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const MOCK_UDP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MOCK_BIND_ATTEMPTS: usize = 10;
+
+// What the server received from the client.
+pub struct MockServerReport {
+    pub connect_packet: ClientConnectPacket,
+}
+
+// Server for one client: X25519 key exchange, 'ConnectServerAnswer::Ok' with one room
+// and one user, 'UserConnected' and then 'KeepAliveCheck' every 'keep_alive_interval'
+// until the client disconnects.
+// Voice is not supported: UDP packets are answered with 1 byte so that
+// the client's UDP service stops right away (with an error) instead of waiting.
+pub struct MockServer {
+    pub port: u16,
+    keep_alive_answer_count: Arc<AtomicUsize>,
+    tcp_thread: JoinHandle<MockServerReport>,
+    udp_thread: JoinHandle<()>,
+    udp_stop: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    pub fn start(keep_alive_interval: Duration) -> Self {
+        let (listener, udp_socket) = MockServer::bind();
+        let port = listener.local_addr().unwrap().port();
+
+        let keep_alive_answer_count = Arc::new(AtomicUsize::new(0));
+        let keep_alive_answer_count_copy = Arc::clone(&keep_alive_answer_count);
+        let tcp_thread = thread::spawn(move || {
+            MockServer::serve_tcp(
+                listener,
+                port,
+                keep_alive_interval,
+                keep_alive_answer_count_copy,
+            )
+        });
+
+        let udp_stop = Arc::new(AtomicBool::new(false));
+        let udp_stop_copy = Arc::clone(&udp_stop);
+        let udp_thread = thread::spawn(move || MockServer::serve_udp(udp_socket, udp_stop_copy));
+
+        MockServer {
+            port,
+            keep_alive_answer_count,
+            tcp_thread,
+            udp_thread,
+            udp_stop,
+        }
+    }
+    /// 'KeepAliveCheck' packets received from the client.
+    pub fn get_keep_alive_answer_count(&self) -> usize {
+        self.keep_alive_answer_count.load(Ordering::SeqCst)
+    }
+    /// Waits for the client to disconnect.
+    pub fn wait(self) -> MockServerReport {
+        let report = self
+            .tcp_thread
+            .join()
+            .expect("mock server TCP thread failed");
+
+        self.udp_stop.store(true, Ordering::SeqCst);
+        self.udp_thread
+            .join()
+            .expect("mock server UDP thread failed");
+
+        report
+    }
+    /// TCP and UDP on the same (random) port, like the real server.
+    fn bind() -> (TcpListener, UdpSocket) {
+        for _ in 0..MOCK_BIND_ATTEMPTS {
+            let listener = TcpListener::bind((MOCK_SERVER_NAME, 0)).unwrap();
+            let port = listener.local_addr().unwrap().port();
+
+            if let Ok(udp_socket) = UdpSocket::bind((MOCK_SERVER_NAME, port)) {
+                return (listener, udp_socket);
+            }
+        }
+
+        panic!("failed to find a free port for the mock server");
+    }
+    fn serve_tcp(
+        listener: TcpListener,
+        port: u16,
+        keep_alive_interval: Duration,
+        keep_alive_answer_count: Arc<AtomicUsize>,
+    ) -> MockServerReport {
+        let (mut socket, _) = listener.accept().unwrap();
+        socket.set_read_timeout(Some(MOCK_READ_TIMEOUT)).unwrap();
+
+        // Key exchange, the server sends its public key first.
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        socket
+            .write_all(PublicKey::from(&secret).as_bytes())
+            .unwrap();
+
+        let mut client_public_key = [0u8; 32];
+        socket.read_exact(&mut client_public_key).unwrap();
+
+        let shared_secret = secret.diffie_hellman(&PublicKey::from(client_public_key));
+        let salt = format!("{}{}", MOCK_SERVER_NAME, port);
+        let secret_key =
+            UserTcpService::derive_session_keys(shared_secret.as_bytes(), salt.as_bytes()).tcp_key;
+
+        // Connect packet (legacy format: (u16) size instead of the frame header).
+        let mut size_buf = vec![0u8; std::mem::size_of::<u16>()];
+        socket.read_exact(&mut size_buf).unwrap();
+        let mut packet = vec![0u8; bincode::deserialize::<u16>(&size_buf).unwrap() as usize];
+        socket.read_exact(&mut packet).unwrap();
+        let connect_packet =
+            PacketBuilder::parse_encrypted_packet::<ClientConnectPacket>(packet, &secret_key)
+                .unwrap();
+
+        // Answer, (u64) size and the encrypted packet.
+        let answer = ServerTcpConnectPacket {
+            answer: ConnectServerAnswer::Ok,
+            correct_net_protocol: None,
+            connected_info: Some(vec![RoomNetInfo {
+                room_name: String::from(MOCK_ROOM_NAME),
+                users: vec![UserNetInfo {
+                    username: String::from(MOCK_USER_NAME),
+                    ping: MOCK_USER_PING_MS,
+                }],
+            }]),
+        };
+        let packet = PacketBuilder::build_legacy_encrypted_packet(&answer, &secret_key).unwrap();
+        let packet = &packet[std::mem::size_of::<u16>()..];
+        socket
+            .write_all(&bincode::serialize(&(packet.len() as u64)).unwrap())
+            .unwrap();
+        socket.write_all(packet).unwrap();
+
+        let new_user = ServerTcpMessage::UserConnected {
+            username: String::from(MOCK_NEW_USER_NAME),
+        };
+        socket
+            .write_all(&PacketBuilder::build_encrypted_packet(&new_user, &secret_key).unwrap())
+            .unwrap();
+
+        // Check that the client is alive until it disconnects.
+        socket.set_read_timeout(Some(keep_alive_interval)).unwrap();
+        let mut next_keep_alive_check = Instant::now();
+        loop {
+            if Instant::now() >= next_keep_alive_check {
+                let packet = PacketBuilder::build_encrypted_packet(
+                    &ServerTcpMessage::KeepAliveCheck,
+                    &secret_key,
+                )
+                .unwrap();
+                if socket.write_all(&packet).is_err() {
+                    break; // disconnected
+                }
+                next_keep_alive_check = Instant::now() + keep_alive_interval;
+            }
+
+            let mut header_buf = vec![0u8; TCP_FRAME_HEADER_SIZE];
+            match socket.read_exact(&mut header_buf) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                    continue;
+                }
+                Err(_) => break, // disconnected
+            }
+            let header = PacketBuilder::parse_frame_header(&header_buf).unwrap();
+
+            let mut packet = vec![0u8; header.payload_size as usize];
+            if socket.read_exact(&mut packet).is_err() {
+                break;
+            }
+            let message =
+                PacketBuilder::parse_encrypted_packet::<ClientTcpMessage>(packet, &secret_key)
+                    .unwrap();
+
+            if let ClientTcpMessage::KeepAliveCheck = message {
+                keep_alive_answer_count.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        MockServerReport { connect_packet }
+    }
+    fn serve_udp(udp_socket: UdpSocket, stop: Arc<AtomicBool>) {
+        udp_socket
+            .set_read_timeout(Some(MOCK_UDP_POLL_INTERVAL))
+            .unwrap();
+
+        let mut buf = vec![0u8; UDP_PACKET_MAX_SIZE as usize];
+        while !stop.load(Ordering::SeqCst) {
+            if let Ok((_, client_addr)) = udp_socket.recv_from(&mut buf) {
+                // too small to be a packet
+                let _ = udp_socket.send_to(&[0], client_addr);
+            }
+        }
+    }
+}
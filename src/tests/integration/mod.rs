@@ -0,0 +1,59 @@
+mod connect_flow_tests;
+mod disconnect_tests;
+mod mock_server;
+
+// External.
+use system_wide_key_state::KeyCode;
+
+// Std.
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+// Custom.
+use crate::services::audio_service::audio_service::AudioService;
+use crate::services::event_sink::{EventSink, SubmittedCommand};
+use crate::services::net_service::*;
+use crate::services::tcp_packets::VoiceCodec;
+use crate::services::user_tcp_service::ConnectResult;
+use mock_server::*;
+
+pub const TEST_USER_NAME: &str = "test_user";
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connects to the mock server and waits for 'ConnectResult::Ok',
+/// returns the receiver of the commands submitted to the UI.
+pub fn connect_to_mock_server(
+    net_service: &mut NetService,
+    server: &MockServer,
+) -> mpsc::Receiver<SubmittedCommand> {
+    net_service.init_audio_service(Arc::new(Mutex::new(AudioService::default())));
+
+    let (connect_sender, connect_receiver) = mpsc::channel();
+    let (event_sender, event_receiver) = mpsc::channel();
+    net_service.start(
+        ClientConfig {
+            username: String::from(TEST_USER_NAME),
+            server_name: String::from(MOCK_SERVER_NAME),
+            server_port: server.port.to_string(),
+            server_password: String::new(),
+            push_to_talk_key: KeyCode::KT,
+            voice_codec: VoiceCodec::Opus,
+            server_addr: None,
+        },
+        String::from(TEST_USER_NAME),
+        String::new(),
+        connect_sender,
+        EventSink::Channel(event_sender),
+    );
+
+    loop {
+        match connect_receiver.recv_timeout(CONNECT_TIMEOUT) {
+            Ok(ConnectResult::Ok) => break,
+            Ok(ConnectResult::InfoAboutRoom(_)) | Ok(ConnectResult::InfoAboutOtherUser(..)) => {}
+            Ok(result) => panic!("failed to connect: {:?}", result),
+            Err(_) => panic!("no connect result from the TCP thread"),
+        }
+    }
+
+    event_receiver
+}
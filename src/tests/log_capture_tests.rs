@@ -0,0 +1,147 @@
+// External.
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+// Std.
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// Custom.
+use super::temp_file_path;
+use crate::global_params::*;
+use crate::services::config_service::{ConfigLoadResult, UserConfig};
+
+// Output of the subscriber, shared between the writers it creates.
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs 'f' with a subscriber like the one in 'main' (only for the current thread)
+/// and returns everything that was logged.
+fn capture_logs<F: FnOnce()>(log_level: &str, f: F) -> String {
+    let logs = Arc::new(Mutex::new(Vec::new()));
+
+    let logs_copy = Arc::clone(&logs);
+    let subscriber = FmtSubscriber::builder()
+        .with_env_filter(EnvFilter::new(log_level))
+        .with_ansi(false)
+        .with_writer(move || CapturedLogs(Arc::clone(&logs_copy)))
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, f);
+
+    let logs = logs.lock().unwrap();
+    String::from_utf8(logs.clone()).unwrap()
+}
+
+/// Creates a directory with a corrupted config file, returns the directory and the config path.
+fn corrupted_config(name: &str) -> (String, String) {
+    let dir = temp_file_path(name);
+    fs::create_dir(&dir).unwrap();
+    let path = String::from(
+        Path::new(&dir)
+            .join(CLIENT_CONFIG_FILE_NAME)
+            .to_str()
+            .unwrap(),
+    );
+
+    // wrong magic number
+    fs::write(&path, [0u8; 16]).unwrap();
+
+    (dir, path)
+}
+
+#[test]
+fn corrupted_config_is_logged() {
+    let (dir, path) = corrupted_config("log_capture_corrupted");
+
+    let logs = capture_logs(
+        DEFAULT_LOG_LEVEL,
+        || match UserConfig::open_or_recover_from_path(&path) {
+            Ok((_, ConfigLoadResult::Recovered { .. })) => {}
+            _ => panic!("the corrupted config was not recovered"),
+        },
+    );
+
+    let line = logs
+        .lines()
+        .find(|line| line.contains("failed to read the config file"))
+        .expect("the warning was not logged");
+    assert!(line.contains("WARN"));
+    assert!(line.contains(&path));
+    assert!(line.contains("resetting it to defaults"));
+    assert!(!line.contains("SILENT_WARNING")); // the level is shown by the subscriber
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invalid_config_values_are_logged() {
+    let path = temp_file_path("log_capture_invalid_values.cfg");
+
+    let mut config = UserConfig::empty();
+    config.master_volume = 150;
+    config.save_to_path(&path).unwrap();
+
+    let logs = capture_logs(
+        DEFAULT_LOG_LEVEL,
+        || match UserConfig::open_or_recover_from_path(&path) {
+            Ok((loaded, ConfigLoadResult::Ok)) => assert_eq!(loaded.master_volume, 150),
+            _ => panic!("the config was not opened"),
+        },
+    );
+
+    let line = logs
+        .lines()
+        .find(|line| line.contains("contains invalid values"))
+        .expect("the warning was not logged");
+    assert!(line.contains("WARN"));
+    assert!(line.contains("150"));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn log_level_filters_messages() {
+    for log_level in LOG_LEVELS.iter() {
+        let (dir, path) = corrupted_config(&format!("log_capture_level_{}", log_level));
+
+        let logs = capture_logs(log_level, || {
+            UserConfig::open_or_recover_from_path(&path).unwrap();
+        });
+
+        // everything up to "warn" shows warnings
+        let is_warning_shown = *log_level != "error";
+        assert_eq!(
+            logs.contains("failed to read the config file"),
+            is_warning_shown,
+            "log level: {}",
+            log_level
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn nothing_is_logged_for_valid_config() {
+    let path = temp_file_path("log_capture_valid.cfg");
+    UserConfig::empty().save_to_path(&path).unwrap();
+
+    let logs = capture_logs(LOG_LEVELS[0], || {
+        UserConfig::open_or_recover_from_path(&path).unwrap();
+    });
+
+    assert_eq!(logs, "");
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,54 @@
+mod config_migration_tests;
+mod config_roundtrip_tests;
+mod integration;
+mod log_capture_tests;
+mod user_drag_tests;
+
+// Std.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// Custom.
+use crate::layouts::{connect_layout::*, main_layout::*, settings_layout::*};
+use crate::services::audio_service::audio_service::AudioService;
+use crate::services::config_service::UserConfig;
+use crate::services::net_service::NetService;
+use crate::theme::ApplicationTheme;
+use crate::{ApplicationState, Layout};
+
+/// Path to a file in the system's temp directory, unique for the running test process
+/// (the old file with the same name, if any, is removed).
+pub fn temp_file_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("silent_{}_{}", std::process::id(), name));
+    let path = String::from(path.to_str().unwrap());
+
+    if Path::new(&path).exists() {
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    path
+}
+
+/// State of the application that is not connected and has no window
+/// (the config is not loaded from the disk).
+pub fn test_application_state() -> ApplicationState {
+    let user_config = Arc::new(Mutex::new(UserConfig::empty()));
+
+    ApplicationState {
+        current_layout: Layout::Connect,
+        previous_layout: Layout::Connect,
+        connect_layout: ConnectLayout::new(),
+        settings_layout: SettingsLayout::new(),
+        main_layout: MainLayout::new(),
+        theme: ApplicationTheme::default(),
+        is_connected: false,
+        audio_service: Arc::new(Mutex::new(AudioService::default())),
+        network_service: Arc::new(Mutex::new(NetService::new(Arc::clone(&user_config)))),
+        user_config,
+        window_handle: Arc::new(None),
+        localization: Arc::new(HashMap::new()),
+        last_activity: Instant::now(),
+    }
+}
@@ -0,0 +1,126 @@
+// Std.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Custom.
+use super::test_application_state;
+use crate::layouts::main_layout::MainLayout;
+use crate::misc::locale_keys::*;
+use crate::misc::user_drag_controller::UserDropInfo;
+use crate::ApplicationState;
+
+const NO_PERMISSION_TEXT: &str = "no permission";
+const NOT_SUPPORTED_TEXT: &str = "not supported";
+
+/// User "admin" in the lobby and "user" in "Room 1", "admin" is the local user.
+fn state_with_dragged_user() -> ApplicationState {
+    let mut data = test_application_state();
+
+    let mut localization = HashMap::new();
+    localization.insert(
+        String::from(LOCALE_MAIN_LAYOUT_MESSAGE_MOVE_USER_NO_PERMISSION_TEXT),
+        String::from(NO_PERMISSION_TEXT),
+    );
+    localization.insert(
+        String::from(LOCALE_MAIN_LAYOUT_MESSAGE_MOVE_USER_NOT_SUPPORTED_TEXT),
+        String::from(NOT_SUPPORTED_TEXT),
+    );
+    data.localization = Arc::new(localization);
+
+    let connected_list = &mut data.main_layout.connected_list;
+    connected_list.add_room(String::from("Lobby"));
+    connected_list.add_room(String::from("Room 1"));
+    connected_list
+        .add_user(String::from("admin"), String::from("Lobby"), 0)
+        .unwrap();
+    connected_list
+        .add_user(String::from("user"), String::from("Room 1"), 0)
+        .unwrap();
+
+    // drag started by the 'UserDragController'
+    connected_list.dragged_username = String::from("user");
+    connected_list.dragged_from_room = String::from("Room 1");
+    connected_list.drop_target_room = String::from("Lobby");
+
+    data.main_layout.current_user_name = String::from("admin");
+    data.main_layout.current_user_room = String::from("Lobby");
+
+    data
+}
+
+fn set_admin(data: &mut ApplicationState, username: &str) {
+    for room in data.main_layout.connected_list.rooms.lock().unwrap().iter() {
+        for user in room.users.lock().unwrap().iter_mut() {
+            if user.user_data.username == username {
+                user.user_data.is_admin = true;
+            }
+        }
+    }
+}
+
+fn get_chat_messages(data: &ApplicationState) -> Vec<String> {
+    data.main_layout
+        .chat_list
+        .messages
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|message| message.message.clone())
+        .collect()
+}
+
+fn assert_drag_ended(data: &ApplicationState) {
+    let connected_list = &data.main_layout.connected_list;
+    assert!(connected_list.dragged_username.is_empty());
+    assert!(connected_list.dragged_from_room.is_empty());
+    assert!(connected_list.drop_target_room.is_empty());
+}
+
+fn drop_user(data: &mut ApplicationState, to_room: &str) {
+    let drop_info = UserDropInfo {
+        username: data.main_layout.connected_list.dragged_username.clone(),
+        from_room: data.main_layout.connected_list.dragged_from_room.clone(),
+        to_room: String::from(to_room),
+    };
+
+    MainLayout::user_dropped_on_room_event(data, &drop_info);
+}
+
+#[test]
+fn drop_on_the_same_room_does_nothing() {
+    let mut data = state_with_dragged_user();
+
+    drop_user(&mut data, "Room 1");
+
+    assert_drag_ended(&data);
+    assert!(get_chat_messages(&data).is_empty());
+    assert_eq!(
+        data.main_layout.connected_list.get_user_room("user"),
+        Some(String::from("Room 1"))
+    );
+}
+
+#[test]
+fn drop_without_admin_rights_is_rejected() {
+    let mut data = state_with_dragged_user();
+
+    drop_user(&mut data, "Lobby");
+
+    assert_drag_ended(&data);
+    assert_eq!(get_chat_messages(&data), vec![NO_PERMISSION_TEXT]);
+    assert_eq!(
+        data.main_layout.connected_list.get_user_room("user"),
+        Some(String::from("Room 1"))
+    );
+}
+
+#[test]
+fn drop_by_admin_is_not_supported_by_server() {
+    let mut data = state_with_dragged_user();
+    set_admin(&mut data, "admin");
+
+    drop_user(&mut data, "Lobby");
+
+    assert_drag_ended(&data);
+    assert_eq!(get_chat_messages(&data), vec![NOT_SUPPORTED_TEXT]);
+}
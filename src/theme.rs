@@ -1,8 +1,23 @@
+// External.
 use druid::widget::prelude::*;
-use druid::{Color, Key, Lens};
+use druid::{Color, Key, Lens, Selector};
+use serde::Deserialize;
+
+// Std.
+use std::fs::*;
+use std::path::Path;
+
+// Custom.
+use crate::services::config_service::UserConfig;
 
 pub const BACKGROUND_SPECIAL_COLOR: Key<Color> = Key::new("color.background_color_special");
 
+pub const THEME_FILE_EXTENSION: &str = ".theme.json";
+const DEFAULT_THEME_JSON: &str = include_str!("../res/themes/default.theme.json");
+
+// Theme name (empty for the default theme).
+pub const THEME_MANAGER_APPLY_THEME: Selector<String> = Selector::new("theme_manager_apply_theme");
+
 #[derive(Clone, Data, Lens)]
 pub struct ApplicationTheme {
     pub background_color: Color,
@@ -19,19 +34,327 @@ pub struct ApplicationTheme {
 
 impl Default for ApplicationTheme {
     fn default() -> Self {
+        ThemeManager::load_from_str(DEFAULT_THEME_JSON)
+            .expect("the bundled default theme is invalid")
+    }
+}
+
+impl ApplicationTheme {
+    /// Used instead of the selected theme when the accessibility mode is enabled.
+    pub fn high_contrast() -> Self {
         ApplicationTheme {
-            background_color: Color::rgb8(30, 26, 22),
-            background_special_color: Color::rgb8(35, 30, 25),
-            placeholder_color: Color::rgb8(65, 60, 55),
-            textbox_background_color: Color::rgb8(35, 30, 25),
-            inactive_border_color: Color::rgba(0.0, 0.0, 0.0, 0.0),
-            active_border_color: Color::rgb8(181, 98, 2),
-            text_selection_color: Color::rgb8(181, 98, 2),
-            button_dark_color: Color::rgb8(181, 98, 2),
-            button_light_color: Color::rgb8(181, 98, 2),
-            border_radius: 10.0,
+            background_color: Color::BLACK,
+            background_special_color: Color::rgb8(20, 20, 20),
+            placeholder_color: Color::rgb8(200, 200, 200),
+            textbox_background_color: Color::BLACK,
+            text_selection_color: Color::rgb8(0, 90, 200),
+            active_border_color: Color::rgb8(255, 220, 0),
+            inactive_border_color: Color::WHITE,
+            button_dark_color: Color::rgb8(30, 30, 30),
+            button_light_color: Color::rgb8(60, 60, 60),
+            border_radius: 0.0,
+        }
+    }
+}
+
+// Theme as it's stored in the JSON file, colors are "#RGB", "#RRGGBB" or "#RRGGBBAA".
+#[derive(Deserialize)]
+pub struct ThemeDefinition {
+    pub background_color: String,
+    pub background_special_color: String,
+    pub placeholder_color: String,
+    pub textbox_background_color: String,
+    pub text_selection_color: String,
+    pub active_border_color: String,
+    pub inactive_border_color: String,
+    pub button_dark_color: String,
+    pub button_light_color: String,
+    pub border_radius: f64,
+}
+
+impl ThemeDefinition {
+    pub fn to_application_theme(&self) -> Result<ApplicationTheme, String> {
+        Ok(ApplicationTheme {
+            background_color: ThemeDefinition::parse_color(
+                "background_color",
+                &self.background_color,
+            )?,
+            background_special_color: ThemeDefinition::parse_color(
+                "background_special_color",
+                &self.background_special_color,
+            )?,
+            placeholder_color: ThemeDefinition::parse_color(
+                "placeholder_color",
+                &self.placeholder_color,
+            )?,
+            textbox_background_color: ThemeDefinition::parse_color(
+                "textbox_background_color",
+                &self.textbox_background_color,
+            )?,
+            text_selection_color: ThemeDefinition::parse_color(
+                "text_selection_color",
+                &self.text_selection_color,
+            )?,
+            active_border_color: ThemeDefinition::parse_color(
+                "active_border_color",
+                &self.active_border_color,
+            )?,
+            inactive_border_color: ThemeDefinition::parse_color(
+                "inactive_border_color",
+                &self.inactive_border_color,
+            )?,
+            button_dark_color: ThemeDefinition::parse_color(
+                "button_dark_color",
+                &self.button_dark_color,
+            )?,
+            button_light_color: ThemeDefinition::parse_color(
+                "button_light_color",
+                &self.button_light_color,
+            )?,
+            border_radius: self.border_radius,
+        })
+    }
+
+    fn parse_color(name: &str, value: &str) -> Result<Color, String> {
+        let color = Color::from_hex_str(value);
+        if let Err(e) = color {
+            return Err(format!(
+                "Color::from_hex_str() failed, error: invalid value '{}' for '{}' (error: {}) at [{}, {}]",
+                value,
+                name,
+                e,
+                file!(),
+                line!()
+            ));
         }
+
+        Ok(color.unwrap())
     }
 }
 
-impl ApplicationTheme {}
+// Loads themes from the "*.theme.json" files stored next to the config file.
+pub struct ThemeManager {}
+
+impl ThemeManager {
+    pub fn load_from_file(path: &str) -> Result<ApplicationTheme, String> {
+        let content = read_to_string(path);
+        if let Err(e) = content {
+            return Err(format!(
+                "read_to_string() failed, error: can't read theme '{}' (error: {}) at [{}, {}]",
+                path,
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        let theme = ThemeManager::load_from_str(&content.unwrap());
+        if let Err(e) = theme {
+            return Err(format!(
+                "{} (theme '{}') at [{}, {}]",
+                e,
+                path,
+                file!(),
+                line!()
+            ));
+        }
+
+        theme
+    }
+
+    pub fn load_from_str(json: &str) -> Result<ApplicationTheme, String> {
+        let definition = serde_json::from_str::<ThemeDefinition>(json);
+        if let Err(e) = definition {
+            return Err(format!(
+                "serde_json::from_str() failed, error: {} at [{}, {}]",
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        definition.unwrap().to_application_theme()
+    }
+
+    // Empty name means the bundled default theme.
+    pub fn load_by_name(theme_name: &str) -> Result<ApplicationTheme, String> {
+        if theme_name.is_empty() {
+            return Ok(ApplicationTheme::default());
+        }
+
+        let theme_path = ThemeManager::get_theme_file_path(theme_name);
+        if let Err(e) = theme_path {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+
+        ThemeManager::load_from_file(&theme_path.unwrap())
+    }
+
+    // Returns sorted names (file names without THEME_FILE_EXTENSION)
+    // of the themes found in the config directory.
+    pub fn find_themes() -> Result<Vec<String>, String> {
+        let config_dir = UserConfig::get_config_directory();
+        if let Err(e) = config_dir {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+        let config_dir = config_dir.unwrap();
+
+        let entries = read_dir(&config_dir);
+        if let Err(e) = entries {
+            return Err(format!(
+                "read_dir() failed, error: can't read directory '{}' (error: {}) at [{}, {}]",
+                config_dir,
+                e,
+                file!(),
+                line!()
+            ));
+        }
+
+        let mut themes = Vec::new();
+        for entry in entries.unwrap() {
+            if entry.is_err() {
+                continue;
+            }
+            let path = entry.unwrap().path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+            if let Some(theme_name) = file_name.strip_suffix(THEME_FILE_EXTENSION) {
+                if !theme_name.is_empty() {
+                    themes.push(String::from(theme_name));
+                }
+            }
+        }
+
+        themes.sort();
+
+        Ok(themes)
+    }
+
+    fn get_theme_file_path(theme_name: &str) -> Result<String, String> {
+        let config_dir = UserConfig::get_config_directory();
+        if let Err(e) = config_dir {
+            return Err(format!("{} at [{}, {}]", e, file!(), line!()));
+        }
+
+        let theme_path = format!(
+            "{}{}{}",
+            config_dir.unwrap(),
+            theme_name,
+            THEME_FILE_EXTENSION
+        );
+        if !Path::new(&theme_path).exists() {
+            return Err(format!(
+                "theme file '{}' does not exist at [{}, {}]",
+                theme_path,
+                file!(),
+                line!()
+            ));
+        }
+
+        Ok(theme_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_file_path;
+
+    // Default theme with the specified value of the field.
+    fn theme_json_with(field: &str, value: &str) -> String {
+        let mut theme: serde_json::Value = serde_json::from_str(DEFAULT_THEME_JSON).unwrap();
+        theme[field] = serde_json::from_str(value).unwrap();
+
+        theme.to_string()
+    }
+
+    #[test]
+    fn default_theme_is_deserialized() {
+        let theme = ApplicationTheme::default();
+
+        assert_eq!(theme.background_color.as_rgba8(), (0x1E, 0x1A, 0x16, 0xFF));
+        assert_eq!(theme.button_dark_color.as_rgba8(), (0xB5, 0x62, 0x02, 0xFF));
+        assert_eq!(theme.inactive_border_color.as_rgba8(), (0, 0, 0, 0));
+        assert!((theme.border_radius - 10.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn all_color_formats_are_supported() {
+        for (value, expected) in [
+            ("\"#333\"", (0x33, 0x33, 0x33, 0xFF)),
+            ("\"#1E1E1E\"", (0x1E, 0x1E, 0x1E, 0xFF)),
+            ("\"#1E1E1E80\"", (0x1E, 0x1E, 0x1E, 0x80)),
+        ]
+        .iter()
+        {
+            let theme =
+                ThemeManager::load_from_str(&theme_json_with("button_dark_color", value)).unwrap();
+            assert_eq!(theme.button_dark_color.as_rgba8(), *expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn invalid_color_is_error() {
+        for value in ["\"not a color\"", "\"#12\"", "\"#GGGGGG\"", "\"\""].iter() {
+            let result = ThemeManager::load_from_str(&theme_json_with("placeholder_color", value));
+
+            let error = result.err().unwrap();
+            assert!(
+                error.contains(&format!(
+                    "invalid value '{}' for 'placeholder_color'",
+                    value.trim_matches('"')
+                )),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_value_types_are_errors() {
+        assert!(ThemeManager::load_from_str(&theme_json_with("background_color", "1")).is_err());
+        assert!(ThemeManager::load_from_str(&theme_json_with("border_radius", "\"10\"")).is_err());
+    }
+
+    #[test]
+    fn missing_field_is_error() {
+        let mut theme: serde_json::Value = serde_json::from_str(DEFAULT_THEME_JSON).unwrap();
+        theme
+            .as_object_mut()
+            .unwrap()
+            .remove("text_selection_color");
+
+        let error = ThemeManager::load_from_str(&theme.to_string())
+            .err()
+            .unwrap();
+
+        assert!(
+            error.contains("missing field `text_selection_color`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn invalid_json_is_error() {
+        assert!(ThemeManager::load_from_str("").is_err());
+        assert!(ThemeManager::load_from_str("{").is_err());
+        assert!(ThemeManager::load_from_str("[]").is_err());
+    }
+
+    #[test]
+    fn theme_is_loaded_from_file() {
+        let path = temp_file_path("custom.theme.json");
+        std::fs::write(&path, theme_json_with("background_color", "\"#102030\"")).unwrap();
+
+        let theme = ThemeManager::load_from_file(&path).unwrap();
+        assert_eq!(theme.background_color.as_rgba8(), (0x10, 0x20, 0x30, 0xFF));
+
+        std::fs::remove_file(&path).unwrap();
+        let error = ThemeManager::load_from_file(&path).err().unwrap();
+        assert!(error.contains("can't read theme"), "{}", error);
+    }
+}
@@ -0,0 +1,264 @@
+// External.
+use druid::kurbo::{Rect, RoundedRect};
+use druid::widget::prelude::*;
+
+const PROGRESS_BAR_DEFAULT_WIDTH: f64 = 200.0; // if the width is not constrained
+const PROGRESS_BAR_HEIGHT: f64 = 6.0;
+const PROGRESS_BAR_BORDER_WIDTH: f64 = 1.0;
+const INDETERMINATE_FILL_WIDTH: f64 = 0.3; // part of the bar filled in the indeterminate mode
+const DEFAULT_BOUNCES_PER_SEC: f64 = 0.5; // full cycles (there and back)
+
+type ValueFn<T> = Box<dyn Fn(&T) -> Option<f64>>;
+
+// Drawing of the bar, 'paint' executes the commands in this order.
+#[derive(Debug, PartialEq)]
+pub enum BarPaintCommand {
+    FillBackground(RoundedRect),
+    StrokeBorder(RoundedRect),
+    FillProgress(RoundedRect),
+}
+
+// Progress bar that shows 'Some(value)' (0.0 - 1.0) as a normal progress
+// and 'None' as a fill that bounces from side to side (for operations with unknown duration).
+pub struct AnimatedProgressBar<T> {
+    value: ValueFn<T>,
+    bounces_per_sec: f64,
+    phase: f64, // in [0.0; 1.0), used in the indeterminate mode
+}
+
+impl<T: Data> AnimatedProgressBar<T> {
+    pub fn new(value: impl Fn(&T) -> Option<f64> + 'static) -> Self {
+        AnimatedProgressBar {
+            value: Box::new(value),
+            bounces_per_sec: DEFAULT_BOUNCES_PER_SEC,
+            phase: 0.0,
+        }
+    }
+    /// Animation speed of the indeterminate mode.
+    pub fn with_bounces_per_sec(mut self, bounces_per_sec: f64) -> Self {
+        self.bounces_per_sec = bounces_per_sec;
+        self
+    }
+    // Returns the phase after 'interval_ns' nanoseconds, wraps around to 0.0 after 1.0.
+    pub fn next_phase(phase: f64, interval_ns: u64, bounces_per_sec: f64) -> f64 {
+        let phase = phase + interval_ns as f64 / 1_000_000_000.0 * bounces_per_sec;
+
+        phase.fract()
+    }
+    /// Returns the filled part of the bar as (start, end) in [0.0; 1.0].
+    pub fn get_fill_range(value: Option<f64>, phase: f64) -> (f64, f64) {
+        match value {
+            Some(value) => (0.0, value.clamp(0.0, 1.0)),
+            None => {
+                // goes to the right in the first half of the phase and back in the second one
+                let position = 1.0 - (phase * 2.0 - 1.0).abs();
+                let start = position * (1.0 - INDETERMINATE_FILL_WIDTH);
+                (start, start + INDETERMINATE_FILL_WIDTH)
+            }
+        }
+    }
+    /// Returns what should be drawn for the current value and animation phase.
+    pub fn get_paint_commands(&self, size: Size, data: &T) -> Vec<BarPaintCommand> {
+        let radius = size.height / 2.0;
+
+        let background = size
+            .to_rect()
+            .inset(-PROGRESS_BAR_BORDER_WIDTH / 2.0)
+            .to_rounded_rect(radius);
+        let mut commands = vec![
+            BarPaintCommand::FillBackground(background),
+            BarPaintCommand::StrokeBorder(background),
+        ];
+
+        let (start, end) = AnimatedProgressBar::<T>::get_fill_range((self.value)(data), self.phase);
+        if end > start {
+            let fill = Rect::new(start * size.width, 0.0, end * size.width, size.height)
+                .to_rounded_rect(radius);
+            commands.push(BarPaintCommand::FillProgress(fill));
+        }
+
+        commands
+    }
+}
+
+impl<T: Data> Widget<T> for AnimatedProgressBar<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, _env: &Env) {
+        if let Event::AnimFrame(interval) = event {
+            if (self.value)(data).is_some() {
+                self.phase = 0.0;
+                return;
+            }
+
+            self.phase =
+                AnimatedProgressBar::<T>::next_phase(self.phase, *interval, self.bounces_per_sec);
+            ctx.request_paint();
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if (self.value)(data).is_none() {
+                ctx.request_anim_frame();
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        let old_value = (self.value)(old_data);
+        let value = (self.value)(data);
+        if old_value == value {
+            return;
+        }
+
+        if value.is_none() {
+            // switched to the indeterminate mode
+            ctx.request_anim_frame();
+        }
+        ctx.request_paint();
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            PROGRESS_BAR_DEFAULT_WIDTH
+        };
+
+        bc.constrain(Size::new(width, PROGRESS_BAR_HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        for command in self.get_paint_commands(ctx.size(), data) {
+            match command {
+                BarPaintCommand::FillBackground(rect) => {
+                    ctx.fill(rect, &env.get(druid::theme::BACKGROUND_DARK))
+                }
+                BarPaintCommand::StrokeBorder(rect) => ctx.stroke(
+                    rect,
+                    &env.get(druid::theme::BORDER_DARK),
+                    PROGRESS_BAR_BORDER_WIDTH,
+                ),
+                BarPaintCommand::FillProgress(rect) => {
+                    ctx.fill(rect, &env.get(druid::theme::PRIMARY_LIGHT))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Size = Size::new(200.0, PROGRESS_BAR_HEIGHT);
+
+    fn bar(value: Option<f64>) -> AnimatedProgressBar<()> {
+        AnimatedProgressBar::new(move |_data: &()| value)
+    }
+
+    /// Returns (start, end) of the filled part in pixels, 'None' if nothing is filled.
+    fn get_fill(commands: &[BarPaintCommand]) -> Option<(f64, f64)> {
+        commands.iter().find_map(|command| match command {
+            BarPaintCommand::FillProgress(rect) => Some((rect.rect().x0, rect.rect().x1)),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn background_and_border_are_always_painted() {
+        for value in [Some(0.0), Some(0.5), None].iter() {
+            let commands = bar(*value).get_paint_commands(SIZE, &());
+
+            assert!(matches!(commands[0], BarPaintCommand::FillBackground(_)));
+            assert!(matches!(commands[1], BarPaintCommand::StrokeBorder(_)));
+        }
+    }
+
+    #[test]
+    fn value_is_painted_from_left_side() {
+        let commands = bar(Some(0.5)).get_paint_commands(SIZE, &());
+
+        assert_eq!(commands.len(), 3);
+        assert_eq!(get_fill(&commands), Some((0.0, 100.0)));
+    }
+
+    #[test]
+    fn value_is_clamped() {
+        assert_eq!(
+            get_fill(&bar(Some(1.5)).get_paint_commands(SIZE, &())),
+            Some((0.0, 200.0))
+        );
+        assert_eq!(
+            get_fill(&bar(Some(-0.5)).get_paint_commands(SIZE, &())),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_value_has_no_fill() {
+        let commands = bar(Some(0.0)).get_paint_commands(SIZE, &());
+
+        assert_eq!(commands.len(), 2);
+        assert_eq!(get_fill(&commands), None);
+    }
+
+    #[test]
+    fn indeterminate_fill_has_fixed_width() {
+        let mut bar = bar(None);
+
+        for phase in [0.0, 0.25, 0.5, 0.75].iter() {
+            bar.phase = *phase;
+            let (start, end) = get_fill(&bar.get_paint_commands(SIZE, &())).unwrap();
+
+            assert!((end - start - INDETERMINATE_FILL_WIDTH * SIZE.width).abs() < 1e-9);
+            assert!(start >= 0.0 && end <= SIZE.width);
+        }
+    }
+
+    #[test]
+    fn indeterminate_fill_bounces() {
+        let mut bar = bar(None);
+        let mut starts = Vec::new();
+        for phase in [0.0, 0.25, 0.5, 0.75].iter() {
+            bar.phase = *phase;
+            starts.push(get_fill(&bar.get_paint_commands(SIZE, &())).unwrap().0);
+        }
+
+        // left side, middle, right side, back in the middle
+        assert_eq!(starts, vec![0.0, 70.0, 140.0, 70.0]);
+    }
+
+    #[test]
+    fn value_and_indeterminate_mode_are_painted_differently() {
+        let mut indeterminate = bar(None);
+        indeterminate.phase = 0.25;
+        let mut determinate = bar(Some(0.3));
+        determinate.phase = 0.25;
+
+        assert_ne!(
+            indeterminate.get_paint_commands(SIZE, &()),
+            determinate.get_paint_commands(SIZE, &())
+        );
+        // the phase is ignored for values
+        assert_eq!(
+            get_fill(&determinate.get_paint_commands(SIZE, &())),
+            Some((0.0, 60.0))
+        );
+    }
+
+    #[test]
+    fn phase_wraps_around() {
+        let half_second = 500_000_000;
+
+        let phase = AnimatedProgressBar::<()>::next_phase(0.0, half_second, 0.5);
+        assert!((phase - 0.25).abs() < 1e-9);
+
+        let phase = AnimatedProgressBar::<()>::next_phase(0.75, half_second, 1.0);
+        assert!((phase - 0.25).abs() < 1e-9);
+
+        // faster animation
+        let phase = AnimatedProgressBar::<()>::next_phase(0.0, half_second, 1.0);
+        assert!((phase - 0.5).abs() < 1e-9);
+    }
+}
@@ -1,14 +1,16 @@
 // External.
 use chrono::prelude::*;
+use druid::piet::ImageFormat;
 use druid::widget::prelude::*;
 use druid::widget::{
-    Button, CrossAxisAlignment, EnvScope, Flex, Label, LineBreaking, Padding, Scroll, ViewSwitcher,
+    Button, CrossAxisAlignment, EnvScope, FillStrat, Flex, Image, Label, LineBreaking, Padding,
+    Scroll, ViewSwitcher,
 };
-use druid::{Color, Data, Lens, WidgetExt};
+use druid::{Color, Data, ImageBuf, Lens, Point, WidgetExt};
 use sfml::audio::{Sound, SoundBuffer, SoundStatus};
 
 // Std.
-use std::collections::LinkedList;
+use std::collections::{HashMap, HashSet, LinkedList};
 use std::rc::Rc;
 use std::sync::Mutex;
 use std::thread;
@@ -16,23 +18,45 @@ use std::time::Duration;
 
 // Custom.
 use crate::global_params::*;
+use crate::layouts::main_layout::MainLayout;
+use crate::misc::chat_list_controller::*;
 use crate::misc::custom_data_button_controller::*;
 use crate::misc::locale_keys::*;
+use crate::misc::room_invite_controller::*;
+use crate::misc::search_highlight_controller::*;
+use crate::misc::text_highlighter::TextHighlighter;
+use crate::widgets::reaction_bar::*;
+use crate::widgets::search_panel::SearchMessageResult;
+use crate::widgets::selectable_chat_label::*;
 use crate::ApplicationState;
 
 #[derive(Clone, Data, Lens)]
 pub struct ChatList {
     pub refresh_ui: bool, // because interior mutability (on messages) doesn't work in druid's data
     pub messages: Rc<Mutex<LinkedList<ChatMessage>>>,
+    pub undo_buffer: Rc<Mutex<Option<LinkedList<ChatMessage>>>>, // messages removed by 'clear_all' (1 level)
+    pub highlighted_message_id: Option<u64>,                     // message found by the search
+    pub scroll_offset: Point, // kept here because the chat's Scroll is recreated on rebuild
+    pub pinned_messages: Rc<Mutex<Vec<PinnedMessage>>>, // newest first, shown above the messages
+    pub unread_count: u32,    // messages received while the chat was not shown
+    pub trimmed_count: u64,   // old messages removed because of 'max_messages'
     max_messages: usize,
+    next_message_id: u64,
 }
 
 impl ChatList {
     pub fn new() -> Self {
         ChatList {
             messages: Rc::new(Mutex::new(LinkedList::new())),
-            max_messages: MAX_MESSAGES_ON_SCREEN,
+            undo_buffer: Rc::new(Mutex::new(None)),
+            max_messages: DEFAULT_CHAT_MAX_MESSAGES,
+            trimmed_count: 0,
             refresh_ui: false,
+            highlighted_message_id: None,
+            scroll_offset: Point::ORIGIN,
+            pinned_messages: Rc::new(Mutex::new(Vec::new())),
+            unread_count: 0,
+            next_message_id: 0,
         }
     }
     pub fn build_ui() -> impl Widget<ApplicationState> {
@@ -42,77 +66,327 @@ impl ChatList {
                 _ => Box::new(ChatList::get_list_ui(data)),
             },
         )
+        .controller(RoomInviteController::new())
     }
     fn get_list_ui(data: &ApplicationState) -> impl Widget<ApplicationState> {
         let mut content: Flex<ApplicationState> =
             Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
 
+        if let Some(text) = data
+            .main_layout
+            .chat_list
+            .get_trimmed_messages_text(&data.localization)
+        {
+            content.add_child(ChatList::get_date_separator_ui(text));
+        }
+
+        let separator_labels = data
+            .main_layout
+            .chat_list
+            .get_date_separator_labels(Local::now().naive_local().date(), &data.localization);
+
         let messages_guard = data.main_layout.chat_list.messages.lock().unwrap();
-        for message in messages_guard.iter() {
+        for (message, separator_label) in messages_guard.iter().zip(separator_labels) {
+            if let Some(label) = separator_label {
+                content.add_child(ChatList::get_date_separator_ui(label));
+            }
+
             content.add_child(message.get_ui(data))
         }
 
-        Scroll::new(content).vertical()
+        Flex::column()
+            .must_fill_main_axis(true)
+            .with_child(ChatList::get_pinned_messages_ui(data).expand_width())
+            .with_flex_child(
+                Scroll::new(content)
+                    .vertical()
+                    .controller(ChatListController::new())
+                    .expand_width(),
+                1.0,
+            )
+    }
+    fn get_pinned_messages_ui(data: &ApplicationState) -> impl Widget<ApplicationState> {
+        let mut pinned_column: Flex<ApplicationState> =
+            Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+
+        let pinned_guard = data.main_layout.chat_list.pinned_messages.lock().unwrap();
+        for pinned in pinned_guard.iter() {
+            let server_message_id = pinned.server_message_id;
+
+            pinned_column.add_child(Padding::new(
+                (5.0, 2.0),
+                Label::new(format!("{} {}: {}", PIN_ICON, pinned.author, pinned.text))
+                    .with_text_size(MESSAGE_TEXT_SIZE)
+                    .with_line_break_mode(LineBreaking::Clip)
+                    .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                        data.main_layout
+                            .chat_list
+                            .highlight_server_message(server_message_id);
+                    }),
+            ));
+        }
+
+        pinned_column
+    }
+    fn get_date_separator_ui(label: String) -> impl Widget<ApplicationState> {
+        Padding::new(
+            5.0,
+            Label::new(label)
+                .with_text_size(MESSAGE_TEXT_SIZE)
+                .with_text_color(Color::GRAY)
+                .center()
+                .expand_width(),
+        )
+    }
+    /// Returns "Today", "Yesterday" or a full date like "Monday, January 20".
+    pub fn get_relative_date_label(
+        date: NaiveDate,
+        today: NaiveDate,
+        localization: &HashMap<String, String>,
+    ) -> String {
+        if date == today {
+            return localization
+                .get(LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_TODAY_TEXT)
+                .unwrap()
+                .clone();
+        }
+        if today.pred_opt() == Some(date) {
+            return localization
+                .get(LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_YESTERDAY_TEXT)
+                .unwrap()
+                .clone();
+        }
+
+        let weekday_key = match date.weekday() {
+            Weekday::Mon => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_MONDAY_TEXT,
+            Weekday::Tue => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_TUESDAY_TEXT,
+            Weekday::Wed => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_WEDNESDAY_TEXT,
+            Weekday::Thu => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_THURSDAY_TEXT,
+            Weekday::Fri => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FRIDAY_TEXT,
+            Weekday::Sat => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_SATURDAY_TEXT,
+            Weekday::Sun => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_SUNDAY_TEXT,
+        };
+        let month_key = match date.month() {
+            1 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_JANUARY_TEXT,
+            2 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FEBRUARY_TEXT,
+            3 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_MARCH_TEXT,
+            4 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_APRIL_TEXT,
+            5 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_MAY_TEXT,
+            6 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_JUNE_TEXT,
+            7 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_JULY_TEXT,
+            8 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_AUGUST_TEXT,
+            9 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_SEPTEMBER_TEXT,
+            10 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_OCTOBER_TEXT,
+            11 => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_NOVEMBER_TEXT,
+            _ => LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_DECEMBER_TEXT,
+        };
+
+        localization
+            .get(LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FORMAT_TEXT)
+            .unwrap()
+            .replace("{weekday}", localization.get(weekday_key).unwrap())
+            .replace("{month}", localization.get(month_key).unwrap())
+            .replace("{day}", &date.day().to_string())
+    }
+    /// Returns the date separator label to show before each message
+    /// (messages are grouped by the date they were received), 'None' if
+    /// the message is from the same day as the previous one.
+    pub fn get_date_separator_labels(
+        &self,
+        today: NaiveDate,
+        localization: &HashMap<String, String>,
+    ) -> Vec<Option<String>> {
+        let mut last_date: Option<NaiveDate> = None;
+
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| {
+                let date = message.get_date();
+                if last_date == Some(date) {
+                    return None;
+                }
+                last_date = Some(date);
+
+                Some(ChatList::get_relative_date_label(date, today, localization))
+            })
+            .collect()
+    }
+    /// Returns time left until the relative date labels should be recalculated.
+    pub fn get_time_until_next_day(now: NaiveDateTime) -> Duration {
+        let next_day = now.date().succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        // +1 sec to be sure that the day has changed when the timer fires
+        Duration::from_secs((next_day - now).num_seconds().max(0) as u64 + 1)
+    }
+    /// Called when the day changes, "Today" becomes "Yesterday" and so on.
+    pub fn update_date_separators(&mut self) {
+        self.refresh_ui = !self.refresh_ui;
+    }
+    /// Called by the chat's controller when the chat is scrolled.
+    pub fn save_scroll_position(&mut self, offset: Point) {
+        self.scroll_offset = offset;
+    }
+    /// Called by the chat's controller after the chat is rebuilt.
+    pub fn restore_scroll_position(&self) -> Point {
+        self.scroll_offset
     }
     pub fn clear_text_chat(&mut self) {
         self.messages.lock().unwrap().clear();
+        self.pinned_messages.lock().unwrap().clear();
+        self.unread_count = 0;
+        self.trimmed_count = 0;
+        self.scroll_offset = Point::ORIGIN;
+        self.drop_undo_buffer();
         self.refresh_ui = !self.refresh_ui;
     }
+    /// Clears the chat but keeps the messages so that 'undo_clear_all' can restore them.
+    pub fn clear_all(&mut self) {
+        let messages = std::mem::take(&mut *self.messages.lock().unwrap());
+        *self.undo_buffer.lock().unwrap() = Some(messages);
+        self.refresh_ui = !self.refresh_ui;
+    }
+    pub fn undo_clear_all(&mut self) {
+        if let Some(messages) = self.undo_buffer.lock().unwrap().take() {
+            *self.messages.lock().unwrap() = messages;
+            self.refresh_ui = !self.refresh_ui;
+        }
+    }
+    pub fn can_undo_clear_all(&self) -> bool {
+        self.undo_buffer.lock().unwrap().is_some()
+    }
+    pub fn drop_undo_buffer(&mut self) {
+        if self.undo_buffer.lock().unwrap().take().is_some() {
+            self.refresh_ui = !self.refresh_ui;
+        }
+    }
     pub fn add_info_message(&mut self, message: String) {
+        self.drop_undo_buffer();
+
         let mut messages_guard = self.messages.lock().unwrap();
 
         messages_guard.push_back(ChatMessage::new(
+            self.next_message_id,
             message,
             String::from(""),
             MessageType::InfoMessage,
         ));
+        self.next_message_id += 1;
 
-        if messages_guard.len() > self.max_messages {
-            messages_guard.pop_front();
-        }
+        drop(messages_guard);
+        self.trim_to_limit(self.max_messages);
 
         self.refresh_ui = !self.refresh_ui;
     }
     pub fn add_system_message(&mut self, message: String) {
+        self.drop_undo_buffer();
+
         let mut messages_guard = self.messages.lock().unwrap();
 
         messages_guard.push_back(ChatMessage::new(
+            self.next_message_id,
             message,
             String::from(""),
             MessageType::SystemMessage,
         ));
+        self.next_message_id += 1;
 
-        if messages_guard.len() > self.max_messages {
-            messages_guard.pop_front();
-        }
+        drop(messages_guard);
+        self.trim_to_limit(self.max_messages);
 
         self.refresh_ui = !self.refresh_ui;
     }
-    pub fn add_message(&mut self, message: &str, author: &str) {
+    /// Adds an info message with "Accept" and "Decline" buttons, returns ID of the new chat message.
+    pub fn add_room_invite(&mut self, message: String, room_name: String) -> u64 {
+        self.drop_undo_buffer();
+
         let mut messages_guard = self.messages.lock().unwrap();
 
-        let mut add_message = true;
+        let message_id = self.next_message_id;
 
-        if let Some(last_message) = messages_guard.back_mut() {
-            if last_message.author == author && last_message.time == ChatMessage::current_time() {
-                last_message.message.push('\n');
-                last_message.message.push_str(&message);
-                add_message = false;
-            }
+        let mut new_message = ChatMessage::new(
+            message_id,
+            message,
+            String::from(""),
+            MessageType::InfoMessage,
+        );
+        new_message.room_invite = Some(RoomInvite {
+            room_name,
+            expires_at: Local::now() + chrono::Duration::seconds(ROOM_INVITE_EXPIRY_SEC as i64),
+            is_closed: false,
+        });
+
+        messages_guard.push_back(new_message);
+        self.next_message_id += 1;
+
+        drop(messages_guard);
+        self.trim_to_limit(self.max_messages);
+
+        self.refresh_ui = !self.refresh_ui;
+
+        message_id
+    }
+    /// Hides the invite's buttons (accepted, declined or expired).
+    /// Returns the invite's room if the invite was still active.
+    pub fn close_room_invite(&mut self, message_id: u64) -> Option<String> {
+        let mut messages_guard = self.messages.lock().unwrap();
+
+        // the message might be already removed
+        let invite = messages_guard
+            .iter_mut()
+            .find(|message| message.id == message_id)?
+            .room_invite
+            .as_mut()?;
+        if invite.is_closed {
+            return None;
         }
 
-        if add_message {
-            messages_guard.push_back(ChatMessage::new(
-                message.to_string(),
-                author.to_string(),
-                MessageType::UserMessage,
-            ));
+        invite.is_closed = true;
+        self.refresh_ui = !self.refresh_ui;
 
-            if messages_guard.len() > self.max_messages {
-                messages_guard.pop_front();
-            }
+        if invite.is_expired(Local::now()) {
+            return None;
         }
 
+        Some(invite.room_name.clone())
+    }
+    /// Returns ID of the new chat message.
+    /// Consecutive messages from the same author are shown as a group
+    /// (only the first message of the group has the author header).
+    /// Mentions of 'usernames' ("@username") are highlighted.
+    pub fn add_message(
+        &mut self,
+        message: &str,
+        author: &str,
+        server_message_id: u64,
+        usernames: &[String],
+    ) -> u64 {
+        self.drop_undo_buffer();
+
+        let mut messages_guard = self.messages.lock().unwrap();
+
+        let message_id = self.next_message_id;
+
+        let mut new_message = ChatMessage::new(
+            message_id,
+            message.to_string(),
+            author.to_string(),
+            MessageType::UserMessage,
+        );
+        new_message.server_message_id = Some(server_message_id);
+        new_message.mentions = TextHighlighter::find_mentions(message, usernames);
+
+        if let Some(last_message) = messages_guard.back() {
+            new_message.group_header = !new_message.continues_group_of(last_message);
+        }
+
+        messages_guard.push_back(new_message);
+        self.next_message_id += 1;
+
+        drop(messages_guard);
+        self.trim_to_limit(self.max_messages);
+
         self.refresh_ui = !self.refresh_ui;
 
         thread::spawn(move || {
@@ -123,6 +397,215 @@ impl ChatList {
                 std::thread::sleep(Duration::from_secs(1));
             }
         });
+
+        message_id
+    }
+    /// Used for new messages (removes old messages if the chat has more than 'max_messages').
+    pub fn set_max_messages(&mut self, max_messages: usize) {
+        self.max_messages = max_messages;
+        self.trim_to_limit(max_messages);
+        self.refresh_ui = !self.refresh_ui;
+    }
+    /// Removes the oldest messages so that at most 'limit' messages are left.
+    /// Date separators are created from the remaining messages so a separator
+    /// of the removed messages disappears with them. Does not refresh the UI.
+    pub fn trim_to_limit(&mut self, limit: usize) {
+        let mut messages_guard = self.messages.lock().unwrap();
+        if messages_guard.len() <= limit {
+            return;
+        }
+
+        while messages_guard.len() > limit {
+            messages_guard.pop_front();
+            self.trimmed_count += 1;
+        }
+
+        // the group might have lost its header
+        if let Some(first_message) = messages_guard.front_mut() {
+            first_message.group_header = true;
+        }
+    }
+    /// Returns "N older messages hidden" if some messages were removed by 'trim_to_limit'.
+    pub fn get_trimmed_messages_text(
+        &self,
+        localization: &HashMap<String, String>,
+    ) -> Option<String> {
+        if self.trimmed_count == 0 {
+            return None;
+        }
+
+        Some(format!(
+            "{} {}",
+            self.trimmed_count,
+            localization
+                .get(LOCALE_MAIN_LAYOUT_OLDER_MESSAGES_HIDDEN_TEXT)
+                .unwrap()
+        ))
+    }
+    pub fn increment_unread_count(&mut self) {
+        self.unread_count = self.unread_count.saturating_add(1);
+    }
+    pub fn clear_unread_count(&mut self) {
+        self.unread_count = 0;
+    }
+    /// Returns 'false' if the message was not found or already has an image.
+    pub fn start_image_loading(&mut self, message_id: u64) -> bool {
+        let mut messages_guard = self.messages.lock().unwrap();
+
+        let message = messages_guard
+            .iter_mut()
+            .find(|message| message.id == message_id);
+        if message.is_none() {
+            return false;
+        }
+        let message = message.unwrap();
+
+        if message.image.is_some() {
+            return false;
+        }
+        message.image = Some(ChatImage::Loading);
+
+        self.refresh_ui = !self.refresh_ui;
+
+        true
+    }
+    /// Adds the reaction of the user or removes it if the user already reacted with this emoji.
+    /// Returns 'false' if the message was not found.
+    pub fn toggle_reaction(&mut self, server_message_id: u64, username: &str, emoji: &str) -> bool {
+        let mut messages_guard = self.messages.lock().unwrap();
+
+        let message = messages_guard
+            .iter_mut()
+            .find(|message| message.server_message_id == Some(server_message_id));
+        if message.is_none() {
+            // might be already removed
+            return false;
+        }
+        message.unwrap().toggle_reaction(username, emoji);
+
+        self.refresh_ui = !self.refresh_ui;
+
+        true
+    }
+    /// Returns user messages (newest first) to search in a separate thread.
+    pub fn get_searchable_messages(&self) -> Vec<SearchMessageResult> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|message| message.message_type == MessageType::UserMessage)
+            .map(|message| SearchMessageResult {
+                message_id: message.id,
+                author: message.author.clone(),
+                message: message.message.clone(),
+            })
+            .collect()
+    }
+    /// Case-insensitive search, messages with a word that starts with the query
+    /// go first, then messages that just contain the query (keeps the order of
+    /// the messages inside of these groups).
+    pub fn search(messages: Vec<SearchMessageResult>, query: &str) -> Vec<SearchMessageResult> {
+        let query = query.to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut word_matches = Vec::new();
+        let mut other_matches = Vec::new();
+
+        for message in messages.into_iter() {
+            let text = message.message.to_lowercase();
+            if !text.contains(&query) {
+                continue;
+            }
+
+            if text.split_whitespace().any(|word| word.starts_with(&query)) {
+                word_matches.push(message);
+            } else {
+                other_matches.push(message);
+            }
+        }
+
+        word_matches.append(&mut other_matches);
+        word_matches
+    }
+    pub fn highlight_message(&mut self, message_id: u64) {
+        self.highlighted_message_id = Some(message_id);
+        self.refresh_ui = !self.refresh_ui;
+    }
+    /// Highlights (and scrolls to) the message with the specified server ID.
+    /// Returns 'false' if the message is no longer in the chat.
+    pub fn highlight_server_message(&mut self, server_message_id: u64) -> bool {
+        let message_id = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|message| message.server_message_id == Some(server_message_id))
+            .map(|message| message.id);
+
+        match message_id {
+            Some(message_id) => {
+                self.highlight_message(message_id);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Adds the message to the top of the pinned messages,
+    /// the oldest pinned message is removed if there are more than MAX_PINNED_MESSAGES.
+    pub fn pin_message(&mut self, server_message_id: u64, author: String, text: String) {
+        let mut pinned_guard = self.pinned_messages.lock().unwrap();
+
+        pinned_guard.retain(|pinned| pinned.server_message_id != server_message_id);
+        pinned_guard.insert(
+            0,
+            PinnedMessage {
+                server_message_id,
+                author,
+                text,
+            },
+        );
+        pinned_guard.truncate(MAX_PINNED_MESSAGES);
+
+        self.refresh_ui = !self.refresh_ui;
+    }
+    pub fn clear_highlight(&mut self) {
+        if self.highlighted_message_id.take().is_some() {
+            self.refresh_ui = !self.refresh_ui;
+        }
+    }
+    pub fn set_message_image(&mut self, message_id: u64, image: ChatImage) {
+        let mut messages_guard = self.messages.lock().unwrap();
+
+        // the message might be already removed
+        if let Some(message) = messages_guard
+            .iter_mut()
+            .find(|message| message.id == message_id)
+        {
+            message.image = Some(image);
+            self.refresh_ui = !self.refresh_ui;
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum ChatImage {
+    Loading,
+    Loaded(ImageBuf),
+    TooLarge,
+    Failed,
+}
+
+impl ChatImage {
+    pub fn from_rgba_pixels(pixels: Vec<u8>, width: u32, height: u32) -> Self {
+        ChatImage::Loaded(ImageBuf::from_raw(
+            pixels,
+            ImageFormat::RgbaSeparate,
+            width as usize,
+            height as usize,
+        ))
     }
 }
 
@@ -133,32 +616,53 @@ pub enum MessageType {
     InfoMessage,
 }
 
+#[derive(Clone)]
+pub struct RoomInvite {
+    pub room_name: String,
+    expires_at: DateTime<Local>,
+    is_closed: bool, // accepted, declined or expired
+}
+
+impl RoomInvite {
+    pub fn is_expired(&self, now: DateTime<Local>) -> bool {
+        now >= self.expires_at
+    }
+    /// Returns 'true' if the "Accept" and "Decline" buttons should be shown.
+    pub fn is_active(&self, now: DateTime<Local>) -> bool {
+        !self.is_closed && !self.is_expired(now)
+    }
+}
+
+pub struct PinnedMessage {
+    pub server_message_id: u64,
+    pub author: String,
+    pub text: String,
+}
+
 #[derive(Clone, Data)]
 pub struct ChatMessage {
+    pub id: u64,
+    pub server_message_id: Option<u64>, // same on all clients, 'None' for system and info messages
     pub message: String,
     author: String,
     pub time: String,
     message_type: MessageType,
     pub was_copied: bool,
+    pub group_header: bool, // 'false' if the message continues the group of the previous message
+    #[data(ignore)]
+    received_at: DateTime<Local>,
+    #[data(ignore)]
+    pub image: Option<ChatImage>, // inline preview of the image URL in the message
+    #[data(ignore)]
+    pub reactions: HashMap<String, HashSet<String>>, // emoji - usernames
+    #[data(ignore)]
+    pub room_invite: Option<RoomInvite>, // 'Some' for room invites (info messages)
+    #[data(ignore)]
+    pub mentions: Vec<(usize, usize)>, // byte ranges of "@username" in 'message'
 }
 
 impl ChatMessage {
-    pub fn current_time() -> String {
-        let now = Local::now();
-        let mut hour: String = now.hour().to_string();
-        let mut minute: String = now.minute().to_string();
-
-        if hour.len() == 1 {
-            hour = String::from("0") + &hour;
-        }
-
-        if minute.len() == 1 {
-            minute = String::from("0") + &minute;
-        }
-
-        format!("{}:{}", hour, minute)
-    }
-    pub fn new(message: String, author: String, message_type: MessageType) -> Self {
+    pub fn new(id: u64, message: String, author: String, message_type: MessageType) -> Self {
         let now = Local::now();
         let mut hour: String = now.hour().to_string();
         let mut minute: String = now.minute().to_string();
@@ -172,13 +676,54 @@ impl ChatMessage {
         }
 
         ChatMessage {
+            id,
+            server_message_id: None,
             message,
             author,
             time: format!("{}:{}", hour, minute),
             message_type,
             was_copied: false,
+            group_header: true,
+            received_at: now,
+            image: None,
+            reactions: HashMap::new(),
+            room_invite: None,
+            mentions: Vec::new(),
         }
     }
+    pub fn get_date(&self) -> NaiveDate {
+        self.received_at.naive_local().date()
+    }
+    pub fn toggle_reaction(&mut self, username: &str, emoji: &str) {
+        let usernames = self
+            .reactions
+            .entry(emoji.to_string())
+            .or_insert_with(HashSet::new);
+
+        if !usernames.remove(username) {
+            usernames.insert(username.to_string());
+        }
+
+        if usernames.is_empty() {
+            self.reactions.remove(emoji);
+        }
+    }
+    pub fn get_reaction_count(&self, emoji: &str) -> usize {
+        self.reactions
+            .get(emoji)
+            .map(|usernames| usernames.len())
+            .unwrap_or(0)
+    }
+    /// Whether this message should be shown in the same group as 'previous'
+    /// (system and info messages always break the group).
+    pub fn continues_group_of(&self, previous: &ChatMessage) -> bool {
+        self.message_type == MessageType::UserMessage
+            && previous.message_type == MessageType::UserMessage
+            && self.author == previous.author
+            && self.get_date() == previous.get_date()
+            && (self.received_at - previous.received_at).num_seconds()
+                <= MESSAGE_GROUP_TIME_WINDOW_SEC
+    }
     pub fn get_ui(&self, data: &ApplicationState) -> impl Widget<ApplicationState> {
         let mut _author: &str = &self.author;
 
@@ -201,38 +746,50 @@ impl ChatMessage {
         let mut message_column: Flex<ApplicationState> =
             Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
 
-        message_column.add_child(
-            Flex::row()
-                .with_child(
-                    Label::new(_author)
-                        .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE)
-                        .with_text_color(druid::theme::BUTTON_DARK),
-                )
-                .with_child(
-                    Label::new(format!("  {}", self.time.clone()))
-                        .with_text_size(MESSAGE_TEXT_SIZE)
-                        .with_text_color(Color::GRAY),
-                ),
-        );
+        if self.group_header {
+            message_column.add_child(
+                Flex::row()
+                    .with_child(
+                        Label::new(_author)
+                            .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE)
+                            .with_text_color(druid::theme::BUTTON_DARK),
+                    )
+                    .with_child(
+                        Label::new(format!("  {}", self.time.clone()))
+                            .with_text_size(MESSAGE_TEXT_SIZE)
+                            .with_text_color(Color::GRAY),
+                    ),
+            );
+        } else if self.was_copied {
+            // no header to show the 'copied' notice in
+            message_column.add_child(
+                Label::new(self.time.clone())
+                    .with_text_size(MESSAGE_TEXT_SIZE)
+                    .with_text_color(Color::GRAY),
+            );
+        }
 
         match self.message_type {
             MessageType::UserMessage => {
-                message_column.add_child(EnvScope::new(
-                    |env, _data| {
-                        env.set(druid::theme::BUTTON_DARK, Color::rgba8(0, 0, 0, 0));
-                        env.set(druid::theme::BUTTON_LIGHT, Color::rgba8(0, 0, 0, 0));
-                    },
-                    Button::from_label(
-                        Label::new(self.message.clone())
-                            .with_line_break_mode(LineBreaking::WordWrap)
-                            .with_text_size(MESSAGE_TEXT_SIZE),
-                    )
-                    .controller(CustomDataButtonController::new(
-                        CustomButtonData::MessageData {
-                            message: self.message.clone(),
-                        },
-                    )),
+                // click without selection copies the whole message
+                message_column.add_child(Padding::new(
+                    (0.0, 5.0),
+                    SelectableChatLabel::new(self.message.clone())
+                        .with_highlighted_ranges(&self.mentions)
+                        .with_click_command(CUSTOM_DATA_BUTTON_CLICKED.with(
+                            CustomButtonData::MessageData {
+                                message: self.message.clone(),
+                            },
+                        )),
                 ));
+                if let Some(image) = &self.image {
+                    message_column.add_child(ChatMessage::get_image_ui(image, data));
+                }
+                if let Some(server_message_id) = self.server_message_id {
+                    if !self.reactions.is_empty() {
+                        message_column.add_child(self.get_reactions_ui(server_message_id));
+                    }
+                }
             }
             MessageType::SystemMessage => {
                 message_column.add_child(EnvScope::new(
@@ -271,9 +828,766 @@ impl ChatMessage {
                         },
                     )),
                 ));
+                if let Some(invite) = &self.room_invite {
+                    if invite.is_active(Local::now()) {
+                        message_column.add_child(self.get_room_invite_ui(data));
+                    }
+                }
+            }
+        }
+
+        let message_widget: Box<dyn Widget<ApplicationState>> = match self.server_message_id {
+            Some(server_message_id) => {
+                let can_pin = data
+                    .main_layout
+                    .connected_list
+                    .is_user_admin(&data.main_layout.current_user_name);
+                Box::new(ReactionBar::new(message_column, server_message_id, can_pin))
+            }
+            None => Box::new(message_column),
+        };
+
+        let message_widget = if self.group_header {
+            Padding::new(5.0, message_widget)
+        } else {
+            // indent and stick to the previous message of the group
+            Padding::new((20.0, 0.0, 5.0, 5.0), message_widget)
+        };
+
+        if data.main_layout.chat_list.highlighted_message_id == Some(self.id) {
+            message_widget
+                .background(druid::theme::SELECTED_TEXT_BACKGROUND_COLOR)
+                .rounded(druid::theme::BUTTON_BORDER_RADIUS)
+                .controller(SearchHighlightController::new())
+                .boxed()
+        } else {
+            message_widget.boxed()
+        }
+    }
+    fn get_room_invite_ui(&self, data: &ApplicationState) -> impl Widget<ApplicationState> {
+        let message_id = self.id;
+
+        Flex::row()
+            .with_child(
+                Button::from_label(
+                    Label::new(
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_ROOM_INVITE_ACCEPT_TEXT)
+                            .unwrap()
+                            .clone(),
+                    )
+                    .with_text_size(MESSAGE_TEXT_SIZE),
+                )
+                .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                    MainLayout::accept_room_invite(data, message_id);
+                }),
+            )
+            .with_spacer(5.0)
+            .with_child(
+                Button::from_label(
+                    Label::new(
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_ROOM_INVITE_DECLINE_TEXT)
+                            .unwrap()
+                            .clone(),
+                    )
+                    .with_text_size(MESSAGE_TEXT_SIZE),
+                )
+                .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                    data.main_layout.chat_list.close_room_invite(message_id);
+                }),
+            )
+    }
+    /// Reaction counts as small buttons (in the order of the reaction bar).
+    fn get_reactions_ui(&self, server_message_id: u64) -> impl Widget<ApplicationState> {
+        let mut row: Flex<ApplicationState> = Flex::row();
+
+        for emoji in REACTION_EMOJIS.iter() {
+            let count = self.get_reaction_count(emoji);
+            if count == 0 {
+                continue;
+            }
+
+            row.add_child(ReactionBar::build_reaction_button(
+                server_message_id,
+                emoji,
+                format!("{} {}", emoji, count),
+            ));
+            row.add_spacer(2.0);
+        }
+
+        row
+    }
+    fn get_image_ui(
+        image: &ChatImage,
+        data: &ApplicationState,
+    ) -> Box<dyn Widget<ApplicationState>> {
+        let text_key = match image {
+            ChatImage::Loaded(image_buf) => {
+                return Box::new(
+                    Image::new(image_buf.clone())
+                        .fill_mode(FillStrat::Contain)
+                        .fix_size(image_buf.width() as f64, image_buf.height() as f64),
+                );
+            }
+            ChatImage::Loading => LOCALE_MAIN_LAYOUT_IMAGE_LOADING_TEXT,
+            ChatImage::TooLarge => LOCALE_MAIN_LAYOUT_IMAGE_TOO_LARGE_TEXT,
+            ChatImage::Failed => LOCALE_MAIN_LAYOUT_IMAGE_FAILED_TEXT,
+        };
+
+        Box::new(
+            Label::new(data.localization.get(text_key).unwrap().clone())
+                .with_text_size(MESSAGE_TEXT_SIZE)
+                .with_text_color(Color::GRAY),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, min: u32, sec: u32) -> DateTime<Local> {
+        let time = NaiveDate::from_ymd_opt(2026, 10, 16)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap();
+        Local.from_local_datetime(&time).unwrap()
+    }
+
+    fn message_at(
+        author: &str,
+        message_type: MessageType,
+        received_at: DateTime<Local>,
+    ) -> ChatMessage {
+        let mut message =
+            ChatMessage::new(0, String::from("text"), String::from(author), message_type);
+        message.received_at = received_at;
+        message
+    }
+
+    fn user_message_at(author: &str, received_at: DateTime<Local>) -> ChatMessage {
+        message_at(author, MessageType::UserMessage, received_at)
+    }
+
+    #[test]
+    fn messages_of_same_author_are_grouped() {
+        let first = user_message_at("user1", time(12, 0, 0));
+        let second = user_message_at("user1", time(12, 0, 10));
+
+        assert!(second.continues_group_of(&first));
+    }
+
+    #[test]
+    fn message_of_other_author_breaks_group() {
+        let first = user_message_at("user1", time(12, 0, 0));
+        let second = user_message_at("user2", time(12, 0, 10));
+
+        assert!(!second.continues_group_of(&first));
+    }
+
+    #[test]
+    fn system_and_info_messages_break_group() {
+        let user_message = user_message_at("user1", time(12, 0, 0));
+
+        for message_type in [MessageType::SystemMessage, MessageType::InfoMessage].iter() {
+            let other = message_at("", *message_type, time(12, 0, 5));
+            let next_user_message = user_message_at("user1", time(12, 0, 10));
+
+            assert!(!other.continues_group_of(&user_message));
+            assert!(!next_user_message.continues_group_of(&other));
+        }
+    }
+
+    #[test]
+    fn group_ends_after_time_window() {
+        let first = user_message_at("user1", time(12, 0, 0));
+
+        let last_in_window = user_message_at(
+            "user1",
+            time(12, 0, 0) + chrono::Duration::seconds(MESSAGE_GROUP_TIME_WINDOW_SEC),
+        );
+        assert!(last_in_window.continues_group_of(&first));
+
+        let after_window = user_message_at(
+            "user1",
+            time(12, 0, 0) + chrono::Duration::seconds(MESSAGE_GROUP_TIME_WINDOW_SEC + 1),
+        );
+        assert!(!after_window.continues_group_of(&first));
+    }
+
+    #[test]
+    fn window_is_counted_from_previous_message() {
+        // each message is within the window of the previous one
+        let messages: Vec<ChatMessage> = (0..5)
+            .map(|i| user_message_at("user1", time(12, i, 0)))
+            .collect();
+
+        for pair in messages.windows(2) {
+            assert!(pair[1].continues_group_of(&pair[0]));
+        }
+    }
+
+    #[test]
+    fn new_day_breaks_group() {
+        let first = user_message_at("user1", time(23, 59, 50));
+        let second = user_message_at("user1", time(23, 59, 50) + chrono::Duration::seconds(20));
+
+        assert!(!second.continues_group_of(&first));
+    }
+
+    #[test]
+    fn trimmed_group_gets_header() {
+        let mut chat_list = ChatList::new();
+        {
+            let mut messages_guard = chat_list.messages.lock().unwrap();
+            for i in 0..3 {
+                let mut message = user_message_at("user1", time(12, 0, i));
+                message.group_header = i == 0;
+                messages_guard.push_back(message);
             }
         }
 
-        Padding::new(5.0, message_column)
+        chat_list.trim_to_limit(2);
+
+        let messages_guard = chat_list.messages.lock().unwrap();
+        let group_headers: Vec<bool> = messages_guard
+            .iter()
+            .map(|message| message.group_header)
+            .collect();
+        assert_eq!(group_headers, vec![true, false]);
+    }
+
+    fn chat_list_with_message(server_message_id: u64) -> ChatList {
+        let chat_list = ChatList::new();
+        let mut message = user_message_at("user1", time(12, 0, 0));
+        message.server_message_id = Some(server_message_id);
+        chat_list.messages.lock().unwrap().push_back(message);
+        chat_list
+    }
+
+    fn get_reaction_count(chat_list: &ChatList, emoji: &str) -> usize {
+        chat_list
+            .messages
+            .lock()
+            .unwrap()
+            .front()
+            .unwrap()
+            .get_reaction_count(emoji)
+    }
+
+    #[test]
+    fn reactions_are_counted_per_emoji() {
+        let mut message = user_message_at("user1", time(12, 0, 0));
+
+        message.toggle_reaction("user1", "👍");
+        message.toggle_reaction("user2", "👍");
+        message.toggle_reaction("user3", "👍");
+        message.toggle_reaction("user2", "❤️");
+
+        assert_eq!(message.get_reaction_count("👍"), 3);
+        assert_eq!(message.get_reaction_count("❤️"), 1);
+        assert_eq!(message.get_reaction_count("👎"), 0);
+    }
+
+    #[test]
+    fn user_is_counted_once_per_emoji() {
+        let mut message = user_message_at("user1", time(12, 0, 0));
+
+        // the second reaction with the same emoji removes the first one
+        message.toggle_reaction("user2", "👍");
+        message.toggle_reaction("user2", "👍");
+        assert_eq!(message.get_reaction_count("👍"), 0);
+        assert!(message.reactions.is_empty());
+
+        message.toggle_reaction("user2", "👍");
+        message.toggle_reaction("user3", "👍");
+        message.toggle_reaction("user2", "👍");
+        assert_eq!(message.get_reaction_count("👍"), 1);
+        assert!(message.reactions["👍"].contains("user3"));
+    }
+
+    #[test]
+    fn reaction_is_applied_by_server_message_id() {
+        let mut chat_list = chat_list_with_message(42);
+
+        assert!(chat_list.toggle_reaction(42, "user2", "👍"));
+        assert!(chat_list.toggle_reaction(42, "user3", "👍"));
+        assert_eq!(get_reaction_count(&chat_list, "👍"), 2);
+
+        // removed or unknown message
+        assert!(!chat_list.toggle_reaction(43, "user2", "👍"));
+        assert_eq!(get_reaction_count(&chat_list, "👍"), 2);
+    }
+
+    fn search_message(message_id: u64, message: &str) -> SearchMessageResult {
+        SearchMessageResult {
+            message_id,
+            author: String::from("user1"),
+            message: String::from(message),
+        }
+    }
+
+    fn search_ids(messages: &[SearchMessageResult], query: &str) -> Vec<u64> {
+        ChatList::search(messages.to_vec(), query)
+            .iter()
+            .map(|message| message.message_id)
+            .collect()
+    }
+
+    #[test]
+    fn search_ranks_word_prefix_matches_first() {
+        let messages = vec![
+            search_message(4, "the address is wrong"),
+            search_message(3, "Dressing room"),
+            search_message(2, "no match"),
+            search_message(1, "new dress"),
+        ];
+
+        // both groups keep the original (newest first) order
+        assert_eq!(search_ids(&messages, "dress"), vec![3, 1, 4]);
+    }
+
+    #[test]
+    fn search_is_case_insensitive() {
+        let messages = vec![
+            search_message(2, "ПРИВЕТ всем"),
+            search_message(1, "Hello World"),
+        ];
+
+        assert_eq!(search_ids(&messages, "привет"), vec![2]);
+        assert_eq!(search_ids(&messages, "WORLD"), vec![1]);
+        assert!(search_ids(&messages, "").is_empty());
+    }
+
+    #[test]
+    fn scroll_position_is_restored_after_rebuild() {
+        let mut chat_list = ChatList::new();
+        assert_eq!(chat_list.restore_scroll_position(), Point::ORIGIN);
+
+        chat_list.save_scroll_position(Point::new(0.0, 250.5));
+        // the chat is rebuilt (for example, after returning from the settings)
+        chat_list.update_date_separators();
+
+        assert_eq!(chat_list.restore_scroll_position(), Point::new(0.0, 250.5));
+
+        chat_list.save_scroll_position(Point::new(0.0, 10.0));
+        assert_eq!(chat_list.restore_scroll_position(), Point::new(0.0, 10.0));
+    }
+
+    #[test]
+    fn scroll_position_is_kept_with_new_messages() {
+        let mut chat_list = ChatList::new();
+        chat_list.save_scroll_position(Point::new(0.0, 100.0));
+
+        chat_list.add_info_message(String::from("user1 connected."));
+
+        assert_eq!(chat_list.restore_scroll_position(), Point::new(0.0, 100.0));
+    }
+
+    #[test]
+    fn cleared_chat_is_scrolled_to_top() {
+        let mut chat_list = ChatList::new();
+        chat_list.add_info_message(String::from("user1 connected."));
+        chat_list.save_scroll_position(Point::new(0.0, 100.0));
+
+        chat_list.clear_text_chat();
+
+        assert_eq!(chat_list.restore_scroll_position(), Point::ORIGIN);
+    }
+
+    fn date_separator_localization() -> HashMap<String, String> {
+        let mut localization = HashMap::new();
+        for (key, value) in [
+            (LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_TODAY_TEXT, "Today"),
+            (
+                LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_YESTERDAY_TEXT,
+                "Yesterday",
+            ),
+            (
+                LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_FORMAT_TEXT,
+                "{weekday}, {month} {day}",
+            ),
+            (
+                LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_WEDNESDAY_TEXT,
+                "Wednesday",
+            ),
+            (LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_THURSDAY_TEXT, "Thursday"),
+            (LOCALE_MAIN_LAYOUT_DATE_SEPARATOR_OCTOBER_TEXT, "October"),
+        ]
+        .iter()
+        {
+            localization.insert(String::from(*key), String::from(*value));
+        }
+        localization
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    fn chat_list_with_messages_at(times: &[DateTime<Local>]) -> ChatList {
+        let chat_list = ChatList::new();
+        for received_at in times.iter() {
+            chat_list
+                .messages
+                .lock()
+                .unwrap()
+                .push_back(user_message_at("user1", *received_at));
+        }
+        chat_list
+    }
+
+    #[test]
+    fn relative_date_labels() {
+        let localization = date_separator_localization();
+
+        for (day, label) in [
+            (16, "Today"),
+            (15, "Yesterday"),
+            (14, "Wednesday, October 14"),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                ChatList::get_relative_date_label(date(*day), date(16), &localization),
+                *label
+            );
+        }
+    }
+
+    #[test]
+    fn separators_are_shown_once_per_day() {
+        let localization = date_separator_localization();
+        let chat_list = chat_list_with_messages_at(&[
+            time(23, 59, 50) - chrono::Duration::days(1),
+            time(0, 0, 0) - chrono::Duration::seconds(1),
+            time(0, 0, 0),
+            time(12, 0, 0),
+        ]);
+
+        assert_eq!(
+            chat_list.get_date_separator_labels(date(16), &localization),
+            vec![
+                Some(String::from("Yesterday")),
+                None,
+                Some(String::from("Today")),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn separators_change_at_midnight() {
+        let localization = date_separator_localization();
+        let chat_list = chat_list_with_messages_at(&[
+            time(0, 0, 0) - chrono::Duration::seconds(1),
+            time(0, 0, 0),
+        ]);
+
+        // the midnight timer fires
+        let before_midnight = time(23, 59, 59).naive_local();
+        let after_midnight = before_midnight
+            + chrono::Duration::from_std(ChatList::get_time_until_next_day(before_midnight))
+                .unwrap();
+        assert_eq!(after_midnight.date(), date(17));
+
+        assert_eq!(
+            chat_list.get_date_separator_labels(before_midnight.date(), &localization),
+            vec![Some(String::from("Yesterday")), Some(String::from("Today"))]
+        );
+        assert_eq!(
+            chat_list.get_date_separator_labels(after_midnight.date(), &localization),
+            vec![
+                Some(String::from("Thursday, October 15")),
+                Some(String::from("Yesterday"))
+            ]
+        );
+    }
+
+    #[test]
+    fn time_until_next_day() {
+        for (now, seconds) in [
+            (time(0, 0, 0), 24 * 60 * 60 + 1),
+            (time(12, 0, 0), 12 * 60 * 60 + 1),
+            (time(23, 59, 59), 2),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                ChatList::get_time_until_next_day(now.naive_local()),
+                Duration::from_secs(*seconds)
+            );
+        }
+    }
+
+    fn get_pinned_ids(chat_list: &ChatList) -> Vec<u64> {
+        chat_list
+            .pinned_messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|pinned| pinned.server_message_id)
+            .collect()
+    }
+
+    fn pin(chat_list: &mut ChatList, server_message_id: u64) {
+        chat_list.pin_message(
+            server_message_id,
+            String::from("admin"),
+            format!("message {}", server_message_id),
+        );
+    }
+
+    #[test]
+    fn pinned_messages_are_capped_at_3() {
+        let mut chat_list = ChatList::new();
+
+        for server_message_id in 1..=5 {
+            pin(&mut chat_list, server_message_id);
+            assert!(get_pinned_ids(&chat_list).len() <= MAX_PINNED_MESSAGES);
+        }
+
+        // the newest is shown first, the oldest ones are removed
+        assert_eq!(get_pinned_ids(&chat_list), vec![5, 4, 3]);
+    }
+
+    #[test]
+    fn pinning_again_moves_message_to_top() {
+        let mut chat_list = ChatList::new();
+        for server_message_id in 1..=3 {
+            pin(&mut chat_list, server_message_id);
+        }
+
+        pin(&mut chat_list, 1);
+
+        assert_eq!(get_pinned_ids(&chat_list), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn cleared_chat_has_no_pinned_messages() {
+        let mut chat_list = ChatList::new();
+        pin(&mut chat_list, 1);
+
+        chat_list.clear_text_chat();
+
+        assert!(get_pinned_ids(&chat_list).is_empty());
+    }
+
+    fn get_invite(chat_list: &ChatList, message_id: u64) -> RoomInvite {
+        chat_list
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|message| message.id == message_id)
+            .unwrap()
+            .room_invite
+            .clone()
+            .unwrap()
+    }
+
+    fn expire_invite(chat_list: &mut ChatList, message_id: u64) {
+        let mut messages_guard = chat_list.messages.lock().unwrap();
+        let message = messages_guard
+            .iter_mut()
+            .find(|message| message.id == message_id)
+            .unwrap();
+        message.room_invite.as_mut().unwrap().expires_at = Local::now();
+    }
+
+    #[test]
+    fn room_invite_expires_after_expiry_time() {
+        let mut chat_list = ChatList::new();
+        let before = Local::now();
+        let message_id = chat_list.add_room_invite(String::from("invite"), String::from("Gaming"));
+        let after = Local::now();
+
+        let invite = get_invite(&chat_list, message_id);
+        let expiry = chrono::Duration::seconds(ROOM_INVITE_EXPIRY_SEC as i64);
+        let almost = chrono::Duration::milliseconds(ROOM_INVITE_EXPIRY_SEC as i64 * 1000 - 1);
+
+        assert!(invite.is_active(after));
+        assert!(invite.is_active(before + almost));
+        assert!(invite.is_expired(after + expiry));
+        assert!(!invite.is_active(after + expiry));
+    }
+
+    #[test]
+    fn room_invite_can_be_accepted_once() {
+        let mut chat_list = ChatList::new();
+        let message_id = chat_list.add_room_invite(String::from("invite"), String::from("Gaming"));
+
+        assert_eq!(
+            chat_list.close_room_invite(message_id),
+            Some(String::from("Gaming"))
+        );
+        assert!(!get_invite(&chat_list, message_id).is_active(Local::now()));
+
+        assert_eq!(chat_list.close_room_invite(message_id), None);
+    }
+
+    #[test]
+    fn declined_room_invite_cannot_be_accepted() {
+        let mut chat_list = ChatList::new();
+        let message_id = chat_list.add_room_invite(String::from("invite"), String::from("Gaming"));
+
+        // "Decline" ignores the room
+        chat_list.close_room_invite(message_id);
+
+        assert_eq!(chat_list.close_room_invite(message_id), None);
+    }
+
+    #[test]
+    fn expired_room_invite_cannot_be_accepted() {
+        let mut chat_list = ChatList::new();
+        let message_id = chat_list.add_room_invite(String::from("invite"), String::from("Gaming"));
+
+        expire_invite(&mut chat_list, message_id);
+
+        assert_eq!(chat_list.close_room_invite(message_id), None);
+        assert!(!get_invite(&chat_list, message_id).is_active(Local::now()));
+    }
+
+    #[test]
+    fn removed_room_invite_cannot_be_accepted() {
+        let mut chat_list = ChatList::new();
+        let message_id = chat_list.add_room_invite(String::from("invite"), String::from("Gaming"));
+
+        chat_list.clear_text_chat();
+
+        assert_eq!(chat_list.close_room_invite(message_id), None);
+    }
+
+    fn chat_list_with_system_messages(count: usize) -> ChatList {
+        let mut chat_list = ChatList::new();
+        for i in 0..count {
+            chat_list.add_system_message(format!("message {}", i));
+        }
+        chat_list
+    }
+
+    fn get_message_texts(chat_list: &ChatList) -> Vec<String> {
+        chat_list
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| message.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn messages_at_limit_are_not_trimmed() {
+        let mut chat_list = chat_list_with_system_messages(3);
+
+        chat_list.trim_to_limit(3);
+
+        assert_eq!(get_message_texts(&chat_list).len(), 3);
+        assert_eq!(chat_list.trimmed_count, 0);
+        assert_eq!(chat_list.get_trimmed_messages_text(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn oldest_message_over_limit_is_trimmed() {
+        let mut chat_list = chat_list_with_system_messages(4);
+
+        chat_list.trim_to_limit(3);
+
+        assert_eq!(
+            get_message_texts(&chat_list),
+            vec!["message 1", "message 2", "message 3"]
+        );
+        assert_eq!(chat_list.trimmed_count, 1);
+    }
+
+    #[test]
+    fn zero_limit_removes_all_messages() {
+        let mut chat_list = chat_list_with_system_messages(2);
+
+        chat_list.trim_to_limit(0);
+
+        assert!(get_message_texts(&chat_list).is_empty());
+        assert_eq!(chat_list.trimmed_count, 2);
+    }
+
+    #[test]
+    fn new_messages_are_trimmed_to_max_messages() {
+        let mut chat_list = ChatList::new();
+        chat_list.set_max_messages(3);
+
+        for i in 0..5 {
+            chat_list.add_system_message(format!("message {}", i));
+            assert!(get_message_texts(&chat_list).len() <= 3);
+        }
+        chat_list.add_room_invite(String::from("invite"), String::from("Gaming"));
+
+        assert_eq!(
+            get_message_texts(&chat_list),
+            vec!["message 3", "message 4", "invite"]
+        );
+        assert_eq!(chat_list.trimmed_count, 3);
+    }
+
+    #[test]
+    fn lower_max_messages_trims_existing_messages() {
+        let mut chat_list = chat_list_with_system_messages(5);
+
+        chat_list.set_max_messages(2);
+
+        assert_eq!(
+            get_message_texts(&chat_list),
+            vec!["message 3", "message 4"]
+        );
+
+        // a higher limit does not bring them back
+        chat_list.set_max_messages(10);
+        assert_eq!(get_message_texts(&chat_list).len(), 2);
+        assert_eq!(chat_list.trimmed_count, 3);
+    }
+
+    #[test]
+    fn trimmed_count_is_shown_until_chat_is_cleared() {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_OLDER_MESSAGES_HIDDEN_TEXT),
+            String::from("older messages hidden"),
+        );
+        let mut chat_list = chat_list_with_system_messages(5);
+        chat_list.trim_to_limit(3);
+
+        assert_eq!(
+            chat_list.get_trimmed_messages_text(&localization),
+            Some(String::from("2 older messages hidden"))
+        );
+
+        chat_list.clear_text_chat();
+        assert_eq!(chat_list.trimmed_count, 0);
+        assert_eq!(chat_list.get_trimmed_messages_text(&localization), None);
+    }
+
+    #[test]
+    fn date_separator_is_kept_while_its_messages_are_left() {
+        let localization = date_separator_localization();
+        let yesterday = time(12, 0, 0) - chrono::Duration::days(1);
+        let mut chat_list =
+            chat_list_with_messages_at(&[yesterday, yesterday, time(12, 0, 0), time(12, 0, 1)]);
+
+        chat_list.trim_to_limit(3);
+        assert_eq!(
+            chat_list.get_date_separator_labels(date(16), &localization),
+            vec![
+                Some(String::from("Yesterday")),
+                Some(String::from("Today")),
+                None
+            ]
+        );
+
+        // the last message of yesterday is removed
+        chat_list.trim_to_limit(2);
+        assert_eq!(
+            chat_list.get_date_separator_labels(date(16), &localization),
+            vec![Some(String::from("Today")), None]
+        );
     }
 }
@@ -0,0 +1,63 @@
+// External.
+use druid::widget::prelude::*;
+use druid::widget::{Button, Flex, Label};
+
+// Custom.
+use crate::global_params::*;
+use crate::misc::locale_keys::*;
+use crate::ApplicationState;
+
+// Inline confirmation: the question followed by "Yes" and "No" buttons
+// (druid has no modal dialogs, so show it in place of the button that asks for confirmation).
+pub struct ConfirmDialog;
+
+impl ConfirmDialog {
+    pub fn new(
+        question_locale_key: &'static str,
+        on_confirm: impl Fn(&mut EventCtx, &mut ApplicationState, &Env) + 'static,
+        on_cancel: impl Fn(&mut EventCtx, &mut ApplicationState, &Env) + 'static,
+    ) -> impl Widget<ApplicationState> {
+        ConfirmDialog::with_answers(
+            question_locale_key,
+            LOCALE_CONFIRM_DIALOG_YES_TEXT,
+            LOCALE_CONFIRM_DIALOG_NO_TEXT,
+            on_confirm,
+            on_cancel,
+        )
+    }
+    /// Same as 'new' but with custom texts instead of "Yes" and "No".
+    pub fn with_answers(
+        question_locale_key: &'static str,
+        confirm_locale_key: &'static str,
+        cancel_locale_key: &'static str,
+        on_confirm: impl Fn(&mut EventCtx, &mut ApplicationState, &Env) + 'static,
+        on_cancel: impl Fn(&mut EventCtx, &mut ApplicationState, &Env) + 'static,
+    ) -> impl Widget<ApplicationState> {
+        Flex::row()
+            .with_child(
+                Label::new(move |data: &ApplicationState, _env: &Env| {
+                    format!("{}  ", data.localization.get(question_locale_key).unwrap())
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Button::from_label(
+                    Label::new(move |data: &ApplicationState, _env: &Env| {
+                        data.localization.get(confirm_locale_key).unwrap().clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .on_click(on_confirm),
+            )
+            .with_default_spacer()
+            .with_child(
+                Button::from_label(
+                    Label::new(move |data: &ApplicationState, _env: &Env| {
+                        data.localization.get(cancel_locale_key).unwrap().clone()
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .on_click(on_cancel),
+            )
+    }
+}
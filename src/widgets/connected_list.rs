@@ -2,30 +2,48 @@
 use chrono::prelude::*;
 use druid::widget::prelude::*;
 use druid::widget::{
-    Button, CrossAxisAlignment, Either, EnvScope, Flex, Label, Scroll, SizedBox, ViewSwitcher,
+    Button, CrossAxisAlignment, EnvScope, Flex, Label, Scroll, SizedBox, ViewSwitcher,
 };
-use druid::{Color, Data, Lens, TextAlignment, WidgetExt};
+use druid::{Color, Data, Lens, LensExt, TextAlignment, WidgetExt};
 use sfml::audio::{Sound, SoundBuffer, SoundStatus};
 
 // Std.
-use std::collections::LinkedList;
+use std::collections::{HashMap, LinkedList};
 use std::rc::Rc;
 use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
 // Custom.
+use super::room_info_popup::RoomInfoSnapshot;
+use super::slide_transition::SlideTransition;
+use super::talking_indicator::TalkingIndicator;
+use super::tooltip::Tooltip;
 use super::user_info::UserInfo;
 use crate::global_params::*;
-use crate::misc::{custom_data_button_controller::*, locale_keys::*};
+use crate::layouts::main_layout::MainLayout;
+use crate::misc::{
+    accessibility_controller::*, connected_list_controller::*, custom_data_button_controller::*,
+    locale_keys::*, new_badge_controller::*, user_drag_controller::*,
+};
 use crate::ApplicationState;
 
+const USER_STATUS_ICON: &str = "●";
+const NEW_USER_BADGE_COLOR: Color = Color::rgb8(60, 140, 220);
+const VOICE_ACTIVE_ROOM_COLOR: Color = Color::rgb8(40, 130, 40); // room button while somebody talks there
+
 #[derive(Clone, Data, Lens)]
 pub struct ConnectedList {
     pub refresh_ui: bool, // because interior mutability (on rooms) doesn't work in druid's data
     pub rooms: Rc<Mutex<LinkedList<RoomItem>>>,
     pub is_showing_user_info: bool,
+    pub animation_progress: f64, // 0.0 - list is shown, 1.0 - user info is shown
     pub user_info_layout: UserInfo,
+    pub dragged_username: String,            // empty if not dragging
+    pub dragged_from_room: String,           // empty if not dragging
+    pub drop_target_room: String,            // room under the cursor while dragging
+    pub is_compact: bool,                    // smaller entries without the ping
+    pub room_info: Option<RoomInfoSnapshot>, // shown in the room info popup if 'Some'
 }
 
 impl ConnectedList {
@@ -34,7 +52,13 @@ impl ConnectedList {
             refresh_ui: false,
             rooms: Rc::new(Mutex::new(LinkedList::new())),
             is_showing_user_info: false,
+            animation_progress: 0.0,
             user_info_layout: UserInfo::from(UserItemData::empty()),
+            dragged_username: String::new(),
+            dragged_from_room: String::new(),
+            drop_target_room: String::new(),
+            is_compact: false,
+            room_info: None,
         }
     }
     pub fn build_ui() -> impl Widget<ApplicationState> {
@@ -42,12 +66,15 @@ impl ConnectedList {
             |data: &ApplicationState, _env| data.main_layout.connected_list.refresh_ui,
             |selector, _data, _env| match selector {
                 _ => Box::new(
-                    Either::new(
-                        |data: &ApplicationState, _env| {
+                    SlideTransition::new(
+                        ConnectedList::build_list_ui(),
+                        UserInfo::build_ui().expand(),
+                        |data: &ApplicationState| {
                             data.main_layout.connected_list.is_showing_user_info
                         },
-                        UserInfo::build_ui().expand(),
-                        ConnectedList::build_list_ui(),
+                        ApplicationState::main_layout
+                            .then(MainLayout::connected_list)
+                            .then(ConnectedList::animation_progress),
                     )
                     .expand(),
                 ),
@@ -63,6 +90,7 @@ impl ConnectedList {
             },
         )))
         .vertical()
+        .controller(ConnectedListController)
     }
     fn get_rooms_ui(data: &ApplicationState) -> impl Widget<ApplicationState> {
         let mut column: Flex<ApplicationState> =
@@ -90,16 +118,111 @@ impl ConnectedList {
             }
         }
     }
+    pub fn open_room_info(&mut self, room_name: &str, current_user_room: &str) {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        if let Some(room) = rooms_guard
+            .iter()
+            .find(|room| room.room_data.name == room_name)
+        {
+            self.room_info = Some(RoomInfoSnapshot::capture(room, current_user_room));
+        }
+    }
+    /// Case-insensitive, sorted by name.
+    pub fn find_users_by_prefix(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let mut usernames: Vec<String> = self
+            .rooms
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|room| {
+                room.users
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|user| user.user_data.username.clone())
+                    .collect::<Vec<String>>()
+            })
+            .filter(|username| username.to_lowercase().starts_with(&prefix))
+            .collect();
+        usernames.sort();
+
+        usernames
+    }
     pub fn hide_user_info(&mut self) {
         self.is_showing_user_info = false;
 
         self.refresh_ui = !self.refresh_ui;
     }
+    /// Returns 'false' if the user was not found.
+    pub fn clear_new_badge(&mut self, username: &str) -> bool {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        for room in rooms_guard.iter() {
+            let mut users_guard = room.users.lock().unwrap();
+            let user = users_guard
+                .iter_mut()
+                .find(|user| user.user_data.username == username);
+            if let Some(user) = user {
+                if user.user_data.show_new_badge {
+                    user.user_data.show_new_badge = false;
+                    self.refresh_ui = !self.refresh_ui;
+                }
+                return true;
+            }
+        }
+
+        false
+    }
+    /// Returns 'None' if the user was not found.
+    pub fn is_new_badge_shown(&self, username: &str) -> Option<bool> {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        for room in rooms_guard.iter() {
+            let users_guard = room.users.lock().unwrap();
+            let user = users_guard
+                .iter()
+                .find(|user| user.user_data.username == username);
+            if let Some(user) = user {
+                return Some(user.user_data.show_new_badge);
+            }
+        }
+
+        None
+    }
+    pub fn close_room_info(&mut self) {
+        self.room_info = None;
+    }
     pub fn clear_all_users(&mut self) {
         self.rooms.lock().unwrap().clear();
+        self.room_info = None;
 
         self.refresh_ui = !self.refresh_ui;
     }
+    pub fn set_room_description(
+        &mut self,
+        room_name: &str,
+        description: String,
+    ) -> Result<(), String> {
+        let mut rooms_guard = self.rooms.lock().unwrap();
+
+        let room_entry = rooms_guard
+            .iter_mut()
+            .find(|room_info| room_info.room_data.name == room_name);
+        if room_entry.is_none() {
+            return Err(format!("An error occurred at UserList::set_room_description(), error: room with name '{}' not found at [{}, {}]", room_name, file!(), line!()));
+        }
+        room_entry.unwrap().room_data.description = description;
+
+        self.refresh_ui = !self.refresh_ui;
+
+        Ok(())
+    }
     pub fn get_room_count(&self) -> usize {
         self.rooms.lock().unwrap().len()
     }
@@ -159,6 +282,36 @@ impl ConnectedList {
 
         Err(()) // not found
     }
+    pub fn add_user_voice_session(
+        &mut self,
+        username: &str,
+        talking_ms: u64,
+        bytes_received: u64,
+    ) -> Result<(), ()> {
+        let mut rooms_guard = self.rooms.lock().unwrap();
+
+        for room in rooms_guard.iter_mut() {
+            let mut users_guard = room.users.lock().unwrap();
+            for user in users_guard.iter_mut() {
+                if user.user_data.username == username {
+                    user.user_data
+                        .voice_stats
+                        .add_session(talking_ms, bytes_received);
+
+                    if self.is_showing_user_info
+                        && self.user_info_layout.user_data.username == username
+                    {
+                        self.user_info_layout.update_data(user.user_data.clone());
+                    }
+
+                    self.refresh_ui = !self.refresh_ui;
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(()) // not found
+    }
     pub fn move_user(
         &mut self,
         username: &str,
@@ -255,6 +408,97 @@ impl ConnectedList {
             "An error occurred at UserList::remove_user(), error: can't find user with name '{}' at [{}, {}]",
             username, file!(), line!()))
     }
+    pub fn is_user_talking(&self, username: &str) -> bool {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        for room in rooms_guard.iter() {
+            let users_guard = room.users.lock().unwrap();
+
+            if let Some(user) = users_guard
+                .iter()
+                .find(|user| user.user_data.username == username)
+            {
+                return user.user_data.is_talking;
+            }
+        }
+
+        false
+    }
+    /// Usernames from all rooms, one per line.
+    pub fn get_usernames(&self) -> Vec<String> {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        let mut usernames: Vec<String> = Vec::new();
+        for room in rooms_guard.iter() {
+            let users_guard = room.users.lock().unwrap();
+            for user in users_guard.iter() {
+                usernames.push(user.user_data.username.clone());
+            }
+        }
+
+        usernames
+    }
+    pub fn get_all_usernames(&self) -> String {
+        self.get_usernames().join("\n")
+    }
+    pub fn end_user_drag(&mut self) {
+        self.dragged_username.clear();
+        self.dragged_from_room.clear();
+        self.drop_target_room.clear();
+    }
+    pub fn is_user_admin(&self, username: &str) -> bool {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        for room in rooms_guard.iter() {
+            let users_guard = room.users.lock().unwrap();
+            for user in users_guard.iter() {
+                if user.user_data.username == username {
+                    return user.user_data.is_admin;
+                }
+            }
+        }
+
+        false
+    }
+    pub fn set_user_status(&mut self, username: &str, status: UserStatus) -> Result<(), ()> {
+        let rooms_guard = self.rooms.lock().unwrap();
+
+        for room in rooms_guard.iter() {
+            let mut users_guard = room.users.lock().unwrap();
+            for user in users_guard.iter_mut() {
+                if user.user_data.username == username {
+                    user.user_data.status = status;
+                    self.refresh_ui = !self.refresh_ui;
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(())
+    }
+    /// Returns the name of the room the user is in.
+    pub fn get_user_room(&self, username: &str) -> Option<String> {
+        self.rooms
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|room| {
+                room.users
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .any(|user| user.user_data.username == username)
+            })
+            .map(|room| room.room_data.name.clone())
+    }
+    /// Text size of room and user entries (smaller in the compact mode).
+    pub fn get_entry_text_size(&self) -> f64 {
+        if self.is_compact {
+            MESSAGE_AUTHOR_TEXT_SIZE
+        } else {
+            TEXT_SIZE
+        }
+    }
     pub fn get_user_count(&self) -> usize {
         let rooms_guard = self.rooms.lock().unwrap();
 
@@ -266,6 +510,19 @@ impl ConnectedList {
 
         user_count
     }
+    pub fn get_room_user_counts(&self) -> Vec<(String, usize)> {
+        self.rooms
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|room| {
+                (
+                    room.room_data.name.clone(),
+                    room.users.lock().unwrap().len(),
+                )
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Data)]
@@ -277,7 +534,10 @@ pub struct RoomItem {
 impl RoomItem {
     pub fn new(room_name: String) -> Self {
         RoomItem {
-            room_data: RoomItemData { name: room_name },
+            room_data: RoomItemData {
+                name: room_name,
+                description: String::new(),
+            },
             users: Rc::new(Mutex::new(LinkedList::new())),
         }
     }
@@ -293,25 +553,92 @@ impl RoomItem {
             .unwrap()
             .push_back(UserItem::new_from_data(user_data))
     }
+    /// Returns 'true' if at least one user in this room is talking.
+    pub fn has_active_voice(&self) -> bool {
+        self.users
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|user| user.user_data.is_talking)
+    }
+    pub fn get_talking_user_count(&self) -> usize {
+        self.users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|user| user.user_data.is_talking)
+            .count()
+    }
+    /// Returns the room name, "Gaming (2 talking)" if somebody is talking.
+    pub fn get_label_text(&self, localization: &HashMap<String, String>) -> String {
+        if !self.has_active_voice() {
+            return self.room_data.name.clone();
+        }
+
+        format!(
+            "{} ({} {})",
+            self.room_data.name,
+            self.get_talking_user_count(),
+            localization
+                .get(LOCALE_MAIN_LAYOUT_ROOM_TALKING_TEXT)
+                .unwrap()
+        )
+    }
     pub fn get_ui(&self, data: &ApplicationState) -> impl Widget<ApplicationState> {
         let mut column: Flex<ApplicationState> =
             Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
 
         // add room name first
-        column.add_child(
-            Button::from_label(Label::new(self.room_data.name.clone()).with_text_size(TEXT_SIZE))
-                .controller(CustomDataButtonController::new(
-                    CustomButtonData::ConnectedListData {
+        let room_name = self.room_data.name.clone();
+        let user_count = self.users.lock().unwrap().len();
+        let has_active_voice = self.has_active_voice();
+        let room_label_text = self.get_label_text(&data.localization);
+        let room_text_size = data.main_layout.connected_list.get_entry_text_size();
+        let room_button = EnvScope::new(
+            move |env, data: &ApplicationState| {
+                if has_active_voice {
+                    env.set(druid::theme::BUTTON_DARK, VOICE_ACTIVE_ROOM_COLOR);
+                    env.set(druid::theme::BUTTON_LIGHT, VOICE_ACTIVE_ROOM_COLOR);
+                }
+
+                // highlight while a user is dragged over this room
+                if data.main_layout.connected_list.drop_target_room == room_name {
+                    env.set(
+                        druid::theme::BORDER_DARK,
+                        env.get(druid::theme::PRIMARY_LIGHT),
+                    );
+                }
+            },
+            Button::from_label(Label::new(room_label_text).with_text_size(room_text_size))
+                .controller(
+                    CustomDataButtonController::new(CustomButtonData::ConnectedListData {
                         is_room: true,
                         button_name: self.room_data.name.clone(),
-                    },
-                )),
+                    })
+                    .with_aria_label(AccessibilityController::room_label(
+                        &self.room_data.name,
+                        user_count,
+                    )),
+                ),
+        )
+        .controller(RoomDropController::new(self.room_data.name.clone()));
+        let users_guard = self.users.lock().unwrap();
+        let mut tooltip_text = format!(
+            "{}: {} ({})",
+            data.localization
+                .get(LOCALE_MAIN_LAYOUT_ROOM_USER_COUNT_TEXT)
+                .unwrap(),
+            users_guard.len(),
+            self.room_data.name
         );
+        if !self.room_data.description.is_empty() {
+            tooltip_text = format!("{}\n{}", self.room_data.description, tooltip_text);
+        }
+        column.add_child(Tooltip::new(room_button, tooltip_text));
 
         // then add users
-        let users_guard = self.users.lock().unwrap();
         for user in users_guard.iter() {
-            column.add_child(user.get_ui(data));
+            column.add_child(user.get_ui(data, &self.room_data.name));
         }
 
         column
@@ -321,6 +648,17 @@ impl RoomItem {
 #[derive(Clone, Data)]
 pub struct RoomItemData {
     pub name: String,
+    pub description: String, // empty if the server didn't send one
+}
+
+// Widgets of the user entry, see 'UserItem::get_shown_parts'.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UserItemPart {
+    TalkingIndicator,
+    StatusIcon,
+    Name,
+    NewBadge,
+    Ping,
 }
 
 #[derive(Clone, Data)]
@@ -337,56 +675,131 @@ impl UserItem {
                 volume: 100.0,
                 is_talking: false,
                 connected_time_point: Rc::new(Local::now()),
+                voice_stats: VoiceStats::default(),
+                is_admin: false,
+                status: UserStatus::Active,
+                show_new_badge: true,
             },
         }
     }
     pub fn new_from_data(user_data: UserItemData) -> Self {
         UserItem { user_data }
     }
-    pub fn get_ui(&self, data: &ApplicationState) -> impl Widget<ApplicationState> {
+    /// Parts of the entry in the order they are shown,
+    /// the compact mode only shows the talking indicator and the name.
+    pub fn get_shown_parts(&self, is_compact: bool) -> Vec<UserItemPart> {
+        if is_compact {
+            return vec![UserItemPart::TalkingIndicator, UserItemPart::Name];
+        }
+
+        let mut parts = vec![
+            UserItemPart::TalkingIndicator,
+            UserItemPart::StatusIcon,
+            UserItemPart::Name,
+        ];
+        if self.user_data.show_new_badge {
+            parts.push(UserItemPart::NewBadge);
+        }
+        parts.push(UserItemPart::Ping);
+
+        parts
+    }
+    pub fn get_ui(
+        &self,
+        data: &ApplicationState,
+        room_name: &str,
+    ) -> impl Widget<ApplicationState> {
+        let text_size = data.main_layout.connected_list.get_entry_text_size();
+
         let mut row: Flex<ApplicationState> = Flex::row()
             .must_fill_main_axis(true)
-            .with_child(SizedBox::new(Label::new("  ").with_text_size(TEXT_SIZE)))
-            .with_child(SizedBox::new(Label::new("  ").with_text_size(TEXT_SIZE)));
+            .with_child(SizedBox::new(Label::new("  ").with_text_size(text_size)));
 
-        // add user name
-        let mut user_label: Label<ApplicationState> =
-            Label::new(self.user_data.username.clone()).with_text_size(TEXT_SIZE);
+        for part in self.get_shown_parts(data.main_layout.connected_list.is_compact) {
+            match part {
+                UserItemPart::TalkingIndicator => row.add_child(TalkingIndicator::new(
+                    self.user_data.is_talking,
+                    data.theme.button_dark_color.clone(),
+                )),
+                UserItemPart::StatusIcon => row.add_child(
+                    Label::new(USER_STATUS_ICON)
+                        .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE)
+                        .with_text_color(match self.user_data.status {
+                            UserStatus::Active => Color::rgb8(80, 200, 80),
+                            UserStatus::Away => Color::GRAY,
+                        }),
+                ),
+                UserItemPart::Name => {
+                    let user_label: Label<ApplicationState> =
+                        Label::new(self.user_data.username.clone()).with_text_size(text_size);
 
-        if self.user_data.is_talking {
-            user_label.set_text_color(data.theme.button_dark_color.clone());
+                    row.add_child(EnvScope::new(
+                        |env, _data| {
+                            env.set(druid::theme::BUTTON_DARK, Color::rgba8(0, 0, 0, 0));
+                            env.set(druid::theme::BUTTON_LIGHT, Color::rgba8(0, 0, 0, 0));
+                        },
+                        Button::from_label(user_label)
+                            .controller(
+                                CustomDataButtonController::new(
+                                    CustomButtonData::ConnectedListData {
+                                        is_room: false,
+                                        button_name: self.user_data.username.clone(),
+                                    },
+                                )
+                                .with_aria_label(
+                                    AccessibilityController::user_label(
+                                        &self.user_data.username,
+                                        self.user_data.ping_ms,
+                                        self.user_data.volume,
+                                    ),
+                                ),
+                            )
+                            .controller(UserDragController::new(
+                                self.user_data.username.clone(),
+                                String::from(room_name),
+                            )),
+                    ));
+                }
+                UserItemPart::NewBadge => {
+                    row.add_child(
+                        Label::new(|data: &ApplicationState, _env: &Env| {
+                            data.localization
+                                .get(LOCALE_MAIN_LAYOUT_NEW_USER_BADGE_TEXT)
+                                .unwrap()
+                                .clone()
+                        })
+                        .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE * 0.6)
+                        .with_text_color(Color::WHITE)
+                        .padding((3.0, 1.0))
+                        .background(NEW_USER_BADGE_COLOR)
+                        .rounded(3.0)
+                        .controller(NewBadgeController::new(
+                            self.user_data.username.clone(),
+                            *self.user_data.connected_time_point,
+                        )),
+                    );
+                    row.add_spacer(5.0);
+                }
+                UserItemPart::Ping => {
+                    let user_ping = self.user_data.ping_ms;
+                    row.add_child(
+                        Label::new(move |data: &ApplicationState, _env: &Env| {
+                            format!(
+                                "{} {}",
+                                user_ping,
+                                data.localization
+                                    .get(LOCALE_MAIN_LAYOUT_USER_INFO_PING_TIME_TEXT)
+                                    .unwrap()
+                            )
+                        })
+                        .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE)
+                        .with_text_color(Color::GRAY)
+                        .with_text_alignment(TextAlignment::End),
+                    );
+                }
+            }
         }
 
-        row.add_child(EnvScope::new(
-            |env, _data| {
-                env.set(druid::theme::BUTTON_DARK, Color::rgba8(0, 0, 0, 0));
-                env.set(druid::theme::BUTTON_LIGHT, Color::rgba8(0, 0, 0, 0));
-            },
-            Button::from_label(user_label).controller(CustomDataButtonController::new(
-                CustomButtonData::ConnectedListData {
-                    is_room: false,
-                    button_name: self.user_data.username.clone(),
-                },
-            )),
-        ));
-
-        // add user ping
-        let user_ping = self.user_data.ping_ms;
-        row.add_child(
-            Label::new(move |data: &ApplicationState, _env: &Env| {
-                format!(
-                    "{} {}",
-                    user_ping,
-                    data.localization
-                        .get(LOCALE_MAIN_LAYOUT_USER_INFO_PING_TIME_TEXT)
-                        .unwrap()
-                )
-            })
-            .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE)
-            .with_text_color(Color::GRAY)
-            .with_text_alignment(TextAlignment::End),
-        );
-
         row
     }
 }
@@ -398,6 +811,10 @@ pub struct UserItemData {
     pub volume: f64,
     pub is_talking: bool,
     pub connected_time_point: Rc<DateTime<Local>>, // using Rc because DateTime does not implement Clone
+    pub voice_stats: VoiceStats,
+    pub is_admin: bool, // the server does not report admin rights yet, so always 'false'
+    pub status: UserStatus, // only known for the local user
+    pub show_new_badge: bool, // 'true' for recently joined users
 }
 
 impl Clone for UserItemData {
@@ -408,6 +825,10 @@ impl Clone for UserItemData {
             volume: self.volume,
             is_talking: self.is_talking,
             connected_time_point: Rc::new((*self.connected_time_point).clone()),
+            voice_stats: self.voice_stats.clone(),
+            is_admin: self.is_admin,
+            status: self.status,
+            show_new_badge: self.show_new_badge,
         }
     }
 }
@@ -420,6 +841,352 @@ impl UserItemData {
             is_talking: false,
             volume: 100.0,
             connected_time_point: Rc::new(Local::now()),
+            voice_stats: VoiceStats::default(),
+            is_admin: false,
+            status: UserStatus::Active,
+            show_new_badge: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Data, PartialEq, Debug)]
+pub enum UserStatus {
+    Active,
+    Away,
+}
+
+// Voice statistics of a user in the current server session.
+#[derive(Clone, Data, Default)]
+pub struct VoiceStats {
+    pub sessions: u32,
+    pub total_talking_ms: u64,
+    pub bytes_received: u64,
+}
+
+impl VoiceStats {
+    pub fn add_session(&mut self, talking_ms: u64, bytes_received: u64) {
+        self.sessions = self.sessions.saturating_add(1);
+        self.total_talking_ms = self.total_talking_ms.saturating_add(talking_ms);
+        self.bytes_received = self.bytes_received.saturating_add(bytes_received);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_with_rooms(room_names: &[&str]) -> ConnectedList {
+        let mut list = ConnectedList::new();
+        for room_name in room_names {
+            list.add_room(String::from(*room_name));
+        }
+
+        list
+    }
+
+    fn get_room_description(list: &ConnectedList, room_name: &str) -> String {
+        list.rooms
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|room| room.room_data.name == room_name)
+            .unwrap()
+            .room_data
+            .description
+            .clone()
+    }
+
+    #[test]
+    fn room_description_is_set_for_room() {
+        let mut list = list_with_rooms(&["Lobby", "Room 1"]);
+
+        list.set_room_description("Room 1", String::from("описание"))
+            .unwrap();
+
+        assert_eq!(get_room_description(&list, "Room 1"), "описание");
+        // no description - no tooltip
+        assert!(get_room_description(&list, "Lobby").is_empty());
+    }
+
+    #[test]
+    fn room_description_for_unknown_room_is_error() {
+        let mut list = list_with_rooms(&["Lobby"]);
+
+        assert!(list
+            .set_room_description("Room 1", String::from("description"))
+            .is_err());
+        assert!(get_room_description(&list, "Lobby").is_empty());
+    }
+
+    fn get_user_data(list: &ConnectedList, username: &str) -> UserItemData {
+        for room in list.rooms.lock().unwrap().iter() {
+            if let Some(user) = room
+                .users
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|user| user.user_data.username == username)
+            {
+                return user.user_data.clone();
+            }
+        }
+
+        panic!("user '{}' not found", username);
+    }
+
+    #[test]
+    fn voice_stats_are_accumulated() {
+        let mut stats = VoiceStats::default();
+
+        stats.add_session(1500, 4000);
+        stats.add_session(500, 1000);
+        stats.add_session(0, 0); // pressed and released right away
+
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(stats.total_talking_ms, 2000);
+        assert_eq!(stats.bytes_received, 5000);
+    }
+
+    #[test]
+    fn voice_stats_saturate() {
+        let mut stats = VoiceStats {
+            sessions: u32::MAX,
+            total_talking_ms: u64::MAX - 1,
+            bytes_received: u64::MAX,
+        };
+
+        stats.add_session(10, 10);
+
+        assert_eq!(stats.sessions, u32::MAX);
+        assert_eq!(stats.total_talking_ms, u64::MAX);
+        assert_eq!(stats.bytes_received, u64::MAX);
+    }
+
+    #[test]
+    fn voice_sessions_are_added_to_the_user() {
+        let mut list = list_with_rooms(&["Lobby", "Room 1"]);
+        list.add_user(String::from("user1"), String::from("Lobby"), 0)
+            .unwrap();
+        list.add_user(String::from("user2"), String::from("Room 1"), 0)
+            .unwrap();
+
+        list.add_user_voice_session("user2", 1000, 2000).unwrap();
+        list.add_user_voice_session("user2", 3000, 6000).unwrap();
+
+        let stats = get_user_data(&list, "user2").voice_stats;
+        assert_eq!(stats.sessions, 2);
+        assert_eq!(stats.total_talking_ms, 4000);
+        assert_eq!(stats.bytes_received, 8000);
+
+        assert_eq!(get_user_data(&list, "user1").voice_stats.sessions, 0);
+        assert!(list.add_user_voice_session("user3", 1000, 2000).is_err());
+    }
+
+    fn list_with_three_rooms_and_five_users() -> ConnectedList {
+        let mut list = list_with_rooms(&["Lobby", "Room 1", "Room 2"]);
+        for (username, room_name) in [
+            ("user1", "Lobby"),
+            ("user2", "Lobby"),
+            ("пользователь", "Room 1"),
+            ("user4", "Room 2"),
+            ("user5", "Room 2"),
+        ]
+        .iter()
+        {
+            list.add_user(String::from(*username), String::from(*room_name), 0)
+                .unwrap();
+        }
+
+        list
+    }
+
+    #[test]
+    fn all_usernames_are_collected_by_room() {
+        let list = list_with_three_rooms_and_five_users();
+
+        assert_eq!(
+            list.get_all_usernames(),
+            "user1\nuser2\nпользователь\nuser4\nuser5"
+        );
+    }
+
+    #[test]
+    fn all_usernames_follow_moved_and_removed_users() {
+        let mut list = list_with_three_rooms_and_five_users();
+
+        // moved by the current user (no sounds are played)
+        list.move_user("user1", "Room 2", "user1", "Room 2")
+            .unwrap();
+        let mut removed_user_room = String::new();
+        list.remove_user("user4", &mut removed_user_room).unwrap();
+        assert_eq!(removed_user_room, "Room 2");
+
+        assert_eq!(
+            list.get_all_usernames(),
+            "user2\nпользователь\nuser5\nuser1"
+        );
+    }
+
+    #[test]
+    fn all_usernames_of_empty_list_is_empty() {
+        assert!(ConnectedList::new().get_all_usernames().is_empty());
+        assert!(list_with_rooms(&["Lobby", "Room 1"])
+            .get_all_usernames()
+            .is_empty());
+    }
+
+    #[test]
+    fn added_user_has_new_badge_until_cleared() {
+        let mut list = list_with_rooms(&["Lobby", "Room 1"]);
+        list.add_user(String::from("user1"), String::from("Room 1"), 0)
+            .unwrap();
+
+        assert_eq!(list.is_new_badge_shown("user1"), Some(true));
+
+        assert!(list.clear_new_badge("user1"));
+        assert_eq!(list.is_new_badge_shown("user1"), Some(false));
+
+        // clearing again changes nothing
+        assert!(list.clear_new_badge("user1"));
+        assert_eq!(list.is_new_badge_shown("user1"), Some(false));
+    }
+
+    #[test]
+    fn new_badge_of_unknown_user() {
+        let mut list = list_with_rooms(&["Lobby"]);
+
+        assert!(!list.clear_new_badge("user1"));
+        assert_eq!(list.is_new_badge_shown("user1"), None);
+    }
+
+    #[test]
+    fn compact_entry_only_has_talking_indicator_and_name() {
+        let user = UserItem::new(String::from("user1"), 42);
+
+        assert_eq!(
+            user.get_shown_parts(true),
+            vec![UserItemPart::TalkingIndicator, UserItemPart::Name]
+        );
+        assert_eq!(
+            user.get_shown_parts(false),
+            vec![
+                UserItemPart::TalkingIndicator,
+                UserItemPart::StatusIcon,
+                UserItemPart::Name,
+                UserItemPart::NewBadge,
+                UserItemPart::Ping,
+            ]
+        );
+    }
+
+    #[test]
+    fn new_badge_is_only_shown_for_new_users() {
+        let mut user = UserItem::new(String::from("user1"), 42);
+        user.user_data.show_new_badge = false;
+
+        assert_eq!(
+            user.get_shown_parts(false),
+            vec![
+                UserItemPart::TalkingIndicator,
+                UserItemPart::StatusIcon,
+                UserItemPart::Name,
+                UserItemPart::Ping,
+            ]
+        );
+        assert_eq!(
+            user.get_shown_parts(true),
+            vec![UserItemPart::TalkingIndicator, UserItemPart::Name]
+        );
+    }
+
+    #[test]
+    fn compact_entries_use_smaller_text() {
+        let mut list = ConnectedList::new();
+        assert_eq!(list.get_entry_text_size(), TEXT_SIZE);
+
+        list.is_compact = true;
+        assert_eq!(list.get_entry_text_size(), MESSAGE_AUTHOR_TEXT_SIZE);
+        assert!(MESSAGE_AUTHOR_TEXT_SIZE < TEXT_SIZE);
+    }
+
+    #[test]
+    fn entries_are_built_in_both_modes() {
+        let mut data = crate::tests::test_application_state();
+        let mut localization = std::collections::HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_ROOM_USER_COUNT_TEXT),
+            String::from("Users"),
+        );
+        data.localization = std::sync::Arc::new(localization);
+        let mut room = RoomItem::new(String::from("Lobby"));
+        room.add_user(String::from("user1"), 42);
+        let user = UserItem::new(String::from("user1"), 42);
+
+        for is_compact in [false, true].iter() {
+            data.main_layout.connected_list.is_compact = *is_compact;
+
+            // widgets are only built (there is no window to render them in tests)
+            let _room_ui = room.get_ui(&data);
+            let _user_ui = user.get_ui(&data, "Lobby");
+        }
+    }
+
+    fn room_with_talking_users(talking: &[bool]) -> RoomItem {
+        let mut room = RoomItem::new(String::from("Gaming"));
+        for (i, is_talking) in talking.iter().enumerate() {
+            let mut user_data = UserItemData::empty();
+            user_data.username = format!("user{}", i);
+            user_data.is_talking = *is_talking;
+            room.add_user_from_user_data(user_data);
+        }
+
+        room
+    }
+
+    fn talking_localization() -> HashMap<String, String> {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_ROOM_TALKING_TEXT),
+            String::from("talking"),
+        );
+        localization
+    }
+
+    #[test]
+    fn room_without_talking_users_has_no_active_voice() {
+        for room in [
+            RoomItem::new(String::from("Gaming")),
+            room_with_talking_users(&[false, false, false]),
+        ]
+        .iter()
+        {
+            assert!(!room.has_active_voice());
+            assert_eq!(room.get_talking_user_count(), 0);
+            assert_eq!(room.get_label_text(&talking_localization()), "Gaming");
         }
     }
+
+    #[test]
+    fn room_with_one_talking_user_has_active_voice() {
+        let room = room_with_talking_users(&[false, true, false]);
+
+        assert!(room.has_active_voice());
+        assert_eq!(room.get_talking_user_count(), 1);
+        assert_eq!(
+            room.get_label_text(&talking_localization()),
+            "Gaming (1 talking)"
+        );
+    }
+
+    #[test]
+    fn room_with_multiple_talking_users_has_active_voice() {
+        let room = room_with_talking_users(&[true, false, true, true]);
+
+        assert!(room.has_active_voice());
+        assert_eq!(room.get_talking_user_count(), 3);
+        assert_eq!(
+            room.get_label_text(&talking_localization()),
+            "Gaming (3 talking)"
+        );
+    }
 }
@@ -1,3 +1,13 @@
+pub mod animated_progress_bar;
 pub mod chat_list;
+pub mod confirm_dialog;
 pub mod connected_list;
+pub mod ptt_status;
+pub mod reaction_bar;
+pub mod room_info_popup;
+pub mod search_panel;
+pub mod selectable_chat_label;
+pub mod slide_transition;
+pub mod talking_indicator;
+pub mod tooltip;
 pub mod user_info;
@@ -0,0 +1,265 @@
+// External.
+use druid::widget::prelude::*;
+use druid::widget::{Either, Label, SizedBox};
+use druid::{Color, Point, TimerToken, WidgetExt, WidgetPod};
+
+// Std.
+use std::time::{Duration, Instant};
+
+// Custom.
+use crate::global_params::*;
+use crate::misc::locale_keys::*;
+use crate::ApplicationState;
+
+const PTT_STATUS_BOTTOM_OFFSET: f64 = 15.0;
+
+// Time since the push-to-talk recording started.
+#[derive(Default)]
+pub struct PttRecordingTimer {
+    recording_started: Option<Instant>,
+}
+
+impl PttRecordingTimer {
+    pub fn start(&mut self, now: Instant) {
+        self.recording_started = Some(now);
+    }
+    pub fn stop(&mut self) {
+        self.recording_started = None;
+    }
+    pub fn is_running(&self) -> bool {
+        self.recording_started.is_some()
+    }
+    /// Returns 'None' if the recording is not active.
+    pub fn get_elapsed_sec(&self, now: Instant) -> Option<u64> {
+        self.recording_started
+            .map(|recording_started| now.saturating_duration_since(recording_started).as_secs())
+    }
+}
+
+// Shows the push-to-talk key and the recording time on top of the wrapped widget (bottom-center)
+// while the push-to-talk recording is active.
+pub struct PttStatusWidget {
+    child: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    status: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    timer: TimerToken,
+    recording_timer: PttRecordingTimer,
+}
+
+impl PttStatusWidget {
+    pub fn new(child: impl Widget<ApplicationState> + 'static) -> Self {
+        PttStatusWidget {
+            child: WidgetPod::new(Box::new(child)),
+            status: WidgetPod::new(Box::new(PttStatusWidget::build_status_ui())),
+            timer: TimerToken::INVALID,
+            recording_timer: PttRecordingTimer::default(),
+        }
+    }
+    pub fn get_status_text(data: &ApplicationState) -> String {
+        format!(
+            "{} — {} {} {}",
+            data.settings_layout.push_to_talk_key_text,
+            data.localization
+                .get(LOCALE_MAIN_LAYOUT_PTT_RECORDING_TEXT)
+                .unwrap(),
+            data.main_layout.ptt_recording_sec,
+            data.localization
+                .get(LOCALE_MAIN_LAYOUT_PTT_SECONDS_TEXT)
+                .unwrap()
+        )
+    }
+    fn build_status_ui() -> impl Widget<ApplicationState> {
+        Either::new(
+            |data: &ApplicationState, _env| data.main_layout.is_ptt_recording,
+            Label::new(|data: &ApplicationState, _env: &Env| {
+                PttStatusWidget::get_status_text(data)
+            })
+            .with_text_size(MESSAGE_TEXT_SIZE)
+            .padding((8.0, 4.0))
+            .background(Color::rgba8(0, 0, 0, 180))
+            .rounded(druid::theme::BUTTON_BORDER_RADIUS),
+            SizedBox::empty(),
+        )
+    }
+}
+
+impl Widget<ApplicationState> for PttStatusWidget {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut ApplicationState, env: &Env) {
+        if let Event::Timer(token) = event {
+            if *token == self.timer {
+                if let Some(elapsed_sec) = self.recording_timer.get_elapsed_sec(Instant::now()) {
+                    data.main_layout.ptt_recording_sec = elapsed_sec;
+                    self.timer = ctx.request_timer(Duration::from_secs(1));
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        self.child.lifecycle(ctx, event, data, env);
+        self.status.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if old_data.main_layout.is_ptt_recording != data.main_layout.is_ptt_recording {
+            if data.main_layout.is_ptt_recording {
+                self.recording_timer.start(Instant::now());
+                self.timer = ctx.request_timer(Duration::from_secs(1));
+            } else {
+                self.timer = TimerToken::INVALID;
+                self.recording_timer.stop();
+            }
+        }
+
+        self.child.update(ctx, data, env);
+        self.status.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &ApplicationState,
+        env: &Env,
+    ) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+
+        let status_size = self.status.layout(ctx, &bc.loosen(), data, env);
+        self.status.set_origin(
+            ctx,
+            data,
+            env,
+            Point::new(
+                (size.width - status_size.width) / 2.0,
+                size.height - status_size.height - PTT_STATUS_BOTTOM_OFFSET,
+            ),
+        );
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &ApplicationState, env: &Env) {
+        self.child.paint(ctx, data, env);
+        self.status.paint(ctx, data, env);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_application_state;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn timer_is_stopped_by_default() {
+        let timer = PttRecordingTimer::default();
+
+        assert!(!timer.is_running());
+        assert_eq!(timer.get_elapsed_sec(Instant::now()), None);
+    }
+
+    #[test]
+    fn elapsed_time_is_counted_in_whole_seconds() {
+        let started = Instant::now();
+        let mut timer = PttRecordingTimer::default();
+        timer.start(started);
+
+        assert!(timer.is_running());
+        assert_eq!(timer.get_elapsed_sec(started), Some(0));
+        assert_eq!(
+            timer.get_elapsed_sec(started + Duration::from_millis(999)),
+            Some(0)
+        );
+        assert_eq!(
+            timer.get_elapsed_sec(started + Duration::from_secs(1)),
+            Some(1)
+        );
+        assert_eq!(
+            timer.get_elapsed_sec(started + Duration::from_millis(61_900)),
+            Some(61)
+        );
+    }
+
+    #[test]
+    fn stopped_timer_has_no_elapsed_time() {
+        let started = Instant::now();
+        let mut timer = PttRecordingTimer::default();
+        timer.start(started);
+
+        timer.stop();
+
+        assert!(!timer.is_running());
+        assert_eq!(
+            timer.get_elapsed_sec(started + Duration::from_secs(5)),
+            None
+        );
+    }
+
+    #[test]
+    fn restarted_timer_counts_from_new_start() {
+        let started = Instant::now();
+        let mut timer = PttRecordingTimer::default();
+        timer.start(started);
+        timer.stop();
+
+        timer.start(started + Duration::from_secs(10));
+
+        assert_eq!(
+            timer.get_elapsed_sec(started + Duration::from_secs(12)),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn status_text_shows_key_and_elapsed_time() {
+        let mut localization = HashMap::new();
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_PTT_RECORDING_TEXT),
+            String::from("recording"),
+        );
+        localization.insert(
+            String::from(LOCALE_MAIN_LAYOUT_PTT_SECONDS_TEXT),
+            String::from("sec."),
+        );
+        let mut data = test_application_state();
+        data.localization = Arc::new(localization);
+        data.settings_layout.push_to_talk_key_text = String::from("T");
+
+        data.main_layout.set_ptt_recording(true);
+        assert_eq!(
+            PttStatusWidget::get_status_text(&data),
+            "T — recording 0 sec."
+        );
+
+        data.main_layout.ptt_recording_sec = 42;
+        assert_eq!(
+            PttStatusWidget::get_status_text(&data),
+            "T — recording 42 sec."
+        );
+
+        // the next recording starts from zero
+        data.main_layout.set_ptt_recording(false);
+        data.main_layout.set_ptt_recording(true);
+        assert_eq!(
+            PttStatusWidget::get_status_text(&data),
+            "T — recording 0 sec."
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// External.
+use druid::widget::prelude::*;
+use druid::widget::{Button, Flex, Label};
+use druid::{Point, Selector, TimerToken, WidgetExt, WidgetPod};
+
+// Std.
+use std::time::Duration;
+
+// Custom.
+use crate::global_params::*;
+use crate::ApplicationState;
+
+pub const REACTION_EMOJIS: [&str; 5] = ["👍", "👎", "❤️", "😂", "😮"];
+pub const REACTION_BAR_HOVER_DELAY_MS: u64 = 500;
+
+// (server message ID, emoji), handled by the delegate.
+pub const CHAT_LIST_REACTION_CLICKED: Selector<(u64, String)> =
+    Selector::new("chat_list_reaction_clicked");
+
+// Server message ID, handled by the delegate.
+pub const CHAT_LIST_PIN_CLICKED: Selector<u64> = Selector::new("chat_list_pin_clicked");
+
+// Shows a bar with reaction emoji on top of the wrapped message (top-right corner)
+// after the message was hovered for REACTION_BAR_HOVER_DELAY_MS.
+// Admins also see a pin button in this bar.
+pub struct ReactionBar {
+    child: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    bar: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    timer: TimerToken,
+    is_bar_shown: bool,
+}
+
+impl ReactionBar {
+    pub fn new(
+        child: impl Widget<ApplicationState> + 'static,
+        server_message_id: u64,
+        can_pin: bool,
+    ) -> Self {
+        ReactionBar {
+            child: WidgetPod::new(Box::new(child)),
+            bar: WidgetPod::new(Box::new(ReactionBar::build_bar_ui(
+                server_message_id,
+                can_pin,
+            ))),
+            timer: TimerToken::INVALID,
+            is_bar_shown: false,
+        }
+    }
+    /// Button that toggles the reaction of the current user, also used for reaction counts.
+    pub fn build_reaction_button(
+        server_message_id: u64,
+        emoji: &'static str,
+        text: String,
+    ) -> impl Widget<ApplicationState> {
+        Button::from_label(Label::new(text).with_text_size(MESSAGE_TEXT_SIZE)).on_click(
+            move |ctx, _data: &mut ApplicationState, _env| {
+                ctx.submit_command(
+                    CHAT_LIST_REACTION_CLICKED.with((server_message_id, emoji.to_string())),
+                );
+            },
+        )
+    }
+    fn build_bar_ui(server_message_id: u64, can_pin: bool) -> impl Widget<ApplicationState> {
+        let mut row: Flex<ApplicationState> = Flex::row();
+
+        for emoji in REACTION_EMOJIS.iter() {
+            row.add_child(ReactionBar::build_reaction_button(
+                server_message_id,
+                emoji,
+                emoji.to_string(),
+            ));
+        }
+
+        if can_pin {
+            row.add_child(
+                Button::from_label(Label::new(PIN_ICON).with_text_size(MESSAGE_TEXT_SIZE))
+                    .on_click(move |ctx, _data: &mut ApplicationState, _env| {
+                        ctx.submit_command(CHAT_LIST_PIN_CLICKED.with(server_message_id));
+                    }),
+            );
+        }
+
+        row.background(druid::theme::BACKGROUND_LIGHT)
+            .rounded(druid::theme::BUTTON_BORDER_RADIUS)
+    }
+}
+
+impl Widget<ApplicationState> for ReactionBar {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut ApplicationState, env: &Env) {
+        match event {
+            Event::MouseMove(_) => {
+                if !self.is_bar_shown {
+                    // restart the delay
+                    self.timer =
+                        ctx.request_timer(Duration::from_millis(REACTION_BAR_HOVER_DELAY_MS));
+                }
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                if ctx.is_hot() {
+                    self.is_bar_shown = true;
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        if self.is_bar_shown {
+            // the bar is on top
+            self.bar.event(ctx, event, data, env);
+        }
+        if !ctx.is_handled() {
+            self.child.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if let LifeCycle::HotChanged(false) = event {
+            self.timer = TimerToken::INVALID;
+            if self.is_bar_shown {
+                self.is_bar_shown = false;
+                ctx.request_paint();
+            }
+        }
+
+        self.child.lifecycle(ctx, event, data, env);
+        self.bar.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        self.child.update(ctx, data, env);
+        self.bar.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &ApplicationState,
+        env: &Env,
+    ) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+
+        let bar_size = self.bar.layout(ctx, &bc.loosen(), data, env);
+        self.bar.set_origin(
+            ctx,
+            data,
+            env,
+            Point::new((size.width - bar_size.width).max(0.0), 0.0),
+        );
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &ApplicationState, env: &Env) {
+        self.child.paint(ctx, data, env);
+
+        if self.is_bar_shown {
+            self.bar.paint(ctx, data, env);
+        }
+    }
+}
@@ -0,0 +1,386 @@
+// External.
+use druid::widget::prelude::*;
+use druid::widget::{CrossAxisAlignment, Flex, Label, Scroll, SizedBox, ViewSwitcher};
+use druid::{Color, Data, Point, Rect, Selector, WidgetExt, WidgetPod};
+
+// Std.
+use std::rc::Rc;
+
+// Custom.
+use super::connected_list::RoomItem;
+use crate::global_params::*;
+use crate::misc::locale_keys::*;
+use crate::ApplicationState;
+
+const ROOM_INFO_POPUP_WIDTH: f64 = 300.0;
+const ROOM_INFO_POPUP_USERS_HEIGHT: f64 = 200.0;
+
+// Submitted from the room button's context menu, handled by the delegate.
+pub const CONNECTED_LIST_VIEW_ROOM_INFO: Selector<String> =
+    Selector::new("connected_list_view_room_info");
+
+// Room info at the moment the popup was opened (it's not updated while shown).
+#[derive(Clone, Data)]
+pub struct RoomInfoSnapshot {
+    pub room_name: String,
+    pub is_current_room: bool,
+    pub users: Rc<Vec<RoomUserSnapshot>>,
+    pub total_talking_ms: u64, // sum of all users in this session
+}
+
+#[derive(Clone, Data)]
+pub struct RoomUserSnapshot {
+    pub username: String,
+    pub ping_ms: u16,
+    pub is_talking: bool,
+}
+
+impl RoomInfoSnapshot {
+    pub fn capture(room: &RoomItem, current_user_room: &str) -> Self {
+        let users_guard = room.users.lock().unwrap();
+
+        RoomInfoSnapshot {
+            room_name: room.room_data.name.clone(),
+            is_current_room: room.room_data.name == current_user_room,
+            users: Rc::new(
+                users_guard
+                    .iter()
+                    .map(|user| RoomUserSnapshot {
+                        username: user.user_data.username.clone(),
+                        ping_ms: user.user_data.ping_ms,
+                        is_talking: user.user_data.is_talking,
+                    })
+                    .collect(),
+            ),
+            total_talking_ms: users_guard
+                .iter()
+                .map(|user| user.user_data.voice_stats.total_talking_ms)
+                .sum(),
+        }
+    }
+}
+
+// Shows the room info (opened from the room's context menu) on top of the wrapped widget (center),
+// any click outside of the popup closes it.
+pub struct RoomInfoPopup {
+    child: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    popup: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+}
+
+impl RoomInfoPopup {
+    pub fn new(child: impl Widget<ApplicationState> + 'static) -> Self {
+        RoomInfoPopup {
+            child: WidgetPod::new(Box::new(child)),
+            popup: WidgetPod::new(Box::new(ViewSwitcher::new(
+                |data: &ApplicationState, _env| data.main_layout.connected_list.room_info.clone(),
+                |room_info, data, _env| match room_info {
+                    Some(room_info) => RoomInfoPopup::build_popup_ui(room_info, data),
+                    None => SizedBox::empty().boxed(),
+                },
+            ))),
+        }
+    }
+    /// Clicks outside of the popup close it.
+    fn is_outside_popup(popup_rect: Rect, click_pos: Point) -> bool {
+        !popup_rect.contains(click_pos)
+    }
+    fn build_popup_ui(
+        room_info: &RoomInfoSnapshot,
+        data: &ApplicationState,
+    ) -> Box<dyn Widget<ApplicationState>> {
+        let mut column: Flex<ApplicationState> =
+            Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+
+        column.add_child(Label::new(room_info.room_name.clone()).with_text_size(TEXT_SIZE));
+        if room_info.is_current_room {
+            column.add_child(
+                Label::new(
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_ROOM_INFO_CURRENT_ROOM_TEXT)
+                        .unwrap()
+                        .clone(),
+                )
+                .with_text_size(MESSAGE_TEXT_SIZE)
+                .with_text_color(Color::GRAY),
+            );
+        }
+
+        let total_sec = room_info.total_talking_ms / 1000;
+        column.add_child(
+            Label::new(format!(
+                "{}: {}:{:02}.",
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_ROOM_INFO_VOICE_ACTIVITY_TEXT)
+                    .unwrap(),
+                total_sec / 60,
+                total_sec % 60
+            ))
+            .with_text_size(MESSAGE_TEXT_SIZE),
+        );
+
+        column.add_default_spacer();
+        column.add_child(
+            Label::new(format!(
+                "{}: {}",
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_ROOM_USER_COUNT_TEXT)
+                    .unwrap(),
+                room_info.users.len()
+            ))
+            .with_text_size(MESSAGE_TEXT_SIZE)
+            .with_text_color(Color::GRAY),
+        );
+
+        let mut users_column: Flex<ApplicationState> =
+            Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+        for user in room_info.users.iter() {
+            let mut user_text = format!(
+                "{} ({} {})",
+                user.username,
+                user.ping_ms,
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_USER_INFO_PING_TIME_TEXT)
+                    .unwrap()
+            );
+            if user.is_talking {
+                user_text = format!(
+                    "{} - {}",
+                    user_text,
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_ROOM_TALKING_TEXT)
+                        .unwrap()
+                );
+            }
+            users_column.add_child(Label::new(user_text).with_text_size(MESSAGE_TEXT_SIZE));
+        }
+        column.add_child(
+            Scroll::new(users_column)
+                .vertical()
+                .fix_height(ROOM_INFO_POPUP_USERS_HEIGHT)
+                .expand_width(),
+        );
+
+        column
+            .fix_width(ROOM_INFO_POPUP_WIDTH)
+            .padding(8.0)
+            .background(druid::theme::BACKGROUND_LIGHT)
+            .border(druid::theme::BORDER_DARK, 1.0)
+            .rounded(druid::theme::BUTTON_BORDER_RADIUS)
+            .boxed()
+    }
+}
+
+impl Widget<ApplicationState> for RoomInfoPopup {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut ApplicationState, env: &Env) {
+        if data.main_layout.connected_list.room_info.is_some() {
+            match event {
+                Event::MouseDown(mouse)
+                    if RoomInfoPopup::is_outside_popup(self.popup.layout_rect(), mouse.pos) =>
+                {
+                    data.main_layout.connected_list.close_room_info();
+                    ctx.set_handled();
+                    return;
+                }
+                Event::KeyDown(key) if key.key == druid::keyboard_types::Key::Escape => {
+                    data.main_layout.connected_list.close_room_info();
+                    ctx.set_handled();
+                    return;
+                }
+                _ => {}
+            }
+
+            // the popup is on top
+            self.popup.event(ctx, event, data, env);
+        }
+        if !ctx.is_handled() {
+            self.child.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        self.child.lifecycle(ctx, event, data, env);
+        self.popup.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        self.child.update(ctx, data, env);
+        self.popup.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &ApplicationState,
+        env: &Env,
+    ) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+
+        let popup_size = self.popup.layout(ctx, &bc.loosen(), data, env);
+        self.popup.set_origin(
+            ctx,
+            data,
+            env,
+            Point::new(
+                ((size.width - popup_size.width) / 2.0).max(0.0),
+                ((size.height - popup_size.height) / 2.0).max(0.0),
+            ),
+        );
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &ApplicationState, env: &Env) {
+        self.child.paint(ctx, data, env);
+
+        if data.main_layout.connected_list.room_info.is_some() {
+            self.popup.paint(ctx, data, env);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::connected_list::ConnectedList;
+
+    fn list_with_users() -> ConnectedList {
+        let mut list = ConnectedList::new();
+        list.add_room(String::from("Lobby"));
+        list.add_room(String::from("Gaming"));
+        for (username, room, ping_ms) in [
+            ("me", "Lobby", 10),
+            ("user1", "Gaming", 25),
+            ("user2", "Gaming", 140),
+        ]
+        .iter()
+        {
+            list.add_user(String::from(*username), String::from(*room), *ping_ms)
+                .unwrap();
+        }
+        list.add_user_voice_session("user1", 61_500, 0).unwrap();
+        list.add_user_voice_session("user2", 2_000, 0).unwrap();
+
+        list
+    }
+
+    fn set_user_talking(list: &ConnectedList, username: &str) {
+        for room in list.rooms.lock().unwrap().iter() {
+            for user in room.users.lock().unwrap().iter_mut() {
+                if user.user_data.username == username {
+                    user.user_data.is_talking = true;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn snapshot_captures_room_users() {
+        let mut list = list_with_users();
+        set_user_talking(&list, "user2");
+
+        list.open_room_info("Gaming", "Lobby");
+
+        let room_info = list.room_info.as_ref().unwrap();
+        assert_eq!(room_info.room_name, "Gaming");
+        assert!(!room_info.is_current_room);
+        assert_eq!(room_info.total_talking_ms, 63_500);
+
+        let users: Vec<(&str, u16, bool)> = room_info
+            .users
+            .iter()
+            .map(|user| (user.username.as_str(), user.ping_ms, user.is_talking))
+            .collect();
+        assert_eq!(users, vec![("user1", 25, false), ("user2", 140, true)]);
+    }
+
+    #[test]
+    fn snapshot_of_current_room() {
+        let mut list = list_with_users();
+
+        list.open_room_info("Lobby", "Lobby");
+
+        let room_info = list.room_info.as_ref().unwrap();
+        assert!(room_info.is_current_room);
+        assert_eq!(room_info.users.len(), 1);
+        assert_eq!(room_info.total_talking_ms, 0);
+    }
+
+    #[test]
+    fn snapshot_is_not_updated() {
+        let mut list = list_with_users();
+        list.open_room_info("Gaming", "Lobby");
+
+        set_user_talking(&list, "user1");
+        list.set_user_ping("user1", 500).unwrap();
+        list.add_user(String::from("user3"), String::from("Gaming"), 0)
+            .unwrap();
+
+        let room_info = list.room_info.as_ref().unwrap();
+        assert_eq!(room_info.users.len(), 2);
+        assert_eq!(room_info.users[0].ping_ms, 25);
+        assert!(!room_info.users[0].is_talking);
+    }
+
+    #[test]
+    fn unknown_room_opens_nothing() {
+        let mut list = list_with_users();
+
+        list.open_room_info("Music", "Lobby");
+
+        assert!(list.room_info.is_none());
+    }
+
+    #[test]
+    fn click_outside_of_popup_dismisses_it() {
+        let popup_rect = Rect::new(100.0, 100.0, 400.0, 300.0);
+
+        assert!(!RoomInfoPopup::is_outside_popup(
+            popup_rect,
+            Point::new(250.0, 200.0)
+        ));
+        assert!(!RoomInfoPopup::is_outside_popup(
+            popup_rect,
+            Point::new(100.0, 100.0)
+        ));
+
+        for pos in [
+            Point::new(99.0, 200.0),
+            Point::new(250.0, 99.0),
+            Point::new(400.0, 200.0),
+            Point::new(250.0, 300.0),
+            Point::ORIGIN,
+        ]
+        .iter()
+        {
+            assert!(RoomInfoPopup::is_outside_popup(popup_rect, *pos));
+        }
+    }
+
+    #[test]
+    fn dismissed_popup_is_closed() {
+        let mut list = list_with_users();
+        list.open_room_info("Gaming", "Lobby");
+
+        list.close_room_info();
+        assert!(list.room_info.is_none());
+
+        // disconnect
+        list.open_room_info("Gaming", "Lobby");
+        list.clear_all_users();
+        assert!(list.room_info.is_none());
+    }
+}
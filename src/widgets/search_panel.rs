@@ -0,0 +1,391 @@
+// External.
+use druid::widget::prelude::*;
+use druid::widget::{
+    Button, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, Scroll, SizedBox,
+    TextBox, ViewSwitcher,
+};
+use druid::{Color, LensExt, Point, Selector, Target, WidgetExt, WidgetId, WidgetPod};
+
+// Std.
+use std::thread;
+
+// Custom.
+use crate::global_params::*;
+use crate::layouts::main_layout::MainLayout;
+use crate::misc::locale_keys::*;
+use crate::services::event_sink::EventSink;
+use crate::widgets::chat_list::ChatList;
+use crate::ApplicationState;
+
+const SEARCH_PANEL_TOP_OFFSET: f64 = 40.0;
+const SEARCH_PANEL_WIDTH: f64 = 400.0;
+const SEARCH_PANEL_RESULTS_HEIGHT: f64 = 300.0;
+const SEARCH_MAX_SHOWN_RESULTS: usize = 20; // per section
+
+// Submitted to open the search panel (Ctrl+F).
+pub const SEARCH_PANEL_OPEN: Selector<()> = Selector::new("search_panel_open");
+// Chat search is done in a separate thread, results are handled by the delegate.
+pub const SEARCH_RESULTS_READY: Selector<SearchResults> = Selector::new("search_results_ready");
+
+#[derive(Clone, Default)]
+pub struct SearchMessageResult {
+    pub message_id: u64,
+    pub author: String,
+    pub message: String,
+}
+
+#[derive(Clone, Default)]
+pub struct SearchResults {
+    pub query: String,
+    pub messages: Vec<SearchMessageResult>, // best matches first
+    pub users: Vec<String>,
+}
+
+// Shows the search box and the results (messages and users) on top of the wrapped widget (top-center).
+pub struct SearchPanel {
+    child: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    panel: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
+    text_box_id: WidgetId,
+}
+
+impl SearchPanel {
+    pub fn new(child: impl Widget<ApplicationState> + 'static) -> Self {
+        let text_box_id = WidgetId::next();
+        SearchPanel {
+            child: WidgetPod::new(Box::new(child)),
+            panel: WidgetPod::new(Box::new(SearchPanel::build_panel_ui(text_box_id))),
+            text_box_id,
+        }
+    }
+    /// Starts the search, results will be submitted as SEARCH_RESULTS_READY.
+    pub fn start_search(data: &ApplicationState, event_sink: EventSink) {
+        let query = data.main_layout.search_query.trim().to_string();
+
+        // users are searched right away, there are not so many of them
+        let users = data.main_layout.connected_list.find_users_by_prefix(&query);
+        let messages = data.main_layout.chat_list.get_searchable_messages();
+
+        thread::spawn(move || {
+            let messages = ChatList::search(messages, &query);
+            event_sink
+                .submit_command(
+                    SEARCH_RESULTS_READY,
+                    SearchResults {
+                        query,
+                        messages,
+                        users,
+                    },
+                    Target::Auto,
+                )
+                .expect("failed to submit SEARCH_RESULTS_READY command");
+        });
+    }
+    fn build_panel_ui(text_box_id: WidgetId) -> impl Widget<ApplicationState> {
+        Either::new(
+            |data: &ApplicationState, _env| data.main_layout.is_search_shown,
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Label::new(|data: &ApplicationState, _env: &Env| {
+                        format!(
+                            "{}:",
+                            data.localization
+                                .get(LOCALE_MAIN_LAYOUT_SEARCH_TITLE_TEXT)
+                                .unwrap()
+                        )
+                    })
+                    .with_text_size(TEXT_SIZE),
+                )
+                .with_child(
+                    TextBox::new()
+                        .with_text_size(TEXT_SIZE)
+                        .fix_width(SEARCH_PANEL_WIDTH)
+                        .with_id(text_box_id)
+                        .lens(ApplicationState::main_layout.then(MainLayout::search_query))
+                        .controller(SearchQueryController {}),
+                )
+                .with_default_spacer()
+                .with_child(
+                    ViewSwitcher::new(
+                        |data: &ApplicationState, _env| data.main_layout.search_results.clone(),
+                        |_selector, data, _env| SearchPanel::build_results_ui(data),
+                    )
+                    .fix_width(SEARCH_PANEL_WIDTH),
+                )
+                .padding(8.0)
+                .background(druid::theme::BACKGROUND_LIGHT)
+                .border(druid::theme::BORDER_DARK, 1.0)
+                .rounded(druid::theme::BUTTON_BORDER_RADIUS),
+            SizedBox::empty(),
+        )
+    }
+    fn build_results_ui(data: &ApplicationState) -> Box<dyn Widget<ApplicationState>> {
+        let results = &data.main_layout.search_results;
+        if results.query.is_empty() {
+            return SizedBox::empty().boxed();
+        }
+
+        let mut column: Flex<ApplicationState> =
+            Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+
+        column.add_child(
+            Label::new(format!(
+                "{} ({})",
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_SEARCH_MESSAGES_SECTION_TEXT)
+                    .unwrap(),
+                results.messages.len()
+            ))
+            .with_text_size(TEXT_SIZE)
+            .with_text_color(Color::GRAY),
+        );
+        for message in results.messages.iter().take(SEARCH_MAX_SHOWN_RESULTS) {
+            let message_id = message.message_id;
+            column.add_child(
+                Button::from_label(
+                    Label::new(format!("{}: {}", message.author, message.message))
+                        .with_text_size(MESSAGE_TEXT_SIZE)
+                        .with_line_break_mode(LineBreaking::WordWrap),
+                )
+                .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                    data.main_layout.close_search();
+                    data.main_layout.chat_list.highlight_message(message_id);
+                })
+                .expand_width(),
+            );
+        }
+
+        column.add_default_spacer();
+        column.add_child(
+            Label::new(format!(
+                "{} ({})",
+                data.localization
+                    .get(LOCALE_MAIN_LAYOUT_SEARCH_USERS_SECTION_TEXT)
+                    .unwrap(),
+                results.users.len()
+            ))
+            .with_text_size(TEXT_SIZE)
+            .with_text_color(Color::GRAY),
+        );
+        for username in results.users.iter().take(SEARCH_MAX_SHOWN_RESULTS) {
+            let username = username.clone();
+            column.add_child(
+                Button::from_label(Label::new(username.clone()).with_text_size(MESSAGE_TEXT_SIZE))
+                    .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                        data.main_layout.close_search();
+                        data.main_layout
+                            .connected_list
+                            .open_selected_user_info(username.clone());
+                    }),
+            );
+        }
+
+        Scroll::new(column)
+            .vertical()
+            .fix_height(SEARCH_PANEL_RESULTS_HEIGHT)
+            .boxed()
+    }
+}
+
+impl Widget<ApplicationState> for SearchPanel {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut ApplicationState, env: &Env) {
+        match event {
+            Event::Command(command) if command.is(SEARCH_PANEL_OPEN) => {
+                data.main_layout.is_search_shown = true;
+                ctx.set_focus(self.text_box_id);
+                ctx.set_handled();
+                return;
+            }
+            Event::KeyDown(key)
+                if data.main_layout.is_search_shown
+                    && key.key == druid::keyboard_types::Key::Escape =>
+            {
+                data.main_layout.close_search();
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+
+        if data.main_layout.is_search_shown {
+            // the panel is on top
+            self.panel.event(ctx, event, data, env);
+        }
+        if !ctx.is_handled() {
+            self.child.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        self.child.lifecycle(ctx, event, data, env);
+        self.panel.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        self.child.update(ctx, data, env);
+        self.panel.update(ctx, data, env);
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &ApplicationState,
+        env: &Env,
+    ) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+
+        let panel_size = self.panel.layout(ctx, &bc.loosen(), data, env);
+        self.panel.set_origin(
+            ctx,
+            data,
+            env,
+            Point::new(
+                ((size.width - panel_size.width) / 2.0).max(0.0),
+                SEARCH_PANEL_TOP_OFFSET,
+            ),
+        );
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &ApplicationState, env: &Env) {
+        self.child.paint(ctx, data, env);
+
+        if data.main_layout.is_search_shown {
+            self.panel.paint(ctx, data, env);
+        }
+    }
+}
+
+// Starts the search when the query is changed.
+struct SearchQueryController {}
+
+impl<W: Widget<ApplicationState>> Controller<ApplicationState, W> for SearchQueryController {
+    fn update(
+        &mut self,
+        child: &mut W,
+        ctx: &mut UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        env: &Env,
+    ) {
+        if old_data.main_layout.search_query != data.main_layout.search_query {
+            SearchPanel::start_search(data, ctx.get_external_handle().into());
+        }
+
+        child.update(ctx, old_data, data, env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::event_sink::SubmittedCommand;
+    use crate::tests::test_application_state;
+
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn state_with_messages_and_users() -> ApplicationState {
+        let mut data = test_application_state();
+
+        let main_layout = &mut data.main_layout;
+        main_layout.add_room(String::from("Lobby"));
+        for username in ["Anna", "anton", "bob"].iter() {
+            main_layout
+                .connected_list
+                .add_user(String::from(*username), String::from("Lobby"), 0)
+                .unwrap();
+        }
+
+        let usernames: Vec<String> = Vec::new();
+        let chat_list = &mut main_layout.chat_list;
+        chat_list.add_message("happy day", "Anna", 1, &usernames);
+        chat_list.add_message("I will ask Anna", "bob", 2, &usernames);
+        chat_list.add_message("anniversary today!", "anton", 3, &usernames);
+
+        data
+    }
+
+    fn search(data: &mut ApplicationState, query: &str) -> SearchResults {
+        data.main_layout.search_query = String::from(query);
+
+        let (sender, receiver) = mpsc::channel::<SubmittedCommand>();
+        SearchPanel::start_search(data, EventSink::Channel(sender));
+
+        let command = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        command.get(SEARCH_RESULTS_READY).unwrap().clone()
+    }
+
+    #[test]
+    fn messages_and_users_are_found_separately() {
+        let mut data = state_with_messages_and_users();
+
+        let results = search(&mut data, "  AN ");
+
+        assert_eq!(results.query, "AN");
+        // newest first, the author is not searched
+        let messages: Vec<&str> = results
+            .messages
+            .iter()
+            .map(|message| message.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["anniversary today!", "I will ask Anna"]);
+        assert_eq!(results.messages[0].author, "anton");
+        assert_eq!(results.users, vec!["Anna", "anton"]);
+    }
+
+    #[test]
+    fn word_matches_are_ranked_first() {
+        let mut data = state_with_messages_and_users();
+
+        // "today" only contains the query, newer messages go first otherwise
+        let results = search(&mut data, "day");
+
+        let messages: Vec<&str> = results
+            .messages
+            .iter()
+            .map(|message| message.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["happy day", "anniversary today!"]);
+        assert!(results.users.is_empty());
+    }
+
+    #[test]
+    fn only_users_or_only_messages() {
+        let mut data = state_with_messages_and_users();
+
+        let results = search(&mut data, "bo");
+        assert!(results.messages.is_empty());
+        assert_eq!(results.users, vec!["bob"]);
+
+        let results = search(&mut data, "today");
+        assert_eq!(results.messages.len(), 1);
+        assert!(results.users.is_empty());
+    }
+
+    #[test]
+    fn empty_query_finds_nothing() {
+        let mut data = state_with_messages_and_users();
+
+        let results = search(&mut data, "   ");
+
+        assert!(results.query.is_empty());
+        assert!(results.messages.is_empty());
+        assert!(results.users.is_empty());
+    }
+}
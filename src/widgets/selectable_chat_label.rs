@@ -0,0 +1,216 @@
+// External.
+use druid::text::{Attribute, RichText};
+use druid::widget::prelude::*;
+use druid::{Application, Command, Cursor, FontWeight, HotKey, Point, SysMods, TextLayout};
+
+// Std.
+use std::ops::Range;
+
+// Custom.
+use crate::global_params::*;
+
+// Label which text can be selected with the mouse and copied with Ctrl+C.
+pub struct SelectableChatLabel {
+    text: String,
+    layout: TextLayout<RichText>,
+    selection_start: usize, // byte offset in 'text'
+    selection_end: usize,   // byte offset in 'text'
+    is_selecting: bool,
+    click_command: Option<Command>, // submitted on click without selection
+}
+
+impl SelectableChatLabel {
+    pub fn new(text: String) -> Self {
+        let mut layout = TextLayout::from_text(RichText::new(text.as_str().into()));
+        layout.set_text_size(MESSAGE_TEXT_SIZE);
+
+        SelectableChatLabel {
+            text,
+            layout,
+            selection_start: 0,
+            selection_end: 0,
+            is_selecting: false,
+            click_command: None,
+        }
+    }
+    pub fn with_click_command(mut self, command: Command) -> Self {
+        self.click_command = Some(command);
+        self
+    }
+    /// Shows the text in the specified byte ranges (like @mentions) bold
+    /// and with the theme's accent color.
+    pub fn with_highlighted_ranges(mut self, ranges: &[(usize, usize)]) -> Self {
+        let mut text = RichText::new(self.text.as_str().into());
+        for (start, end) in ranges.iter() {
+            text.add_attribute(
+                *start..*end,
+                Attribute::text_color(druid::theme::PRIMARY_LIGHT),
+            );
+            text.add_attribute(*start..*end, Attribute::weight(FontWeight::BOLD));
+        }
+        self.layout.set_text(text);
+        self
+    }
+    pub fn get_selected_range(&self) -> Range<usize> {
+        let start = SelectableChatLabel::clamp_to_char_boundary(&self.text, self.selection_start);
+        let end = SelectableChatLabel::clamp_to_char_boundary(&self.text, self.selection_end);
+
+        start.min(end)..start.max(end)
+    }
+    pub fn get_selected_text(&self) -> &str {
+        &self.text[self.get_selected_range()]
+    }
+    /// Moves the offset back to the closest char boundary (for multibyte UTF-8 characters).
+    pub fn clamp_to_char_boundary(text: &str, offset: usize) -> usize {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+    fn clear_selection(&mut self) {
+        self.selection_start = 0;
+        self.selection_end = 0;
+    }
+}
+
+impl<T: Data> Widget<T> for SelectableChatLabel {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                let offset = self.layout.text_position_for_point(mouse.pos);
+                self.selection_start = offset;
+                self.selection_end = offset;
+                self.is_selecting = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.request_paint();
+            }
+            Event::MouseMove(mouse) => {
+                ctx.set_cursor(&Cursor::IBeam);
+                if self.is_selecting && ctx.is_active() {
+                    self.selection_end = self.layout.text_position_for_point(mouse.pos);
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button.is_left() && self.is_selecting => {
+                self.selection_end = self.layout.text_position_for_point(mouse.pos);
+                self.is_selecting = false;
+                ctx.set_active(false);
+                ctx.request_paint();
+
+                if self.get_selected_range().is_empty() {
+                    if let Some(command) = &self.click_command {
+                        ctx.submit_command(command.clone());
+                    }
+                }
+            }
+            Event::KeyDown(key) if HotKey::new(SysMods::Cmd, "c").matches(key) => {
+                if !self.get_selected_range().is_empty() {
+                    Application::global()
+                        .clipboard()
+                        .put_string(self.get_selected_text());
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        if let LifeCycle::FocusChanged(false) = event {
+            self.clear_selection();
+            ctx.request_paint();
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        self.layout.set_wrap_width(bc.max().width);
+        self.layout.rebuild_if_needed(ctx.text(), env);
+
+        bc.constrain(self.layout.size())
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        let selected_range = self.get_selected_range();
+        if !selected_range.is_empty() {
+            let selection_color = env.get(druid::theme::SELECTED_TEXT_BACKGROUND_COLOR);
+            for rect in self.layout.rects_for_range(selected_range) {
+                ctx.fill(rect, &selection_color);
+            }
+        }
+
+        self.layout.draw(ctx, Point::ORIGIN);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 'a' - 1 byte, 'é' - 2 bytes, '中' - 3 bytes, '😀' - 4 bytes.
+    const TEXT: &str = "aé中😀";
+
+    fn label_with_selection(selection_start: usize, selection_end: usize) -> SelectableChatLabel {
+        let mut label = SelectableChatLabel::new(String::from(TEXT));
+        label.selection_start = selection_start;
+        label.selection_end = selection_end;
+        label
+    }
+
+    #[test]
+    fn offsets_are_moved_back_to_char_boundary() {
+        let expected = [0, 1, 1, 3, 3, 3, 6, 6, 6, 6, 10];
+        for (offset, expected) in expected.iter().enumerate() {
+            assert_eq!(
+                SelectableChatLabel::clamp_to_char_boundary(TEXT, offset),
+                *expected,
+                "offset {}",
+                offset
+            );
+        }
+    }
+
+    #[test]
+    fn offsets_after_end_are_clamped() {
+        assert_eq!(SelectableChatLabel::clamp_to_char_boundary(TEXT, 11), 10);
+        assert_eq!(
+            SelectableChatLabel::clamp_to_char_boundary(TEXT, usize::MAX),
+            10
+        );
+        assert_eq!(SelectableChatLabel::clamp_to_char_boundary("", 5), 0);
+    }
+
+    #[test]
+    fn selection_inside_multibyte_chars() {
+        // starts inside 'é' and ends inside '😀'
+        let label = label_with_selection(2, 8);
+        assert_eq!(label.get_selected_range(), 1..6);
+        assert_eq!(label.get_selected_text(), "é中");
+    }
+
+    #[test]
+    fn backward_selection() {
+        let label = label_with_selection(10, 1);
+        assert_eq!(label.get_selected_range(), 1..10);
+        assert_eq!(label.get_selected_text(), "é中😀");
+    }
+
+    #[test]
+    fn selection_inside_one_char_is_empty() {
+        let label = label_with_selection(7, 9);
+        assert!(label.get_selected_range().is_empty());
+        assert_eq!(label.get_selected_text(), "");
+    }
+
+    #[test]
+    fn cleared_selection_is_empty() {
+        let mut label = label_with_selection(0, 10);
+        assert_eq!(label.get_selected_text(), TEXT);
+
+        label.clear_selection();
+        assert!(label.get_selected_range().is_empty());
+    }
+}
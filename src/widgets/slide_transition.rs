@@ -0,0 +1,202 @@
+// External.
+use druid::widget::prelude::*;
+use druid::{Lens, Point, WidgetPod};
+
+pub const SLIDE_TRANSITION_DURATION_MS: f64 = 200.0;
+
+// Moves 'progress' towards 1.0 (if the panel is shown) or 0.0 over SLIDE_TRANSITION_DURATION_MS.
+pub fn next_slide_progress(progress: f64, interval_ns: u64, is_panel_shown: bool) -> f64 {
+    let step = interval_ns as f64 / 1_000_000.0 / SLIDE_TRANSITION_DURATION_MS;
+
+    if is_panel_shown {
+        (progress + step).min(1.0)
+    } else {
+        (progress - step).max(0.0)
+    }
+}
+
+// Panel is moved to the left from the right edge as the progress grows.
+pub fn get_panel_offset_x(width: f64, progress: f64) -> f64 {
+    width * (1.0 - progress)
+}
+
+// Shows 'panel' on top of 'background' by sliding it in from the right edge.
+// Progress (0.0 - hidden, 1.0 - fully shown) is stored in the data
+// so that the animation survives widget rebuilds.
+pub struct SlideTransition<T, L> {
+    background: WidgetPod<T, Box<dyn Widget<T>>>,
+    panel: WidgetPod<T, Box<dyn Widget<T>>>,
+    is_panel_shown: fn(&T) -> bool,
+    progress: L,
+}
+
+impl<T: Data, L: Lens<T, f64>> SlideTransition<T, L> {
+    pub fn new(
+        background: impl Widget<T> + 'static,
+        panel: impl Widget<T> + 'static,
+        is_panel_shown: fn(&T) -> bool,
+        progress: L,
+    ) -> Self {
+        SlideTransition {
+            background: WidgetPod::new(Box::new(background)),
+            panel: WidgetPod::new(Box::new(panel)),
+            is_panel_shown,
+            progress,
+        }
+    }
+    fn get_target_progress(&self, data: &T) -> f64 {
+        if (self.is_panel_shown)(data) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+    fn get_progress(&self, data: &T) -> f64 {
+        self.progress.with(data, |progress| *progress)
+    }
+}
+
+impl<T: Data, L: Lens<T, f64>> Widget<T> for SlideTransition<T, L> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::AnimFrame(interval) = event {
+            let is_panel_shown = (self.is_panel_shown)(data);
+            let progress = next_slide_progress(self.get_progress(data), *interval, is_panel_shown);
+            self.progress.with_mut(data, |value| *value = progress);
+
+            if progress != self.get_target_progress(data) {
+                ctx.request_anim_frame();
+            }
+            ctx.request_layout();
+        }
+
+        let progress = self.get_progress(data);
+        match event {
+            // only the widget on top receives mouse input
+            Event::MouseDown(_) | Event::MouseUp(_) | Event::MouseMove(_) | Event::Wheel(_) => {
+                if progress > 0.0 {
+                    self.panel.event(ctx, event, data, env);
+                } else {
+                    self.background.event(ctx, event, data, env);
+                }
+            }
+            _ => {
+                self.background.event(ctx, event, data, env);
+                self.panel.event(ctx, event, data, env);
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.get_progress(data) != self.get_target_progress(data) {
+                ctx.request_anim_frame();
+            }
+        }
+
+        self.background.lifecycle(ctx, event, data, env);
+        self.panel.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if (self.is_panel_shown)(old_data) != (self.is_panel_shown)(data) {
+            ctx.request_anim_frame();
+        }
+
+        self.background.update(ctx, data, env);
+        self.panel.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let background_size = self.background.layout(ctx, bc, data, env);
+        self.background.set_origin(ctx, data, env, Point::ORIGIN);
+
+        let panel_size = self.panel.layout(ctx, bc, data, env);
+        let size = bc.constrain(Size::new(
+            background_size.width.max(panel_size.width),
+            background_size.height.max(panel_size.height),
+        ));
+
+        let offset_x = get_panel_offset_x(size.width, self.get_progress(data));
+        self.panel
+            .set_origin(ctx, data, env, Point::new(offset_x, 0.0));
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let progress = self.get_progress(data);
+        let clip_rect = ctx.size().to_rect();
+
+        ctx.with_save(|ctx| {
+            ctx.clip(clip_rect);
+
+            if progress < 1.0 {
+                self.background.paint(ctx, data, env);
+            }
+            if progress > 0.0 {
+                self.panel.paint(ctx, data, env);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_NS: u64 = 16_000_000;
+
+    #[test]
+    fn progress_grows_with_frame_interval() {
+        let progress = next_slide_progress(0.0, FRAME_NS, true);
+
+        assert!((progress - 16.0 / SLIDE_TRANSITION_DURATION_MS).abs() < 1e-9);
+    }
+
+    #[test]
+    fn panel_is_shown_after_duration() {
+        let frame_count = (SLIDE_TRANSITION_DURATION_MS * 1_000_000.0 / FRAME_NS as f64) as usize;
+
+        let mut progress = 0.0;
+        for _ in 0..frame_count {
+            progress = next_slide_progress(progress, FRAME_NS, true);
+            assert!(progress < 1.0);
+        }
+
+        // clamped
+        progress = next_slide_progress(progress, FRAME_NS, true);
+        assert_eq!(progress, 1.0);
+        progress = next_slide_progress(progress, FRAME_NS, true);
+        assert_eq!(progress, 1.0);
+    }
+
+    #[test]
+    fn progress_is_clamped() {
+        let long_frame_ns = (SLIDE_TRANSITION_DURATION_MS * 10.0 * 1_000_000.0) as u64;
+
+        assert_eq!(next_slide_progress(0.5, long_frame_ns, true), 1.0);
+        assert_eq!(next_slide_progress(0.5, long_frame_ns, false), 0.0);
+        assert_eq!(next_slide_progress(0.0, FRAME_NS, false), 0.0);
+        assert_eq!(next_slide_progress(1.0, FRAME_NS, true), 1.0);
+    }
+
+    #[test]
+    fn closing_continues_from_current_progress() {
+        let progress = next_slide_progress(0.6, FRAME_NS, false);
+
+        assert!((progress - (0.6 - 16.0 / SLIDE_TRANSITION_DURATION_MS)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zero_interval_does_not_change_progress() {
+        assert_eq!(next_slide_progress(0.3, 0, true), 0.3);
+        assert_eq!(next_slide_progress(0.3, 0, false), 0.3);
+    }
+
+    #[test]
+    fn panel_slides_from_right_edge() {
+        assert_eq!(get_panel_offset_x(300.0, 0.0), 300.0);
+        assert_eq!(get_panel_offset_x(300.0, 0.5), 150.0);
+        assert_eq!(get_panel_offset_x(300.0, 1.0), 0.0);
+    }
+}
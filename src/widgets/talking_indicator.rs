@@ -0,0 +1,150 @@
+// External.
+use druid::kurbo::Circle;
+use druid::widget::prelude::*;
+use druid::Color;
+
+pub const TALKING_INDICATOR_SIZE: f64 = 16.0;
+const TALKING_INDICATOR_MAX_RADIUS: f64 = TALKING_INDICATOR_SIZE / 2.0 - 1.0; // keep the stroke inside
+const TALKING_INDICATOR_PULSES_PER_SEC: f64 = 1.0;
+const TALKING_INDICATOR_STROKE_WIDTH: f64 = 2.0;
+
+// Small pulsing ring (expands and fades) shown while the user is talking.
+pub struct TalkingIndicator {
+    is_talking: bool,
+    phase: f64, // in [0.0; 1.0)
+    color: Color,
+}
+
+impl TalkingIndicator {
+    pub fn new(is_talking: bool, color: Color) -> Self {
+        TalkingIndicator {
+            is_talking,
+            phase: 0.0,
+            color,
+        }
+    }
+    // Returns the phase after 'interval_ns' nanoseconds, wraps around to 0.0 after 1.0.
+    pub fn next_phase(phase: f64, interval_ns: u64) -> f64 {
+        let phase = phase + interval_ns as f64 / 1_000_000_000.0 * TALKING_INDICATOR_PULSES_PER_SEC;
+
+        phase.fract()
+    }
+    pub fn get_radius(phase: f64) -> f64 {
+        phase.max(0.0).min(1.0) * TALKING_INDICATOR_MAX_RADIUS
+    }
+}
+
+impl<T: Data> Widget<T> for TalkingIndicator {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
+        if let Event::AnimFrame(interval) = event {
+            if !self.is_talking {
+                self.phase = 0.0;
+                return;
+            }
+
+            self.phase = TalkingIndicator::next_phase(self.phase, *interval);
+            ctx.request_paint();
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.is_talking {
+                ctx.request_anim_frame();
+            }
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        bc.constrain(Size::new(TALKING_INDICATOR_SIZE, TALKING_INDICATOR_SIZE))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, _env: &Env) {
+        if !self.is_talking {
+            return;
+        }
+
+        let center = ctx.size().to_rect().center();
+        let ring = Circle::new(center, TalkingIndicator::get_radius(self.phase));
+
+        // fade out while expanding
+        ctx.stroke(
+            ring,
+            &self.color.clone().with_alpha(1.0 - self.phase),
+            TALKING_INDICATOR_STROKE_WIDTH,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC_NS: u64 = 1_000_000_000;
+
+    #[test]
+    fn phase_is_increased_by_frame_interval() {
+        let frame_ns = SEC_NS / 60;
+
+        let phase = TalkingIndicator::next_phase(0.0, frame_ns);
+
+        let expected = frame_ns as f64 / SEC_NS as f64 * TALKING_INDICATOR_PULSES_PER_SEC;
+        assert!((phase - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn phase_wraps_around() {
+        let half_pulse_ns = (SEC_NS as f64 / TALKING_INDICATOR_PULSES_PER_SEC / 2.0) as u64;
+
+        let phase = TalkingIndicator::next_phase(0.75, half_pulse_ns);
+        assert!((phase - 0.25).abs() < 1e-9);
+
+        // exactly one pulse
+        let phase = TalkingIndicator::next_phase(0.0, half_pulse_ns * 2);
+        assert!(phase.abs() < 1e-9);
+    }
+
+    #[test]
+    fn phase_is_unchanged_without_time() {
+        assert_eq!(TalkingIndicator::next_phase(0.5, 0), 0.5);
+    }
+
+    #[test]
+    fn phase_stays_in_range_after_long_frame() {
+        // the window was not drawn for a while
+        let phase = TalkingIndicator::next_phase(0.9, SEC_NS * 100 + 1);
+
+        assert!((0.0..1.0).contains(&phase));
+    }
+
+    #[test]
+    fn radius_boundaries() {
+        assert_eq!(TalkingIndicator::get_radius(0.0), 0.0);
+        assert_eq!(
+            TalkingIndicator::get_radius(0.5),
+            TALKING_INDICATOR_MAX_RADIUS / 2.0
+        );
+        assert_eq!(
+            TalkingIndicator::get_radius(1.0),
+            TALKING_INDICATOR_MAX_RADIUS
+        );
+
+        // clamped
+        assert_eq!(TalkingIndicator::get_radius(-0.5), 0.0);
+        assert_eq!(
+            TalkingIndicator::get_radius(1.5),
+            TALKING_INDICATOR_MAX_RADIUS
+        );
+    }
+
+    #[test]
+    fn ring_stays_inside_widget() {
+        assert!(
+            TalkingIndicator::get_radius(1.0) + TALKING_INDICATOR_STROKE_WIDTH / 2.0
+                <= TALKING_INDICATOR_SIZE / 2.0
+        );
+    }
+}
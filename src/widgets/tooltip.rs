@@ -0,0 +1,293 @@
+// External.
+use druid::widget::prelude::*;
+use druid::{Point, Rect, TextLayout, TimerToken, Vec2};
+
+// Std.
+use std::time::Duration;
+
+// Custom.
+use crate::global_params::*;
+
+pub const TOOLTIP_DELAY_MS: u64 = 500;
+const TOOLTIP_PADDING: f64 = 4.0;
+const TOOLTIP_CURSOR_OFFSET: f64 = 16.0;
+
+// Shows a text near the cursor after the wrapped widget was hovered for TOOLTIP_DELAY_MS.
+pub struct Tooltip<W> {
+    child: W,
+    text: TextLayout<String>,
+    delay: TooltipDelay,
+    cursor_pos: Point,
+}
+
+// When to show and hide the tooltip, the timer is requested by the widget.
+struct TooltipDelay {
+    timer: TimerToken,
+    is_shown: bool,
+}
+
+impl TooltipDelay {
+    fn new() -> Self {
+        TooltipDelay {
+            timer: TimerToken::INVALID,
+            is_shown: false,
+        }
+    }
+    /// Returns the delay of the timer to request (the previous timer is ignored after
+    /// 'set_timer') or 'None' if the tooltip is already shown.
+    fn on_mouse_move(&self) -> Option<Duration> {
+        if self.is_shown {
+            return None;
+        }
+
+        Some(Duration::from_millis(TOOLTIP_DELAY_MS))
+    }
+    fn set_timer(&mut self, timer: TimerToken) {
+        self.timer = timer;
+    }
+    /// Returns 'true' if the tooltip was shown.
+    fn on_timer(&mut self, timer: TimerToken, is_hot: bool) -> bool {
+        if timer != self.timer {
+            // old timer
+            return false;
+        }
+
+        self.timer = TimerToken::INVALID;
+        if is_hot {
+            self.is_shown = true;
+        }
+
+        self.is_shown
+    }
+    /// Returns 'true' if the tooltip was hidden.
+    fn on_mouse_leave(&mut self) -> bool {
+        self.timer = TimerToken::INVALID;
+
+        let was_shown = self.is_shown;
+        self.is_shown = false;
+
+        was_shown
+    }
+}
+
+impl<W> Tooltip<W> {
+    pub fn new(child: W, text: String) -> Self {
+        let mut text_layout = TextLayout::from_text(text);
+        text_layout.set_text_size(MESSAGE_TEXT_SIZE);
+
+        Tooltip {
+            child,
+            text: text_layout,
+            delay: TooltipDelay::new(),
+            cursor_pos: Point::ORIGIN,
+        }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for Tooltip<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseMove(mouse) => {
+                self.cursor_pos = mouse.pos;
+                if let Some(delay) = self.delay.on_mouse_move() {
+                    // restart the delay
+                    self.delay.set_timer(ctx.request_timer(delay));
+                }
+            }
+            Event::Timer(token) => {
+                if self.delay.on_timer(*token, ctx.is_hot()) {
+                    ctx.request_paint();
+                }
+            }
+            _ => {}
+        }
+
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(false) = event {
+            if self.delay.on_mouse_leave() {
+                ctx.request_paint();
+            }
+        }
+
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, old_data, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.text.rebuild_if_needed(ctx.text(), env);
+
+        self.child.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+
+        if !self.delay.is_shown {
+            return;
+        }
+
+        if let Some(layout) = self.text.layout() {
+            let layout = layout.clone();
+            let text_origin = self.cursor_pos
+                + Vec2::new(TOOLTIP_PADDING, TOOLTIP_CURSOR_OFFSET + TOOLTIP_PADDING);
+            let background_rect = Rect::from_origin_size(
+                self.cursor_pos + Vec2::new(0.0, TOOLTIP_CURSOR_OFFSET),
+                self.text.size() + Size::new(TOOLTIP_PADDING * 2.0, TOOLTIP_PADDING * 2.0),
+            );
+            let background_color = env.get(druid::theme::BACKGROUND_DARK);
+            let border_color = env.get(druid::theme::BORDER_LIGHT);
+
+            // draw above other widgets
+            ctx.paint_with_z_index(1, move |ctx| {
+                ctx.fill(background_rect, &background_color);
+                ctx.stroke(background_rect, &border_color, 1.0);
+                ctx.draw_text(&layout, text_origin);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fires the requested timers when the time is advanced (like druid does).
+    struct MockTimer {
+        now: Duration,
+        timers: Vec<(TimerToken, Duration)>, // token - deadline
+    }
+
+    impl MockTimer {
+        fn new() -> Self {
+            MockTimer {
+                now: Duration::ZERO,
+                timers: Vec::new(),
+            }
+        }
+        fn request_timer(&mut self, delay: Duration) -> TimerToken {
+            let token = TimerToken::next();
+            self.timers.push((token, self.now + delay));
+            token
+        }
+        fn advance(&mut self, time: Duration) -> Vec<TimerToken> {
+            self.now += time;
+
+            let now = self.now;
+            let fired = self
+                .timers
+                .iter()
+                .filter(|(_, deadline)| *deadline <= now)
+                .map(|(token, _)| *token)
+                .collect();
+            self.timers.retain(|(_, deadline)| *deadline > now);
+
+            fired
+        }
+    }
+
+    fn mouse_move(delay: &mut TooltipDelay, timer: &mut MockTimer) {
+        if let Some(time) = delay.on_mouse_move() {
+            delay.set_timer(timer.request_timer(time));
+        }
+    }
+
+    fn fire_timers(delay: &mut TooltipDelay, fired: Vec<TimerToken>, is_hot: bool) -> bool {
+        fired.into_iter().any(|token| delay.on_timer(token, is_hot))
+    }
+
+    #[test]
+    fn tooltip_is_shown_after_delay() {
+        let mut delay = TooltipDelay::new();
+        let mut timer = MockTimer::new();
+
+        mouse_move(&mut delay, &mut timer);
+
+        let fired = timer.advance(Duration::from_millis(TOOLTIP_DELAY_MS - 1));
+        assert!(!fire_timers(&mut delay, fired, true));
+        assert!(!delay.is_shown);
+
+        let fired = timer.advance(Duration::from_millis(1));
+        assert!(fire_timers(&mut delay, fired, true));
+        assert!(delay.is_shown);
+    }
+
+    #[test]
+    fn mouse_move_restarts_delay() {
+        let mut delay = TooltipDelay::new();
+        let mut timer = MockTimer::new();
+
+        mouse_move(&mut delay, &mut timer);
+        timer.advance(Duration::from_millis(300));
+        mouse_move(&mut delay, &mut timer);
+
+        // the first timer is ignored
+        let fired = timer.advance(Duration::from_millis(TOOLTIP_DELAY_MS - 300));
+        assert_eq!(fired.len(), 1);
+        assert!(!fire_timers(&mut delay, fired, true));
+
+        let fired = timer.advance(Duration::from_millis(300));
+        assert!(fire_timers(&mut delay, fired, true));
+    }
+
+    #[test]
+    fn mouse_move_over_shown_tooltip_does_not_request_timer() {
+        let mut delay = TooltipDelay::new();
+        let mut timer = MockTimer::new();
+
+        mouse_move(&mut delay, &mut timer);
+        let fired = timer.advance(Duration::from_millis(TOOLTIP_DELAY_MS));
+        fire_timers(&mut delay, fired, true);
+
+        assert_eq!(delay.on_mouse_move(), None);
+    }
+
+    #[test]
+    fn tooltip_is_not_shown_after_mouse_leave() {
+        let mut delay = TooltipDelay::new();
+        let mut timer = MockTimer::new();
+
+        mouse_move(&mut delay, &mut timer);
+        timer.advance(Duration::from_millis(100));
+        assert!(!delay.on_mouse_leave());
+
+        let fired = timer.advance(Duration::from_millis(TOOLTIP_DELAY_MS));
+        assert!(!fire_timers(&mut delay, fired, false));
+        assert!(!delay.is_shown);
+    }
+
+    #[test]
+    fn tooltip_is_hidden_on_mouse_leave() {
+        let mut delay = TooltipDelay::new();
+        let mut timer = MockTimer::new();
+
+        mouse_move(&mut delay, &mut timer);
+        let fired = timer.advance(Duration::from_millis(TOOLTIP_DELAY_MS));
+        fire_timers(&mut delay, fired, true);
+
+        assert!(delay.on_mouse_leave());
+        assert!(!delay.is_shown);
+
+        // the delay starts again
+        assert_eq!(
+            delay.on_mouse_move(),
+            Some(Duration::from_millis(TOOLTIP_DELAY_MS))
+        );
+    }
+
+    #[test]
+    fn timer_is_ignored_if_not_hovered() {
+        let mut delay = TooltipDelay::new();
+        let mut timer = MockTimer::new();
+
+        mouse_move(&mut delay, &mut timer);
+        let fired = timer.advance(Duration::from_millis(TOOLTIP_DELAY_MS));
+
+        assert!(!fire_timers(&mut delay, fired, false));
+    }
+}
@@ -1,5 +1,7 @@
 use druid::widget::prelude::*;
-use druid::widget::{Button, CrossAxisAlignment, Flex, Label, LineBreaking, Slider};
+use druid::widget::{
+    Button, CrossAxisAlignment, Flex, Label, LineBreaking, SizedBox, Slider, ViewSwitcher,
+};
 use druid::{Color, Data, Lens, LensExt, WidgetExt};
 
 use chrono::prelude::*;
@@ -12,6 +14,8 @@ use crate::widgets::connected_list::*;
 use crate::ApplicationState;
 use crate::CustomSliderID;
 
+const DEFAULT_USER_VOLUME: f64 = 100.0;
+
 #[derive(Clone, Data, Lens)]
 pub struct UserInfo {
     pub user_data: UserItemData,
@@ -107,6 +111,66 @@ impl UserInfo {
                 .with_text_size(TEXT_SIZE),
             )
             .with_default_spacer()
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    format!(
+                        "{}: {}.",
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_USER_INFO_VOICE_SESSIONS_TEXT)
+                            .unwrap(),
+                        data.main_layout
+                            .connected_list
+                            .user_info_layout
+                            .user_data
+                            .voice_stats
+                            .sessions
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    let total_sec = data
+                        .main_layout
+                        .connected_list
+                        .user_info_layout
+                        .user_data
+                        .voice_stats
+                        .total_talking_ms
+                        / 1000;
+                    format!(
+                        "{}: {}:{:02}.",
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_USER_INFO_TALKING_TIME_TEXT)
+                            .unwrap(),
+                        total_sec / 60,
+                        total_sec % 60
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_child(
+                Label::new(|data: &ApplicationState, _env: &Env| {
+                    format!(
+                        "{}: {:.1} {}.",
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_USER_INFO_RECEIVED_TEXT)
+                            .unwrap(),
+                        data.main_layout
+                            .connected_list
+                            .user_info_layout
+                            .user_data
+                            .voice_stats
+                            .bytes_received as f64
+                            / 1024.0,
+                        data.localization
+                            .get(LOCALE_MAIN_LAYOUT_USER_INFO_KILOBYTES_TEXT)
+                            .unwrap()
+                    )
+                })
+                .with_text_size(TEXT_SIZE),
+            )
+            .with_default_spacer()
             .with_child(
                 Label::new(|data: &ApplicationState, _env: &Env| {
                     format!(
@@ -136,14 +200,27 @@ impl UserInfo {
                 .with_text_color(Color::GRAY)
                 .with_text_size(TEXT_SIZE),
             )
+            .with_child(
+                Flex::row()
+                    .with_child(UserInfo::build_volume_button(
+                        LOCALE_MAIN_LAYOUT_USER_INFO_VOLUME_MUTE_BUTTON_TEXT,
+                        0.0,
+                    ))
+                    .with_default_spacer()
+                    .with_child(UserInfo::build_volume_button(
+                        LOCALE_MAIN_LAYOUT_USER_INFO_VOLUME_AUTO_BUTTON_TEXT,
+                        DEFAULT_USER_VOLUME,
+                    )),
+            )
             .with_child(
                 Slider::new()
                     .with_step(1.0)
                     .with_range(0.0, 100.0)
                     .expand_width()
-                    .controller(CustomSliderController::new(
-                        CustomSliderID::UserVolumeSlider,
-                    ))
+                    .controller(
+                        CustomSliderController::new(CustomSliderID::UserVolumeSlider)
+                            .with_aria_label(String::from("User volume")),
+                    )
                     .lens(
                         ApplicationState::main_layout.then(
                             MainLayout::connected_list.then(
@@ -153,6 +230,20 @@ impl UserInfo {
                         ),
                     ),
             )
+            .with_child(ViewSwitcher::new(
+                |data: &ApplicationState, _env| {
+                    (
+                        data.main_layout
+                            .connected_list
+                            .user_info_layout
+                            .user_data
+                            .username
+                            .clone(),
+                        data.main_layout.connected_list.refresh_ui,
+                    )
+                },
+                |_selector, data, _env| UserInfo::build_invite_ui(data),
+            ))
             .with_child(
                 Button::from_label(
                     Label::new(|data: &ApplicationState, _env: &Env| {
@@ -166,7 +257,229 @@ impl UserInfo {
                 .on_click(UserInfo::on_back_clicked),
             )
     }
+    /// "Invite to Room" with a button per room (except the user's room),
+    /// empty for our own info and for admins.
+    fn build_invite_ui(data: &ApplicationState) -> Box<dyn Widget<ApplicationState>> {
+        let connected_list = &data.main_layout.connected_list;
+        let username = &connected_list.user_info_layout.user_data.username;
+        if *username == data.main_layout.current_user_name
+            || connected_list.user_info_layout.user_data.is_admin
+        {
+            return SizedBox::empty().boxed();
+        }
+
+        let user_room = connected_list.get_user_room(username);
+
+        let mut column: Flex<ApplicationState> = Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_default_spacer()
+            .with_child(
+                Label::new(format!(
+                    "{}:",
+                    data.localization
+                        .get(LOCALE_MAIN_LAYOUT_USER_INFO_INVITE_TO_ROOM_TEXT)
+                        .unwrap()
+                ))
+                .with_text_size(TEXT_SIZE),
+            );
+        for (room_name, _) in connected_list.get_room_user_counts() {
+            if Some(&room_name) == user_room.as_ref() {
+                continue;
+            }
+
+            let target_username = username.clone();
+            column.add_child(
+                Button::from_label(
+                    Label::new(room_name.clone()).with_text_size(MESSAGE_AUTHOR_TEXT_SIZE),
+                )
+                .on_click(move |_ctx, data: &mut ApplicationState, _env| {
+                    MainLayout::invite_user_to_room(
+                        data,
+                        target_username.clone(),
+                        room_name.clone(),
+                    );
+                }),
+            );
+        }
+        column.add_default_spacer();
+
+        column.boxed()
+    }
+    /// Small button that sets the user volume to the specified value.
+    fn build_volume_button(locale_key: &'static str, volume: f64) -> impl Widget<ApplicationState> {
+        Button::from_label(
+            Label::new(move |data: &ApplicationState, _env: &Env| {
+                data.localization.get(locale_key).unwrap().clone()
+            })
+            .with_text_size(MESSAGE_AUTHOR_TEXT_SIZE),
+        )
+        .on_click(move |ctx, data: &mut ApplicationState, _env| {
+            UserInfo::on_volume_button_clicked(ctx, data, volume);
+        })
+    }
+    fn on_volume_button_clicked(ctx: &mut EventCtx, data: &mut ApplicationState, volume: f64) {
+        // same as moving the slider (applied to the audio service and the user list)
+        ctx.submit_command(
+            CUSTOM_SLIDER_ON_VALUE_CHANGED.with(UserInfo::set_user_volume(data, volume)),
+        );
+    }
+    /// Sets the volume of the shown user, returns the slider event that applies it.
+    fn set_user_volume(data: &mut ApplicationState, volume: f64) -> OnCustomSliderMovedInfo {
+        data.main_layout
+            .connected_list
+            .user_info_layout
+            .user_data
+            .volume = volume;
+
+        OnCustomSliderMovedInfo {
+            custom_slider_id: CustomSliderID::UserVolumeSlider,
+            value: volume as u16,
+        }
+    }
     fn on_back_clicked(_ctx: &mut EventCtx, data: &mut ApplicationState, _env: &Env) {
         data.main_layout.connected_list.hide_user_info();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::audio_service::audio_service::UserVoiceData;
+    use crate::tests::test_application_state;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn state_with_shown_user() -> ApplicationState {
+        let mut data = test_application_state();
+        data.main_layout.current_user_name = String::from("me");
+        data.main_layout.current_user_room = String::from("Lobby");
+        data.main_layout.add_room(String::from("Lobby"));
+
+        let localization = Arc::new(HashMap::new());
+        for username in ["me", "user1", "user2"].iter() {
+            data.main_layout
+                .add_user(
+                    String::from(*username),
+                    String::from("Lobby"),
+                    0,
+                    true,
+                    &localization,
+                )
+                .unwrap();
+
+            if *username != "me" {
+                data.audio_service
+                    .lock()
+                    .unwrap()
+                    .users_voice_data
+                    .lock()
+                    .unwrap()
+                    .push(Arc::new(Mutex::new(UserVoiceData::new(String::from(
+                        *username,
+                    )))));
+            }
+        }
+
+        data.main_layout
+            .connected_list
+            .open_selected_user_info(String::from("user1"));
+
+        data
+    }
+
+    /// Returns the volume of the user in the user list and in the audio service.
+    fn get_user_volume(data: &ApplicationState, username: &str) -> (f64, i32) {
+        let list_volume = data
+            .main_layout
+            .connected_list
+            .rooms
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|room| room.users.lock().unwrap().clone())
+            .find(|user| user.user_data.username == username)
+            .unwrap()
+            .user_data
+            .volume;
+
+        let audio_service_guard = data.audio_service.lock().unwrap();
+        let users_voice_data = audio_service_guard.users_voice_data.lock().unwrap();
+        let audio_volume = users_voice_data
+            .iter()
+            .find(|user| user.lock().unwrap().username == username)
+            .unwrap()
+            .lock()
+            .unwrap()
+            .user_volume;
+
+        (list_volume, audio_volume)
+    }
+
+    fn click_volume_button(data: &mut ApplicationState, volume: f64) {
+        let info = UserInfo::set_user_volume(data, volume);
+        assert!(info.custom_slider_id == CustomSliderID::UserVolumeSlider);
+        assert_eq!(info.value, volume as u16);
+
+        // what the delegate does on CUSTOM_SLIDER_ON_VALUE_CHANGED
+        MainLayout::user_volume_slider_moved_event(data);
+    }
+
+    #[test]
+    fn mute_sets_volume_to_zero() {
+        let mut data = state_with_shown_user();
+
+        click_volume_button(&mut data, 0.0);
+
+        assert_eq!(
+            data.main_layout
+                .connected_list
+                .user_info_layout
+                .user_data
+                .volume,
+            0.0
+        );
+        assert_eq!(get_user_volume(&data, "user1"), (0.0, 0));
+        assert_eq!(
+            get_user_volume(&data, "user2"),
+            (DEFAULT_USER_VOLUME, DEFAULT_USER_VOLUME as i32)
+        );
+    }
+
+    #[test]
+    fn auto_resets_volume_to_default() {
+        let mut data = state_with_shown_user();
+        click_volume_button(&mut data, 35.0);
+        assert_eq!(get_user_volume(&data, "user1"), (35.0, 35));
+
+        click_volume_button(&mut data, DEFAULT_USER_VOLUME);
+
+        assert_eq!(
+            data.main_layout
+                .connected_list
+                .user_info_layout
+                .user_data
+                .volume,
+            DEFAULT_USER_VOLUME
+        );
+        assert_eq!(
+            get_user_volume(&data, "user1"),
+            (DEFAULT_USER_VOLUME, DEFAULT_USER_VOLUME as i32)
+        );
+    }
+
+    #[test]
+    fn volume_of_other_user_is_not_changed() {
+        let mut data = state_with_shown_user();
+        data.main_layout
+            .connected_list
+            .open_selected_user_info(String::from("user2"));
+
+        click_volume_button(&mut data, 0.0);
+
+        assert_eq!(get_user_volume(&data, "user2"), (0.0, 0));
+        assert_eq!(
+            get_user_volume(&data, "user1"),
+            (DEFAULT_USER_VOLUME, DEFAULT_USER_VOLUME as i32)
+        );
+    }
+}